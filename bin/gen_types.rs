@@ -1,6 +1,6 @@
 use deciduous::{
-    CommandLog, DecisionContext, DecisionEdge, DecisionNode, DecisionSession, RoadmapConflict,
-    RoadmapItem, RoadmapSyncState,
+    CommandLog, DecisionContext, DecisionEdge, DecisionNode, DecisionSession, EdgeType, NodeStatus,
+    NodeType, RoadmapConflict, RoadmapItem, RoadmapSyncState,
 };
 use std::fs;
 use std::path::PathBuf;
@@ -14,6 +14,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Generate TypeScript definitions
     let definitions = vec![
+        ("NodeType", NodeType::decl()),
+        ("NodeStatus", NodeStatus::decl()),
+        ("EdgeType", EdgeType::decl()),
         ("DecisionNode", DecisionNode::decl()),
         ("DecisionEdge", DecisionEdge::decl()),
         ("DecisionContext", DecisionContext::decl()),
@@ -0,0 +1,60 @@
+/*
+ * C API for reading and querying a deciduous decision graph.
+ *
+ * Build with `cargo build --release` in bindings/ffi and link against
+ * libdeciduous_ffi. Query functions return a NUL-terminated JSON string that
+ * the caller frees with deciduous_string_free(), or NULL on failure, when
+ * deciduous_last_error() says why. A handle may be used from one thread at a
+ * time.
+ */
+#ifndef DECIDUOUS_H
+#define DECIDUOUS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define DECIDUOUS_ABI_VERSION 1
+
+typedef struct DeciduousDb DeciduousDb;
+
+/* DECIDUOUS_ABI_VERSION of the loaded library */
+uint32_t deciduous_abi_version(void);
+
+/* Why the last failing call on this thread failed, or NULL. Owned by the
+ * library; valid until the next failing call on the same thread. */
+const char *deciduous_last_error(void);
+
+/* Open the database at path, or the project's (.deciduous/deciduous.db,
+ * honouring DECIDUOUS_DB_PATH) when path is NULL */
+DeciduousDb *deciduous_open(const char *path);
+void deciduous_close(DeciduousDb *db);
+void deciduous_string_free(char *s);
+
+/* {"nodes": [...], "edges": [...]} */
+char *deciduous_graph(const DeciduousDb *db);
+
+/* The part of the graph reachable from the len ids in roots */
+char *deciduous_subgraph(const DeciduousDb *db, const int32_t *roots, size_t len);
+
+/* {"node": {...}, "parents": [...], "children": [...], "edges": [...]} */
+char *deciduous_node(const DeciduousDb *db, int32_t node_id);
+
+/* The limit newest nodes and the edges between them (limit 0: 20) */
+char *deciduous_recent(const DeciduousDb *db, uint32_t limit);
+
+/* Nodes newest first; NULL filters match anything (limit 0: 50) */
+char *deciduous_query(const DeciduousDb *db, const char *node_type, const char *status,
+                      const char *branch, uint32_t limit);
+
+/* Nodes whose title, description or prompt contains text (limit 0: 20) */
+char *deciduous_search(const DeciduousDb *db, const char *text, uint32_t limit);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* DECIDUOUS_H */
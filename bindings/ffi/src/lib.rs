@@ -0,0 +1,281 @@
+//! C API for reading and querying a deciduous graph
+//!
+//! For editor plugins in languages that can call C (a Neovim plugin through
+//! LuaJIT's FFI, an Emacs module, ...) but can't link Rust. The declarations
+//! are in `include/deciduous.h`.
+//!
+//! Every query returns a NUL-terminated JSON string owned by the caller and
+//! released with `deciduous_string_free`; the shapes are those of the MCP
+//! tools of the same name. On failure a function returns NULL and
+//! `deciduous_last_error` says why. Panics are caught at the boundary.
+
+use deciduous::{filter_graph_from_roots, mcp, Database};
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// Bumped when a declaration in deciduous.h changes incompatibly
+pub const ABI_VERSION: u32 = 1;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// Run `f`, turning an error or panic into NULL and the thread's last error
+fn guard<T>(f: impl FnOnce() -> Result<*mut T, String>) -> *mut T {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(message)) => {
+            set_error(message);
+            ptr::null_mut()
+        }
+        Err(_) => {
+            set_error("deciduous panicked".to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// A borrowed C string argument; NULL and "" are None
+///
+/// # Safety
+/// `s` must be NULL or point to a NUL-terminated string.
+unsafe fn opt_str<'a>(s: *const c_char) -> Result<Option<&'a str>, String> {
+    if s.is_null() {
+        return Ok(None);
+    }
+    let s = CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| "argument is not valid UTF-8".to_string())?;
+    Ok(Some(s).filter(|s| !s.is_empty()))
+}
+
+/// # Safety
+/// `db` must be NULL or a handle from `deciduous_open` not yet closed.
+unsafe fn db_ref<'a>(db: *const Database) -> Result<&'a Database, String> {
+    db.as_ref()
+        .ok_or_else(|| "database handle is NULL".to_string())
+}
+
+fn json_string(value: &Value) -> Result<*mut c_char, String> {
+    let text = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    CString::new(text)
+        .map(CString::into_raw)
+        .map_err(|e| e.to_string())
+}
+
+fn limit_args(limit: u32) -> Value {
+    if limit == 0 {
+        json!({})
+    } else {
+        json!({ "limit": limit })
+    }
+}
+
+/// The ABI version of the loaded library, `ABI_VERSION`
+#[no_mangle]
+pub extern "C" fn deciduous_abi_version() -> u32 {
+    ABI_VERSION
+}
+
+/// Why the last failing call on this thread failed, or NULL
+///
+/// The string belongs to the library and lasts until the next failing call on
+/// the same thread.
+#[no_mangle]
+pub extern "C" fn deciduous_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
+
+/// Open the database at `path`, or the project's when `path` is NULL
+///
+/// # Safety
+/// `path` must be NULL or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn deciduous_open(path: *const c_char) -> *mut Database {
+    guard(|| {
+        let db = match opt_str(path)? {
+            Some(path) => Database::open_at(path),
+            None => Database::open(),
+        }
+        .map_err(|e| e.to_string())?;
+        Ok(Box::into_raw(Box::new(db)))
+    })
+}
+
+/// Close a database opened with `deciduous_open`; NULL is ignored
+///
+/// # Safety
+/// `db` must be NULL or a handle from `deciduous_open` not yet closed.
+#[no_mangle]
+pub unsafe extern "C" fn deciduous_close(db: *mut Database) {
+    if !db.is_null() {
+        drop(Box::from_raw(db));
+    }
+}
+
+/// Free a string returned by this library; NULL is ignored
+///
+/// # Safety
+/// `s` must be NULL or a string returned by this library not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn deciduous_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// The whole graph, `{"nodes": [...], "edges": [...]}`
+///
+/// # Safety
+/// `db` must be a handle from `deciduous_open`.
+#[no_mangle]
+pub unsafe extern "C" fn deciduous_graph(db: *const Database) -> *mut c_char {
+    guard(|| {
+        let graph = db_ref(db)?.get_graph().map_err(|e| e.to_string())?;
+        json_string(&json!(graph))
+    })
+}
+
+/// What descends from the `len` node ids at `roots`, in the graph's shape
+///
+/// # Safety
+/// `db` must be a handle from `deciduous_open`; `roots` must point to `len`
+/// ids (it may be NULL when `len` is 0).
+#[no_mangle]
+pub unsafe extern "C" fn deciduous_subgraph(
+    db: *const Database,
+    roots: *const i32,
+    len: usize,
+) -> *mut c_char {
+    guard(|| {
+        let roots = if len == 0 {
+            &[][..]
+        } else if roots.is_null() {
+            return Err("roots is NULL".to_string());
+        } else {
+            std::slice::from_raw_parts(roots, len)
+        };
+        let graph = db_ref(db)?.get_graph().map_err(|e| e.to_string())?;
+        json_string(&json!(filter_graph_from_roots(&graph, roots)))
+    })
+}
+
+/// A node with its parents, children and edges
+///
+/// # Safety
+/// `db` must be a handle from `deciduous_open`.
+#[no_mangle]
+pub unsafe extern "C" fn deciduous_node(db: *const Database, node_id: i32) -> *mut c_char {
+    guard(|| {
+        let value = mcp::get_context(db_ref(db)?, &json!({ "node_id": node_id }))?;
+        json_string(&value)
+    })
+}
+
+/// The `limit` newest nodes (0 for the default of 20) and the edges between them
+///
+/// # Safety
+/// `db` must be a handle from `deciduous_open`.
+#[no_mangle]
+pub unsafe extern "C" fn deciduous_recent(db: *const Database, limit: u32) -> *mut c_char {
+    guard(|| json_string(&mcp::get_context(db_ref(db)?, &limit_args(limit))?))
+}
+
+/// Nodes newest first, filtered by type, status and branch (each NULL for
+/// any), at most `limit` (0 for the default of 50)
+///
+/// # Safety
+/// `db` must be a handle from `deciduous_open`; the strings must be NULL or
+/// NUL-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn deciduous_query(
+    db: *const Database,
+    node_type: *const c_char,
+    status: *const c_char,
+    branch: *const c_char,
+    limit: u32,
+) -> *mut c_char {
+    guard(|| {
+        let mut args = limit_args(limit);
+        for (key, value) in [("type", node_type), ("status", status), ("branch", branch)] {
+            if let Some(value) = opt_str(value)? {
+                args[key] = json!(value);
+            }
+        }
+        json_string(&mcp::query(db_ref(db)?, &args)?)
+    })
+}
+
+/// Nodes whose title, description or prompt contains `text`, newest first,
+/// at most `limit` (0 for the default of 20)
+///
+/// # Safety
+/// `db` must be a handle from `deciduous_open`; `text` must be NUL-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn deciduous_search(
+    db: *const Database,
+    text: *const c_char,
+    limit: u32,
+) -> *mut c_char {
+    guard(|| {
+        let mut args = limit_args(limit);
+        if let Some(text) = opt_str(text)? {
+            args["text"] = json!(text);
+        }
+        json_string(&mcp::search(db_ref(db)?, &args)?)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take(s: *mut c_char) -> Value {
+        assert!(!s.is_null(), "{:?}", CStr::from_ptr(deciduous_last_error()));
+        let value = serde_json::from_str(CStr::from_ptr(s).to_str().unwrap()).unwrap();
+        deciduous_string_free(s);
+        value
+    }
+
+    #[test]
+    fn test_c_api() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = CString::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let (lua, goal_type) = (CString::new("lua").unwrap(), CString::new("goal").unwrap());
+        unsafe {
+            let db = deciduous_open(path.as_ptr());
+            assert!(!db.is_null());
+            let goal = (*db).node("goal", "Embed the graph").insert().unwrap();
+            let option = (*db).node("option", "Lua via C").insert().unwrap();
+            (*db).edge(goal, option).insert().unwrap();
+
+            let found = take(deciduous_search(db, lua.as_ptr(), 0));
+            assert_eq!(found[0]["id"], option);
+            let goals = take(deciduous_query(
+                db,
+                goal_type.as_ptr(),
+                ptr::null(),
+                ptr::null(),
+                0,
+            ));
+            assert_eq!(goals.as_array().unwrap().len(), 1);
+            let context = take(deciduous_node(db, option));
+            assert_eq!(context["parents"][0]["id"], goal);
+            let sub = take(deciduous_subgraph(db, &option, 1));
+            assert_eq!(sub["nodes"].as_array().unwrap().len(), 1);
+
+            assert!(deciduous_node(db, 999).is_null());
+            let error = CStr::from_ptr(deciduous_last_error()).to_str().unwrap();
+            assert!(error.contains("999"));
+            deciduous_close(db);
+        }
+    }
+}
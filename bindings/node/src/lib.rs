@@ -0,0 +1,214 @@
+//! Node.js bindings for deciduous
+//!
+//! Built with `napi build` into a `.node` addon, so the viewer's dev tooling,
+//! OpenCode plugins and JS agent hooks can read and write the graph in-process
+//! instead of spawning the CLI for every node. Nodes, edges, patches and
+//! apply results cross into JS as plain objects in the same JSON shape
+//! `deciduous graph` and `diff export` write.
+
+use deciduous::{
+    filter_graph_from_roots, graph_to_dot, ConflictStrategy, DecisionGraph, DotConfig, GraphPatch,
+};
+use napi::{Error, Result};
+use napi_derive::napi;
+use serde_json::Value;
+
+fn js_err(e: impl std::fmt::Display) -> Error {
+    Error::from_reason(e.to_string())
+}
+
+fn to_js<T: serde::Serialize>(value: &T) -> Result<Value> {
+    serde_json::to_value(value).map_err(js_err)
+}
+
+/// Fields for `Database.addNode`
+#[napi(object)]
+pub struct NewNode {
+    pub node_type: String,
+    pub title: String,
+    pub description: Option<String>,
+    /// 0-100
+    pub confidence: Option<u32>,
+    pub commit: Option<String>,
+    pub prompt: Option<String>,
+    pub files: Option<Vec<String>>,
+    pub branch: Option<String>,
+}
+
+/// A deciduous graph database
+#[napi]
+pub struct Database {
+    inner: deciduous::Database,
+}
+
+impl Database {
+    fn graph_for(&self, roots: Option<Vec<i32>>) -> Result<DecisionGraph> {
+        let graph = self.inner.get_graph().map_err(js_err)?;
+        Ok(match roots {
+            Some(roots) => filter_graph_from_roots(&graph, &roots),
+            None => graph,
+        })
+    }
+}
+
+#[napi]
+impl Database {
+    /// Open the database at `path`, or find the project's database the way
+    /// the CLI does (honouring `DECIDUOUS_DB_PATH`)
+    #[napi(constructor)]
+    pub fn new(path: Option<String>) -> Result<Self> {
+        let inner = match path {
+            Some(path) => deciduous::Database::open_at(path),
+            None => deciduous::Database::open(),
+        }
+        .map_err(js_err)?;
+        Ok(Self { inner })
+    }
+
+    /// All nodes
+    #[napi]
+    pub fn nodes(&self) -> Result<Value> {
+        to_js(&self.inner.get_all_nodes().map_err(js_err)?)
+    }
+
+    /// All edges
+    #[napi]
+    pub fn edges(&self) -> Result<Value> {
+        to_js(&self.inner.get_all_edges().map_err(js_err)?)
+    }
+
+    /// `{ nodes, edges }`, optionally only what descends from `roots`
+    #[napi]
+    pub fn graph(&self, roots: Option<Vec<i32>>) -> Result<Value> {
+        to_js(&self.graph_for(roots)?)
+    }
+
+    /// Add a node and return its id
+    #[napi]
+    pub fn add_node(&self, node: NewNode) -> Result<i32> {
+        let mut builder = self.inner.node(&node.node_type, &node.title);
+        if let Some(description) = node.description {
+            builder = builder.description(description);
+        }
+        if let Some(confidence) = node.confidence {
+            let confidence = u8::try_from(confidence)
+                .ok()
+                .filter(|c| *c <= 100)
+                .ok_or_else(|| js_err(format!("confidence must be 0-100, got {}", confidence)))?;
+            builder = builder.confidence(confidence);
+        }
+        if let Some(commit) = node.commit {
+            builder = builder.commit(commit);
+        }
+        if let Some(prompt) = node.prompt {
+            builder = builder.prompt(prompt);
+        }
+        if let Some(files) = node.files {
+            builder = builder.files(files);
+        }
+        if let Some(branch) = node.branch {
+            builder = builder.branch(branch);
+        }
+        builder.insert().map_err(js_err)
+    }
+
+    /// Connect two nodes and return the edge id; `edgeType` defaults to leads_to
+    #[napi]
+    pub fn link(
+        &self,
+        from_id: i32,
+        to_id: i32,
+        edge_type: Option<String>,
+        rationale: Option<String>,
+    ) -> Result<i32> {
+        let mut edge = self.inner.edge(from_id, to_id);
+        if let Some(edge_type) = edge_type {
+            edge = edge.edge_type(edge_type);
+        }
+        if let Some(rationale) = rationale {
+            edge = edge.rationale(rationale);
+        }
+        edge.insert().map_err(js_err)
+    }
+
+    /// Set a node's status
+    #[napi]
+    pub fn set_status(&self, node_id: i32, status: String) -> Result<()> {
+        self.inner
+            .update_node_status(node_id, &status)
+            .map_err(js_err)
+    }
+
+    /// Export nodes (all, or `nodeIds`) and their edges as a patch object
+    #[napi]
+    pub fn export_patch(
+        &self,
+        node_ids: Option<Vec<i32>>,
+        branch: Option<String>,
+        author: Option<String>,
+    ) -> Result<Value> {
+        let patch = self
+            .inner
+            .export_patch(
+                node_ids,
+                branch.as_deref(),
+                author,
+                deciduous::get_current_git_commit(),
+            )
+            .map_err(js_err)?;
+        to_js(&patch)
+    }
+
+    /// Apply a patch object and return what changed
+    ///
+    /// `strategy` settles conflicting fields: "ours", "theirs" or "newest".
+    /// By default conflicts keep the local value and are recorded for review.
+    #[napi]
+    pub fn apply_patch(
+        &self,
+        patch: Value,
+        dry_run: Option<bool>,
+        strategy: Option<String>,
+    ) -> Result<Value> {
+        let patch: GraphPatch =
+            serde_json::from_value(patch).map_err(|e| js_err(format!("Invalid patch: {}", e)))?;
+        let strategy = match strategy {
+            Some(s) => ConflictStrategy::parse(&s).ok_or_else(|| {
+                js_err(format!(
+                    "unknown strategy '{}' (use ours, theirs or newest)",
+                    s
+                ))
+            })?,
+            None => ConflictStrategy::default(),
+        };
+        let result = self
+            .inner
+            .apply_patch(&patch, dry_run.unwrap_or(false), strategy)
+            .map_err(js_err)?;
+        to_js(&result)
+    }
+
+    /// The graph (or what descends from `roots`) in Graphviz DOT
+    #[napi]
+    pub fn to_dot(&self, roots: Option<Vec<i32>>, title: Option<String>) -> Result<String> {
+        let config = DotConfig {
+            title,
+            ..DotConfig::default()
+        };
+        Ok(graph_to_dot(&self.graph_for(roots)?, &config))
+    }
+}
+
+/// Read a patch file (plain or encrypted) into a patch object
+#[napi]
+pub fn load_patch(path: String) -> Result<Value> {
+    to_js(&GraphPatch::load(std::path::Path::new(&path)).map_err(js_err)?)
+}
+
+/// Write a patch object to `path`
+#[napi]
+pub fn save_patch(patch: Value, path: String) -> Result<()> {
+    let patch: GraphPatch =
+        serde_json::from_value(patch).map_err(|e| js_err(format!("Invalid patch: {}", e)))?;
+    patch.save(std::path::Path::new(&path)).map_err(js_err)
+}
@@ -0,0 +1,51 @@
+"""Read and write deciduous decision graphs from Python.
+
+    import deciduous
+
+    db = deciduous.Database()  # .deciduous/deciduous.db, like the CLI
+    goal = db.add_node("goal", "Faster CI", confidence=80)
+    option = db.add_node("option", "Cache the cargo registry")
+    db.link(goal, option, rationale="Most of the time is downloads")
+
+    nodes, edges = deciduous.to_dataframes(db)
+    graph = deciduous.to_networkx(db)
+"""
+
+from ._deciduous import Database, GraphPatch, __version__
+
+__all__ = ["Database", "GraphPatch", "to_dataframes", "to_networkx", "__version__"]
+
+
+def to_dataframes(db, roots=None):
+    """Nodes and edges as two pandas DataFrames, indexed by id.
+
+    Needs pandas (``pip install deciduous-py[pandas]``).
+    """
+    import pandas as pd
+
+    graph = db.graph(roots)
+    nodes = pd.DataFrame(graph["nodes"])
+    edges = pd.DataFrame(graph["edges"])
+    if not nodes.empty:
+        nodes = nodes.set_index("id")
+    if not edges.empty:
+        edges = edges.set_index("id")
+    return nodes, edges
+
+
+def to_networkx(db, roots=None):
+    """The graph as a networkx MultiDiGraph keyed by node id.
+
+    Node and edge attributes are the fields `deciduous graph` exports.
+    Needs networkx (``pip install deciduous-py[networkx]``).
+    """
+    import networkx as nx
+
+    graph = db.graph(roots)
+    g = nx.MultiDiGraph()
+    for node in graph["nodes"]:
+        g.add_node(node["id"], **{k: v for k, v in node.items() if k != "id"})
+    for edge in graph["edges"]:
+        attrs = {k: v for k, v in edge.items() if k not in ("from_node_id", "to_node_id")}
+        g.add_edge(edge["from_node_id"], edge["to_node_id"], key=edge["id"], **attrs)
+    return g
@@ -0,0 +1,295 @@
+//! Python bindings for deciduous
+//!
+//! Built with maturin into `deciduous._deciduous` and re-exported by the
+//! `deciduous` package. Nodes, edges and patch results cross into Python as
+//! plain dicts and lists (by way of the crate's JSON serialization), so they
+//! drop straight into `pandas.DataFrame` or `networkx`. The helpers for those
+//! live in `python/deciduous/__init__.py`.
+
+use deciduous::{
+    filter_graph_from_roots, generate_pr_writeup, graph_to_dot, ConflictStrategy, DecisionGraph,
+    DotConfig, WriteupConfig,
+};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// A serializable value as the Python object `json.loads` would give
+fn to_py<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let text = serde_json::to_string(value).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+    Ok(py
+        .import_bound("json")?
+        .call_method1("loads", (text,))?
+        .unbind())
+}
+
+fn runtime_err(e: impl std::fmt::Display) -> PyErr {
+    PyRuntimeError::new_err(e.to_string())
+}
+
+/// A deciduous graph database
+#[pyclass(name = "Database", module = "deciduous")]
+struct Database {
+    inner: deciduous::Database,
+}
+
+impl Database {
+    fn graph_for(&self, roots: Option<Vec<i32>>) -> PyResult<DecisionGraph> {
+        let graph = self.inner.get_graph().map_err(runtime_err)?;
+        Ok(match roots {
+            Some(roots) => filter_graph_from_roots(&graph, &roots),
+            None => graph,
+        })
+    }
+}
+
+#[pymethods]
+impl Database {
+    /// Open the database at `path`, or the project's database
+    ///
+    /// Without a path this finds `.deciduous/deciduous.db` the same way the
+    /// CLI does, honouring `DECIDUOUS_DB_PATH`.
+    #[new]
+    #[pyo3(signature = (path=None))]
+    fn new(path: Option<PathBuf>) -> PyResult<Self> {
+        let inner = match path {
+            Some(path) => deciduous::Database::open_at(path),
+            None => deciduous::Database::open(),
+        }
+        .map_err(runtime_err)?;
+        Ok(Self { inner })
+    }
+
+    /// All nodes, as a list of dicts
+    fn nodes(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &self.inner.get_all_nodes().map_err(runtime_err)?)
+    }
+
+    /// All edges, as a list of dicts
+    fn edges(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &self.inner.get_all_edges().map_err(runtime_err)?)
+    }
+
+    /// `{"nodes": [...], "edges": [...]}`, optionally only what descends from `roots`
+    #[pyo3(signature = (roots=None))]
+    fn graph(&self, py: Python<'_>, roots: Option<Vec<i32>>) -> PyResult<PyObject> {
+        to_py(py, &self.graph_for(roots)?)
+    }
+
+    /// Add a node and return its id
+    #[pyo3(signature = (
+        node_type,
+        title,
+        description=None,
+        confidence=None,
+        commit=None,
+        prompt=None,
+        files=None,
+        branch=None
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn add_node(
+        &self,
+        node_type: &str,
+        title: &str,
+        description: Option<String>,
+        confidence: Option<u8>,
+        commit: Option<String>,
+        prompt: Option<String>,
+        files: Option<Vec<String>>,
+        branch: Option<String>,
+    ) -> PyResult<i32> {
+        if let Some(confidence) = confidence.filter(|c| *c > 100) {
+            return Err(PyValueError::new_err(format!(
+                "confidence must be 0-100, got {}",
+                confidence
+            )));
+        }
+        let mut node = self.inner.node(node_type, title);
+        if let Some(description) = description {
+            node = node.description(description);
+        }
+        if let Some(confidence) = confidence {
+            node = node.confidence(confidence);
+        }
+        if let Some(commit) = commit {
+            node = node.commit(commit);
+        }
+        if let Some(prompt) = prompt {
+            node = node.prompt(prompt);
+        }
+        if let Some(files) = files {
+            node = node.files(files);
+        }
+        if let Some(branch) = branch {
+            node = node.branch(branch);
+        }
+        node.insert().map_err(runtime_err)
+    }
+
+    /// Connect two nodes and return the edge id
+    #[pyo3(signature = (from_id, to_id, edge_type="leads_to", rationale=None))]
+    fn link(
+        &self,
+        from_id: i32,
+        to_id: i32,
+        edge_type: &str,
+        rationale: Option<String>,
+    ) -> PyResult<i32> {
+        let mut edge = self.inner.edge(from_id, to_id).edge_type(edge_type);
+        if let Some(rationale) = rationale {
+            edge = edge.rationale(rationale);
+        }
+        edge.insert().map_err(runtime_err)
+    }
+
+    /// Set a node's status
+    fn set_status(&self, node_id: i32, status: &str) -> PyResult<()> {
+        self.inner
+            .update_node_status(node_id, status)
+            .map_err(runtime_err)
+    }
+
+    /// Export nodes (all, or `node_ids`) and their edges as a patch
+    #[pyo3(signature = (node_ids=None, branch=None, author=None))]
+    fn export_patch(
+        &self,
+        node_ids: Option<Vec<i32>>,
+        branch: Option<&str>,
+        author: Option<String>,
+    ) -> PyResult<GraphPatch> {
+        let base_commit = deciduous::get_current_git_commit();
+        let inner = self
+            .inner
+            .export_patch(node_ids, branch, author, base_commit)
+            .map_err(runtime_err)?;
+        Ok(GraphPatch { inner })
+    }
+
+    /// Apply a patch and return what changed, as a dict
+    ///
+    /// `strategy` settles conflicting fields: "ours", "theirs" or "newest".
+    /// By default conflicts keep the local value and are recorded for review.
+    #[pyo3(signature = (patch, dry_run=false, strategy=None))]
+    fn apply_patch(
+        &self,
+        py: Python<'_>,
+        patch: &GraphPatch,
+        dry_run: bool,
+        strategy: Option<&str>,
+    ) -> PyResult<PyObject> {
+        let strategy = match strategy {
+            Some(s) => ConflictStrategy::parse(s).ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "unknown strategy '{}' (use ours, theirs or newest)",
+                    s
+                ))
+            })?,
+            None => ConflictStrategy::default(),
+        };
+        let result = self
+            .inner
+            .apply_patch(&patch.inner, dry_run, strategy)
+            .map_err(runtime_err)?;
+        to_py(py, &result)
+    }
+
+    /// The graph (or what descends from `roots`) in Graphviz DOT
+    #[pyo3(signature = (roots=None, title=None, rankdir="TB"))]
+    fn to_dot(
+        &self,
+        roots: Option<Vec<i32>>,
+        title: Option<String>,
+        rankdir: &str,
+    ) -> PyResult<String> {
+        let config = DotConfig {
+            title,
+            rankdir: rankdir.to_string(),
+            ..DotConfig::default()
+        };
+        Ok(graph_to_dot(&self.graph_for(roots)?, &config))
+    }
+
+    /// A PR description in markdown for what descends from `roots`
+    #[pyo3(signature = (title, roots=None))]
+    fn writeup(&self, title: String, roots: Option<Vec<i32>>) -> PyResult<String> {
+        let config = WriteupConfig {
+            title,
+            root_ids: vec![],
+            include_dot: false,
+            include_test_plan: true,
+            png_filename: None,
+            github_repo: None,
+            git_branch: None,
+        };
+        Ok(generate_pr_writeup(&self.graph_for(roots)?, &config))
+    }
+}
+
+/// A patch file of nodes and edges to share with `deciduous diff apply`
+#[pyclass(name = "GraphPatch", module = "deciduous")]
+struct GraphPatch {
+    inner: deciduous::GraphPatch,
+}
+
+#[pymethods]
+impl GraphPatch {
+    /// Read a patch file
+    #[staticmethod]
+    fn load(path: PathBuf) -> PyResult<Self> {
+        let inner = deciduous::GraphPatch::load(&path).map_err(runtime_err)?;
+        Ok(Self { inner })
+    }
+
+    /// Write the patch to `path`
+    fn save(&self, path: PathBuf) -> PyResult<()> {
+        self.inner.save(&path).map_err(runtime_err)
+    }
+
+    #[getter]
+    fn author(&self) -> Option<String> {
+        self.inner.author.clone()
+    }
+
+    #[getter]
+    fn branch(&self) -> Option<String> {
+        self.inner.branch.clone()
+    }
+
+    #[getter]
+    fn nodes(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &self.inner.nodes)
+    }
+
+    #[getter]
+    fn edges(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &self.inner.edges)
+    }
+
+    /// The whole patch as a dict, in its file format
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &self.inner)
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.nodes.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "GraphPatch(author={:?}, nodes={}, edges={})",
+            self.inner.author,
+            self.inner.nodes.len(),
+            self.inner.edges.len()
+        )
+    }
+}
+
+#[pymodule]
+fn _deciduous(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Database>()?;
+    m.add_class::<GraphPatch>()?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
@@ -0,0 +1,558 @@
+//! Import architecture decision records (MADR and Log4brains)
+//!
+//! `deciduous import adr docs/decisions` reads a directory of MADR markdown
+//! files; `deciduous import log4brains` finds the ADR folders named in
+//! `.log4brains.yml` (Log4brains writes MADR too). Each record becomes a
+//! decision, with an option per "Considered Options" entry, linked `chosen`
+//! or `rejected` by the "Decision Outcome", and an outcome node for the
+//! outcome text. Links between records ("Superseded by", "Refines", ...)
+//! become edges. Decisions remember their file in `adr` metadata, so
+//! re-importing updates them instead of adding copies.
+
+use crate::db::Database;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Markdown files in an ADR folder that aren't records
+const NOT_RECORDS: &[&str] = &["readme", "index", "template", "adr-template"];
+
+/// A link from one record to another
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdrLink {
+    /// The link's wording, e.g. "superseded by" or "refines"
+    pub relation: String,
+    /// The target record's file name without `.md`
+    pub target: String,
+}
+
+/// One parsed decision record
+#[derive(Debug, Clone, Default)]
+pub struct Adr {
+    /// File name without `.md`, used to resolve links
+    pub name: String,
+    pub path: String,
+    pub title: String,
+    /// Lowercased first word of the status, e.g. "accepted"
+    pub status: String,
+    pub date: Option<String>,
+    pub context: Option<String>,
+    pub options: Vec<String>,
+    pub chosen: Option<String>,
+    /// The "Decision Outcome" section, consequences included
+    pub outcome: Option<String>,
+    pub links: Vec<AdrLink>,
+}
+
+/// Strip list markers, brackets, quotes and emphasis from an item
+fn clean(item: &str) -> String {
+    let item = item.trim();
+    let item = item
+        .strip_prefix("* ")
+        .or_else(|| item.strip_prefix("- "))
+        .unwrap_or_else(|| {
+            let digits = item.trim_start_matches(|c: char| c.is_ascii_digit());
+            digits
+                .strip_prefix(". ")
+                .filter(|_| digits.len() < item.len())
+                .unwrap_or(item)
+        });
+    item.trim()
+        .trim_matches(|c: char| matches!(c, '[' | ']' | '{' | '}' | '"' | '*' | '`' | '_'))
+        .trim()
+        .to_string()
+}
+
+/// Markdown links `[text](target.md)` in `line`, as (text, file name without `.md`)
+fn md_links(line: &str) -> Vec<(String, String)> {
+    let mut links = Vec::new();
+    let mut rest = line;
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find("](").map(|i| open + i) else {
+            break;
+        };
+        let Some(end) = rest[close..].find(')').map(|i| close + i) else {
+            break;
+        };
+        let target = &rest[close + 2..end];
+        let file = target.rsplit('/').next().unwrap_or(target);
+        if let Some(name) = file.split('#').next().and_then(|f| f.strip_suffix(".md")) {
+            links.push((rest[open + 1..close].to_string(), name.to_string()));
+        }
+        rest = &rest[end + 1..];
+    }
+    links
+}
+
+/// A `Key: value` header line (`* Status: accepted`, `- Date: ...` or front
+/// matter `status: accepted`) as (lowercased key, value)
+fn header(line: &str) -> Option<(String, String)> {
+    let line = line.trim().trim_start_matches(['*', '-']).trim();
+    let (key, value) = line.split_once(':')?;
+    let key = key.trim().to_lowercase();
+    (!key.is_empty() && key.split_whitespace().count() <= 2)
+        .then(|| (key, value.trim().to_string()))
+}
+
+/// The link's relation: the words before the link, e.g. "superseded by"
+fn relation(line: &str, default: &str) -> String {
+    let before = line[..line.find('[').unwrap_or(0)]
+        .trim()
+        .trim_start_matches(['*', '-'])
+        .trim()
+        .trim_end_matches(':')
+        .trim()
+        .to_lowercase();
+    let before = before.strip_prefix("status:").unwrap_or(&before).trim();
+    if before.is_empty() {
+        default.to_string()
+    } else {
+        before.to_string()
+    }
+}
+
+/// Parse one MADR file; None if it has no `#` title
+pub fn parse(name: &str, path: &str, content: &str) -> Option<Adr> {
+    let mut adr = Adr {
+        name: name.to_string(),
+        path: path.to_string(),
+        ..Default::default()
+    };
+    let mut lines = content.lines().peekable();
+
+    // MADR 3 front matter
+    if lines.peek().map(|l| l.trim()) == Some("---") {
+        lines.next();
+        for line in lines.by_ref() {
+            if line.trim() == "---" {
+                break;
+            }
+            if let Some((key, value)) = header(line) {
+                match key.as_str() {
+                    "status" => adr.status = value,
+                    "date" => adr.date = Some(value),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    let mut section: Option<String> = None;
+    let mut sections: HashMap<String, Vec<&str>> = HashMap::new();
+    for line in lines {
+        if let Some(title) = line.strip_prefix("# ") {
+            if adr.title.is_empty() {
+                adr.title = title.trim().to_string();
+            }
+            continue;
+        }
+        if let Some(heading) = line.strip_prefix("## ") {
+            section = Some(heading.trim().to_lowercase());
+            continue;
+        }
+        match &section {
+            Some(name) => sections.entry(name.clone()).or_default().push(line),
+            None => {
+                if let Some((key, value)) = header(line) {
+                    match key.as_str() {
+                        "status" => adr.status = value,
+                        "date" => adr.date = Some(value),
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+    if adr.title.is_empty() {
+        return None;
+    }
+
+    // "superseded by [ADR-0005](0005-x.md)" in the status
+    for (_, target) in md_links(&adr.status) {
+        adr.links.push(AdrLink {
+            relation: relation(&adr.status, "superseded by"),
+            target,
+        });
+    }
+    adr.status = adr
+        .status
+        .split(|c: char| !c.is_alphanumeric())
+        .find(|w| !w.is_empty())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let text = |lines: &[&str]| {
+        let text = lines.join("\n").trim().to_string();
+        (!text.is_empty()).then_some(text)
+    };
+    for (heading, body) in &sections {
+        if heading.starts_with("context") {
+            adr.context = text(body);
+        } else if heading.starts_with("considered options") {
+            adr.options = body
+                .iter()
+                .filter(|l| {
+                    let l = l.trim_start();
+                    l.starts_with("* ")
+                        || l.starts_with("- ")
+                        || l.split_once(". ")
+                            .is_some_and(|(n, _)| n.chars().all(|c| c.is_ascii_digit()))
+                })
+                .filter(|l| !l.starts_with(' ') && !l.starts_with('\t'))
+                .map(|l| clean(l))
+                .filter(|o| !o.is_empty())
+                .collect();
+        } else if heading.starts_with("decision outcome") {
+            adr.outcome = text(body);
+            adr.chosen = body.iter().find_map(|l| {
+                let (_, rest) = l.split_once("hosen option:")?;
+                let rest = rest.trim();
+                let chosen = match rest.chars().next()? {
+                    q @ ('"' | '`' | '[') => {
+                        let close = if q == '[' { ']' } else { q };
+                        rest[1..].split(close).next()?.to_string()
+                    }
+                    '*' => rest.trim_start_matches('*').split("**").next()?.to_string(),
+                    _ => rest.split(", because").next()?.to_string(),
+                };
+                Some(clean(&chosen)).filter(|c| !c.is_empty())
+            });
+        } else if heading == "links" || heading == "more information" {
+            for line in body {
+                for (_, target) in md_links(line) {
+                    adr.links.push(AdrLink {
+                        relation: relation(line, "relates to"),
+                        target,
+                    });
+                }
+            }
+        }
+    }
+    Some(adr)
+}
+
+/// The records in `dir`, by file name
+pub fn read_dir(dir: &Path) -> Result<Vec<Adr>, String> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| format!("Reading {}: {}", dir.display(), e))?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|x| x == "md"))
+        .filter(|p| {
+            p.file_stem()
+                .map(|s| s.to_string_lossy().to_lowercase())
+                .is_some_and(|s| !NOT_RECORDS.contains(&s.as_str()))
+        })
+        .collect();
+    files.sort();
+    let mut adrs = Vec::new();
+    for file in files {
+        let content = std::fs::read_to_string(&file)
+            .map_err(|e| format!("Reading {}: {}", file.display(), e))?;
+        let name = file
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if let Some(adr) = parse(&name, &file.to_string_lossy(), &content) {
+            adrs.push(adr);
+        }
+    }
+    Ok(adrs)
+}
+
+/// ADR folders named by `adrFolder` in `<root>/.log4brains.yml`, for the
+/// project and each package
+pub fn log4brains_dirs(root: &Path) -> Result<Vec<PathBuf>, String> {
+    let config = root.join(".log4brains.yml");
+    let content = std::fs::read_to_string(&config)
+        .map_err(|e| format!("Reading {}: {}", config.display(), e))?;
+    let dirs: Vec<PathBuf> = content
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("adrFolder:"))
+        .map(|v| v.split(" #").next().unwrap_or(v))
+        .map(|v| v.trim().trim_matches(['"', '\'']))
+        .filter(|v| !v.is_empty())
+        .map(|v| root.join(v))
+        .collect();
+    if dirs.is_empty() {
+        return Err(format!("No adrFolder in {}", config.display()));
+    }
+    Ok(dirs)
+}
+
+/// The node status for an ADR status
+pub fn node_status(status: &str) -> &'static str {
+    match status {
+        "accepted" | "done" => "completed",
+        "rejected" | "deprecated" | "superseded" => "rejected",
+        "proposed" | "draft" => "pending",
+        _ => "pending",
+    }
+}
+
+/// What an import did
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub decisions_created: usize,
+    pub decisions_updated: usize,
+    /// Options and outcomes added under new decisions
+    pub nodes_created: usize,
+    pub edges_created: usize,
+}
+
+/// The option `chosen` names, matched loosely
+fn chosen_index(options: &[String], chosen: &str) -> Option<usize> {
+    let chosen = chosen.to_lowercase();
+    options
+        .iter()
+        .position(|o| o.to_lowercase() == chosen)
+        .or_else(|| {
+            options.iter().position(|o| {
+                let o = o.to_lowercase();
+                o.starts_with(&chosen) || chosen.starts_with(&o)
+            })
+        })
+}
+
+/// Add or update a decision for each record, then link the records
+pub fn import(db: &Database, adrs: &[Adr], source: &str) -> Result<ImportSummary, String> {
+    let err = |e: crate::db::DbError| e.to_string();
+    let mut summary = ImportSummary::default();
+    let mut decisions: HashMap<String, i32> = HashMap::new();
+    let mut fresh = HashSet::new();
+
+    for adr in adrs {
+        let description = adr.context.as_deref();
+        let status = node_status(&adr.status);
+        let meta = serde_json::json!({
+            "path": adr.path,
+            "status": adr.status,
+            "date": adr.date,
+            "source": source,
+        });
+        if let Some(existing) = db.find_node_by_adr(&adr.path).map_err(err)? {
+            if existing.title != adr.title
+                || existing.description.as_deref() != description
+                || existing.status != status
+            {
+                db.update_node_content(existing.id, &adr.title, description)
+                    .map_err(err)?;
+                db.update_node_status(existing.id, status).map_err(err)?;
+                db.update_node_metadata_field(existing.id, "adr", meta)
+                    .map_err(err)?;
+                summary.decisions_updated += 1;
+            }
+            decisions.insert(adr.name.clone(), existing.id);
+            continue;
+        }
+
+        let mut node = db.node("decision", &adr.title);
+        if let Some(context) = description {
+            node = node.description(context);
+        }
+        let decision = node.insert().map_err(err)?;
+        db.update_node_status(decision, status).map_err(err)?;
+        db.update_node_metadata_field(decision, "adr", meta)
+            .map_err(err)?;
+        summary.decisions_created += 1;
+        decisions.insert(adr.name.clone(), decision);
+        fresh.insert(decision);
+
+        let chosen = adr
+            .chosen
+            .as_deref()
+            .and_then(|c| chosen_index(&adr.options, c));
+        let mut chosen_option = None;
+        for (i, title) in adr.options.iter().enumerate() {
+            let option = db.node("option", title).insert().map_err(err)?;
+            summary.nodes_created += 1;
+            let edge_type = match chosen {
+                Some(c) if c == i => {
+                    chosen_option = Some(option);
+                    db.update_node_status(option, "completed").map_err(err)?;
+                    "chosen"
+                }
+                Some(_) => {
+                    db.update_node_status(option, "rejected").map_err(err)?;
+                    "rejected"
+                }
+                None => "leads_to",
+            };
+            db.edge(decision, option)
+                .edge_type(edge_type)
+                .rationale("Considered option")
+                .insert()
+                .map_err(err)?;
+            summary.edges_created += 1;
+        }
+
+        if let Some(text) = &adr.outcome {
+            let title = match &adr.chosen {
+                Some(chosen) => format!("Chosen: {}", chosen),
+                None => format!("Outcome: {}", adr.title),
+            };
+            let outcome = db
+                .node("outcome", &title)
+                .description(text.clone())
+                .insert()
+                .map_err(err)?;
+            summary.nodes_created += 1;
+            db.edge(chosen_option.unwrap_or(decision), outcome)
+                .rationale("Decision outcome")
+                .insert()
+                .map_err(err)?;
+            summary.edges_created += 1;
+        }
+    }
+
+    // Links between records: "superseded by" points from the old record to
+    // the new, the rest from the record to its target
+    let existing: HashSet<(i32, i32)> = db
+        .get_all_edges()
+        .map_err(err)?
+        .iter()
+        .map(|e| (e.from_node_id, e.to_node_id))
+        .collect();
+    let mut linked = HashSet::new();
+    for adr in adrs {
+        let Some(&this) = decisions.get(&adr.name) else {
+            continue;
+        };
+        for link in &adr.links {
+            let Some(&target) = decisions.get(&link.target) else {
+                continue;
+            };
+            let (from, to, rationale) = match link.relation.as_str() {
+                "supersedes" => (target, this, "Superseded by".to_string()),
+                r if r.starts_with("superseded") => (this, target, "Superseded by".to_string()),
+                r => {
+                    let mut chars = r.chars();
+                    let first = chars.next().map(|c| c.to_uppercase().to_string());
+                    (this, target, first.unwrap_or_default() + chars.as_str())
+                }
+            };
+            if from == to
+                || existing.contains(&(from, to))
+                || !(fresh.contains(&from) || fresh.contains(&to))
+                || !linked.insert((from, to))
+            {
+                continue;
+            }
+            db.edge(from, to)
+                .rationale(rationale)
+                .insert()
+                .map_err(err)?;
+            summary.edges_created += 1;
+        }
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MADR: &str = "# Use Postgres for storage
+
+* Status: superseded by [ADR-0002](0002-use-sqlite.md)
+* Date: 2024-03-01
+
+## Context and Problem Statement
+
+We need a database.
+
+## Considered Options
+
+* [Postgres]
+* [SQLite]
+  * nested detail
+
+## Decision Outcome
+
+Chosen option: \"Postgres\", because we know it.
+
+### Consequences
+
+* Good, because it scales.
+";
+
+    const LOG4BRAINS: &str = "---
+status: accepted
+date: 2024-06-01
+---
+
+# Use SQLite
+
+## Considered Options
+
+1. Postgres
+2. SQLite
+
+## Decision Outcome
+
+Chosen option: **SQLite**, because it ships in the binary.
+
+## Links
+
+* Supersedes [Use Postgres](0001-use-postgres.md)
+* Refines [Storage](https://example.com/storage.md)
+";
+
+    #[test]
+    fn test_adr_import() {
+        let old = parse("0001-use-postgres", "adr/0001-use-postgres.md", MADR).unwrap();
+        assert_eq!(old.title, "Use Postgres for storage");
+        assert_eq!(old.status, "superseded");
+        assert_eq!(old.date.as_deref(), Some("2024-03-01"));
+        assert_eq!(old.options, vec!["Postgres", "SQLite"]);
+        assert_eq!(old.chosen.as_deref(), Some("Postgres"));
+        assert_eq!(
+            old.links,
+            vec![AdrLink {
+                relation: "superseded by".to_string(),
+                target: "0002-use-sqlite".to_string()
+            }]
+        );
+        let new = parse("0002-use-sqlite", "adr/0002-use-sqlite.md", LOG4BRAINS).unwrap();
+        assert_eq!(new.status, "accepted");
+        assert_eq!(new.options, vec!["Postgres", "SQLite"]);
+        assert_eq!(new.chosen.as_deref(), Some("SQLite"));
+        assert_eq!(new.links[0].relation, "supersedes");
+        assert!(parse("x", "x.md", "no title").is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let adrs = vec![old, new];
+        let summary = import(&db, &adrs, "madr").unwrap();
+        assert_eq!(summary.decisions_created, 2);
+        assert_eq!(summary.nodes_created, 6);
+        // 4 options, 2 outcomes and one supersession for both records' links
+        assert_eq!(summary.edges_created, 7);
+
+        let nodes = db.get_all_nodes().unwrap();
+        let old_id = db
+            .find_node_by_adr("adr/0001-use-postgres.md")
+            .unwrap()
+            .unwrap()
+            .id;
+        let new_id = db
+            .find_node_by_adr("adr/0002-use-sqlite.md")
+            .unwrap()
+            .unwrap()
+            .id;
+        let by_id = |id: i32| nodes.iter().find(|n| n.id == id).unwrap();
+        assert_eq!(by_id(old_id).status, "rejected");
+        assert_eq!(by_id(new_id).status, "completed");
+        let edges = db.get_all_edges().unwrap();
+        assert!(edges
+            .iter()
+            .any(|e| e.from_node_id == old_id && e.to_node_id == new_id));
+        assert!(edges.iter().any(|e| e.from_node_id == new_id
+            && e.edge_type == "chosen"
+            && by_id(e.to_node_id).title == "SQLite"));
+
+        let again = import(&db, &adrs, "madr").unwrap();
+        assert_eq!(again.decisions_created + again.decisions_updated, 0);
+        assert_eq!(again.edges_created, 0);
+        assert_eq!(db.get_all_nodes().unwrap().len(), nodes.len());
+    }
+}
@@ -0,0 +1,205 @@
+//! Questions answered from the decision graph
+//!
+//! `deciduous ask` picks the nodes most relevant to a question, by matching
+//! words and (when the `[embeddings]` endpoint answers) by meaning, writes
+//! them with their edges and the prompts of the spans that created them into
+//! a prompt, and has a model answer citing node IDs as `#12`.
+
+use crate::db::{Database, DecisionEdge, DecisionNode};
+use crate::embed::{node_text, SearchHit};
+use regex::Regex;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+
+/// Characters of a span's user prompt included per span
+const SPAN_PREVIEW: usize = 300;
+
+const SYSTEM: &str = "You answer questions about a software project using its decision graph: \
+                      goals, decisions, options, actions, outcomes and observations recorded \
+                      while the work was done. Use only the nodes given. Cite the nodes behind \
+                      each claim by ID, like #12. If the nodes don't answer the question, say so.";
+
+/// Words too common to say anything about relevance
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "did", "do", "does", "for", "from", "how", "in", "is", "it", "of",
+    "on", "or", "the", "to", "was", "we", "what", "when", "where", "which", "who", "why", "with",
+];
+
+fn terms(text: &str) -> HashSet<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 1 && !STOPWORDS.contains(w))
+        .map(String::from)
+        .collect()
+}
+
+/// Share of the question's words that appear in each node's text (0 to 1)
+pub fn keyword_scores(nodes: &[DecisionNode], question: &str) -> HashMap<i32, f32> {
+    let wanted = terms(question);
+    if wanted.is_empty() {
+        return HashMap::new();
+    }
+    nodes
+        .iter()
+        .filter_map(|n| {
+            let found = terms(&node_text(n)).intersection(&wanted).count();
+            (found > 0).then(|| (n.id, found as f32 / wanted.len() as f32))
+        })
+        .collect()
+}
+
+/// The `limit` nodes scoring highest on keyword share plus semantic
+/// similarity, best first
+pub fn retrieve(
+    db: &Database,
+    question: &str,
+    semantic: &[SearchHit],
+    limit: usize,
+) -> Result<Vec<DecisionNode>, String> {
+    let nodes = db.get_all_nodes().map_err(|e| e.to_string())?;
+    let mut scores = keyword_scores(&nodes, question);
+    for hit in semantic {
+        *scores.entry(hit.node.id).or_default() += hit.score.max(0.0);
+    }
+    let mut ranked: Vec<(f32, DecisionNode)> = nodes
+        .into_iter()
+        .filter_map(|n| scores.get(&n.id).map(|s| (*s, n)))
+        .collect();
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.id.cmp(&b.1.id)));
+    Ok(ranked.into_iter().take(limit).map(|(_, n)| n).collect())
+}
+
+/// The selected nodes, the edges between them and the prompts of the spans
+/// that created them, as plain text for the model
+pub fn context(db: &Database, nodes: &[DecisionNode]) -> Result<String, String> {
+    let ids: HashSet<i32> = nodes.iter().map(|n| n.id).collect();
+    let mut text = String::from("Nodes:\n");
+    for node in nodes {
+        text.push_str(&format!(
+            "#{} [{}, {}] {}\n",
+            node.id, node.node_type, node.status, node.title
+        ));
+        let meta = node
+            .metadata_json
+            .as_deref()
+            .and_then(|m| serde_json::from_str::<Value>(m).ok())
+            .unwrap_or(Value::Null);
+        if let Some(description) = node.description.as_deref().filter(|d| !d.is_empty()) {
+            text.push_str(&format!("  description: {}\n", description));
+        }
+        for field in ["prompt", "files", "commit", "branch"] {
+            if let Some(value) = meta[field].as_str().filter(|v| !v.is_empty()) {
+                text.push_str(&format!("  {}: {}\n", field, value));
+            }
+        }
+        for span in db.get_spans_for_node(node.id).map_err(|e| e.to_string())? {
+            if let Some(preview) = span.user_preview.as_deref().filter(|p| !p.is_empty()) {
+                let preview: String = preview.chars().take(SPAN_PREVIEW).collect();
+                text.push_str(&format!("  asked during span #{}: {}\n", span.id, preview));
+            }
+        }
+    }
+
+    let edges: Vec<DecisionEdge> = db
+        .get_all_edges()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|e| ids.contains(&e.from_node_id) && ids.contains(&e.to_node_id))
+        .collect();
+    if !edges.is_empty() {
+        text.push_str("\nEdges:\n");
+        for edge in edges {
+            text.push_str(&format!(
+                "#{} -> #{} ({})",
+                edge.from_node_id, edge.to_node_id, edge.edge_type
+            ));
+            if let Some(rationale) = edge.rationale.as_deref().filter(|r| !r.is_empty()) {
+                text.push_str(&format!(": {}", rationale));
+            }
+            text.push('\n');
+        }
+    }
+    Ok(text)
+}
+
+/// Messages API request asking `model` the question over `context`
+pub fn llm_request(question: &str, context: &str, model: &str) -> Value {
+    json!({
+        "model": model,
+        "max_tokens": 1024,
+        "system": SYSTEM,
+        "messages": [{
+            "role": "user",
+            "content": format!("{}\nQuestion: {}", context, question),
+        }],
+    })
+}
+
+/// The text blocks of a Messages API response
+pub fn reply_text(reply: &str) -> Result<String, String> {
+    let reply: Value =
+        serde_json::from_str(reply).map_err(|e| format!("bad response from model: {}", e))?;
+    let text: Vec<&str> = reply["content"]
+        .as_array()
+        .ok_or("response from model has no content")?
+        .iter()
+        .filter(|block| block["type"] == "text")
+        .filter_map(|block| block["text"].as_str())
+        .collect();
+    Ok(text.join(""))
+}
+
+/// Node IDs cited as `#12` in `answer` that were among `nodes`, in order
+pub fn citations(answer: &str, nodes: &[DecisionNode]) -> Vec<i32> {
+    let re = Regex::new(r"#(\d+)").expect("valid regex");
+    let mut cited = Vec::new();
+    for caps in re.captures_iter(answer) {
+        if let Ok(id) = caps[1].parse::<i32>() {
+            if nodes.iter().any(|n| n.id == id) && !cited.contains(&id) {
+                cited.push(id);
+            }
+        }
+    }
+    cited
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ask_pipeline() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let goal = db.node("goal", "Pick a storage engine").insert().unwrap();
+        let sqlite = db
+            .node("decision", "Use SQLite for the graph")
+            .description("Single file, no server")
+            .insert()
+            .unwrap();
+        db.node("action", "Build the wasm viewer").insert().unwrap();
+        db.edge(goal, sqlite)
+            .rationale("embedded is enough")
+            .insert()
+            .unwrap();
+
+        let nodes = retrieve(&db, "Why did we pick SQLite for storage?", &[], 5).unwrap();
+        let ids: Vec<i32> = nodes.iter().map(|n| n.id).collect();
+        assert_eq!(ids, vec![goal, sqlite]);
+
+        let text = context(&db, &nodes).unwrap();
+        assert!(text.contains(&format!("#{} [decision, pending] Use SQLite", sqlite)));
+        assert!(text.contains("  description: Single file, no server\n"));
+        assert!(text.contains(&format!(
+            "#{} -> #{} (leads_to): embedded is enough",
+            goal, sqlite
+        )));
+        assert!(!text.contains("wasm"));
+
+        let reply = json!({ "content": [
+            { "type": "text", "text": format!("SQLite was chosen (#{}, #{}, #99).", sqlite, sqlite) }
+        ]});
+        let answer = reply_text(&reply.to_string()).unwrap();
+        assert_eq!(citations(&answer, &nodes), vec![sqlite]);
+    }
+}
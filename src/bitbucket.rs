@@ -0,0 +1,604 @@
+//! Bitbucket Cloud Integration
+//!
+//! Bitbucket implementation of the `Forge` trait. Bitbucket has no CLI
+//! comparable to `gh`/`glab`, so this talks to the 2.0 REST API through
+//! `curl`, authenticating with `BITBUCKET_USERNAME` + `BITBUCKET_APP_PASSWORD`
+//! or a `BITBUCKET_TOKEN` access token.
+//!
+//! Bitbucket's issue tracker is optional per repository and has no labels;
+//! labels map onto issue components, which (like milestones) can only be
+//! created in the repository settings.
+
+use crate::forge::{Forge, ForgeKind, Milestone, PullRequest, ReviewThread};
+use crate::github::{CommentAuthor, GitHubComment, GitHubError, GitHubIssue, Result};
+use crate::remote_patch::{curl_auth_from_env, curl_json};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const API_BASE: &str = "https://api.bitbucket.org/2.0";
+
+/// Follow at most this many `next` links when listing
+const MAX_PAGES: usize = 20;
+
+/// Bitbucket Cloud client using the REST API via `curl`
+pub struct BitbucketClient {
+    repo: Option<String>, // "workspace/repo_slug" format
+}
+
+/// Map Bitbucket issue states onto "open"/"closed"
+///
+/// new/open/on hold are still open; resolved/closed/invalid/duplicate/wontfix are done.
+fn normalize_issue_state(state: &str) -> String {
+    match state {
+        "new" | "open" | "on hold" => "open".to_string(),
+        _ => "closed".to_string(),
+    }
+}
+
+/// Map Bitbucket PR states (OPEN, MERGED, DECLINED, SUPERSEDED) onto open/merged/closed
+fn normalize_pr_state(state: &str) -> String {
+    match state {
+        "OPEN" => "open".to_string(),
+        "MERGED" => "merged".to_string(),
+        _ => "closed".to_string(),
+    }
+}
+
+/// Quote a value for a Bitbucket `q=` filter
+fn query_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn issue_from_json(v: &Value) -> Option<GitHubIssue> {
+    Some(GitHubIssue {
+        number: v.get("id")?.as_i64()? as i32,
+        title: v.get("title")?.as_str()?.to_string(),
+        body: v
+            .pointer("/content/raw")
+            .and_then(|b| b.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        state: normalize_issue_state(v.get("state")?.as_str()?),
+        html_url: v
+            .pointer("/links/html/href")
+            .and_then(|h| h.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        created_at: v
+            .get("created_on")
+            .and_then(|d| d.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        updated_at: v
+            .get("updated_on")
+            .and_then(|d| d.as_str())
+            .unwrap_or_default()
+            .to_string(),
+    })
+}
+
+fn pull_request_from_json(v: &Value) -> Option<PullRequest> {
+    Some(PullRequest {
+        number: v.get("id")?.as_i64()? as i32,
+        title: v.get("title")?.as_str()?.to_string(),
+        state: normalize_pr_state(v.get("state")?.as_str()?),
+        head_branch: v
+            .pointer("/source/branch/name")
+            .and_then(|b| b.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        html_url: v
+            .pointer("/links/html/href")
+            .and_then(|h| h.as_str())
+            .unwrap_or_default()
+            .to_string(),
+    })
+}
+
+/// Convert an issue/PR comment, skipping deleted ones
+fn comment_from_json(v: &Value) -> Option<GitHubComment> {
+    if v.get("deleted").and_then(|d| d.as_bool()) == Some(true) {
+        return None;
+    }
+    let user = v.get("user");
+    let login = user
+        .and_then(|u| u.get("nickname").or_else(|| u.get("display_name")))
+        .and_then(|n| n.as_str())
+        .unwrap_or("unknown");
+    Some(GitHubComment {
+        id: v.get("id")?.as_i64()?,
+        body: v.pointer("/content/raw")?.as_str()?.to_string(),
+        author: CommentAuthor {
+            login: login.to_string(),
+        },
+        created_at: v
+            .get("created_on")
+            .and_then(|d| d.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        created_at_alt: None,
+    })
+}
+
+/// Group inline PR comments into threads (root comment plus its replies)
+fn group_review_threads(comments: &[Value]) -> Vec<ReviewThread> {
+    let parent_of: HashMap<i64, i64> = comments
+        .iter()
+        .filter_map(|c| Some((c.get("id")?.as_i64()?, c.pointer("/parent/id")?.as_i64()?)))
+        .collect();
+
+    // Walk up reply chains to the thread's root comment
+    let root_of = |mut id: i64| {
+        let mut hops = 0;
+        while let Some(parent) = parent_of.get(&id) {
+            id = *parent;
+            hops += 1;
+            if hops > comments.len() {
+                break;
+            }
+        }
+        id
+    };
+
+    let mut threads: Vec<ReviewThread> = comments
+        .iter()
+        .filter(|c| c.get("parent").is_none() && c.get("inline").is_some())
+        .filter_map(|c| {
+            Some(ReviewThread {
+                id: c.get("id")?.as_i64()?.to_string(),
+                path: c
+                    .pointer("/inline/path")
+                    .and_then(|p| p.as_str())
+                    .map(|p| p.to_string()),
+                line: c
+                    .pointer("/inline/to")
+                    .and_then(|l| l.as_i64())
+                    .or_else(|| c.pointer("/inline/from").and_then(|l| l.as_i64()))
+                    .map(|l| l as i32),
+                excerpt: None,
+                resolved: c.get("resolution").is_some_and(|r| !r.is_null()),
+                comments: vec![],
+            })
+        })
+        .collect();
+
+    for comment in comments {
+        let Some(id) = comment.get("id").and_then(|i| i.as_i64()) else {
+            continue;
+        };
+        let root = root_of(id).to_string();
+        if let (Some(thread), Some(parsed)) = (
+            threads.iter_mut().find(|t| t.id == root),
+            comment_from_json(comment),
+        ) {
+            thread.comments.push(parsed);
+        }
+    }
+
+    threads.retain(|t| !t.comments.is_empty());
+    threads
+}
+
+impl BitbucketClient {
+    /// Create a new client, optionally with explicit repo
+    pub fn new(repo: Option<String>) -> Self {
+        Self { repo }
+    }
+
+    /// Get the repo name
+    pub fn repo_name(&self) -> Option<&str> {
+        self.repo.as_deref()
+    }
+
+    /// API path prefix for this repository
+    fn repo_path(&self) -> Result<String> {
+        self.repo
+            .as_ref()
+            .map(|r| format!("repositories/{}", r))
+            .ok_or_else(|| GitHubError::ParseError {
+                message: "Repository unknown; specify --repo workspace/repo".to_string(),
+            })
+    }
+
+    /// A `BITBUCKET_TOKEN`, else `BITBUCKET_USERNAME` + `BITBUCKET_APP_PASSWORD`
+    fn auth_config() -> Option<String> {
+        curl_auth_from_env(
+            "BITBUCKET_TOKEN",
+            "BITBUCKET_USERNAME",
+            "BITBUCKET_APP_PASSWORD",
+        )
+    }
+
+    /// Call `url`, a path under `API_BASE` or a full `next` page link
+    fn request(&self, method: &str, url: &str, body: Option<&Value>) -> Result<Value> {
+        let url = if url.starts_with("https://") {
+            url.to_string()
+        } else {
+            format!("{}/{}", API_BASE, url)
+        };
+        curl_json(
+            method,
+            &url,
+            &Self::auth_config().unwrap_or_default(),
+            body,
+            |v| {
+                v.pointer("/error/message")
+                    .and_then(|m| m.as_str())
+                    .map(|m| m.to_string())
+            },
+        )
+    }
+
+    /// GET a paginated collection, following `next` links
+    fn get_all(&self, url: &str) -> Result<Vec<Value>> {
+        let mut values = Vec::new();
+        let mut next = Some(url.to_string());
+        let mut pages = 0;
+
+        while let Some(url) = next.take() {
+            let page = self.request("GET", &url, None)?;
+            if let Some(items) = page.get("values").and_then(|v| v.as_array()) {
+                values.extend(items.iter().cloned());
+            }
+            pages += 1;
+            if pages < MAX_PAGES {
+                next = page
+                    .get("next")
+                    .and_then(|n| n.as_str())
+                    .map(|n| n.to_string());
+            }
+        }
+
+        Ok(values)
+    }
+
+    /// Issues path with a `q=` filter
+    fn issues_query(&self, query: &str) -> Result<String> {
+        let params =
+            serde_urlencoded::to_string([("q", query), ("pagelen", "50")]).map_err(|e| {
+                GitHubError::ParseError {
+                    message: e.to_string(),
+                }
+            })?;
+        Ok(format!("{}/issues?{}", self.repo_path()?, params))
+    }
+
+    /// Number of issues matching a filter (from the paged response's `size`)
+    fn count_issues(&self, query: &str) -> Result<i32> {
+        let page = self.request("GET", &self.issues_query(query)?, None)?;
+        Ok(page.get("size").and_then(|s| s.as_i64()).unwrap_or(0) as i32)
+    }
+
+    fn update_issue(&self, number: i32, fields: Value) -> Result<()> {
+        let path = format!("{}/issues/{}", self.repo_path()?, number);
+        self.request("PUT", &path, Some(&fields))
+            .map(|_| ())
+            .map_err(|e| match e {
+                GitHubError::CommandFailed { ref stderr, .. } if stderr.starts_with("HTTP 404") => {
+                    GitHubError::IssueNotFound { number }
+                }
+                other => other,
+            })
+    }
+}
+
+impl Forge for BitbucketClient {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::Bitbucket
+    }
+
+    fn repo_name(&self) -> Option<&str> {
+        BitbucketClient::repo_name(self)
+    }
+
+    fn check_auth(&self) -> Result<bool> {
+        if Self::auth_config().is_none() {
+            return Ok(false);
+        }
+        Ok(self.request("GET", "user", None).is_ok())
+    }
+
+    fn create_issue(&self, title: &str, body: &str, labels: &[&str]) -> Result<GitHubIssue> {
+        let mut fields = json!({
+            "title": title,
+            "content": { "raw": body },
+            "kind": "task",
+        });
+        // Components must already exist; skip rather than fail the create
+        if let Some(label) = labels.first() {
+            if self.label_exists(label)? {
+                fields["component"] = json!({ "name": label });
+            }
+        }
+
+        let path = format!("{}/issues", self.repo_path()?);
+        let created = self.request("POST", &path, Some(&fields))?;
+        issue_from_json(&created).ok_or_else(|| GitHubError::ParseError {
+            message: "Unexpected issue response".to_string(),
+        })
+    }
+
+    fn get_issue(&self, number: i32) -> Result<GitHubIssue> {
+        let path = format!("{}/issues/{}", self.repo_path()?, number);
+        let issue = self.request("GET", &path, None).map_err(|e| match e {
+            GitHubError::CommandFailed { ref stderr, .. } if stderr.starts_with("HTTP 404") => {
+                GitHubError::IssueNotFound { number }
+            }
+            other => other,
+        })?;
+        issue_from_json(&issue).ok_or_else(|| GitHubError::ParseError {
+            message: format!("Unexpected response for issue #{}", number),
+        })
+    }
+
+    fn update_issue_body(&self, number: i32, body: &str) -> Result<()> {
+        self.update_issue(number, json!({ "content": { "raw": body } }))
+    }
+
+    fn update_issue_title(&self, number: i32, title: &str) -> Result<()> {
+        self.update_issue(number, json!({ "title": title }))
+    }
+
+    fn close_issue(&self, number: i32) -> Result<()> {
+        self.update_issue(number, json!({ "state": "resolved" }))
+    }
+
+    fn reopen_issue(&self, number: i32) -> Result<()> {
+        self.update_issue(number, json!({ "state": "open" }))
+    }
+
+    fn get_issue_comments(&self, number: i32) -> Result<Vec<GitHubComment>> {
+        let path = format!(
+            "{}/issues/{}/comments?pagelen=100",
+            self.repo_path()?,
+            number
+        );
+        Ok(self
+            .get_all(&path)?
+            .iter()
+            .filter_map(comment_from_json)
+            .collect())
+    }
+
+    fn add_comment(&self, number: i32, body: &str) -> Result<()> {
+        let path = format!("{}/issues/{}/comments", self.repo_path()?, number);
+        self.request("POST", &path, Some(&json!({ "content": { "raw": body } })))?;
+        Ok(())
+    }
+
+    fn list_issues_with_label(&self, label: &str) -> Result<Vec<GitHubIssue>> {
+        let path = self.issues_query(&format!("component.name={}", query_string(label)))?;
+        Ok(self
+            .get_all(&path)?
+            .iter()
+            .filter_map(issue_from_json)
+            .collect())
+    }
+
+    fn find_issue_by_title(&self, title: &str) -> Result<Option<GitHubIssue>> {
+        let path = self.issues_query(&format!("title={}", query_string(title)))?;
+        Ok(self
+            .get_all(&path)?
+            .iter()
+            .filter_map(issue_from_json)
+            .find(|i| i.title == title))
+    }
+
+    fn label_exists(&self, name: &str) -> Result<bool> {
+        let path = format!(
+            "{}/components/{}",
+            self.repo_path()?,
+            name.replace(' ', "%20")
+        );
+        match self.request("GET", &path, None) {
+            Ok(_) => Ok(true),
+            Err(GitHubError::CommandFailed { ref stderr, .. })
+                if stderr.starts_with("HTTP 404") =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn create_label(&self, name: &str, _description: &str, _color: &str) -> Result<()> {
+        Err(GitHubError::CommandFailed {
+            command: "bitbucket components".to_string(),
+            stderr: format!(
+                "Bitbucket can't create issue components via the API; add '{}' under Repository settings > Issue tracker",
+                name
+            ),
+        })
+    }
+
+    fn list_pull_requests(&self, state: &str) -> Result<Vec<PullRequest>> {
+        let states: &[&str] = match state {
+            "closed" => &["DECLINED", "SUPERSEDED"],
+            "merged" => &["MERGED"],
+            "all" => &["OPEN", "MERGED", "DECLINED", "SUPERSEDED"],
+            _ => &["OPEN"],
+        };
+        let mut params: Vec<(&str, &str)> = states.iter().map(|s| ("state", *s)).collect();
+        params.push(("pagelen", "50"));
+        let query = serde_urlencoded::to_string(&params).map_err(|e| GitHubError::ParseError {
+            message: e.to_string(),
+        })?;
+        let path = format!("{}/pullrequests?{}", self.repo_path()?, query);
+
+        Ok(self
+            .get_all(&path)?
+            .iter()
+            .filter_map(pull_request_from_json)
+            .collect())
+    }
+
+    fn get_pull_request(&self, number: i32) -> Result<PullRequest> {
+        let path = format!("{}/pullrequests/{}", self.repo_path()?, number);
+        let pr = self.request("GET", &path, None)?;
+        pull_request_from_json(&pr).ok_or_else(|| GitHubError::ParseError {
+            message: format!("Unexpected response for pull request #{}", number),
+        })
+    }
+
+    fn get_pull_request_comments(&self, number: i32) -> Result<Vec<GitHubComment>> {
+        let path = format!(
+            "{}/pullrequests/{}/comments?pagelen=100",
+            self.repo_path()?,
+            number
+        );
+        // Conversation comments only; inline ones are review threads
+        Ok(self
+            .get_all(&path)?
+            .iter()
+            .filter(|c| c.get("inline").is_none())
+            .filter_map(comment_from_json)
+            .collect())
+    }
+
+    fn add_pull_request_comment(&self, number: i32, body: &str) -> Result<()> {
+        let path = format!("{}/pullrequests/{}/comments", self.repo_path()?, number);
+        self.request("POST", &path, Some(&json!({ "content": { "raw": body } })))?;
+        Ok(())
+    }
+
+    fn update_pull_request_comment(&self, number: i32, comment_id: i64, body: &str) -> Result<()> {
+        let path = format!(
+            "{}/pullrequests/{}/comments/{}",
+            self.repo_path()?,
+            number,
+            comment_id
+        );
+        self.request("PUT", &path, Some(&json!({ "content": { "raw": body } })))?;
+        Ok(())
+    }
+
+    fn get_review_threads(&self, number: i32) -> Result<Vec<ReviewThread>> {
+        let path = format!(
+            "{}/pullrequests/{}/comments?pagelen=100",
+            self.repo_path()?,
+            number
+        );
+        Ok(group_review_threads(&self.get_all(&path)?))
+    }
+
+    fn list_milestones(&self) -> Result<Vec<Milestone>> {
+        let path = format!("{}/milestones?pagelen=100", self.repo_path()?);
+        let mut milestones = Vec::new();
+        for m in self.get_all(&path)? {
+            let (Some(id), Some(name)) = (
+                m.get("id").and_then(|i| i.as_i64()),
+                m.get("name").and_then(|n| n.as_str()),
+            ) else {
+                continue;
+            };
+            // Bitbucket milestones carry no state or counts; derive them from issues
+            let filter = format!("milestone.name={}", query_string(name));
+            let open_issues = self.count_issues(&format!(
+                "{} AND (state=\"new\" OR state=\"open\" OR state=\"on hold\")",
+                filter
+            ))?;
+            let total = self.count_issues(&filter)?;
+            milestones.push(Milestone {
+                number: id as i32,
+                title: name.to_string(),
+                state: "open".to_string(),
+                open_issues,
+                closed_issues: total - open_issues,
+            });
+        }
+        Ok(milestones)
+    }
+
+    fn create_milestone(&self, title: &str, _description: &str) -> Result<Milestone> {
+        Err(GitHubError::CommandFailed {
+            command: "bitbucket milestones".to_string(),
+            stderr: format!(
+                "Bitbucket can't create milestones via the API; add '{}' under Repository settings > Issue tracker",
+                title
+            ),
+        })
+    }
+
+    fn set_issue_milestone(&self, number: i32, milestone: &str) -> Result<()> {
+        self.update_issue(number, json!({ "milestone": { "name": milestone } }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_creation() {
+        let client = BitbucketClient::new(Some("team/app".to_string()));
+        assert_eq!(client.repo_name(), Some("team/app"));
+        assert_eq!(client.kind(), ForgeKind::Bitbucket);
+        assert_eq!(client.repo_path().unwrap(), "repositories/team/app");
+        assert!(BitbucketClient::new(None).repo_path().is_err());
+    }
+
+    #[test]
+    fn test_normalize_states() {
+        assert_eq!(normalize_issue_state("new"), "open");
+        assert_eq!(normalize_issue_state("on hold"), "open");
+        assert_eq!(normalize_issue_state("resolved"), "closed");
+        assert_eq!(normalize_issue_state("wontfix"), "closed");
+        assert_eq!(normalize_pr_state("MERGED"), "merged");
+        assert_eq!(normalize_pr_state("DECLINED"), "closed");
+        assert_eq!(normalize_pr_state("OPEN"), "open");
+    }
+
+    #[test]
+    fn test_issue_and_pr_conversion() {
+        let issue = issue_from_json(&json!({
+            "id": 7,
+            "title": "Roadmap item",
+            "content": {"raw": "Body"},
+            "state": "resolved",
+            "links": {"html": {"href": "https://bitbucket.org/team/app/issues/7"}},
+            "created_on": "2025-01-01T00:00:00Z",
+            "updated_on": "2025-01-02T00:00:00Z"
+        }))
+        .unwrap();
+        assert_eq!(issue.number, 7);
+        assert_eq!(issue.state, "closed");
+        assert_eq!(issue.body, "Body");
+
+        let pr = pull_request_from_json(&json!({
+            "id": 3,
+            "title": "Add feature",
+            "state": "OPEN",
+            "source": {"branch": {"name": "feature"}},
+            "links": {"html": {"href": "https://bitbucket.org/team/app/pull-requests/3"}}
+        }))
+        .unwrap();
+        assert_eq!(pr.head_branch, "feature");
+        assert_eq!(pr.state, "open");
+    }
+
+    #[test]
+    fn test_group_review_threads() {
+        let comments = vec![
+            json!({"id": 1, "content": {"raw": "General"}, "user": {"nickname": "amy"}}),
+            json!({"id": 2, "content": {"raw": "Rename this"}, "user": {"nickname": "bob"},
+                   "inline": {"path": "src/lib.rs", "to": 12}, "resolution": {"type": "comment_resolution"}}),
+            json!({"id": 3, "content": {"raw": "Done"}, "user": {"nickname": "amy"},
+                   "inline": {"path": "src/lib.rs", "to": 12}, "parent": {"id": 2}}),
+            json!({"id": 4, "content": {"raw": "Thanks"}, "user": {"display_name": "Bob B"},
+                   "inline": {"path": "src/lib.rs", "to": 12}, "parent": {"id": 3}}),
+        ];
+        let threads = group_review_threads(&comments);
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].id, "2");
+        assert_eq!(threads[0].path.as_deref(), Some("src/lib.rs"));
+        assert_eq!(threads[0].line, Some(12));
+        assert!(threads[0].resolved);
+        assert_eq!(threads[0].comments.len(), 3);
+        assert_eq!(threads[0].comments[2].author.login, "Bob B");
+    }
+
+    #[test]
+    fn test_query_quoting() {
+        assert_eq!(query_string(r#"say "hi""#), r#""say \"hi\"""#);
+    }
+}
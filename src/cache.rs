@@ -0,0 +1,151 @@
+//! Issue and pull request cache refresh
+//!
+//! `github_issue_cache` and `github_pr_cache` back the issue/PR state shown in
+//! the TUI and web viewer. Entries older than `github.cache_ttl_hours` are
+//! refreshed from the forge, either explicitly (`deciduous github refresh`) or
+//! lazily in the background when the viewers read them.
+
+use crate::config::Config;
+use crate::db::Database;
+use crate::forge::{detect_forge, git_remote_url, parse_repo_from_remote, Forge, PullRequest};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// What a cache refresh did
+#[derive(Debug, Default)]
+pub struct CacheRefresh {
+    /// Issues re-fetched and re-cached
+    pub issues: usize,
+    /// Pull requests re-fetched and re-cached
+    pub pull_requests: usize,
+    /// Roadmap items whose issue state was updated
+    pub roadmap_items: usize,
+    /// Entries that couldn't be refreshed, e.g. "#12: not found"
+    pub failed: Vec<String>,
+}
+
+/// Repository the forge client talks to, falling back to the `origin` remote
+fn forge_repo(forge: &dyn Forge) -> Option<String> {
+    forge
+        .repo_name()
+        .map(|r| r.to_string())
+        .or_else(|| git_remote_url().as_deref().and_then(parse_repo_from_remote))
+}
+
+/// Fetch a pull request from the forge and update the local PR cache
+pub fn refresh_pull_request(
+    db: &Database,
+    forge: &dyn Forge,
+    number: i32,
+) -> Result<PullRequest, String> {
+    let pr = forge.get_pull_request(number).map_err(|e| e.to_string())?;
+    let repo = forge_repo(forge).ok_or_else(|| "could not determine repository".to_string())?;
+    db.cache_pull_request(
+        pr.number,
+        &repo,
+        &pr.title,
+        &pr.state,
+        &pr.head_branch,
+        &pr.html_url,
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(pr)
+}
+
+/// Re-fetch cached issues and pull requests for the forge's repository
+///
+/// With `max_age_hours` set, only entries older than that are refreshed;
+/// `None` refreshes everything. Entries cached for other repositories are
+/// left alone since this client can't reach them.
+pub fn refresh_cache(
+    db: &Database,
+    forge: &dyn Forge,
+    max_age_hours: Option<i64>,
+) -> Result<CacheRefresh, String> {
+    let repo = forge_repo(forge).ok_or_else(|| "could not determine repository".to_string())?;
+    let mut summary = CacheRefresh::default();
+
+    let issues = match max_age_hours {
+        Some(hours) => db.get_expired_cached_issues(hours),
+        None => db.get_cached_issues_for_repo(&repo),
+    }
+    .map_err(|e| e.to_string())?;
+
+    for cached in issues.iter().filter(|i| i.repo == repo) {
+        let issue = match forge.get_issue(cached.issue_number) {
+            Ok(issue) => issue,
+            Err(e) => {
+                summary
+                    .failed
+                    .push(format!("issue #{}: {}", cached.issue_number, e));
+                continue;
+            }
+        };
+        db.cache_github_issue(
+            issue.number,
+            &repo,
+            &issue.title,
+            Some(&issue.body),
+            &issue.state,
+            &issue.html_url,
+            &issue.created_at,
+            &issue.updated_at,
+        )
+        .map_err(|e| e.to_string())?;
+        summary.roadmap_items += db
+            .update_roadmap_issue_state(issue.number, &issue.state)
+            .map_err(|e| e.to_string())?;
+        summary.issues += 1;
+    }
+
+    let prs = match max_age_hours {
+        Some(hours) => db.get_expired_cached_pull_requests(hours),
+        None => db.get_all_cached_pull_requests(),
+    }
+    .map_err(|e| e.to_string())?;
+
+    for cached in prs.iter().filter(|p| p.repo == repo) {
+        match refresh_pull_request(db, forge, cached.pr_number) {
+            Ok(_) => summary.pull_requests += 1,
+            Err(e) => summary
+                .failed
+                .push(format!("PR #{}: {}", cached.pr_number, e)),
+        }
+    }
+
+    Ok(summary)
+}
+
+static BACKGROUND_REFRESH_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Refresh expired cache entries on a background thread
+///
+/// Called when the TUI/web viewer reads issue state. Returns immediately; the
+/// refreshed state shows up on the next read. Does nothing if the TTL is 0,
+/// nothing has expired, or a refresh is already running.
+pub fn refresh_expired_in_background(config: &Config) {
+    let ttl = config.github.cache_ttl_hours;
+    if ttl <= 0 {
+        return;
+    }
+
+    let has_expired = Database::open()
+        .map(|db| {
+            db.get_expired_cached_issues(ttl)
+                .is_ok_and(|i| !i.is_empty())
+                || db
+                    .get_expired_cached_pull_requests(ttl)
+                    .is_ok_and(|p| !p.is_empty())
+        })
+        .unwrap_or(false);
+    if !has_expired || BACKGROUND_REFRESH_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let config = config.clone();
+    std::thread::spawn(move || {
+        if let (Ok(db), Ok(forge)) = (Database::open(), detect_forge(None, &config)) {
+            let _ = refresh_cache(&db, forge.as_ref(), Some(ttl));
+        }
+        BACKGROUND_REFRESH_RUNNING.store(false, Ordering::SeqCst);
+    });
+}
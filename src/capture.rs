@@ -0,0 +1,142 @@
+//! Limits on how much trace content is stored
+//!
+//! Chatty sessions can grow the database quickly. `[trace.capture]` trades
+//! detail for size: oversized thinking and response bodies, the bodies and
+//! tool results of spans outside a sample, and the output of named tools can
+//! be left out. Span metadata and previews are always kept, and so are tool
+//! calls, which subagent, commit and tool-usage analysis rely on.
+
+use crate::config::CaptureConfig;
+
+/// Content kinds kept for every span, sampled or not
+const ALWAYS_KEPT: &[&str] = &["tool_input", "mcp_call"];
+
+/// Content kinds subject to the body size limit
+const BODIES: &[&str] = &["thinking", "response"];
+
+/// Content kinds holding a tool's output
+const TOOL_OUTPUTS: &[&str] = &["tool_output", "tool_error", "mcp_result", "mcp_error"];
+
+/// Which content to store, built from `[trace.capture]`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CaptureFilter {
+    max_body_bytes: Option<usize>,
+    sample_every: u32,
+    skip_tool_output: Vec<String>,
+}
+
+impl CaptureFilter {
+    pub fn from_config(config: &CaptureConfig) -> Self {
+        Self {
+            max_body_bytes: config
+                .max_body_kb
+                .map(|kb| usize::try_from(kb.saturating_mul(1024)).unwrap_or(usize::MAX)),
+            sample_every: config.full_content_every.unwrap_or(1).max(1),
+            skip_tool_output: config.skip_tool_output.clone(),
+        }
+    }
+
+    /// Whether everything is stored
+    pub fn is_unrestricted(&self) -> bool {
+        self.max_body_bytes.is_none() && self.sample_every <= 1 && self.skip_tool_output.is_empty()
+    }
+
+    /// Whether the sample needs a span's sequence number to decide
+    pub fn is_sampling(&self) -> bool {
+        self.sample_every > 1
+    }
+
+    /// Whether a span gets its full content (sequence numbers start at 1)
+    pub fn samples(&self, sequence_num: i32) -> bool {
+        self.sample_every <= 1 || (sequence_num - 1).rem_euclid(self.sample_every as i32) == 0
+    }
+
+    /// Whether output of `tool_name` is left out
+    pub fn skips_tool(&self, tool_name: &str) -> bool {
+        // MCP content names tools "server/tool"; the API calls them mcp__server__tool
+        let mcp_name = tool_name
+            .split_once('/')
+            .map(|(server, tool)| format!("mcp__{}__{}", server, tool));
+        self.skip_tool_output
+            .iter()
+            .any(|t| t == tool_name || Some(t) == mcp_name.as_ref())
+    }
+
+    /// Whether to store one piece of content
+    ///
+    /// `sampled` is `samples()` for the span; `tool_name` is the tool that
+    /// produced it, when known.
+    pub fn keeps(
+        &self,
+        content_type: &str,
+        tool_name: Option<&str>,
+        len: usize,
+        sampled: bool,
+    ) -> bool {
+        if ALWAYS_KEPT.contains(&content_type) {
+            return true;
+        }
+        if !sampled {
+            return false;
+        }
+        if BODIES.contains(&content_type) {
+            return self.max_body_bytes.map_or(true, |max| len <= max);
+        }
+        if TOOL_OUTPUTS.contains(&content_type) {
+            return !tool_name.is_some_and(|t| self.skips_tool(t));
+        }
+        true
+    }
+
+    /// Whether tool outputs need their tool's name to decide
+    pub fn filters_tools(&self, content_type: &str) -> bool {
+        !self.skip_tool_output.is_empty() && TOOL_OUTPUTS.contains(&content_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter() -> CaptureFilter {
+        CaptureFilter::from_config(&CaptureConfig {
+            max_body_kb: Some(1),
+            full_content_every: Some(3),
+            skip_tool_output: vec!["Read".to_string(), "mcp__docs__search".to_string()],
+        })
+    }
+
+    #[test]
+    fn test_default_keeps_everything() {
+        let f = CaptureFilter::from_config(&CaptureConfig::default());
+        assert!(f.is_unrestricted());
+        assert!(f.samples(2));
+        assert!(f.keeps("response", None, 10_000_000, true));
+        assert!(f.keeps("tool_output", Some("Read"), 10, true));
+    }
+
+    #[test]
+    fn test_sampling() {
+        let f = filter();
+        let sampled: Vec<i32> = (1..=7).filter(|&n| f.samples(n)).collect();
+        assert_eq!(sampled, vec![1, 4, 7]);
+        assert!(!f.keeps("response", None, 10, false));
+        assert!(!f.keeps("tool_output", Some("Bash"), 10, false));
+        assert!(f.keeps("tool_input", Some("Bash"), 10_000, false));
+        assert!(f.keeps("mcp_call", Some("docs/search"), 10, false));
+    }
+
+    #[test]
+    fn test_size_and_tool_limits() {
+        let f = filter();
+        assert!(f.keeps("thinking", None, 1024, true));
+        assert!(!f.keeps("thinking", None, 1025, true));
+        // The size limit is for bodies, not tool output
+        assert!(f.keeps("tool_output", Some("Bash"), 1_000_000, true));
+        assert!(!f.keeps("tool_output", Some("Read"), 10, true));
+        assert!(!f.keeps("mcp_result", Some("docs/search"), 10, true));
+        assert!(f.keeps("tool_output", None, 10, true));
+        assert!(f.filters_tools("tool_error"));
+        assert!(!f.filters_tools("response"));
+    }
+}
@@ -0,0 +1,527 @@
+//! CI validation for decision graphs (`deciduous ci`)
+//!
+//! Checks the committed patch files in `.deciduous/patches/` the way a
+//! reviewer would: patches parse, edges resolve, node/edge types are known,
+//! only root goals are orphans, signatures check out, and the PR branch
+//! actually exported its nodes. Findings can be printed as GitHub Actions
+//! annotations.
+
+use crate::config::SyncConfig;
+use crate::db::DecisionNode;
+use crate::diff::{GraphPatch, PATCH_VERSION, UPDATABLE_FIELDS};
+use crate::signing::{verify, Provenance};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Node types deciduous knows about
+pub const NODE_TYPES: &[&str] = &[
+    "goal",
+    "decision",
+    "option",
+    "action",
+    "outcome",
+    "observation",
+];
+
+/// Edge types deciduous knows about
+pub const EDGE_TYPES: &[&str] = &[
+    "leads_to", "requires", "chosen", "rejected", "blocks", "enables",
+];
+
+/// How bad a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A single problem found during CI validation
+#[derive(Debug, Clone)]
+pub struct CiFinding {
+    pub severity: Severity,
+    /// Patch file the finding belongs to, if any
+    pub file: Option<PathBuf>,
+    pub message: String,
+}
+
+impl CiFinding {
+    pub fn error(file: Option<&Path>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            file: file.map(Path::to_path_buf),
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(file: Option<&Path>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            file: file.map(Path::to_path_buf),
+            message: message.into(),
+        }
+    }
+
+    /// Format as a GitHub Actions workflow command (`::error file=...::msg`)
+    pub fn annotation(&self) -> String {
+        let level = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let message = escape_data(&self.message);
+        match &self.file {
+            Some(file) => format!(
+                "::{} file={}::{}",
+                level,
+                escape_property(&file.to_string_lossy()),
+                message
+            ),
+            None => format!("::{}::{}", level, message),
+        }
+    }
+}
+
+/// Escape a workflow command message
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a workflow command property value
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Outcome of a CI run
+#[derive(Debug, Default)]
+pub struct CiReport {
+    pub findings: Vec<CiFinding>,
+    pub patches_checked: usize,
+    pub nodes_checked: usize,
+}
+
+impl CiReport {
+    pub fn errors(&self) -> usize {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == Severity::Error)
+            .count()
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == Severity::Warning)
+            .count()
+    }
+
+    /// Whether the build should pass (`strict` fails on warnings too)
+    pub fn passed(&self, strict: bool) -> bool {
+        self.errors() == 0 && (!strict || self.warnings() == 0)
+    }
+}
+
+/// Load every `*.json` patch in a directory, sorted by filename
+///
+/// Unparseable patches are reported as errors rather than aborting.
+pub fn load_patches(dir: &Path) -> (Vec<(PathBuf, GraphPatch)>, Vec<CiFinding>) {
+    let mut patches = Vec::new();
+    let mut findings = Vec::new();
+
+    let mut paths: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().map(|e| e == "json").unwrap_or(false))
+            .collect(),
+        Err(_) => return (patches, findings),
+    };
+    paths.sort();
+
+    for path in paths {
+        match GraphPatch::load(&path) {
+            Ok(patch) => patches.push((path, patch)),
+            Err(e) => findings.push(CiFinding::error(Some(path.as_path()), e)),
+        }
+    }
+
+    (patches, findings)
+}
+
+/// Validate one patch; `known` holds every change_id available to resolve edges
+pub fn validate_patch(path: &Path, patch: &GraphPatch, known: &HashSet<&str>) -> Vec<CiFinding> {
+    let mut findings = Vec::new();
+    let file = Some(path);
+
+    if patch.version != "1.0" && patch.version != PATCH_VERSION {
+        findings.push(CiFinding::warning(
+            file,
+            format!("Unknown patch version '{}'", patch.version),
+        ));
+    }
+
+    let mut seen = HashSet::new();
+    for node in &patch.nodes {
+        if !seen.insert(node.change_id.as_str()) {
+            findings.push(CiFinding::error(
+                file,
+                format!("Duplicate node change_id {}", short_id(&node.change_id)),
+            ));
+        }
+        if !NODE_TYPES.contains(&node.node_type.as_str()) {
+            findings.push(CiFinding::error(
+                file,
+                format!(
+                    "Node '{}' has unknown type '{}'",
+                    node.title, node.node_type
+                ),
+            ));
+        }
+        if let Some(meta) = &node.metadata_json {
+            match serde_json::from_str::<serde_json::Value>(meta) {
+                Ok(value) => {
+                    let confidence = value.get("confidence").and_then(|c| c.as_u64());
+                    if confidence.is_some_and(|c| c > 100) {
+                        findings.push(CiFinding::warning(
+                            file,
+                            format!("Node '{}' has confidence above 100", node.title),
+                        ));
+                    }
+                }
+                Err(e) => findings.push(CiFinding::error(
+                    file,
+                    format!("Node '{}' has invalid metadata JSON: {}", node.title, e),
+                )),
+            }
+        }
+    }
+
+    for update in &patch.updated_nodes {
+        for field in update.fields.keys() {
+            if !UPDATABLE_FIELDS.contains(&field.as_str()) {
+                findings.push(CiFinding::warning(
+                    file,
+                    format!(
+                        "Update to {} sets unknown field '{}'",
+                        short_id(&update.change_id),
+                        field
+                    ),
+                ));
+            }
+        }
+    }
+
+    for edge in &patch.edges {
+        if !EDGE_TYPES.contains(&edge.edge_type.as_str()) {
+            findings.push(CiFinding::warning(
+                file,
+                format!("Unknown edge type '{}'", edge.edge_type),
+            ));
+        }
+        for (end, change_id) in [("from", &edge.from_change_id), ("to", &edge.to_change_id)] {
+            if !known.contains(change_id.as_str()) {
+                findings.push(CiFinding::error(
+                    file,
+                    format!(
+                        "{} edge references missing node ({}: {})",
+                        edge.edge_type,
+                        end,
+                        short_id(change_id)
+                    ),
+                ));
+            }
+        }
+    }
+
+    findings
+}
+
+/// Check a patch's signature against `[sync]` settings
+///
+/// A bad signature is always an error; unsigned or untrusted patches are
+/// errors only when signatures are required.
+pub fn check_signature(path: &Path, patch: &GraphPatch, sync: &SyncConfig) -> Vec<CiFinding> {
+    let provenance = verify(patch, sync);
+    let message = format!("Patch is {}", provenance.describe());
+    if !provenance.acceptable(sync) {
+        vec![CiFinding::error(Some(path), message)]
+    } else if matches!(provenance, Provenance::Untrusted(_)) {
+        vec![CiFinding::warning(Some(path), message)]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Lint the combined graph: only root goals may lack a parent
+pub fn lint_graph(patches: &[(PathBuf, GraphPatch)]) -> Vec<CiFinding> {
+    let has_parent: HashSet<&str> = patches
+        .iter()
+        .flat_map(|(_, p)| p.edges.iter().map(|e| e.to_change_id.as_str()))
+        .collect();
+
+    let mut reported = HashSet::new();
+    let mut findings = Vec::new();
+    for (path, patch) in patches {
+        for node in &patch.nodes {
+            if node.node_type == "goal"
+                || has_parent.contains(node.change_id.as_str())
+                || !reported.insert(node.change_id.as_str())
+            {
+                continue;
+            }
+            findings.push(CiFinding::warning(
+                Some(path.as_path()),
+                format!(
+                    "Orphan {} '{}' has no incoming edge (only root goals may be orphans)",
+                    node.node_type, node.title
+                ),
+            ));
+        }
+    }
+
+    findings
+}
+
+/// Branch a node was created on, from its metadata
+fn node_branch(metadata_json: Option<&str>) -> Option<String> {
+    metadata_json
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        .and_then(|v| v.get("branch").and_then(|b| b.as_str()).map(String::from))
+}
+
+/// Check that `branch` has an exported patch covering its local nodes
+///
+/// `local_nodes` are nodes from the local database, when one exists (it's
+/// gitignored, so usually absent in CI).
+pub fn check_branch_exported(
+    branch: &str,
+    patches: &[(PathBuf, GraphPatch)],
+    local_nodes: &[DecisionNode],
+) -> Vec<CiFinding> {
+    let exported: HashSet<&str> = patches
+        .iter()
+        .flat_map(|(_, p)| p.nodes.iter().map(|n| n.change_id.as_str()))
+        .collect();
+
+    let branch_has_patch = patches.iter().any(|(_, p)| {
+        p.branch.as_deref() == Some(branch)
+            || p.nodes
+                .iter()
+                .any(|n| node_branch(n.metadata_json.as_deref()).as_deref() == Some(branch))
+    });
+
+    let mut findings = Vec::new();
+    if !branch_has_patch {
+        findings.push(CiFinding::error(
+            None,
+            format!(
+                "No decision graph patch for branch '{}'. Run: deciduous diff export --branch {} -o .deciduous/patches/{}.json",
+                branch,
+                branch,
+                branch.replace('/', "-")
+            ),
+        ));
+    }
+
+    for node in local_nodes {
+        if node_branch(node.metadata_json.as_deref()).as_deref() != Some(branch) {
+            continue;
+        }
+        if !exported.contains(node.change_id.as_str()) {
+            findings.push(CiFinding::error(
+                None,
+                format!(
+                    "Node {} '{}' on branch '{}' is not in any patch",
+                    node.id, node.title, branch
+                ),
+            ));
+        }
+    }
+
+    findings
+}
+
+/// Run every check and collect a report
+///
+/// `branch` is skipped for the export check when `None` (e.g. on main).
+pub fn run_checks(
+    patches_dir: &Path,
+    branch: Option<&str>,
+    local_nodes: &[DecisionNode],
+    sync: &SyncConfig,
+) -> CiReport {
+    let (patches, mut findings) = load_patches(patches_dir);
+
+    let known: HashSet<&str> = patches
+        .iter()
+        .flat_map(|(_, p)| p.nodes.iter().map(|n| n.change_id.as_str()))
+        .chain(local_nodes.iter().map(|n| n.change_id.as_str()))
+        .collect();
+
+    for (path, patch) in &patches {
+        findings.extend(validate_patch(path, patch, &known));
+        findings.extend(check_signature(path, patch, sync));
+    }
+    findings.extend(lint_graph(&patches));
+
+    if let Some(branch) = branch {
+        findings.extend(check_branch_exported(branch, &patches, local_nodes));
+    }
+
+    CiReport {
+        nodes_checked: known.len(),
+        patches_checked: patches.len(),
+        findings,
+    }
+}
+
+fn short_id(change_id: &str) -> &str {
+    &change_id[..8.min(change_id.len())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::{PatchEdge, PatchNode};
+
+    fn node(change_id: &str, node_type: &str, branch: Option<&str>) -> PatchNode {
+        PatchNode {
+            change_id: change_id.to_string(),
+            node_type: node_type.to_string(),
+            title: format!("{} {}", node_type, change_id),
+            description: None,
+            status: "pending".to_string(),
+            metadata_json: branch.map(|b| format!(r#"{{"branch":"{}"}}"#, b)),
+            created_at: "2025-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    fn edge(from: &str, to: &str) -> PatchEdge {
+        PatchEdge {
+            from_change_id: from.to_string(),
+            to_change_id: to.to_string(),
+            edge_type: "leads_to".to_string(),
+            rationale: None,
+        }
+    }
+
+    fn patch(branch: Option<&str>, nodes: Vec<PatchNode>, edges: Vec<PatchEdge>) -> GraphPatch {
+        let mut p = GraphPatch::new(None, branch.map(String::from), None);
+        p.nodes = nodes;
+        p.edges = edges;
+        p
+    }
+
+    #[test]
+    fn test_annotation_format() {
+        let finding = CiFinding::error(Some(Path::new("a,b.json")), "line1\nline2 100%");
+        assert_eq!(
+            finding.annotation(),
+            "::error file=a%2Cb.json::line1%0Aline2 100%25"
+        );
+        assert_eq!(CiFinding::warning(None, "x").annotation(), "::warning::x");
+    }
+
+    #[test]
+    fn test_validate_patch_missing_refs_and_types() {
+        let p = patch(
+            None,
+            vec![node("g1", "goal", None), node("x1", "thing", None)],
+            vec![edge("g1", "missing")],
+        );
+        let known: HashSet<&str> = ["g1", "x1"].into_iter().collect();
+        let findings = validate_patch(Path::new("p.json"), &p, &known);
+
+        assert_eq!(findings.len(), 2);
+        assert!(findings.iter().all(|f| f.severity == Severity::Error));
+        assert!(findings.iter().any(|f| f.message.contains("unknown type")));
+        assert!(findings.iter().any(|f| f.message.contains("missing node")));
+    }
+
+    #[test]
+    fn test_lint_graph_orphans() {
+        let patches = vec![(
+            PathBuf::from("p.json"),
+            patch(
+                None,
+                vec![
+                    node("g1", "goal", None),
+                    node("a1", "action", None),
+                    node("o1", "outcome", None),
+                ],
+                vec![edge("g1", "a1")],
+            ),
+        )];
+        let findings = lint_graph(&patches);
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].message.contains("outcome"));
+    }
+
+    #[test]
+    fn test_check_branch_exported() {
+        let patches = vec![(
+            PathBuf::from("feature.json"),
+            patch(None, vec![node("g1", "goal", Some("feature-x"))], vec![]),
+        )];
+        assert!(check_branch_exported("feature-x", &patches, &[]).is_empty());
+
+        let missing = check_branch_exported("other", &patches, &[]);
+        assert_eq!(missing.len(), 1);
+        assert!(missing[0].message.contains("No decision graph patch"));
+
+        let local = DecisionNode {
+            id: 5,
+            change_id: "unexported".to_string(),
+            node_type: "action".into(),
+            title: "Local only".to_string(),
+            description: None,
+            status: "pending".into(),
+            created_at: String::new(),
+            updated_at: String::new(),
+            metadata_json: Some(r#"{"branch":"feature-x"}"#.to_string()),
+        };
+        let findings = check_branch_exported("feature-x", &patches, &[local]);
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].message.contains("not in any patch"));
+    }
+
+    #[test]
+    #[cfg(feature = "signing")]
+    fn test_check_signature() {
+        let path = Path::new("p.json");
+        let mut sync = SyncConfig::default();
+        let mut p = patch(None, vec![node("g1", "goal", None)], vec![]);
+        assert!(check_signature(path, &p, &sync).is_empty());
+
+        let dir = tempfile::tempdir().unwrap();
+        let key = crate::signing::SigningKey::load_or_create(&dir.path().join("key.pk8")).unwrap();
+        key.sign(&mut p).unwrap();
+        let findings = check_signature(path, &p, &sync);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Warning);
+
+        sync.require_signatures = true;
+        assert_eq!(
+            check_signature(path, &p, &sync)[0].severity,
+            Severity::Error
+        );
+        sync.trusted_keys
+            .insert("alice".to_string(), key.public_key_hex());
+        assert!(check_signature(path, &p, &sync).is_empty());
+
+        p.nodes[0].title = "Changed".to_string();
+        assert_eq!(
+            check_signature(path, &p, &sync)[0].severity,
+            Severity::Error
+        );
+    }
+
+    #[test]
+    fn test_report_passed() {
+        let mut report = CiReport::default();
+        assert!(report.passed(true));
+        report.findings.push(CiFinding::warning(None, "w"));
+        assert!(report.passed(false));
+        assert!(!report.passed(true));
+    }
+}
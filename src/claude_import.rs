@@ -0,0 +1,309 @@
+//! Import Claude Code's local session transcripts as trace sessions
+//!
+//! Claude Code keeps every session as JSONL under
+//! `~/.claude/projects/<project>/<session>.jsonl`: one line per user message,
+//! tool result batch, or assistant content block. Lines belonging to one API
+//! response share `message.id`, so each distinct id becomes a span, timed
+//! from the user line that preceded it to its last block. Tool results are
+//! stored on the span whose request carried them, as the interceptor does.
+
+use crate::db::Database;
+use crate::mcp_trace::result_text;
+use crate::proxy::{is_injected, parse_response, record_span, Api, SpanCapture};
+use crate::redact::{store_content, Redactor};
+use chrono::DateTime;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Claude Code's placeholder model for messages it generated locally
+const SYNTHETIC_MODEL: &str = "<synthetic>";
+
+/// Command recorded on imported sessions
+pub const IMPORTED_COMMAND: &str = "claude (imported)";
+
+/// A tool result sent with a span's request
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolResult {
+    pub tool_use_id: String,
+    pub content: String,
+    pub is_error: bool,
+}
+
+/// One API response rebuilt from transcript lines
+#[derive(Debug, Clone)]
+pub struct ImportedSpan {
+    pub capture: SpanCapture,
+    pub started_at: String,
+    pub completed_at: String,
+    pub tool_results: Vec<ToolResult>,
+}
+
+/// A transcript parsed into a session
+#[derive(Debug, Clone, Default)]
+pub struct ImportedSession {
+    pub session_id: String,
+    pub working_dir: Option<String>,
+    pub git_branch: Option<String>,
+    pub summary: Option<String>,
+    pub started_at: Option<String>,
+    pub ended_at: Option<String>,
+    pub spans: Vec<ImportedSpan>,
+}
+
+/// Transcript files under `path` (a `.jsonl` file or a directory of them)
+pub fn find_transcripts(path: &Path) -> Vec<PathBuf> {
+    if path.is_file() {
+        return vec![path.to_path_buf()];
+    }
+    let mut files = Vec::new();
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return files;
+    };
+    for entry in entries.flatten() {
+        let p = entry.path();
+        if p.is_dir() {
+            files.extend(find_transcripts(&p));
+        } else if p.extension().is_some_and(|e| e == "jsonl") {
+            files.push(p);
+        }
+    }
+    files.sort();
+    files
+}
+
+fn user_text(content: &Value) -> Option<String> {
+    let text = match content {
+        Value::String(s) => s.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter(|b| b["type"] == "text")
+            .filter_map(|b| b["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => return None,
+    };
+    let text = text.trim();
+    (!text.is_empty() && !is_injected(text)).then(|| text.chars().take(500).collect())
+}
+
+/// Parse a transcript; `session_id` names the imported session
+pub fn parse_transcript(session_id: &str, text: &str) -> ImportedSession {
+    let mut session = ImportedSession {
+        session_id: session_id.to_string(),
+        ..Default::default()
+    };
+    let mut by_message: HashMap<String, usize> = HashMap::new();
+    let mut user_preview: Option<String> = None;
+    let mut request_time: Option<String> = None;
+    let mut pending: Vec<ToolResult> = Vec::new();
+
+    for line in text.lines() {
+        let Ok(entry) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if entry["type"] == "summary" {
+            session.summary = entry["summary"].as_str().map(str::to_string);
+            continue;
+        }
+        let Some(timestamp) = entry["timestamp"].as_str() else {
+            continue;
+        };
+        if session.working_dir.is_none() {
+            session.working_dir = entry["cwd"].as_str().map(str::to_string);
+        }
+        if session.git_branch.is_none() {
+            session.git_branch = entry["gitBranch"]
+                .as_str()
+                .filter(|b| !b.is_empty())
+                .map(str::to_string);
+        }
+        session
+            .started_at
+            .get_or_insert_with(|| timestamp.to_string());
+        session.ended_at = Some(timestamp.to_string());
+
+        let message = &entry["message"];
+        match entry["type"].as_str() {
+            Some("user") => {
+                request_time = Some(timestamp.to_string());
+                for block in message["content"].as_array().into_iter().flatten() {
+                    if block["type"] == "tool_result" {
+                        pending.push(ToolResult {
+                            tool_use_id: block["tool_use_id"].as_str().unwrap_or("").to_string(),
+                            content: result_text(&block["content"]),
+                            is_error: block["is_error"].as_bool().unwrap_or(false),
+                        });
+                    }
+                }
+                if entry["isMeta"] != true {
+                    if let Some(text) = user_text(&message["content"]) {
+                        user_preview = Some(text);
+                    }
+                }
+            }
+            Some("assistant") => {
+                if message["model"] == SYNTHETIC_MODEL {
+                    continue;
+                }
+                let id = message["id"]
+                    .as_str()
+                    .or(entry["uuid"].as_str())
+                    .unwrap_or("")
+                    .to_string();
+                let index = *by_message.entry(id).or_insert_with(|| {
+                    session.spans.push(ImportedSpan {
+                        capture: SpanCapture {
+                            user_preview: user_preview.clone(),
+                            ..Default::default()
+                        },
+                        started_at: request_time
+                            .clone()
+                            .unwrap_or_else(|| timestamp.to_string()),
+                        completed_at: timestamp.to_string(),
+                        tool_results: std::mem::take(&mut pending),
+                    });
+                    session.spans.len() - 1
+                });
+
+                let span = &mut session.spans[index];
+                span.completed_at = timestamp.to_string();
+                // Blocks of one response arrive on separate lines, most
+                // without a stop reason
+                let stop_reason = span.capture.stop_reason.take();
+                parse_response(Api::Anthropic, &message.to_string(), &mut span.capture);
+                if span.capture.stop_reason.is_none() {
+                    span.capture.stop_reason = stop_reason;
+                }
+                if let Some(request_id) = entry["requestId"].as_str() {
+                    span.capture.request_id = Some(request_id.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Results nothing was sent after (the session ended on a tool call)
+    if let Some(last) = session.spans.last_mut() {
+        last.tool_results.append(&mut pending);
+    }
+    session
+}
+
+fn millis_between(start: &str, end: &str) -> i32 {
+    match (
+        DateTime::parse_from_rfc3339(start),
+        DateTime::parse_from_rfc3339(end),
+    ) {
+        (Ok(s), Ok(e)) => (e - s).num_milliseconds().clamp(0, i64::from(i32::MAX)) as i32,
+        _ => 0,
+    }
+}
+
+/// Store a parsed transcript; returns the span count, or None if the
+/// session was already imported
+pub fn import_session(
+    db: &Database,
+    redactor: &Redactor,
+    session: &ImportedSession,
+) -> crate::db::Result<Option<usize>> {
+    if db.get_trace_session(&session.session_id)?.is_some() {
+        return Ok(None);
+    }
+    db.start_trace_session(
+        &session.session_id,
+        session.working_dir.as_deref(),
+        session.git_branch.as_deref(),
+        Some(IMPORTED_COMMAND),
+    )?;
+
+    for span in &session.spans {
+        let duration = millis_between(&span.started_at, &span.completed_at);
+        let span_id = record_span(db, redactor, &session.session_id, &span.capture, duration)?;
+        db.set_trace_span_times(span_id, &span.started_at, &span.completed_at)?;
+        for result in &span.tool_results {
+            let content_type = if result.is_error {
+                "tool_error"
+            } else {
+                "tool_output"
+            };
+            store_content(
+                db,
+                redactor,
+                span_id,
+                content_type,
+                &result.content,
+                None,
+                Some(&result.tool_use_id),
+            )?;
+        }
+    }
+
+    db.end_trace_session(&session.session_id, session.summary.as_deref())?;
+    if let (Some(started), Some(ended)) = (&session.started_at, &session.ended_at) {
+        db.set_trace_session_times(&session.session_id, started, ended)?;
+    }
+    Ok(Some(session.spans.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSCRIPT: &str = r#"{"type":"summary","summary":"Fix the parser"}
+{"type":"user","timestamp":"2025-06-01T10:00:00.000Z","cwd":"/repo","gitBranch":"fix-parser","message":{"role":"user","content":"Fix the parser"}}
+{"type":"assistant","timestamp":"2025-06-01T10:00:02.000Z","requestId":"req_1","message":{"id":"msg_1","model":"claude-sonnet-4","content":[{"type":"thinking","thinking":"Look first."}],"stop_reason":null,"usage":{"input_tokens":10,"output_tokens":1}}}
+{"type":"assistant","timestamp":"2025-06-01T10:00:03.500Z","requestId":"req_1","message":{"id":"msg_1","model":"claude-sonnet-4","content":[{"type":"tool_use","id":"tu_1","name":"Bash","input":{"command":"ls"}}],"stop_reason":"tool_use","usage":{"input_tokens":10,"output_tokens":25}}}
+{"type":"user","timestamp":"2025-06-01T10:00:04.000Z","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"tu_1","content":"parser.rs","is_error":false}]}}
+{"type":"assistant","timestamp":"2025-06-01T10:00:06.000Z","message":{"id":"msg_2","model":"claude-sonnet-4","content":[{"type":"text","text":"Done."}],"stop_reason":null,"usage":{"input_tokens":40,"output_tokens":3}}}
+{"type":"assistant","timestamp":"2025-06-01T10:00:07.000Z","message":{"id":"msg_3","model":"<synthetic>","content":[{"type":"text","text":"No response requested."}]}}
+not json
+"#;
+
+    #[test]
+    fn test_parse_transcript() {
+        let session = parse_transcript("abc", TRANSCRIPT);
+        assert_eq!(session.summary.as_deref(), Some("Fix the parser"));
+        assert_eq!(session.working_dir.as_deref(), Some("/repo"));
+        assert_eq!(session.git_branch.as_deref(), Some("fix-parser"));
+        assert_eq!(session.spans.len(), 2);
+
+        let first = &session.spans[0];
+        assert_eq!(first.capture.thinking, "Look first.");
+        assert_eq!(first.capture.tool_calls[0].name.as_deref(), Some("Bash"));
+        assert_eq!(first.capture.output_tokens, Some(25));
+        assert_eq!(first.capture.stop_reason.as_deref(), Some("tool_use"));
+        assert_eq!(first.capture.request_id.as_deref(), Some("req_1"));
+        assert_eq!(
+            first.capture.user_preview.as_deref(),
+            Some("Fix the parser")
+        );
+        assert_eq!(millis_between(&first.started_at, &first.completed_at), 3500);
+
+        let second = &session.spans[1];
+        assert_eq!(second.capture.response, "Done.");
+        assert_eq!(second.tool_results[0].tool_use_id, "tu_1");
+        assert_eq!(second.tool_results[0].content, "parser.rs");
+        assert_eq!(second.started_at, "2025-06-01T10:00:04.000Z");
+    }
+
+    #[test]
+    fn test_import_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let session = parse_transcript("abc", TRANSCRIPT);
+        let redactor = Redactor::default();
+
+        assert_eq!(import_session(&db, &redactor, &session).unwrap(), Some(2));
+        assert_eq!(import_session(&db, &redactor, &session).unwrap(), None);
+
+        let trace = db.get_trace_session("abc").unwrap().unwrap();
+        assert_eq!(trace.started_at, "2025-06-01T10:00:00.000Z");
+        assert_eq!(trace.total_output_tokens, 28);
+        let spans = db.get_trace_spans("abc").unwrap();
+        assert_eq!(spans[0].duration_ms, Some(3500));
+        assert_eq!(spans[0].started_at, "2025-06-01T10:00:00.000Z");
+        let content = db.get_trace_content(spans[1].id).unwrap();
+        assert!(content.iter().any(|c| c.content_type == "tool_output"));
+    }
+}
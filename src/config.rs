@@ -3,9 +3,13 @@
 //! Reads from .deciduous/config.toml
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 /// Configuration structure
+///
+/// The config is embedded in exported graph data, so secret fields
+/// (`sync.token`, `github.webhook_secret`) are read but never serialized.
 #[derive(Debug, Deserialize, Serialize, Default, Clone)]
 pub struct Config {
     /// Branch settings
@@ -15,16 +19,696 @@ pub struct Config {
     /// GitHub settings for external repository references
     #[serde(default)]
     pub github: GithubConfig,
+
+    /// Forge settings (GitHub, GitLab or Bitbucket) for roadmap and issue sync
+    #[serde(default)]
+    pub forge: ForgeConfig,
+
+    /// API trace settings (model pricing, budgets, redaction)
+    #[serde(default)]
+    pub trace: TraceConfig,
+
+    /// Patch sharing settings (signature verification)
+    #[serde(default)]
+    pub sync: SyncConfig,
+
+    /// Who you are, for patch authorship (default: git user.name)
+    #[serde(default)]
+    pub identity: IdentityConfig,
+
+    /// Team roster, keyed by handle, for display names and signing keys
+    #[serde(default)]
+    pub team: BTreeMap<String, TeamMember>,
+
+    /// Shell commands run on graph events, with a JSON payload on stdin
+    #[serde(default)]
+    pub hooks: HooksConfig,
+
+    /// Per-type default confidence and required fields for `add` and `link`
+    #[serde(default)]
+    pub rules: RulesConfig,
+
+    /// Named `deciduous sync` destinations (`[export.profiles.<name>]`)
+    #[serde(default)]
+    pub export: ExportConfig,
+
+    /// Embedding model behind `deciduous search --semantic`
+    #[serde(default)]
+    pub embeddings: EmbeddingsConfig,
+
+    /// Monorepo sub-projects, recorded on nodes by `deciduous add`
+    #[serde(default)]
+    pub projects: ProjectsConfig,
+
+    /// When `deciduous stale` flags decisions and actions as stuck
+    #[serde(default)]
+    pub stale: StaleConfig,
+
+    /// The Notion database `deciduous sync --notion` mirrors nodes into
+    #[serde(default)]
+    pub notion: NotionConfig,
 }
 
-/// GitHub-related configuration for commit/PR links
+/// Where `deciduous sync --notion` mirrors nodes, and how
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct NotionConfig {
+    /// ID of the Notion database (from its URL)
+    #[serde(default)]
+    pub database_id: Option<String>,
+
+    /// Environment variable holding the integration token
+    #[serde(default = "default_notion_token_env")]
+    pub token_env: String,
+
+    /// Node types to mirror
+    #[serde(default = "default_notion_node_types")]
+    pub node_types: Vec<String>,
+
+    /// Database property names for node fields
+    #[serde(default)]
+    pub properties: NotionProperties,
+}
+
+fn default_notion_token_env() -> String {
+    "NOTION_TOKEN".to_string()
+}
+
+fn default_notion_node_types() -> Vec<String> {
+    ["goal", "decision", "outcome"].map(String::from).to_vec()
+}
+
+impl Default for NotionConfig {
+    fn default() -> Self {
+        Self {
+            database_id: None,
+            token_env: default_notion_token_env(),
+            node_types: default_notion_node_types(),
+            properties: NotionProperties::default(),
+        }
+    }
+}
+
+/// Notion property names for each node field; an empty name leaves the
+/// field out. `title` must be the database's title property, and
+/// `change_id` a text property pages are matched by.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct NotionProperties {
+    pub title: String,
+    pub change_id: String,
+    /// Select
+    pub node_type: String,
+    /// Select
+    pub status: String,
+    /// Number
+    pub confidence: String,
+    /// Text
+    pub branch: String,
+    /// Text, cut to Notion's 2000 characters
+    pub description: String,
+}
+
+impl Default for NotionProperties {
+    fn default() -> Self {
+        Self {
+            title: "Name".to_string(),
+            change_id: "Deciduous ID".to_string(),
+            node_type: "Type".to_string(),
+            status: "Status".to_string(),
+            confidence: "Confidence".to_string(),
+            branch: "Branch".to_string(),
+            description: String::new(),
+        }
+    }
+}
+
+/// Thresholds for `deciduous stale` and `audit --stale`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct StaleConfig {
+    /// Days a decision may go without a chosen option
+    #[serde(default = "default_decision_days")]
+    pub decision_days: i64,
+
+    /// Days an action may stay pending or active
+    #[serde(default = "default_action_days")]
+    pub action_days: i64,
+}
+
+fn default_decision_days() -> i64 {
+    7
+}
+
+fn default_action_days() -> i64 {
+    3
+}
+
+impl Default for StaleConfig {
+    fn default() -> Self {
+        Self {
+            decision_days: default_decision_days(),
+            action_days: default_action_days(),
+        }
+    }
+}
+
+/// Which sub-project a directory of a monorepo belongs to
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+pub struct ProjectsConfig {
+    /// Repo-relative directory to project name (e.g. "services/billing" = "billing");
+    /// the longest directory containing the working directory wins
+    #[serde(default)]
+    pub paths: BTreeMap<String, String>,
+
+    /// Outside the mapped directories, name the project after the top-level
+    /// directory the command runs in
+    #[serde(default)]
+    pub auto_detect: bool,
+}
+
+/// Where node embeddings come from
+/// Any OpenAI-compatible `/v1/embeddings` endpoint works, including a local
+/// model served by Ollama or llama.cpp.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct EmbeddingsConfig {
+    /// Endpoint URL (e.g. "http://localhost:11434/v1/embeddings" for Ollama)
+    #[serde(default = "default_embeddings_url")]
+    pub url: String,
+
+    /// Model name sent to the endpoint; vectors are stored per model
+    #[serde(default = "default_embeddings_model")]
+    pub model: String,
+
+    /// Environment variable holding the API key; no key is sent if it's unset
+    #[serde(default = "default_embeddings_key_env")]
+    pub api_key_env: String,
+}
+
+fn default_embeddings_url() -> String {
+    "https://api.openai.com/v1/embeddings".to_string()
+}
+
+fn default_embeddings_model() -> String {
+    "text-embedding-3-small".to_string()
+}
+
+fn default_embeddings_key_env() -> String {
+    "OPENAI_API_KEY".to_string()
+}
+
+impl Default for EmbeddingsConfig {
+    fn default() -> Self {
+        Self {
+            url: default_embeddings_url(),
+            model: default_embeddings_model(),
+            api_key_env: default_embeddings_key_env(),
+        }
+    }
+}
+
+/// Sections `deciduous sync` can write next to the graph JSON
+pub const EXPORT_SECTIONS: &[&str] = &["roadmap", "git_history"];
+
+/// Export profiles, selected with `deciduous sync --profile <name>`
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+pub struct ExportConfig {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ExportProfile>,
+}
+
+impl ExportConfig {
+    /// Look up a profile, checking its sections
+    pub fn profile(&self, name: &str) -> Result<ExportProfile, String> {
+        let Some(profile) = self.profiles.get(name) else {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            return Err(if known.is_empty() {
+                format!(
+                    "No export profile `{}`; add [export.profiles.{}] to .deciduous/config.toml",
+                    name, name
+                )
+            } else {
+                format!(
+                    "No export profile `{}` (configured: {})",
+                    name,
+                    known.join(", ")
+                )
+            });
+        };
+        if let Some(unknown) = profile
+            .include
+            .iter()
+            .find(|s| !EXPORT_SECTIONS.contains(&s.as_str()))
+        {
+            return Err(format!(
+                "[export.profiles.{}] includes unknown section `{}` (known: {})",
+                name,
+                unknown,
+                EXPORT_SECTIONS.join(", ")
+            ));
+        }
+        Ok(profile.clone())
+    }
+}
+
+/// Where `deciduous sync` writes and what it leaves out
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ExportProfile {
+    /// Graph JSON path; the other sections are written next to it
+    #[serde(default = "default_export_output")]
+    pub output: String,
+
+    /// Directories that get a copy of every file written (e.g. "docs/demo")
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+
+    /// Sections written besides the graph (see `EXPORT_SECTIONS`)
+    #[serde(default = "default_export_sections")]
+    pub include: Vec<String>,
+
+    /// Run the `[trace.redact]` rules over node text before writing
+    #[serde(default)]
+    pub redact: bool,
+
+    /// Leave captured prompts out of node metadata
+    #[serde(default)]
+    pub strip_prompts: bool,
+}
+
+fn default_export_output() -> String {
+    "docs/graph-data.json".to_string()
+}
+
+fn default_export_sections() -> Vec<String> {
+    EXPORT_SECTIONS.iter().map(|s| s.to_string()).collect()
+}
+
+impl Default for ExportProfile {
+    fn default() -> Self {
+        Self {
+            output: default_export_output(),
+            mirrors: Vec::new(),
+            include: default_export_sections(),
+            redact: false,
+            strip_prompts: false,
+        }
+    }
+}
+
+impl ExportProfile {
+    pub fn includes(&self, section: &str) -> bool {
+        self.include.iter().any(|s| s == section)
+    }
+}
+
+/// Node fields `[rules.require]` can ask for, with the `add` flag that sets each
+pub const NODE_FIELDS: &[(&str, &str)] = &[
+    ("prompt", "--prompt or --prompt-stdin"),
+    ("description", "--description"),
+    ("confidence", "--confidence"),
+    ("commit", "--commit"),
+    ("files", "--files"),
+    ("branch", "--branch"),
+    ("pr", "--pr"),
+];
+
+/// Edge fields `[rules.edge_require]` can ask for, with the `link` flag
+pub const EDGE_FIELDS: &[(&str, &str)] = &[("rationale", "--rationale")];
+
+/// Conventions for new nodes and edges, enforced by `add` and `link`
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+pub struct RulesConfig {
+    /// Confidence used when `add` gets no `-c`, by node type (e.g. goal = 90)
+    #[serde(default)]
+    pub confidence: BTreeMap<String, u8>,
+
+    /// Fields each node type must have (e.g. goal = ["prompt"])
+    #[serde(default)]
+    pub require: BTreeMap<String, Vec<String>>,
+
+    /// Fields each edge type must have (e.g. rejected = ["rationale"])
+    #[serde(default)]
+    pub edge_require: BTreeMap<String, Vec<String>>,
+}
+
+impl RulesConfig {
+    /// Confidence for a new node of this type when none was given
+    pub fn default_confidence(&self, node_type: &str) -> Option<u8> {
+        self.confidence.get(node_type).copied()
+    }
+
+    /// Check a new node against `[rules.require]`; `present` lists the
+    /// fields it has (names from `NODE_FIELDS`)
+    pub fn check_node(&self, node_type: &str, present: &[&str]) -> Result<(), String> {
+        check_required(
+            &format!("{} nodes", node_type),
+            "rules.require",
+            self.require.get(node_type),
+            NODE_FIELDS,
+            present,
+        )
+    }
+
+    /// Check a new edge against `[rules.edge_require]`
+    pub fn check_edge(&self, edge_type: &str, present: &[&str]) -> Result<(), String> {
+        check_required(
+            &format!("{} edges", edge_type),
+            "rules.edge_require",
+            self.edge_require.get(edge_type),
+            EDGE_FIELDS,
+            present,
+        )
+    }
+}
+
+fn check_required(
+    what: &str,
+    section: &str,
+    required: Option<&Vec<String>>,
+    fields: &[(&str, &str)],
+    present: &[&str],
+) -> Result<(), String> {
+    let mut missing = Vec::new();
+    for field in required.into_iter().flatten() {
+        let Some((_, flag)) = fields.iter().find(|(name, _)| *name == field.as_str()) else {
+            let known: Vec<&str> = fields.iter().map(|(name, _)| *name).collect();
+            return Err(format!(
+                "[{}] lists unknown field `{}` (known: {})",
+                section,
+                field,
+                known.join(", ")
+            ));
+        };
+        if !present.contains(&field.as_str()) {
+            missing.push(format!("{} ({})", field, flag));
+        }
+    }
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} require {} (see [{}] in .deciduous/config.toml)",
+            what,
+            missing.join(", "),
+            section
+        ))
+    }
+}
+
+/// Lifecycle hooks (see `lifecycle`)
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+pub struct HooksConfig {
+    /// After `deciduous add` creates a node
+    #[serde(default)]
+    pub on_node_add: Option<String>,
+
+    /// After `deciduous sync` writes the graph export
+    #[serde(default)]
+    pub on_sync: Option<String>,
+
+    /// After a trace session ends
+    #[serde(default)]
+    pub on_session_end: Option<String>,
+
+    /// When `deciduous stale --notify` finds stuck decisions or actions
+    #[serde(default)]
+    pub on_stale: Option<String>,
+}
+
+impl HooksConfig {
+    /// The command configured for an event, if any
+    pub fn command_for(&self, event: &str) -> Option<&str> {
+        let command = match event {
+            crate::lifecycle::ON_NODE_ADD => &self.on_node_add,
+            crate::lifecycle::ON_SYNC => &self.on_sync,
+            crate::lifecycle::ON_SESSION_END => &self.on_session_end,
+            crate::lifecycle::ON_STALE => &self.on_stale,
+            _ => &None,
+        };
+        command.as_deref().filter(|c| !c.trim().is_empty())
+    }
+}
+
+/// The local user's identity
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+pub struct IdentityConfig {
+    /// Name written as the author of exported patches
+    #[serde(default)]
+    pub name: Option<String>,
+
+    #[serde(default)]
+    pub email: Option<String>,
+
+    /// Patch signing key file (default: ~/.deciduous/signing-key.pk8)
+    #[serde(default)]
+    pub key: Option<String>,
+}
+
+/// A teammate in the `[team.<handle>]` roster
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+pub struct TeamMember {
+    /// Display name (default: the handle)
+    #[serde(default)]
+    pub name: Option<String>,
+
+    #[serde(default)]
+    pub email: Option<String>,
+
+    /// Patch signing public key, hex; patches it signs are trusted
+    #[serde(default)]
+    pub key: Option<String>,
+
+    /// Other spellings seen in patch authors and commits (e.g. git user.name)
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+impl TeamMember {
+    /// Whether a free-form author string refers to this member
+    fn matches(&self, handle: &str, who: &str) -> bool {
+        let same = |s: &str| s.trim().eq_ignore_ascii_case(who);
+        same(handle)
+            || self.name.as_deref().is_some_and(same)
+            || self.email.as_deref().is_some_and(same)
+            || self.aliases.iter().any(|a| same(a))
+    }
+}
+
+/// Patch sharing configuration
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+pub struct SyncConfig {
+    /// Public keys (hex) whose patch signatures are trusted, keyed by owner
+    /// Print yours with `deciduous diff key`.
+    #[serde(default)]
+    pub trusted_keys: BTreeMap<String, String>,
+
+    /// Refuse patches that aren't signed by a trusted key (default: only warn)
+    #[serde(default)]
+    pub require_signatures: bool,
+
+    /// Patch hub (`deciduous serve --sync`) used by `push` and `pull`
+    #[serde(default)]
+    pub server: Option<String>,
+
+    /// Bearer token the hub requires and clients send
+    /// Prefer the DECIDUOUS_SYNC_TOKEN environment variable.
+    #[serde(default, skip_serializing)]
+    pub token: Option<String>,
+}
+
+/// API trace configuration
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct TraceConfig {
+    /// Per-model prices, keyed by model name or prefix (e.g. "claude-sonnet-4")
+    /// Entries override or extend the built-in price table.
+    #[serde(default)]
+    pub pricing: BTreeMap<String, ModelPrice>,
+
+    /// Usage limits checked by the proxy after each span
+    #[serde(default)]
+    pub budget: BudgetConfig,
+
+    /// Secret redaction applied before trace content is stored
+    #[serde(default)]
+    pub redact: RedactConfig,
+
+    /// Automatic pruning, applied when a proxy session ends
+    #[serde(default)]
+    pub retention: RetentionConfig,
+
+    /// Limits on how much span content is stored
+    #[serde(default)]
+    pub capture: CaptureConfig,
+}
+
+/// What full span content is stored
+/// Span metadata and previews are always kept; by default so is all content.
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+pub struct CaptureConfig {
+    /// Don't store thinking or response bodies larger than this
+    #[serde(default)]
+    pub max_body_kb: Option<u64>,
+
+    /// Store full content for every Nth span only (1 = every span)
+    /// Other spans keep their tool calls but not bodies or tool results.
+    #[serde(default)]
+    pub full_content_every: Option<u32>,
+
+    /// Tools whose output isn't stored (e.g. "Read", "Glob")
+    #[serde(default)]
+    pub skip_tool_output: Vec<String>,
+}
+
+/// How long trace data is kept
+/// With neither limit set, nothing is pruned automatically.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct RetentionConfig {
+    /// Delete sessions started more than this many days ago
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+
+    /// Delete the oldest ended sessions until the database's used space fits
+    #[serde(default)]
+    pub max_db_size_mb: Option<u64>,
+
+    /// Never delete sessions linked to a decision node (default: true)
+    #[serde(default = "default_true")]
+    pub keep_linked: bool,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_age_days: None,
+            max_db_size_mb: None,
+            keep_linked: true,
+        }
+    }
+}
+
+impl RetentionConfig {
+    /// Whether any limit is set
+    pub fn is_enabled(&self) -> bool {
+        self.max_age_days.is_some() || self.max_db_size_mb.is_some()
+    }
+}
+
+/// Secret redaction for captured trace content
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct RedactConfig {
+    /// Redact before storing (default: true)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Apply the built-in patterns for API keys, tokens and private keys
+    /// Default: true
+    #[serde(default = "default_true")]
+    pub builtin: bool,
+
+    /// Environment variables whose values are redacted wherever they appear,
+    /// in addition to the usual credential variables (ANTHROPIC_API_KEY, ...)
+    #[serde(default)]
+    pub env_vars: Vec<String>,
+
+    /// Custom patterns, keyed by a name recorded with each redaction
+    /// A capture group named `secret` limits the redaction to that group.
+    #[serde(default)]
+    pub patterns: BTreeMap<String, String>,
+}
+
+impl Default for RedactConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            builtin: true,
+            env_vars: Vec::new(),
+            patterns: BTreeMap::new(),
+        }
+    }
+}
+
+/// Token and cost limits for traced API usage
+/// Tokens are input + output; cost uses the pricing table.
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+pub struct BudgetConfig {
+    #[serde(default)]
+    pub session_tokens: Option<i64>,
+    #[serde(default)]
+    pub session_cost: Option<f64>,
+    /// Across all sessions since local midnight
+    #[serde(default)]
+    pub daily_tokens: Option<i64>,
+    #[serde(default)]
+    pub daily_cost: Option<f64>,
+    /// Refuse further API calls once a limit is blown (default: only warn)
+    #[serde(default)]
+    pub enforce: bool,
+}
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+    /// Cache hits; defaults to the input price
+    #[serde(default)]
+    pub cache_read: Option<f64>,
+    /// Cache writes; defaults to the input price
+    #[serde(default)]
+    pub cache_write: Option<f64>,
+}
+
+/// Forge selection for roadmap/issue sync
 #[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ForgeConfig {
+    /// Which forge to talk to: "github", "gitlab", "bitbucket" or "jira"
+    /// When unset, detected from the `origin` remote URL (default: GitHub)
+    #[serde(default)]
+    pub kind: Option<String>,
+
+    /// Jira site, e.g. "https://acme.atlassian.net" (default: `JIRA_URL`)
+    #[serde(default)]
+    pub jira_url: Option<String>,
+
+    /// Jira project key issues are created in, e.g. "PROJ"
+    #[serde(default)]
+    pub jira_project: Option<String>,
+
+    /// Issue type for new Jira issues (default: "Task")
+    #[serde(default)]
+    pub jira_issue_type: Option<String>,
+}
+
+/// GitHub-related configuration for commit/PR links
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct GithubConfig {
     /// External repository for commit links (e.g., "phoenixframework/phoenix")
     /// When set, commit hashes in nodes will link to this repo instead of the local one.
     /// Format: "owner/repo"
     #[serde(default)]
     pub commit_repo: Option<String>,
+
+    /// Shared secret for verifying webhooks sent to `deciduous serve`
+    /// (`DECIDUOUS_WEBHOOK_SECRET` takes precedence when set).
+    #[serde(default, skip_serializing)]
+    pub webhook_secret: Option<String>,
+
+    /// How long cached issue/PR state stays fresh before it's refreshed on read
+    /// Default: 24 hours. Set to 0 to disable lazy refresh.
+    #[serde(default = "default_cache_ttl_hours")]
+    pub cache_ttl_hours: i64,
+}
+
+fn default_cache_ttl_hours() -> i64 {
+    24
+}
+
+impl Default for GithubConfig {
+    fn default() -> Self {
+        Self {
+            commit_repo: None,
+            webhook_secret: None,
+            cache_ttl_hours: default_cache_ttl_hours(),
+        }
+    }
 }
 
 /// Branch-related configuration
@@ -73,7 +757,7 @@ impl Config {
     }
 
     /// Find config.toml by walking up directory tree
-    fn find_config_path() -> Option<PathBuf> {
+    pub fn find_config_path() -> Option<PathBuf> {
         let current_dir = std::env::current_dir().ok()?;
         let mut dir = current_dir.as_path();
 
@@ -95,6 +779,269 @@ impl Config {
     pub fn is_main_branch(&self, branch: &str) -> bool {
         self.branch.main_branches.iter().any(|b| b == branch)
     }
+
+    /// Author for exported patches: `[identity] name`, else git user.name
+    pub fn author(&self) -> Option<String> {
+        self.identity
+            .name
+            .clone()
+            .filter(|n| !n.trim().is_empty())
+            .or_else(crate::db::get_git_user_name)
+    }
+
+    /// The roster handle a free-form author, email or alias refers to
+    pub fn team_handle(&self, who: &str) -> Option<&str> {
+        let who = who.trim();
+        if who.is_empty() {
+            return None;
+        }
+        self.team
+            .iter()
+            .find(|(handle, member)| member.matches(handle, who))
+            .map(|(handle, _)| handle.as_str())
+    }
+
+    /// How to show an author: the roster's display name, else as given
+    pub fn display_name(&self, who: &str) -> String {
+        match self.team_handle(who) {
+            Some(handle) => self.team[handle]
+                .name
+                .clone()
+                .unwrap_or_else(|| handle.to_string()),
+            None => who.to_string(),
+        }
+    }
+
+    /// Display name for a git author; the email identifies teammates best
+    pub fn commit_author(&self, name: &str, email: &str) -> String {
+        if self.team_handle(email).is_some() {
+            self.display_name(email)
+        } else {
+            self.display_name(name)
+        }
+    }
+
+    /// `[sync]` settings with roster members' keys added to `trusted_keys`
+    ///
+    /// Entries already in `trusted_keys` win over a roster key for the
+    /// same handle.
+    pub fn sync_with_team(&self) -> SyncConfig {
+        let mut sync = self.sync.clone();
+        for (handle, member) in &self.team {
+            if let Some(key) = member.key.as_ref().filter(|k| !k.trim().is_empty()) {
+                sync.trusted_keys
+                    .entry(handle.clone())
+                    .or_insert_with(|| key.clone());
+            }
+        }
+        sync
+    }
+}
+
+/// Where an effective config value comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigSource {
+    Default,
+    File,
+    Env(&'static str),
+}
+
+/// Flatten TOML tables into dotted keys and their TOML-formatted values
+fn flatten(prefix: &str, value: &toml::Value, out: &mut BTreeMap<String, String>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&key, value, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.to_string());
+        }
+    }
+}
+
+impl Config {
+    /// Every effective setting with its source, given the config file's content
+    ///
+    /// The sync token is only ever shown as `<hidden>`.
+    pub fn effective_values(
+        &self,
+        file_content: Option<&str>,
+    ) -> Vec<(String, String, ConfigSource)> {
+        let mut values = BTreeMap::new();
+        if let Ok(effective) = toml::Value::try_from(self) {
+            flatten("", &effective, &mut values);
+        }
+        let mut in_file = BTreeMap::new();
+        if let Some(raw) = file_content.and_then(|c| toml::from_str::<toml::Value>(c).ok()) {
+            flatten("", &raw, &mut in_file);
+        }
+
+        let mut result: Vec<(String, String, ConfigSource)> = values
+            .into_iter()
+            .map(|(key, value)| {
+                let source = if in_file.contains_key(&key) {
+                    ConfigSource::File
+                } else {
+                    ConfigSource::Default
+                };
+                (key, value, source)
+            })
+            .collect();
+
+        let env_token = std::env::var(crate::hub::SYNC_TOKEN_ENV).is_ok_and(|t| !t.is_empty());
+        if env_token {
+            result.push((
+                "sync.token".to_string(),
+                "<hidden>".to_string(),
+                ConfigSource::Env(crate::hub::SYNC_TOKEN_ENV),
+            ));
+        } else if self.sync.token.as_deref().is_some_and(|t| !t.is_empty()) {
+            result.push((
+                "sync.token".to_string(),
+                "<hidden>".to_string(),
+                ConfigSource::File,
+            ));
+        }
+        result.sort_by(|a, b| a.0.cmp(&b.0));
+        result
+    }
+}
+
+/// Problems in a config file that `Config::load` would otherwise swallow
+///
+/// A file that doesn't parse makes `load` fall back to the defaults; keys
+/// it doesn't know (such as `main_branchs`) are dropped. Each is reported
+/// with the closest known key when there is one.
+pub fn validate_config(content: &str) -> Vec<String> {
+    if let Err(e) = toml::from_str::<toml::Value>(content) {
+        return vec![format!("not valid TOML: {}", e)];
+    }
+    // Whatever deserialization skips is unknown, including keys that are
+    // accepted but never serialized (`sync.token`, `github.webhook_secret`)
+    let mut ignored = Vec::new();
+    let config: Config =
+        match serde_ignored::deserialize(toml::Deserializer::new(content), |path| {
+            ignored.push(path.to_string().replace("?.", ""))
+        }) {
+            Ok(c) => c,
+            Err(e) => return vec![format!("invalid setting (all defaults are used): {}", e)],
+        };
+
+    // Keys at the same level, for the hint
+    let known = toml::Value::try_from(&config).ok();
+    let defaults = toml::Value::try_from(Config::default()).ok();
+    ignored
+        .into_iter()
+        .map(|path| {
+            let (parent, key) = path.rsplit_once('.').unwrap_or(("", &path));
+            let candidates = table_at(known.as_ref(), parent)
+                .into_iter()
+                .chain(table_at(defaults.as_ref(), parent))
+                .flat_map(|t| t.keys());
+            let hint = closest(key, candidates)
+                .map(|c| format!("; did you mean `{}`?", c))
+                .unwrap_or_default();
+            format!("unknown key `{}` is ignored{}", path, hint)
+        })
+        .collect()
+}
+
+/// The table at dotted `path` under `root`
+fn table_at<'a>(root: Option<&'a toml::Value>, path: &str) -> Option<&'a toml::Table> {
+    path.split('.')
+        .filter(|s| !s.is_empty())
+        .try_fold(root?, |v, k| v.get(k))
+        .and_then(|v| v.as_table())
+}
+
+/// The candidate within a small edit distance of `key`, if any
+fn closest<'a>(key: &str, candidates: impl Iterator<Item = &'a String>) -> Option<&'a str> {
+    let max = (key.len() / 3).max(1);
+    candidates
+        .map(|c| (edit_distance(key, c), c))
+        .filter(|(d, _)| *d <= max)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c.as_str())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != *cb);
+            row.push(substitute.min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+/// Set `key` (dotted, e.g. `branch.auto_detect`) in a config file's content
+///
+/// Edits the line in place, or adds it to its section, so comments and
+/// layout survive. `value` is read as TOML (`true`, `3`, `["a", "b"]`) and
+/// otherwise taken as a string. The result must load as a valid config.
+pub fn set_config_value(content: &str, key: &str, value: &str) -> Result<String, String> {
+    let Some((section, name)) = key.rsplit_once('.') else {
+        return Err(format!(
+            "`{}` isn't a setting; use section.key, e.g. branch.auto_detect",
+            key
+        ));
+    };
+    let literal = match toml::from_str::<toml::Value>(&format!("v = {}", value)) {
+        Ok(_) => value.trim().to_string(),
+        Err(_) => toml::Value::String(value.to_string()).to_string(),
+    };
+    let line = format!("{} = {}", name, literal);
+    let header = format!("[{}]", section);
+
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let strip = |l: &str| l.split('#').next().unwrap_or("").trim().to_string();
+    match lines.iter().position(|l| strip(l) == header) {
+        Some(start) => {
+            let end = lines[start + 1..]
+                .iter()
+                .position(|l| strip(l).starts_with('['))
+                .map_or(lines.len(), |i| start + 1 + i);
+            let existing = (start + 1..end).find(|&i| {
+                strip(&lines[i])
+                    .split_once('=')
+                    .is_some_and(|(k, _)| k.trim() == name)
+            });
+            match existing {
+                Some(i) => lines[i] = line,
+                None => {
+                    // After the section's last setting, before trailing blank lines
+                    let last = (start..end)
+                        .rev()
+                        .find(|&i| !lines[i].trim().is_empty())
+                        .unwrap_or(start);
+                    lines.insert(last + 1, line);
+                }
+            }
+        }
+        None => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(header);
+            lines.push(line);
+        }
+    }
+
+    let updated = format!("{}\n", lines.join("\n"));
+    if let Some(problem) = validate_config(&updated).into_iter().next() {
+        return Err(problem);
+    }
+    Ok(updated)
 }
 
 #[cfg(test)]
@@ -121,4 +1068,312 @@ auto_detect = true
         assert!(config.is_main_branch("develop"));
         assert!(!config.is_main_branch("feature-x"));
     }
+
+    #[test]
+    fn test_parse_forge_config() {
+        let toml = r#"
+[forge]
+kind = "gitlab"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.forge.kind.as_deref(), Some("gitlab"));
+        assert!(Config::default().forge.kind.is_none());
+    }
+
+    #[test]
+    fn test_parse_sync_config() {
+        let toml = r#"
+[sync]
+require_signatures = true
+
+[sync.trusted_keys]
+alice = "3b6a27bc"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert!(config.sync.require_signatures);
+        assert_eq!(config.sync.trusted_keys["alice"], "3b6a27bc");
+        assert!(!Config::default().sync.require_signatures);
+
+        let config: Config =
+            toml::from_str("[sync]\nserver = \"http://hub:3000\"\ntoken = \"s3cret\"\n").unwrap();
+        assert_eq!(config.sync.server.as_deref(), Some("http://hub:3000"));
+        assert!(!serde_json::to_string(&config).unwrap().contains("s3cret"));
+    }
+
+    #[test]
+    fn test_identity_and_team() {
+        let toml = r#"
+[identity]
+name = "Alice Smith"
+email = "alice@example.com"
+
+[team.bob]
+name = "Bob Jones"
+email = "bob@example.com"
+key = "ab12"
+aliases = ["bjones"]
+
+[team.carol]
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.author().as_deref(), Some("Alice Smith"));
+        assert_eq!(config.team_handle("BJones"), Some("bob"));
+        assert_eq!(config.display_name("bob@example.com"), "Bob Jones");
+        assert_eq!(config.display_name("carol"), "carol");
+        assert_eq!(config.display_name("dave"), "dave");
+        assert_eq!(config.commit_author("B", "bob@example.com"), "Bob Jones");
+        assert_eq!(config.team_handle(""), None);
+        assert_eq!(config.sync_with_team().trusted_keys["bob"], "ab12");
+        assert!(!config.sync_with_team().trusted_keys.contains_key("carol"));
+    }
+
+    #[test]
+    fn test_rules() {
+        let toml = r#"
+[rules.confidence]
+goal = 90
+
+[rules.require]
+goal = ["prompt", "confidence"]
+outcome = ["commits"]
+
+[rules.edge_require]
+rejected = ["rationale"]
+"#;
+        let rules = toml::from_str::<Config>(toml).unwrap().rules;
+        assert_eq!(rules.default_confidence("goal"), Some(90));
+        assert_eq!(rules.default_confidence("action"), None);
+        assert!(rules.check_node("goal", &["prompt", "confidence"]).is_ok());
+        let err = rules.check_node("goal", &["confidence"]).unwrap_err();
+        assert!(err.starts_with("goal nodes require prompt (--prompt or --prompt-stdin)"));
+        assert!(rules.check_node("action", &[]).is_ok());
+        assert!(rules
+            .check_node("outcome", &["commit"])
+            .unwrap_err()
+            .contains("unknown field `commits`"));
+        assert!(rules.check_edge("rejected", &[]).is_err());
+        assert!(rules.check_edge("rejected", &["rationale"]).is_ok());
+        assert!(rules.check_edge("leads_to", &[]).is_ok());
+    }
+
+    #[test]
+    fn test_export_profiles() {
+        let toml = r#"
+[export.profiles.pages]
+mirrors = ["docs/demo"]
+redact = true
+strip_prompts = true
+
+[export.profiles.internal]
+output = "internal/graph-data.json"
+include = ["git_history"]
+
+[export.profiles.broken]
+include = ["roadmaps"]
+"#;
+        let export = toml::from_str::<Config>(toml).unwrap().export;
+        let pages = export.profile("pages").unwrap();
+        assert_eq!(pages.output, "docs/graph-data.json");
+        assert!(pages.includes("roadmap") && pages.includes("git_history"));
+        assert!(pages.redact && pages.strip_prompts);
+
+        let internal = export.profile("internal").unwrap();
+        assert_eq!(internal.output, "internal/graph-data.json");
+        assert!(!internal.includes("roadmap"));
+        assert!(!internal.redact);
+
+        assert!(export
+            .profile("broken")
+            .unwrap_err()
+            .contains("unknown section `roadmaps`"));
+        assert!(export
+            .profile("public")
+            .unwrap_err()
+            .contains("configured: broken, internal, pages"));
+        assert_eq!(
+            validate_config(
+                "[export.profiles.pages]
+redcat = true
+"
+            ),
+            vec!["unknown key `export.profiles.pages.redcat` is ignored; did you mean `redact`?"]
+        );
+    }
+
+    #[test]
+    fn test_validate_config() {
+        assert!(validate_config("[branch]\nmain_branches = [\"main\"]\n").is_empty());
+        let problems = validate_config("[branch]\nmain_branchs = [\"main\"]\n");
+        assert_eq!(
+            problems,
+            vec!["unknown key `branch.main_branchs` is ignored; did you mean `main_branches`?"]
+        );
+        // Free-form maps are fine; unknown sections are not
+        assert!(validate_config("[sync.trusted_keys]\nalice = \"ab\"\n").is_empty());
+        assert_eq!(validate_config("[sink]\n").len(), 1);
+        assert!(validate_config("[branch\n")[0].starts_with("not valid TOML"));
+        assert!(validate_config("[branch]\nauto_detect = \"yes\"\n")[0].starts_with("invalid"));
+    }
+
+    #[test]
+    fn test_set_config_value() {
+        let content =
+            "# Settings\n[branch]\n# Main branches\nmain_branches = [\"main\"]\n\n[github]\n";
+        let updated = set_config_value(content, "branch.auto_detect", "false").unwrap();
+        assert!(updated.contains("main_branches = [\"main\"]\nauto_detect = false\n\n[github]"));
+        assert!(updated.starts_with("# Settings\n"));
+
+        let updated =
+            set_config_value(&updated, "branch.main_branches", r#"["main", "trunk"]"#).unwrap();
+        let config: Config = toml::from_str(&updated).unwrap();
+        assert!(config.is_main_branch("trunk"));
+        assert!(!config.branch.auto_detect);
+
+        let updated = set_config_value(&updated, "sync.server", "http://hub:3000").unwrap();
+        assert!(updated.ends_with("[sync]\nserver = \"http://hub:3000\"\n"));
+
+        assert!(set_config_value(content, "branch.main_branchs", "[]").is_err());
+        assert!(set_config_value(content, "branch", "x").is_err());
+
+        // Secrets are read but never serialized
+        let updated = set_config_value(content, "github.webhook_secret", "s3cret").unwrap();
+        assert!(validate_config(&updated).is_empty());
+        let config: Config = toml::from_str(&updated).unwrap();
+        assert_eq!(config.github.webhook_secret.as_deref(), Some("s3cret"));
+        let updated = set_config_value(&updated, "sync.token", "t0ken").unwrap();
+        assert!(validate_config(&updated).is_empty());
+        assert_eq!(
+            validate_config("[github]\nwebhook_secrt = \"x\"\n"),
+            vec!["unknown key `github.webhook_secrt` is ignored"]
+        );
+    }
+
+    #[test]
+    fn test_effective_values() {
+        let content = "[branch]\nauto_detect = false\n";
+        let config: Config = toml::from_str(content).unwrap();
+        let values = config.effective_values(Some(content));
+        let find = |key: &str| values.iter().find(|(k, _, _)| k == key).cloned();
+        assert_eq!(
+            find("branch.auto_detect"),
+            Some((
+                "branch.auto_detect".to_string(),
+                "false".to_string(),
+                ConfigSource::File
+            ))
+        );
+        assert_eq!(
+            find("branch.main_branches").unwrap().2,
+            ConfigSource::Default
+        );
+    }
+
+    #[test]
+    fn test_cache_ttl_default_and_override() {
+        assert_eq!(Config::default().github.cache_ttl_hours, 24);
+        let config: Config = toml::from_str("[github]\ncommit_repo = \"o/r\"\n").unwrap();
+        assert_eq!(config.github.cache_ttl_hours, 24);
+        let config: Config = toml::from_str("[github]\ncache_ttl_hours = 2\n").unwrap();
+        assert_eq!(config.github.cache_ttl_hours, 2);
+    }
+
+    #[test]
+    fn test_parse_trace_pricing() {
+        let toml = r#"
+[trace.pricing."claude-sonnet-4"]
+input = 2.5
+output = 12.0
+cache_read = 0.25
+
+[trace.pricing.llama-local]
+input = 0.0
+output = 0.0
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let sonnet = config.trace.pricing["claude-sonnet-4"];
+        assert_eq!(sonnet.output, 12.0);
+        assert_eq!(sonnet.cache_read, Some(0.25));
+        assert_eq!(sonnet.cache_write, None);
+        assert!(config.trace.pricing.contains_key("llama-local"));
+        assert!(Config::default().trace.pricing.is_empty());
+    }
+
+    #[test]
+    fn test_parse_trace_budget() {
+        let toml = r#"
+[trace.budget]
+session_cost = 5.0
+daily_tokens = 2000000
+enforce = true
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.trace.budget.session_cost, Some(5.0));
+        assert_eq!(config.trace.budget.daily_tokens, Some(2_000_000));
+        assert_eq!(config.trace.budget.session_tokens, None);
+        assert!(config.trace.budget.enforce);
+        assert_eq!(Config::default().trace.budget, BudgetConfig::default());
+    }
+
+    #[test]
+    fn test_parse_trace_redact() {
+        let toml = r#"
+[trace.redact]
+env_vars = ["STRIPE_KEY"]
+patterns = { internal_host = '[a-z]+\.corp' }
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert!(config.trace.redact.enabled);
+        assert!(config.trace.redact.builtin);
+        assert_eq!(config.trace.redact.env_vars, vec!["STRIPE_KEY"]);
+        assert_eq!(
+            config.trace.redact.patterns["internal_host"],
+            r"[a-z]+\.corp"
+        );
+        assert!(Config::default().trace.redact.enabled);
+    }
+
+    #[test]
+    fn test_parse_trace_retention() {
+        let toml = r#"
+[trace.retention]
+max_age_days = 14
+max_db_size_mb = 500
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let retention = &config.trace.retention;
+        assert_eq!(retention.max_age_days, Some(14));
+        assert_eq!(retention.max_db_size_mb, Some(500));
+        assert!(retention.keep_linked);
+        assert!(retention.is_enabled());
+        assert!(!Config::default().trace.retention.is_enabled());
+    }
+
+    #[test]
+    fn test_parse_trace_capture() {
+        let toml = r#"
+[trace.capture]
+max_body_kb = 64
+full_content_every = 5
+skip_tool_output = ["Read", "Glob"]
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let capture = &config.trace.capture;
+        assert_eq!(capture.max_body_kb, Some(64));
+        assert_eq!(capture.full_content_every, Some(5));
+        assert_eq!(capture.skip_tool_output, vec!["Read", "Glob"]);
+        assert_eq!(Config::default().trace.capture, CaptureConfig::default());
+    }
+
+    #[test]
+    fn test_webhook_secret_not_serialized() {
+        let toml = r#"
+[github]
+commit_repo = "owner/repo"
+webhook_secret = "hunter2"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.github.webhook_secret.as_deref(), Some("hunter2"));
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(!json.contains("hunter2"));
+    }
 }
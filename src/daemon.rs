@@ -0,0 +1,260 @@
+//! JSON-RPC backend for editor plugins
+//!
+//! `deciduous daemon` answers JSON-RPC 2.0 requests, one message per line, on
+//! stdio or (with `--socket`) on a Unix socket, so a VS Code or Neovim
+//! extension can keep one process around for gutter annotations instead of
+//! spawning the CLI on every cursor move. Writes share the MCP tools' code
+//! and `[rules]` checks.
+//!
+//! Methods:
+//! - `nodes/near-file` `{path, limit?}`: nodes whose `files` include the path
+//! - `node/get` `{id}`: a node with its parents, children and edges
+//! - `node/create`: the MCP `add_node` arguments
+//! - `edge/create`: the MCP `link` arguments
+//! - `nodes/query`, `nodes/search`: the MCP `query` and `search` tools
+//! - `context/pack` `{path | node_id, limit?}`: the chains from root goals down
+//!   to those nodes, as nodes, edges and a plain-text summary for a prompt
+
+use crate::db::{node_touches_file, DecisionEdge, DecisionNode};
+use crate::mcp::{self, error, id_arg, limit_arg, str_arg, INVALID_PARAMS, METHOD_NOT_FOUND};
+use crate::store::GraphStore;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Write};
+
+const METHODS: &[&str] = &[
+    "nodes/near-file",
+    "node/get",
+    "node/create",
+    "edge/create",
+    "nodes/query",
+    "nodes/search",
+    "context/pack",
+];
+
+/// Answer requests read from `input` on `output` until `input` closes
+pub fn serve_lines(
+    db: &dyn GraphStore,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> std::io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle(db, &message),
+            Err(e) => Some(error(Value::Null, mcp::PARSE_ERROR, &e.to_string())),
+        };
+        if let Some(response) = response {
+            writeln!(output, "{}", response)?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Serve requests on stdin and stdout
+pub fn serve_stdio(db: &dyn GraphStore) -> Result<(), String> {
+    serve_lines(db, std::io::stdin().lock(), std::io::stdout()).map_err(|e| format!("stdio: {}", e))
+}
+
+/// Serve each connection to a Unix socket at `path` on its own thread
+///
+/// A stale socket left at `path` by an earlier daemon is replaced.
+#[cfg(unix)]
+pub fn serve_socket(db: &dyn GraphStore, path: &std::path::Path) -> Result<(), String> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixListener;
+
+    if let Ok(meta) = std::fs::symlink_metadata(path) {
+        if !meta.file_type().is_socket() {
+            return Err(format!("{} exists and is not a socket", path.display()));
+        }
+        std::fs::remove_file(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    let listener = UnixListener::bind(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    std::thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let stream = stream.map_err(|e| e.to_string())?;
+            scope.spawn(move || {
+                if let Ok(reader) = stream.try_clone() {
+                    // A client hanging up mid-request only ends its own connection
+                    let _ = serve_lines(db, std::io::BufReader::new(reader), stream);
+                }
+            });
+        }
+        Ok(())
+    })
+}
+
+/// The response to one JSON-RPC message; None for notifications
+pub fn handle(db: &dyn GraphStore, message: &Value) -> Option<Value> {
+    let id = message.get("id")?.clone();
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+    let result = match message["method"].as_str().unwrap_or_default() {
+        "initialize" => Ok(json!({
+            "name": "deciduous",
+            "version": env!("CARGO_PKG_VERSION"),
+            "methods": METHODS,
+        })),
+        "nodes/near-file" => near_file(db, &params),
+        "node/get" => id_arg(&params, "id").and_then(|id| mcp::node_context(db, id)),
+        "node/create" => mcp::add_node(db, &params),
+        "edge/create" => mcp::link(db, &params),
+        "nodes/query" => mcp::query(db, &params),
+        "nodes/search" => mcp::search(db, &params),
+        "context/pack" => context_pack(db, &params),
+        method => {
+            return Some(error(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("Unknown method: {}", method),
+            ))
+        }
+    };
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(message) => error(id, INVALID_PARAMS, &message),
+    })
+}
+
+/// Nodes recording `path` in their files, newest first
+fn nodes_near(db: &dyn GraphStore, path: &str, limit: usize) -> Result<Vec<DecisionNode>, String> {
+    let mut nodes: Vec<DecisionNode> = db
+        .get_all_nodes()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|n| node_touches_file(n, path))
+        .collect();
+    nodes.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+    nodes.truncate(limit);
+    Ok(nodes)
+}
+
+fn near_file(db: &dyn GraphStore, params: &Value) -> Result<Value, String> {
+    let path = str_arg(params, "path").ok_or("`path` is required")?;
+    Ok(json!(nodes_near(db, path, limit_arg(params, 50))?))
+}
+
+/// The nodes from a root down to `id`, following the lowest-numbered parent
+fn chain_to(id: i32, parents: &HashMap<i32, Vec<i32>>) -> Vec<i32> {
+    let mut chain = vec![id];
+    let mut seen = HashSet::from([id]);
+    while let Some(&parent) = parents
+        .get(chain.last().unwrap_or(&id))
+        .and_then(|p| p.iter().min())
+    {
+        if !seen.insert(parent) {
+            break;
+        }
+        chain.push(parent);
+    }
+    chain.reverse();
+    chain
+}
+
+fn context_pack(db: &dyn GraphStore, params: &Value) -> Result<Value, String> {
+    let seeds: Vec<i32> = match (str_arg(params, "path"), params.get("node_id")) {
+        (Some(path), _) => nodes_near(db, path, limit_arg(params, 10))?
+            .iter()
+            .map(|n| n.id)
+            .collect(),
+        (None, Some(_)) => vec![id_arg(params, "node_id")?],
+        (None, None) => return Err("`path` or `node_id` is required".to_string()),
+    };
+
+    let nodes: HashMap<i32, DecisionNode> = db
+        .get_all_nodes()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|n| (n.id, n))
+        .collect();
+    let edges = db.get_all_edges().map_err(|e| e.to_string())?;
+    let mut parents: HashMap<i32, Vec<i32>> = HashMap::new();
+    for edge in &edges {
+        parents
+            .entry(edge.to_node_id)
+            .or_default()
+            .push(edge.from_node_id);
+    }
+
+    let mut text = String::new();
+    let mut keep = HashSet::new();
+    for seed in seeds {
+        if !nodes.contains_key(&seed) {
+            return Err(format!("Node {} does not exist", seed));
+        }
+        let chain: Vec<&DecisionNode> = chain_to(seed, &parents)
+            .into_iter()
+            .filter_map(|id| nodes.get(&id))
+            .collect();
+        let steps: Vec<String> = chain
+            .iter()
+            .map(|n| format!("{} #{} {} ({})", n.node_type, n.id, n.title, n.status))
+            .collect();
+        text.push_str(&format!("- {}\n", steps.join(" -> ")));
+        keep.extend(chain.iter().map(|n| n.id));
+    }
+
+    let mut kept: Vec<&DecisionNode> = keep.iter().filter_map(|id| nodes.get(id)).collect();
+    kept.sort_by_key(|n| n.id);
+    let kept_edges: Vec<&DecisionEdge> = edges
+        .iter()
+        .filter(|e| keep.contains(&e.from_node_id) && keep.contains(&e.to_node_id))
+        .collect();
+    Ok(json!({ "nodes": kept, "edges": kept_edges, "text": text }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn test_daemon_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let goal = db.node("goal", "Fast startup").insert().unwrap();
+        let action = db
+            .node("action", "Lazy-load config")
+            .files(["src/config.rs"])
+            .insert()
+            .unwrap();
+        db.edge(goal, action).insert().unwrap();
+
+        let input = [
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "nodes/near-file",
+                    "params": { "path": "/repo/src/config.rs" } }),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "context/pack",
+                    "params": { "path": "src/config.rs" } }),
+            json!({ "jsonrpc": "2.0", "id": 3, "method": "node/get", "params": {} }),
+            json!({ "jsonrpc": "2.0", "id": 4, "method": "nodes/everything" }),
+        ]
+        .iter()
+        .map(|m| m.to_string() + "\n")
+        .collect::<String>();
+        let mut output = Vec::new();
+        serve_lines(&db, input.as_bytes(), &mut output).unwrap();
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+
+        assert_eq!(responses[0]["result"][0]["id"], action);
+        let pack = &responses[1]["result"];
+        assert_eq!(pack["nodes"].as_array().unwrap().len(), 2);
+        assert_eq!(pack["edges"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            pack["text"],
+            format!(
+                "- goal #{} Fast startup (pending) -> action #{} Lazy-load config (pending)\n",
+                goal, action
+            )
+        );
+        assert_eq!(responses[2]["error"]["code"], INVALID_PARAMS);
+        assert_eq!(responses[3]["error"]["code"], METHOD_NOT_FOUND);
+    }
+}
@@ -3,12 +3,17 @@
 //! Stores decision graphs and command logs for AI-assisted development.
 //! Uses embedded migrations for schema management.
 
+use crate::config::RulesConfig;
+use crate::model::{EdgeType, NodeStatus, NodeType};
 use crate::schema::*;
+use crate::store::GraphStore;
 use diesel::prelude::*;
 use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
 use diesel::sqlite::SqliteConnection;
 use serde_json::json;
 use std::path::Path;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
 #[cfg(feature = "ts-rs")]
 use ts_rs::TS;
 use uuid::Uuid;
@@ -54,6 +59,29 @@ pub fn build_metadata_json(
     Some(serde_json::Value::Object(obj).to_string())
 }
 
+/// Whether a recorded file and `path` name the same file (either may be
+/// relative to a subdirectory of the repo)
+pub fn same_file(recorded: &str, path: &str) -> bool {
+    let f = recorded.trim_start_matches("./");
+    f == path || path.ends_with(&format!("/{}", f)) || f.ends_with(&format!("/{}", path))
+}
+
+/// Whether a node's `files` metadata includes `path` (either may be repo-relative)
+pub fn node_touches_file(node: &DecisionNode, path: &str) -> bool {
+    let files = node
+        .metadata_json
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        .and_then(|v| v.get("files").cloned())
+        .and_then(|f| f.as_array().cloned())
+        .unwrap_or_default();
+
+    files
+        .iter()
+        .filter_map(|f| f.as_str())
+        .any(|f| same_file(f, path))
+}
+
 /// Get current git branch name
 pub fn get_current_git_branch() -> Option<String> {
     std::process::Command::new("git")
@@ -90,6 +118,42 @@ pub fn get_current_git_commit() -> Option<String> {
         })
 }
 
+/// Name from `git config user.name`, falling back to `$USER`
+pub fn get_git_user_name() -> Option<String> {
+    std::process::Command::new("git")
+        .args(["config", "user.name"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|u| !u.is_empty())
+        .or_else(|| std::env::var("USER").ok())
+}
+
+/// Goal a new trace session should be linked to
+///
+/// Prefers goals created on `branch` over goals from other branches, and
+/// open goals (not completed or rejected) over closed ones; the most recent
+/// wins within each group.
+pub fn pick_auto_link_goal<'a>(
+    nodes: &'a [DecisionNode],
+    branch: Option<&str>,
+) -> Option<&'a DecisionNode> {
+    nodes
+        .iter()
+        .filter(|n| n.node_type == "goal")
+        .max_by_key(|n| {
+            let node_branch = n
+                .metadata_json
+                .as_deref()
+                .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+                .and_then(|m| m.get("branch").and_then(|b| b.as_str()).map(String::from));
+            let on_branch = branch.is_some() && node_branch.as_deref() == branch;
+            let open = n.status != "completed" && n.status != "rejected";
+            (on_branch, open, &n.created_at)
+        })
+}
+
 /// Walk up directory tree to find .deciduous folder (like git finds .git)
 /// Can be overridden with DECIDUOUS_DB_PATH env var
 fn get_db_path() -> std::path::PathBuf {
@@ -219,10 +283,10 @@ pub struct NewDecisionNode<'a> {
 pub struct DecisionNode {
     pub id: i32,
     pub change_id: String,
-    pub node_type: String,
+    pub node_type: NodeType,
     pub title: String,
     pub description: Option<String>,
-    pub status: String,
+    pub status: NodeStatus,
     pub created_at: String,
     pub updated_at: String,
     pub metadata_json: Option<String>,
@@ -253,12 +317,109 @@ pub struct DecisionEdge {
     pub to_node_id: i32,
     pub from_change_id: Option<String>,
     pub to_change_id: Option<String>,
-    pub edge_type: String,
+    pub edge_type: EdgeType,
     pub weight: Option<f64>,
     pub rationale: Option<String>,
     pub created_at: String,
 }
 
+/// Insertable node tombstone
+#[derive(Insertable)]
+#[diesel(table_name = node_tombstones)]
+pub struct NewNodeTombstone<'a> {
+    pub change_id: &'a str,
+    pub deleted_at: &'a str,
+}
+
+/// A deleted node, identified by change_id
+#[derive(Queryable, Selectable, Debug, Clone, serde::Serialize)]
+#[diesel(table_name = node_tombstones)]
+pub struct NodeTombstone {
+    pub change_id: String,
+    pub deleted_at: String,
+}
+
+/// Insertable node conflict
+#[derive(Insertable)]
+#[diesel(table_name = node_conflicts)]
+pub struct NewNodeConflict<'a> {
+    pub change_id: &'a str,
+    pub field: &'a str,
+    pub local_value: Option<&'a str>,
+    pub remote_value: Option<&'a str>,
+    pub remote_updated_at: &'a str,
+    pub patch_author: Option<&'a str>,
+    pub resolution: Option<&'a str>,
+    pub detected_at: &'a str,
+    pub resolved_at: Option<&'a str>,
+}
+
+/// A node field that differed between an applied patch and the local copy
+#[derive(Queryable, Selectable, Debug, Clone, serde::Serialize)]
+#[diesel(table_name = node_conflicts)]
+pub struct NodeConflict {
+    pub id: i32,
+    pub change_id: String,
+    pub field: String,
+    pub local_value: Option<String>,
+    pub remote_value: Option<String>,
+    /// When the patch's version was last changed
+    pub remote_updated_at: String,
+    pub patch_author: Option<String>,
+    /// "ours" or "theirs" once resolved
+    pub resolution: Option<String>,
+    pub detected_at: String,
+    pub resolved_at: Option<String>,
+}
+
+/// Insertable node export record
+#[derive(Insertable)]
+#[diesel(table_name = node_exports)]
+pub struct NewNodeExport<'a> {
+    pub change_id: &'a str,
+    pub patch_path: &'a str,
+    pub branch: Option<&'a str>,
+    pub exported_at: &'a str,
+}
+
+/// A node written to a patch file by `diff export`
+#[derive(Queryable, Selectable, Debug, Clone, serde::Serialize)]
+#[diesel(table_name = node_exports)]
+pub struct NodeExport {
+    pub id: i32,
+    pub change_id: String,
+    pub patch_path: String,
+    /// Branch filter the export used, if any
+    pub branch: Option<String>,
+    pub exported_at: String,
+}
+
+/// Insertable applied patch record
+#[derive(Insertable)]
+#[diesel(table_name = applied_patches)]
+pub struct NewAppliedPatch<'a> {
+    pub patch_path: &'a str,
+    pub content_hash: &'a str,
+    pub commit_sha: Option<&'a str>,
+    pub applied_at: &'a str,
+    pub author: Option<&'a str>,
+}
+
+/// A patch file applied to this database
+#[derive(Queryable, Selectable, Debug, Clone, serde::Serialize)]
+#[diesel(table_name = applied_patches)]
+pub struct AppliedPatch {
+    pub id: i32,
+    pub patch_path: String,
+    /// SHA-256 of the file's bytes, hex
+    pub content_hash: String,
+    /// Commit the file was read from, for patches found by `diff scan`
+    pub commit_sha: Option<String>,
+    pub applied_at: String,
+    /// Patch author, resolved through the team roster when applied
+    pub author: Option<String>,
+}
+
 /// Insertable decision context
 #[derive(Insertable)]
 #[diesel(table_name = decision_context)]
@@ -346,6 +507,35 @@ pub struct CommandLog {
     pub decision_node_id: Option<i32>,
 }
 
+/// Insertable record of a change made by a logged command
+#[derive(Insertable)]
+#[diesel(table_name = command_changes)]
+pub struct NewCommandChange<'a> {
+    pub command_id: i32,
+    pub kind: &'a str,
+    pub target_id: i32,
+    pub action: &'a str,
+    pub detail: &'a str,
+    pub created_at: &'a str,
+}
+
+/// A node or edge a logged command created, changed or deleted
+#[derive(Queryable, Selectable, Debug, Clone, serde::Serialize)]
+#[diesel(table_name = command_changes)]
+pub struct CommandChange {
+    pub id: i32,
+    pub command_id: i32,
+    /// "node" or "edge"
+    pub kind: String,
+    pub target_id: i32,
+    /// "created", "updated" or "deleted"
+    pub action: String,
+    /// `[type] title` for nodes, `from -> to (type)` for edges, or the
+    /// changed fields for updates
+    pub detail: String,
+    pub created_at: String,
+}
+
 // ============================================================================
 // Roadmap Board Models
 // ============================================================================
@@ -423,6 +613,11 @@ pub struct NewRoadmapItem<'a> {
     pub created_at: &'a str,
     pub updated_at: &'a str,
     pub last_synced_at: Option<&'a str>,
+    pub priority: Option<i32>,
+    pub due_date: Option<&'a str>,
+    pub goal_node_id: Option<i32>,
+    pub goal_change_id: Option<&'a str>,
+    pub archived_at: Option<&'a str>,
 }
 
 /// Queryable roadmap item
@@ -448,6 +643,15 @@ pub struct RoadmapItem {
     pub created_at: String,
     pub updated_at: String,
     pub last_synced_at: Option<String>,
+    /// From a `(P1)` annotation; lower is more urgent
+    pub priority: Option<i32>,
+    /// From a `(due: 2025-06)` annotation
+    pub due_date: Option<String>,
+    /// Goal node created for this item by `roadmap start`
+    pub goal_node_id: Option<i32>,
+    pub goal_change_id: Option<String>,
+    /// When `roadmap archive` moved the item to ROADMAP_ARCHIVE.md
+    pub archived_at: Option<String>,
 }
 
 impl RoadmapItem {
@@ -460,6 +664,44 @@ impl RoadmapItem {
     pub fn is_checked(&self) -> bool {
         self.checkbox().is_checked()
     }
+
+    /// Whether this is a checkbox task rather than a section header
+    pub fn is_task(&self) -> bool {
+        self.checkbox() != CheckboxState::None
+    }
+
+    /// Whether the roadmap views count this task as done: checked, or filed
+    /// under a "Completed" section
+    pub fn is_complete(&self) -> bool {
+        let in_completed_section = self
+            .section
+            .as_deref()
+            .is_some_and(|s| s.to_lowercase().contains("completed"));
+        self.is_checked() || in_completed_section
+    }
+}
+
+/// Insertable roadmap item-outcome link
+#[derive(Insertable)]
+#[diesel(table_name = roadmap_item_outcomes)]
+pub struct NewRoadmapItemOutcome<'a> {
+    pub roadmap_item_id: i32,
+    pub outcome_node_id: i32,
+    pub outcome_change_id: &'a str,
+    pub created_at: &'a str,
+}
+
+/// Queryable roadmap item-outcome link
+///
+/// An item can be completed by several outcomes; `RoadmapItem::outcome_node_id`
+/// holds the first one linked.
+#[derive(Queryable, Selectable, Debug, Clone, serde::Serialize)]
+#[diesel(table_name = roadmap_item_outcomes)]
+pub struct RoadmapItemOutcome {
+    pub roadmap_item_id: i32,
+    pub outcome_node_id: i32,
+    pub outcome_change_id: String,
+    pub created_at: String,
 }
 
 /// Insertable roadmap sync state
@@ -555,6 +797,39 @@ pub struct GitHubIssueCache {
     pub cached_at: String,
 }
 
+// ============================================================================
+// GitHub PR Cache
+// ============================================================================
+
+/// Insertable GitHub pull request cache entry
+#[derive(Insertable, Debug)]
+#[diesel(table_name = github_pr_cache)]
+pub struct NewGitHubPrCache<'a> {
+    pub pr_number: i32,
+    pub repo: &'a str,
+    pub title: &'a str,
+    pub state: &'a str,
+    pub head_branch: &'a str,
+    pub html_url: &'a str,
+    pub cached_at: &'a str,
+}
+
+/// Queryable GitHub pull request cache entry
+#[derive(Queryable, Selectable, Debug, Clone, serde::Serialize)]
+#[cfg_attr(feature = "ts-rs", derive(TS))]
+#[cfg_attr(feature = "ts-rs", ts(export))]
+#[diesel(table_name = github_pr_cache)]
+pub struct GitHubPrCache {
+    pub id: i32,
+    pub pr_number: i32,
+    pub repo: String,
+    pub title: String,
+    pub state: String,
+    pub head_branch: String,
+    pub html_url: String,
+    pub cached_at: String,
+}
+
 // ============================================================================
 // Claude Trace Models
 // ============================================================================
@@ -680,6 +955,257 @@ pub struct TraceContent {
     pub sequence_num: i32,
 }
 
+#[cfg(test)]
+impl TraceSpan {
+    /// A span with only its ids set; tests fill in the rest with `..`
+    pub(crate) fn for_test(id: i32) -> Self {
+        TraceSpan {
+            id,
+            change_id: format!("c{}", id),
+            session_id: "s".to_string(),
+            sequence_num: id,
+            started_at: String::new(),
+            completed_at: None,
+            duration_ms: None,
+            model: None,
+            request_id: None,
+            stop_reason: None,
+            input_tokens: None,
+            output_tokens: None,
+            cache_read: None,
+            cache_write: None,
+            user_preview: None,
+            thinking_preview: None,
+            response_preview: None,
+            tool_names: None,
+            linked_node_id: None,
+            linked_change_id: None,
+        }
+    }
+}
+
+#[cfg(test)]
+impl TraceContent {
+    /// A content item of `kind`; `tool` is the tool name and tool_use_id
+    pub(crate) fn for_test(
+        span_id: i32,
+        kind: &str,
+        text: &str,
+        tool: Option<(&str, &str)>,
+    ) -> Self {
+        TraceContent {
+            id: 0,
+            span_id,
+            content_type: kind.to_string(),
+            tool_name: tool.map(|t| t.0.to_string()),
+            tool_use_id: tool.map(|t| t.1.to_string()),
+            content: text.to_string(),
+            sequence_num: 0,
+        }
+    }
+}
+
+/// Insertable trace redaction record
+#[derive(Insertable)]
+#[diesel(table_name = trace_redactions)]
+pub struct NewTraceRedaction<'a> {
+    pub span_id: i32,
+    pub content_type: &'a str,
+    pub rule: &'a str,
+    pub count: i32,
+}
+
+/// Queryable trace redaction record: what was removed from a span's content
+#[derive(Queryable, Selectable, Debug, Clone, serde::Serialize)]
+#[diesel(table_name = trace_redactions)]
+pub struct TraceRedaction {
+    pub id: i32,
+    pub span_id: i32,
+    pub content_type: String,
+    pub rule: String,
+    pub count: i32,
+}
+
+/// Insertable subagent span → parent span link
+#[derive(Insertable)]
+#[diesel(table_name = span_parents)]
+pub struct NewSpanParent<'a> {
+    pub span_id: i32,
+    pub parent_span_id: i32,
+    pub tool_use_id: Option<&'a str>,
+}
+
+/// Insertable or replaceable stream stats for a span
+#[derive(Insertable)]
+#[diesel(table_name = span_streams)]
+pub struct NewSpanStream {
+    pub span_id: i32,
+    pub chunk_count: i32,
+    pub first_token_ms: Option<i32>,
+    pub complete: bool,
+}
+
+/// Queryable stream stats: how a span's streamed response arrived
+#[derive(Queryable, Selectable, Debug, Clone, serde::Serialize)]
+#[diesel(table_name = span_streams)]
+pub struct SpanStream {
+    pub span_id: i32,
+    pub chunk_count: i32,
+    pub first_token_ms: Option<i32>,
+    pub complete: bool,
+}
+
+/// Insertable span annotation
+#[derive(Insertable)]
+#[diesel(table_name = span_annotations)]
+pub struct NewSpanAnnotation<'a> {
+    pub span_id: i32,
+    pub note: &'a str,
+    pub created_at: &'a str,
+}
+
+/// Queryable span annotation: a reviewer's note on a span
+#[derive(Queryable, Selectable, Debug, Clone, serde::Serialize)]
+#[diesel(table_name = span_annotations)]
+pub struct SpanAnnotation {
+    pub id: i32,
+    pub span_id: i32,
+    pub note: String,
+    pub created_at: String,
+}
+
+/// Insertable node embedding
+#[derive(Insertable)]
+#[diesel(table_name = node_embeddings)]
+pub struct NewNodeEmbedding<'a> {
+    pub node_id: i32,
+    pub model: &'a str,
+    pub content_hash: &'a str,
+    pub vector: &'a [u8],
+    pub created_at: &'a str,
+}
+
+/// Queryable node embedding: a node's text as a vector, under one model
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = node_embeddings)]
+pub struct NodeEmbedding {
+    pub node_id: i32,
+    pub model: String,
+    pub content_hash: String,
+    pub vector: Vec<u8>,
+    pub created_at: String,
+}
+
+impl NodeEmbedding {
+    /// The stored vector
+    pub fn values(&self) -> Vec<f32> {
+        self.vector
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect()
+    }
+}
+
+/// Insertable federated repository
+#[derive(Insertable)]
+#[diesel(table_name = federated_repos)]
+pub struct NewFederatedRepo<'a> {
+    pub name: &'a str,
+    pub db_path: &'a str,
+    pub added_at: &'a str,
+}
+
+/// Queryable federated repository: another project's graph, by name
+#[derive(Queryable, Selectable, Debug, Clone, serde::Serialize)]
+#[diesel(table_name = federated_repos)]
+pub struct FederatedRepo {
+    pub name: String,
+    pub db_path: String,
+    pub added_at: String,
+}
+
+/// Insertable graph snapshot
+#[derive(Insertable)]
+#[diesel(table_name = snapshots)]
+pub struct NewSnapshot<'a> {
+    pub label: &'a str,
+    pub created_at: &'a str,
+    pub patch_json: &'a str,
+}
+
+/// Queryable graph snapshot: the whole graph under a label
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = snapshots)]
+pub struct Snapshot {
+    pub id: i32,
+    pub label: String,
+    pub created_at: String,
+    /// A `GraphPatch` holding every node and edge
+    pub patch_json: String,
+}
+
+/// Insertable file index entry
+#[derive(Insertable)]
+#[diesel(table_name = node_files)]
+pub struct NewNodeFile<'a> {
+    pub node_id: i32,
+    pub path: &'a str,
+    pub source: &'a str,
+    pub node_updated_at: &'a str,
+}
+
+/// A file a node touched, from its `files` metadata or its commit's diff
+#[derive(Queryable, Selectable, Debug, Clone, serde::Serialize)]
+#[diesel(table_name = node_files)]
+pub struct NodeFile {
+    pub node_id: i32,
+    pub path: String,
+    /// "files", "commit" or "files, commit"
+    pub source: String,
+    pub node_updated_at: String,
+}
+
+/// Insertable focus marker
+#[derive(Insertable)]
+#[diesel(table_name = focus)]
+pub struct NewFocus<'a> {
+    pub id: i32,
+    pub goal_id: i32,
+    pub started_at: &'a str,
+    pub expires_at: Option<&'a str>,
+}
+
+/// The goal new work is linked to, from `deciduous focus`
+#[derive(Queryable, Selectable, Debug, Clone, serde::Serialize)]
+#[diesel(table_name = focus)]
+pub struct Focus {
+    pub id: i32,
+    pub goal_id: i32,
+    pub started_at: String,
+    /// End of the time box; open-ended if None
+    pub expires_at: Option<String>,
+}
+
+/// Insertable span-commit link
+#[derive(Insertable)]
+#[diesel(table_name = span_commits)]
+pub struct NewSpanCommit<'a> {
+    pub span_id: i32,
+    pub commit_hash: &'a str,
+    pub committed_at: &'a str,
+    pub created_at: &'a str,
+}
+
+/// Queryable span-commit link: a commit made by one of the span's tool calls
+#[derive(Queryable, Selectable, Debug, Clone, serde::Serialize)]
+#[diesel(table_name = span_commits)]
+pub struct SpanCommit {
+    pub span_id: i32,
+    pub commit_hash: String,
+    pub committed_at: String,
+    pub created_at: String,
+}
+
 /// Insertable span-node link
 #[derive(Insertable)]
 #[diesel(table_name = span_nodes)]
@@ -731,6 +1257,22 @@ struct NodeIdOnly {
     id: i32,
 }
 
+/// Helper for byte-size queries
+#[derive(QueryableByName, Debug)]
+struct ByteCount {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    bytes: i64,
+}
+
+/// Helper for per-session trace size queries
+#[derive(QueryableByName, Debug)]
+struct SessionBytes {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    session_id: String,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    bytes: i64,
+}
+
 /// Helper for sqlite_master table queries
 #[derive(QueryableByName, Debug)]
 #[allow(dead_code)]
@@ -749,6 +1291,30 @@ type DbConn = PooledConnection<ConnectionManager<SqliteConnection>>;
 /// Database connection wrapper with connection pool
 pub struct Database {
     pool: DbPool,
+    subscribers: Mutex<Vec<Sender<DbEvent>>>,
+    /// Sub-project the graph is limited to (see `with_project_scope`)
+    project: Option<String>,
+    /// Command log entry that node and edge changes are recorded against
+    command_id: Option<i32>,
+}
+
+/// A node's `project` metadata, in SQL; NULL for nodes without valid metadata
+const NODE_PROJECT_SQL: &str =
+    "(CASE WHEN json_valid(metadata_json) THEN json_extract(metadata_json, '$.project') END)";
+
+/// A change made through a [`Database`], delivered by [`Database::subscribe`]
+///
+/// Only changes made through the same `Database` value are seen; writes from
+/// other processes (another `deciduous add`) still need the file watched.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum DbEvent {
+    /// A node was inserted (`create_node*` or [`NodeBuilder::insert`])
+    NodeAdded { node: DecisionNode },
+    /// An edge was inserted (`create_edge` or [`EdgeBuilder::insert`])
+    EdgeAdded { edge: DecisionEdge },
+    /// `update_node_status` changed a node's status
+    StatusChanged { node_id: i32, status: String },
 }
 
 /// Error type for database operations
@@ -787,39 +1353,411 @@ impl From<diesel::r2d2::Error> for DbError {
 
 pub type Result<T> = std::result::Result<T, DbError>;
 
-impl Database {
-    /// Get the database path that will be used
-    pub fn db_path() -> std::path::PathBuf {
-        get_db_path()
+/// A node waiting to be inserted, from [`Database::node`] or
+/// [`GraphStore::node`](crate::store::GraphStore)
+///
+/// `insert()` hands the node to the store, so every backend gets the same
+/// `[rules]` checks and focus link when they are asked for.
+///
+/// ```no_run
+/// # fn main() -> deciduous::db::Result<()> {
+/// let db = deciduous::Database::open()?;
+/// let id = db
+///     .node("action", "Add LRU cache")
+///     .confidence(85)
+///     .commit_head()
+///     .files(["src/cache.rs"])
+///     .insert()?;
+/// # Ok(())
+/// # }
+/// ```
+#[must_use = "a node is only created by calling .insert()"]
+pub struct NodeBuilder<'a> {
+    store: &'a dyn GraphStore,
+    pub(crate) change_id: Option<String>,
+    pub(crate) node_type: String,
+    pub(crate) title: String,
+    pub(crate) description: Option<String>,
+    pub(crate) confidence: Option<u8>,
+    pub(crate) commit: Option<String>,
+    pub(crate) prompt: Option<String>,
+    pub(crate) files: Option<String>,
+    pub(crate) branch: Option<String>,
+    pub(crate) project: Option<String>,
+    pub(crate) pr: Option<i32>,
+    rules: Option<RulesConfig>,
+    link_focus: bool,
+}
+
+impl<'a> NodeBuilder<'a> {
+    pub(crate) fn new(store: &'a dyn GraphStore, node_type: &str, title: &str) -> Self {
+        NodeBuilder {
+            store,
+            change_id: None,
+            node_type: node_type.to_string(),
+            title: title.to_string(),
+            description: None,
+            confidence: None,
+            commit: None,
+            prompt: None,
+            files: None,
+            branch: None,
+            project: None,
+            pr: None,
+            rules: None,
+            link_focus: false,
+        }
     }
 
-    /// Create a new database at a custom path
-    pub fn new(path: &str) -> Result<Self> {
-        Self::open_at(path)
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
     }
 
-    /// Open database at default path (respects DECIDUOUS_DB_PATH env var)
-    pub fn open() -> Result<Self> {
-        let path = get_db_path();
-        // Create parent directory if it doesn't exist
-        if let Some(parent) = path.parent() {
-            if !parent.exists() {
-                std::fs::create_dir_all(parent).ok();
-            }
-        }
-        Self::open_at(&path)
+    /// Confidence 0-100; higher values are capped at 100
+    pub fn confidence(mut self, confidence: u8) -> Self {
+        self.confidence = Some(confidence);
+        self
     }
 
-    /// Open database at specified path
-    pub fn open_at<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let path_str = path.as_ref().to_string_lossy().to_string();
-        let manager = ConnectionManager::<SqliteConnection>::new(&path_str);
-        let pool = Pool::builder()
-            .max_size(5)
-            .build(manager)
-            .map_err(|e| DbError::Connection(e.to_string()))?;
+    pub fn commit(mut self, commit: impl Into<String>) -> Self {
+        self.commit = Some(commit.into());
+        self
+    }
+
+    /// The commit checked out now, if in a git repository
+    pub fn commit_head(mut self) -> Self {
+        self.commit = get_current_git_commit();
+        self
+    }
+
+    /// The user prompt behind the node, verbatim
+    pub fn prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = Some(prompt.into());
+        self
+    }
+
+    pub fn files<I, S>(mut self, files: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let files: Vec<String> = files.into_iter().map(|f| f.as_ref().to_string()).collect();
+        self.files = (!files.is_empty()).then(|| files.join(","));
+        self
+    }
+
+    pub fn branch(mut self, branch: impl Into<String>) -> Self {
+        self.branch = Some(branch.into());
+        self
+    }
+
+    /// The branch checked out now, if in a git repository
+    pub fn current_branch(mut self) -> Self {
+        self.branch = get_current_git_branch();
+        self
+    }
+
+    /// Use this change_id instead of a new one (for patch application)
+    pub fn change_id(mut self, change_id: impl Into<String>) -> Self {
+        self.change_id = Some(change_id.into());
+        self
+    }
+
+    /// The sub-project the node belongs to; a scoped database uses its own
+    pub fn project(mut self, project: impl Into<String>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    /// The pull request the node is about
+    pub fn pr(mut self, number: i32) -> Self {
+        self.pr = Some(number);
+        self
+    }
+
+    /// Apply `[rules]`: the type's default confidence when none is set, and
+    /// a validation error from `insert()` when a required field is missing
+    pub fn rules(mut self, rules: &RulesConfig) -> Self {
+        self.rules = Some(rules.clone());
+        self
+    }
+
+    /// Link the node from the focused goal, if there is one (see
+    /// `deciduous focus`); goals aren't linked
+    pub fn link_focus(mut self) -> Self {
+        self.link_focus = true;
+        self
+    }
+
+    /// Fields `[rules.require]` can ask for that this node has
+    fn present(&self) -> Vec<&'static str> {
+        [
+            ("prompt", self.prompt.is_some()),
+            ("description", self.description.is_some()),
+            ("confidence", self.confidence.is_some()),
+            ("commit", self.commit.is_some()),
+            ("files", self.files.is_some()),
+            ("branch", self.branch.is_some()),
+            ("pr", self.pr.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(field, set)| set.then_some(field))
+        .collect()
+    }
+
+    /// Insert the node; returns its ID
+    pub fn insert(mut self) -> Result<i32> {
+        if let Some(rules) = self.rules.take() {
+            self.confidence = self
+                .confidence
+                .or_else(|| rules.default_confidence(&self.node_type));
+            rules
+                .check_node(&self.node_type, &self.present())
+                .map_err(DbError::Validation)?;
+        }
+
+        let id = self.store.insert_node(&self)?;
+        if self.link_focus && self.node_type != "goal" {
+            if let Some(goal) = self.store.focused_goal()? {
+                self.store.edge(goal.id, id).rationale("Focus").insert()?;
+            }
+        }
+        Ok(id)
+    }
+}
+
+/// An edge waiting to be inserted, from [`Database::edge`]
+///
+/// The edge type defaults to `leads_to`.
+#[must_use = "an edge is only created by calling .insert()"]
+pub struct EdgeBuilder<'a> {
+    store: &'a dyn GraphStore,
+    pub(crate) from_id: i32,
+    pub(crate) to_id: i32,
+    pub(crate) edge_type: String,
+    pub(crate) rationale: Option<String>,
+    rules: Option<RulesConfig>,
+}
+
+impl<'a> EdgeBuilder<'a> {
+    pub(crate) fn new(store: &'a dyn GraphStore, from_id: i32, to_id: i32) -> Self {
+        EdgeBuilder {
+            store,
+            from_id,
+            to_id,
+            edge_type: "leads_to".to_string(),
+            rationale: None,
+            rules: None,
+        }
+    }
+
+    /// leads_to, requires, chosen, rejected, blocks or enables
+    pub fn edge_type(mut self, edge_type: impl Into<String>) -> Self {
+        self.edge_type = edge_type.into();
+        self
+    }
+
+    pub fn rationale(mut self, rationale: impl Into<String>) -> Self {
+        self.rationale = Some(rationale.into());
+        self
+    }
+
+    /// Apply `[rules.edge_require]`: a validation error from `insert()` when
+    /// a required field is missing
+    pub fn rules(mut self, rules: &RulesConfig) -> Self {
+        self.rules = Some(rules.clone());
+        self
+    }
+
+    /// Insert the edge; returns its ID
+    ///
+    /// Fails with a validation error if either node doesn't exist.
+    pub fn insert(self) -> Result<i32> {
+        if let Some(rules) = &self.rules {
+            let present: &[&str] = if self.rationale.is_some() {
+                &["rationale"]
+            } else {
+                &[]
+            };
+            rules
+                .check_edge(&self.edge_type, present)
+                .map_err(DbError::Validation)?;
+        }
+        self.store.insert_edge(&self)
+    }
+}
+
+impl Database {
+    /// Get the database path that will be used
+    pub fn db_path() -> std::path::PathBuf {
+        get_db_path()
+    }
+
+    /// Create a new database at a custom path
+    pub fn new(path: &str) -> Result<Self> {
+        Self::open_at(path)
+    }
+
+    /// Open database at default path (respects DECIDUOUS_DB_PATH env var)
+    pub fn open() -> Result<Self> {
+        let path = get_db_path();
+        // Create parent directory if it doesn't exist
+        if let Some(parent) = path.parent() {
+            if !parent.exists() {
+                std::fs::create_dir_all(parent).ok();
+            }
+        }
+        Self::open_at(&path)
+    }
+
+    /// Limit the graph to nodes whose `project` metadata is `project`, and
+    /// the edges between them
+    ///
+    /// Graph reads (`get_all_nodes`, `get_all_edges`, `get_graph*`, `iter_*`
+    /// and `write_graph_json`) leave other projects out, `get_node_by_id`
+    /// doesn't find their nodes, and writes by node ID (status, content,
+    /// metadata, edges, delete, merge) fail with a validation error naming
+    /// the node.
+    pub fn with_project_scope(mut self, project: Option<String>) -> Self {
+        self.project = project;
+        self
+    }
+
+    /// The sub-project the graph is limited to, if any
+    pub fn project_scope(&self) -> Option<&str> {
+        self.project.as_deref()
+    }
+
+    /// Record every node and edge created, changed or deleted through this
+    /// `Database` against command log entry `command_id`
+    /// (see `get_command_changes`)
+    pub fn with_command_log(mut self, command_id: Option<i32>) -> Self {
+        self.command_id = command_id;
+        self
+    }
+
+    /// Note a change for the command being logged, if any
+    fn record_change(&self, kind: &str, target_id: i32, action: &str, detail: &str) -> Result<()> {
+        let Some(command_id) = self.command_id else {
+            return Ok(());
+        };
+        let mut conn = self.get_conn()?;
+        diesel::insert_into(command_changes::table)
+            .values(&NewCommandChange {
+                command_id,
+                kind,
+                target_id,
+                action,
+                detail,
+                created_at: &chrono::Local::now().to_rfc3339(),
+            })
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    /// Nodes in the project scope (all nodes when unscoped)
+    fn scoped_nodes<'a>(&self) -> decision_nodes::BoxedQuery<'a, diesel::sqlite::Sqlite> {
+        let query = decision_nodes::table.into_boxed();
+        match &self.project {
+            Some(project) => query.filter(
+                diesel::dsl::sql::<diesel::sql_types::Bool>(&format!("{} = ", NODE_PROJECT_SQL))
+                    .bind::<diesel::sql_types::Text, _>(project.clone()),
+            ),
+            None => query,
+        }
+    }
+
+    /// Fail if any of `ids` is a node outside the project scope
+    ///
+    /// Ids with no node pass, for the caller to report as it already does.
+    fn check_scope(&self, ids: &[i32]) -> Result<()> {
+        let Some(project) = &self.project else {
+            return Ok(());
+        };
+        let mut conn = self.get_conn()?;
+        let existing: Vec<i32> = decision_nodes::table
+            .filter(decision_nodes::id.eq_any(ids))
+            .select(decision_nodes::id)
+            .load(&mut conn)?;
+        let in_scope: Vec<i32> = self
+            .scoped_nodes()
+            .filter(decision_nodes::id.eq_any(ids))
+            .select(decision_nodes::id)
+            .load(&mut conn)?;
+        match existing.iter().find(|id| !in_scope.contains(id)) {
+            Some(id) => Err(DbError::Validation(format!(
+                "Node {} is not in project '{}' (--scope)",
+                id, project
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Edges between nodes in the project scope (all edges when unscoped)
+    fn scoped_edges<'a>(&self) -> decision_edges::BoxedQuery<'a, diesel::sqlite::Sqlite> {
+        let query = decision_edges::table.into_boxed();
+        match &self.project {
+            Some(project) => {
+                let in_project = format!(
+                    "IN (SELECT id FROM decision_nodes WHERE {} = ",
+                    NODE_PROJECT_SQL
+                );
+                query.filter(
+                    diesel::dsl::sql::<diesel::sql_types::Bool>(&format!(
+                        "from_node_id {}",
+                        in_project
+                    ))
+                    .bind::<diesel::sql_types::Text, _>(project.clone())
+                    .sql(&format!(") AND to_node_id {}", in_project))
+                    .bind::<diesel::sql_types::Text, _>(project.clone())
+                    .sql(")"),
+                )
+            }
+            None => query,
+        }
+    }
+
+    /// Receive an event for every node, edge and status change made from now on
+    ///
+    /// Events are sent after the change is written. Dropping the receiver
+    /// unsubscribes.
+    pub fn subscribe(&self) -> Receiver<DbEvent> {
+        let (sender, receiver) = channel();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(sender);
+        }
+        receiver
+    }
+
+    /// Send an event to every subscriber; `event` is only built if there are any
+    fn emit(&self, event: impl FnOnce() -> Option<DbEvent>) {
+        let Ok(mut subscribers) = self.subscribers.lock() else {
+            return;
+        };
+        if subscribers.is_empty() {
+            return;
+        }
+        if let Some(event) = event() {
+            subscribers.retain(|s| s.send(event.clone()).is_ok());
+        }
+    }
+
+    /// Open database at specified path
+    pub fn open_at<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path_str = path.as_ref().to_string_lossy().to_string();
+        let manager = ConnectionManager::<SqliteConnection>::new(&path_str);
+        let pool = Pool::builder()
+            .max_size(5)
+            .build(manager)
+            .map_err(|e| DbError::Connection(e.to_string()))?;
 
-        let db = Self { pool };
+        let db = Self {
+            pool,
+            subscribers: Mutex::new(Vec::new()),
+            project: None,
+            command_id: None,
+        };
         // Auto-migrate FIRST - add change_id columns to existing databases before init_schema creates new tables
         let _ = db.migrate_add_change_ids_raw();
         db.init_schema()?;
@@ -959,6 +1897,71 @@ impl Database {
         )
         .execute(&mut conn)?;
 
+        diesel::sql_query(
+            r#"
+            CREATE TABLE IF NOT EXISTS node_tombstones (
+                change_id TEXT PRIMARY KEY NOT NULL,
+                deleted_at TEXT NOT NULL
+            )
+        "#,
+        )
+        .execute(&mut conn)?;
+
+        diesel::sql_query(
+            r#"
+            CREATE TABLE IF NOT EXISTS node_conflicts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+                change_id TEXT NOT NULL,
+                field TEXT NOT NULL,
+                local_value TEXT,
+                remote_value TEXT,
+                remote_updated_at TEXT NOT NULL,
+                patch_author TEXT,
+                resolution TEXT,
+                detected_at TEXT NOT NULL,
+                resolved_at TEXT
+            )
+        "#,
+        )
+        .execute(&mut conn)?;
+
+        diesel::sql_query(
+            r#"
+            CREATE TABLE IF NOT EXISTS node_exports (
+                id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+                change_id TEXT NOT NULL,
+                patch_path TEXT NOT NULL,
+                branch TEXT,
+                exported_at TEXT NOT NULL
+            )
+        "#,
+        )
+        .execute(&mut conn)?;
+
+        diesel::sql_query(
+            r#"
+            CREATE TABLE IF NOT EXISTS applied_patches (
+                id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+                patch_path TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                commit_sha TEXT,
+                applied_at TEXT NOT NULL,
+                author TEXT
+            )
+        "#,
+        )
+        .execute(&mut conn)?;
+
+        // Author attribution was added to applied_patches after it first shipped
+        let applied_columns: Vec<PragmaTableInfo> =
+            diesel::sql_query("PRAGMA table_info(applied_patches)")
+                .load(&mut conn)
+                .unwrap_or_default();
+        if !applied_columns.iter().any(|c| c.name == "author") {
+            diesel::sql_query("ALTER TABLE applied_patches ADD COLUMN author TEXT")
+                .execute(&mut conn)?;
+        }
+
         diesel::sql_query(
             r#"
             CREATE TABLE IF NOT EXISTS decision_context (
@@ -1022,6 +2025,22 @@ impl Database {
         )
         .execute(&mut conn)?;
 
+        diesel::sql_query(
+            r#"
+            CREATE TABLE IF NOT EXISTS command_changes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+                command_id INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                target_id INTEGER NOT NULL,
+                action TEXT NOT NULL,
+                detail TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (command_id) REFERENCES command_log(id)
+            )
+        "#,
+        )
+        .execute(&mut conn)?;
+
         // Roadmap Board Tables
         diesel::sql_query(
             r#"
@@ -1050,6 +2069,55 @@ impl Database {
         )
         .execute(&mut conn)?;
 
+        // Columns added to roadmap_items after it first shipped
+        let roadmap_columns: Vec<PragmaTableInfo> =
+            diesel::sql_query("PRAGMA table_info(roadmap_items)")
+                .load(&mut conn)
+                .unwrap_or_default();
+        for (column, sql_type) in [
+            ("priority", "INTEGER"),
+            ("due_date", "TEXT"),
+            ("goal_node_id", "INTEGER"),
+            ("goal_change_id", "TEXT"),
+            ("archived_at", "TEXT"),
+        ] {
+            if !roadmap_columns.iter().any(|c| c.name == column) {
+                diesel::sql_query(format!(
+                    "ALTER TABLE roadmap_items ADD COLUMN {} {}",
+                    column, sql_type
+                ))
+                .execute(&mut conn)?;
+            }
+        }
+
+        // Roadmap item-outcome links (an item can need several outcomes)
+        diesel::sql_query(
+            r#"
+            CREATE TABLE IF NOT EXISTS roadmap_item_outcomes (
+                roadmap_item_id INTEGER NOT NULL,
+                outcome_node_id INTEGER NOT NULL,
+                outcome_change_id TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (roadmap_item_id, outcome_node_id),
+                FOREIGN KEY (roadmap_item_id) REFERENCES roadmap_items(id),
+                FOREIGN KEY (outcome_node_id) REFERENCES decision_nodes(id)
+            )
+        "#,
+        )
+        .execute(&mut conn)?;
+
+        // Carry over links made before the join table existed
+        diesel::sql_query(
+            r#"
+            INSERT OR IGNORE INTO roadmap_item_outcomes
+                (roadmap_item_id, outcome_node_id, outcome_change_id, created_at)
+            SELECT id, outcome_node_id, outcome_change_id, updated_at
+            FROM roadmap_items
+            WHERE outcome_node_id IS NOT NULL AND outcome_change_id IS NOT NULL
+        "#,
+        )
+        .execute(&mut conn)?;
+
         diesel::sql_query(
             r#"
             CREATE TABLE IF NOT EXISTS roadmap_sync_state (
@@ -1102,6 +2170,24 @@ impl Database {
         )
         .execute(&mut conn)?;
 
+        // GitHub PR cache for nodes linked with --pr
+        diesel::sql_query(
+            r#"
+            CREATE TABLE IF NOT EXISTS github_pr_cache (
+                id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+                pr_number INTEGER NOT NULL,
+                repo TEXT NOT NULL,
+                title TEXT NOT NULL,
+                state TEXT NOT NULL,
+                head_branch TEXT NOT NULL,
+                html_url TEXT NOT NULL,
+                cached_at TEXT NOT NULL,
+                UNIQUE(repo, pr_number)
+            )
+        "#,
+        )
+        .execute(&mut conn)?;
+
         // Claude Trace Tables
         diesel::sql_query(
             r#"
@@ -1172,52 +2258,186 @@ impl Database {
         )
         .execute(&mut conn)?;
 
-        // Span-Node linking table (tracks which nodes were created during which spans)
         diesel::sql_query(
             r#"
-            CREATE TABLE IF NOT EXISTS span_nodes (
+            CREATE TABLE IF NOT EXISTS trace_redactions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
                 span_id INTEGER NOT NULL,
-                node_id INTEGER NOT NULL,
-                created_at TEXT NOT NULL,
-                PRIMARY KEY (span_id, node_id),
-                FOREIGN KEY (span_id) REFERENCES trace_spans(id),
-                FOREIGN KEY (node_id) REFERENCES decision_nodes(id)
+                content_type TEXT NOT NULL,
+                rule TEXT NOT NULL,
+                count INTEGER NOT NULL,
+                FOREIGN KEY (span_id) REFERENCES trace_spans(id)
             )
         "#,
         )
         .execute(&mut conn)?;
 
-        // Create indexes
-        diesel::sql_query("CREATE INDEX IF NOT EXISTS idx_nodes_type ON decision_nodes(node_type)")
-            .execute(&mut conn)?;
-        diesel::sql_query("CREATE INDEX IF NOT EXISTS idx_nodes_status ON decision_nodes(status)")
-            .execute(&mut conn)?;
         diesel::sql_query(
-            "CREATE INDEX IF NOT EXISTS idx_nodes_change_id ON decision_nodes(change_id)",
+            r#"
+            CREATE TABLE IF NOT EXISTS span_parents (
+                span_id INTEGER PRIMARY KEY NOT NULL,
+                parent_span_id INTEGER NOT NULL,
+                tool_use_id TEXT,
+                FOREIGN KEY (span_id) REFERENCES trace_spans(id),
+                FOREIGN KEY (parent_span_id) REFERENCES trace_spans(id)
+            )
+        "#,
         )
         .execute(&mut conn)?;
+
         diesel::sql_query(
-            "CREATE INDEX IF NOT EXISTS idx_edges_from ON decision_edges(from_node_id)",
+            r#"
+            CREATE TABLE IF NOT EXISTS span_streams (
+                span_id INTEGER PRIMARY KEY NOT NULL,
+                chunk_count INTEGER NOT NULL,
+                first_token_ms INTEGER,
+                complete BOOLEAN NOT NULL,
+                FOREIGN KEY (span_id) REFERENCES trace_spans(id)
+            )
+        "#,
         )
         .execute(&mut conn)?;
-        diesel::sql_query("CREATE INDEX IF NOT EXISTS idx_edges_to ON decision_edges(to_node_id)")
-            .execute(&mut conn)?;
+
         diesel::sql_query(
-            "CREATE INDEX IF NOT EXISTS idx_edges_from_change ON decision_edges(from_change_id)",
+            r#"
+            CREATE TABLE IF NOT EXISTS span_annotations (
+                id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+                span_id INTEGER NOT NULL,
+                note TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (span_id) REFERENCES trace_spans(id)
+            )
+        "#,
         )
         .execute(&mut conn)?;
+
         diesel::sql_query(
-            "CREATE INDEX IF NOT EXISTS idx_edges_to_change ON decision_edges(to_change_id)",
+            r#"
+            CREATE TABLE IF NOT EXISTS node_embeddings (
+                node_id INTEGER PRIMARY KEY NOT NULL,
+                model TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                vector BLOB NOT NULL,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (node_id) REFERENCES decision_nodes(id)
+            )
+        "#,
         )
         .execute(&mut conn)?;
+
         diesel::sql_query(
-            "CREATE INDEX IF NOT EXISTS idx_command_started_at ON command_log(started_at)",
+            r#"
+            CREATE TABLE IF NOT EXISTS federated_repos (
+                name TEXT PRIMARY KEY NOT NULL,
+                db_path TEXT NOT NULL,
+                added_at TEXT NOT NULL
+            )
+        "#,
         )
         .execute(&mut conn)?;
 
-        // Roadmap indexes
         diesel::sql_query(
-            "CREATE INDEX IF NOT EXISTS idx_roadmap_items_change_id ON roadmap_items(change_id)",
+            r#"
+            CREATE TABLE IF NOT EXISTS snapshots (
+                id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+                label TEXT NOT NULL UNIQUE,
+                created_at TEXT NOT NULL,
+                patch_json TEXT NOT NULL
+            )
+        "#,
+        )
+        .execute(&mut conn)?;
+
+        diesel::sql_query(
+            r#"
+            CREATE TABLE IF NOT EXISTS node_files (
+                node_id INTEGER NOT NULL,
+                path TEXT NOT NULL,
+                source TEXT NOT NULL,
+                node_updated_at TEXT NOT NULL,
+                PRIMARY KEY (node_id, path)
+            )
+        "#,
+        )
+        .execute(&mut conn)?;
+
+        diesel::sql_query(
+            r#"
+            CREATE TABLE IF NOT EXISTS focus (
+                id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+                goal_id INTEGER NOT NULL,
+                started_at TEXT NOT NULL,
+                expires_at TEXT,
+                FOREIGN KEY (goal_id) REFERENCES decision_nodes(id)
+            )
+        "#,
+        )
+        .execute(&mut conn)?;
+
+        diesel::sql_query(
+            r#"
+            CREATE TABLE IF NOT EXISTS span_commits (
+                span_id INTEGER NOT NULL,
+                commit_hash TEXT NOT NULL,
+                committed_at TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (span_id, commit_hash),
+                FOREIGN KEY (span_id) REFERENCES trace_spans(id)
+            )
+        "#,
+        )
+        .execute(&mut conn)?;
+
+        // Span-Node linking table (tracks which nodes were created during which spans)
+        diesel::sql_query(
+            r#"
+            CREATE TABLE IF NOT EXISTS span_nodes (
+                span_id INTEGER NOT NULL,
+                node_id INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (span_id, node_id),
+                FOREIGN KEY (span_id) REFERENCES trace_spans(id),
+                FOREIGN KEY (node_id) REFERENCES decision_nodes(id)
+            )
+        "#,
+        )
+        .execute(&mut conn)?;
+
+        // Create indexes
+        diesel::sql_query("CREATE INDEX IF NOT EXISTS idx_nodes_type ON decision_nodes(node_type)")
+            .execute(&mut conn)?;
+        diesel::sql_query("CREATE INDEX IF NOT EXISTS idx_nodes_status ON decision_nodes(status)")
+            .execute(&mut conn)?;
+        diesel::sql_query(
+            "CREATE INDEX IF NOT EXISTS idx_nodes_change_id ON decision_nodes(change_id)",
+        )
+        .execute(&mut conn)?;
+        diesel::sql_query(
+            "CREATE INDEX IF NOT EXISTS idx_edges_from ON decision_edges(from_node_id)",
+        )
+        .execute(&mut conn)?;
+        diesel::sql_query("CREATE INDEX IF NOT EXISTS idx_edges_to ON decision_edges(to_node_id)")
+            .execute(&mut conn)?;
+        diesel::sql_query(
+            "CREATE INDEX IF NOT EXISTS idx_edges_from_change ON decision_edges(from_change_id)",
+        )
+        .execute(&mut conn)?;
+        diesel::sql_query(
+            "CREATE INDEX IF NOT EXISTS idx_edges_to_change ON decision_edges(to_change_id)",
+        )
+        .execute(&mut conn)?;
+        diesel::sql_query(
+            "CREATE INDEX IF NOT EXISTS idx_command_started_at ON command_log(started_at)",
+        )
+        .execute(&mut conn)?;
+        diesel::sql_query(
+            "CREATE INDEX IF NOT EXISTS idx_command_changes_command ON command_changes(command_id)",
+        )
+        .execute(&mut conn)?;
+
+        // Roadmap indexes
+        diesel::sql_query(
+            "CREATE INDEX IF NOT EXISTS idx_roadmap_items_change_id ON roadmap_items(change_id)",
         )
         .execute(&mut conn)?;
         diesel::sql_query(
@@ -1226,6 +2446,7 @@ impl Database {
         .execute(&mut conn)?;
         diesel::sql_query("CREATE INDEX IF NOT EXISTS idx_roadmap_items_github_issue ON roadmap_items(github_issue_number)").execute(&mut conn)?;
         diesel::sql_query("CREATE INDEX IF NOT EXISTS idx_roadmap_items_outcome ON roadmap_items(outcome_change_id)").execute(&mut conn)?;
+        diesel::sql_query("CREATE INDEX IF NOT EXISTS idx_roadmap_item_outcomes_node ON roadmap_item_outcomes(outcome_node_id)").execute(&mut conn)?;
         diesel::sql_query("CREATE INDEX IF NOT EXISTS idx_roadmap_conflicts_item ON roadmap_conflicts(item_change_id)").execute(&mut conn)?;
         diesel::sql_query("CREATE INDEX IF NOT EXISTS idx_github_issue_cache_repo ON github_issue_cache(repo, issue_number)").execute(&mut conn)?;
 
@@ -1246,6 +2467,26 @@ impl Database {
             "CREATE INDEX IF NOT EXISTS idx_trace_content_span_id ON trace_content(span_id)",
         )
         .execute(&mut conn)?;
+        diesel::sql_query(
+            "CREATE INDEX IF NOT EXISTS idx_trace_content_tool_use_id ON trace_content(tool_use_id)",
+        )
+        .execute(&mut conn)?;
+        diesel::sql_query(
+            "CREATE INDEX IF NOT EXISTS idx_trace_redactions_span_id ON trace_redactions(span_id)",
+        )
+        .execute(&mut conn)?;
+        diesel::sql_query(
+            "CREATE INDEX IF NOT EXISTS idx_span_commits_hash ON span_commits(commit_hash)",
+        )
+        .execute(&mut conn)?;
+        diesel::sql_query(
+            "CREATE INDEX IF NOT EXISTS idx_span_parents_parent ON span_parents(parent_span_id)",
+        )
+        .execute(&mut conn)?;
+        diesel::sql_query(
+            "CREATE INDEX IF NOT EXISTS idx_span_annotations_span_id ON span_annotations(span_id)",
+        )
+        .execute(&mut conn)?;
 
         // Span-node linking indexes
         diesel::sql_query(
@@ -1359,105 +2600,56 @@ impl Database {
     // Decision Graph Operations
     // ========================================================================
 
-    /// Create a new decision node
-    pub fn create_node(
-        &self,
-        node_type: &str,
-        title: &str,
-        description: Option<&str>,
-        confidence: Option<u8>,
-        commit: Option<&str>,
-    ) -> Result<i32> {
-        self.create_node_full(
-            node_type,
-            title,
-            description,
-            confidence,
-            commit,
-            None,
-            None,
-            None,
-        )
-    }
-
-    /// Create a decision node with full metadata (including prompt and files)
-    pub fn create_node_full(
-        &self,
-        node_type: &str,
-        title: &str,
-        description: Option<&str>,
-        confidence: Option<u8>,
-        commit: Option<&str>,
-        prompt: Option<&str>,
-        files: Option<&str>,
-        branch: Option<&str>,
-    ) -> Result<i32> {
-        let mut conn = self.get_conn()?;
-        let now = chrono::Local::now().to_rfc3339();
-        let change_id = Uuid::new_v4().to_string();
-
-        // Build metadata JSON with all optional fields
-        let metadata = build_metadata_json(confidence, commit, prompt, files, branch);
-
-        let new_node = NewDecisionNode {
-            change_id: &change_id,
-            node_type,
-            title,
-            description,
-            status: "pending",
-            created_at: &now,
-            updated_at: &now,
-            metadata_json: metadata.as_deref(),
-        };
-
-        diesel::insert_into(decision_nodes::table)
-            .values(&new_node)
-            .execute(&mut conn)?;
-
-        let id: i32 = diesel::select(diesel::dsl::sql::<diesel::sql_types::Integer>(
-            "last_insert_rowid()",
-        ))
-        .first(&mut conn)?;
-
-        Ok(id)
+    /// Start building a node; see [`NodeBuilder`]
+    pub fn node(&self, node_type: &str, title: &str) -> NodeBuilder<'_> {
+        NodeBuilder::new(self, node_type, title)
     }
 
-    /// Add a node (alias for create_node for doc examples)
-    pub fn add_node(
-        &self,
-        node_type: &str,
-        title: &str,
-        description: Option<&str>,
-        confidence: Option<u8>,
-        commit: Option<&str>,
-    ) -> Result<i32> {
-        self.create_node(node_type, title, description, confidence, commit)
+    /// Start building an edge from one node to another; see [`EdgeBuilder`]
+    pub fn edge(&self, from_id: i32, to_id: i32) -> EdgeBuilder<'_> {
+        EdgeBuilder::new(self, from_id, to_id)
     }
 
-    /// Create a node with a specific change_id (for patch application)
-    pub fn create_node_with_change_id(
-        &self,
-        change_id: &str,
-        node_type: &str,
-        title: &str,
-        description: Option<&str>,
-        confidence: Option<u8>,
-        commit: Option<&str>,
-        prompt: Option<&str>,
-        files: Option<&str>,
-        branch: Option<&str>,
-    ) -> Result<i32> {
+    /// Insert a node from a builder; [`NodeBuilder::insert`] calls this
+    pub(crate) fn insert_node(&self, node: &NodeBuilder<'_>) -> Result<i32> {
         let mut conn = self.get_conn()?;
         let now = chrono::Local::now().to_rfc3339();
+        let change_id = node
+            .change_id
+            .clone()
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
 
         // Build metadata JSON with all optional fields
-        let metadata = build_metadata_json(confidence, commit, prompt, files, branch);
+        let metadata = build_metadata_json(
+            node.confidence,
+            node.commit.as_deref(),
+            node.prompt.as_deref(),
+            node.files.as_deref(),
+            node.branch.as_deref(),
+        );
+        // A node added through a scoped Database belongs to its project
+        let project = self.project.as_ref().or(node.project.as_ref());
+        let metadata = if project.is_some() || node.pr.is_some() {
+            let mut meta: serde_json::Value = metadata
+                .as_deref()
+                .and_then(|m| serde_json::from_str(m).ok())
+                .unwrap_or_else(|| json!({}));
+            if let Some(project) = project {
+                meta["project"] = json!(project);
+            }
+            if let Some(pr) = node.pr {
+                meta["pr"] = json!(pr);
+            }
+            Some(meta.to_string())
+        } else {
+            metadata
+        };
 
         let new_node = NewDecisionNode {
-            change_id,
-            node_type,
-            title,
-            description,
+            change_id: &change_id,
+            node_type: &node.node_type,
+            title: &node.title,
+            description: node.description.as_deref(),
             status: "pending",
             created_at: &now,
             updated_at: &now,
@@ -1472,18 +2664,25 @@ impl Database {
             "last_insert_rowid()",
         ))
         .first(&mut conn)?;
-
+        drop(conn);
+        self.record_change(
+            "node",
+            id,
+            "created",
+            &format!("[{}] {}", node.node_type, node.title),
+        )?;
+
+        self.emit(|| {
+            let node = self.get_node_by_id(id).ok()??;
+            Some(DbEvent::NodeAdded { node })
+        });
         Ok(id)
     }
 
-    /// Create an edge between nodes
-    pub fn create_edge(
-        &self,
-        from_id: i32,
-        to_id: i32,
-        edge_type: &str,
-        rationale: Option<&str>,
-    ) -> Result<i32> {
+    /// Insert an edge from a builder; [`EdgeBuilder::insert`] calls this
+    pub(crate) fn insert_edge(&self, edge: &EdgeBuilder<'_>) -> Result<i32> {
+        let (from_id, to_id) = (edge.from_id, edge.to_id);
+        self.check_scope(&[from_id, to_id])?;
         let mut conn = self.get_conn()?;
 
         // Validate both nodes exist and get their change_ids
@@ -1523,9 +2722,9 @@ impl Database {
             to_node_id: to_id,
             from_change_id: from_change_id.as_deref(),
             to_change_id: to_change_id.as_deref(),
-            edge_type,
+            edge_type: &edge.edge_type,
             weight: Some(1.0),
-            rationale,
+            rationale: edge.rationale.as_deref(),
             created_at: &now,
         };
 
@@ -1538,37 +2737,161 @@ impl Database {
         ))
         .first(&mut conn)?;
 
+        self.emit(|| {
+            let edge = decision_edges::table
+                .filter(decision_edges::id.eq(id))
+                .first::<DecisionEdge>(&mut conn)
+                .ok()?;
+            Some(DbEvent::EdgeAdded { edge })
+        });
+        drop(conn);
+        self.record_change(
+            "edge",
+            id,
+            "created",
+            &format!("{} -> {} ({})", from_id, to_id, edge.edge_type),
+        )?;
         Ok(id)
     }
 
-    /// Add an edge (alias for create_edge for doc examples)
-    pub fn add_edge(
+    /// Create a new decision node
+    pub fn create_node(
         &self,
-        from_id: i32,
-        to_id: i32,
-        edge_type: &str,
-        rationale: Option<&str>,
+        node_type: &str,
+        title: &str,
+        description: Option<&str>,
+        confidence: Option<u8>,
+        commit: Option<&str>,
     ) -> Result<i32> {
-        self.create_edge(from_id, to_id, edge_type, rationale)
+        self.create_node_full(
+            node_type,
+            title,
+            description,
+            confidence,
+            commit,
+            None,
+            None,
+            None,
+        )
     }
 
-    /// Update node status
-    pub fn update_node_status(&self, node_id: i32, status: &str) -> Result<()> {
-        let mut conn = self.get_conn()?;
-        let now = chrono::Local::now().to_rfc3339();
-
-        diesel::update(decision_nodes::table.filter(decision_nodes::id.eq(node_id)))
-            .set((
-                decision_nodes::status.eq(status),
-                decision_nodes::updated_at.eq(&now),
-            ))
-            .execute(&mut conn)?;
+    /// Create a decision node with full metadata (including prompt and files)
+    pub fn create_node_full(
+        &self,
+        node_type: &str,
+        title: &str,
+        description: Option<&str>,
+        confidence: Option<u8>,
+        commit: Option<&str>,
+        prompt: Option<&str>,
+        files: Option<&str>,
+        branch: Option<&str>,
+    ) -> Result<i32> {
+        NodeBuilder {
+            description: description.map(String::from),
+            confidence,
+            commit: commit.map(String::from),
+            prompt: prompt.map(String::from),
+            files: files.map(String::from),
+            branch: branch.map(String::from),
+            ..self.node(node_type, title)
+        }
+        .insert()
+    }
 
-        Ok(())
+    /// Add a node (alias for create_node for doc examples)
+    pub fn add_node(
+        &self,
+        node_type: &str,
+        title: &str,
+        description: Option<&str>,
+        confidence: Option<u8>,
+        commit: Option<&str>,
+    ) -> Result<i32> {
+        self.create_node(node_type, title, description, confidence, commit)
+    }
+
+    /// Create a node with a specific change_id (for patch application)
+    pub fn create_node_with_change_id(
+        &self,
+        change_id: &str,
+        node_type: &str,
+        title: &str,
+        description: Option<&str>,
+        confidence: Option<u8>,
+        commit: Option<&str>,
+        prompt: Option<&str>,
+        files: Option<&str>,
+        branch: Option<&str>,
+    ) -> Result<i32> {
+        NodeBuilder {
+            change_id: Some(change_id.to_string()),
+            description: description.map(String::from),
+            confidence,
+            commit: commit.map(String::from),
+            prompt: prompt.map(String::from),
+            files: files.map(String::from),
+            branch: branch.map(String::from),
+            ..self.node(node_type, title)
+        }
+        .insert()
+    }
+
+    /// Create an edge between nodes
+    pub fn create_edge(
+        &self,
+        from_id: i32,
+        to_id: i32,
+        edge_type: &str,
+        rationale: Option<&str>,
+    ) -> Result<i32> {
+        EdgeBuilder {
+            rationale: rationale.map(String::from),
+            ..self.edge(from_id, to_id).edge_type(edge_type)
+        }
+        .insert()
+    }
+
+    /// Add an edge (alias for create_edge for doc examples)
+    pub fn add_edge(
+        &self,
+        from_id: i32,
+        to_id: i32,
+        edge_type: &str,
+        rationale: Option<&str>,
+    ) -> Result<i32> {
+        self.create_edge(from_id, to_id, edge_type, rationale)
+    }
+
+    /// Update node status
+    pub fn update_node_status(&self, node_id: i32, status: &str) -> Result<()> {
+        self.check_scope(&[node_id])?;
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+
+        let updated = diesel::update(decision_nodes::table.filter(decision_nodes::id.eq(node_id)))
+            .set((
+                decision_nodes::status.eq(status),
+                decision_nodes::updated_at.eq(&now),
+            ))
+            .execute(&mut conn)?;
+        drop(conn);
+
+        if updated > 0 {
+            self.record_change("node", node_id, "updated", &format!("status = {}", status))?;
+        }
+        self.emit(|| {
+            (updated > 0).then(|| DbEvent::StatusChanged {
+                node_id,
+                status: status.to_string(),
+            })
+        });
+        Ok(())
     }
 
     /// Update a node's commit hash in metadata_json
     pub fn update_node_commit(&self, node_id: i32, commit_hash: &str) -> Result<()> {
+        self.check_scope(&[node_id])?;
         let mut conn = self.get_conn()?;
         let now = chrono::Local::now().to_rfc3339();
 
@@ -1598,68 +2921,615 @@ impl Database {
                 decision_nodes::updated_at.eq(&now),
             ))
             .execute(&mut conn)?;
-
+
+        drop(conn);
+        self.record_change("node", node_id, "updated", "metadata.commit")
+    }
+
+    /// Update a node's prompt in metadata_json
+    pub fn update_node_prompt(&self, node_id: i32, prompt: &str) -> Result<()> {
+        self.check_scope(&[node_id])?;
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+
+        // Get current metadata
+        let current_meta: Option<String> = decision_nodes::table
+            .filter(decision_nodes::id.eq(node_id))
+            .select(decision_nodes::metadata_json)
+            .first(&mut conn)?;
+
+        // Parse existing metadata or create new
+        let mut meta: serde_json::Value = current_meta
+            .as_ref()
+            .and_then(|m| serde_json::from_str(m).ok())
+            .unwrap_or_else(|| serde_json::json!({}));
+
+        // Add/update prompt field
+        if let Some(obj) = meta.as_object_mut() {
+            obj.insert("prompt".to_string(), serde_json::json!(prompt));
+        }
+
+        let new_meta = serde_json::to_string(&meta)
+            .map_err(|e| DbError::Validation(format!("JSON serialization error: {}", e)))?;
+
+        diesel::update(decision_nodes::table.filter(decision_nodes::id.eq(node_id)))
+            .set((
+                decision_nodes::metadata_json.eq(Some(new_meta)),
+                decision_nodes::updated_at.eq(&now),
+            ))
+            .execute(&mut conn)?;
+
+        drop(conn);
+        self.record_change("node", node_id, "updated", "metadata.prompt")
+    }
+
+    /// Set an arbitrary key in a node's metadata_json, preserving other fields
+    pub fn update_node_metadata_field(
+        &self,
+        node_id: i32,
+        key: &str,
+        value: serde_json::Value,
+    ) -> Result<()> {
+        self.check_scope(&[node_id])?;
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+
+        let current_meta: Option<String> = decision_nodes::table
+            .filter(decision_nodes::id.eq(node_id))
+            .select(decision_nodes::metadata_json)
+            .first(&mut conn)?;
+
+        let mut meta: serde_json::Value = current_meta
+            .as_ref()
+            .and_then(|m| serde_json::from_str(m).ok())
+            .unwrap_or_else(|| serde_json::json!({}));
+
+        if let Some(obj) = meta.as_object_mut() {
+            obj.insert(key.to_string(), value);
+        }
+
+        let new_meta = serde_json::to_string(&meta)
+            .map_err(|e| DbError::Validation(format!("JSON serialization error: {}", e)))?;
+
+        diesel::update(decision_nodes::table.filter(decision_nodes::id.eq(node_id)))
+            .set((
+                decision_nodes::metadata_json.eq(Some(new_meta)),
+                decision_nodes::updated_at.eq(&now),
+            ))
+            .execute(&mut conn)?;
+
+        drop(conn);
+        self.record_change("node", node_id, "updated", &format!("metadata.{}", key))
+    }
+
+    /// Update a node's title and description
+    pub fn update_node_content(
+        &self,
+        node_id: i32,
+        title: &str,
+        description: Option<&str>,
+    ) -> Result<()> {
+        self.check_scope(&[node_id])?;
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+
+        diesel::update(decision_nodes::table.filter(decision_nodes::id.eq(node_id)))
+            .set((
+                decision_nodes::title.eq(title),
+                decision_nodes::description.eq(description),
+                decision_nodes::updated_at.eq(&now),
+            ))
+            .execute(&mut conn)?;
+
+        drop(conn);
+        self.record_change("node", node_id, "updated", "title, description")
+    }
+
+    /// Overwrite a node's editable fields, stamping it with `updated_at`
+    ///
+    /// Used when merging patches, so the node keeps the time of the change
+    /// rather than the time it was applied.
+    pub fn replace_node_fields(
+        &self,
+        node_id: i32,
+        title: &str,
+        description: Option<&str>,
+        status: &str,
+        metadata_json: Option<&str>,
+        updated_at: &str,
+    ) -> Result<()> {
+        self.check_scope(&[node_id])?;
+        let mut conn = self.get_conn()?;
+
+        diesel::update(decision_nodes::table.filter(decision_nodes::id.eq(node_id)))
+            .set((
+                decision_nodes::title.eq(title),
+                decision_nodes::description.eq(description),
+                decision_nodes::status.eq(status),
+                decision_nodes::metadata_json.eq(metadata_json),
+                decision_nodes::updated_at.eq(updated_at),
+            ))
+            .execute(&mut conn)?;
+
+        drop(conn);
+        self.record_change(
+            "node",
+            node_id,
+            "updated",
+            "title, description, status, metadata",
+        )
+    }
+
+    /// Delete a node with its edges and links, leaving a tombstone
+    ///
+    /// Returns false if there was no such node.
+    pub fn delete_node(&self, node_id: i32) -> Result<bool> {
+        self.delete_node_at(node_id, &chrono::Local::now().to_rfc3339())
+    }
+
+    /// Delete a node, recording `deleted_at` as the time of deletion
+    pub fn delete_node_at(&self, node_id: i32, deleted_at: &str) -> Result<bool> {
+        self.check_scope(&[node_id])?;
+        let mut conn = self.get_conn()?;
+        let Some(node) = decision_nodes::table
+            .filter(decision_nodes::id.eq(node_id))
+            .first::<DecisionNode>(&mut conn)
+            .optional()?
+        else {
+            return Ok(false);
+        };
+
+        let edges: Vec<DecisionEdge> = decision_edges::table
+            .filter(
+                decision_edges::from_node_id
+                    .eq(node_id)
+                    .or(decision_edges::to_node_id.eq(node_id)),
+            )
+            .load(&mut conn)?;
+        diesel::delete(
+            decision_edges::table.filter(
+                decision_edges::from_node_id
+                    .eq(node_id)
+                    .or(decision_edges::to_node_id.eq(node_id)),
+            ),
+        )
+        .execute(&mut conn)?;
+        diesel::delete(decision_context::table.filter(decision_context::node_id.eq(node_id)))
+            .execute(&mut conn)?;
+        diesel::delete(session_nodes::table.filter(session_nodes::node_id.eq(node_id)))
+            .execute(&mut conn)?;
+        diesel::delete(span_nodes::table.filter(span_nodes::node_id.eq(node_id)))
+            .execute(&mut conn)?;
+        diesel::delete(node_embeddings::table.filter(node_embeddings::node_id.eq(node_id)))
+            .execute(&mut conn)?;
+        diesel::delete(node_files::table.filter(node_files::node_id.eq(node_id)))
+            .execute(&mut conn)?;
+        diesel::delete(focus::table.filter(focus::goal_id.eq(node_id))).execute(&mut conn)?;
+        diesel::delete(
+            roadmap_item_outcomes::table.filter(roadmap_item_outcomes::outcome_node_id.eq(node_id)),
+        )
+        .execute(&mut conn)?;
+
+        // Nullable references are cleared rather than deleted
+        diesel::update(trace_spans::table.filter(trace_spans::linked_node_id.eq(node_id)))
+            .set((
+                trace_spans::linked_node_id.eq(None::<i32>),
+                trace_spans::linked_change_id.eq(None::<String>),
+            ))
+            .execute(&mut conn)?;
+        diesel::update(trace_sessions::table.filter(trace_sessions::linked_node_id.eq(node_id)))
+            .set((
+                trace_sessions::linked_node_id.eq(None::<i32>),
+                trace_sessions::linked_change_id.eq(None::<String>),
+            ))
+            .execute(&mut conn)?;
+        diesel::update(roadmap_items::table.filter(roadmap_items::outcome_node_id.eq(node_id)))
+            .set((
+                roadmap_items::outcome_node_id.eq(None::<i32>),
+                roadmap_items::outcome_change_id.eq(None::<String>),
+            ))
+            .execute(&mut conn)?;
+        diesel::update(roadmap_items::table.filter(roadmap_items::goal_node_id.eq(node_id)))
+            .set((
+                roadmap_items::goal_node_id.eq(None::<i32>),
+                roadmap_items::goal_change_id.eq(None::<String>),
+            ))
+            .execute(&mut conn)?;
+        diesel::update(command_log::table.filter(command_log::decision_node_id.eq(node_id)))
+            .set(command_log::decision_node_id.eq(None::<i32>))
+            .execute(&mut conn)?;
+        diesel::update(
+            decision_sessions::table.filter(decision_sessions::root_node_id.eq(node_id)),
+        )
+        .set(decision_sessions::root_node_id.eq(None::<i32>))
+        .execute(&mut conn)?;
+
+        diesel::delete(node_conflicts::table.filter(node_conflicts::change_id.eq(&node.change_id)))
+            .execute(&mut conn)?;
+
+        diesel::delete(decision_nodes::table.filter(decision_nodes::id.eq(node_id)))
+            .execute(&mut conn)?;
+        diesel::replace_into(node_tombstones::table)
+            .values(&NewNodeTombstone {
+                change_id: &node.change_id,
+                deleted_at,
+            })
+            .execute(&mut conn)?;
+        drop(conn);
+
+        for edge in &edges {
+            self.record_change(
+                "edge",
+                edge.id,
+                "deleted",
+                &format!(
+                    "{} -> {} ({})",
+                    edge.from_node_id, edge.to_node_id, edge.edge_type
+                ),
+            )?;
+        }
+        self.record_change(
+            "node",
+            node_id,
+            "deleted",
+            &format!("[{}] {}", node.node_type, node.title),
+        )?;
+        Ok(true)
+    }
+
+    /// Fold node `drop` into node `keep`, then delete `drop`
+    ///
+    /// `drop`'s edges are re-created on `keep` (skipping ones `keep` already
+    /// has and ones between the two), its span links move over, and `keep`
+    /// takes its description, prompt and commit where it has none, and the
+    /// union of both nodes' files. Returns false if either node is missing.
+    pub fn merge_nodes(&self, keep: i32, drop: i32) -> Result<bool> {
+        if keep == drop {
+            return Err(DbError::Validation(format!(
+                "Cannot merge node {} into itself",
+                keep
+            )));
+        }
+        self.check_scope(&[keep, drop])?;
+        let (Some(kept), Some(dropped)) = (self.get_node_by_id(keep)?, self.get_node_by_id(drop)?)
+        else {
+            return Ok(false);
+        };
+
+        let edges: Vec<DecisionEdge> = {
+            let mut conn = self.get_conn()?;
+            decision_edges::table.load(&mut conn)?
+        };
+        let has_edge = |from: i32, to: i32, edge_type: &str| {
+            edges
+                .iter()
+                .any(|e| e.from_node_id == from && e.to_node_id == to && e.edge_type == edge_type)
+        };
+        for edge in &edges {
+            let (from, to) = match (edge.from_node_id == drop, edge.to_node_id == drop) {
+                (true, false) => (keep, edge.to_node_id),
+                (false, true) => (edge.from_node_id, keep),
+                _ => continue,
+            };
+            if from != to && !has_edge(from, to, &edge.edge_type) {
+                self.create_edge(from, to, &edge.edge_type, edge.rationale.as_deref())?;
+            }
+        }
+
+        let meta = |node: &DecisionNode| -> serde_json::Value {
+            node.metadata_json
+                .as_deref()
+                .and_then(|m| serde_json::from_str(m).ok())
+                .unwrap_or_else(|| json!({}))
+        };
+        let (kept_meta, dropped_meta) = (meta(&kept), meta(&dropped));
+        let present = |v: &serde_json::Value| v.as_str().is_some_and(|s| !s.is_empty());
+        for key in ["prompt", "commit"] {
+            if !present(&kept_meta[key]) && present(&dropped_meta[key]) {
+                self.update_node_metadata_field(keep, key, dropped_meta[key].clone())?;
+            }
+        }
+        let mut files: Vec<&serde_json::Value> = Vec::new();
+        for list in [&kept_meta["files"], &dropped_meta["files"]] {
+            for file in list.as_array().into_iter().flatten() {
+                if !files.contains(&file) {
+                    files.push(file);
+                }
+            }
+        }
+        if files.len() > kept_meta["files"].as_array().map(Vec::len).unwrap_or(0) {
+            self.update_node_metadata_field(keep, "files", json!(files))?;
+        }
+        if kept.description.as_deref().unwrap_or_default().is_empty() {
+            if let Some(description) = dropped.description.as_deref().filter(|d| !d.is_empty()) {
+                self.update_node_content(keep, &kept.title, Some(description))?;
+            }
+        }
+
+        let span_ids: Vec<i32> = {
+            let mut conn = self.get_conn()?;
+            span_nodes::table
+                .filter(span_nodes::node_id.eq(drop))
+                .select(span_nodes::span_id)
+                .load(&mut conn)?
+        };
+        for span_id in span_ids {
+            self.link_span_to_node_via_table(span_id, keep)?;
+        }
+
+        self.delete_node(drop)
+    }
+
+    /// Remember that a node was deleted elsewhere; keeps an existing tombstone
+    pub fn add_tombstone(&self, change_id: &str, deleted_at: &str) -> Result<()> {
+        let mut conn = self.get_conn()?;
+        diesel::insert_or_ignore_into(node_tombstones::table)
+            .values(&NewNodeTombstone {
+                change_id,
+                deleted_at,
+            })
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    /// All recorded node deletions
+    pub fn get_tombstones(&self) -> Result<Vec<NodeTombstone>> {
+        let mut conn = self.get_conn()?;
+        let tombstones = node_tombstones::table
+            .order(node_tombstones::deleted_at.asc())
+            .load::<NodeTombstone>(&mut conn)?;
+        Ok(tombstones)
+    }
+
+    /// Set a node's creation time, e.g. to the author's when applying a patch
+    pub fn set_node_created_at(&self, node_id: i32, created_at: &str) -> Result<()> {
+        self.check_scope(&[node_id])?;
+        let mut conn = self.get_conn()?;
+        diesel::update(decision_nodes::table.filter(decision_nodes::id.eq(node_id)))
+            .set(decision_nodes::created_at.eq(created_at))
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    /// Record a node conflict, optionally already resolved
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_node_conflict(
+        &self,
+        change_id: &str,
+        field: &str,
+        local_value: Option<&str>,
+        remote_value: Option<&str>,
+        remote_updated_at: &str,
+        patch_author: Option<&str>,
+        resolution: Option<&str>,
+    ) -> Result<i32> {
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+
+        diesel::insert_into(node_conflicts::table)
+            .values(&NewNodeConflict {
+                change_id,
+                field,
+                local_value,
+                remote_value,
+                remote_updated_at,
+                patch_author,
+                resolution,
+                detected_at: &now,
+                resolved_at: resolution.map(|_| now.as_str()),
+            })
+            .execute(&mut conn)?;
+
+        let id: i32 = diesel::select(diesel::dsl::sql::<diesel::sql_types::Integer>(
+            "last_insert_rowid()",
+        ))
+        .first(&mut conn)?;
+
+        Ok(id)
+    }
+
+    /// A conflict already recorded for this incoming value of a field
+    pub fn find_node_conflict(
+        &self,
+        change_id: &str,
+        field: &str,
+        remote_value: Option<&str>,
+    ) -> Result<Option<NodeConflict>> {
+        let mut conn = self.get_conn()?;
+        let conflicts = node_conflicts::table
+            .filter(node_conflicts::change_id.eq(change_id))
+            .filter(node_conflicts::field.eq(field))
+            .order(node_conflicts::id.desc())
+            .load::<NodeConflict>(&mut conn)?;
+        Ok(conflicts
+            .into_iter()
+            .find(|c| c.remote_value.as_deref() == remote_value))
+    }
+
+    /// Get a node conflict by ID
+    pub fn get_node_conflict(&self, conflict_id: i32) -> Result<Option<NodeConflict>> {
+        let mut conn = self.get_conn()?;
+        let conflict = node_conflicts::table
+            .filter(node_conflicts::id.eq(conflict_id))
+            .first::<NodeConflict>(&mut conn)
+            .optional()?;
+        Ok(conflict)
+    }
+
+    /// Node conflicts, oldest first; resolved ones only with `include_resolved`
+    pub fn get_node_conflicts(&self, include_resolved: bool) -> Result<Vec<NodeConflict>> {
+        let mut conn = self.get_conn()?;
+        let mut query = node_conflicts::table
+            .order(node_conflicts::id.asc())
+            .into_boxed();
+        if !include_resolved {
+            query = query.filter(node_conflicts::resolution.is_null());
+        }
+        Ok(query.load::<NodeConflict>(&mut conn)?)
+    }
+
+    /// Mark a node conflict resolved
+    pub fn resolve_node_conflict(&self, conflict_id: i32, resolution: &str) -> Result<()> {
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+
+        diesel::update(node_conflicts::table.filter(node_conflicts::id.eq(conflict_id)))
+            .set((
+                node_conflicts::resolution.eq(Some(resolution)),
+                node_conflicts::resolved_at.eq(Some(&now)),
+            ))
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    /// Record that nodes were written to a patch file
+    pub fn record_node_exports(
+        &self,
+        change_ids: &[&str],
+        patch_path: &str,
+        branch: Option<&str>,
+    ) -> Result<()> {
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+        let rows: Vec<NewNodeExport> = change_ids
+            .iter()
+            .map(|change_id| NewNodeExport {
+                change_id,
+                patch_path,
+                branch,
+                exported_at: &now,
+            })
+            .collect();
+        diesel::insert_into(node_exports::table)
+            .values(&rows)
+            .execute(&mut conn)?;
         Ok(())
     }
 
-    /// Update a node's prompt in metadata_json
-    pub fn update_node_prompt(&self, node_id: i32, prompt: &str) -> Result<()> {
+    /// Every recorded export, oldest first
+    pub fn get_node_exports(&self) -> Result<Vec<NodeExport>> {
+        let mut conn = self.get_conn()?;
+        Ok(node_exports::table
+            .order(node_exports::id.asc())
+            .load::<NodeExport>(&mut conn)?)
+    }
+
+    /// Record that a patch file was applied
+    pub fn record_applied_patch(
+        &self,
+        patch_path: &str,
+        content_hash: &str,
+        commit_sha: Option<&str>,
+        author: Option<&str>,
+    ) -> Result<()> {
         let mut conn = self.get_conn()?;
         let now = chrono::Local::now().to_rfc3339();
+        diesel::insert_into(applied_patches::table)
+            .values(&NewAppliedPatch {
+                patch_path,
+                content_hash,
+                commit_sha,
+                applied_at: &now,
+                author,
+            })
+            .execute(&mut conn)?;
+        Ok(())
+    }
 
-        // Get current metadata
-        let current_meta: Option<String> = decision_nodes::table
-            .filter(decision_nodes::id.eq(node_id))
-            .select(decision_nodes::metadata_json)
-            .first(&mut conn)?;
+    /// Every applied patch, oldest first
+    pub fn get_applied_patches(&self) -> Result<Vec<AppliedPatch>> {
+        let mut conn = self.get_conn()?;
+        Ok(applied_patches::table
+            .order(applied_patches::id.asc())
+            .load::<AppliedPatch>(&mut conn)?)
+    }
 
-        // Parse existing metadata or create new
-        let mut meta: serde_json::Value = current_meta
-            .as_ref()
-            .and_then(|m| serde_json::from_str(m).ok())
-            .unwrap_or_else(|| serde_json::json!({}));
+    /// Find the first node whose metadata `key` object satisfies `matches`
+    fn find_node_by_metadata<F>(&self, key: &str, matches: F) -> Result<Option<DecisionNode>>
+    where
+        F: Fn(&serde_json::Value) -> bool,
+    {
+        let mut conn = self.get_conn()?;
+        let pattern = format!("%\"{}\"%", key);
+        let candidates = decision_nodes::table
+            .filter(decision_nodes::metadata_json.like(pattern))
+            .load::<DecisionNode>(&mut conn)?;
 
-        // Add/update prompt field
-        if let Some(obj) = meta.as_object_mut() {
-            obj.insert("prompt".to_string(), serde_json::json!(prompt));
-        }
+        Ok(candidates.into_iter().find(|node| {
+            node.metadata_json
+                .as_deref()
+                .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+                .and_then(|meta| meta.get(key).map(&matches))
+                .unwrap_or(false)
+        }))
+    }
 
-        let new_meta = serde_json::to_string(&meta)
-            .map_err(|e| DbError::Validation(format!("JSON serialization error: {}", e)))?;
+    /// Find the node imported from a forge issue (metadata `issue.repo` + `issue.number`)
+    pub fn find_node_by_issue(&self, repo: &str, number: i32) -> Result<Option<DecisionNode>> {
+        self.find_node_by_metadata("issue", |issue| {
+            issue.get("repo").and_then(|r| r.as_str()) == Some(repo)
+                && issue.get("number").and_then(|n| n.as_i64()) == Some(number as i64)
+        })
+    }
 
-        diesel::update(decision_nodes::table.filter(decision_nodes::id.eq(node_id)))
-            .set((
-                decision_nodes::metadata_json.eq(Some(new_meta)),
-                decision_nodes::updated_at.eq(&now),
-            ))
-            .execute(&mut conn)?;
+    /// Find the observation imported from an issue comment (metadata `issue_comment`)
+    pub fn find_node_by_issue_comment(
+        &self,
+        repo: &str,
+        comment_id: i64,
+    ) -> Result<Option<DecisionNode>> {
+        self.find_node_by_metadata("issue_comment", |comment| {
+            comment.get("repo").and_then(|r| r.as_str()) == Some(repo)
+                && comment.get("id").and_then(|n| n.as_i64()) == Some(comment_id)
+        })
+    }
 
-        Ok(())
+    /// Find the node imported from a PR review thread (metadata `review_thread`)
+    pub fn find_node_by_review_thread(
+        &self,
+        repo: &str,
+        thread_id: &str,
+    ) -> Result<Option<DecisionNode>> {
+        self.find_node_by_metadata("review_thread", |thread| {
+            thread.get("repo").and_then(|r| r.as_str()) == Some(repo)
+                && thread.get("id").and_then(|i| i.as_str()) == Some(thread_id)
+        })
+    }
+
+    /// Find the decision imported from an ADR file (metadata `adr.path`)
+    pub fn find_node_by_adr(&self, path: &str) -> Result<Option<DecisionNode>> {
+        self.find_node_by_metadata("adr", |adr| {
+            adr.get("path").and_then(|p| p.as_str()) == Some(path)
+        })
     }
 
-    /// Get all nodes
+    /// Get all nodes (in the project scope, if set)
     pub fn get_all_nodes(&self) -> Result<Vec<DecisionNode>> {
         let mut conn = self.get_conn()?;
-        let nodes = decision_nodes::table
+        let nodes = self
+            .scoped_nodes()
             .order(decision_nodes::created_at.asc())
             .load::<DecisionNode>(&mut conn)?;
         Ok(nodes)
     }
 
-    /// Get a single node by ID
+    /// Get a single node by ID (None for nodes outside the project scope)
     pub fn get_node_by_id(&self, node_id: i32) -> Result<Option<DecisionNode>> {
         let mut conn = self.get_conn()?;
-        let node = decision_nodes::table
+        let node = self
+            .scoped_nodes()
             .filter(decision_nodes::id.eq(node_id))
             .first::<DecisionNode>(&mut conn)
             .optional()?;
         Ok(node)
     }
 
-    /// Get all edges
+    /// Get all edges (between nodes in the project scope, if set)
     pub fn get_all_edges(&self) -> Result<Vec<DecisionEdge>> {
         let mut conn = self.get_conn()?;
-        let edges = decision_edges::table
+        let edges = self
+            .scoped_edges()
             .order(decision_edges::created_at.asc())
             .load::<DecisionEdge>(&mut conn)?;
         Ok(edges)
@@ -1674,7 +3544,8 @@ impl Database {
             .select(decision_edges::to_node_id)
             .load(&mut conn)?;
 
-        let children = decision_nodes::table
+        let children = self
+            .scoped_nodes()
             .filter(decision_nodes::id.eq_any(child_ids))
             .load::<DecisionNode>(&mut conn)?;
 
@@ -1690,7 +3561,8 @@ impl Database {
             .select(decision_edges::from_node_id)
             .load(&mut conn)?;
 
-        let parents = decision_nodes::table
+        let parents = self
+            .scoped_nodes()
             .filter(decision_nodes::id.eq_any(parent_ids))
             .load::<DecisionNode>(&mut conn)?;
 
@@ -1701,10 +3573,12 @@ impl Database {
     pub fn get_graph(&self) -> Result<DecisionGraph> {
         let nodes = self.get_all_nodes()?;
         let edges = self.get_all_edges()?;
+        let pull_requests = self.get_all_cached_pull_requests()?;
         Ok(DecisionGraph {
             nodes,
             edges,
             config: None,
+            pull_requests,
         })
     }
 
@@ -1715,13 +3589,197 @@ impl Database {
     ) -> Result<DecisionGraph> {
         let nodes = self.get_all_nodes()?;
         let edges = self.get_all_edges()?;
+        let pull_requests = self.get_all_cached_pull_requests()?;
         Ok(DecisionGraph {
             nodes,
             edges,
             config,
+            pull_requests,
+        })
+    }
+
+    fn node_batch(&self, after: i32, limit: i64) -> Result<Vec<DecisionNode>> {
+        let mut conn = self.get_conn()?;
+        Ok(self
+            .scoped_nodes()
+            .filter(decision_nodes::id.gt(after))
+            .order(decision_nodes::id.asc())
+            .limit(limit)
+            .load(&mut conn)?)
+    }
+
+    fn edge_batch(&self, after: i32, limit: i64) -> Result<Vec<DecisionEdge>> {
+        let mut conn = self.get_conn()?;
+        Ok(self
+            .scoped_edges()
+            .filter(decision_edges::id.gt(after))
+            .order(decision_edges::id.asc())
+            .limit(limit)
+            .load(&mut conn)?)
+    }
+
+    /// All nodes in id order, read `batch` rows at a time
+    ///
+    /// Unlike `get_all_nodes`, only one batch is in memory at once. Each batch
+    /// is its own query, so rows written while iterating may or may not be seen.
+    pub fn iter_nodes(&self, batch: usize) -> Cursor<'_, DecisionNode> {
+        Cursor::new(self, batch, Database::node_batch, |n| n.id)
+    }
+
+    /// All edges in id order, read `batch` rows at a time
+    pub fn iter_edges(&self, batch: usize) -> Cursor<'_, DecisionEdge> {
+        Cursor::new(self, batch, Database::edge_batch, |e| e.id)
+    }
+
+    /// Up to `limit` nodes with ids after `after`, and the edges into them
+    ///
+    /// Pass the page's `next` as `after` to get the following page. Each edge
+    /// comes with the page holding its target node, so the pages together
+    /// hold every edge once.
+    pub fn get_graph_page(&self, after: Option<i32>, limit: usize) -> Result<GraphPage> {
+        let after_id = after.unwrap_or(i32::MIN);
+        let limit = limit.max(1);
+        let nodes = self.node_batch(after_id, limit as i64)?;
+        let (edges, next) = match nodes.last() {
+            Some(last) => {
+                let mut conn = self.get_conn()?;
+                let edges = self
+                    .scoped_edges()
+                    .filter(decision_edges::to_node_id.gt(after_id))
+                    .filter(decision_edges::to_node_id.le(last.id))
+                    .order(decision_edges::id.asc())
+                    .load(&mut conn)?;
+                (edges, (nodes.len() == limit).then_some(last.id))
+            }
+            None => (Vec::new(), None),
+        };
+        // Small, and needed to draw the first nodes
+        let pull_requests = if after.is_none() {
+            self.get_all_cached_pull_requests()?
+        } else {
+            Vec::new()
+        };
+        Ok(GraphPage {
+            nodes,
+            edges,
+            config: None,
+            pull_requests,
+            next,
         })
     }
 
+    /// Write the graph as `deciduous graph` prints it, without loading it all
+    ///
+    /// The output is pretty-printed `get_graph()`, with nodes and edges in id
+    /// order.
+    pub fn write_graph_json(&self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        self.stream_graph_json(out, None, |_| true, |_| ())
+            .map(|_| ())
+    }
+
+    /// `write_graph_json` with config, passing each row through the callers
+    ///
+    /// `node` may edit a node, or return false to leave it out; edges touching
+    /// a left-out node are left out too. `edge` may edit the edges written.
+    /// The output is pretty-printed `get_graph_with_config(config)` after the
+    /// same changes. Returns the number of nodes and edges written.
+    pub fn stream_graph_json(
+        &self,
+        out: &mut impl std::io::Write,
+        config: Option<&crate::config::Config>,
+        mut node: impl FnMut(&mut DecisionNode) -> bool,
+        mut edge: impl FnMut(&mut DecisionEdge),
+    ) -> std::io::Result<(usize, usize)> {
+        fn array<T: serde::Serialize>(
+            out: &mut impl std::io::Write,
+            key: &str,
+            items: impl Iterator<Item = Result<Option<T>>>,
+        ) -> std::io::Result<usize> {
+            write!(out, "  \"{}\": [", key)?;
+            let mut count = 0;
+            for item in items {
+                let Some(item) = item.map_err(io_error)? else {
+                    continue;
+                };
+                out.write_all(if count == 0 { b"\n    " } else { b",\n    " })?;
+                indented(out, &item, "    ")?;
+                count += 1;
+            }
+            out.write_all(if count == 0 { b"]" } else { b"\n  ]" })?;
+            Ok(count)
+        }
+
+        /// `item` pretty-printed, with `indent` before each line after the first
+        fn indented(
+            out: &mut impl std::io::Write,
+            item: &impl serde::Serialize,
+            indent: &str,
+        ) -> std::io::Result<()> {
+            let json = serde_json::to_string_pretty(item)?;
+            for (i, line) in json.lines().enumerate() {
+                if i > 0 {
+                    out.write_all(b"\n")?;
+                    out.write_all(indent.as_bytes())?;
+                }
+                out.write_all(line.as_bytes())?;
+            }
+            Ok(())
+        }
+
+        fn io_error(e: DbError) -> std::io::Error {
+            std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
+        }
+
+        const BATCH: usize = 1000;
+        let pull_requests = self.get_all_cached_pull_requests().map_err(io_error)?;
+        // Only the ids of left-out nodes are kept, usually none
+        let mut dropped = std::collections::HashSet::new();
+        out.write_all(b"{\n")?;
+        let nodes = array(
+            out,
+            "nodes",
+            self.iter_nodes(BATCH).map(|row| {
+                row.map(|mut n| {
+                    if node(&mut n) {
+                        Some(n)
+                    } else {
+                        dropped.insert(n.id);
+                        None
+                    }
+                })
+            }),
+        )?;
+        out.write_all(b",\n")?;
+        let edges = array(
+            out,
+            "edges",
+            self.iter_edges(BATCH).map(|row| {
+                row.map(|mut e| {
+                    let kept =
+                        !dropped.contains(&e.from_node_id) && !dropped.contains(&e.to_node_id);
+                    kept.then(|| {
+                        edge(&mut e);
+                        e
+                    })
+                })
+            }),
+        )?;
+        if let Some(config) = config {
+            out.write_all(b",\n  \"config\": ")?;
+            indented(out, config, "  ")?;
+        }
+        if !pull_requests.is_empty() {
+            out.write_all(b",\n")?;
+            array(
+                out,
+                "pull_requests",
+                pull_requests.into_iter().map(|p| Ok(Some(p))),
+            )?;
+        }
+        out.write_all(b"\n}\n")?;
+        Ok((nodes, edges))
+    }
+
     // ========================================================================
     // Command Log Operations
     // ========================================================================
@@ -1796,6 +3854,16 @@ impl Database {
         Ok(commands)
     }
 
+    /// The nodes and edges a logged command created, changed or deleted, in order
+    pub fn get_command_changes(&self, command_id: i32) -> Result<Vec<CommandChange>> {
+        let mut conn = self.get_conn()?;
+        let changes = command_changes::table
+            .filter(command_changes::command_id.eq(command_id))
+            .order(command_changes::id.asc())
+            .load::<CommandChange>(&mut conn)?;
+        Ok(changes)
+    }
+
     // ========================================================================
     // Roadmap Board Operations
     // ========================================================================
@@ -1830,6 +3898,11 @@ impl Database {
             created_at: &now,
             updated_at: &now,
             last_synced_at: None,
+            priority: None,
+            due_date: None,
+            goal_node_id: None,
+            goal_change_id: None,
+            archived_at: None,
         };
 
         diesel::insert_into(roadmap_items::table)
@@ -1881,6 +3954,11 @@ impl Database {
             created_at: &now,
             updated_at: &now,
             last_synced_at: None,
+            priority: None,
+            due_date: None,
+            goal_node_id: None,
+            goal_change_id: None,
+            archived_at: None,
         };
 
         diesel::insert_into(roadmap_items::table)
@@ -1907,6 +3985,7 @@ impl Database {
     /// Clear all roadmap items (for refresh)
     pub fn clear_roadmap_items(&self) -> Result<usize> {
         let mut conn = self.get_conn()?;
+        diesel::delete(roadmap_item_outcomes::table).execute(&mut conn)?;
         let deleted = diesel::delete(roadmap_items::table).execute(&mut conn)?;
         Ok(deleted)
     }
@@ -1952,6 +4031,27 @@ impl Database {
         Ok(())
     }
 
+    /// Update the issue state on every roadmap item linked to an issue number
+    pub fn update_roadmap_issue_state(
+        &self,
+        issue_number: i32,
+        issue_state: &str,
+    ) -> Result<usize> {
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+
+        let affected = diesel::update(
+            roadmap_items::table.filter(roadmap_items::github_issue_number.eq(Some(issue_number))),
+        )
+        .set((
+            roadmap_items::github_issue_state.eq(Some(issue_state)),
+            roadmap_items::updated_at.eq(&now),
+        ))
+        .execute(&mut conn)?;
+
+        Ok(affected)
+    }
+
     /// Update a roadmap item's GitHub issue info by finding it by title (first match)
     pub fn update_roadmap_item_github_by_title(
         &self,
@@ -2010,6 +4110,9 @@ impl Database {
     }
 
     /// Link a roadmap item to a decision graph outcome node
+    ///
+    /// Items can link to several outcomes; the first one linked is also kept
+    /// on the item itself for the views and ROADMAP.md metadata.
     pub fn link_roadmap_to_outcome(
         &self,
         item_id: i32,
@@ -2019,10 +4122,93 @@ impl Database {
         let mut conn = self.get_conn()?;
         let now = chrono::Local::now().to_rfc3339();
 
+        diesel::insert_or_ignore_into(roadmap_item_outcomes::table)
+            .values(&NewRoadmapItemOutcome {
+                roadmap_item_id: item_id,
+                outcome_node_id,
+                outcome_change_id,
+                created_at: &now,
+            })
+            .execute(&mut conn)?;
+
+        diesel::update(
+            roadmap_items::table
+                .filter(roadmap_items::id.eq(item_id))
+                .filter(roadmap_items::outcome_node_id.is_null()),
+        )
+        .set((
+            roadmap_items::outcome_node_id.eq(Some(outcome_node_id)),
+            roadmap_items::outcome_change_id.eq(Some(outcome_change_id)),
+        ))
+        .execute(&mut conn)?;
+
+        diesel::update(roadmap_items::table.filter(roadmap_items::id.eq(item_id)))
+            .set(roadmap_items::updated_at.eq(&now))
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    /// Outcome links for a roadmap item, oldest first
+    pub fn get_roadmap_item_outcomes(&self, item_id: i32) -> Result<Vec<RoadmapItemOutcome>> {
+        let mut conn = self.get_conn()?;
+        let links = roadmap_item_outcomes::table
+            .filter(roadmap_item_outcomes::roadmap_item_id.eq(item_id))
+            .order(roadmap_item_outcomes::created_at.asc())
+            .load::<RoadmapItemOutcome>(&mut conn)?;
+        Ok(links)
+    }
+
+    /// Remove one outcome link from a roadmap item
+    ///
+    /// The item's own outcome fields move to the next remaining link.
+    /// Returns false if the item wasn't linked to that outcome.
+    pub fn unlink_roadmap_outcome(&self, item_id: i32, outcome_node_id: i32) -> Result<bool> {
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+
+        let deleted = diesel::delete(
+            roadmap_item_outcomes::table
+                .filter(roadmap_item_outcomes::roadmap_item_id.eq(item_id))
+                .filter(roadmap_item_outcomes::outcome_node_id.eq(outcome_node_id)),
+        )
+        .execute(&mut conn)?;
+        if deleted == 0 {
+            return Ok(false);
+        }
+
+        let next = roadmap_item_outcomes::table
+            .filter(roadmap_item_outcomes::roadmap_item_id.eq(item_id))
+            .order(roadmap_item_outcomes::created_at.asc())
+            .first::<RoadmapItemOutcome>(&mut conn)
+            .optional()?;
+
+        diesel::update(roadmap_items::table.filter(roadmap_items::id.eq(item_id)))
+            .set((
+                roadmap_items::outcome_node_id.eq(next.as_ref().map(|l| l.outcome_node_id)),
+                roadmap_items::outcome_change_id
+                    .eq(next.as_ref().map(|l| l.outcome_change_id.as_str())),
+                roadmap_items::updated_at.eq(&now),
+            ))
+            .execute(&mut conn)?;
+
+        Ok(true)
+    }
+
+    /// Link a roadmap item to the goal node started for it
+    pub fn link_roadmap_to_goal(
+        &self,
+        item_id: i32,
+        goal_node_id: i32,
+        goal_change_id: &str,
+    ) -> Result<()> {
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+
         diesel::update(roadmap_items::table.filter(roadmap_items::id.eq(item_id)))
             .set((
-                roadmap_items::outcome_node_id.eq(Some(outcome_node_id)),
-                roadmap_items::outcome_change_id.eq(Some(outcome_change_id)),
+                roadmap_items::goal_node_id.eq(Some(goal_node_id)),
+                roadmap_items::goal_change_id.eq(Some(goal_change_id)),
                 roadmap_items::updated_at.eq(&now),
             ))
             .execute(&mut conn)?;
@@ -2030,11 +4216,16 @@ impl Database {
         Ok(())
     }
 
-    /// Unlink a roadmap item from its outcome node
+    /// Unlink a roadmap item from all of its outcome nodes
     pub fn unlink_roadmap_from_outcome(&self, item_id: i32) -> Result<()> {
         let mut conn = self.get_conn()?;
         let now = chrono::Local::now().to_rfc3339();
 
+        diesel::delete(
+            roadmap_item_outcomes::table.filter(roadmap_item_outcomes::roadmap_item_id.eq(item_id)),
+        )
+        .execute(&mut conn)?;
+
         diesel::update(roadmap_items::table.filter(roadmap_items::id.eq(item_id)))
             .set((
                 roadmap_items::outcome_node_id.eq(None::<i32>),
@@ -2061,6 +4252,51 @@ impl Database {
         Ok(())
     }
 
+    /// Set a roadmap item's priority and target date
+    pub fn set_roadmap_item_schedule(
+        &self,
+        item_id: i32,
+        priority: Option<i32>,
+        due_date: Option<&str>,
+    ) -> Result<()> {
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+
+        diesel::update(roadmap_items::table.filter(roadmap_items::id.eq(item_id)))
+            .set((
+                roadmap_items::priority.eq(priority),
+                roadmap_items::due_date.eq(due_date),
+                roadmap_items::updated_at.eq(&now),
+            ))
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    /// Mark a section's header row and its items as archived
+    ///
+    /// Returns the number of rows marked.
+    pub fn archive_roadmap_section(&self, section: &str) -> Result<usize> {
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+
+        let header = roadmap_items::title
+            .eq(section)
+            .and(roadmap_items::checkbox_state.eq("none"));
+        let updated = diesel::update(
+            roadmap_items::table
+                .filter(header.or(roadmap_items::section.eq(section)))
+                .filter(roadmap_items::archived_at.is_null()),
+        )
+        .set((
+            roadmap_items::archived_at.eq(Some(&now)),
+            roadmap_items::updated_at.eq(&now),
+        ))
+        .execute(&mut conn)?;
+
+        Ok(updated)
+    }
+
     /// Update last synced timestamp for a roadmap item
     pub fn update_roadmap_item_synced(&self, item_id: i32) -> Result<()> {
         let mut conn = self.get_conn()?;
@@ -2214,6 +4450,10 @@ impl Database {
     /// Delete a roadmap item by ID
     pub fn delete_roadmap_item(&self, item_id: i32) -> Result<()> {
         let mut conn = self.get_conn()?;
+        diesel::delete(
+            roadmap_item_outcomes::table.filter(roadmap_item_outcomes::roadmap_item_id.eq(item_id)),
+        )
+        .execute(&mut conn)?;
         diesel::delete(roadmap_items::table.filter(roadmap_items::id.eq(item_id)))
             .execute(&mut conn)?;
         Ok(())
@@ -2234,6 +4474,81 @@ impl Database {
         Ok((is_complete, has_outcome, issue_closed))
     }
 
+    /// Roadmap issues ready to close: every checkbox in the section is checked
+    /// and all of its linked outcome nodes are completed.
+    ///
+    /// Returns the section entry (which carries the issue number) and its items.
+    pub fn get_roadmap_issues_ready_to_close(
+        &self,
+    ) -> Result<Vec<(RoadmapItem, Vec<RoadmapItem>)>> {
+        let items = self.get_all_roadmap_items()?;
+        let outcomes_done = self.roadmap_outcomes_done()?;
+
+        let mut ready = Vec::new();
+        let open_sections = items.iter().filter(|i| {
+            i.checkbox() == CheckboxState::None
+                && i.github_issue_number.is_some()
+                && i.github_issue_state.as_deref() != Some("closed")
+        });
+
+        for section in open_sections {
+            let tasks: Vec<RoadmapItem> = items
+                .iter()
+                .filter(|i| {
+                    i.checkbox() != CheckboxState::None
+                        && i.section.as_deref() == Some(section.title.as_str())
+                })
+                .cloned()
+                .collect();
+
+            let all_done = !tasks.is_empty()
+                && tasks
+                    .iter()
+                    .all(|t| t.is_checked() && outcomes_done.contains(&t.id));
+
+            if all_done {
+                ready.push((section.clone(), tasks));
+            }
+        }
+
+        Ok(ready)
+    }
+
+    /// Unchecked roadmap items whose linked outcome nodes are all completed
+    ///
+    /// These are the checkboxes `roadmap reconcile` flips.
+    pub fn get_roadmap_items_ready_to_check(&self) -> Result<Vec<RoadmapItem>> {
+        let outcomes_done = self.roadmap_outcomes_done()?;
+
+        Ok(self
+            .get_all_roadmap_items()?
+            .into_iter()
+            .filter(|i| i.checkbox() == CheckboxState::Unchecked && outcomes_done.contains(&i.id))
+            .collect())
+    }
+
+    /// Ids of roadmap items that have outcome links, all of them completed
+    fn roadmap_outcomes_done(&self) -> Result<std::collections::HashSet<i32>> {
+        let mut conn = self.get_conn()?;
+        let completed: std::collections::HashSet<i32> = self
+            .get_all_nodes()?
+            .into_iter()
+            .filter(|n| n.status == "completed")
+            .map(|n| n.id)
+            .collect();
+        let links = roadmap_item_outcomes::table.load::<RoadmapItemOutcome>(&mut conn)?;
+
+        let mut pending = std::collections::HashSet::new();
+        let mut linked = std::collections::HashSet::new();
+        for link in &links {
+            linked.insert(link.roadmap_item_id);
+            if !completed.contains(&link.outcome_node_id) {
+                pending.insert(link.roadmap_item_id);
+            }
+        }
+        Ok(linked.difference(&pending).copied().collect())
+    }
+
     // ========================================================================
     // GitHub Issue Cache Methods
     // ========================================================================
@@ -2302,22 +4617,118 @@ impl Database {
         let mut conn = self.get_conn()?;
 
         let issues = github_issue_cache::table
-            .filter(github_issue_cache::repo.eq(repo))
-            .order(github_issue_cache::issue_number.desc())
+            .filter(github_issue_cache::repo.eq(repo))
+            .order(github_issue_cache::issue_number.desc())
+            .load::<GitHubIssueCache>(&mut conn)?;
+
+        Ok(issues)
+    }
+
+    /// Get all cached issues
+    pub fn get_all_cached_issues(&self) -> Result<Vec<GitHubIssueCache>> {
+        let mut conn = self.get_conn()?;
+
+        let issues = github_issue_cache::table
+            .order(github_issue_cache::cached_at.desc())
+            .load::<GitHubIssueCache>(&mut conn)?;
+
+        Ok(issues)
+    }
+
+    /// Cache a pull request's state (upsert by repo + number)
+    pub fn cache_pull_request(
+        &self,
+        pr_number: i32,
+        repo: &str,
+        title: &str,
+        state: &str,
+        head_branch: &str,
+        html_url: &str,
+    ) -> Result<()> {
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+
+        diesel::delete(
+            github_pr_cache::table
+                .filter(github_pr_cache::repo.eq(repo))
+                .filter(github_pr_cache::pr_number.eq(pr_number)),
+        )
+        .execute(&mut conn)?;
+
+        let new_cache = NewGitHubPrCache {
+            pr_number,
+            repo,
+            title,
+            state,
+            head_branch,
+            html_url,
+            cached_at: &now,
+        };
+
+        diesel::insert_into(github_pr_cache::table)
+            .values(&new_cache)
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    /// Get a cached pull request by repo and number
+    pub fn get_cached_pull_request(
+        &self,
+        repo: &str,
+        pr_number: i32,
+    ) -> Result<Option<GitHubPrCache>> {
+        let mut conn = self.get_conn()?;
+
+        let result = github_pr_cache::table
+            .filter(github_pr_cache::repo.eq(repo))
+            .filter(github_pr_cache::pr_number.eq(pr_number))
+            .first::<GitHubPrCache>(&mut conn)
+            .optional()?;
+
+        Ok(result)
+    }
+
+    /// Get all cached pull requests
+    pub fn get_all_cached_pull_requests(&self) -> Result<Vec<GitHubPrCache>> {
+        let mut conn = self.get_conn()?;
+
+        let prs = github_pr_cache::table
+            .order(github_pr_cache::pr_number.desc())
+            .load::<GitHubPrCache>(&mut conn)?;
+
+        Ok(prs)
+    }
+
+    /// Get cached issues last fetched more than `max_age_hours` ago
+    pub fn get_expired_cached_issues(&self, max_age_hours: i64) -> Result<Vec<GitHubIssueCache>> {
+        let mut conn = self.get_conn()?;
+        let cutoff = chrono::Local::now() - chrono::Duration::hours(max_age_hours);
+        let cutoff_str = cutoff.to_rfc3339();
+
+        let issues = github_issue_cache::table
+            .filter(github_issue_cache::cached_at.lt(&cutoff_str))
+            .order(github_issue_cache::cached_at.asc())
             .load::<GitHubIssueCache>(&mut conn)?;
 
         Ok(issues)
     }
 
-    /// Get all cached issues
-    pub fn get_all_cached_issues(&self) -> Result<Vec<GitHubIssueCache>> {
+    /// Get cached pull requests last fetched more than `max_age_hours` ago
+    pub fn get_expired_cached_pull_requests(
+        &self,
+        max_age_hours: i64,
+    ) -> Result<Vec<GitHubPrCache>> {
         let mut conn = self.get_conn()?;
+        let cutoff = chrono::Local::now() - chrono::Duration::hours(max_age_hours);
+        let cutoff_str = cutoff.to_rfc3339();
 
-        let issues = github_issue_cache::table
-            .order(github_issue_cache::cached_at.desc())
-            .load::<GitHubIssueCache>(&mut conn)?;
+        let prs = github_pr_cache::table
+            .filter(github_pr_cache::cached_at.lt(&cutoff_str))
+            .order(github_pr_cache::cached_at.asc())
+            .load::<GitHubPrCache>(&mut conn)?;
 
-        Ok(issues)
+        Ok(prs)
     }
 
     /// Clear cached issues older than a specified duration
@@ -2426,6 +4837,17 @@ impl Database {
         Ok(sessions)
     }
 
+    /// Get the most recently started session that hasn't ended
+    pub fn get_active_trace_session(&self) -> Result<Option<TraceSession>> {
+        let mut conn = self.get_conn()?;
+        let session = trace_sessions::table
+            .filter(trace_sessions::ended_at.is_null())
+            .order(trace_sessions::started_at.desc())
+            .first::<TraceSession>(&mut conn)
+            .optional()?;
+        Ok(session)
+    }
+
     /// Get trace sessions linked to decision nodes
     pub fn get_linked_trace_sessions(&self, limit: i64) -> Result<Vec<TraceSession>> {
         let mut conn = self.get_conn()?;
@@ -2619,6 +5041,40 @@ impl Database {
         Ok(())
     }
 
+    /// Overwrite a span's timestamps, for spans recorded after the fact
+    pub fn set_trace_span_times(
+        &self,
+        span_id: i32,
+        started_at: &str,
+        completed_at: &str,
+    ) -> Result<()> {
+        let mut conn = self.get_conn()?;
+        diesel::update(trace_spans::table.filter(trace_spans::id.eq(span_id)))
+            .set((
+                trace_spans::started_at.eq(started_at),
+                trace_spans::completed_at.eq(Some(completed_at)),
+            ))
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    /// Overwrite a session's start and end times
+    pub fn set_trace_session_times(
+        &self,
+        session_id: &str,
+        started_at: &str,
+        ended_at: &str,
+    ) -> Result<()> {
+        let mut conn = self.get_conn()?;
+        diesel::update(trace_sessions::table.filter(trace_sessions::session_id.eq(session_id)))
+            .set((
+                trace_sessions::started_at.eq(started_at),
+                trace_sessions::ended_at.eq(Some(ended_at)),
+            ))
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
     /// Get spans for a session
     pub fn get_trace_spans(&self, session_id: &str) -> Result<Vec<TraceSpan>> {
         let mut conn = self.get_conn()?;
@@ -2629,6 +5085,25 @@ impl Database {
         Ok(spans)
     }
 
+    /// Get spans across all sessions, oldest first (for cost reports)
+    pub fn get_all_trace_spans(&self) -> Result<Vec<TraceSpan>> {
+        let mut conn = self.get_conn()?;
+        let spans = trace_spans::table
+            .order(trace_spans::started_at.asc())
+            .load::<TraceSpan>(&mut conn)?;
+        Ok(spans)
+    }
+
+    /// Get spans started at or after `since` (an RFC 3339 timestamp or date prefix)
+    pub fn get_trace_spans_since(&self, since: &str) -> Result<Vec<TraceSpan>> {
+        let mut conn = self.get_conn()?;
+        let spans = trace_spans::table
+            .filter(trace_spans::started_at.ge(since))
+            .order(trace_spans::started_at.asc())
+            .load::<TraceSpan>(&mut conn)?;
+        Ok(spans)
+    }
+
     /// Get a single span by ID
     pub fn get_trace_span(&self, span_id: i32) -> Result<Option<TraceSpan>> {
         let mut conn = self.get_conn()?;
@@ -2679,6 +5154,39 @@ impl Database {
         Ok(id)
     }
 
+    /// Record redactions made to one piece of span content
+    pub fn add_trace_redactions(
+        &self,
+        span_id: i32,
+        content_type: &str,
+        hits: &std::collections::BTreeMap<String, usize>,
+    ) -> Result<()> {
+        let mut conn = self.get_conn()?;
+        let rows: Vec<NewTraceRedaction> = hits
+            .iter()
+            .map(|(rule, count)| NewTraceRedaction {
+                span_id,
+                content_type,
+                rule,
+                count: i32::try_from(*count).unwrap_or(i32::MAX),
+            })
+            .collect();
+        diesel::insert_into(trace_redactions::table)
+            .values(&rows)
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    /// Get the redactions recorded for a span
+    pub fn get_trace_redactions(&self, span_id: i32) -> Result<Vec<TraceRedaction>> {
+        let mut conn = self.get_conn()?;
+        let redactions = trace_redactions::table
+            .filter(trace_redactions::span_id.eq(span_id))
+            .order(trace_redactions::id.asc())
+            .load::<TraceRedaction>(&mut conn)?;
+        Ok(redactions)
+    }
+
     /// Get content for a span
     pub fn get_trace_content(&self, span_id: i32) -> Result<Vec<TraceContent>> {
         let mut conn = self.get_conn()?;
@@ -2704,6 +5212,34 @@ impl Database {
         Ok(content)
     }
 
+    /// Name of the tool a `tool_use_id` called, from its recorded input
+    pub fn get_tool_name_for_use_id(&self, tool_use_id: &str) -> Result<Option<String>> {
+        let mut conn = self.get_conn()?;
+        let name = trace_content::table
+            .filter(trace_content::tool_use_id.eq(tool_use_id))
+            .filter(trace_content::content_type.eq("tool_input"))
+            .select(trace_content::tool_name)
+            .first::<Option<String>>(&mut conn)
+            .optional()?;
+        Ok(name.flatten())
+    }
+
+    /// Get content of the given types across all spans (for tool analytics)
+    pub fn get_all_trace_content_of_types(
+        &self,
+        content_types: &[&str],
+    ) -> Result<Vec<TraceContent>> {
+        let mut conn = self.get_conn()?;
+        let content = trace_content::table
+            .filter(trace_content::content_type.eq_any(content_types))
+            .order((
+                trace_content::span_id.asc(),
+                trace_content::sequence_num.asc(),
+            ))
+            .load::<TraceContent>(&mut conn)?;
+        Ok(content)
+    }
+
     /// Link a trace session to a decision node
     pub fn link_trace_session_to_node(&self, session_id: &str, node_id: i32) -> Result<()> {
         let mut conn = self.get_conn()?;
@@ -2772,48 +5308,139 @@ impl Database {
 
     /// Prune old trace data (sessions and their spans/content)
     pub fn prune_traces(&self, days: u32, keep_linked: bool) -> Result<(usize, usize, usize)> {
+        let session_ids = self.trace_sessions_to_prune(Some(days), None, keep_linked)?;
+        self.delete_trace_sessions(&session_ids)
+    }
+
+    /// Sessions a retention policy would delete
+    ///
+    /// Selects sessions started more than `max_age_days` ago, then the oldest
+    /// ended sessions until the database's used space, less their trace data,
+    /// is at most `max_db_bytes`.
+    pub fn trace_sessions_to_prune(
+        &self,
+        max_age_days: Option<u32>,
+        max_db_bytes: Option<u64>,
+        keep_linked: bool,
+    ) -> Result<Vec<String>> {
         let mut conn = self.get_conn()?;
-        let cutoff = chrono::Local::now() - chrono::Duration::days(i64::from(days));
-        let cutoff_str = cutoff.to_rfc3339();
+        let mut session_ids: Vec<String> = Vec::new();
+
+        if let Some(days) = max_age_days {
+            let cutoff = chrono::Local::now() - chrono::Duration::days(i64::from(days));
+            let mut query = trace_sessions::table
+                .filter(trace_sessions::started_at.lt(cutoff.to_rfc3339()))
+                .select(trace_sessions::session_id)
+                .into_boxed();
+            if keep_linked {
+                query = query.filter(trace_sessions::linked_node_id.is_null());
+            }
+            session_ids = query.load(&mut conn)?;
+        }
 
-        // Find sessions to delete
-        let mut query = trace_sessions::table
-            .filter(trace_sessions::started_at.lt(&cutoff_str))
-            .into_boxed();
+        if let Some(max_bytes) = max_db_bytes {
+            let used: ByteCount = diesel::sql_query(
+                "SELECT (page_count - freelist_count) * page_size AS bytes \
+                 FROM pragma_page_count(), pragma_freelist_count(), pragma_page_size()",
+            )
+            .get_result(&mut conn)?;
+            let sizes: Vec<SessionBytes> = diesel::sql_query(format!(
+                "SELECT s.session_id AS session_id, COALESCE(( \
+                 SELECT SUM(LENGTH(c.content)) FROM trace_content c \
+                 JOIN trace_spans sp ON c.span_id = sp.id \
+                 WHERE sp.session_id = s.session_id), 0) AS bytes \
+                 FROM trace_sessions s \
+                 WHERE s.ended_at IS NOT NULL {} \
+                 ORDER BY s.started_at ASC, s.id ASC",
+                if keep_linked {
+                    "AND s.linked_node_id IS NULL"
+                } else {
+                    ""
+                }
+            ))
+            .load(&mut conn)?;
 
-        if keep_linked {
-            query = query.filter(trace_sessions::linked_node_id.is_null());
+            let max_bytes = i64::try_from(max_bytes).unwrap_or(i64::MAX);
+            let mut remaining = used.bytes;
+            for s in &sizes {
+                if session_ids.contains(&s.session_id) {
+                    remaining -= s.bytes;
+                }
+            }
+            for s in sizes {
+                if remaining <= max_bytes {
+                    break;
+                }
+                if !session_ids.contains(&s.session_id) {
+                    remaining -= s.bytes;
+                    session_ids.push(s.session_id);
+                }
+            }
         }
 
-        let sessions_to_delete: Vec<TraceSession> = query.load(&mut conn)?;
-        let session_ids: Vec<&str> = sessions_to_delete
-            .iter()
-            .map(|s| s.session_id.as_str())
-            .collect();
+        Ok(session_ids)
+    }
+
+    /// Count the (sessions, spans, content items) deleting these sessions would remove
+    pub fn count_trace_sessions_data(
+        &self,
+        session_ids: &[String],
+    ) -> Result<(usize, usize, usize)> {
+        let mut conn = self.get_conn()?;
+        let sessions: i64 = trace_sessions::table
+            .filter(trace_sessions::session_id.eq_any(session_ids))
+            .count()
+            .get_result(&mut conn)?;
+        let span_ids: Vec<i32> = trace_spans::table
+            .filter(trace_spans::session_id.eq_any(session_ids))
+            .select(trace_spans::id)
+            .load(&mut conn)?;
+        let content: i64 = trace_content::table
+            .filter(trace_content::span_id.eq_any(&span_ids))
+            .count()
+            .get_result(&mut conn)?;
+        Ok((sessions as usize, span_ids.len(), content as usize))
+    }
 
+    /// Delete sessions with their spans and everything recorded against them
+    ///
+    /// Returns (sessions, spans, content items) deleted.
+    pub fn delete_trace_sessions(&self, session_ids: &[String]) -> Result<(usize, usize, usize)> {
         if session_ids.is_empty() {
             return Ok((0, 0, 0));
         }
+        let mut conn = self.get_conn()?;
 
-        // Get span IDs for these sessions
-        let spans_to_delete: Vec<TraceSpan> = trace_spans::table
-            .filter(trace_spans::session_id.eq_any(&session_ids))
+        let span_ids: Vec<i32> = trace_spans::table
+            .filter(trace_spans::session_id.eq_any(session_ids))
+            .select(trace_spans::id)
             .load(&mut conn)?;
-        let span_ids: Vec<i32> = spans_to_delete.iter().map(|s| s.id).collect();
 
         // Delete content first (FK constraint)
+        diesel::delete(span_nodes::table.filter(span_nodes::span_id.eq_any(&span_ids)))
+            .execute(&mut conn)?;
+        diesel::delete(span_commits::table.filter(span_commits::span_id.eq_any(&span_ids)))
+            .execute(&mut conn)?;
+        diesel::delete(span_parents::table.filter(span_parents::span_id.eq_any(&span_ids)))
+            .execute(&mut conn)?;
+        diesel::delete(span_annotations::table.filter(span_annotations::span_id.eq_any(&span_ids)))
+            .execute(&mut conn)?;
+        diesel::delete(span_streams::table.filter(span_streams::span_id.eq_any(&span_ids)))
+            .execute(&mut conn)?;
+        diesel::delete(trace_redactions::table.filter(trace_redactions::span_id.eq_any(&span_ids)))
+            .execute(&mut conn)?;
         let content_deleted =
             diesel::delete(trace_content::table.filter(trace_content::span_id.eq_any(&span_ids)))
                 .execute(&mut conn)?;
 
         // Delete spans
         let spans_deleted =
-            diesel::delete(trace_spans::table.filter(trace_spans::session_id.eq_any(&session_ids)))
+            diesel::delete(trace_spans::table.filter(trace_spans::session_id.eq_any(session_ids)))
                 .execute(&mut conn)?;
 
         // Delete sessions
         let sessions_deleted = diesel::delete(
-            trace_sessions::table.filter(trace_sessions::session_id.eq_any(&session_ids)),
+            trace_sessions::table.filter(trace_sessions::session_id.eq_any(session_ids)),
         )
         .execute(&mut conn)?;
 
@@ -2887,19 +5514,380 @@ impl Database {
             .order(trace_spans::id.asc())
             .load::<TraceSpan>(&mut conn)?;
 
-        Ok(spans)
+        Ok(spans)
+    }
+
+    /// Get the count of nodes created during a specific span
+    pub fn get_node_count_for_span(&self, span_id: i32) -> Result<i64> {
+        let mut conn = self.get_conn()?;
+
+        let count: i64 = span_nodes::table
+            .filter(span_nodes::span_id.eq(span_id))
+            .count()
+            .get_result(&mut conn)?;
+
+        Ok(count)
+    }
+
+    /// Record that a span was made by a subagent started from `parent_span_id`
+    ///
+    /// Returns false if the span already had a parent.
+    pub fn set_span_parent(
+        &self,
+        span_id: i32,
+        parent_span_id: i32,
+        tool_use_id: Option<&str>,
+    ) -> Result<bool> {
+        let mut conn = self.get_conn()?;
+        let inserted = diesel::insert_or_ignore_into(span_parents::table)
+            .values(&NewSpanParent {
+                span_id,
+                parent_span_id,
+                tool_use_id,
+            })
+            .execute(&mut conn)?;
+        Ok(inserted > 0)
+    }
+
+    /// Parent span of each subagent span in a session (span ID → parent ID)
+    pub fn get_span_parents(
+        &self,
+        session_id: &str,
+    ) -> Result<std::collections::HashMap<i32, i32>> {
+        let mut conn = self.get_conn()?;
+        let span_ids: Vec<i32> = trace_spans::table
+            .filter(trace_spans::session_id.eq(session_id))
+            .select(trace_spans::id)
+            .load(&mut conn)?;
+        let parents: Vec<(i32, i32)> = span_parents::table
+            .filter(span_parents::span_id.eq_any(&span_ids))
+            .select((span_parents::span_id, span_parents::parent_span_id))
+            .load(&mut conn)?;
+        Ok(parents.into_iter().collect())
+    }
+
+    /// Record how a span's streamed response arrived, replacing earlier stats
+    pub fn set_span_stream(
+        &self,
+        span_id: i32,
+        chunk_count: i32,
+        first_token_ms: Option<i32>,
+        complete: bool,
+    ) -> Result<()> {
+        let mut conn = self.get_conn()?;
+        diesel::replace_into(span_streams::table)
+            .values(&NewSpanStream {
+                span_id,
+                chunk_count,
+                first_token_ms,
+                complete,
+            })
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    /// Stream stats for a span, if its response was streamed
+    pub fn get_span_stream(&self, span_id: i32) -> Result<Option<SpanStream>> {
+        let mut conn = self.get_conn()?;
+        let stream = span_streams::table
+            .filter(span_streams::span_id.eq(span_id))
+            .first::<SpanStream>(&mut conn)
+            .optional()?;
+        Ok(stream)
+    }
+
+    /// Add a reviewer note to a span
+    pub fn annotate_span(&self, span_id: i32, note: &str) -> Result<i32> {
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+        diesel::insert_into(span_annotations::table)
+            .values(&NewSpanAnnotation {
+                span_id,
+                note,
+                created_at: &now,
+            })
+            .execute(&mut conn)?;
+        let id: i32 = diesel::select(diesel::dsl::sql::<diesel::sql_types::Integer>(
+            "last_insert_rowid()",
+        ))
+        .first(&mut conn)?;
+        Ok(id)
+    }
+
+    /// Delete an annotation; returns false if it didn't exist
+    pub fn delete_span_annotation(&self, annotation_id: i32) -> Result<bool> {
+        let mut conn = self.get_conn()?;
+        let deleted =
+            diesel::delete(span_annotations::table.filter(span_annotations::id.eq(annotation_id)))
+                .execute(&mut conn)?;
+        Ok(deleted > 0)
+    }
+
+    /// Annotations on the given spans, oldest first (span ID → annotations)
+    pub fn get_annotations_for_spans(
+        &self,
+        span_ids: &[i32],
+    ) -> Result<std::collections::HashMap<i32, Vec<SpanAnnotation>>> {
+        let mut conn = self.get_conn()?;
+        let annotations: Vec<SpanAnnotation> = span_annotations::table
+            .filter(span_annotations::span_id.eq_any(span_ids))
+            .order(span_annotations::id.asc())
+            .load(&mut conn)?;
+        let mut by_span: std::collections::HashMap<i32, Vec<SpanAnnotation>> =
+            std::collections::HashMap::new();
+        for annotation in annotations {
+            by_span
+                .entry(annotation.span_id)
+                .or_default()
+                .push(annotation);
+        }
+        Ok(by_span)
+    }
+
+    /// Store a node's embedding, replacing any earlier one
+    pub fn set_node_embedding(
+        &self,
+        node_id: i32,
+        model: &str,
+        content_hash: &str,
+        vector: &[f32],
+    ) -> Result<()> {
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+        let bytes: Vec<u8> = vector.iter().flat_map(|v| v.to_le_bytes()).collect();
+        diesel::replace_into(node_embeddings::table)
+            .values(&NewNodeEmbedding {
+                node_id,
+                model,
+                content_hash,
+                vector: &bytes,
+                created_at: &now,
+            })
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    /// Embeddings produced by `model`
+    pub fn get_node_embeddings(&self, model: &str) -> Result<Vec<NodeEmbedding>> {
+        let mut conn = self.get_conn()?;
+        let embeddings = node_embeddings::table
+            .filter(node_embeddings::model.eq(model))
+            .order(node_embeddings::node_id.asc())
+            .load(&mut conn)?;
+        Ok(embeddings)
+    }
+
+    /// Register another project's database under `name`, replacing any
+    /// earlier registration of that name
+    pub fn add_federated_repo(&self, name: &str, db_path: &str) -> Result<()> {
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+        diesel::replace_into(federated_repos::table)
+            .values(&NewFederatedRepo {
+                name,
+                db_path,
+                added_at: &now,
+            })
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    /// Registered repositories, by name
+    pub fn get_federated_repos(&self) -> Result<Vec<FederatedRepo>> {
+        let mut conn = self.get_conn()?;
+        let repos = federated_repos::table
+            .order(federated_repos::name.asc())
+            .load(&mut conn)?;
+        Ok(repos)
+    }
+
+    /// Forget a registered repository; returns false if there was none
+    pub fn remove_federated_repo(&self, name: &str) -> Result<bool> {
+        let mut conn = self.get_conn()?;
+        let deleted = diesel::delete(federated_repos::table.filter(federated_repos::name.eq(name)))
+            .execute(&mut conn)?;
+        Ok(deleted > 0)
+    }
+
+    /// Store a snapshot; returns its ID
+    ///
+    /// Fails with a validation error if `label` is taken.
+    pub fn create_snapshot(&self, label: &str, patch_json: &str) -> Result<i32> {
+        let mut conn = self.get_conn()?;
+        let taken = snapshots::table
+            .filter(snapshots::label.eq(label))
+            .count()
+            .get_result::<i64>(&mut conn)?;
+        if taken > 0 {
+            return Err(DbError::Validation(format!(
+                "A snapshot named '{}' already exists",
+                label
+            )));
+        }
+        let now = chrono::Local::now().to_rfc3339();
+        diesel::insert_into(snapshots::table)
+            .values(&NewSnapshot {
+                label,
+                created_at: &now,
+                patch_json,
+            })
+            .execute(&mut conn)?;
+        let id: i32 = diesel::select(diesel::dsl::sql::<diesel::sql_types::Integer>(
+            "last_insert_rowid()",
+        ))
+        .first(&mut conn)?;
+        Ok(id)
+    }
+
+    /// All snapshots, oldest first
+    pub fn get_snapshots(&self) -> Result<Vec<Snapshot>> {
+        let mut conn = self.get_conn()?;
+        let snapshots = snapshots::table
+            .order(snapshots::id.asc())
+            .load(&mut conn)?;
+        Ok(snapshots)
+    }
+
+    /// The snapshot labeled `label`, or with that ID
+    pub fn get_snapshot(&self, label: &str) -> Result<Option<Snapshot>> {
+        let mut conn = self.get_conn()?;
+        let by_label = snapshots::table
+            .filter(snapshots::label.eq(label))
+            .first::<Snapshot>(&mut conn)
+            .optional()?;
+        match (by_label, label.parse::<i32>()) {
+            (Some(snapshot), _) => Ok(Some(snapshot)),
+            (None, Ok(id)) => Ok(snapshots::table
+                .filter(snapshots::id.eq(id))
+                .first(&mut conn)
+                .optional()?),
+            (None, Err(_)) => Ok(None),
+        }
+    }
+
+    /// Delete a snapshot by label or ID; returns false if there was none
+    pub fn delete_snapshot(&self, label: &str) -> Result<bool> {
+        let Some(snapshot) = self.get_snapshot(label)? else {
+            return Ok(false);
+        };
+        let mut conn = self.get_conn()?;
+        diesel::delete(snapshots::table.filter(snapshots::id.eq(snapshot.id)))
+            .execute(&mut conn)?;
+        Ok(true)
+    }
+
+    /// Every file index entry
+    pub fn get_node_files(&self) -> Result<Vec<NodeFile>> {
+        let mut conn = self.get_conn()?;
+        let files = node_files::table
+            .order((node_files::node_id.asc(), node_files::path.asc()))
+            .load::<NodeFile>(&mut conn)?;
+        Ok(files)
+    }
+
+    /// Replace a node's file index entries with `files` (path, source)
+    pub fn set_node_files(
+        &self,
+        node_id: i32,
+        node_updated_at: &str,
+        files: &[(String, String)],
+    ) -> Result<()> {
+        let mut conn = self.get_conn()?;
+        diesel::delete(node_files::table.filter(node_files::node_id.eq(node_id)))
+            .execute(&mut conn)?;
+        let rows: Vec<NewNodeFile> = files
+            .iter()
+            .map(|(path, source)| NewNodeFile {
+                node_id,
+                path,
+                source,
+                node_updated_at,
+            })
+            .collect();
+        diesel::insert_into(node_files::table)
+            .values(&rows)
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    /// Focus on `goal_id`, replacing any earlier focus
+    pub fn set_focus(&self, goal_id: i32, expires_at: Option<&str>) -> Result<Focus> {
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+        diesel::replace_into(focus::table)
+            .values(&NewFocus {
+                id: 1,
+                goal_id,
+                started_at: &now,
+                expires_at,
+            })
+            .execute(&mut conn)?;
+        Ok(Focus {
+            id: 1,
+            goal_id,
+            started_at: now,
+            expires_at: expires_at.map(String::from),
+        })
+    }
+
+    /// The current focus, expired or not
+    pub fn get_focus(&self) -> Result<Option<Focus>> {
+        let mut conn = self.get_conn()?;
+        let found = focus::table.first::<Focus>(&mut conn).optional()?;
+        Ok(found)
     }
 
-    /// Get the count of nodes created during a specific span
-    pub fn get_node_count_for_span(&self, span_id: i32) -> Result<i64> {
+    /// End the focus; returns false if there was none
+    pub fn clear_focus(&self) -> Result<bool> {
         let mut conn = self.get_conn()?;
+        let deleted = diesel::delete(focus::table).execute(&mut conn)?;
+        Ok(deleted > 0)
+    }
 
-        let count: i64 = span_nodes::table
-            .filter(span_nodes::span_id.eq(span_id))
-            .count()
-            .get_result(&mut conn)?;
+    /// Record that a span's tool calls made a commit
+    ///
+    /// Returns false if the link already existed.
+    pub fn link_span_to_commit(
+        &self,
+        span_id: i32,
+        commit_hash: &str,
+        committed_at: &str,
+    ) -> Result<bool> {
+        let mut conn = self.get_conn()?;
+        let now = chrono::Local::now().to_rfc3339();
+        let inserted = diesel::insert_or_ignore_into(span_commits::table)
+            .values(&NewSpanCommit {
+                span_id,
+                commit_hash,
+                committed_at,
+                created_at: &now,
+            })
+            .execute(&mut conn)?;
+        Ok(inserted > 0)
+    }
 
-        Ok(count)
+    /// Commits made during a span, oldest first
+    pub fn get_commits_for_span(&self, span_id: i32) -> Result<Vec<SpanCommit>> {
+        let mut conn = self.get_conn()?;
+        let commits = span_commits::table
+            .filter(span_commits::span_id.eq(span_id))
+            .order(span_commits::committed_at.asc())
+            .load::<SpanCommit>(&mut conn)?;
+        Ok(commits)
+    }
+
+    /// Spans that made a commit, matched by full hash or prefix
+    pub fn get_spans_for_commit(&self, commit: &str) -> Result<Vec<TraceSpan>> {
+        let mut conn = self.get_conn()?;
+        let span_ids: Vec<i32> = span_commits::table
+            .filter(span_commits::commit_hash.like(format!("{}%", commit)))
+            .select(span_commits::span_id)
+            .load(&mut conn)?;
+        let spans = trace_spans::table
+            .filter(trace_spans::id.eq_any(&span_ids))
+            .order(trace_spans::started_at.asc())
+            .load::<TraceSpan>(&mut conn)?;
+        Ok(spans)
     }
 
     /// Get node counts for multiple spans at once (for efficient list display)
@@ -2946,6 +5934,102 @@ pub struct DecisionGraph {
     /// Optional config from .deciduous/config.toml (for external repo links, etc.)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub config: Option<crate::config::Config>,
+    /// Cached state of pull requests referenced by nodes (`pr` metadata)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pull_requests: Vec<GitHubPrCache>,
+}
+
+impl DecisionGraph {
+    /// Find the cached pull request a node is linked to, if any
+    pub fn pull_request_for(&self, node: &DecisionNode) -> Option<&GitHubPrCache> {
+        let number = node_pr_number(node)?;
+        self.pull_requests.iter().find(|pr| pr.pr_number == number)
+    }
+}
+
+/// One page of the graph from `Database::get_graph_page`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct GraphPage {
+    pub nodes: Vec<DecisionNode>,
+    /// Edges whose target is one of `nodes`
+    pub edges: Vec<DecisionEdge>,
+    /// Config for the viewer, as in `DecisionGraph` (set by callers)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<crate::config::Config>,
+    /// Cached pull requests, on the first page only
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pull_requests: Vec<GitHubPrCache>,
+    /// `after` for the next page; None on the last
+    pub next: Option<i32>,
+}
+
+/// Iterator over a table in id order, a batch of rows at a time
+///
+/// From `Database::iter_nodes` and `Database::iter_edges`. A failed query is
+/// yielded as an error and ends the iteration.
+pub struct Cursor<'a, T> {
+    db: &'a Database,
+    fetch: fn(&Database, i32, i64) -> Result<Vec<T>>,
+    id: fn(&T) -> i32,
+    batch: i64,
+    after: i32,
+    rows: std::vec::IntoIter<T>,
+    done: bool,
+}
+
+impl<'a, T> Cursor<'a, T> {
+    fn new(
+        db: &'a Database,
+        batch: usize,
+        fetch: fn(&Database, i32, i64) -> Result<Vec<T>>,
+        id: fn(&T) -> i32,
+    ) -> Self {
+        Self {
+            db,
+            fetch,
+            id,
+            batch: batch.max(1) as i64,
+            after: i32::MIN,
+            rows: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+}
+
+impl<T> Iterator for Cursor<'_, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if let Some(row) = self.rows.next() {
+            return Some(Ok(row));
+        }
+        if self.done {
+            return None;
+        }
+        match (self.fetch)(self.db, self.after, self.batch) {
+            Ok(rows) => {
+                self.done = (rows.len() as i64) < self.batch;
+                if let Some(last) = rows.last() {
+                    self.after = (self.id)(last);
+                }
+                self.rows = rows.into_iter();
+                self.rows.next().map(Ok)
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Extract the linked PR number from a node's metadata
+pub fn node_pr_number(node: &DecisionNode) -> Option<i32> {
+    node.metadata_json
+        .as_ref()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        .and_then(|v| v.get("pr").and_then(|p| p.as_i64()))
+        .map(|n| n as i32)
 }
 
 #[cfg(test)]
@@ -3109,6 +6193,45 @@ mod tests {
         assert!(CURRENT_SCHEMA.features.contains(&"decision_edges"));
     }
 
+    // === pick_auto_link_goal Tests ===
+
+    fn goal(id: i32, status: &str, created_at: &str, branch: Option<&str>) -> DecisionNode {
+        DecisionNode {
+            id,
+            change_id: format!("c{}", id),
+            node_type: "goal".into(),
+            title: format!("Goal {}", id),
+            description: None,
+            status: status.into(),
+            created_at: created_at.to_string(),
+            updated_at: created_at.to_string(),
+            metadata_json: branch.map(|b| format!(r#"{{"branch":"{}"}}"#, b)),
+        }
+    }
+
+    #[test]
+    fn test_pick_auto_link_goal() {
+        let nodes = vec![
+            goal(1, "pending", "2025-06-01T10:00:00", Some("feature-x")),
+            goal(2, "completed", "2025-06-02T10:00:00", Some("feature-x")),
+            goal(3, "pending", "2025-06-03T10:00:00", Some("main")),
+            goal(4, "rejected", "2025-06-04T10:00:00", None),
+        ];
+        let pick = |branch| pick_auto_link_goal(&nodes, branch).map(|n| n.id);
+
+        // Open goal on the branch beats newer closed and off-branch goals
+        assert_eq!(pick(Some("feature-x")), Some(1));
+        assert_eq!(pick(Some("main")), Some(3));
+        // No goals on the branch: most recent open goal anywhere
+        assert_eq!(pick(Some("other")), Some(3));
+        assert_eq!(pick(None), Some(3));
+        assert_eq!(
+            pick_auto_link_goal(&nodes[3..], None).map(|n| n.id),
+            Some(4)
+        );
+        assert!(pick_auto_link_goal(&[], Some("main")).is_none());
+    }
+
     // === update_node_commit Tests ===
 
     #[test]
@@ -3198,4 +6321,640 @@ mod tests {
 
         assert_eq!(meta.get("commit").unwrap(), "new_commit_hash");
     }
+
+    // === PR links ===
+
+    #[test]
+    fn test_pull_request_cache_in_graph() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path.to_str().unwrap()).unwrap();
+
+        let node_id = db
+            .create_node("action", "Ship it", None, None, None)
+            .unwrap();
+        db.update_node_metadata_field(node_id, "pr", serde_json::json!(42))
+            .unwrap();
+        db.cache_pull_request(42, "owner/repo", "Ship it", "open", "feat", "https://x/42")
+            .unwrap();
+        // Re-caching replaces the previous state
+        db.cache_pull_request(
+            42,
+            "owner/repo",
+            "Ship it",
+            "merged",
+            "feat",
+            "https://x/42",
+        )
+        .unwrap();
+
+        let graph = db.get_graph().unwrap();
+        assert_eq!(graph.pull_requests.len(), 1);
+        let pr = graph.pull_request_for(&graph.nodes[0]).unwrap();
+        assert_eq!(pr.state, "merged");
+        assert_eq!(node_pr_number(&graph.nodes[0]), Some(42));
+    }
+
+    #[test]
+    fn test_expired_cache_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path.to_str().unwrap()).unwrap();
+
+        db.cache_github_issue(1, "o/r", "Issue", None, "open", "u", "c", "u")
+            .unwrap();
+        db.cache_pull_request(2, "o/r", "PR", "open", "b", "u")
+            .unwrap();
+
+        // Freshly cached entries aren't expired
+        assert!(db.get_expired_cached_issues(1).unwrap().is_empty());
+        assert!(db.get_expired_cached_pull_requests(1).unwrap().is_empty());
+        // A cutoff in the future treats everything as expired
+        assert_eq!(db.get_expired_cached_issues(-1).unwrap().len(), 1);
+        assert_eq!(db.get_expired_cached_pull_requests(-1).unwrap().len(), 1);
+    }
+
+    // === Issue import helpers ===
+
+    #[test]
+    fn test_find_node_by_issue() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path.to_str().unwrap()).unwrap();
+
+        let node_id = db
+            .create_node("goal", "Imported goal", None, Some(90), None)
+            .unwrap();
+        db.update_node_metadata_field(
+            node_id,
+            "issue",
+            serde_json::json!({"repo": "owner/repo", "number": 12, "url": "https://x"}),
+        )
+        .unwrap();
+
+        let found = db.find_node_by_issue("owner/repo", 12).unwrap().unwrap();
+        assert_eq!(found.id, node_id);
+        assert!(db.find_node_by_issue("owner/repo", 13).unwrap().is_none());
+        assert!(db.find_node_by_issue("other/repo", 12).unwrap().is_none());
+
+        let obs_id = db
+            .create_node("observation", "@someone: comment", None, None, None)
+            .unwrap();
+        db.update_node_metadata_field(
+            obs_id,
+            "issue_comment",
+            serde_json::json!({"repo": "owner/repo", "issue": 12, "id": 9876543210_i64}),
+        )
+        .unwrap();
+        let found_comment = db
+            .find_node_by_issue_comment("owner/repo", 9876543210)
+            .unwrap()
+            .unwrap();
+        assert_eq!(found_comment.id, obs_id);
+        // The comment's own "issue" number doesn't make it an imported issue
+        assert_eq!(
+            db.find_node_by_issue("owner/repo", 12).unwrap().unwrap().id,
+            node_id
+        );
+
+        // Existing metadata survives
+        let meta: serde_json::Value =
+            serde_json::from_str(found.metadata_json.as_ref().unwrap()).unwrap();
+        assert_eq!(meta.get("confidence").unwrap(), 90);
+    }
+
+    #[test]
+    fn test_update_node_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path.to_str().unwrap()).unwrap();
+
+        let node_id = db.create_node("goal", "Old", None, None, None).unwrap();
+        db.update_node_content(node_id, "New", Some("Body"))
+            .unwrap();
+
+        let node = db.get_node_by_id(node_id).unwrap().unwrap();
+        assert_eq!(node.title, "New");
+        assert_eq!(node.description.as_deref(), Some("Body"));
+    }
+
+    #[test]
+    fn test_roadmap_issues_ready_to_close() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path.to_str().unwrap()).unwrap();
+
+        let section_id = db
+            .create_roadmap_item("Auth", None, Some("v1"), None, "none")
+            .unwrap();
+        db.update_roadmap_item_github(section_id, Some(7), Some("open"))
+            .unwrap();
+        let task_a = db
+            .create_roadmap_item("Login", None, Some("Auth"), None, "checked")
+            .unwrap();
+        let task_b = db
+            .create_roadmap_item("Logout", None, Some("Auth"), None, "checked")
+            .unwrap();
+
+        let outcome_a = db
+            .create_node("outcome", "Login works", None, None, None)
+            .unwrap();
+        let outcome_b = db
+            .create_node("outcome", "Logout works", None, None, None)
+            .unwrap();
+        db.link_roadmap_to_outcome(task_a, outcome_a, "a").unwrap();
+        db.link_roadmap_to_outcome(task_b, outcome_b, "b").unwrap();
+        db.update_node_status(outcome_a, "completed").unwrap();
+
+        // One outcome still pending
+        assert!(db.get_roadmap_issues_ready_to_close().unwrap().is_empty());
+
+        db.update_node_status(outcome_b, "completed").unwrap();
+        let ready = db.get_roadmap_issues_ready_to_close().unwrap();
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].0.github_issue_number, Some(7));
+        assert_eq!(ready[0].1.len(), 2);
+
+        // Already closed issues aren't returned again
+        db.update_roadmap_item_github(section_id, Some(7), Some("closed"))
+            .unwrap();
+        assert!(db.get_roadmap_issues_ready_to_close().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_roadmap_items_ready_to_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path.to_str().unwrap()).unwrap();
+
+        let open_task = db
+            .create_roadmap_item("Login", None, Some("Auth"), None, "unchecked")
+            .unwrap();
+        let done_task = db
+            .create_roadmap_item("Logout", None, Some("Auth"), None, "checked")
+            .unwrap();
+        let outcome = db
+            .create_node("outcome", "Auth works", None, None, None)
+            .unwrap();
+        db.link_roadmap_to_outcome(open_task, outcome, "a").unwrap();
+        db.link_roadmap_to_outcome(done_task, outcome, "a").unwrap();
+
+        assert!(db.get_roadmap_items_ready_to_check().unwrap().is_empty());
+
+        db.update_node_status(outcome, "completed").unwrap();
+        let ready = db.get_roadmap_items_ready_to_check().unwrap();
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].id, open_task);
+    }
+
+    #[test]
+    fn test_roadmap_item_multiple_outcomes() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+
+        let item = db
+            .create_roadmap_item("Login", None, Some("Auth"), None, "unchecked")
+            .unwrap();
+        let first = db
+            .create_node("outcome", "Backend merged", None, None, None)
+            .unwrap();
+        let second = db
+            .create_node("outcome", "UI merged", None, None, None)
+            .unwrap();
+        db.link_roadmap_to_outcome(item, first, "first").unwrap();
+        db.link_roadmap_to_outcome(item, second, "second").unwrap();
+        // Linking the same outcome twice is a no-op
+        db.link_roadmap_to_outcome(item, second, "second").unwrap();
+
+        let links = db.get_roadmap_item_outcomes(item).unwrap();
+        assert_eq!(links.len(), 2);
+        let stored = &db.get_all_roadmap_items().unwrap()[0];
+        assert_eq!(stored.outcome_node_id, Some(first));
+
+        // Not ready until every linked outcome is completed
+        db.update_node_status(first, "completed").unwrap();
+        assert!(db.get_roadmap_items_ready_to_check().unwrap().is_empty());
+        db.update_node_status(second, "completed").unwrap();
+        assert_eq!(db.get_roadmap_items_ready_to_check().unwrap().len(), 1);
+
+        // Dropping the first link promotes the next one
+        assert!(db.unlink_roadmap_outcome(item, first).unwrap());
+        assert!(!db.unlink_roadmap_outcome(item, first).unwrap());
+        let stored = &db.get_all_roadmap_items().unwrap()[0];
+        assert_eq!(stored.outcome_node_id, Some(second));
+        assert_eq!(stored.outcome_change_id.as_deref(), Some("second"));
+
+        db.unlink_roadmap_from_outcome(item).unwrap();
+        assert!(db.get_roadmap_item_outcomes(item).unwrap().is_empty());
+        assert!(db.get_roadmap_items_ready_to_check().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_roadmap_item_schedule() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let db = Database::new(db_path.to_str().unwrap()).unwrap();
+
+        let id = db
+            .create_roadmap_item("Login (P1)", None, Some("Auth"), None, "unchecked")
+            .unwrap();
+        db.set_roadmap_item_schedule(id, Some(1), Some("2025-06"))
+            .unwrap();
+
+        let item = &db.get_all_roadmap_items().unwrap()[0];
+        assert_eq!(item.priority, Some(1));
+        assert_eq!(item.due_date.as_deref(), Some("2025-06"));
+
+        // Reopening runs the column migration again without failing
+        drop(db);
+        let db = Database::new(db_path.to_str().unwrap()).unwrap();
+        assert_eq!(db.get_all_roadmap_items().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_archive_roadmap_section() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+
+        db.create_roadmap_item("Shipped", None, Some("Milestone"), None, "none")
+            .unwrap();
+        db.create_roadmap_item("Done thing", None, Some("Shipped"), None, "checked")
+            .unwrap();
+        db.create_roadmap_item("Open Part", None, Some("Milestone"), None, "none")
+            .unwrap();
+        db.create_roadmap_item("Next thing", None, Some("Open Part"), None, "unchecked")
+            .unwrap();
+
+        assert_eq!(db.archive_roadmap_section("Shipped").unwrap(), 2);
+        // Already archived rows keep their original timestamp
+        assert_eq!(db.archive_roadmap_section("Shipped").unwrap(), 0);
+
+        let archived: Vec<String> = db
+            .get_all_roadmap_items()
+            .unwrap()
+            .into_iter()
+            .filter(|i| i.archived_at.is_some())
+            .map(|i| i.title)
+            .collect();
+        assert_eq!(archived, vec!["Shipped", "Done thing"]);
+    }
+
+    #[test]
+    fn test_link_roadmap_to_goal() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+
+        let item = db
+            .create_roadmap_item("Login", None, Some("Auth"), None, "unchecked")
+            .unwrap();
+        let goal = db.create_node("goal", "Login", None, None, None).unwrap();
+        let goal_change_id = db.get_node_by_id(goal).unwrap().unwrap().change_id;
+        db.link_roadmap_to_goal(item, goal, &goal_change_id)
+            .unwrap();
+
+        let item = &db.get_all_roadmap_items().unwrap()[0];
+        assert_eq!(item.goal_node_id, Some(goal));
+        assert_eq!(
+            item.goal_change_id.as_deref(),
+            Some(goal_change_id.as_str())
+        );
+        assert_eq!(item.outcome_node_id, None);
+    }
+
+    #[test]
+    fn test_paged_reads() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let graph_json = |db: &Database| {
+            let mut out = Vec::new();
+            db.write_graph_json(&mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let pretty =
+            |db: &Database| serde_json::to_string_pretty(&db.get_graph().unwrap()).unwrap();
+        assert_eq!(graph_json(&db), pretty(&db) + "\n");
+
+        let ids: Vec<i32> = (0..5)
+            .map(|i| db.node("action", &format!("Step {}", i)).insert().unwrap())
+            .collect();
+        for pair in ids.windows(2) {
+            db.edge(pair[0], pair[1]).insert().unwrap();
+        }
+        db.edge(ids[4], ids[0]).insert().unwrap();
+
+        let nodes: Vec<i32> = db.iter_nodes(2).map(|n| n.unwrap().id).collect();
+        assert_eq!(nodes, ids);
+        assert_eq!(db.iter_edges(5).count(), 5);
+
+        // Every edge turns up exactly once across the pages
+        let (mut after, mut pages, mut edges) = (None, 0, Vec::new());
+        loop {
+            let page = db.get_graph_page(after, 2).unwrap();
+            pages += 1;
+            edges.extend(page.edges.iter().map(|e| e.id));
+            match page.next {
+                Some(next) => after = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(pages, 3);
+        assert_eq!(edges.len(), 5);
+        edges.sort_unstable();
+        let all: Vec<i32> = db.get_all_edges().unwrap().iter().map(|e| e.id).collect();
+        assert_eq!(edges, all);
+
+        assert_eq!(graph_json(&db), pretty(&db) + "\n");
+
+        // Left-out nodes take their edges with them; config goes in as exported
+        let config = crate::config::Config::default();
+        let mut out = Vec::new();
+        let counts = db
+            .stream_graph_json(
+                &mut out,
+                Some(&config),
+                |n| {
+                    n.title.make_ascii_uppercase();
+                    n.id != ids[0]
+                },
+                |e| e.rationale = Some("kept".to_string()),
+            )
+            .unwrap();
+        assert_eq!(counts, (4, 3));
+        let mut expected = db.get_graph_with_config(Some(config)).unwrap();
+        expected.nodes.retain(|n| n.id != ids[0]);
+        expected
+            .edges
+            .retain(|e| e.from_node_id != ids[0] && e.to_node_id != ids[0]);
+        for n in &mut expected.nodes {
+            n.title.make_ascii_uppercase();
+        }
+        for e in &mut expected.edges {
+            e.rationale = Some("kept".to_string());
+        }
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            serde_json::to_string_pretty(&expected).unwrap() + "\n"
+        );
+    }
+
+    #[test]
+    fn test_trace_sessions_to_prune_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+
+        for id in ["old", "linked", "recent", "active"] {
+            db.start_trace_session(id, None, None, None).unwrap();
+            let span = db.create_trace_span(id, None, None).unwrap();
+            db.add_trace_content(span, "response", &"x".repeat(1000), None, None)
+                .unwrap();
+            if id != "active" {
+                db.end_trace_session(id, None).unwrap();
+            }
+        }
+        let node = db.create_node("goal", "Keep me", None, None, None).unwrap();
+        db.link_trace_session_to_node("linked", node).unwrap();
+
+        // Nothing is too old, and everything fits
+        assert!(db
+            .trace_sessions_to_prune(Some(1), Some(u64::MAX), true)
+            .unwrap()
+            .is_empty());
+
+        // Over any size: oldest ended sessions first, never active or linked
+        let ids = db.trace_sessions_to_prune(None, Some(0), true).unwrap();
+        assert_eq!(ids, vec!["old", "recent"]);
+        let ids = db.trace_sessions_to_prune(None, Some(0), false).unwrap();
+        assert_eq!(ids, vec!["old", "linked", "recent"]);
+
+        assert_eq!(db.count_trace_sessions_data(&ids).unwrap(), (3, 3, 3));
+        assert_eq!(db.delete_trace_sessions(&ids).unwrap(), (3, 3, 3));
+        assert_eq!(db.get_trace_sessions(10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_span_annotations() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        db.start_trace_session("s", None, None, None).unwrap();
+        let span = db.create_trace_span("s", None, None).unwrap();
+
+        let first = db.annotate_span(span, "started guessing here").unwrap();
+        db.annotate_span(span, "this is where it went wrong")
+            .unwrap();
+        let notes = db.get_annotations_for_spans(&[span]).unwrap();
+        let notes: Vec<&str> = notes[&span].iter().map(|a| a.note.as_str()).collect();
+        assert_eq!(
+            notes,
+            vec!["started guessing here", "this is where it went wrong"]
+        );
+
+        assert!(db.delete_span_annotation(first).unwrap());
+        assert!(!db.delete_span_annotation(first).unwrap());
+        assert_eq!(
+            db.get_annotations_for_spans(&[span]).unwrap()[&span].len(),
+            1
+        );
+
+        db.delete_trace_sessions(&["s".to_string()]).unwrap();
+        assert!(db.get_annotations_for_spans(&[span]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_node_and_edge_builders() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+
+        let goal = db.node("goal", "Add caching").insert().unwrap();
+        let action = db
+            .node("action", "Write LRU")
+            .description("In memory for now")
+            .confidence(85)
+            .commit("abc123")
+            .prompt("make it faster")
+            .files(["src/cache.rs", "src/lib.rs"])
+            .branch("feature-cache")
+            .insert()
+            .unwrap();
+
+        let node = db.get_node_by_id(action).unwrap().unwrap();
+        assert_eq!(node.description.as_deref(), Some("In memory for now"));
+        let meta: serde_json::Value =
+            serde_json::from_str(node.metadata_json.as_ref().unwrap()).unwrap();
+        assert_eq!(meta["confidence"], 85);
+        assert_eq!(meta["files"], json!(["src/cache.rs", "src/lib.rs"]));
+        assert_eq!(meta["branch"], "feature-cache");
+        assert!(db
+            .get_node_by_id(goal)
+            .unwrap()
+            .unwrap()
+            .metadata_json
+            .is_none());
+
+        db.edge(goal, action)
+            .edge_type("chosen")
+            .rationale("simplest")
+            .insert()
+            .unwrap();
+        let edges = db.get_all_edges().unwrap();
+        assert_eq!(edges[0].edge_type, "chosen");
+        assert_eq!(edges[0].rationale.as_deref(), Some("simplest"));
+        assert!(db.edge(goal, 999).insert().is_err());
+    }
+
+    #[test]
+    fn test_builder_conventions_through_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let store: &dyn GraphStore = &db;
+        let rules: RulesConfig = toml::from_str(
+            "[confidence]\naction = 70\n[require]\ngoal = [\"prompt\"]\n[edge_require]\nrejected = [\"rationale\"]",
+        )
+        .unwrap();
+
+        let err = store.node("goal", "Cache").rules(&rules).insert().unwrap_err();
+        assert!(err.to_string().contains("prompt"), "{}", err);
+        let goal = store
+            .node("goal", "Cache")
+            .prompt("make it faster")
+            .rules(&rules)
+            .insert()
+            .unwrap();
+        db.set_focus(goal, None).unwrap();
+
+        let action = store
+            .node("action", "Write LRU")
+            .rules(&rules)
+            .project("api")
+            .pr(12)
+            .link_focus()
+            .insert()
+            .unwrap();
+        let meta: serde_json::Value = serde_json::from_str(
+            db.get_node_by_id(action)
+                .unwrap()
+                .unwrap()
+                .metadata_json
+                .as_ref()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(meta["confidence"], 70);
+        assert_eq!(meta["project"], "api");
+        assert_eq!(meta["pr"], 12);
+        let edges = db.get_all_edges().unwrap();
+        assert_eq!((edges[0].from_node_id, edges[0].to_node_id), (goal, action));
+        assert_eq!(edges[0].rationale.as_deref(), Some("Focus"));
+
+        let rejected = store.edge(goal, action).edge_type("rejected").rules(&rules);
+        assert!(rejected.insert().is_err());
+    }
+
+    #[test]
+    fn test_subscribe() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let before = db.node("goal", "Unseen").insert().unwrap();
+
+        let receiver = db.subscribe();
+        let goal = db.node("goal", "Add caching").insert().unwrap();
+        db.edge(before, goal).insert().unwrap();
+        db.update_node_status(goal, "completed").unwrap();
+        db.update_node_status(999, "completed").unwrap();
+
+        let events: Vec<DbEvent> = receiver.try_iter().collect();
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], DbEvent::NodeAdded { node } if node.title == "Add caching"));
+        assert!(
+            matches!(&events[1], DbEvent::EdgeAdded { edge } if edge.from_node_id == before && edge.to_node_id == goal)
+        );
+        assert!(matches!(
+            &events[2],
+            DbEvent::StatusChanged { node_id, status } if *node_id == goal && status == "completed"
+        ));
+
+        // A dropped receiver is forgotten on the next change
+        drop(receiver);
+        db.node("action", "Write LRU").insert().unwrap();
+        assert!(db.subscribers.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_command_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        let db = Database::new(path.to_str().unwrap()).unwrap();
+        let untracked = db.node("goal", "Before logging").insert().unwrap();
+
+        let command_id = db.log_command("deciduous add", None, None).unwrap();
+        let db = Database::open_at(&path)
+            .unwrap()
+            .with_command_log(Some(command_id));
+        let action = db.node("action", "Add cache").insert().unwrap();
+        let edge = db.edge(untracked, action).insert().unwrap();
+        db.update_node_status(action, "completed").unwrap();
+        db.update_node_metadata_field(action, "files", json!(["src/cache.rs"]))
+            .unwrap();
+        db.delete_node(action).unwrap();
+
+        let changes: Vec<(String, i32, String, String)> = db
+            .get_command_changes(command_id)
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.kind, c.target_id, c.action, c.detail))
+            .collect();
+        let change = |kind: &str, id: i32, action: &str, detail: String| {
+            (kind.to_string(), id, action.to_string(), detail)
+        };
+        assert_eq!(
+            changes,
+            vec![
+                change("node", action, "created", "[action] Add cache".into()),
+                change(
+                    "edge",
+                    edge,
+                    "created",
+                    format!("{} -> {} (leads_to)", untracked, action)
+                ),
+                change("node", action, "updated", "status = completed".into()),
+                change("node", action, "updated", "metadata.files".into()),
+                change(
+                    "edge",
+                    edge,
+                    "deleted",
+                    format!("{} -> {} (leads_to)", untracked, action)
+                ),
+                change("node", action, "deleted", "[action] Add cache".into()),
+            ]
+        );
+        assert!(db.get_command_changes(command_id + 1).unwrap().is_empty());
+    }
+
+    // === node_touches_file Tests ===
+
+    fn node_with_files(files: &str) -> DecisionNode {
+        DecisionNode {
+            id: 1,
+            change_id: "c".to_string(),
+            node_type: "action".into(),
+            title: "t".to_string(),
+            description: None,
+            status: "pending".into(),
+            created_at: String::new(),
+            updated_at: String::new(),
+            metadata_json: Some(format!(r#"{{"files":{}}}"#, files)),
+        }
+    }
+
+    #[test]
+    fn test_node_touches_file() {
+        let node = node_with_files(r#"["src/db.rs","./README.md"]"#);
+        assert!(node_touches_file(&node, "src/db.rs"));
+        assert!(node_touches_file(&node, "README.md"));
+        assert!(!node_touches_file(&node, "src/main.rs"));
+
+        // Files recorded relative to a subdirectory still match
+        let node = node_with_files(r#"["db.rs"]"#);
+        assert!(node_touches_file(&node, "src/db.rs"));
+        assert!(!node_touches_file(&node, "src/xdb.rs"));
+    }
 }
@@ -0,0 +1,160 @@
+//! Probable duplicate nodes
+//!
+//! Agents that retry a step often log the same action twice. `deciduous
+//! audit --dedupe` pairs nodes of the same type whose titles are nearly
+//! identical and that were created close together or record the same commit,
+//! and offers to merge each pair with `deciduous merge`.
+
+use crate::db::DecisionNode;
+use crate::trace_diff::similarity;
+use chrono::DateTime;
+use serde_json::Value;
+
+/// Title similarity (0 to 1) accepted for nodes that record the same commit
+const SAME_COMMIT_SIMILARITY: f64 = 0.5;
+
+/// Two nodes that look like one logged twice
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Duplicate {
+    /// The older node, which the newer one is merged into
+    pub keep: i32,
+    pub drop: i32,
+    /// Title similarity, from 0.0 to 1.0
+    pub similarity: f64,
+    /// Why the pair was flagged
+    pub reason: String,
+}
+
+/// Lowercased words of a title, punctuation dropped
+fn normalize(title: &str) -> String {
+    title
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn commit(node: &DecisionNode) -> Option<String> {
+    node.metadata_json
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<Value>(m).ok())
+        .and_then(|m| m["commit"].as_str().map(String::from))
+        .filter(|c| !c.is_empty())
+}
+
+/// Seconds between two RFC 3339 timestamps; None if either doesn't parse
+fn seconds_apart(a: &str, b: &str) -> Option<i64> {
+    let (a, b) = (
+        DateTime::parse_from_rfc3339(a).ok()?,
+        DateTime::parse_from_rfc3339(b).ok()?,
+    );
+    Some((a - b).num_seconds().abs())
+}
+
+/// Pairs of same-type nodes whose titles are at least `min_similarity` alike
+/// and that were created within `window_minutes` of each other, plus
+/// same-commit pairs with loosely similar titles; oldest pairs first
+///
+/// A node already flagged as the newer half of a pair isn't paired again.
+pub fn find_duplicates(
+    nodes: &[DecisionNode],
+    min_similarity: f64,
+    window_minutes: i64,
+) -> Vec<Duplicate> {
+    let mut nodes: Vec<&DecisionNode> = nodes.iter().collect();
+    nodes.sort_by_key(|n| n.id);
+    let titles: Vec<String> = nodes.iter().map(|n| normalize(&n.title)).collect();
+    let commits: Vec<Option<String>> = nodes.iter().map(|n| commit(n)).collect();
+
+    let mut found: Vec<Duplicate> = Vec::new();
+    for (j, newer) in nodes.iter().enumerate() {
+        for (i, older) in nodes[..j].iter().enumerate() {
+            if older.node_type != newer.node_type
+                || found
+                    .iter()
+                    .any(|d| d.drop == older.id || d.drop == newer.id)
+            {
+                continue;
+            }
+            let score = similarity(&titles[i], &titles[j]);
+            let close = seconds_apart(&older.created_at, &newer.created_at)
+                .is_some_and(|s| s <= window_minutes * 60);
+            let reason = match &commits[i] {
+                Some(hash)
+                    if commits[j].as_ref() == Some(hash) && score >= SAME_COMMIT_SIMILARITY =>
+                {
+                    format!("same commit {}", &hash[..hash.len().min(7)])
+                }
+                _ if close && score >= min_similarity => {
+                    format!("created within {} minutes", window_minutes)
+                }
+                _ => continue,
+            };
+            found.push(Duplicate {
+                keep: older.id,
+                drop: newer.id,
+                similarity: score,
+                reason,
+            });
+            break;
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn test_find_and_merge_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let goal = db.node("goal", "Speed up startup").insert().unwrap();
+        let first = db
+            .node("action", "Cache the parsed config")
+            .files(["src/config.rs"])
+            .insert()
+            .unwrap();
+        let retry = db
+            .node("action", "Cache the parsed config.")
+            .description("Second attempt")
+            .files(["src/main.rs"])
+            .commit("abc1234def")
+            .insert()
+            .unwrap();
+        let other = db.node("action", "Lazy-load themes").insert().unwrap();
+        let outcome = db.node("outcome", "Startup is 40ms").insert().unwrap();
+        db.edge(goal, first).insert().unwrap();
+        db.edge(goal, retry).insert().unwrap();
+        db.edge(retry, outcome).insert().unwrap();
+
+        let nodes = db.get_all_nodes().unwrap();
+        let found = find_duplicates(&nodes, 0.85, 60);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].keep, found[0].drop), (first, retry));
+        assert!(!found.iter().any(|d| d.drop == other));
+
+        assert!(db.merge_nodes(first, retry).unwrap());
+        assert!(db.get_node_by_id(retry).unwrap().is_none());
+        let merged = db.get_node_by_id(first).unwrap().unwrap();
+        assert_eq!(merged.description.as_deref(), Some("Second attempt"));
+        let meta: Value = serde_json::from_str(merged.metadata_json.as_deref().unwrap()).unwrap();
+        assert_eq!(
+            meta["files"],
+            serde_json::json!(["src/config.rs", "src/main.rs"])
+        );
+        assert_eq!(meta["commit"], "abc1234def");
+        let edges = db.get_all_edges().unwrap();
+        assert_eq!(edges.len(), 2);
+        assert!(edges
+            .iter()
+            .any(|e| e.from_node_id == first && e.to_node_id == outcome));
+        assert!(!db.merge_nodes(first, retry).unwrap());
+        assert!(db.merge_nodes(first, first).is_err());
+    }
+}
@@ -2,12 +2,29 @@
 //!
 //! Implements jj-inspired change_id based syncing between local databases
 //! and version-controlled patch files.
+//!
+//! Version 2 patches also carry field-level updates to existing nodes and
+//! tombstones for deleted ones. Both are stamped with the time of the change,
+//! and a local node edited after that time keeps its own version. Version 1
+//! patches (nodes and edges only) still load and apply.
+//!
+//! A node the receiver already has that still differs after updates are
+//! merged (the local copy changed after the author's, or a v1 patch carries a
+//! different version) is a conflict. Each differing field is recorded in
+//! `node_conflicts`, and a `ConflictStrategy` decides which side wins.
 
-use crate::db::{Database, DecisionEdge, DecisionNode};
+use crate::db::{Database, DecisionEdge, DecisionNode, NodeConflict};
+use crate::signing::PatchSignature;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 
+/// Format version written by `GraphPatch::new`
+pub const PATCH_VERSION: &str = "2.0";
+
+/// Node fields a `NodeUpdate` can change
+pub const UPDATABLE_FIELDS: &[&str] = &["title", "description", "status", "metadata_json"];
+
 /// A patch file containing nodes and edges to sync
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphPatch {
@@ -25,6 +42,15 @@ pub struct GraphPatch {
     pub nodes: Vec<PatchNode>,
     /// Edges included in this patch
     pub edges: Vec<PatchEdge>,
+    /// Changes to nodes the receiver may already have (v2)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub updated_nodes: Vec<NodeUpdate>,
+    /// Nodes the author deleted (v2)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tombstones: Vec<Tombstone>,
+    /// Author's signature over the rest of the patch (see `signing`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<PatchSignature>,
 }
 
 /// A node in a patch file (uses change_id, not integer id)
@@ -46,6 +72,27 @@ pub struct PatchNode {
     pub created_at: String,
 }
 
+/// Field-level changes to a node, keyed by change_id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeUpdate {
+    /// Change ID of the updated node
+    pub change_id: String,
+    /// When the node was changed (RFC 3339)
+    pub updated_at: String,
+    /// New values by field name (see `UPDATABLE_FIELDS`); null clears a
+    /// nullable field
+    pub fields: BTreeMap<String, Option<String>>,
+}
+
+/// A deleted node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tombstone {
+    /// Change ID of the deleted node
+    pub change_id: String,
+    /// When the node was deleted (RFC 3339)
+    pub deleted_at: String,
+}
+
 /// An edge in a patch file (uses change_ids for references)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PatchEdge {
@@ -67,27 +114,49 @@ impl GraphPatch {
         base_commit: Option<String>,
     ) -> Self {
         Self {
-            version: "1.0".to_string(),
+            version: PATCH_VERSION.to_string(),
             author,
             branch,
             created_at: chrono::Local::now().to_rfc3339(),
             base_commit,
             nodes: Vec::new(),
             edges: Vec::new(),
+            updated_nodes: Vec::new(),
+            tombstones: Vec::new(),
+            signature: None,
         }
     }
 
-    /// Load a patch from a JSON file
+    /// Load a patch from a JSON file, decrypting it if it's age-encrypted
     pub fn load(path: &Path) -> Result<Self, String> {
-        let content = std::fs::read_to_string(path)
-            .map_err(|e| format!("Failed to read patch file: {}", e))?;
-        serde_json::from_str(&content).map_err(|e| format!("Failed to parse patch JSON: {}", e))
+        let content =
+            std::fs::read(path).map_err(|e| format!("Failed to read patch file: {}", e))?;
+        Self::from_bytes(&content)
+    }
+
+    /// Parse a patch file's content, decrypting it if it's age-encrypted
+    pub fn from_bytes(content: &[u8]) -> Result<Self, String> {
+        if crate::encryption::is_encrypted(content) {
+            let plaintext = crate::encryption::decrypt(content)?;
+            return serde_json::from_slice(&plaintext)
+                .map_err(|e| format!("Failed to parse decrypted patch JSON: {}", e));
+        }
+        serde_json::from_slice(content).map_err(|e| format!("Failed to parse patch JSON: {}", e))
     }
 
     /// Save the patch to a JSON file
     pub fn save(&self, path: &Path) -> Result<(), String> {
-        let content = serde_json::to_string_pretty(self)
-            .map_err(|e| format!("Failed to serialize patch: {}", e))?;
+        self.save_encrypted(path, &[])
+    }
+
+    /// Save the patch, age-encrypted to `recipients` unless that's empty
+    pub fn save_encrypted(&self, path: &Path, recipients: &[String]) -> Result<(), String> {
+        let mut content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize patch: {}", e))?
+            .into_bytes();
+        if !recipients.is_empty() {
+            content = crate::encryption::encrypt(&content, recipients)?;
+        }
 
         // Create parent directories if needed
         if let Some(parent) = path.parent() {
@@ -98,34 +167,122 @@ impl GraphPatch {
         std::fs::write(path, content).map_err(|e| format!("Failed to write patch file: {}", e))
     }
 
+    /// Whether the patch carries no changes at all
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+            && self.edges.is_empty()
+            && self.updated_nodes.is_empty()
+            && self.tombstones.is_empty()
+    }
+
+    /// Whether two patches carry the same changes, ignoring metadata such as
+    /// `created_at` and the signature
+    pub fn same_content(&self, other: &GraphPatch) -> bool {
+        let content = |p: &GraphPatch| {
+            serde_json::to_value((&p.nodes, &p.edges, &p.updated_nodes, &p.tombstones)).ok()
+        };
+        content(self) == content(other)
+    }
+
     /// Add a node to the patch
     pub fn add_node(&mut self, node: &DecisionNode) {
         self.nodes.push(PatchNode {
             change_id: node.change_id.clone(),
-            node_type: node.node_type.clone(),
+            node_type: node.node_type.to_string(),
             title: node.title.clone(),
             description: node.description.clone(),
-            status: node.status.clone(),
+            status: node.status.to_string(),
             metadata_json: node.metadata_json.clone(),
             created_at: node.created_at.clone(),
         });
     }
 
+    /// Add a node's current field values as an update
+    pub fn add_update(&mut self, node: &DecisionNode) {
+        let fields = BTreeMap::from([
+            ("title".to_string(), Some(node.title.clone())),
+            ("description".to_string(), node.description.clone()),
+            ("status".to_string(), Some(node.status.to_string())),
+            ("metadata_json".to_string(), node.metadata_json.clone()),
+        ]);
+        self.updated_nodes.push(NodeUpdate {
+            change_id: node.change_id.clone(),
+            updated_at: node.updated_at.clone(),
+            fields,
+        });
+    }
+
+    /// Add a deleted node
+    pub fn add_tombstone(&mut self, change_id: &str, deleted_at: &str) {
+        self.tombstones.push(Tombstone {
+            change_id: change_id.to_string(),
+            deleted_at: deleted_at.to_string(),
+        });
+    }
+
     /// Add an edge to the patch
     pub fn add_edge(&mut self, edge: &DecisionEdge) {
         if let (Some(from_cid), Some(to_cid)) = (&edge.from_change_id, &edge.to_change_id) {
             self.edges.push(PatchEdge {
                 from_change_id: from_cid.clone(),
                 to_change_id: to_cid.clone(),
-                edge_type: edge.edge_type.clone(),
+                edge_type: edge.edge_type.to_string(),
                 rationale: edge.rationale.clone(),
             });
         }
     }
 }
 
+/// How to settle a node field that differs between a patch and the local copy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictStrategy {
+    /// Keep the local value and leave the conflict for review
+    #[default]
+    Record,
+    /// Keep the local value
+    Ours,
+    /// Take the patch's value
+    Theirs,
+    /// Take whichever side changed last
+    Newest,
+}
+
+impl ConflictStrategy {
+    /// Parse a `--strategy` value: ours, theirs or newest
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "ours" => Some(Self::Ours),
+            "theirs" => Some(Self::Theirs),
+            "newest" => Some(Self::Newest),
+            _ => None,
+        }
+    }
+
+    /// Whether to take the patch's value, given when each side last changed;
+    /// None leaves the conflict open
+    pub fn takes_theirs(self, theirs_at: &str, ours_at: &str) -> Option<bool> {
+        match self {
+            Self::Record => None,
+            Self::Ours => Some(false),
+            Self::Theirs => Some(true),
+            Self::Newest => Some(is_later(theirs_at, ours_at)),
+        }
+    }
+}
+
+/// A node field's value by name (see `UPDATABLE_FIELDS`)
+fn node_field<'a>(node: &'a DecisionNode, field: &str) -> Option<&'a str> {
+    match field {
+        "title" => Some(node.title.as_str()),
+        "description" => node.description.as_deref(),
+        "status" => Some(node.status.as_str()),
+        "metadata_json" => node.metadata_json.as_deref(),
+        _ => None,
+    }
+}
+
 /// Result of applying a patch
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct ApplyResult {
     /// Number of nodes added
     pub nodes_added: usize,
@@ -137,6 +294,40 @@ pub struct ApplyResult {
     pub edges_skipped: usize,
     /// Edges that couldn't be created (missing nodes)
     pub edges_failed: Vec<String>,
+    /// Number of existing nodes updated
+    pub nodes_updated: usize,
+    /// Updates skipped (unknown node, or the local node changed later)
+    pub updates_skipped: usize,
+    /// Number of nodes deleted by tombstones
+    pub nodes_deleted: usize,
+    /// Tombstones skipped (the local node changed after the deletion)
+    pub tombstones_skipped: usize,
+    /// Fields that differed from the local copy and weren't settled before
+    pub conflicts: usize,
+    /// Conflicts the strategy resolved (the rest are left for review)
+    pub conflicts_resolved: usize,
+}
+
+/// Whether timestamp `a` is later than `b`
+///
+/// RFC 3339 times are compared as instants, so differing UTC offsets don't
+/// matter; anything else falls back to comparing the strings.
+fn is_later(a: &str, b: &str) -> bool {
+    match (
+        chrono::DateTime::parse_from_rfc3339(a),
+        chrono::DateTime::parse_from_rfc3339(b),
+    ) {
+        (Ok(a), Ok(b)) => a > b,
+        _ => a > b,
+    }
+}
+
+/// The branch recorded in a node's metadata
+fn node_branch(node: &DecisionNode) -> Option<String> {
+    node.metadata_json
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        .and_then(|m| m.get("branch").and_then(|b| b.as_str()).map(String::from))
 }
 
 impl Database {
@@ -190,6 +381,20 @@ impl Database {
             patch.add_node(node);
         }
 
+        // Nodes changed since creation also go out as updates, so receivers
+        // that already have them pick up the changes
+        for node in nodes
+            .iter()
+            .filter(|n| is_later(&n.updated_at, &n.created_at))
+        {
+            patch.add_update(node);
+        }
+
+        // Deletions aren't tied to a branch or node selection
+        for tombstone in self.get_tombstones()? {
+            patch.add_tombstone(&tombstone.change_id, &tombstone.deleted_at);
+        }
+
         // Add edges where BOTH endpoints are in the patch
         // Note: We use AND, not OR, because applying a patch requires both nodes to exist
         for edge in &all_edges {
@@ -205,11 +410,62 @@ impl Database {
         Ok(patch)
     }
 
-    /// Apply a patch to the database
+    /// Export only what changed since earlier exports (`diff export --new`)
+    ///
+    /// Nodes that were never exported go out in full; exported ones only as
+    /// updates, and only if they changed after their last export. Edges are
+    /// kept when they touch a new node.
+    pub fn export_new_patch(
+        &self,
+        branch_filter: Option<&str>,
+        author: Option<String>,
+        base_commit: Option<String>,
+    ) -> Result<GraphPatch, crate::db::DbError> {
+        let mut last_export: HashMap<String, String> = HashMap::new();
+        for export in self.get_node_exports()? {
+            let later = last_export
+                .get(&export.change_id)
+                .map_or(true, |at| is_later(&export.exported_at, at));
+            if later {
+                last_export.insert(export.change_id, export.exported_at);
+            }
+        }
+
+        let mut patch = self.export_patch(None, branch_filter, author, base_commit)?;
+        patch
+            .nodes
+            .retain(|n| !last_export.contains_key(&n.change_id));
+        let new: HashSet<String> = patch.nodes.iter().map(|n| n.change_id.clone()).collect();
+
+        // Updates are only built for exported nodes, so pick up exported ones
+        // that changed since
+        let changed: Vec<DecisionNode> = self
+            .get_all_nodes()?
+            .into_iter()
+            .filter(|n| {
+                last_export
+                    .get(&n.change_id)
+                    .is_some_and(|at| is_later(&n.updated_at, at))
+            })
+            .filter(|n| branch_filter.map_or(true, |b| node_branch(n).as_deref() == Some(b)))
+            .collect();
+        patch.updated_nodes.retain(|u| new.contains(&u.change_id));
+        for node in &changed {
+            patch.add_update(node);
+        }
+
+        patch
+            .edges
+            .retain(|e| new.contains(&e.from_change_id) || new.contains(&e.to_change_id));
+        Ok(patch)
+    }
+
+    /// Apply a patch to the database, settling conflicts with `strategy`
     pub fn apply_patch(
         &self,
         patch: &GraphPatch,
         dry_run: bool,
+        strategy: ConflictStrategy,
     ) -> Result<ApplyResult, crate::db::DbError> {
         let mut result = ApplyResult::default();
 
@@ -224,9 +480,19 @@ impl Database {
             .map(|n| (n.change_id.clone(), n.id))
             .collect();
 
+        // Deleted here or in this patch: don't (re)create
+        let deleted: HashSet<String> = self
+            .get_tombstones()?
+            .into_iter()
+            .map(|t| t.change_id)
+            .chain(patch.tombstones.iter().map(|t| t.change_id.clone()))
+            .collect();
+
         // Apply nodes
         for patch_node in &patch.nodes {
-            if existing_change_ids.contains(&patch_node.change_id) {
+            if existing_change_ids.contains(&patch_node.change_id)
+                || deleted.contains(&patch_node.change_id)
+            {
                 result.nodes_skipped += 1;
                 continue;
             }
@@ -295,18 +561,210 @@ impl Database {
                     branch.as_deref(),
                 )?;
 
+                // Match the author's copy, dated as of its creation so later
+                // updates and tombstones compare against the author's timeline
+                self.replace_node_fields(
+                    local_id,
+                    &patch_node.title,
+                    patch_node.description.as_deref(),
+                    &patch_node.status,
+                    patch_node.metadata_json.as_deref(),
+                    &patch_node.created_at,
+                )?;
+                self.set_node_created_at(local_id, &patch_node.created_at)?;
+
                 change_id_to_local_id.insert(patch_node.change_id.clone(), local_id);
             }
 
             result.nodes_added += 1;
         }
 
+        // Apply updates; a local change made later wins
+        let mut added_in_dry_run: HashSet<&str> = HashSet::new();
+        if dry_run {
+            added_in_dry_run = patch
+                .nodes
+                .iter()
+                .map(|n| n.change_id.as_str())
+                .filter(|cid| !existing_change_ids.contains(*cid) && !deleted.contains(*cid))
+                .collect();
+        }
+        for update in &patch.updated_nodes {
+            let local = match change_id_to_local_id.get(&update.change_id) {
+                Some(&id) => self.get_node_by_id(id)?,
+                None => None,
+            };
+            let Some(local) = local else {
+                if added_in_dry_run.contains(update.change_id.as_str()) {
+                    result.nodes_updated += 1;
+                } else {
+                    result.updates_skipped += 1;
+                }
+                continue;
+            };
+            if is_later(&local.updated_at, &update.updated_at) {
+                result.updates_skipped += 1;
+                continue;
+            }
+
+            let field = |name: &str| update.fields.get(name);
+            let title = field("title")
+                .and_then(|v| v.as_deref())
+                .unwrap_or(local.title.as_str());
+            let status = field("status")
+                .and_then(|v| v.as_deref())
+                .unwrap_or(local.status.as_str());
+            let description = match field("description") {
+                Some(v) => v.as_deref(),
+                None => local.description.as_deref(),
+            };
+            let metadata_json = match field("metadata_json") {
+                Some(v) => v.as_deref(),
+                None => local.metadata_json.as_deref(),
+            };
+            let unchanged = title == local.title
+                && status == local.status
+                && description == local.description.as_deref()
+                && metadata_json == local.metadata_json.as_deref();
+            if unchanged {
+                continue;
+            }
+
+            if !dry_run {
+                self.replace_node_fields(
+                    local.id,
+                    title,
+                    description,
+                    status,
+                    metadata_json,
+                    &update.updated_at,
+                )?;
+            }
+            result.nodes_updated += 1;
+        }
+
+        // Apply tombstones; a local change made after the deletion keeps the node
+        for tombstone in &patch.tombstones {
+            let Some(&local_id) = change_id_to_local_id.get(&tombstone.change_id) else {
+                if !dry_run {
+                    self.add_tombstone(&tombstone.change_id, &tombstone.deleted_at)?;
+                }
+                continue;
+            };
+            let local = self.get_node_by_id(local_id)?;
+            if local
+                .as_ref()
+                .is_some_and(|n| is_later(&n.updated_at, &tombstone.deleted_at))
+            {
+                result.tombstones_skipped += 1;
+                continue;
+            }
+            if !dry_run {
+                self.delete_node_at(local_id, &tombstone.deleted_at)?;
+            }
+            change_id_to_local_id.remove(&tombstone.change_id);
+            result.nodes_deleted += 1;
+        }
+
+        // Nodes we already had that still differ are conflicts
+        let updates: HashMap<&str, &NodeUpdate> = patch
+            .updated_nodes
+            .iter()
+            .map(|u| (u.change_id.as_str(), u))
+            .collect();
+        for patch_node in &patch.nodes {
+            if !existing_change_ids.contains(&patch_node.change_id) {
+                continue;
+            }
+            let local = match change_id_to_local_id.get(&patch_node.change_id) {
+                Some(&id) => self.get_node_by_id(id)?,
+                None => None,
+            };
+            let Some(local) = local else {
+                continue;
+            };
+            let update = updates.get(patch_node.change_id.as_str());
+            match update {
+                // Merged above, so the local copy is theirs
+                Some(u) if !is_later(&local.updated_at, &u.updated_at) => continue,
+                // A v2 patch lists the author's edits; without one the local
+                // copy has simply moved on from theirs
+                None if patch.version != "1.0"
+                    && is_later(&local.updated_at, &patch_node.created_at) =>
+                {
+                    continue
+                }
+                _ => {}
+            }
+            let theirs_at =
+                update.map_or(patch_node.created_at.as_str(), |u| u.updated_at.as_str());
+
+            let mut incoming: BTreeMap<&str, Option<&str>> = BTreeMap::from([
+                ("title", Some(patch_node.title.as_str())),
+                ("description", patch_node.description.as_deref()),
+                ("status", Some(patch_node.status.as_str())),
+                ("metadata_json", patch_node.metadata_json.as_deref()),
+            ]);
+            if let Some(update) = update {
+                for (field, value) in &update.fields {
+                    if let Some(slot) = incoming.get_mut(field.as_str()) {
+                        *slot = value.as_deref();
+                    }
+                }
+            }
+
+            let mut take: Vec<(&str, Option<&str>)> = Vec::new();
+            for (field, theirs) in incoming {
+                let ours = node_field(&local, field);
+                if theirs == ours || (theirs.is_none() && matches!(field, "title" | "status")) {
+                    continue;
+                }
+                let recorded = self.find_node_conflict(&local.change_id, field, theirs)?;
+                if recorded.as_ref().is_some_and(|c| c.resolution.is_some()) {
+                    continue;
+                }
+
+                result.conflicts += 1;
+                let takes_theirs = strategy.takes_theirs(theirs_at, &local.updated_at);
+                if takes_theirs.is_some() {
+                    result.conflicts_resolved += 1;
+                }
+                if dry_run {
+                    continue;
+                }
+                let conflict_id = match recorded {
+                    Some(c) => c.id,
+                    None => self.create_node_conflict(
+                        &local.change_id,
+                        field,
+                        ours,
+                        theirs,
+                        theirs_at,
+                        patch.author.as_deref(),
+                        None,
+                    )?,
+                };
+                if let Some(takes_theirs) = takes_theirs {
+                    self.resolve_node_conflict(
+                        conflict_id,
+                        if takes_theirs { "theirs" } else { "ours" },
+                    )?;
+                    if takes_theirs {
+                        take.push((field, theirs));
+                    }
+                }
+            }
+            if !take.is_empty() {
+                self.set_node_fields(&local, &take)?;
+            }
+        }
+
         // Get existing edges (by change_id pairs)
         let existing_edges = self.get_all_edges()?;
         let existing_edge_keys: HashSet<(String, String, String)> = existing_edges
             .iter()
             .filter_map(|e| match (&e.from_change_id, &e.to_change_id) {
-                (Some(from), Some(to)) => Some((from.clone(), to.clone(), e.edge_type.clone())),
+                (Some(from), Some(to)) => Some((from.clone(), to.clone(), e.edge_type.to_string())),
                 _ => None,
             })
             .collect();
@@ -319,7 +777,10 @@ impl Database {
                 patch_edge.edge_type.clone(),
             );
 
-            if existing_edge_keys.contains(&edge_key) {
+            if existing_edge_keys.contains(&edge_key)
+                || deleted.contains(&patch_edge.from_change_id)
+                || deleted.contains(&patch_edge.to_change_id)
+            {
                 result.edges_skipped += 1;
                 continue;
             }
@@ -352,6 +813,76 @@ impl Database {
 
         Ok(result)
     }
+
+    /// Resolve recorded conflicts with `strategy`; returns how many were resolved
+    ///
+    /// Conflicts on one node are decided together, against the node as it
+    /// was before any of them were applied.
+    pub fn resolve_conflicts(
+        &self,
+        conflicts: &[NodeConflict],
+        strategy: ConflictStrategy,
+    ) -> Result<usize, crate::db::DbError> {
+        let mut by_node: BTreeMap<&str, Vec<&NodeConflict>> = BTreeMap::new();
+        for conflict in conflicts.iter().filter(|c| c.resolution.is_none()) {
+            by_node
+                .entry(conflict.change_id.as_str())
+                .or_default()
+                .push(conflict);
+        }
+
+        let nodes: HashMap<String, DecisionNode> = self
+            .get_all_nodes()?
+            .into_iter()
+            .map(|n| (n.change_id.clone(), n))
+            .collect();
+        let mut resolved = 0;
+        for (change_id, conflicts) in by_node {
+            let Some(local) = nodes.get(change_id) else {
+                continue;
+            };
+            let mut take: Vec<(&str, Option<&str>)> = Vec::new();
+            for conflict in conflicts {
+                let Some(takes_theirs) =
+                    strategy.takes_theirs(&conflict.remote_updated_at, &local.updated_at)
+                else {
+                    continue;
+                };
+                if takes_theirs {
+                    take.push((conflict.field.as_str(), conflict.remote_value.as_deref()));
+                }
+                self.resolve_node_conflict(
+                    conflict.id,
+                    if takes_theirs { "theirs" } else { "ours" },
+                )?;
+                resolved += 1;
+            }
+            if !take.is_empty() {
+                self.set_node_fields(local, &take)?;
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// Overwrite some of a node's fields, as a local change made now
+    fn set_node_fields(
+        &self,
+        node: &DecisionNode,
+        values: &[(&str, Option<&str>)],
+    ) -> Result<(), crate::db::DbError> {
+        let value = |field: &str| match values.iter().find(|(f, _)| *f == field) {
+            Some((_, v)) => *v,
+            None => node_field(node, field),
+        };
+        self.replace_node_fields(
+            node.id,
+            value("title").unwrap_or(&node.title),
+            value("description"),
+            value("status").unwrap_or(&node.status),
+            value("metadata_json"),
+            &chrono::Local::now().to_rfc3339(),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -362,10 +893,10 @@ mod tests {
         DecisionNode {
             id,
             change_id: change_id.to_string(),
-            node_type: node_type.to_string(),
+            node_type: node_type.into(),
             title: title.to_string(),
             description: None,
-            status: "pending".to_string(),
+            status: "pending".into(),
             created_at: "2024-01-01T00:00:00Z".to_string(),
             updated_at: "2024-01-01T00:00:00Z".to_string(),
             metadata_json: Some(r#"{"branch": "main", "confidence": 90}"#.to_string()),
@@ -379,7 +910,7 @@ mod tests {
             to_node_id: to,
             from_change_id: Some(from_cid.to_string()),
             to_change_id: Some(to_cid.to_string()),
-            edge_type: "leads_to".to_string(),
+            edge_type: "leads_to".into(),
             weight: Some(1.0),
             rationale: Some("test rationale".to_string()),
             created_at: "2024-01-01T00:00:00Z".to_string(),
@@ -396,7 +927,7 @@ mod tests {
             Some("abc123".to_string()),
         );
 
-        assert_eq!(patch.version, "1.0");
+        assert_eq!(patch.version, PATCH_VERSION);
         assert_eq!(patch.author, Some("alice".to_string()));
         assert_eq!(patch.branch, Some("feature-x".to_string()));
         assert_eq!(patch.base_commit, Some("abc123".to_string()));
@@ -474,7 +1005,10 @@ mod tests {
         let json = serde_json::to_string_pretty(&patch).expect("serialize");
 
         // Verify it contains expected fields
-        assert!(json.contains("\"version\": \"1.0\""));
+        assert!(json.contains("\"version\": \"2.0\""));
+        // Empty v2 sections are left out, so the file reads like v1
+        assert!(!json.contains("updated_nodes"));
+        assert!(!json.contains("tombstones"));
         assert!(json.contains("\"author\": \"alice\""));
         assert!(json.contains("\"nodes\": []"));
         assert!(json.contains("\"edges\": []"));
@@ -487,10 +1021,10 @@ mod tests {
         let node = DecisionNode {
             id: 42,
             change_id: "unique-uuid".to_string(),
-            node_type: "decision".to_string(),
+            node_type: "decision".into(),
             title: "Choose framework".to_string(),
             description: Some("Evaluate options".to_string()),
-            status: "completed".to_string(),
+            status: "completed".into(),
             created_at: "2024-06-01T12:00:00Z".to_string(),
             updated_at: "2024-06-01T12:00:00Z".to_string(),
             metadata_json: Some(r#"{"confidence": 85}"#.to_string()),
@@ -519,6 +1053,252 @@ mod tests {
         assert_eq!(result.edges_added, 0);
         assert_eq!(result.edges_skipped, 0);
         assert!(result.edges_failed.is_empty());
+        assert_eq!(result.nodes_updated, 0);
+        assert_eq!(result.nodes_deleted, 0);
+    }
+
+    // === Updates and Tombstones ===
+
+    #[test]
+    fn test_v1_patch_loads() {
+        let json = r#"{"version": "1.0", "author": null, "branch": null,
+            "created_at": "2025-01-01T00:00:00Z", "base_commit": null,
+            "nodes": [], "edges": []}"#;
+        let patch: GraphPatch = serde_json::from_str(json).expect("v1 patch");
+        assert!(patch.updated_nodes.is_empty());
+        assert!(patch.tombstones.is_empty());
+    }
+
+    #[test]
+    fn test_apply_updates_and_tombstones() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+
+        let mut goal = sample_node(1, "cid-goal", "goal", "Ship v2");
+        goal.created_at = "2025-01-01T10:00:00+00:00".to_string();
+        let action = sample_node(2, "cid-action", "action", "Write code");
+        let mut patch = GraphPatch::new(None, None, None);
+        patch.add_node(&goal);
+        patch.add_node(&action);
+        patch.add_edge(&sample_edge(1, 1, 2, "cid-goal", "cid-action"));
+        assert_eq!(
+            db.apply_patch(&patch, false, ConflictStrategy::Record)
+                .unwrap()
+                .nodes_added,
+            2
+        );
+
+        // Later the goal is completed and the action deleted
+        goal.status = "completed".into();
+        goal.updated_at = "2025-01-02T10:00:00+00:00".to_string();
+        let mut changes = GraphPatch::new(None, None, None);
+        changes.add_update(&goal);
+        changes.add_tombstone("cid-action", "2025-01-02T11:00:00+00:00");
+        let json = serde_json::to_string(&changes).unwrap();
+        let changes: GraphPatch = serde_json::from_str(&json).unwrap();
+
+        let dry = db
+            .apply_patch(&changes, true, ConflictStrategy::Record)
+            .unwrap();
+        assert_eq!((dry.nodes_updated, dry.nodes_deleted), (1, 1));
+        assert_eq!(db.get_all_nodes().unwrap().len(), 2);
+
+        let result = db
+            .apply_patch(&changes, false, ConflictStrategy::Record)
+            .unwrap();
+        assert_eq!((result.nodes_updated, result.nodes_deleted), (1, 1));
+        let nodes = db.get_all_nodes().unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].status, "completed");
+        assert_eq!(nodes[0].updated_at, "2025-01-02T10:00:00+00:00");
+        assert!(db.get_all_edges().unwrap().is_empty());
+        assert_eq!(db.get_tombstones().unwrap()[0].change_id, "cid-action");
+
+        // Re-applying the original patch doesn't bring the action back
+        let again = db
+            .apply_patch(&patch, false, ConflictStrategy::Record)
+            .unwrap();
+        assert_eq!(again.nodes_added, 0);
+        assert_eq!(again.edges_skipped, 1);
+        assert_eq!(db.get_all_nodes().unwrap().len(), 1);
+
+        // An update older than the local state is skipped
+        goal.status = "pending".into();
+        goal.updated_at = "2025-01-01T12:00:00+00:00".to_string();
+        let mut stale = GraphPatch::new(None, None, None);
+        stale.add_update(&goal);
+        assert_eq!(
+            db.apply_patch(&stale, false, ConflictStrategy::Record)
+                .unwrap()
+                .updates_skipped,
+            1
+        );
+        assert_eq!(db.get_all_nodes().unwrap()[0].status, "completed");
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let record = ConflictStrategy::Record;
+
+        let mut goal = sample_node(1, "cid-goal", "goal", "Ship v2");
+        goal.created_at = "2025-01-01T10:00:00+00:00".to_string();
+        let mut first = GraphPatch::new(Some("alice".to_string()), None, None);
+        first.add_node(&goal);
+        db.apply_patch(&first, false, record).unwrap();
+
+        // Both sides change the status; ours is later
+        let local = db.get_all_nodes().unwrap().remove(0);
+        assert_eq!(local.created_at, goal.created_at);
+        db.replace_node_fields(
+            local.id,
+            "Ship v2",
+            None,
+            "active",
+            local.metadata_json.as_deref(),
+            "2025-01-03T00:00:00+00:00",
+        )
+        .unwrap();
+        goal.status = "completed".into();
+        goal.updated_at = "2025-01-02T00:00:00+00:00".to_string();
+        let mut theirs = GraphPatch::new(Some("alice".to_string()), None, None);
+        theirs.add_node(&goal);
+        theirs.add_update(&goal);
+
+        let dry = db.apply_patch(&theirs, true, record).unwrap();
+        assert_eq!(dry.conflicts, 1);
+        assert!(db.get_node_conflicts(true).unwrap().is_empty());
+
+        let result = db.apply_patch(&theirs, false, record).unwrap();
+        assert_eq!((result.conflicts, result.conflicts_resolved), (1, 0));
+        let open = db.get_node_conflicts(false).unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].field, "status");
+        assert_eq!(open[0].local_value.as_deref(), Some("active"));
+        assert_eq!(open[0].remote_value.as_deref(), Some("completed"));
+        assert_eq!(open[0].patch_author.as_deref(), Some("alice"));
+
+        // Re-applying doesn't record it twice
+        db.apply_patch(&theirs, false, record).unwrap();
+        assert_eq!(db.get_node_conflicts(true).unwrap().len(), 1);
+
+        // Newest keeps our later change, and the conflict stays settled
+        let result = db
+            .apply_patch(&theirs, false, ConflictStrategy::Newest)
+            .unwrap();
+        assert_eq!(result.conflicts_resolved, 1);
+        assert_eq!(db.get_all_nodes().unwrap()[0].status, "active");
+        assert!(db.get_node_conflicts(false).unwrap().is_empty());
+        assert_eq!(db.apply_patch(&theirs, false, record).unwrap().conflicts, 0);
+
+        // A different value is a new conflict, resolved later for theirs
+        goal.status = "rejected".into();
+        let mut again = GraphPatch::new(None, None, None);
+        again.add_node(&goal);
+        again.add_update(&goal);
+        db.apply_patch(&again, false, record).unwrap();
+        let open = db.get_node_conflicts(false).unwrap();
+        assert_eq!(
+            db.resolve_conflicts(&open, ConflictStrategy::Theirs)
+                .unwrap(),
+            1
+        );
+        assert_eq!(db.get_all_nodes().unwrap()[0].status, "rejected");
+        assert!(db.get_node_conflicts(false).unwrap().is_empty());
+        assert_eq!(db.get_node_conflicts(true).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_conflict_strategy_parse() {
+        assert_eq!(
+            ConflictStrategy::parse("ours"),
+            Some(ConflictStrategy::Ours)
+        );
+        assert_eq!(
+            ConflictStrategy::parse("newest"),
+            Some(ConflictStrategy::Newest)
+        );
+        assert_eq!(ConflictStrategy::parse("mine"), None);
+        let newest = ConflictStrategy::Newest;
+        assert_eq!(
+            newest.takes_theirs("2025-01-02T00:00:00Z", "2025-01-01T00:00:00Z"),
+            Some(true)
+        );
+        assert_eq!(ConflictStrategy::Record.takes_theirs("b", "a"), None);
+    }
+
+    #[test]
+    fn test_export_includes_updates_and_tombstones() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let goal = db.create_node("goal", "Goal", None, None, None).unwrap();
+        let action = db
+            .create_node("action", "Action", None, None, None)
+            .unwrap();
+        db.replace_node_fields(
+            goal,
+            "Goal",
+            None,
+            "completed",
+            None,
+            "2999-01-01T00:00:00Z",
+        )
+        .unwrap();
+        assert!(db.delete_node(action).unwrap());
+        assert!(!db.delete_node(action).unwrap());
+
+        let patch = db.export_patch(None, None, None, None).unwrap();
+        assert_eq!(patch.nodes.len(), 1);
+        assert_eq!(patch.updated_nodes.len(), 1);
+        assert_eq!(
+            patch.updated_nodes[0].fields["status"].as_deref(),
+            Some("completed")
+        );
+        assert_eq!(patch.tombstones.len(), 1);
+    }
+
+    #[test]
+    fn test_export_new() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let goal = db.create_node("goal", "Goal", None, None, None).unwrap();
+        let action = db
+            .create_node("action", "Action", None, None, None)
+            .unwrap();
+        db.add_edge(goal, action, "leads_to", None).unwrap();
+
+        let first = db.export_new_patch(None, None, None).unwrap();
+        assert_eq!((first.nodes.len(), first.edges.len()), (2, 1));
+        let cids: Vec<&str> = first.nodes.iter().map(|n| n.change_id.as_str()).collect();
+        db.record_node_exports(&cids, "first.json", None).unwrap();
+
+        let nothing = db.export_new_patch(None, None, None).unwrap();
+        assert!(nothing.is_empty());
+
+        let outcome = db
+            .create_node("outcome", "Outcome", None, None, None)
+            .unwrap();
+        db.add_edge(action, outcome, "leads_to", None).unwrap();
+        db.replace_node_fields(
+            goal,
+            "Goal",
+            None,
+            "completed",
+            None,
+            "2999-01-01T00:00:00Z",
+        )
+        .unwrap();
+
+        let second = db.export_new_patch(None, None, None).unwrap();
+        let titles: Vec<&str> = second.nodes.iter().map(|n| n.title.as_str()).collect();
+        assert_eq!(titles, vec!["Outcome"]);
+        assert_eq!(second.edges.len(), 1);
+        assert_eq!(second.updated_nodes.len(), 1);
+        assert_eq!(
+            second.updated_nodes[0].fields["status"].as_deref(),
+            Some("completed")
+        );
     }
 
     // === Edge Cases ===
@@ -541,10 +1321,10 @@ mod tests {
         let node = DecisionNode {
             id: 1,
             change_id: "cid-special".to_string(),
-            node_type: "goal".to_string(),
+            node_type: "goal".into(),
             title: "Handle \"quotes\" and 'apostrophes'".to_string(),
             description: Some("Line1\nLine2\tTabbed".to_string()),
-            status: "pending".to_string(),
+            status: "pending".into(),
             created_at: "2024-01-01T00:00:00Z".to_string(),
             updated_at: "2024-01-01T00:00:00Z".to_string(),
             metadata_json: None,
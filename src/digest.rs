@@ -0,0 +1,269 @@
+//! Periodic team digest
+//!
+//! `deciduous digest --since 7d` summarizes a window of work: goals opened,
+//! outcomes reached, decisions still waiting on a choice, API usage from the
+//! trace tables, and roadmap items added, completed or archived. The result
+//! is Markdown, or Slack's mrkdwn with `--slack`.
+
+use crate::db::{Database, DecisionNode, Result, RoadmapItem};
+use crate::pricing::{format_cost, PriceTable, Usage};
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDate, TimeZone};
+
+/// Start of the window described by `spec`: a span back from `now` such as
+/// "7d", "2w" or "12h", a date ("2025-06-01"), or an RFC 3339 timestamp
+pub fn parse_since(
+    spec: &str,
+    now: DateTime<Local>,
+) -> std::result::Result<DateTime<FixedOffset>, String> {
+    let spec = spec.trim();
+    if let Ok(t) = DateTime::parse_from_rfc3339(spec) {
+        return Ok(t);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(spec, "%Y-%m-%d") {
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+        return Local
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map(|t| t.fixed_offset())
+            .ok_or_else(|| format!("No local midnight on {}", spec));
+    }
+    let (count, unit) = spec.split_at(spec.len().saturating_sub(1));
+    let count: i64 = count.parse().map_err(|_| {
+        format!(
+            "Unrecognized --since '{}' (use e.g. 7d, 2w, 12h or 2025-06-01)",
+            spec
+        )
+    })?;
+    let span = match unit {
+        "h" => Duration::hours(count),
+        "d" => Duration::days(count),
+        "w" => Duration::weeks(count),
+        _ => {
+            return Err(format!(
+                "Unrecognized --since '{}' (use e.g. 7d, 2w, 12h or 2025-06-01)",
+                spec
+            ))
+        }
+    };
+    Ok((now - span).fixed_offset())
+}
+
+/// What happened between `since` and when the digest was made
+#[derive(Debug, Clone)]
+pub struct Digest {
+    pub since: DateTime<FixedOffset>,
+    pub until: DateTime<FixedOffset>,
+    pub new_goals: Vec<DecisionNode>,
+    /// Outcomes logged, or marked completed, in the window
+    pub outcomes: Vec<DecisionNode>,
+    /// Decisions with no chosen option that aren't completed or rejected,
+    /// whenever they were logged
+    pub open_decisions: Vec<DecisionNode>,
+    pub spans: usize,
+    pub usage: Usage,
+    pub roadmap_added: Vec<RoadmapItem>,
+    pub roadmap_completed: Vec<RoadmapItem>,
+    pub roadmap_archived: Vec<RoadmapItem>,
+}
+
+fn within(timestamp: &str, since: &DateTime<FixedOffset>) -> bool {
+    DateTime::parse_from_rfc3339(timestamp).is_ok_and(|t| t >= *since)
+}
+
+/// Gather the digest for the window starting at `since`
+pub fn collect(db: &Database, since: DateTime<FixedOffset>, prices: &PriceTable) -> Result<Digest> {
+    let nodes = db.get_all_nodes()?;
+    let edges = db.get_all_edges()?;
+    let of_type = |node_type: &'static str| nodes.iter().filter(move |n| n.node_type == node_type);
+
+    let new_goals = of_type("goal")
+        .filter(|n| within(&n.created_at, &since))
+        .cloned()
+        .collect();
+    let outcomes = of_type("outcome")
+        .filter(|n| {
+            within(&n.created_at, &since)
+                || (n.status == "completed" && within(&n.updated_at, &since))
+        })
+        .cloned()
+        .collect();
+    let open_decisions = of_type("decision")
+        .filter(|n| n.status != "completed" && n.status != "rejected")
+        .filter(|n| {
+            !edges
+                .iter()
+                .any(|e| e.from_node_id == n.id && e.edge_type == "chosen")
+        })
+        .cloned()
+        .collect();
+
+    let spans: Vec<_> = db
+        .get_all_trace_spans()?
+        .into_iter()
+        .filter(|s| within(&s.started_at, &since))
+        .collect();
+
+    let items = db.get_all_roadmap_items()?;
+    let tasks = || items.iter().filter(|i| i.is_task());
+    Ok(Digest {
+        since,
+        until: Local::now().fixed_offset(),
+        new_goals,
+        outcomes,
+        open_decisions,
+        spans: spans.len(),
+        usage: Usage::of(prices, &spans),
+        roadmap_added: tasks()
+            .filter(|i| within(&i.created_at, &since))
+            .cloned()
+            .collect(),
+        roadmap_completed: tasks()
+            .filter(|i| i.is_complete() && i.archived_at.is_none() && within(&i.updated_at, &since))
+            .cloned()
+            .collect(),
+        roadmap_archived: tasks()
+            .filter(|i| i.archived_at.as_deref().is_some_and(|t| within(t, &since)))
+            .cloned()
+            .collect(),
+    })
+}
+
+/// Render the digest; `slack` uses mrkdwn (`*bold*` headings) instead of Markdown
+pub fn render(digest: &Digest, slack: bool) -> String {
+    let heading = |text: &str| {
+        if slack {
+            format!("*{}*\n", text)
+        } else {
+            format!("## {}\n\n", text)
+        }
+    };
+    let node_lines = |nodes: &[DecisionNode]| -> String {
+        if nodes.is_empty() {
+            return "- none\n".to_string();
+        }
+        nodes
+            .iter()
+            .map(|n| format!("- #{} {}\n", n.id, n.title))
+            .collect()
+    };
+    let item_lines = |items: &[RoadmapItem]| -> String {
+        items
+            .iter()
+            .map(|i| match i.github_issue_number {
+                Some(issue) => format!("- {} (#{})\n", i.title, issue),
+                None => format!("- {}\n", i.title),
+            })
+            .collect()
+    };
+
+    let range = format!(
+        "{} to {}",
+        digest.since.format("%Y-%m-%d"),
+        digest.until.format("%Y-%m-%d")
+    );
+    let mut out = if slack {
+        format!("*Decision digest, {}*\n\n", range)
+    } else {
+        format!("# Decision digest, {}\n\n", range)
+    };
+
+    out.push_str(&heading(&format!("New goals ({})", digest.new_goals.len())));
+    out.push_str(&node_lines(&digest.new_goals));
+    out.push('\n');
+    out.push_str(&heading(&format!("Outcomes ({})", digest.outcomes.len())));
+    out.push_str(&node_lines(&digest.outcomes));
+    out.push('\n');
+    out.push_str(&heading(&format!(
+        "Open decisions ({})",
+        digest.open_decisions.len()
+    )));
+    out.push_str(&node_lines(&digest.open_decisions));
+    out.push('\n');
+
+    out.push_str(&heading("API usage"));
+    out.push_str(&format!(
+        "- {} requests, {} tokens, {}\n\n",
+        digest.spans,
+        digest.usage.tokens,
+        format_cost(digest.usage.cost)
+    ));
+
+    out.push_str(&heading("Roadmap"));
+    let moved = [
+        ("Added", &digest.roadmap_added),
+        ("Completed", &digest.roadmap_completed),
+        ("Archived", &digest.roadmap_archived),
+    ];
+    if moved.iter().all(|(_, items)| items.is_empty()) {
+        out.push_str("- no changes\n");
+    }
+    for (label, items) in moved {
+        if !items.is_empty() {
+            out.push_str(&format!("{} ({}):\n", label, items.len()));
+            out.push_str(&item_lines(items));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest() {
+        let now = Local::now();
+        let week_ago = parse_since("7d", now).unwrap();
+        assert_eq!((now.fixed_offset() - week_ago).num_days(), 7);
+        assert_eq!(
+            parse_since("2w", now).unwrap(),
+            (now - Duration::weeks(2)).fixed_offset()
+        );
+        assert_eq!(
+            parse_since("2025-06-01", now)
+                .unwrap()
+                .format("%Y-%m-%d %H:%M")
+                .to_string(),
+            "2025-06-01 00:00"
+        );
+        assert!(parse_since("7 days", now).is_err());
+        assert!(parse_since("d", now).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let goal = db.node("goal", "Faster CI").insert().unwrap();
+        let open = db
+            .node("decision", "Which cache backend?")
+            .insert()
+            .unwrap();
+        let decided = db.node("decision", "Runner size").insert().unwrap();
+        let option = db.node("option", "Large runners").insert().unwrap();
+        db.edge(decided, option)
+            .edge_type("chosen")
+            .insert()
+            .unwrap();
+        let outcome = db.node("outcome", "CI takes 4 minutes").insert().unwrap();
+
+        let digest = collect(&db, week_ago, &PriceTable::default()).unwrap();
+        let ids = |nodes: &[DecisionNode]| nodes.iter().map(|n| n.id).collect::<Vec<_>>();
+        assert_eq!(ids(&digest.new_goals), vec![goal]);
+        assert_eq!(ids(&digest.outcomes), vec![outcome]);
+        assert_eq!(ids(&digest.open_decisions), vec![open]);
+
+        let markdown = render(&digest, false);
+        assert!(markdown.contains("## New goals (1)\n\n"));
+        assert!(markdown.contains(&format!("- #{} Which cache backend?\n", open)));
+        assert!(markdown.contains("- 0 requests, 0 tokens, $0.00\n"));
+        assert!(markdown.contains("## Roadmap\n\n- no changes\n"));
+        assert!(render(&digest, true).contains("*Open decisions (1)*\n"));
+
+        let later = collect(
+            &db,
+            parse_since("2999-01-01", now).unwrap(),
+            &PriceTable::default(),
+        )
+        .unwrap();
+        assert!(later.new_goals.is_empty());
+        assert_eq!(later.open_decisions.len(), 1);
+    }
+}
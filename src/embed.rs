@@ -0,0 +1,329 @@
+//! Node embeddings and semantic search
+//!
+//! A node's title, description and prompt are sent to the `[embeddings]`
+//! endpoint and the vector stored in `node_embeddings`, keyed by model and a
+//! hash of the text so only new or edited nodes are embedded again.
+//! `deciduous search --semantic` ranks nodes by cosine similarity to the
+//! query; `--related <id>` ranks them against a node to suggest links.
+
+use crate::config::EmbeddingsConfig;
+use crate::db::{Database, DecisionNode};
+use crate::remote_patch::{curl_header, curl_request};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Nodes sent per embeddings request
+const BATCH: usize = 64;
+
+/// Turns text into vectors
+pub trait Embedder {
+    /// Model name the vectors are stored under
+    fn model(&self) -> &str;
+
+    /// One vector per text, in order
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String>;
+}
+
+/// An OpenAI-compatible `/v1/embeddings` endpoint, called through curl
+pub struct ApiEmbedder {
+    config: EmbeddingsConfig,
+}
+
+impl ApiEmbedder {
+    pub fn new(config: EmbeddingsConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Embedder for ApiEmbedder {
+    fn model(&self) -> &str {
+        &self.config.model
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let key = std::env::var(&self.config.api_key_env)
+            .ok()
+            .filter(|k| !k.is_empty());
+        let body = json!({ "model": self.config.model, "input": texts });
+        let mut config = curl_header("Content-Type: application/json");
+        if let Some(key) = key {
+            config += &curl_header(&format!("Authorization: Bearer {}", key));
+        }
+        let (status, response) = curl_request(
+            "POST",
+            &self.config.url,
+            &config,
+            Some(body.to_string().as_bytes()),
+        )
+        .map_err(|e| e.to_string())?;
+        match status {
+            200..=299 => parse_response(&response, texts.len()),
+            0 => Err(format!("no response from {}", self.config.url)),
+            code => Err(format!("HTTP {}: {}", code, response.trim())),
+        }
+    }
+}
+
+/// The vectors in an embeddings response, in input order
+fn parse_response(response: &str, expected: usize) -> Result<Vec<Vec<f32>>, String> {
+    let response: Value =
+        serde_json::from_str(response).map_err(|e| format!("bad embeddings response: {}", e))?;
+    let mut data: Vec<&Value> = response["data"]
+        .as_array()
+        .ok_or("embeddings response has no `data`")?
+        .iter()
+        .collect();
+    data.sort_by_key(|d| d["index"].as_u64().unwrap_or(0));
+    let vectors: Vec<Vec<f32>> = data
+        .iter()
+        .map(|d| {
+            d["embedding"]
+                .as_array()
+                .map(|v| {
+                    v.iter()
+                        .filter_map(Value::as_f64)
+                        .map(|x| x as f32)
+                        .collect()
+                })
+                .unwrap_or_default()
+        })
+        .collect();
+    if vectors.len() != expected || vectors.iter().any(Vec::is_empty) {
+        return Err(format!(
+            "expected {} embeddings, got {} usable",
+            expected,
+            vectors.iter().filter(|v| !v.is_empty()).count()
+        ));
+    }
+    Ok(vectors)
+}
+
+/// The text embedded for a node: title, description and prompt
+pub fn node_text(node: &DecisionNode) -> String {
+    let prompt = node
+        .metadata_json
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<Value>(m).ok())
+        .and_then(|m| m["prompt"].as_str().map(String::from));
+    [Some(node.title.clone()), node.description.clone(), prompt]
+        .into_iter()
+        .flatten()
+        .filter(|s| !s.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// SHA-256 of the embedded text, hex encoded
+pub fn content_hash(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text.as_bytes()))
+}
+
+/// Cosine similarity; 0 for vectors of different lengths or zero length
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+/// A node and how close it is to what was searched for
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SearchHit {
+    pub score: f32,
+    pub node: DecisionNode,
+}
+
+/// Embed nodes that have no vector under the embedder's model, or whose text
+/// changed since; returns how many were embedded
+pub fn index(db: &Database, embedder: &dyn Embedder) -> Result<usize, String> {
+    let stored: HashMap<i32, String> = db
+        .get_node_embeddings(embedder.model())
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|e| (e.node_id, e.content_hash))
+        .collect();
+    let pending: Vec<(i32, String, String)> = db
+        .get_all_nodes()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|n| {
+            let text = node_text(n);
+            (n.id, content_hash(&text), text)
+        })
+        .filter(|(id, hash, _)| stored.get(id) != Some(hash))
+        .collect();
+
+    for batch in pending.chunks(BATCH) {
+        let texts: Vec<String> = batch.iter().map(|(_, _, text)| text.clone()).collect();
+        let vectors = embedder.embed(&texts)?;
+        for ((id, hash, _), vector) in batch.iter().zip(vectors) {
+            db.set_node_embedding(*id, embedder.model(), hash, &vector)
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(pending.len())
+}
+
+/// Stored vectors under `model` ranked by similarity to `target`, best first
+fn rank(
+    db: &Database,
+    model: &str,
+    target: &[f32],
+    skip: impl Fn(i32) -> bool,
+    limit: usize,
+) -> Result<Vec<SearchHit>, String> {
+    let mut nodes: HashMap<i32, DecisionNode> = db
+        .get_all_nodes()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|n| (n.id, n))
+        .collect();
+    let mut scored: Vec<(f32, i32)> = db
+        .get_node_embeddings(model)
+        .map_err(|e| e.to_string())?
+        .iter()
+        .filter(|e| !skip(e.node_id))
+        .map(|e| (cosine(target, &e.values()), e.node_id))
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+    Ok(scored
+        .into_iter()
+        .filter_map(|(score, id)| nodes.remove(&id).map(|node| SearchHit { score, node }))
+        .take(limit)
+        .collect())
+}
+
+/// Nodes closest in meaning to `query`, embedding new and changed nodes first
+pub fn search(
+    db: &Database,
+    embedder: &dyn Embedder,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<SearchHit>, String> {
+    index(db, embedder)?;
+    let target = embedder
+        .embed(&[query.to_string()])?
+        .pop()
+        .ok_or("no embedding for the query")?;
+    rank(db, embedder.model(), &target, |_| false, limit)
+}
+
+/// Nodes closest in meaning to `node_id` that it has no edge to: candidates
+/// for a `deciduous link`
+pub fn related(
+    db: &Database,
+    embedder: &dyn Embedder,
+    node_id: i32,
+    limit: usize,
+) -> Result<Vec<SearchHit>, String> {
+    index(db, embedder)?;
+    let target = db
+        .get_node_embeddings(embedder.model())
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|e| e.node_id == node_id)
+        .ok_or_else(|| format!("Node {} does not exist", node_id))?
+        .values();
+    let linked: Vec<i32> = db
+        .get_all_edges()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .filter_map(|e| {
+            if e.from_node_id == node_id {
+                Some(e.to_node_id)
+            } else if e.to_node_id == node_id {
+                Some(e.from_node_id)
+            } else {
+                None
+            }
+        })
+        .collect();
+    rank(
+        db,
+        embedder.model(),
+        &target,
+        |id| id == node_id || linked.contains(&id),
+        limit,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// Letter counts: texts sharing words land close together
+    struct Letters {
+        calls: Cell<usize>,
+    }
+
+    impl Embedder for Letters {
+        fn model(&self) -> &str {
+            "letters"
+        }
+
+        fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+            self.calls.set(self.calls.get() + texts.len());
+            Ok(texts
+                .iter()
+                .map(|t| {
+                    let mut v = vec![0.0; 26];
+                    for c in t.to_lowercase().bytes().filter(u8::is_ascii_lowercase) {
+                        v[(c - b'a') as usize] += 1.0;
+                    }
+                    v
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_semantic_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let sqlite = db
+            .node("decision", "Store the graph in sqlite")
+            .insert()
+            .unwrap();
+        let wasm = db.node("action", "Build the wasm viewer").insert().unwrap();
+        let storage = db.node("goal", "Pick a storage engine").insert().unwrap();
+        db.edge(storage, sqlite).insert().unwrap();
+        let embedder = Letters {
+            calls: Cell::new(0),
+        };
+
+        let hits = search(&db, &embedder, "why sqlite storage", 2).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].node.id, sqlite);
+        assert!(hits[0].score >= hits[1].score);
+        assert_eq!(embedder.calls.get(), 4);
+
+        // Unchanged nodes aren't embedded again; edited ones are
+        assert_eq!(index(&db, &embedder).unwrap(), 0);
+        db.update_node_prompt(wasm, "ship a viewer").unwrap();
+        assert_eq!(index(&db, &embedder).unwrap(), 1);
+
+        let related = related(&db, &embedder, storage, 5).unwrap();
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0].node.id, wasm);
+        assert!(super::related(&db, &embedder, 999, 5).is_err());
+
+        assert_eq!(cosine(&[1.0, 0.0], &[2.0, 0.0]), 1.0);
+        assert_eq!(cosine(&[1.0], &[1.0, 0.0]), 0.0);
+        let parsed = parse_response(
+            r#"{"data":[{"index":1,"embedding":[0.5]},{"index":0,"embedding":[0.25]}]}"#,
+            2,
+        )
+        .unwrap();
+        assert_eq!(parsed, vec![vec![0.25], vec![0.5]]);
+        assert!(parse_response(r#"{"data":[]}"#, 1).is_err());
+    }
+}
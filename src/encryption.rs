@@ -0,0 +1,123 @@
+//! age encryption for patch files
+//!
+//! `diff export --encrypt-for <recipient>` pipes the patch JSON through the
+//! `age` CLI, so patches carrying verbatim prompts can sit in a public repo.
+//! Anything that loads a patch decrypts it on the way in, using the age
+//! identity in `DECIDUOUS_AGE_IDENTITY` or `~/.deciduous/age-identity.txt`.
+//! The identity is personal, so it never goes in the committed config.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Environment variable naming the age identity file to decrypt with
+pub const AGE_IDENTITY_ENV: &str = "DECIDUOUS_AGE_IDENTITY";
+
+const BINARY_HEADER: &[u8] = b"age-encryption.org/v1";
+const ARMOR_HEADER: &[u8] = b"-----BEGIN AGE ENCRYPTED FILE-----";
+
+/// Whether file content is an age-encrypted file (binary or armored)
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    let start = bytes
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    let bytes = &bytes[start..];
+    bytes.starts_with(BINARY_HEADER) || bytes.starts_with(ARMOR_HEADER)
+}
+
+/// The identity file to decrypt with: the environment variable, else
+/// `~/.deciduous/age-identity.txt`
+pub fn identity_path() -> Result<PathBuf, String> {
+    if let Ok(path) = std::env::var(AGE_IDENTITY_ENV) {
+        if !path.is_empty() {
+            return Ok(PathBuf::from(path));
+        }
+    }
+    let home = std::env::var("HOME").map_err(|_| "HOME environment variable not set")?;
+    Ok(PathBuf::from(home)
+        .join(".deciduous")
+        .join("age-identity.txt"))
+}
+
+/// Run `age` with `input` on stdin and return its stdout
+fn age(args: &[String], input: &[u8]) -> Result<Vec<u8>, String> {
+    let mut child = Command::new("age")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run age (is it installed?): {}", e))?;
+
+    // Write from a thread so a large patch can't fill the stdout pipe first
+    let mut stdin = child.stdin.take().ok_or("Failed to open age's stdin")?;
+    let input = input.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run age: {}", e))?;
+    let written = writer.join().map_err(|_| "Failed to write to age")?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "age failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    written.map_err(|e| format!("Failed to write to age: {}", e))?;
+    Ok(output.stdout)
+}
+
+/// Encrypt `plaintext` to every recipient, ASCII-armored so it diffs as text
+pub fn encrypt(plaintext: &[u8], recipients: &[String]) -> Result<Vec<u8>, String> {
+    if recipients.is_empty() {
+        return Err("No age recipients given".to_string());
+    }
+    let mut args = vec!["--encrypt".to_string(), "--armor".to_string()];
+    for recipient in recipients {
+        args.push("--recipient".to_string());
+        args.push(recipient.trim().to_string());
+    }
+    age(&args, plaintext)
+}
+
+/// Decrypt an age file with the user's identity
+pub fn decrypt(ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+    let identity = identity_path()?;
+    if !identity.exists() {
+        return Err(format!(
+            "Patch is age-encrypted and no identity was found at {} \
+             (create one with age-keygen, or set {})",
+            identity.display(),
+            AGE_IDENTITY_ENV
+        ));
+    }
+    let args = vec![
+        "--decrypt".to_string(),
+        "--identity".to_string(),
+        identity.to_string_lossy().to_string(),
+    ];
+    age(&args, ciphertext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_encrypted() {
+        assert!(is_encrypted(
+            b"-----BEGIN AGE ENCRYPTED FILE-----\nYWdl\n-----END AGE ENCRYPTED FILE-----\n"
+        ));
+        assert!(is_encrypted(b"age-encryption.org/v1\n-> X25519 abc\n"));
+        assert!(is_encrypted(b"\n  -----BEGIN AGE ENCRYPTED FILE-----\n"));
+        assert!(!is_encrypted(b"{\"version\": \"1.0\"}"));
+        assert!(!is_encrypted(b""));
+    }
+
+    #[test]
+    fn test_encrypt_needs_recipient() {
+        assert!(encrypt(b"{}", &[]).is_err());
+    }
+}
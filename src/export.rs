@@ -1,9 +1,14 @@
 //! Export utilities for decision graphs
 //!
-//! Provides DOT graph export and PR writeup generation.
-
-use crate::db::{DecisionEdge, DecisionGraph, DecisionNode};
-use std::collections::{HashMap, HashSet};
+//! Provides DOT graph export, PR and trace session writeup generation, the
+//! roadmap progress badge and the scrubbing applied by export profiles.
+
+use crate::db::{
+    node_pr_number, DecisionEdge, DecisionGraph, DecisionNode, RoadmapItem, TraceSession, TraceSpan,
+};
+use crate::redact::Redactor;
+use crate::trace_diff;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Write;
 
 // Helper macro for infallible String writes
@@ -138,6 +143,38 @@ fn extract_commit(metadata: &Option<String>) -> Option<String> {
     })
 }
 
+/// Describe a node's linked PR: number, cached state and URL (if known)
+fn pr_reference(
+    graph: &DecisionGraph,
+    node: &DecisionNode,
+    repo: Option<&str>,
+) -> Option<(i32, Option<String>, Option<String>)> {
+    let number = node_pr_number(node)?;
+    match graph.pull_request_for(node) {
+        Some(pr) => Some((number, Some(pr.state.clone()), Some(pr.html_url.clone()))),
+        None => Some((
+            number,
+            None,
+            repo.map(|r| format!("https://github.com/{}/pull/{}", r, number)),
+        )),
+    }
+}
+
+/// Markdown badge for a node's linked PR, e.g. " ([#12](url), merged)"
+fn pr_badge(graph: &DecisionGraph, node: &DecisionNode, repo: Option<&str>) -> String {
+    let Some((number, state, url)) = pr_reference(graph, node, repo) else {
+        return String::new();
+    };
+    let link = match url {
+        Some(url) => format!("[#{}]({})", number, url),
+        None => format!("#{}", number),
+    };
+    match state {
+        Some(state) => format!(" ({}, {})", link, state),
+        None => format!(" ({})", link),
+    }
+}
+
 /// Convert a decision graph to DOT format
 pub fn graph_to_dot(graph: &DecisionGraph, config: &DotConfig) -> String {
     let mut dot = String::new();
@@ -171,13 +208,27 @@ pub fn graph_to_dot(graph: &DecisionGraph, config: &DotConfig) -> String {
             }
         }
 
+        let mut pr_attrs = String::new();
+        if let Some((number, state, url)) = pr_reference(graph, node, None) {
+            let mut tooltip = format!("PR #{}", number);
+            if let Some(state) = state {
+                w!(tooltip, " ({})", state);
+            }
+            if let Some(url) = url {
+                w!(tooltip, "\n{}", url);
+                w!(pr_attrs, " URL=\"{}\"", escape_dot(&url));
+            }
+            w!(pr_attrs, " tooltip=\"{}\"", escape_dot(&tooltip));
+        }
+
         wln!(
             dot,
-            "  {} [label=\"{}\" shape=\"{}\" fillcolor=\"{}\" style=\"filled\"];",
+            "  {} [label=\"{}\" shape=\"{}\" fillcolor=\"{}\" style=\"filled\"{}];",
             node.id,
             escape_dot(&label),
             node_shape(&node.node_type),
-            node_color(&node.node_type)
+            node_color(&node.node_type),
+            pr_attrs
         );
     }
 
@@ -253,6 +304,7 @@ pub fn filter_graph_from_roots(graph: &DecisionGraph, root_ids: &[i32]) -> Decis
         nodes,
         edges,
         config: graph.config.clone(),
+        pull_requests: graph.pull_requests.clone(),
     }
 }
 
@@ -278,6 +330,7 @@ pub fn filter_graph_by_ids(graph: &DecisionGraph, node_ids: &[i32]) -> DecisionG
         nodes,
         edges,
         config: graph.config.clone(),
+        pull_requests: graph.pull_requests.clone(),
     }
 }
 
@@ -356,6 +409,42 @@ pub fn generate_pr_writeup(graph: &DecisionGraph, config: &WriteupConfig) -> Str
         wln!(writeup);
     }
 
+    write_graph_sections(&mut writeup, &filtered, config.github_repo.as_deref());
+
+    if config.include_dot {
+        write_dot_section(&mut writeup, &filtered, config);
+    }
+
+    // Test plan section
+    if config.include_test_plan {
+        wln!(writeup, "## Test Plan\n");
+
+        // Generate test plan from outcomes
+        let test_items: Vec<String> = filtered
+            .nodes
+            .iter()
+            .filter(|n| n.node_type == "outcome" && n.status == "completed")
+            .map(|o| format!("- [x] {}", o.title))
+            .collect();
+
+        if test_items.is_empty() {
+            wln!(writeup, "- [ ] Verify implementation");
+            wln!(writeup, "- [ ] Run test suite");
+        } else {
+            for item in test_items {
+                wln!(writeup, "{}", item);
+            }
+        }
+        wln!(writeup);
+    }
+
+    write_node_reference(&mut writeup, &filtered, "This PR");
+
+    writeup
+}
+
+/// Key Decisions, Implementation and Outcomes sections of a writeup
+fn write_graph_sections(writeup: &mut String, filtered: &DecisionGraph, repo: Option<&str>) {
     // Decisions section
     let decisions: Vec<&DecisionNode> = filtered
         .nodes
@@ -439,7 +528,9 @@ pub fn generate_pr_writeup(graph: &DecisionGraph, config: &WriteupConfig) -> Str
                 .map(|c| format!(" `{}`", &c[..7.min(c.len())]))
                 .unwrap_or_default();
 
-            wln!(writeup, "- {}{}", action.title, commit_badge);
+            let pr = pr_badge(filtered, action, repo);
+
+            wln!(writeup, "- {}{}{}", action.title, commit_badge, pr);
         }
         wln!(writeup);
     }
@@ -460,94 +551,346 @@ pub fn generate_pr_writeup(graph: &DecisionGraph, config: &WriteupConfig) -> Str
                 .map(|c| format!(" ({}% confidence)", c))
                 .unwrap_or_default();
 
-            wln!(writeup, "- {}{}", outcome.title, conf_badge);
+            let pr = pr_badge(filtered, outcome, repo);
+
+            wln!(writeup, "- {}{}{}", outcome.title, conf_badge, pr);
         }
         wln!(writeup);
     }
+}
 
-    // DOT graph section
-    if config.include_dot {
-        wln!(writeup, "## Decision Graph\n");
-
-        // Build image URL if PNG filename provided
-        let image_url = config.png_filename.as_ref().map(|filename| {
-            if let (Some(repo), Some(branch)) = (&config.github_repo, &config.git_branch) {
-                format!(
-                    "https://raw.githubusercontent.com/{}/{}/{}",
-                    repo, branch, filename
-                )
-            } else {
-                // Fallback to relative path (won't work in PR descriptions but OK for files)
-                filename.clone()
-            }
-        });
-
-        // If image URL available, show the PNG image
-        if let Some(url) = &image_url {
-            wln!(writeup, "![Decision Graph]({})\n", url);
-
-            // Put DOT source in collapsible details
-            wln!(writeup, "<details>");
-            wln!(writeup, "<summary>DOT source (click to expand)</summary>\n");
+/// Decision Graph section: DOT source, with the rendered PNG if configured
+fn write_dot_section(writeup: &mut String, filtered: &DecisionGraph, config: &WriteupConfig) {
+    wln!(writeup, "## Decision Graph\n");
+
+    // Build image URL if PNG filename provided
+    let image_url = config.png_filename.as_ref().map(|filename| {
+        if let (Some(repo), Some(branch)) = (&config.github_repo, &config.git_branch) {
+            format!(
+                "https://raw.githubusercontent.com/{}/{}/{}",
+                repo, branch, filename
+            )
+        } else {
+            // Fallback to relative path (won't work in PR descriptions but OK for files)
+            filename.clone()
         }
+    });
 
-        wln!(writeup, "```dot");
-        let dot_config = DotConfig {
-            title: Some(config.title.clone()),
-            show_ids: true,
-            show_rationale: false, // Keep DOT compact in writeup
-            show_confidence: true,
-            rankdir: "TB".to_string(),
-        };
-        w!(writeup, "{}", graph_to_dot(&filtered, &dot_config));
-        wln!(writeup, "```\n");
+    // If image URL available, show the PNG image
+    if let Some(url) = &image_url {
+        wln!(writeup, "![Decision Graph]({})\n", url);
 
-        if image_url.is_some() {
-            wln!(writeup, "</details>\n");
-        } else {
-            wln!(
-                writeup,
-                "*Render with: `dot -Tpng graph.dot -o graph.png`*\n"
-            );
-        }
+        // Put DOT source in collapsible details
+        wln!(writeup, "<details>");
+        wln!(writeup, "<summary>DOT source (click to expand)</summary>\n");
     }
 
-    // Test plan section
-    if config.include_test_plan {
-        wln!(writeup, "## Test Plan\n");
-
-        // Generate test plan from outcomes
-        let test_items: Vec<String> = outcomes
-            .iter()
-            .filter(|o| o.status == "completed")
-            .map(|o| format!("- [x] {}", o.title))
-            .collect();
+    wln!(writeup, "```dot");
+    let dot_config = DotConfig {
+        title: Some(config.title.clone()),
+        show_ids: true,
+        show_rationale: false, // Keep DOT compact in writeup
+        show_confidence: true,
+        rankdir: "TB".to_string(),
+    };
+    w!(writeup, "{}", graph_to_dot(filtered, &dot_config));
+    wln!(writeup, "```\n");
 
-        if test_items.is_empty() {
-            wln!(writeup, "- [ ] Verify implementation");
-            wln!(writeup, "- [ ] Run test suite");
-        } else {
-            for item in test_items {
-                wln!(writeup, "{}", item);
-            }
-        }
-        wln!(writeup);
+    if image_url.is_some() {
+        wln!(writeup, "</details>\n");
+    } else {
+        wln!(
+            writeup,
+            "*Render with: `dot -Tpng graph.dot -o graph.png`*\n"
+        );
     }
+}
 
-    // Decision graph reference
+/// Decision Graph Reference section listing the writeup's node IDs
+fn write_node_reference(writeup: &mut String, filtered: &DecisionGraph, subject: &str) {
     if !filtered.nodes.is_empty() {
         let node_ids: Vec<String> = filtered.nodes.iter().map(|n| n.id.to_string()).collect();
         wln!(writeup, "## Decision Graph Reference\n");
         wln!(
             writeup,
-            "This PR corresponds to deciduous nodes: {}\n",
+            "{} corresponds to deciduous nodes: {}\n",
+            subject,
             node_ids.join(", ")
         );
     }
+}
+
+/// A trace session and what it produced, for `generate_trace_writeup`
+pub struct SessionActivity<'a> {
+    pub session: &'a TraceSession,
+    pub spans: &'a [TraceSpan],
+    /// IDs of the nodes logged during each span
+    pub span_nodes: HashMap<i32, Vec<i32>>,
+    /// Commits made by each span's tool calls, as (hash, subject)
+    pub span_commits: HashMap<i32, Vec<(String, String)>>,
+}
+
+/// First non-empty line of a message, for timeline entries
+fn first_line(text: &str) -> Option<&str> {
+    text.lines().map(str::trim).find(|l| !l.is_empty())
+}
+
+/// Generate a "what the agent did" report for a trace session
+///
+/// `graph` holds the nodes linked to the session and its spans (see
+/// `filter_graph_by_ids`). A timeline of the session's turns comes first,
+/// then the same decision, implementation and outcome sections as a PR
+/// writeup, then the commits. `config.root_ids` and `include_test_plan` are
+/// not used.
+pub fn generate_trace_writeup(
+    graph: &DecisionGraph,
+    activity: &SessionActivity,
+    config: &WriteupConfig,
+) -> String {
+    let session = activity.session;
+    let spans = activity.spans;
+    let mut writeup = String::new();
+
+    wln!(writeup, "## What the agent did\n");
+
+    let mut context = format!(
+        "Session `{}`",
+        &session.session_id[..8.min(session.session_id.len())]
+    );
+    if let Some(branch) = &session.git_branch {
+        w!(context, " on `{}`", branch);
+    }
+    w!(context, ", started {}", session.started_at);
+    let minutes = session.ended_at.as_deref().and_then(|end| {
+        let start = chrono::DateTime::parse_from_rfc3339(&session.started_at).ok()?;
+        let end = chrono::DateTime::parse_from_rfc3339(end).ok()?;
+        Some((end - start).num_minutes())
+    });
+    if let Some(minutes) = minutes {
+        w!(context, " and ran {} min", minutes);
+    }
+    wln!(
+        writeup,
+        "{}: {} API calls, {} input / {} output tokens.\n",
+        context,
+        spans.len(),
+        session.total_input_tokens,
+        session.total_output_tokens
+    );
+    if let Some(summary) = &session.summary {
+        wln!(writeup, "{}\n", summary);
+    }
+
+    let goals: Vec<&DecisionNode> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == "goal")
+        .collect();
+    if !goals.is_empty() {
+        for goal in &goals {
+            wln!(writeup, "**Goal:** {}", goal.title);
+        }
+        wln!(writeup);
+    }
+
+    // Timeline: one entry per user message
+    let turns = trace_diff::turns(spans);
+    if !turns.is_empty() {
+        wln!(writeup, "## Timeline\n");
+
+        for (i, turn) in turns.iter().enumerate() {
+            let request = turn[0]
+                .user_preview
+                .as_deref()
+                .and_then(first_line)
+                .map(|l| truncate(l, 100))
+                .unwrap_or_else(|| "(no user message)".to_string());
+            wln!(writeup, "{}. **{}**", i + 1, request);
+
+            let mut tools: BTreeMap<&str, usize> = BTreeMap::new();
+            for name in turn
+                .iter()
+                .flat_map(|s| s.tool_names.as_deref().unwrap_or("").split(','))
+                .map(str::trim)
+                .filter(|n| !n.is_empty())
+            {
+                *tools.entry(name).or_insert(0) += 1;
+            }
+            if !tools.is_empty() {
+                let tools: Vec<String> = tools
+                    .iter()
+                    .map(|(name, n)| match n {
+                        1 => name.to_string(),
+                        n => format!("{} ×{}", name, n),
+                    })
+                    .collect();
+                wln!(writeup, "   - Used {}", tools.join(", "));
+            }
+
+            let mut logged: Vec<i32> = Vec::new();
+            for span in turn.iter() {
+                let ids = activity.span_nodes.get(&span.id).into_iter().flatten();
+                for &id in span.linked_node_id.iter().chain(ids) {
+                    if !logged.contains(&id) {
+                        logged.push(id);
+                    }
+                }
+            }
+            for node in logged
+                .iter()
+                .filter_map(|id| graph.nodes.iter().find(|n| n.id == *id))
+            {
+                wln!(
+                    writeup,
+                    "   - Logged {} #{}: {}",
+                    node.node_type,
+                    node.id,
+                    node.title
+                );
+            }
+
+            for span in turn.iter() {
+                for (hash, subject) in activity.span_commits.get(&span.id).into_iter().flatten() {
+                    wln!(
+                        writeup,
+                        "   - Committed `{}` {}",
+                        &hash[..7.min(hash.len())],
+                        subject
+                    );
+                }
+            }
+
+            let reply = turn
+                .iter()
+                .rev()
+                .find_map(|s| s.response_preview.as_deref().and_then(first_line));
+            if let Some(reply) = reply {
+                wln!(writeup, "   - Replied: {}", truncate(reply, 120));
+            }
+        }
+        wln!(writeup);
+    }
+
+    write_graph_sections(&mut writeup, graph, config.github_repo.as_deref());
+
+    let commits: Vec<&(String, String)> = spans
+        .iter()
+        .flat_map(|s| activity.span_commits.get(&s.id).into_iter().flatten())
+        .collect();
+    if !commits.is_empty() {
+        wln!(writeup, "## Commits\n");
+        for (hash, subject) in commits {
+            wln!(writeup, "- `{}` {}", &hash[..7.min(hash.len())], subject);
+        }
+        wln!(writeup);
+    }
+
+    if config.include_dot && !graph.nodes.is_empty() {
+        write_dot_section(&mut writeup, graph, config);
+    }
+
+    write_node_reference(&mut writeup, graph, "This session");
 
     writeup
 }
 
+/// Roadmap progress as a shields.io endpoint badge (`badge.json`)
+///
+/// Counts tasks the same way as the roadmap views: section headers are
+/// skipped, and a task is done when checked or under a "Completed" section.
+/// Point `https://img.shields.io/endpoint?url=<pages url>/badge.json` at it.
+pub fn roadmap_badge(items: &[RoadmapItem]) -> serde_json::Value {
+    let tasks: Vec<&RoadmapItem> = items.iter().filter(|i| i.is_task()).collect();
+    let done = tasks.iter().filter(|i| i.is_complete()).count();
+    let total = tasks.len();
+
+    let color = match (done * 100).checked_div(total) {
+        None => "lightgrey",
+        Some(100) => "brightgreen",
+        Some(pct) if pct >= 75 => "green",
+        Some(pct) if pct >= 40 => "yellow",
+        Some(_) => "orange",
+    };
+
+    serde_json::json!({
+        "schemaVersion": 1,
+        "label": "roadmap",
+        "message": format!("{}/{}", done, total),
+        "color": color,
+    })
+}
+
+/// Prepare a graph for a less trusted export destination
+///
+/// Drops the `prompt` from node metadata when `strip_prompts` is set, and runs
+/// `redactor` over titles, descriptions, string metadata and edge rationales.
+/// Returns how many secrets were redacted.
+pub fn scrub_graph(
+    graph: &mut DecisionGraph,
+    redactor: Option<&Redactor>,
+    strip_prompts: bool,
+) -> usize {
+    let nodes: usize = graph
+        .nodes
+        .iter_mut()
+        .map(|node| scrub_node(node, redactor, strip_prompts))
+        .sum();
+    let edges: usize = graph
+        .edges
+        .iter_mut()
+        .map(|edge| scrub_edge(edge, redactor))
+        .sum();
+    nodes + edges
+}
+
+fn redact_into(text: &mut String, redactor: Option<&Redactor>) -> usize {
+    let Some(redactor) = redactor else {
+        return 0;
+    };
+    let redacted = redactor.redact(text);
+    *text = redacted.text;
+    redacted.hits.values().sum()
+}
+
+/// `scrub_graph` for one node, for exports that stream the graph
+pub fn scrub_node(
+    node: &mut DecisionNode,
+    redactor: Option<&Redactor>,
+    strip_prompts: bool,
+) -> usize {
+    let mut hits = redact_into(&mut node.title, redactor);
+    if let Some(description) = &mut node.description {
+        hits += redact_into(description, redactor);
+    }
+    let Some(mut meta) = node
+        .metadata_json
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+    else {
+        return hits;
+    };
+    if let Some(obj) = meta.as_object_mut() {
+        if strip_prompts {
+            obj.remove("prompt");
+        }
+        for value in obj.values_mut() {
+            if let serde_json::Value::String(s) = value {
+                hits += redact_into(s, redactor);
+            }
+        }
+    }
+    node.metadata_json = Some(meta.to_string());
+    hits
+}
+
+/// `scrub_graph` for one edge's rationale
+pub fn scrub_edge(edge: &mut DecisionEdge, redactor: Option<&Redactor>) -> usize {
+    match &mut edge.rationale {
+        Some(rationale) => redact_into(rationale, redactor),
+        None => 0,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -558,10 +901,10 @@ mod tests {
                 DecisionNode {
                     id: 1,
                     change_id: "change-id-1".to_string(),
-                    node_type: "goal".to_string(),
+                    node_type: "goal".into(),
                     title: "Build feature X".to_string(),
                     description: None,
-                    status: "pending".to_string(),
+                    status: "pending".into(),
                     created_at: "2025-01-01T00:00:00Z".to_string(),
                     updated_at: "2025-01-01T00:00:00Z".to_string(),
                     metadata_json: Some(r#"{"confidence":90}"#.to_string()),
@@ -569,10 +912,10 @@ mod tests {
                 DecisionNode {
                     id: 2,
                     change_id: "change-id-2".to_string(),
-                    node_type: "decision".to_string(),
+                    node_type: "decision".into(),
                     title: "Choose approach".to_string(),
                     description: None,
-                    status: "pending".to_string(),
+                    status: "pending".into(),
                     created_at: "2025-01-01T00:00:00Z".to_string(),
                     updated_at: "2025-01-01T00:00:00Z".to_string(),
                     metadata_json: None,
@@ -580,10 +923,10 @@ mod tests {
                 DecisionNode {
                     id: 3,
                     change_id: "change-id-3".to_string(),
-                    node_type: "action".to_string(),
+                    node_type: "action".into(),
                     title: "Implement solution".to_string(),
                     description: None,
-                    status: "completed".to_string(),
+                    status: "completed".into(),
                     created_at: "2025-01-01T00:00:00Z".to_string(),
                     updated_at: "2025-01-01T00:00:00Z".to_string(),
                     metadata_json: Some(r#"{"commit":"abc1234"}"#.to_string()),
@@ -596,7 +939,7 @@ mod tests {
                     to_node_id: 2,
                     from_change_id: Some("change-id-1".to_string()),
                     to_change_id: Some("change-id-2".to_string()),
-                    edge_type: "leads_to".to_string(),
+                    edge_type: "leads_to".into(),
                     weight: Some(1.0),
                     rationale: Some("Goal requires decision".to_string()),
                     created_at: "2025-01-01T00:00:00Z".to_string(),
@@ -607,13 +950,14 @@ mod tests {
                     to_node_id: 3,
                     from_change_id: Some("change-id-2".to_string()),
                     to_change_id: Some("change-id-3".to_string()),
-                    edge_type: "leads_to".to_string(),
+                    edge_type: "leads_to".into(),
                     weight: Some(1.0),
                     rationale: None,
                     created_at: "2025-01-01T00:00:00Z".to_string(),
                 },
             ],
             config: None,
+            pull_requests: vec![],
         }
     }
 
@@ -887,12 +1231,65 @@ mod tests {
 
     // === Empty Graph Tests ===
 
+    fn graph_with_pr() -> DecisionGraph {
+        let mut graph = sample_graph();
+        graph.nodes[2].metadata_json = Some(r#"{"commit":"abc1234","pr":42}"#.to_string());
+        graph.pull_requests.push(crate::db::GitHubPrCache {
+            id: 1,
+            pr_number: 42,
+            repo: "owner/repo".to_string(),
+            title: "Feature X".to_string(),
+            state: "merged".to_string(),
+            head_branch: "feature-x".to_string(),
+            html_url: "https://github.com/owner/repo/pull/42".to_string(),
+            cached_at: "2025-01-01T00:00:00Z".to_string(),
+        });
+        graph
+    }
+
+    #[test]
+    fn test_dot_pr_tooltip() {
+        let dot = graph_to_dot(&graph_with_pr(), &DotConfig::default());
+        assert!(dot.contains(r#"URL="https://github.com/owner/repo/pull/42""#));
+        assert!(dot.contains(r#"tooltip="PR #42 (merged)\nhttps://github.com/owner/repo/pull/42""#));
+        // Nodes without a PR get no tooltip
+        assert_eq!(dot.matches("tooltip=").count(), 1);
+    }
+
+    #[test]
+    fn test_writeup_pr_links() {
+        let config = WriteupConfig {
+            title: "Test PR".to_string(),
+            root_ids: vec![],
+            include_dot: false,
+            include_test_plan: false,
+            png_filename: None,
+            github_repo: None,
+            git_branch: None,
+        };
+        let writeup = generate_pr_writeup(&graph_with_pr(), &config);
+        assert!(writeup.contains(
+            "- Implement solution `abc1234` ([#42](https://github.com/owner/repo/pull/42), merged)"
+        ));
+
+        // Uncached PRs fall back to a URL built from the repo
+        let mut graph = graph_with_pr();
+        graph.pull_requests.clear();
+        let config = WriteupConfig {
+            github_repo: Some("owner/repo".to_string()),
+            ..config
+        };
+        let writeup = generate_pr_writeup(&graph, &config);
+        assert!(writeup.contains("([#42](https://github.com/owner/repo/pull/42))"));
+    }
+
     #[test]
     fn test_dot_empty_graph() {
         let graph = DecisionGraph {
             nodes: vec![],
             edges: vec![],
             config: None,
+            pull_requests: vec![],
         };
         let config = DotConfig::default();
         let dot = graph_to_dot(&graph, &config);
@@ -907,6 +1304,7 @@ mod tests {
             nodes: vec![],
             edges: vec![],
             config: None,
+            pull_requests: vec![],
         };
         let config = WriteupConfig {
             title: "Empty".to_string(),
@@ -922,4 +1320,170 @@ mod tests {
         // Should still produce valid output
         assert!(writeup.contains("## Summary"));
     }
+
+    fn trace_span(id: i32, user: &str, tools: Option<&str>, reply: Option<&str>) -> TraceSpan {
+        TraceSpan {
+            id,
+            change_id: format!("c{}", id),
+            session_id: "0123456789abcdef".to_string(),
+            sequence_num: id,
+            started_at: String::new(),
+            completed_at: None,
+            duration_ms: None,
+            model: None,
+            request_id: None,
+            stop_reason: None,
+            input_tokens: None,
+            output_tokens: None,
+            cache_read: None,
+            cache_write: None,
+            user_preview: Some(user.to_string()),
+            thinking_preview: None,
+            response_preview: reply.map(str::to_string),
+            tool_names: tools.map(str::to_string),
+            linked_node_id: None,
+            linked_change_id: None,
+        }
+    }
+
+    #[test]
+    fn test_trace_writeup() {
+        let session = TraceSession {
+            id: 1,
+            session_id: "0123456789abcdef".to_string(),
+            started_at: "2025-01-01T10:00:00+00:00".to_string(),
+            ended_at: Some("2025-01-01T10:25:00+00:00".to_string()),
+            working_dir: None,
+            git_branch: Some("feature-x".to_string()),
+            command: None,
+            summary: None,
+            total_input_tokens: 1200,
+            total_output_tokens: 300,
+            total_cache_read: 0,
+            total_cache_write: 0,
+            linked_node_id: Some(1),
+            linked_change_id: None,
+        };
+        let mut first = trace_span(1, "Build feature X\nwith tests", Some("Read,Bash"), None);
+        first.linked_node_id = Some(1);
+        let spans = vec![
+            first,
+            trace_span(
+                2,
+                "Build feature X\nwith tests",
+                Some("Bash"),
+                Some("Done."),
+            ),
+            trace_span(3, "Thanks", None, Some("You're welcome.")),
+        ];
+        let activity = SessionActivity {
+            session: &session,
+            spans: &spans,
+            span_nodes: HashMap::from([(2, vec![3])]),
+            span_commits: HashMap::from([(
+                2,
+                vec![("abc1234def".to_string(), "Add feature X".to_string())],
+            )]),
+        };
+        let graph = filter_graph_by_ids(&sample_graph(), &[1, 3]);
+        let config = WriteupConfig {
+            title: "Session".to_string(),
+            root_ids: vec![],
+            include_dot: false,
+            include_test_plan: true,
+            png_filename: None,
+            github_repo: None,
+            git_branch: None,
+        };
+        let writeup = generate_trace_writeup(&graph, &activity, &config);
+
+        assert!(writeup.contains("Session `01234567` on `feature-x`"));
+        assert!(writeup.contains("and ran 25 min: 3 API calls, 1200 input / 300 output tokens."));
+        assert!(writeup.contains("**Goal:** Build feature X"));
+        assert!(writeup.contains("1. **Build feature X**\n   - Used Bash ×2, Read\n"));
+        assert!(writeup.contains("   - Logged goal #1: Build feature X"));
+        assert!(writeup.contains("   - Logged action #3: Implement solution"));
+        assert!(writeup.contains("   - Committed `abc1234` Add feature X"));
+        assert!(writeup.contains("   - Replied: Done."));
+        assert!(writeup.contains("2. **Thanks**"));
+        assert!(writeup.contains("## Implementation"));
+        assert!(writeup.contains("## Commits\n\n- `abc1234` Add feature X"));
+        assert!(!writeup.contains("## Test Plan"));
+        assert!(writeup.contains("This session corresponds to deciduous nodes: 1, 3"));
+    }
+
+    fn roadmap_item(id: i32, section: &str, checkbox_state: &str) -> RoadmapItem {
+        RoadmapItem {
+            id,
+            change_id: format!("item-{}", id),
+            title: format!("Item {}", id),
+            description: None,
+            section: Some(section.to_string()),
+            parent_id: None,
+            checkbox_state: checkbox_state.to_string(),
+            github_issue_number: None,
+            github_issue_state: None,
+            outcome_node_id: None,
+            outcome_change_id: None,
+            markdown_line_start: None,
+            markdown_line_end: None,
+            content_hash: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+            last_synced_at: None,
+            priority: None,
+            due_date: None,
+            goal_node_id: None,
+            goal_change_id: None,
+            archived_at: None,
+        }
+    }
+
+    #[test]
+    fn test_roadmap_badge() {
+        let items = vec![
+            roadmap_item(1, "v1", "none"),
+            roadmap_item(2, "Auth", "checked"),
+            roadmap_item(3, "Auth", "unchecked"),
+            roadmap_item(4, "Completed", "unchecked"),
+            roadmap_item(5, "Auth", "unchecked"),
+        ];
+        let badge = roadmap_badge(&items);
+        assert_eq!(badge["schemaVersion"], 1);
+        assert_eq!(badge["label"], "roadmap");
+        assert_eq!(badge["message"], "2/4");
+        assert_eq!(badge["color"], "yellow");
+
+        assert_eq!(roadmap_badge(&[])["color"], "lightgrey");
+        assert_eq!(roadmap_badge(&items[1..2])["color"], "brightgreen");
+    }
+
+    #[test]
+    fn test_scrub_graph() {
+        let key = "sk-ant-REDACTED";
+        let mut graph = sample_graph();
+        graph.nodes[0].title = format!("Rotate {}", key);
+        graph.nodes[0].metadata_json = Some(
+            serde_json::json!({ "confidence": 90, "prompt": "use my key", "branch": key })
+                .to_string(),
+        );
+
+        let mut stripped = graph.clone();
+        assert_eq!(scrub_graph(&mut stripped, None, true), 0);
+        assert!(stripped.nodes[0].title.contains(key));
+        assert!(!stripped.nodes[0]
+            .metadata_json
+            .as_deref()
+            .unwrap()
+            .contains("prompt"));
+
+        let redactor = Redactor::default();
+        assert_eq!(scrub_graph(&mut graph, Some(&redactor), false), 2);
+        assert_eq!(graph.nodes[0].title, "Rotate [REDACTED:anthropic_key]");
+        let meta: serde_json::Value =
+            serde_json::from_str(graph.nodes[0].metadata_json.as_deref().unwrap()).unwrap();
+        assert_eq!(meta["prompt"], "use my key");
+        assert_eq!(meta["confidence"], 90);
+        assert_eq!(meta["branch"], "[REDACTED:anthropic_key]");
+    }
 }
@@ -0,0 +1,234 @@
+//! Graphs from several repositories, queried together
+//!
+//! `deciduous federate add ../billing` registers another project's database
+//! under a name. `graph --federated`, `search --federated` and
+//! `serve --federated` then read it next to the local graph. Federated nodes
+//! and edges get fresh IDs after the local ones, change_ids prefixed with
+//! `<name>:`, and a `repo` metadata field, so nothing collides with local
+//! data. The other databases are only read.
+
+use crate::db::{Database, DecisionEdge, DecisionGraph, DecisionNode, FederatedRepo};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// The database for `path`: a repository (or its `.deciduous` directory)
+/// holding `.deciduous/deciduous.db`, or a database file
+pub fn database_path(path: &Path) -> Result<PathBuf, String> {
+    let candidates = [
+        path.join(".deciduous").join("deciduous.db"),
+        path.join("deciduous.db"),
+        path.to_path_buf(),
+    ];
+    let found = candidates
+        .iter()
+        .find(|p| p.is_file())
+        .ok_or_else(|| format!("No deciduous database at {}", path.display()))?;
+    found
+        .canonicalize()
+        .map_err(|e| format!("{}: {}", found.display(), e))
+}
+
+/// Whether `name` can prefix change_ids: letters, digits, `-` and `_`
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Register the repository at `path`, named `name` or after its directory
+pub fn register(db: &Database, path: &Path, name: Option<&str>) -> Result<FederatedRepo, String> {
+    let db_path = database_path(path)?;
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => path
+            .canonicalize()
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+            .map(|n| n.trim_start_matches('.').to_string())
+            .unwrap_or_default(),
+    };
+    if !valid_name(&name) {
+        return Err(format!(
+            "'{}' can't name a repository; use letters, digits, '-' and '_' (--name)",
+            name
+        ));
+    }
+    if db_path
+        == crate::db::Database::db_path()
+            .canonicalize()
+            .unwrap_or_default()
+    {
+        return Err("That is this project's own database".to_string());
+    }
+    Database::open_at(&db_path)
+        .map_err(|e| format!("{}: {}", db_path.display(), e))?
+        .get_all_nodes()
+        .map_err(|e| format!("{}: {}", db_path.display(), e))?;
+    let db_path = db_path.to_string_lossy().to_string();
+    db.add_federated_repo(&name, &db_path)
+        .map_err(|e| e.to_string())?;
+    Ok(FederatedRepo {
+        name,
+        db_path,
+        added_at: chrono::Local::now().to_rfc3339(),
+    })
+}
+
+/// A registered repository's graph
+fn open(repo: &FederatedRepo) -> Result<Database, String> {
+    if !Path::new(&repo.db_path).is_file() {
+        return Err(format!(
+            "Federated repo '{}': {} is gone (deciduous federate remove {})",
+            repo.name, repo.db_path, repo.name
+        ));
+    }
+    Database::open_at(&repo.db_path).map_err(|e| format!("Federated repo '{}': {}", repo.name, e))
+}
+
+/// `repo`'s nodes and edges renumbered from `next_id`, with namespaced
+/// change_ids and the repo name in each node's metadata
+pub fn namespace(
+    repo: &str,
+    nodes: Vec<DecisionNode>,
+    edges: Vec<DecisionEdge>,
+    next_id: &mut i32,
+) -> (Vec<DecisionNode>, Vec<DecisionEdge>) {
+    let prefixed = |change_id: &str| format!("{}:{}", repo, change_id);
+    let mut ids = std::collections::HashMap::new();
+    let nodes: Vec<DecisionNode> = nodes
+        .into_iter()
+        .map(|mut node| {
+            ids.insert(node.id, *next_id);
+            node.id = *next_id;
+            *next_id += 1;
+            node.change_id = prefixed(&node.change_id);
+            let mut meta: Value = node
+                .metadata_json
+                .as_deref()
+                .and_then(|m| serde_json::from_str(m).ok())
+                .filter(Value::is_object)
+                .unwrap_or_else(|| serde_json::json!({}));
+            meta["repo"] = Value::String(repo.to_string());
+            node.metadata_json = Some(meta.to_string());
+            node
+        })
+        .collect();
+    let edges = edges
+        .into_iter()
+        .filter_map(|mut edge| {
+            edge.from_node_id = *ids.get(&edge.from_node_id)?;
+            edge.to_node_id = *ids.get(&edge.to_node_id)?;
+            edge.id = *next_id;
+            *next_id += 1;
+            edge.from_change_id = edge.from_change_id.as_deref().map(prefixed);
+            edge.to_change_id = edge.to_change_id.as_deref().map(prefixed);
+            Some(edge)
+        })
+        .collect();
+    (nodes, edges)
+}
+
+/// `graph` with every registered repository's graph appended
+pub fn federate(db: &Database, mut graph: DecisionGraph) -> Result<DecisionGraph, String> {
+    let mut next_id = graph
+        .nodes
+        .iter()
+        .map(|n| n.id)
+        .chain(graph.edges.iter().map(|e| e.id))
+        .max()
+        .unwrap_or(0)
+        + 1;
+    for repo in db.get_federated_repos().map_err(|e| e.to_string())? {
+        let other = open(&repo)?;
+        let nodes = other.get_all_nodes().map_err(|e| e.to_string())?;
+        let edges = other.get_all_edges().map_err(|e| e.to_string())?;
+        let (nodes, edges) = namespace(&repo.name, nodes, edges, &mut next_id);
+        graph.nodes.extend(nodes);
+        graph.edges.extend(edges);
+    }
+    Ok(graph)
+}
+
+/// Text search over the local graph ("local") and every registered one;
+/// node IDs are each repository's own
+pub fn search(
+    db: &Database,
+    text: &str,
+    limit: usize,
+) -> Result<Vec<(String, DecisionNode)>, String> {
+    let mut found: Vec<(String, DecisionNode)> = crate::mcp::search_nodes(db, text, limit)?
+        .into_iter()
+        .map(|n| ("local".to_string(), n))
+        .collect();
+    for repo in db.get_federated_repos().map_err(|e| e.to_string())? {
+        let other = open(&repo)?;
+        found.extend(
+            crate::mcp::search_nodes(&other, text, limit)?
+                .into_iter()
+                .map(|n| (repo.name.clone(), n)),
+        );
+    }
+    found.sort_by(|a, b| b.1.created_at.cmp(&a.1.created_at));
+    found.truncate(limit);
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_federation() {
+        let dir = tempfile::tempdir().unwrap();
+        let local = Database::new(dir.path().join("local.db").to_str().unwrap()).unwrap();
+        local.node("goal", "Checkout v2").insert().unwrap();
+
+        let billing_dir = dir.path().join("billing");
+        std::fs::create_dir_all(billing_dir.join(".deciduous")).unwrap();
+        let billing_db = billing_dir.join(".deciduous").join("deciduous.db");
+        let billing = Database::new(billing_db.to_str().unwrap()).unwrap();
+        let goal = billing.node("goal", "Checkout invoices").insert().unwrap();
+        let action = billing.node("action", "Add invoice API").insert().unwrap();
+        billing.edge(goal, action).insert().unwrap();
+
+        assert!(register(&local, &dir.path().join("missing"), None).is_err());
+        assert!(register(&local, &billing_dir, Some("bad name")).is_err());
+        let repo = register(&local, &billing_dir, None).unwrap();
+        assert_eq!(repo.name, "billing");
+
+        let graph = federate(&local, local.get_graph().unwrap()).unwrap();
+        assert_eq!(graph.nodes.len(), 3);
+        let remote = graph
+            .nodes
+            .iter()
+            .find(|n| n.title == "Add invoice API")
+            .unwrap();
+        assert!(remote.change_id.starts_with("billing:"));
+        assert!(remote
+            .metadata_json
+            .as_deref()
+            .unwrap()
+            .contains("\"repo\":\"billing\""));
+        let edge = &graph.edges[0];
+        assert_eq!(edge.to_node_id, remote.id);
+        assert!(graph.nodes.iter().any(|n| n.id == edge.from_node_id));
+        let mut ids: Vec<i32> = graph.nodes.iter().map(|n| n.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 3);
+
+        let hits = search(&local, "checkout", 10).unwrap();
+        let repos: Vec<&str> = hits.iter().map(|(r, _)| r.as_str()).collect();
+        assert!(repos.contains(&"local") && repos.contains(&"billing"));
+
+        assert!(local.remove_federated_repo("billing").unwrap());
+        assert_eq!(
+            federate(&local, local.get_graph().unwrap())
+                .unwrap()
+                .nodes
+                .len(),
+            1
+        );
+    }
+}
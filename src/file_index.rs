@@ -0,0 +1,226 @@
+//! Which nodes touched which files
+//!
+//! `deciduous why src/serve.rs` lists the decisions and actions behind a
+//! file, newest first. It reads the `node_files` index, which holds each
+//! node's `files` metadata and the files its linked commit changed.
+//! `refresh` re-indexes only nodes updated since they were last indexed, so
+//! git is asked about a commit once per change to its node.
+
+use crate::db::{same_file, Database, DecisionNode};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::process::Command;
+
+/// A node's `files` metadata, without leading `./`
+pub fn metadata_files(node: &DecisionNode) -> Vec<String> {
+    node.metadata_json
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        .and_then(|m| m["files"].as_array().cloned())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|f| f.as_str())
+        .map(|f| f.trim().trim_start_matches("./").to_string())
+        .filter(|f| !f.is_empty())
+        .collect()
+}
+
+fn metadata_commit(node: &DecisionNode) -> Option<String> {
+    node.metadata_json
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        .and_then(|m| m["commit"].as_str().map(String::from))
+        .filter(|c| !c.is_empty())
+}
+
+/// Files changed by `commit`; empty if git doesn't know it
+pub fn commit_files(commit: &str) -> Vec<String> {
+    Command::new("git")
+        .args(["diff-tree", "--no-commit-id", "--name-only", "-r", "--root"])
+        .arg(commit)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .filter(|l| !l.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A node's index entries: (path, source)
+fn entries(
+    node: &DecisionNode,
+    commit_files: &impl Fn(&str) -> Vec<String>,
+) -> Vec<(String, String)> {
+    let mut sources: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for path in metadata_files(node) {
+        sources.entry(path).or_default().push("files");
+    }
+    if let Some(commit) = metadata_commit(node) {
+        for path in commit_files(&commit) {
+            let found = sources.entry(path).or_default();
+            if !found.contains(&"commit") {
+                found.push("commit");
+            }
+        }
+    }
+    sources
+        .into_iter()
+        .map(|(path, found)| (path, found.join(", ")))
+        .collect()
+}
+
+fn refresh_with(
+    db: &Database,
+    nodes: &[DecisionNode],
+    commit_files: impl Fn(&str) -> Vec<String>,
+) -> Result<usize, String> {
+    let indexed: HashMap<i32, String> = db
+        .get_node_files()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|f| (f.node_id, f.node_updated_at))
+        .collect();
+    let mut refreshed = 0;
+    for node in nodes {
+        if indexed.get(&node.id) == Some(&node.updated_at) {
+            continue;
+        }
+        let files = entries(node, &commit_files);
+        if files.is_empty() && !indexed.contains_key(&node.id) {
+            continue;
+        }
+        db.set_node_files(node.id, &node.updated_at, &files)
+            .map_err(|e| e.to_string())?;
+        refreshed += 1;
+    }
+    Ok(refreshed)
+}
+
+/// Re-index `nodes` changed since they were last indexed; returns how many
+pub fn refresh(db: &Database, nodes: &[DecisionNode]) -> Result<usize, String> {
+    refresh_with(db, nodes, commit_files)
+}
+
+/// A node that touched the file `why` was asked about
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FileTouch {
+    pub node: DecisionNode,
+    /// The path as indexed
+    pub path: String,
+    /// "files", "commit" or "files, commit"
+    pub source: String,
+}
+
+/// The nodes that touched `path`, newest first, from an up-to-date index
+pub fn why(db: &Database, path: &str, limit: usize) -> Result<Vec<FileTouch>, String> {
+    let nodes = db.get_all_nodes().map_err(|e| e.to_string())?;
+    refresh(db, &nodes)?;
+    touches(db, nodes, path, limit)
+}
+
+fn touches(
+    db: &Database,
+    nodes: Vec<DecisionNode>,
+    path: &str,
+    limit: usize,
+) -> Result<Vec<FileTouch>, String> {
+    let mut nodes: HashMap<i32, DecisionNode> = nodes.into_iter().map(|n| (n.id, n)).collect();
+    let mut found: Vec<FileTouch> = db
+        .get_node_files()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|f| same_file(&f.path, path))
+        .filter_map(|f| {
+            Some(FileTouch {
+                node: nodes.remove(&f.node_id)?,
+                path: f.path,
+                source: f.source,
+            })
+        })
+        .collect();
+    found.sort_by(|a, b| {
+        b.node
+            .created_at
+            .cmp(&a.node.created_at)
+            .then(b.node.id.cmp(&a.node.id))
+    });
+    found.truncate(limit);
+    Ok(found)
+}
+
+/// `path` relative to the root of the repository the database is in, when
+/// it points inside it; otherwise as given, without a leading `./`
+pub fn repo_path(path: &str) -> String {
+    let root = Database::db_path()
+        .parent()
+        .and_then(Path::parent)
+        .and_then(|r| r.canonicalize().ok());
+    let given = Path::new(path);
+    let absolute = if given.is_absolute() {
+        given.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(given)
+    };
+    let absolute = absolute.canonicalize().unwrap_or(absolute);
+    root.and_then(|root| absolute.strip_prefix(root).ok().map(Path::to_path_buf))
+        .map(|p| p.to_string_lossy().to_string())
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| path.trim_start_matches("./").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let decision = db
+            .node("decision", "Stream the graph")
+            .files(["./src/serve.rs", "src/db.rs"])
+            .insert()
+            .unwrap();
+        let action = db
+            .node("action", "Add paging")
+            .commit("abc1234")
+            .insert()
+            .unwrap();
+        db.node("goal", "Faster viewer").insert().unwrap();
+        let fake_git = |commit: &str| match commit {
+            "abc1234" => vec!["src/serve.rs".to_string(), "web/api.ts".to_string()],
+            _ => vec![],
+        };
+
+        let nodes = db.get_all_nodes().unwrap();
+        assert_eq!(refresh_with(&db, &nodes, fake_git).unwrap(), 2);
+        assert_eq!(refresh_with(&db, &nodes, |_| unreachable!()).unwrap(), 0);
+
+        let found = touches(&db, nodes, "src/serve.rs", 10).unwrap();
+        assert_eq!(
+            found.iter().map(|t| t.node.id).collect::<Vec<_>>(),
+            vec![action, decision]
+        );
+        assert_eq!(found[0].source, "commit");
+        assert_eq!(found[1].source, "files");
+        let nodes = db.get_all_nodes().unwrap();
+        assert_eq!(touches(&db, nodes.clone(), "api.ts", 10).unwrap().len(), 1);
+
+        // A changed node is re-indexed
+        db.update_node_metadata_field(action, "files", serde_json::json!(["web/api.ts"]))
+            .unwrap();
+        let nodes = db.get_all_nodes().unwrap();
+        assert_eq!(refresh_with(&db, &nodes, fake_git).unwrap(), 1);
+        let found = touches(&db, nodes, "web/api.ts", 10).unwrap();
+        assert_eq!(found[0].source, "files, commit");
+
+        db.delete_node(decision).unwrap();
+        let nodes = db.get_all_nodes().unwrap();
+        assert_eq!(touches(&db, nodes, "src/db.rs", 10).unwrap().len(), 0);
+    }
+}
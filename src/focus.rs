@@ -0,0 +1,103 @@
+//! `deciduous focus`: one goal that new work links to
+//!
+//! `deciduous focus 12 --for 2h` stores goal 12 as the focus until the time
+//! box ends. While it holds, `deciduous add` links each new non-goal node
+//! from it, and trace sessions, handoff notes and `deciduous context` use it
+//! as the active goal instead of guessing from the most recent goal on the
+//! branch. `deciduous focus --clear` ends it early.
+
+use crate::db::{pick_auto_link_goal, Database, DecisionNode, Focus};
+use chrono::{DateTime, Local};
+
+/// Focus on `goal_id`, optionally for a span such as "2h" or "1d"
+pub fn start(
+    db: &Database,
+    goal_id: i32,
+    duration: Option<&str>,
+    now: DateTime<Local>,
+) -> Result<Focus, String> {
+    let goal = db
+        .get_node_by_id(goal_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Node {} not found", goal_id))?;
+    if goal.node_type != "goal" {
+        return Err(format!(
+            "Node {} is a {}; focus needs a goal",
+            goal_id, goal.node_type
+        ));
+    }
+    let expires_at = match duration {
+        Some(spec) => {
+            let span = now.fixed_offset() - crate::digest::parse_since(spec, now)?;
+            if span <= chrono::Duration::zero() {
+                return Err(format!("'{}' is not a span of time (e.g. 2h, 1d)", spec));
+            }
+            Some((now + span).to_rfc3339())
+        }
+        None => None,
+    };
+    db.set_focus(goal_id, expires_at.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// Whether `focus` still holds at `now`
+pub fn is_current(focus: &Focus, now: DateTime<Local>) -> bool {
+    focus
+        .expires_at
+        .as_deref()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map_or(true, |expires| expires > now)
+}
+
+/// The focused goal at `now`, if the focus holds and the goal still exists
+pub fn active(db: &Database, now: DateTime<Local>) -> Option<DecisionNode> {
+    let focus = db.get_focus().ok()??;
+    if !is_current(&focus, now) {
+        return None;
+    }
+    db.get_node_by_id(focus.goal_id).ok()?
+}
+
+/// The goal new work belongs to: the focus, else the branch's most recent
+/// open goal
+pub fn active_goal(
+    db: &Database,
+    nodes: &[DecisionNode],
+    branch: Option<&str>,
+) -> Option<DecisionNode> {
+    active(db, Local::now()).or_else(|| pick_auto_link_goal(nodes, branch).cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_focus() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let focused = db.node("goal", "Paged graph API").insert().unwrap();
+        let newer = db.node("goal", "Dark mode").insert().unwrap();
+        let action = db.node("action", "Add cursor").insert().unwrap();
+        let now = Local::now();
+
+        assert!(start(&db, action, None, now).is_err());
+        assert!(start(&db, focused, Some("soon"), now).is_err());
+        let nodes = db.get_all_nodes().unwrap();
+        assert_eq!(active_goal(&db, &nodes, None).map(|g| g.id), Some(newer));
+
+        let focus = start(&db, focused, Some("2h"), now).unwrap();
+        assert!(is_current(&focus, now + Duration::minutes(119)));
+        assert!(!is_current(&focus, now + Duration::minutes(121)));
+        assert_eq!(active(&db, now).map(|g| g.id), Some(focused));
+        assert_eq!(active_goal(&db, &nodes, None).map(|g| g.id), Some(focused));
+        assert!(active(&db, now + Duration::hours(3)).is_none());
+
+        start(&db, focused, None, now).unwrap();
+        assert!(active(&db, now + Duration::days(30)).is_some());
+        db.delete_node(focused).unwrap();
+        assert!(db.get_focus().unwrap().is_none());
+        assert!(!db.clear_focus().unwrap());
+    }
+}
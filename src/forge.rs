@@ -0,0 +1,637 @@
+//! Forge abstraction (GitHub, GitLab, Bitbucket, Jira)
+//!
+//! Roadmap sync and issue caching talk to a `Forge` instead of a concrete
+//! client, so the same commands work against GitHub (`gh`), GitLab (`glab`),
+//! Bitbucket Cloud (REST API) and Jira (REST API, issues only).
+//! The forge is picked from `.deciduous/config.toml` or the `origin` remote.
+
+use crate::bitbucket::BitbucketClient;
+use crate::config::Config;
+use crate::github::{GitHubClient, GitHubComment, GitHubIssue, Result};
+use crate::gitlab::GitLabClient;
+use crate::jira::JiraClient;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// Which forge a repository is hosted on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForgeKind {
+    GitHub,
+    GitLab,
+    Bitbucket,
+    Jira,
+}
+
+impl ForgeKind {
+    /// Parse from config value ("github", "gitlab", "bitbucket", "jira")
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "github" => Some(ForgeKind::GitHub),
+            "gitlab" => Some(ForgeKind::GitLab),
+            "bitbucket" => Some(ForgeKind::Bitbucket),
+            "jira" => Some(ForgeKind::Jira),
+            _ => None,
+        }
+    }
+
+    /// Guess the forge from a git remote URL
+    pub fn from_remote_url(url: &str) -> Option<Self> {
+        let url = url.to_lowercase();
+        if url.contains("gitlab") {
+            Some(ForgeKind::GitLab)
+        } else if url.contains("github") {
+            Some(ForgeKind::GitHub)
+        } else if url.contains("bitbucket") {
+            Some(ForgeKind::Bitbucket)
+        } else {
+            None
+        }
+    }
+
+    /// Display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            ForgeKind::GitHub => "GitHub",
+            ForgeKind::GitLab => "GitLab",
+            ForgeKind::Bitbucket => "Bitbucket",
+            ForgeKind::Jira => "Jira",
+        }
+    }
+
+    /// CLI tool used to talk to this forge
+    pub fn cli(&self) -> &'static str {
+        match self {
+            ForgeKind::GitHub => "gh",
+            ForgeKind::GitLab => "glab",
+            ForgeKind::Bitbucket | ForgeKind::Jira => "curl",
+        }
+    }
+
+    /// How to authenticate when `check_auth` fails
+    pub fn auth_hint(&self) -> &'static str {
+        match self {
+            ForgeKind::GitHub => "Run 'gh auth login' first",
+            ForgeKind::GitLab => "Run 'glab auth login' first",
+            ForgeKind::Bitbucket => {
+                "Set BITBUCKET_USERNAME and BITBUCKET_APP_PASSWORD (or BITBUCKET_TOKEN) first"
+            }
+            ForgeKind::Jira => "Set JIRA_EMAIL and JIRA_API_TOKEN (or JIRA_TOKEN) first",
+        }
+    }
+}
+
+impl std::fmt::Display for ForgeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Pull request (GitHub) or merge request (GitLab)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PullRequest {
+    pub number: i32,
+    pub title: String,
+    pub state: String, // "open", "closed" or "merged"
+    pub head_branch: String,
+    pub html_url: String,
+}
+
+/// An inline review thread on a pull/merge request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewThread {
+    /// Forge id of the thread (GraphQL node id / GitLab discussion id)
+    pub id: String,
+    /// File the thread is attached to
+    pub path: Option<String>,
+    pub line: Option<i32>,
+    /// Diff hunk the first comment was left on, when the forge provides it
+    pub excerpt: Option<String>,
+    pub resolved: bool,
+    pub comments: Vec<GitHubComment>,
+}
+
+/// A release milestone (GitHub milestone / GitLab project milestone)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Milestone {
+    pub number: i32,
+    pub title: String,
+    pub state: String, // "open" or "closed"
+    pub open_issues: i32,
+    pub closed_issues: i32,
+}
+
+/// Operations deciduous needs from an issue tracker / code forge
+///
+/// Issues are returned as `GitHubIssue` regardless of forge; GitLab states
+/// are normalized to "open"/"closed" so callers don't need to care.
+pub trait Forge {
+    /// Which forge this client talks to
+    fn kind(&self) -> ForgeKind;
+
+    /// Repo identifier ("owner/repo" or "group/project")
+    fn repo_name(&self) -> Option<&str>;
+
+    /// Check if the forge CLI is authenticated
+    fn check_auth(&self) -> Result<bool>;
+
+    /// Create a new issue
+    fn create_issue(&self, title: &str, body: &str, labels: &[&str]) -> Result<GitHubIssue>;
+
+    /// Get an issue by number
+    fn get_issue(&self, number: i32) -> Result<GitHubIssue>;
+
+    /// Update an issue's body
+    fn update_issue_body(&self, number: i32, body: &str) -> Result<()>;
+
+    /// Update an issue's title
+    fn update_issue_title(&self, number: i32, title: &str) -> Result<()>;
+
+    /// Close an issue
+    fn close_issue(&self, number: i32) -> Result<()>;
+
+    /// Reopen an issue
+    fn reopen_issue(&self, number: i32) -> Result<()>;
+
+    /// Get comments on an issue
+    fn get_issue_comments(&self, number: i32) -> Result<Vec<GitHubComment>>;
+
+    /// Add a comment to an issue
+    fn add_comment(&self, number: i32, body: &str) -> Result<()>;
+
+    /// List issues (open and closed) with a specific label
+    fn list_issues_with_label(&self, label: &str) -> Result<Vec<GitHubIssue>>;
+
+    /// Find an issue whose title matches exactly (case-insensitive)
+    fn find_issue_by_title(&self, title: &str) -> Result<Option<GitHubIssue>>;
+
+    /// Check if a label exists
+    fn label_exists(&self, name: &str) -> Result<bool>;
+
+    /// Create a label (color is hex without '#')
+    fn create_label(&self, name: &str, description: &str, color: &str) -> Result<()>;
+
+    /// List pull/merge requests by state ("open", "closed", "merged", "all")
+    fn list_pull_requests(&self, state: &str) -> Result<Vec<PullRequest>>;
+
+    /// Get a single pull/merge request by number
+    fn get_pull_request(&self, number: i32) -> Result<PullRequest>;
+
+    /// Get conversation comments on a pull/merge request
+    fn get_pull_request_comments(&self, number: i32) -> Result<Vec<GitHubComment>>;
+
+    /// Add a comment to a pull/merge request
+    fn add_pull_request_comment(&self, number: i32, body: &str) -> Result<()>;
+
+    /// Replace the body of an existing pull/merge request comment
+    fn update_pull_request_comment(&self, number: i32, comment_id: i64, body: &str) -> Result<()>;
+
+    /// Get inline review threads (with their comments) on a pull/merge request
+    fn get_review_threads(&self, number: i32) -> Result<Vec<ReviewThread>>;
+
+    /// List milestones (open and closed)
+    fn list_milestones(&self) -> Result<Vec<Milestone>>;
+
+    /// Create a milestone
+    fn create_milestone(&self, title: &str, description: &str) -> Result<Milestone>;
+
+    /// Assign an issue to a milestone by title
+    fn set_issue_milestone(&self, number: i32, milestone: &str) -> Result<()>;
+
+    /// Find the open pull/merge request whose head is `branch`
+    fn find_pull_request_for_branch(&self, branch: &str) -> Result<Option<PullRequest>> {
+        Ok(self
+            .list_pull_requests("open")?
+            .into_iter()
+            .find(|pr| pr.head_branch == branch))
+    }
+
+    /// How issue `number` is written in links and output ("#12", "PROJ-12")
+    fn issue_ref(&self, number: i32) -> String {
+        format!("#{}", number)
+    }
+}
+
+impl Forge for GitHubClient {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::GitHub
+    }
+
+    fn repo_name(&self) -> Option<&str> {
+        GitHubClient::repo_name(self)
+    }
+
+    fn check_auth(&self) -> Result<bool> {
+        GitHubClient::check_auth()
+    }
+
+    fn create_issue(&self, title: &str, body: &str, labels: &[&str]) -> Result<GitHubIssue> {
+        GitHubClient::create_issue(self, title, body, labels)
+    }
+
+    fn get_issue(&self, number: i32) -> Result<GitHubIssue> {
+        GitHubClient::get_issue(self, number)
+    }
+
+    fn update_issue_body(&self, number: i32, body: &str) -> Result<()> {
+        GitHubClient::update_issue_body(self, number, body)
+    }
+
+    fn update_issue_title(&self, number: i32, title: &str) -> Result<()> {
+        GitHubClient::update_issue_title(self, number, title)
+    }
+
+    fn close_issue(&self, number: i32) -> Result<()> {
+        GitHubClient::close_issue(self, number)
+    }
+
+    fn reopen_issue(&self, number: i32) -> Result<()> {
+        GitHubClient::reopen_issue(self, number)
+    }
+
+    fn get_issue_comments(&self, number: i32) -> Result<Vec<GitHubComment>> {
+        GitHubClient::get_issue_comments(self, number)
+    }
+
+    fn add_comment(&self, number: i32, body: &str) -> Result<()> {
+        GitHubClient::add_comment(self, number, body)
+    }
+
+    fn list_issues_with_label(&self, label: &str) -> Result<Vec<GitHubIssue>> {
+        GitHubClient::list_issues_with_label(self, label)
+    }
+
+    fn find_issue_by_title(&self, title: &str) -> Result<Option<GitHubIssue>> {
+        GitHubClient::find_issue_by_title(self, title)
+    }
+
+    fn label_exists(&self, name: &str) -> Result<bool> {
+        GitHubClient::label_exists(self, name)
+    }
+
+    fn create_label(&self, name: &str, description: &str, color: &str) -> Result<()> {
+        GitHubClient::create_label(self, name, description, color)
+    }
+
+    fn list_pull_requests(&self, state: &str) -> Result<Vec<PullRequest>> {
+        GitHubClient::list_pull_requests(self, state)
+    }
+
+    fn get_pull_request(&self, number: i32) -> Result<PullRequest> {
+        GitHubClient::get_pull_request(self, number)
+    }
+
+    fn get_pull_request_comments(&self, number: i32) -> Result<Vec<GitHubComment>> {
+        GitHubClient::get_pull_request_comments(self, number)
+    }
+
+    fn add_pull_request_comment(&self, number: i32, body: &str) -> Result<()> {
+        GitHubClient::add_pull_request_comment(self, number, body)
+    }
+
+    fn update_pull_request_comment(&self, _number: i32, comment_id: i64, body: &str) -> Result<()> {
+        GitHubClient::update_pull_request_comment(self, comment_id, body)
+    }
+
+    fn get_review_threads(&self, number: i32) -> Result<Vec<ReviewThread>> {
+        GitHubClient::get_review_threads(self, number)
+    }
+
+    fn list_milestones(&self) -> Result<Vec<Milestone>> {
+        GitHubClient::list_milestones(self)
+    }
+
+    fn create_milestone(&self, title: &str, description: &str) -> Result<Milestone> {
+        GitHubClient::create_milestone(self, title, description)
+    }
+
+    fn set_issue_milestone(&self, number: i32, milestone: &str) -> Result<()> {
+        GitHubClient::set_issue_milestone(self, number, milestone)
+    }
+}
+
+/// Get the URL of the `origin` remote
+pub fn git_remote_url() -> Option<String> {
+    Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Extract the repo path from a remote URL
+///
+/// Handles `git@host:group/sub/repo.git` and `https://host/group/repo.git`.
+pub fn parse_repo_from_remote(url: &str) -> Option<String> {
+    let url = url.trim().trim_end_matches('/').trim_end_matches(".git");
+
+    let path = if let Some(rest) = url.split("://").nth(1) {
+        // https://host/path or ssh://git@host/path
+        rest.split_once('/').map(|(_, p)| p)?
+    } else {
+        // scp-style: git@host:path
+        url.split_once(':').map(|(_, p)| p)?
+    };
+
+    let path = path.trim_start_matches('/');
+    if path.contains('/') {
+        Some(path.to_string())
+    } else {
+        None
+    }
+}
+
+/// Decide which forge to use: config `[forge] kind` wins, then the remote URL,
+/// falling back to GitHub.
+pub fn resolve_forge_kind(config: &Config, remote_url: Option<&str>) -> ForgeKind {
+    config
+        .forge
+        .kind
+        .as_deref()
+        .and_then(ForgeKind::parse)
+        .or_else(|| remote_url.and_then(ForgeKind::from_remote_url))
+        .unwrap_or(ForgeKind::GitHub)
+}
+
+/// Build a forge client for the current repository
+///
+/// `repo` overrides auto-detection of the repo path.
+pub fn detect_forge(repo: Option<String>, config: &Config) -> Result<Box<dyn Forge>> {
+    let remote = git_remote_url();
+
+    match resolve_forge_kind(config, remote.as_deref()) {
+        ForgeKind::GitHub => match repo {
+            Some(r) => Ok(Box::new(GitHubClient::new(Some(r)))),
+            None => Ok(Box::new(GitHubClient::auto_detect()?)),
+        },
+        ForgeKind::GitLab => {
+            let repo = repo.or_else(|| remote.as_deref().and_then(parse_repo_from_remote));
+            Ok(Box::new(GitLabClient::new(repo)))
+        }
+        ForgeKind::Bitbucket => {
+            let repo = repo.or_else(|| remote.as_deref().and_then(parse_repo_from_remote));
+            Ok(Box::new(BitbucketClient::new(repo)))
+        }
+        ForgeKind::Jira => Ok(Box::new(JiraClient::new(
+            config.forge.jira_url.clone(),
+            repo.or_else(|| config.forge.jira_project.clone()),
+            config.forge.jira_issue_type.clone(),
+        ))),
+    }
+}
+
+/// Ensure the 'roadmap' label exists, creating it if needed
+/// Returns Ok(true) if label was created, Ok(false) if it already existed
+pub fn ensure_roadmap_label(client: &dyn Forge) -> Result<bool> {
+    match client.label_exists("roadmap") {
+        Ok(true) => Ok(false), // Already exists
+        Ok(false) => {
+            client.create_label(
+                "roadmap",
+                "Roadmap item synced from ROADMAP.md by deciduous",
+                "0e8a16", // Green color
+            )?;
+            Ok(true) // Created
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Find a milestone by title, creating it if it doesn't exist
+///
+/// `known` is the already-fetched milestone list; newly created milestones are
+/// appended so repeated calls don't create duplicates.
+pub fn ensure_milestone(
+    client: &dyn Forge,
+    known: &mut Vec<Milestone>,
+    title: &str,
+    description: &str,
+) -> Result<(Milestone, bool)> {
+    if let Some(existing) = known.iter().find(|m| m.title == title) {
+        return Ok((existing.clone(), false));
+    }
+
+    let created = client.create_milestone(title, description)?;
+    known.push(created.clone());
+    Ok((created, true))
+}
+
+/// Hidden marker identifying the PR comment deciduous owns
+pub const PR_COMMENT_MARKER: &str = "<!-- deciduous:pr-writeup -->";
+
+/// What `upsert_pr_comment` did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentUpsert {
+    Created,
+    Updated(i64),
+}
+
+/// Prefix a comment body with the deciduous marker
+pub fn mark_comment_body(body: &str) -> String {
+    format!("{}\n{}", PR_COMMENT_MARKER, body)
+}
+
+/// Find the deciduous-owned comment among a PR's comments
+pub fn find_marked_comment(comments: &[GitHubComment]) -> Option<&GitHubComment> {
+    comments.iter().find(|c| c.body.contains(PR_COMMENT_MARKER))
+}
+
+/// Post the writeup as a PR comment, editing our previous comment if there is one
+///
+/// Keeps a single comment per PR instead of piling up a new one on every run.
+pub fn upsert_pr_comment(client: &dyn Forge, pr: i32, body: &str) -> Result<CommentUpsert> {
+    let body = mark_comment_body(body);
+    let comments = client.get_pull_request_comments(pr)?;
+
+    match find_marked_comment(&comments) {
+        Some(existing) => {
+            client.update_pull_request_comment(pr, existing.id, &body)?;
+            Ok(CommentUpsert::Updated(existing.id))
+        }
+        None => {
+            client.add_pull_request_comment(pr, &body)?;
+            Ok(CommentUpsert::Created)
+        }
+    }
+}
+
+/// Extract the host from a remote URL ("gitlab.example.com")
+pub fn parse_host_from_remote(url: &str) -> Option<String> {
+    let url = url.trim();
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest.split('/').next()?,
+        None => url.split_once(':').map(|(host, _)| host)?,
+    };
+    // Drop "git@" userinfo and ":port"
+    let host = rest.rsplit('@').next()?.split(':').next()?;
+    if host.is_empty() {
+        None
+    } else {
+        Some(host.to_string())
+    }
+}
+
+/// URL serving a raw file from a branch, for embedding images in comments
+pub fn raw_file_url(kind: ForgeKind, host: &str, repo: &str, branch: &str, path: &str) -> String {
+    match kind {
+        ForgeKind::GitHub => format!(
+            "https://raw.githubusercontent.com/{}/{}/{}",
+            repo, branch, path
+        ),
+        ForgeKind::GitLab => format!("https://{}/{}/-/raw/{}/{}", host, repo, branch, path),
+        ForgeKind::Bitbucket => format!("https://{}/{}/raw/{}/{}", host, repo, branch, path),
+        // Jira hosts no code; `code_host` resolves the Git host instead
+        ForgeKind::Jira => format!("https://{}/{}/{}", host, branch, path),
+    }
+}
+
+/// Web URL for a commit
+pub fn commit_url(kind: ForgeKind, host: &str, repo: &str, hash: &str) -> String {
+    match kind {
+        ForgeKind::GitHub => format!("https://github.com/{}/commit/{}", repo, hash),
+        ForgeKind::GitLab => format!("https://{}/{}/-/commit/{}", host, repo, hash),
+        ForgeKind::Bitbucket => format!("https://{}/{}/commits/{}", host, repo, hash),
+        ForgeKind::Jira => format!("https://{}/{}/commit/{}", host, repo, hash),
+    }
+}
+
+/// Where the code lives, as (kind, host, repo): the forge itself, or the
+/// `origin` remote when issues are tracked in Jira
+pub fn code_host(forge: &dyn Forge) -> Option<(ForgeKind, String, String)> {
+    let remote = git_remote_url();
+    let host = remote
+        .as_deref()
+        .and_then(parse_host_from_remote)
+        .unwrap_or_default();
+    match forge.kind() {
+        ForgeKind::Jira => {
+            let remote = remote?;
+            let kind = ForgeKind::from_remote_url(&remote).unwrap_or(ForgeKind::GitHub);
+            Some((kind, host, parse_repo_from_remote(&remote)?))
+        }
+        kind => Some((kind, host, forge.repo_name()?.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forge_kind_parse() {
+        assert_eq!(ForgeKind::parse("github"), Some(ForgeKind::GitHub));
+        assert_eq!(ForgeKind::parse("GitLab"), Some(ForgeKind::GitLab));
+        assert_eq!(ForgeKind::parse("bitbucket"), Some(ForgeKind::Bitbucket));
+        assert_eq!(ForgeKind::parse("Jira"), Some(ForgeKind::Jira));
+        assert_eq!(ForgeKind::parse("gitea"), None);
+    }
+
+    #[test]
+    fn test_forge_kind_from_remote() {
+        assert_eq!(
+            ForgeKind::from_remote_url("git@github.com:owner/repo.git"),
+            Some(ForgeKind::GitHub)
+        );
+        assert_eq!(
+            ForgeKind::from_remote_url("https://gitlab.example.com/group/repo.git"),
+            Some(ForgeKind::GitLab)
+        );
+        assert_eq!(
+            ForgeKind::from_remote_url("git@bitbucket.org:team/repo.git"),
+            Some(ForgeKind::Bitbucket)
+        );
+        assert_eq!(
+            ForgeKind::from_remote_url("https://git.example.com/repo"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_repo_from_remote() {
+        assert_eq!(
+            parse_repo_from_remote("git@github.com:owner/repo.git"),
+            Some("owner/repo".to_string())
+        );
+        assert_eq!(
+            parse_repo_from_remote("https://gitlab.com/group/sub/project.git"),
+            Some("group/sub/project".to_string())
+        );
+        assert_eq!(
+            parse_repo_from_remote("ssh://git@gitlab.com/group/project"),
+            Some("group/project".to_string())
+        );
+        assert_eq!(parse_repo_from_remote("not-a-remote"), None);
+    }
+
+    #[test]
+    fn test_resolve_forge_kind_config_wins() {
+        let mut config = Config::default();
+        config.forge.kind = Some("gitlab".to_string());
+        assert_eq!(
+            resolve_forge_kind(&config, Some("git@github.com:owner/repo.git")),
+            ForgeKind::GitLab
+        );
+    }
+
+    #[test]
+    fn test_resolve_forge_kind_defaults_to_github() {
+        let config = Config::default();
+        assert_eq!(resolve_forge_kind(&config, None), ForgeKind::GitHub);
+        assert_eq!(
+            resolve_forge_kind(&config, Some("https://gitlab.com/group/repo")),
+            ForgeKind::GitLab
+        );
+    }
+
+    fn comment(id: i64, body: &str) -> GitHubComment {
+        GitHubComment {
+            id,
+            body: body.to_string(),
+            author: crate::github::CommentAuthor {
+                login: "someone".to_string(),
+            },
+            created_at: String::new(),
+            created_at_alt: None,
+        }
+    }
+
+    #[test]
+    fn test_find_marked_comment() {
+        let comments = vec![
+            comment(1, "LGTM"),
+            comment(2, &mark_comment_body("## Summary")),
+        ];
+        assert_eq!(find_marked_comment(&comments).map(|c| c.id), Some(2));
+        assert!(find_marked_comment(&comments[..1]).is_none());
+    }
+
+    #[test]
+    fn test_parse_host_and_raw_url() {
+        assert_eq!(
+            parse_host_from_remote("git@gitlab.example.com:group/repo.git"),
+            Some("gitlab.example.com".to_string())
+        );
+        assert_eq!(
+            parse_host_from_remote("ssh://git@gitlab.com:2222/group/repo"),
+            Some("gitlab.com".to_string())
+        );
+        assert_eq!(
+            raw_file_url(ForgeKind::GitLab, "gitlab.com", "g/p", "main", "docs/a.png"),
+            "https://gitlab.com/g/p/-/raw/main/docs/a.png"
+        );
+        assert_eq!(
+            commit_url(ForgeKind::GitLab, "gitlab.com", "g/p", "abc123"),
+            "https://gitlab.com/g/p/-/commit/abc123"
+        );
+        assert_eq!(
+            raw_file_url(ForgeKind::GitHub, "github.com", "o/r", "main", "a.png"),
+            "https://raw.githubusercontent.com/o/r/main/a.png"
+        );
+        assert_eq!(
+            commit_url(ForgeKind::Bitbucket, "bitbucket.org", "team/app", "abc123"),
+            "https://bitbucket.org/team/app/commits/abc123"
+        );
+    }
+}
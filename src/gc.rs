@@ -0,0 +1,254 @@
+//! `deciduous gc`: archive long-rejected option subtrees
+//!
+//! `deciduous gc --rejected-older-than 90d` finds options rejected before the
+//! cutoff (by status or a `rejected` edge) and moves everything below them
+//! into a patch file under `.deciduous/archive/`. The option itself stays as
+//! a stub whose description summarizes what was archived and whose `archived`
+//! metadata names the file. Nodes also reached from outside the subtree, and
+//! subtrees touched since the cutoff, are left alone.
+
+use crate::db::{Database, DecisionEdge, DecisionNode};
+use crate::diff::GraphPatch;
+use chrono::{DateTime, FixedOffset};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// A rejected option and the nodes below it to archive
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub option: DecisionNode,
+    /// When the option was rejected, or last changed
+    pub rejected_at: String,
+    /// Nodes below the option, by ID
+    pub nodes: Vec<DecisionNode>,
+    /// Edges touching those nodes
+    pub edges: Vec<DecisionEdge>,
+}
+
+fn before(timestamp: &str, cutoff: &DateTime<FixedOffset>) -> bool {
+    DateTime::parse_from_rfc3339(timestamp).is_ok_and(|t| t < *cutoff)
+}
+
+/// IDs reachable from `root` by following edges forward, `root` included
+fn reachable(edges: &[DecisionEdge], root: i32) -> HashSet<i32> {
+    let mut seen = HashSet::new();
+    let mut to_visit = vec![root];
+    while let Some(id) = to_visit.pop() {
+        if seen.insert(id) {
+            to_visit.extend(
+                edges
+                    .iter()
+                    .filter(|e| e.from_node_id == id)
+                    .map(|e| e.to_node_id),
+            );
+        }
+    }
+    seen
+}
+
+/// Rejected options with something to archive as of `cutoff`, by option ID
+pub fn plan(
+    nodes: &[DecisionNode],
+    edges: &[DecisionEdge],
+    cutoff: DateTime<FixedOffset>,
+) -> Vec<Candidate> {
+    let mut options: Vec<&DecisionNode> =
+        nodes.iter().filter(|n| n.node_type == "option").collect();
+    options.sort_by_key(|n| n.id);
+
+    let mut taken = HashSet::new();
+    let mut candidates = Vec::new();
+    for option in options {
+        if taken.contains(&option.id) {
+            continue;
+        }
+        let rejecting = edges
+            .iter()
+            .filter(|e| e.to_node_id == option.id && e.edge_type == "rejected")
+            .map(|e| e.created_at.as_str())
+            .max();
+        if option.status != "rejected" && rejecting.is_none() {
+            continue;
+        }
+        let rejected_at = rejecting
+            .into_iter()
+            .chain([option.updated_at.as_str()])
+            .max()
+            .unwrap_or_default()
+            .to_string();
+        if !before(&rejected_at, &cutoff) {
+            continue;
+        }
+
+        // Drop nodes with a parent outside the subtree until none are left
+        let mut below = reachable(edges, option.id);
+        below.remove(&option.id);
+        loop {
+            let shared: Vec<i32> = edges
+                .iter()
+                .filter(|e| below.contains(&e.to_node_id))
+                .filter(|e| e.from_node_id != option.id && !below.contains(&e.from_node_id))
+                .map(|e| e.to_node_id)
+                .collect();
+            if shared.is_empty() {
+                break;
+            }
+            for id in shared {
+                below.remove(&id);
+            }
+        }
+
+        let mut archived: Vec<DecisionNode> = nodes
+            .iter()
+            .filter(|n| below.contains(&n.id))
+            .cloned()
+            .collect();
+        if archived.is_empty() || archived.iter().any(|n| !before(&n.updated_at, &cutoff)) {
+            continue;
+        }
+        archived.sort_by_key(|n| n.id);
+        taken.extend(below.iter().copied());
+        candidates.push(Candidate {
+            option: option.clone(),
+            rejected_at,
+            nodes: archived,
+            edges: edges
+                .iter()
+                .filter(|e| below.contains(&e.from_node_id) || below.contains(&e.to_node_id))
+                .cloned()
+                .collect(),
+        });
+    }
+    candidates
+}
+
+/// The archive: each option as it was, with its subtree
+pub fn archive_patch(candidates: &[Candidate], branch: Option<String>) -> GraphPatch {
+    let mut patch = GraphPatch::new(None, branch, None);
+    for candidate in candidates {
+        patch.add_node(&candidate.option);
+        for node in &candidate.nodes {
+            patch.add_node(node);
+        }
+        for edge in &candidate.edges {
+            patch.add_edge(edge);
+        }
+    }
+    patch
+}
+
+/// The stub's note on what was archived, and where
+pub fn stub_summary(candidate: &Candidate, archive: &str) -> String {
+    let mut out = format!(
+        "Archived {} node(s) below this rejected option to {}:\n",
+        candidate.nodes.len(),
+        archive
+    );
+    for node in &candidate.nodes {
+        out.push_str(&format!(
+            "- #{} [{}] {}\n",
+            node.id, node.node_type, node.title
+        ));
+    }
+    out
+}
+
+/// Write the archive to `path`, turn each option into a stub and delete its
+/// subtree; returns the number of nodes deleted
+pub fn collect(db: &Database, candidates: &[Candidate], path: &Path) -> Result<usize, String> {
+    let err = |e: crate::db::DbError| e.to_string();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    archive_patch(candidates, crate::db::get_current_git_branch()).save(path)?;
+
+    let archive = path.display().to_string();
+    let now = chrono::Local::now().to_rfc3339();
+    let mut deleted = 0;
+    for candidate in candidates {
+        let option = &candidate.option;
+        let summary = stub_summary(candidate, &archive);
+        let description = match option.description.as_deref() {
+            Some(d) if !d.trim().is_empty() => format!("{}\n\n{}", d.trim_end(), summary),
+            _ => summary,
+        };
+        db.update_node_content(option.id, &option.title, Some(&description))
+            .map_err(err)?;
+        db.update_node_metadata_field(
+            option.id,
+            "archived",
+            serde_json::json!({
+                "file": archive,
+                "nodes": candidate.nodes.len(),
+                "archived_at": now,
+            }),
+        )
+        .map_err(err)?;
+        for node in &candidate.nodes {
+            if db.delete_node_at(node.id, &now).map_err(err)? {
+                deleted += 1;
+            }
+        }
+    }
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Local};
+
+    #[test]
+    fn test_gc_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let decision = db.node("decision", "Pick a store").insert().unwrap();
+        let redux = db.node("option", "Redux").insert().unwrap();
+        let spike = db.node("action", "Spike Redux").insert().unwrap();
+        let finding = db
+            .node("observation", "Too much boilerplate")
+            .insert()
+            .unwrap();
+        let shared = db
+            .node("observation", "Store must persist")
+            .insert()
+            .unwrap();
+        let context = db.node("option", "Context").insert().unwrap();
+        db.edge(decision, redux)
+            .edge_type("rejected")
+            .insert()
+            .unwrap();
+        db.edge(decision, context)
+            .edge_type("chosen")
+            .insert()
+            .unwrap();
+        db.edge(redux, spike).insert().unwrap();
+        db.edge(spike, finding).insert().unwrap();
+        db.edge(redux, shared).insert().unwrap();
+        db.edge(context, shared).insert().unwrap();
+
+        let nodes = db.get_all_nodes().unwrap();
+        let edges = db.get_all_edges().unwrap();
+        let now = Local::now().fixed_offset();
+        assert!(plan(&nodes, &edges, now - Duration::days(90)).is_empty());
+
+        let found = plan(&nodes, &edges, now + Duration::minutes(1));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].option.id, redux);
+        let ids: Vec<i32> = found[0].nodes.iter().map(|n| n.id).collect();
+        assert_eq!(ids, vec![spike, finding]);
+        assert!(stub_summary(&found[0], "gc.json")
+            .contains(&format!("- #{} [action] Spike Redux\n", spike)));
+
+        let path = dir.path().join("archive").join("gc.json");
+        assert_eq!(collect(&db, &found, &path).unwrap(), 2);
+        let archive = GraphPatch::load(&path).unwrap();
+        assert_eq!(archive.nodes.len(), 3);
+        assert_eq!(archive.edges.len(), 2);
+        let remaining: Vec<i32> = db.get_all_nodes().unwrap().iter().map(|n| n.id).collect();
+        assert_eq!(remaining, vec![decision, redux, shared, context]);
+        let stub = db.get_node_by_id(redux).unwrap().unwrap();
+        assert!(stub.metadata_json.unwrap().contains("\"archived\""));
+        assert!(stub.description.unwrap().starts_with("Archived 2 node(s)"));
+    }
+}
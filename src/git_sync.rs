@@ -0,0 +1,252 @@
+//! Sync the graph through git refs instead of committed patch files
+//!
+//! Each user's full export is stored as `patch.json` in a commit on
+//! `refs/deciduous/users/<user>`, outside any branch, so it never shows up in
+//! the working tree. `sync-git push` commits a fresh export there and pushes
+//! the ref; `sync-git pull` fetches everyone's refs into
+//! `refs/deciduous/remotes/<remote>/` and applies them. Patches merge by
+//! change_id like `diff apply`, so pulling is idempotent.
+
+use crate::diff::GraphPatch;
+use crate::hooks::{slug, user_slug};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Namespace the users' refs live under, locally and on the remote
+pub const USERS_PREFIX: &str = "refs/deciduous/users/";
+
+/// File holding the patch in each ref's tree
+pub const PATCH_FILE: &str = "patch.json";
+
+/// Ref holding a user's patch
+pub fn user_ref(user: &str) -> String {
+    format!("{}{}", USERS_PREFIX, user_slug(user))
+}
+
+/// Where refs fetched from `remote` (a name or URL) are kept
+pub fn remote_prefix(remote: &str) -> String {
+    format!("refs/deciduous/remotes/{}/", slug(remote))
+}
+
+fn git(repo: &Path, args: &[&str], input: Option<&[u8]>) -> Result<String, String> {
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin
+            .write_all(input)
+            .map_err(|e| format!("Failed to write to git: {}", e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Commit a ref points at, if it exists
+fn resolve(repo: &Path, refname: &str) -> Option<String> {
+    git(
+        repo,
+        &[
+            "rev-parse",
+            "--verify",
+            "-q",
+            &format!("{}^{{commit}}", refname),
+        ],
+        None,
+    )
+    .ok()
+}
+
+/// The patch stored at a ref or commit
+pub fn read_patch(repo: &Path, rev: &str) -> Result<GraphPatch, String> {
+    let content = git(repo, &["show", &format!("{}:{}", rev, PATCH_FILE)], None)?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse patch JSON: {}", e))
+}
+
+/// The fetched copy of `user`'s ref, if the remote has diverged from ours
+///
+/// Its changes need applying before we push, so the next export (and the
+/// merge commit) includes them.
+pub fn unmerged_remote(repo: &Path, remote: &str, user: &str) -> Option<String> {
+    let theirs = resolve(
+        repo,
+        &format!("{}{}", remote_prefix(remote), user_slug(user)),
+    )?;
+    let merged = match resolve(repo, &user_ref(user)) {
+        Some(ours) => git(repo, &["merge-base", "--is-ancestor", &theirs, &ours], None).is_ok(),
+        None => false,
+    };
+    (!merged).then_some(theirs)
+}
+
+/// Commit `patch` onto `user`'s ref; returns the new commit
+///
+/// `merge` (see `unmerged_remote`) becomes a second parent so the push
+/// fast-forwards. Returns None when the patch matches what the ref already
+/// holds and there is nothing to merge.
+pub fn commit_patch(
+    repo: &Path,
+    user: &str,
+    patch: &GraphPatch,
+    merge: Option<&str>,
+) -> Result<Option<String>, String> {
+    let refname = user_ref(user);
+    let parent = resolve(repo, &refname);
+    if merge.is_none() {
+        if let Some(parent) = &parent {
+            if read_patch(repo, parent).is_ok_and(|p| p.same_content(patch)) {
+                return Ok(None);
+            }
+        }
+    }
+
+    let json = serde_json::to_string_pretty(patch)
+        .map_err(|e| format!("Failed to serialize patch: {}", e))?;
+    let blob = git(
+        repo,
+        &["hash-object", "-w", "--stdin"],
+        Some(json.as_bytes()),
+    )?;
+    let tree = git(
+        repo,
+        &["mktree"],
+        Some(format!("100644 blob {}\t{}\n", blob, PATCH_FILE).as_bytes()),
+    )?;
+
+    let message = format!(
+        "deciduous: {} nodes, {} edges",
+        patch.nodes.len(),
+        patch.edges.len()
+    );
+    let mut args = vec!["commit-tree", tree.as_str(), "-m", message.as_str()];
+    for p in parent.iter().map(String::as_str).chain(merge) {
+        args.push("-p");
+        args.push(p);
+    }
+    let commit = git(repo, &args, None)?;
+    git(repo, &["update-ref", &refname, &commit], None)?;
+    Ok(Some(commit))
+}
+
+/// Push `user`'s ref to `remote`
+pub fn push(repo: &Path, remote: &str, user: &str) -> Result<(), String> {
+    let refname = user_ref(user);
+    git(
+        repo,
+        &["push", "-q", remote, &format!("{}:{}", refname, refname)],
+        None,
+    )
+    .map(|_| ())
+}
+
+/// Fetch every user's ref from `remote`; returns (user, local ref) pairs
+pub fn fetch(repo: &Path, remote: &str) -> Result<Vec<(String, String)>, String> {
+    let prefix = remote_prefix(remote);
+    git(
+        repo,
+        &[
+            "fetch",
+            "-q",
+            remote,
+            &format!("+{}*:{}*", USERS_PREFIX, prefix),
+        ],
+        None,
+    )?;
+    let refs = git(
+        repo,
+        &["for-each-ref", "--format=%(refname)", &prefix],
+        None,
+    )?;
+    Ok(refs
+        .lines()
+        .filter_map(|r| Some((r.strip_prefix(&prefix)?.to_string(), r.to_string())))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::PatchNode;
+
+    fn patch(titles: &[&str]) -> GraphPatch {
+        let mut p = GraphPatch::new(Some("alice".to_string()), None, None);
+        for (i, title) in titles.iter().enumerate() {
+            p.nodes.push(PatchNode {
+                change_id: format!("cid-{}", i),
+                node_type: "goal".to_string(),
+                title: title.to_string(),
+                description: None,
+                status: "pending".to_string(),
+                metadata_json: None,
+                created_at: "2025-01-01T00:00:00Z".to_string(),
+            });
+        }
+        p
+    }
+
+    fn repo(path: &Path) {
+        std::fs::create_dir_all(path).unwrap();
+        git(path, &["init", "-q"], None).unwrap();
+        git(path, &["config", "user.name", "Test"], None).unwrap();
+        git(path, &["config", "user.email", "test@example.com"], None).unwrap();
+    }
+
+    #[test]
+    fn test_ref_names() {
+        assert_eq!(user_ref("Alice Smith"), "refs/deciduous/users/alice-smith");
+        assert_eq!(remote_prefix("origin"), "refs/deciduous/remotes/origin/");
+    }
+
+    #[test]
+    fn test_push_and_fetch() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = dir.path().join("remote.git");
+        std::fs::create_dir_all(&remote).unwrap();
+        git(&remote, &["init", "-q", "--bare"], None).unwrap();
+        let remote_url = remote.to_str().unwrap();
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        repo(&a);
+        repo(&b);
+
+        assert!(commit_patch(&a, "alice", &patch(&["One"]), None)
+            .unwrap()
+            .is_some());
+        // Same content: no new commit
+        assert!(commit_patch(&a, "alice", &patch(&["One"]), None)
+            .unwrap()
+            .is_none());
+        push(&a, remote_url, "alice").unwrap();
+
+        let fetched = fetch(&b, remote_url).unwrap();
+        assert_eq!(fetched.len(), 1);
+        assert_eq!(fetched[0].0, "alice");
+        let pulled = read_patch(&b, &fetched[0].1).unwrap();
+        assert_eq!(pulled.nodes[0].title, "One");
+
+        // Alice pushes again from the second clone, merging the first
+        let theirs = unmerged_remote(&b, remote_url, "alice").unwrap();
+        commit_patch(&b, "alice", &patch(&["One", "Two"]), Some(&theirs)).unwrap();
+        push(&b, remote_url, "alice").unwrap();
+        fetch(&b, remote_url).unwrap();
+        assert!(unmerged_remote(&b, remote_url, "alice").is_none());
+    }
+}
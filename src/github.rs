@@ -3,6 +3,7 @@
 //! Wrapper around the GitHub CLI for issue operations.
 //! Uses `gh` instead of direct API to avoid token management complexity.
 
+use crate::forge::{Milestone, PullRequest, ReviewThread};
 use serde::{Deserialize, Serialize};
 use std::process::Command;
 
@@ -34,6 +35,26 @@ pub struct CommentAuthor {
     pub login: String,
 }
 
+/// A GitHub Projects (v2) board and its single-select "Status" field
+#[derive(Debug, Clone)]
+pub struct ProjectBoard {
+    pub id: String,
+    pub title: String,
+    pub status_field_id: Option<String>,
+    /// (option name, option id)
+    pub status_options: Vec<(String, String)>,
+}
+
+impl ProjectBoard {
+    /// Find a status option id by name (case-insensitive)
+    pub fn status_option_id(&self, name: &str) -> Option<&str> {
+        self.status_options
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, id)| id.as_str())
+    }
+}
+
 /// Error type for GitHub operations
 #[derive(Debug)]
 pub enum GitHubError {
@@ -329,45 +350,29 @@ impl GitHubClient {
     }
 
     /// Get comments on an issue
+    ///
+    /// Read from the REST API, which gives numeric ids and snake_case
+    /// timestamps; `gh issue view --json comments` has neither.
     pub fn get_issue_comments(&self, number: i32) -> Result<Vec<GitHubComment>> {
-        let mut cmd = Command::new("gh");
-        cmd.args([
-            "issue",
-            "view",
-            &number.to_string(),
-            "--json",
-            "comments",
-            "-q",
-            ".comments",
-        ]);
-
-        for arg in self.repo_args() {
-            cmd.arg(&arg);
-        }
-
-        let output = cmd.output()?;
+        let endpoint = format!(
+            "repos/{}/issues/{}/comments?per_page=100",
+            self.api_repo_path(),
+            number
+        );
+        // --slurp wraps the pages in one array; without it gh prints them back to back
+        let output = Command::new("gh")
+            .args(["api", &endpoint, "--paginate", "--slurp"])
+            .output()?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr).to_string();
             return Err(GitHubError::CommandFailed {
-                command: format!("gh issue view {} --json comments", number),
+                command: format!("gh api {}", endpoint),
                 stderr,
             });
         }
 
-        let json_str = String::from_utf8_lossy(&output.stdout);
-
-        // Handle empty comments (returns "null" or "[]")
-        if json_str.trim() == "null" || json_str.trim().is_empty() {
-            return Ok(vec![]);
-        }
-
-        let comments: Vec<GitHubComment> =
-            serde_json::from_str(&json_str).map_err(|e| GitHubError::ParseError {
-                message: format!("JSON parse error for comments: {} - Raw: {}", e, json_str),
-            })?;
-
-        Ok(comments)
+        parse_rest_comments(&String::from_utf8_lossy(&output.stdout))
     }
 
     /// Add a comment to an issue
@@ -525,6 +530,392 @@ impl GitHubClient {
         }
     }
 
+    /// List pull requests by state ("open", "closed", "merged", "all")
+    pub fn list_pull_requests(&self, state: &str) -> Result<Vec<PullRequest>> {
+        let mut cmd = Command::new("gh");
+        cmd.args([
+            "pr",
+            "list",
+            "--state",
+            state,
+            "--json",
+            GH_PR_FIELDS,
+            "--limit",
+            "100",
+        ]);
+
+        for arg in self.repo_args() {
+            cmd.arg(&arg);
+        }
+
+        let output = cmd.output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return Err(GitHubError::CommandFailed {
+                command: format!("gh pr list --state {}", state),
+                stderr,
+            });
+        }
+
+        let json_str = String::from_utf8_lossy(&output.stdout);
+
+        let items: Vec<GhPullRequest> =
+            serde_json::from_str(&json_str).map_err(|e| GitHubError::ParseError {
+                message: format!("JSON parse error: {}", e),
+            })?;
+
+        Ok(items.into_iter().map(PullRequest::from).collect())
+    }
+
+    /// Get a single pull request by number
+    pub fn get_pull_request(&self, number: i32) -> Result<PullRequest> {
+        let mut cmd = Command::new("gh");
+        cmd.args(["pr", "view", &number.to_string(), "--json", GH_PR_FIELDS]);
+
+        for arg in self.repo_args() {
+            cmd.arg(&arg);
+        }
+
+        let output = cmd.output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return Err(GitHubError::CommandFailed {
+                command: format!("gh pr view {}", number),
+                stderr,
+            });
+        }
+
+        let json_str = String::from_utf8_lossy(&output.stdout);
+        let item: GhPullRequest =
+            serde_json::from_str(&json_str).map_err(|e| GitHubError::ParseError {
+                message: format!("JSON parse error: {}", e),
+            })?;
+
+        Ok(item.into())
+    }
+
+    /// Repo path for `gh api` endpoints (gh fills in placeholders from the current repo)
+    fn api_repo_path(&self) -> String {
+        self.repo
+            .clone()
+            .unwrap_or_else(|| "{owner}/{repo}".to_string())
+    }
+
+    /// Get conversation comments on a pull request
+    ///
+    /// A PR's conversation is its issue's comment thread.
+    pub fn get_pull_request_comments(&self, number: i32) -> Result<Vec<GitHubComment>> {
+        self.get_issue_comments(number)
+    }
+
+    /// Add a comment to a pull request
+    pub fn add_pull_request_comment(&self, number: i32, body: &str) -> Result<()> {
+        let mut cmd = Command::new("gh");
+        cmd.args(["pr", "comment", &number.to_string(), "--body", body]);
+
+        for arg in self.repo_args() {
+            cmd.arg(&arg);
+        }
+
+        let output = cmd.output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return Err(GitHubError::CommandFailed {
+                command: format!("gh pr comment {}", number),
+                stderr,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Replace the body of an existing pull request comment
+    pub fn update_pull_request_comment(&self, comment_id: i64, body: &str) -> Result<()> {
+        let endpoint = format!(
+            "repos/{}/issues/comments/{}",
+            self.api_repo_path(),
+            comment_id
+        );
+        let body_field = format!("body={}", body);
+        let output = Command::new("gh")
+            .args(["api", "-X", "PATCH", &endpoint, "-f", &body_field])
+            .output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return Err(GitHubError::CommandFailed {
+                command: format!("gh api -X PATCH {}", endpoint),
+                stderr,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// List milestones (open and closed)
+    pub fn list_milestones(&self) -> Result<Vec<Milestone>> {
+        let endpoint = format!(
+            "repos/{}/milestones?state=all&per_page=100",
+            self.api_repo_path()
+        );
+        let output = Command::new("gh").args(["api", &endpoint]).output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return Err(GitHubError::CommandFailed {
+                command: format!("gh api {}", endpoint),
+                stderr,
+            });
+        }
+
+        let items: Vec<RestMilestone> =
+            serde_json::from_slice(&output.stdout).map_err(|e| GitHubError::ParseError {
+                message: format!("JSON parse error for milestones: {}", e),
+            })?;
+
+        Ok(items.into_iter().map(Milestone::from).collect())
+    }
+
+    /// Create a milestone
+    pub fn create_milestone(&self, title: &str, description: &str) -> Result<Milestone> {
+        let endpoint = format!("repos/{}/milestones", self.api_repo_path());
+        let output = Command::new("gh")
+            .args([
+                "api",
+                "-X",
+                "POST",
+                &endpoint,
+                "-f",
+                &format!("title={}", title),
+                "-f",
+                &format!("description={}", description),
+            ])
+            .output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return Err(GitHubError::CommandFailed {
+                command: format!("gh api -X POST {}", endpoint),
+                stderr,
+            });
+        }
+
+        let item: RestMilestone =
+            serde_json::from_slice(&output.stdout).map_err(|e| GitHubError::ParseError {
+                message: format!("JSON parse error for milestone: {}", e),
+            })?;
+
+        Ok(item.into())
+    }
+
+    /// Assign an issue to a milestone by title
+    pub fn set_issue_milestone(&self, number: i32, milestone: &str) -> Result<()> {
+        let mut cmd = Command::new("gh");
+        cmd.args([
+            "issue",
+            "edit",
+            &number.to_string(),
+            "--milestone",
+            milestone,
+        ]);
+
+        for arg in self.repo_args() {
+            cmd.arg(&arg);
+        }
+
+        let output = cmd.output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return Err(GitHubError::CommandFailed {
+                command: format!("gh issue edit {} --milestone", number),
+                stderr,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Run a GraphQL query via `gh api graphql`
+    ///
+    /// `fields` are string variables (-f), `typed_fields` are numbers/booleans (-F).
+    fn graphql(
+        &self,
+        query: &str,
+        fields: &[(&str, &str)],
+        typed_fields: &[(&str, &str)],
+    ) -> Result<serde_json::Value> {
+        let mut cmd = Command::new("gh");
+        cmd.args(["api", "graphql", "-f", &format!("query={}", query)]);
+        for (key, value) in fields {
+            cmd.args(["-f", &format!("{}={}", key, value)]);
+        }
+        for (key, value) in typed_fields {
+            cmd.args(["-F", &format!("{}={}", key, value)]);
+        }
+
+        let output = cmd.output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            if stderr.contains("rate limit") {
+                return Err(GitHubError::RateLimited);
+            }
+            return Err(GitHubError::CommandFailed {
+                command: "gh api graphql".to_string(),
+                stderr,
+            });
+        }
+
+        serde_json::from_slice(&output.stdout).map_err(|e| GitHubError::ParseError {
+            message: format!("GraphQL response parse error: {}", e),
+        })
+    }
+
+    /// Get inline review threads on a pull request (GraphQL; REST has no thread/resolution view)
+    pub fn get_review_threads(&self, number: i32) -> Result<Vec<ReviewThread>> {
+        let Some((owner, name)) = self.repo.as_deref().and_then(|r| r.split_once('/')) else {
+            return Err(GitHubError::ParseError {
+                message: "Repository unknown; specify --repo owner/repo".to_string(),
+            });
+        };
+        let query = r#"
+query($owner: String!, $name: String!, $number: Int!) {
+  repository(owner: $owner, name: $name) {
+    pullRequest(number: $number) {
+      reviewThreads(first: 100) {
+        nodes {
+          id
+          isResolved
+          path
+          line
+          comments(first: 50) {
+            nodes { databaseId body diffHunk createdAt author { login } }
+          }
+        }
+      }
+    }
+  }
+}"#;
+        let number_str = number.to_string();
+        let response = self.graphql(
+            query,
+            &[("owner", owner), ("name", name)],
+            &[("number", &number_str)],
+        )?;
+
+        Ok(parse_review_threads(&response))
+    }
+
+    /// Look up a Projects (v2) board by owner (user or org) and number
+    pub fn get_project(&self, owner: &str, number: u32) -> Result<ProjectBoard> {
+        let query = r#"
+query($owner: String!, $number: Int!) {
+  repositoryOwner(login: $owner) {
+    ... on ProjectV2Owner {
+      projectV2(number: $number) {
+        id
+        title
+        field(name: "Status") {
+          ... on ProjectV2SingleSelectField { id options { id name } }
+        }
+      }
+    }
+  }
+}"#;
+        let number_str = number.to_string();
+        let response = self.graphql(query, &[("owner", owner)], &[("number", &number_str)])?;
+
+        parse_project_board(&response).ok_or_else(|| GitHubError::ParseError {
+            message: format!("Project #{} not found for owner '{}'", number, owner),
+        })
+    }
+
+    /// Get the GraphQL node id of an issue
+    pub fn issue_node_id(&self, number: i32) -> Result<String> {
+        let mut cmd = Command::new("gh");
+        cmd.args([
+            "issue",
+            "view",
+            &number.to_string(),
+            "--json",
+            "id",
+            "-q",
+            ".id",
+        ]);
+
+        for arg in self.repo_args() {
+            cmd.arg(&arg);
+        }
+
+        let output = cmd.output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            if stderr.contains("Could not resolve") {
+                return Err(GitHubError::IssueNotFound { number });
+            }
+            return Err(GitHubError::CommandFailed {
+                command: format!("gh issue view {} --json id", number),
+                stderr,
+            });
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Add an issue to a project (no-op if already there); returns the project item id
+    pub fn add_to_project(&self, project_id: &str, content_id: &str) -> Result<String> {
+        let query = r#"
+mutation($project: ID!, $content: ID!) {
+  addProjectV2ItemById(input: {projectId: $project, contentId: $content}) { item { id } }
+}"#;
+        let response = self.graphql(
+            query,
+            &[("project", project_id), ("content", content_id)],
+            &[],
+        )?;
+
+        response
+            .pointer("/data/addProjectV2ItemById/item/id")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .ok_or_else(|| GitHubError::ParseError {
+                message: format!("Unexpected addProjectV2ItemById response: {}", response),
+            })
+    }
+
+    /// Set a project item's single-select field (e.g. Status) to an option
+    pub fn set_project_item_status(
+        &self,
+        project_id: &str,
+        item_id: &str,
+        field_id: &str,
+        option_id: &str,
+    ) -> Result<()> {
+        let query = r#"
+mutation($project: ID!, $item: ID!, $field: ID!, $option: String!) {
+  updateProjectV2ItemFieldValue(input: {
+    projectId: $project, itemId: $item, fieldId: $field,
+    value: {singleSelectOptionId: $option}
+  }) { projectV2Item { id } }
+}"#;
+        self.graphql(
+            query,
+            &[
+                ("project", project_id),
+                ("item", item_id),
+                ("field", field_id),
+                ("option", option_id),
+            ],
+            &[],
+        )?;
+        Ok(())
+    }
+
     /// Get the repo name
     pub fn repo_name(&self) -> Option<&str> {
         self.repo.as_deref()
@@ -594,21 +985,190 @@ impl GitHubClient {
     }
 }
 
-/// Ensure the 'roadmap' label exists, creating it if needed
-/// Returns Ok(true) if label was created, Ok(false) if it already existed
-pub fn ensure_roadmap_label(client: &GitHubClient) -> Result<bool> {
-    match client.label_exists("roadmap") {
-        Ok(true) => Ok(false), // Already exists
-        Ok(false) => {
-            client.create_label(
-                "roadmap",
-                "Roadmap item synced from ROADMAP.md by deciduous",
-                "0e8a16", // Green color
-            )?;
-            Ok(true) // Created
-        }
-        Err(e) => Err(e),
+/// Milestone as returned by the REST API
+#[derive(Deserialize)]
+struct RestMilestone {
+    number: i32,
+    title: String,
+    state: String,
+    open_issues: i32,
+    closed_issues: i32,
+}
+
+impl From<RestMilestone> for Milestone {
+    fn from(m: RestMilestone) -> Self {
+        Milestone {
+            number: m.number,
+            title: m.title,
+            state: m.state,
+            open_issues: m.open_issues,
+            closed_issues: m.closed_issues,
+        }
+    }
+}
+
+/// Fields requested from `gh pr list/view --json`
+const GH_PR_FIELDS: &str = "number,title,state,headRefName,url";
+
+/// Pull request as returned by `gh pr ... --json`
+#[derive(Deserialize)]
+struct GhPullRequest {
+    number: i32,
+    title: String,
+    state: String, // "OPEN", "CLOSED" or "MERGED"
+    #[serde(rename = "headRefName")]
+    head_ref_name: String,
+    url: String,
+}
+
+impl From<GhPullRequest> for PullRequest {
+    fn from(pr: GhPullRequest) -> Self {
+        PullRequest {
+            number: pr.number,
+            title: pr.title,
+            state: pr.state.to_lowercase(),
+            head_branch: pr.head_ref_name,
+            html_url: pr.url,
+        }
+    }
+}
+
+/// Parse `gh api .../comments --paginate --slurp` output: an array of pages
+fn parse_rest_comments(json_str: &str) -> Result<Vec<GitHubComment>> {
+    if json_str.trim().is_empty() {
+        return Ok(vec![]);
     }
+
+    #[derive(Deserialize)]
+    struct RestUser {
+        login: String,
+    }
+
+    #[derive(Deserialize)]
+    struct RestComment {
+        id: i64,
+        body: String,
+        user: Option<RestUser>,
+        created_at: String,
+    }
+
+    let pages: Vec<Vec<RestComment>> =
+        serde_json::from_str(json_str).map_err(|e| GitHubError::ParseError {
+            message: format!("JSON parse error for comments: {}", e),
+        })?;
+
+    Ok(pages
+        .into_iter()
+        .flatten()
+        .map(|c| GitHubComment {
+            id: c.id,
+            body: c.body,
+            author: CommentAuthor {
+                // Deleted accounts come back as a null user
+                login: c.user.map_or_else(|| "ghost".to_string(), |u| u.login),
+            },
+            created_at: c.created_at,
+            created_at_alt: None,
+        })
+        .collect())
+}
+
+/// Extract a `ProjectBoard` from a `repositoryOwner.projectV2` GraphQL response
+fn parse_project_board(response: &serde_json::Value) -> Option<ProjectBoard> {
+    let project = response.pointer("/data/repositoryOwner/projectV2")?;
+    let field = project.get("field").filter(|f| !f.is_null());
+
+    let status_options = field
+        .and_then(|f| f.get("options"))
+        .and_then(|o| o.as_array())
+        .map(|options| {
+            options
+                .iter()
+                .filter_map(|o| {
+                    Some((
+                        o.get("name")?.as_str()?.to_string(),
+                        o.get("id")?.as_str()?.to_string(),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(ProjectBoard {
+        id: project.get("id")?.as_str()?.to_string(),
+        title: project
+            .get("title")
+            .and_then(|t| t.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        status_field_id: field
+            .and_then(|f| f.get("id"))
+            .and_then(|id| id.as_str())
+            .map(|s| s.to_string()),
+        status_options,
+    })
+}
+
+/// Extract review threads from a `repository.pullRequest.reviewThreads` GraphQL response
+fn parse_review_threads(response: &serde_json::Value) -> Vec<ReviewThread> {
+    let Some(threads) = response
+        .pointer("/data/repository/pullRequest/reviewThreads/nodes")
+        .and_then(|n| n.as_array())
+    else {
+        return vec![];
+    };
+
+    threads
+        .iter()
+        .filter_map(|thread| {
+            let comment_nodes = thread.pointer("/comments/nodes")?.as_array()?;
+            let comments: Vec<GitHubComment> = comment_nodes
+                .iter()
+                .filter_map(|c| {
+                    Some(GitHubComment {
+                        id: c.get("databaseId")?.as_i64()?,
+                        body: c.get("body")?.as_str()?.to_string(),
+                        author: CommentAuthor {
+                            // Deleted accounts come back as a null author
+                            login: c
+                                .pointer("/author/login")
+                                .and_then(|l| l.as_str())
+                                .unwrap_or("ghost")
+                                .to_string(),
+                        },
+                        created_at: c
+                            .get("createdAt")
+                            .and_then(|d| d.as_str())
+                            .unwrap_or_default()
+                            .to_string(),
+                        created_at_alt: None,
+                    })
+                })
+                .collect();
+
+            Some(ReviewThread {
+                id: thread.get("id")?.as_str()?.to_string(),
+                path: thread
+                    .get("path")
+                    .and_then(|p| p.as_str())
+                    .map(|p| p.to_string()),
+                line: thread
+                    .get("line")
+                    .and_then(|l| l.as_i64())
+                    .map(|l| l as i32),
+                excerpt: comment_nodes
+                    .first()
+                    .and_then(|c| c.get("diffHunk"))
+                    .and_then(|h| h.as_str())
+                    .map(|h| h.to_string()),
+                resolved: thread
+                    .get("isResolved")
+                    .and_then(|r| r.as_bool())
+                    .unwrap_or(false),
+                comments,
+            })
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -636,6 +1196,130 @@ mod tests {
         assert!(client_without_repo.repo_args().is_empty());
     }
 
+    #[test]
+    fn test_api_repo_path() {
+        let client = GitHubClient::new(Some("owner/repo".to_string()));
+        assert_eq!(client.api_repo_path(), "owner/repo");
+
+        let client = GitHubClient::new(None);
+        assert_eq!(client.api_repo_path(), "{owner}/{repo}");
+    }
+
+    #[test]
+    fn test_parse_project_board() {
+        let response = serde_json::json!({
+            "data": {"repositoryOwner": {"projectV2": {
+                "id": "PVT_1",
+                "title": "Roadmap",
+                "field": {"id": "PVTSSF_1", "options": [
+                    {"id": "a", "name": "Todo"},
+                    {"id": "b", "name": "In Progress"},
+                    {"id": "c", "name": "Done"}
+                ]}
+            }}}
+        });
+        let board = parse_project_board(&response).unwrap();
+        assert_eq!(board.id, "PVT_1");
+        assert_eq!(board.status_field_id.as_deref(), Some("PVTSSF_1"));
+        assert_eq!(board.status_option_id("in progress"), Some("b"));
+        assert_eq!(board.status_option_id("Blocked"), None);
+
+        let missing = serde_json::json!({"data": {"repositoryOwner": {"projectV2": null}}});
+        assert!(parse_project_board(&missing).is_none());
+    }
+
+    #[test]
+    fn test_parse_review_threads() {
+        let response = serde_json::json!({
+            "data": {"repository": {"pullRequest": {"reviewThreads": {"nodes": [
+                {
+                    "id": "PRRT_1",
+                    "isResolved": true,
+                    "path": "src/db.rs",
+                    "line": 42,
+                    "comments": {"nodes": [
+                        {"databaseId": 11, "body": "Why not a transaction?", "diffHunk": "@@ -1 +1 @@",
+                         "createdAt": "2025-01-01T00:00:00Z", "author": {"login": "alice"}},
+                        {"databaseId": 12, "body": "Done", "diffHunk": "@@ -1 +1 @@",
+                         "createdAt": "2025-01-02T00:00:00Z", "author": null}
+                    ]}
+                }
+            ]}}}}
+        });
+        let threads = parse_review_threads(&response);
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].path.as_deref(), Some("src/db.rs"));
+        assert_eq!(threads[0].line, Some(42));
+        assert!(threads[0].resolved);
+        assert_eq!(threads[0].excerpt.as_deref(), Some("@@ -1 +1 @@"));
+        assert_eq!(threads[0].comments[0].author.login, "alice");
+        assert_eq!(threads[0].comments[1].author.login, "ghost");
+
+        assert!(parse_review_threads(&serde_json::json!({"data": null})).is_empty());
+    }
+
+    #[test]
+    fn test_parse_rest_comments() {
+        // Two pages, as `gh api --paginate --slurp` prints them
+        let json = r#"[
+            [{"id": 1, "body": "First", "user": {"login": "alice"}, "created_at": "2025-01-01T00:00:00Z"}],
+            [{"id": 2, "body": "Second", "user": null, "created_at": "2025-01-02T00:00:00Z"}]
+        ]"#;
+        let comments = parse_rest_comments(json).unwrap();
+        assert_eq!(
+            comments.iter().map(|c| c.id).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(comments[0].author.login, "alice");
+        assert_eq!(comments[1].author.login, "ghost");
+
+        assert!(parse_rest_comments("[]").unwrap().is_empty());
+        assert!(parse_rest_comments("").unwrap().is_empty());
+        // Pages printed back to back, as gh does without --slurp, don't parse
+        assert!(parse_rest_comments("[][]").is_err());
+    }
+
+    #[test]
+    fn test_parse_issue_comments_from_gh_api() {
+        // `gh api repos/o/r/issues/7/comments --paginate --slurp`, one page
+        let json = r#"[
+  [
+    {
+      "url": "https://api.github.com/repos/octo/app/issues/comments/2044562394",
+      "html_url": "https://github.com/octo/app/issues/7#issuecomment-2044562394",
+      "issue_url": "https://api.github.com/repos/octo/app/issues/7",
+      "id": 2044562394,
+      "node_id": "IC_kwDOKx8Hfc554Jna",
+      "user": {
+        "login": "alice",
+        "id": 1024,
+        "node_id": "MDQ6VXNlcjEwMjQ=",
+        "avatar_url": "https://avatars.githubusercontent.com/u/1024?v=4",
+        "type": "User",
+        "site_admin": false
+      },
+      "created_at": "2024-04-09T15:02:11Z",
+      "updated_at": "2024-04-09T15:04:45Z",
+      "author_association": "MEMBER",
+      "body": "Repro'd on main.\r\nIt only happens with an empty cache.",
+      "reactions": {
+        "url": "https://api.github.com/repos/octo/app/issues/comments/2044562394/reactions",
+        "total_count": 1,
+        "+1": 1,
+        "-1": 0
+      },
+      "performed_via_github_app": null
+    }
+  ]
+]"#;
+        let comments = parse_rest_comments(json).unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].id, 2044562394);
+        assert_eq!(comments[0].author.login, "alice");
+        assert_eq!(comments[0].created_at, "2024-04-09T15:02:11Z");
+        assert!(comments[0].body.starts_with("Repro'd on main."));
+    }
+
     // Note: Integration tests would require actual gh CLI and authentication
     // These are covered by manual testing
 }
@@ -0,0 +1,689 @@
+//! GitLab CLI (`glab`) Integration
+//!
+//! GitLab implementation of the `Forge` trait. Mirrors the GitHub client but
+//! shells out to `glab`, mapping issues, notes and merge requests onto the
+//! shared types so roadmap sync doesn't care which forge it talks to.
+
+use crate::forge::{Forge, ForgeKind, Milestone, PullRequest, ReviewThread};
+use crate::github::{CommentAuthor, GitHubComment, GitHubError, GitHubIssue, Result};
+use serde::Deserialize;
+use std::process::Command;
+
+/// Issue as returned by `glab ... --output json`
+#[derive(Debug, Deserialize)]
+struct GitLabIssueJson {
+    iid: i32,
+    title: String,
+    description: Option<String>,
+    state: String, // "opened" or "closed"
+    web_url: String,
+    created_at: String,
+    updated_at: String,
+}
+
+/// Milestone as returned by the GitLab API
+#[derive(Debug, Deserialize)]
+struct GitLabMilestoneJson {
+    id: i64,
+    iid: i32,
+    title: String,
+    state: String, // "active" or "closed"
+}
+
+/// Map GitLab milestone states onto "open"/"closed"
+fn normalize_milestone_state(state: &str) -> String {
+    match state {
+        "active" => "open".to_string(),
+        other => other.to_string(),
+    }
+}
+
+impl From<GitLabIssueJson> for GitHubIssue {
+    fn from(issue: GitLabIssueJson) -> Self {
+        GitHubIssue {
+            number: issue.iid,
+            title: issue.title,
+            body: issue.description.unwrap_or_default(),
+            state: normalize_state(&issue.state),
+            html_url: issue.web_url,
+            created_at: issue.created_at,
+            updated_at: issue.updated_at,
+        }
+    }
+}
+
+/// Merge request as returned by `glab mr ... --output json`
+#[derive(Debug, Deserialize)]
+struct GitLabMergeRequestJson {
+    iid: i32,
+    title: String,
+    state: String, // "opened", "closed" or "merged"
+    source_branch: String,
+    web_url: String,
+}
+
+impl From<GitLabMergeRequestJson> for PullRequest {
+    fn from(mr: GitLabMergeRequestJson) -> Self {
+        PullRequest {
+            number: mr.iid,
+            title: mr.title,
+            state: normalize_state(&mr.state),
+            head_branch: mr.source_branch,
+            html_url: mr.web_url,
+        }
+    }
+}
+
+/// Map GitLab states onto the GitHub vocabulary ("opened" -> "open")
+fn normalize_state(state: &str) -> String {
+    match state.to_lowercase().as_str() {
+        "opened" => "open".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Extract an issue number from `glab issue create` output
+///
+/// The URL looks like "https://gitlab.com/group/repo/-/issues/42".
+fn parse_issue_number(output: &str) -> Option<i32> {
+    output
+        .split_whitespace()
+        .filter(|token| token.contains("/-/issues/"))
+        .filter_map(|url| url.rsplit('/').next())
+        .find_map(|n| n.parse().ok())
+}
+
+/// GitLab client using `glab` CLI
+pub struct GitLabClient {
+    repo: Option<String>, // "group/project" format
+}
+
+impl GitLabClient {
+    /// Create a new client, optionally with explicit repo
+    pub fn new(repo: Option<String>) -> Self {
+        Self { repo }
+    }
+
+    /// Get repo string for glab commands
+    fn repo_args(&self) -> Vec<String> {
+        match &self.repo {
+            Some(repo) => vec!["-R".to_string(), repo.clone()],
+            None => vec![],
+        }
+    }
+
+    /// Project reference for `glab api` paths (URL-encoded path or `:id`)
+    fn project_ref(&self) -> String {
+        match &self.repo {
+            Some(repo) => repo.replace('/', "%2F"),
+            None => ":id".to_string(),
+        }
+    }
+
+    /// Run a glab command scoped to this repo and return stdout
+    fn run(&self, args: &[&str]) -> Result<String> {
+        let mut cmd = Command::new("glab");
+        cmd.args(args);
+
+        for arg in self.repo_args() {
+            cmd.arg(&arg);
+        }
+
+        self.finish(cmd, args)
+    }
+
+    /// Run `glab api` (repo is encoded in the path, so no -R)
+    fn api(&self, args: &[&str]) -> Result<String> {
+        let mut cmd = Command::new("glab");
+        cmd.arg("api").args(args);
+        let mut full = vec!["api"];
+        full.extend_from_slice(args);
+        self.finish(cmd, &full)
+    }
+
+    /// Execute a prepared command, mapping failures onto `GitHubError`
+    fn finish(&self, mut cmd: Command, args: &[&str]) -> Result<String> {
+        let output = cmd.output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            if stderr.contains("404") || stderr.contains("not found") {
+                if let Some(number) = args.get(2).and_then(|n| n.parse().ok()) {
+                    return Err(GitHubError::IssueNotFound { number });
+                }
+            }
+            if stderr.contains("rate limit") || stderr.contains("429") {
+                return Err(GitHubError::RateLimited);
+            }
+            let subcommand: Vec<&str> = args.iter().take(2).copied().collect();
+            return Err(GitHubError::CommandFailed {
+                command: format!("glab {}", subcommand.join(" ")),
+                stderr,
+            });
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    fn parse_issues(json_str: &str) -> Result<Vec<GitHubIssue>> {
+        if json_str.trim().is_empty() || json_str.trim() == "null" {
+            return Ok(vec![]);
+        }
+
+        let items: Vec<GitLabIssueJson> =
+            serde_json::from_str(json_str).map_err(|e| GitHubError::ParseError {
+                message: format!("JSON parse error: {}", e),
+            })?;
+
+        Ok(items.into_iter().map(GitHubIssue::from).collect())
+    }
+
+    /// Parse issue/MR notes, dropping system notes ("changed the description", "closed")
+    fn parse_notes(json_str: &str) -> Result<Vec<GitHubComment>> {
+        #[derive(Deserialize)]
+        struct NoteAuthor {
+            username: String,
+        }
+
+        #[derive(Deserialize)]
+        struct Note {
+            id: i64,
+            body: String,
+            author: NoteAuthor,
+            created_at: String,
+            #[serde(default)]
+            system: bool,
+        }
+
+        let notes: Vec<Note> =
+            serde_json::from_str(json_str).map_err(|e| GitHubError::ParseError {
+                message: format!("JSON parse error for notes: {} - Raw: {}", e, json_str),
+            })?;
+
+        Ok(notes
+            .into_iter()
+            .filter(|n| !n.system)
+            .map(|n| GitHubComment {
+                id: n.id,
+                body: n.body,
+                author: CommentAuthor {
+                    login: n.author.username,
+                },
+                created_at: n.created_at,
+                created_at_alt: None,
+            })
+            .collect())
+    }
+
+    /// Parse MR discussions into review threads, keeping only diff (inline) discussions
+    fn parse_discussions(json_str: &str) -> Result<Vec<ReviewThread>> {
+        #[derive(Deserialize)]
+        struct NoteAuthor {
+            username: String,
+        }
+
+        #[derive(Deserialize)]
+        struct Position {
+            new_path: Option<String>,
+            old_path: Option<String>,
+            new_line: Option<i32>,
+            old_line: Option<i32>,
+        }
+
+        #[derive(Deserialize)]
+        struct DiscussionNote {
+            id: i64,
+            body: String,
+            author: NoteAuthor,
+            created_at: String,
+            #[serde(default)]
+            system: bool,
+            #[serde(default)]
+            resolvable: bool,
+            #[serde(default)]
+            resolved: bool,
+            position: Option<Position>,
+        }
+
+        #[derive(Deserialize)]
+        struct Discussion {
+            id: String,
+            notes: Vec<DiscussionNote>,
+        }
+
+        if json_str.trim().is_empty() || json_str.trim() == "null" {
+            return Ok(vec![]);
+        }
+
+        let discussions: Vec<Discussion> =
+            serde_json::from_str(json_str).map_err(|e| GitHubError::ParseError {
+                message: format!("JSON parse error for discussions: {}", e),
+            })?;
+
+        Ok(discussions
+            .into_iter()
+            .filter_map(|d| {
+                let position = d.notes.first()?.position.as_ref()?;
+                let path = position.new_path.clone().or(position.old_path.clone());
+                let line = position.new_line.or(position.old_line);
+                let resolvable: Vec<&DiscussionNote> =
+                    d.notes.iter().filter(|n| n.resolvable).collect();
+                let resolved = !resolvable.is_empty() && resolvable.iter().all(|n| n.resolved);
+
+                Some(ReviewThread {
+                    id: d.id,
+                    path,
+                    line,
+                    excerpt: None,
+                    resolved,
+                    comments: d
+                        .notes
+                        .into_iter()
+                        .filter(|n| !n.system)
+                        .map(|n| GitHubComment {
+                            id: n.id,
+                            body: n.body,
+                            author: CommentAuthor {
+                                login: n.author.username,
+                            },
+                            created_at: n.created_at,
+                            created_at_alt: None,
+                        })
+                        .collect(),
+                })
+            })
+            .collect())
+    }
+
+    /// Get the repo name
+    pub fn repo_name(&self) -> Option<&str> {
+        self.repo.as_deref()
+    }
+}
+
+impl Forge for GitLabClient {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::GitLab
+    }
+
+    fn repo_name(&self) -> Option<&str> {
+        GitLabClient::repo_name(self)
+    }
+
+    fn check_auth(&self) -> Result<bool> {
+        let output = Command::new("glab").args(["auth", "status"]).output()?;
+        Ok(output.status.success())
+    }
+
+    fn create_issue(&self, title: &str, body: &str, labels: &[&str]) -> Result<GitHubIssue> {
+        let label_list = labels.join(",");
+        let mut args = vec![
+            "issue",
+            "create",
+            "--title",
+            title,
+            "--description",
+            body,
+            "--yes",
+        ];
+        if !labels.is_empty() {
+            args.push("--label");
+            args.push(&label_list);
+        }
+
+        let stdout = self.run(&args)?;
+
+        let number = parse_issue_number(&stdout).ok_or_else(|| GitHubError::ParseError {
+            message: format!(
+                "Could not parse issue number from output: {}",
+                stdout.trim()
+            ),
+        })?;
+
+        // Fetch the full issue details
+        self.get_issue(number)
+    }
+
+    fn get_issue(&self, number: i32) -> Result<GitHubIssue> {
+        let number_str = number.to_string();
+        let json_str = self.run(&["issue", "view", &number_str, "--output", "json"])?;
+
+        let issue: GitLabIssueJson =
+            serde_json::from_str(&json_str).map_err(|e| GitHubError::ParseError {
+                message: format!("JSON parse error: {} - Raw: {}", e, json_str),
+            })?;
+
+        Ok(issue.into())
+    }
+
+    fn update_issue_body(&self, number: i32, body: &str) -> Result<()> {
+        let number_str = number.to_string();
+        self.run(&["issue", "update", &number_str, "--description", body])?;
+        Ok(())
+    }
+
+    fn update_issue_title(&self, number: i32, title: &str) -> Result<()> {
+        let number_str = number.to_string();
+        self.run(&["issue", "update", &number_str, "--title", title])?;
+        Ok(())
+    }
+
+    fn close_issue(&self, number: i32) -> Result<()> {
+        let number_str = number.to_string();
+        self.run(&["issue", "close", &number_str])?;
+        Ok(())
+    }
+
+    fn reopen_issue(&self, number: i32) -> Result<()> {
+        let number_str = number.to_string();
+        self.run(&["issue", "reopen", &number_str])?;
+        Ok(())
+    }
+
+    fn get_issue_comments(&self, number: i32) -> Result<Vec<GitHubComment>> {
+        let path = format!(
+            "projects/{}/issues/{}/notes?sort=asc&per_page=100",
+            self.project_ref(),
+            number
+        );
+        Self::parse_notes(&self.api(&[&path])?)
+    }
+
+    fn add_comment(&self, number: i32, body: &str) -> Result<()> {
+        let number_str = number.to_string();
+        self.run(&["issue", "note", &number_str, "--message", body])?;
+        Ok(())
+    }
+
+    fn list_issues_with_label(&self, label: &str) -> Result<Vec<GitHubIssue>> {
+        let json_str = self.run(&[
+            "issue",
+            "list",
+            "--label",
+            label,
+            "--all",
+            "--per-page",
+            "100",
+            "--output",
+            "json",
+        ])?;
+        Self::parse_issues(&json_str)
+    }
+
+    fn find_issue_by_title(&self, title: &str) -> Result<Option<GitHubIssue>> {
+        let json_str = self.run(&[
+            "issue",
+            "list",
+            "--search",
+            title,
+            "--all",
+            "--per-page",
+            "20",
+            "--output",
+            "json",
+        ])?;
+
+        // Search is fuzzy; only accept an exact title match
+        Ok(Self::parse_issues(&json_str)?
+            .into_iter()
+            .find(|i| i.title.to_lowercase() == title.to_lowercase()))
+    }
+
+    fn label_exists(&self, name: &str) -> Result<bool> {
+        let path = format!("projects/{}/labels?search={}", self.project_ref(), name);
+        let json_str = self.api(&[&path])?;
+
+        #[derive(Deserialize)]
+        struct Label {
+            name: String,
+        }
+
+        let labels: Vec<Label> =
+            serde_json::from_str(&json_str).map_err(|e| GitHubError::ParseError {
+                message: format!("JSON parse error for labels: {}", e),
+            })?;
+
+        Ok(labels.iter().any(|l| l.name == name))
+    }
+
+    fn create_label(&self, name: &str, description: &str, color: &str) -> Result<()> {
+        // GitLab wants "#rrggbb", GitHub takes "rrggbb"
+        let color = if color.starts_with('#') {
+            color.to_string()
+        } else {
+            format!("#{}", color)
+        };
+
+        self.run(&[
+            "label",
+            "create",
+            "--name",
+            name,
+            "--description",
+            description,
+            "--color",
+            &color,
+        ])?;
+        Ok(())
+    }
+
+    fn get_pull_request_comments(&self, number: i32) -> Result<Vec<GitHubComment>> {
+        let path = format!(
+            "projects/{}/merge_requests/{}/notes?sort=asc&per_page=100",
+            self.project_ref(),
+            number
+        );
+        Self::parse_notes(&self.api(&[&path])?)
+    }
+
+    fn get_review_threads(&self, number: i32) -> Result<Vec<ReviewThread>> {
+        let path = format!(
+            "projects/{}/merge_requests/{}/discussions?per_page=100",
+            self.project_ref(),
+            number
+        );
+        Self::parse_discussions(&self.api(&[&path])?)
+    }
+
+    fn add_pull_request_comment(&self, number: i32, body: &str) -> Result<()> {
+        let number_str = number.to_string();
+        self.run(&["mr", "note", &number_str, "--message", body])?;
+        Ok(())
+    }
+
+    fn update_pull_request_comment(&self, number: i32, comment_id: i64, body: &str) -> Result<()> {
+        let path = format!(
+            "projects/{}/merge_requests/{}/notes/{}",
+            self.project_ref(),
+            number,
+            comment_id
+        );
+        let body_field = format!("body={}", body);
+        self.api(&["-X", "PUT", &path, "-f", &body_field])?;
+        Ok(())
+    }
+
+    fn list_milestones(&self) -> Result<Vec<Milestone>> {
+        let path = format!("projects/{}/milestones?per_page=100", self.project_ref());
+        let json_str = self.api(&[&path])?;
+
+        let items: Vec<GitLabMilestoneJson> =
+            serde_json::from_str(&json_str).map_err(|e| GitHubError::ParseError {
+                message: format!("JSON parse error for milestones: {}", e),
+            })?;
+
+        // Issue counts aren't in the list payload; fetch them per milestone
+        items
+            .into_iter()
+            .map(|m| {
+                let issues_path = format!(
+                    "projects/{}/milestones/{}/issues?per_page=100",
+                    self.project_ref(),
+                    m.id
+                );
+                let issues: Vec<GitLabIssueJson> =
+                    serde_json::from_str(&self.api(&[&issues_path])?).map_err(|e| {
+                        GitHubError::ParseError {
+                            message: format!("JSON parse error for milestone issues: {}", e),
+                        }
+                    })?;
+                let closed = issues.iter().filter(|i| i.state == "closed").count() as i32;
+
+                Ok(Milestone {
+                    number: m.iid,
+                    title: m.title,
+                    state: normalize_milestone_state(&m.state),
+                    open_issues: issues.len() as i32 - closed,
+                    closed_issues: closed,
+                })
+            })
+            .collect()
+    }
+
+    fn create_milestone(&self, title: &str, description: &str) -> Result<Milestone> {
+        let path = format!("projects/{}/milestones", self.project_ref());
+        let title_field = format!("title={}", title);
+        let description_field = format!("description={}", description);
+        let json_str = self.api(&[
+            "-X",
+            "POST",
+            &path,
+            "-f",
+            &title_field,
+            "-f",
+            &description_field,
+        ])?;
+
+        let m: GitLabMilestoneJson =
+            serde_json::from_str(&json_str).map_err(|e| GitHubError::ParseError {
+                message: format!("JSON parse error for milestone: {}", e),
+            })?;
+
+        Ok(Milestone {
+            number: m.iid,
+            title: m.title,
+            state: normalize_milestone_state(&m.state),
+            open_issues: 0,
+            closed_issues: 0,
+        })
+    }
+
+    fn set_issue_milestone(&self, number: i32, milestone: &str) -> Result<()> {
+        let number_str = number.to_string();
+        self.run(&["issue", "update", &number_str, "--milestone", milestone])?;
+        Ok(())
+    }
+
+    fn list_pull_requests(&self, state: &str) -> Result<Vec<PullRequest>> {
+        let mut args = vec!["mr", "list", "--per-page", "100", "--output", "json"];
+        match state {
+            "closed" => args.push("--closed"),
+            "merged" => args.push("--merged"),
+            "all" => args.push("--all"),
+            _ => {} // open is the default
+        }
+
+        let json_str = self.run(&args)?;
+        if json_str.trim().is_empty() || json_str.trim() == "null" {
+            return Ok(vec![]);
+        }
+
+        let items: Vec<GitLabMergeRequestJson> =
+            serde_json::from_str(&json_str).map_err(|e| GitHubError::ParseError {
+                message: format!("JSON parse error for merge requests: {}", e),
+            })?;
+
+        Ok(items.into_iter().map(PullRequest::from).collect())
+    }
+
+    fn get_pull_request(&self, number: i32) -> Result<PullRequest> {
+        let number_str = number.to_string();
+        let json_str = self.run(&["mr", "view", &number_str, "--output", "json"])?;
+
+        let mr: GitLabMergeRequestJson =
+            serde_json::from_str(&json_str).map_err(|e| GitHubError::ParseError {
+                message: format!("JSON parse error for merge request: {}", e),
+            })?;
+
+        Ok(mr.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_creation() {
+        let client = GitLabClient::new(Some("group/project".to_string()));
+        assert_eq!(client.repo_name(), Some("group/project"));
+        assert_eq!(client.kind(), ForgeKind::GitLab);
+    }
+
+    #[test]
+    fn test_repo_args_and_project_ref() {
+        let client = GitLabClient::new(Some("group/sub/project".to_string()));
+        assert_eq!(client.repo_args(), vec!["-R", "group/sub/project"]);
+        assert_eq!(client.project_ref(), "group%2Fsub%2Fproject");
+
+        let client = GitLabClient::new(None);
+        assert!(client.repo_args().is_empty());
+        assert_eq!(client.project_ref(), ":id");
+    }
+
+    #[test]
+    fn test_parse_issue_number() {
+        let output = "Creating issue in group/project\n#42 Add feature (just now)\n https://gitlab.com/group/project/-/issues/42\n";
+        assert_eq!(parse_issue_number(output), Some(42));
+        assert_eq!(parse_issue_number("no url here"), None);
+    }
+
+    #[test]
+    fn test_issue_json_conversion() {
+        let json = r#"[{"iid": 7, "title": "Roadmap", "description": null, "state": "opened",
+            "web_url": "https://gitlab.com/g/p/-/issues/7",
+            "created_at": "2025-01-01T00:00:00Z", "updated_at": "2025-01-02T00:00:00Z"}]"#;
+        let issues = GitLabClient::parse_issues(json).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].number, 7);
+        assert_eq!(issues[0].state, "open");
+        assert_eq!(issues[0].body, "");
+    }
+
+    #[test]
+    fn test_normalize_milestone_state() {
+        assert_eq!(normalize_milestone_state("active"), "open");
+        assert_eq!(normalize_milestone_state("closed"), "closed");
+    }
+
+    #[test]
+    fn test_normalize_state() {
+        assert_eq!(normalize_state("opened"), "open");
+        assert_eq!(normalize_state("closed"), "closed");
+        assert_eq!(normalize_state("merged"), "merged");
+    }
+
+    #[test]
+    fn test_parse_discussions_keeps_diff_threads() {
+        let json = r#"[
+            {"id": "abc", "notes": [
+                {"id": 1, "body": "Use a constant here", "author": {"username": "bob"},
+                 "created_at": "2025-01-01", "resolvable": true, "resolved": true,
+                 "position": {"new_path": "src/lib.rs", "old_path": "src/lib.rs", "new_line": 7, "old_line": null}},
+                {"id": 2, "body": "Fixed", "author": {"username": "amy"},
+                 "created_at": "2025-01-02", "resolvable": true, "resolved": true, "position": null}
+            ]},
+            {"id": "def", "notes": [
+                {"id": 3, "body": "General note", "author": {"username": "bob"},
+                 "created_at": "2025-01-01"}
+            ]}
+        ]"#;
+        let threads = GitLabClient::parse_discussions(json).unwrap();
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].id, "abc");
+        assert_eq!(threads[0].path.as_deref(), Some("src/lib.rs"));
+        assert_eq!(threads[0].line, Some(7));
+        assert!(threads[0].resolved);
+        assert_eq!(threads[0].comments.len(), 2);
+    }
+}
@@ -0,0 +1,452 @@
+//! gRPC service for the graph and its traces
+//!
+//! `deciduous serve --grpc` serves `proto/deciduous.proto` for platform teams
+//! that talk gRPC between services rather than the web viewer's REST API.
+//! Nodes and edges go through the same `[rules]` checks as the MCP tools.
+//! Database calls run on tokio's blocking pool.
+
+// Every handler returns tonic's (large) Status
+#![allow(clippy::result_large_err)]
+
+use crate::config::Config;
+use crate::db::{Database, DbError, DecisionEdge, DecisionNode, TraceSession, TraceSpan};
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+/// Messages and service stubs generated from `proto/deciduous.proto`
+pub mod proto {
+    tonic::include_proto!("deciduous.v1");
+}
+
+use proto::deciduous_server::{Deciduous, DeciduousServer};
+
+impl From<DecisionNode> for proto::Node {
+    fn from(n: DecisionNode) -> Self {
+        Self {
+            id: n.id,
+            change_id: n.change_id,
+            node_type: n.node_type.into(),
+            title: n.title,
+            description: n.description,
+            status: n.status.into(),
+            created_at: n.created_at,
+            updated_at: n.updated_at,
+            metadata_json: n.metadata_json,
+        }
+    }
+}
+
+impl From<DecisionEdge> for proto::Edge {
+    fn from(e: DecisionEdge) -> Self {
+        Self {
+            id: e.id,
+            from_node_id: e.from_node_id,
+            to_node_id: e.to_node_id,
+            from_change_id: e.from_change_id,
+            to_change_id: e.to_change_id,
+            edge_type: e.edge_type.into(),
+            weight: e.weight,
+            rationale: e.rationale,
+            created_at: e.created_at,
+        }
+    }
+}
+
+impl From<TraceSession> for proto::TraceSession {
+    fn from(s: TraceSession) -> Self {
+        Self {
+            session_id: s.session_id,
+            started_at: s.started_at,
+            ended_at: s.ended_at,
+            working_dir: s.working_dir,
+            git_branch: s.git_branch,
+            command: s.command,
+            summary: s.summary,
+            total_input_tokens: s.total_input_tokens,
+            total_output_tokens: s.total_output_tokens,
+            total_cache_read: s.total_cache_read,
+            total_cache_write: s.total_cache_write,
+            linked_node_id: s.linked_node_id,
+        }
+    }
+}
+
+impl From<TraceSpan> for proto::TraceSpan {
+    fn from(s: TraceSpan) -> Self {
+        Self {
+            id: s.id,
+            change_id: s.change_id,
+            sequence_num: s.sequence_num,
+            started_at: s.started_at,
+            completed_at: s.completed_at,
+            duration_ms: s.duration_ms,
+            model: s.model,
+            stop_reason: s.stop_reason,
+            input_tokens: s.input_tokens,
+            output_tokens: s.output_tokens,
+            cache_read: s.cache_read,
+            cache_write: s.cache_write,
+            user_preview: s.user_preview,
+            response_preview: s.response_preview,
+            tool_names: s.tool_names,
+            linked_node_id: s.linked_node_id,
+        }
+    }
+}
+
+fn db_status(e: DbError) -> Status {
+    match e {
+        DbError::Validation(msg) => Status::invalid_argument(msg),
+        other => Status::internal(other.to_string()),
+    }
+}
+
+fn node_or_not_found(db: &Database, id: i32) -> Result<DecisionNode, Status> {
+    db.get_node_by_id(id)
+        .map_err(db_status)?
+        .ok_or_else(|| Status::not_found(format!("Node {} does not exist", id)))
+}
+
+/// The `Deciduous` service over one database
+pub struct GraphService {
+    db: Arc<Database>,
+}
+
+impl GraphService {
+    pub fn new(db: Database) -> Self {
+        Self { db: Arc::new(db) }
+    }
+
+    /// Run `f` against the database off the async executor
+    async fn blocking<T, F>(&self, f: F) -> Result<Response<T>, Status>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Database) -> Result<T, Status> + Send + 'static,
+    {
+        let db = Arc::clone(&self.db);
+        tokio::task::spawn_blocking(move || f(&db))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .map(Response::new)
+    }
+}
+
+#[tonic::async_trait]
+impl Deciduous for GraphService {
+    async fn list_nodes(
+        &self,
+        request: Request<proto::ListNodesRequest>,
+    ) -> Result<Response<proto::NodeList>, Status> {
+        let req = request.into_inner();
+        self.blocking(move |db| {
+            let limit = match req.limit {
+                0 => usize::MAX,
+                n => n as usize,
+            };
+            let nodes = db
+                .get_all_nodes()
+                .map_err(db_status)?
+                .into_iter()
+                .filter(|n| {
+                    req.node_type
+                        .as_ref()
+                        .map(|t| n.node_type == *t)
+                        .unwrap_or(true)
+                })
+                .filter(|n| req.status.as_ref().map(|s| n.status == *s).unwrap_or(true))
+                .filter(|n| {
+                    req.branch
+                        .as_ref()
+                        .map(|b| crate::mcp::node_branch(n).as_ref() == Some(b))
+                        .unwrap_or(true)
+                })
+                .take(limit)
+                .map(proto::Node::from)
+                .collect();
+            Ok(proto::NodeList { nodes })
+        })
+        .await
+    }
+
+    async fn get_node(
+        &self,
+        request: Request<proto::GetNodeRequest>,
+    ) -> Result<Response<proto::NodeContext>, Status> {
+        let id = request.into_inner().id;
+        self.blocking(move |db| {
+            let node = node_or_not_found(db, id)?;
+            let edges = db
+                .get_all_edges()
+                .map_err(db_status)?
+                .into_iter()
+                .filter(|e| e.from_node_id == id || e.to_node_id == id)
+                .map(proto::Edge::from)
+                .collect();
+            let nodes =
+                |found: Vec<DecisionNode>| found.into_iter().map(proto::Node::from).collect();
+            Ok(proto::NodeContext {
+                node: Some(node.into()),
+                parents: nodes(db.get_node_parents(id).map_err(db_status)?),
+                children: nodes(db.get_node_children(id).map_err(db_status)?),
+                edges,
+            })
+        })
+        .await
+    }
+
+    async fn create_node(
+        &self,
+        request: Request<proto::CreateNodeRequest>,
+    ) -> Result<Response<proto::Node>, Status> {
+        let req = request.into_inner();
+        self.blocking(move |db| {
+            let confidence = match req.confidence {
+                Some(c) if c > 100 => {
+                    return Err(Status::invalid_argument("confidence must be 0-100"))
+                }
+                c => c.map(|c| c as u8),
+            };
+            let rules = Config::load().rules;
+            let confidence = confidence.or_else(|| rules.default_confidence(&req.node_type));
+            let present: Vec<&str> = [
+                ("prompt", req.prompt.is_some()),
+                ("description", req.description.is_some()),
+                ("confidence", confidence.is_some()),
+                ("commit", req.commit.is_some()),
+                ("files", !req.files.is_empty()),
+                ("branch", req.branch.is_some()),
+            ]
+            .into_iter()
+            .filter_map(|(field, set)| set.then_some(field))
+            .collect();
+            rules
+                .check_node(&req.node_type, &present)
+                .map_err(Status::failed_precondition)?;
+
+            let mut node = db.node(&req.node_type, &req.title).files(&req.files);
+            if let Some(description) = req.description {
+                node = node.description(description);
+            }
+            if let Some(confidence) = confidence {
+                node = node.confidence(confidence);
+            }
+            if let Some(commit) = req.commit {
+                node = node.commit(commit);
+            }
+            if let Some(prompt) = req.prompt {
+                node = node.prompt(prompt);
+            }
+            if let Some(branch) = req.branch {
+                node = node.branch(branch);
+            }
+            let id = node.insert().map_err(db_status)?;
+            Ok(node_or_not_found(db, id)?.into())
+        })
+        .await
+    }
+
+    async fn set_node_status(
+        &self,
+        request: Request<proto::SetNodeStatusRequest>,
+    ) -> Result<Response<proto::Node>, Status> {
+        let req = request.into_inner();
+        self.blocking(move |db| {
+            node_or_not_found(db, req.id)?;
+            db.update_node_status(req.id, &req.status)
+                .map_err(db_status)?;
+            Ok(node_or_not_found(db, req.id)?.into())
+        })
+        .await
+    }
+
+    async fn delete_node(
+        &self,
+        request: Request<proto::DeleteNodeRequest>,
+    ) -> Result<Response<proto::DeleteNodeResponse>, Status> {
+        let id = request.into_inner().id;
+        self.blocking(move |db| {
+            let deleted = db.delete_node(id).map_err(db_status)?;
+            Ok(proto::DeleteNodeResponse { deleted })
+        })
+        .await
+    }
+
+    async fn list_edges(
+        &self,
+        request: Request<proto::ListEdgesRequest>,
+    ) -> Result<Response<proto::EdgeList>, Status> {
+        let node_id = request.into_inner().node_id;
+        self.blocking(move |db| {
+            let edges = db
+                .get_all_edges()
+                .map_err(db_status)?
+                .into_iter()
+                .filter(|e| {
+                    node_id
+                        .map(|id| e.from_node_id == id || e.to_node_id == id)
+                        .unwrap_or(true)
+                })
+                .map(proto::Edge::from)
+                .collect();
+            Ok(proto::EdgeList { edges })
+        })
+        .await
+    }
+
+    async fn create_edge(
+        &self,
+        request: Request<proto::CreateEdgeRequest>,
+    ) -> Result<Response<proto::Edge>, Status> {
+        let req = request.into_inner();
+        self.blocking(move |db| {
+            let edge_type = req.edge_type.as_deref().unwrap_or("leads_to");
+            let present: &[&str] = if req.rationale.is_some() {
+                &["rationale"]
+            } else {
+                &[]
+            };
+            Config::load()
+                .rules
+                .check_edge(edge_type, present)
+                .map_err(Status::failed_precondition)?;
+            let id = db
+                .create_edge(
+                    req.from_node_id,
+                    req.to_node_id,
+                    edge_type,
+                    req.rationale.as_deref(),
+                )
+                .map_err(db_status)?;
+            db.get_all_edges()
+                .map_err(db_status)?
+                .into_iter()
+                .find(|e| e.id == id)
+                .map(proto::Edge::from)
+                .ok_or_else(|| Status::internal(format!("Edge {} vanished", id)))
+        })
+        .await
+    }
+
+    async fn list_trace_sessions(
+        &self,
+        request: Request<proto::ListTraceSessionsRequest>,
+    ) -> Result<Response<proto::TraceSessionList>, Status> {
+        let limit = match request.into_inner().limit {
+            0 => 20,
+            n => i64::from(n),
+        };
+        self.blocking(move |db| {
+            let sessions = db
+                .get_trace_sessions(limit)
+                .map_err(db_status)?
+                .into_iter()
+                .map(proto::TraceSession::from)
+                .collect();
+            Ok(proto::TraceSessionList { sessions })
+        })
+        .await
+    }
+
+    async fn list_trace_spans(
+        &self,
+        request: Request<proto::ListTraceSpansRequest>,
+    ) -> Result<Response<proto::TraceSpanList>, Status> {
+        let session_id = request.into_inner().session_id;
+        self.blocking(move |db| {
+            let spans = db
+                .get_trace_spans(&session_id)
+                .map_err(db_status)?
+                .into_iter()
+                .map(proto::TraceSpan::from)
+                .collect();
+            Ok(proto::TraceSpanList { spans })
+        })
+        .await
+    }
+}
+
+/// Serve the gRPC API on `host:port` until the process is stopped
+pub fn start_grpc_server(host: &str, port: u16) -> Result<(), String> {
+    let addr = format!("{}:{}", host, port)
+        .parse()
+        .map_err(|e| format!("Bad address {}:{}: {}", host, port, e))?;
+    let db = Database::open().map_err(|e| e.to_string())?;
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        tonic::transport::Server::builder()
+            .add_service(DeciduousServer::new(GraphService::new(db)))
+            .serve(addr)
+            .await
+            .map_err(|e| e.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_grpc_service() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let service = GraphService::new(db);
+
+        let goal = service
+            .create_node(Request::new(proto::CreateNodeRequest {
+                node_type: "goal".to_string(),
+                title: "Talk gRPC".to_string(),
+                confidence: Some(90),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let action = service
+            .create_node(Request::new(proto::CreateNodeRequest {
+                node_type: "action".to_string(),
+                title: "Ship the proto".to_string(),
+                files: vec!["proto/deciduous.proto".to_string()],
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let edge = service
+            .create_edge(Request::new(proto::CreateEdgeRequest {
+                from_node_id: goal.id,
+                to_node_id: action.id,
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(edge.edge_type, "leads_to");
+
+        let context = service
+            .get_node(Request::new(proto::GetNodeRequest { id: action.id }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(context.parents[0].id, goal.id);
+        assert_eq!(context.edges.len(), 1);
+
+        let goals = service
+            .list_nodes(Request::new(proto::ListNodesRequest {
+                node_type: Some("goal".to_string()),
+                ..Default::default()
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(goals.nodes.len(), 1);
+
+        let missing = service
+            .set_node_status(Request::new(proto::SetNodeStatusRequest {
+                id: 999,
+                status: "completed".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), tonic::Code::NotFound);
+    }
+}
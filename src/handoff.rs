@@ -0,0 +1,255 @@
+//! Session handoff notes and the `deciduous context` summary
+//!
+//! `deciduous handoff write` ends a session with a structured note: open
+//! questions, next steps and gotchas. The note is an observation node
+//! holding them in `handoff` metadata, linked from the active goal (the
+//! focus, else the branch's open goal). `deciduous context` starts the next
+//! session with that goal, what is still open under it and its latest
+//! handoff.
+
+use crate::db::{Database, DecisionEdge, DecisionNode};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+
+/// An end-of-session note
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Handoff {
+    #[serde(default)]
+    pub open_questions: Vec<String>,
+    #[serde(default)]
+    pub next_steps: Vec<String>,
+    #[serde(default)]
+    pub gotchas: Vec<String>,
+}
+
+impl Handoff {
+    pub fn is_empty(&self) -> bool {
+        self.open_questions.is_empty() && self.next_steps.is_empty() && self.gotchas.is_empty()
+    }
+
+    /// The note's `handoff` metadata, if it is one
+    pub fn of(node: &DecisionNode) -> Option<Handoff> {
+        let meta: serde_json::Value = serde_json::from_str(node.metadata_json.as_deref()?).ok()?;
+        serde_json::from_value(meta.get("handoff")?.clone()).ok()
+    }
+
+    /// The note as markdown sections
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for (heading, items) in [
+            ("Open questions", &self.open_questions),
+            ("Next steps", &self.next_steps),
+            ("Gotchas", &self.gotchas),
+        ] {
+            if items.is_empty() {
+                continue;
+            }
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("{}:\n", heading));
+            for item in items {
+                out.push_str(&format!("- {}\n", item));
+            }
+        }
+        out
+    }
+}
+
+/// Store `handoff` as a note linked from `goal` (default: the active goal
+/// for `branch`); returns the note's ID and the goal's
+pub fn write(
+    db: &Database,
+    handoff: &Handoff,
+    goal: Option<i32>,
+    branch: Option<&str>,
+) -> Result<(i32, Option<i32>), String> {
+    if handoff.is_empty() {
+        return Err("A handoff needs an open question, next step or gotcha".to_string());
+    }
+    let goal = match goal {
+        Some(id) => Some(
+            db.get_node_by_id(id)
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Node {} not found", id))?
+                .id,
+        ),
+        None => {
+            let nodes = db.get_all_nodes().map_err(|e| e.to_string())?;
+            crate::focus::active_goal(db, &nodes, branch).map(|g| g.id)
+        }
+    };
+    let headline = handoff
+        .next_steps
+        .first()
+        .or(handoff.open_questions.first())
+        .or(handoff.gotchas.first())
+        .cloned()
+        .unwrap_or_default();
+    let mut node = db
+        .node("observation", &format!("Handoff: {}", headline))
+        .description(handoff.render());
+    if let Some(branch) = branch {
+        node = node.branch(branch);
+    }
+    let id = node.insert().map_err(|e| e.to_string())?;
+    let meta = serde_json::to_value(handoff).map_err(|e| e.to_string())?;
+    db.update_node_metadata_field(id, "handoff", meta)
+        .map_err(|e| e.to_string())?;
+    if let Some(goal) = goal {
+        db.edge(goal, id)
+            .rationale("Session handoff")
+            .insert()
+            .map_err(|e| e.to_string())?;
+    }
+    Ok((id, goal))
+}
+
+/// The newest handoff linked from `goal`, or the newest of all without one
+pub fn latest(
+    nodes: &[DecisionNode],
+    edges: &[DecisionEdge],
+    goal: Option<i32>,
+) -> Option<(DecisionNode, Handoff)> {
+    let linked: Option<HashSet<i32>> = goal.map(|goal| {
+        edges
+            .iter()
+            .filter(|e| e.from_node_id == goal)
+            .map(|e| e.to_node_id)
+            .collect()
+    });
+    nodes
+        .iter()
+        .filter(|n| linked.as_ref().map_or(true, |ids| ids.contains(&n.id)))
+        .filter_map(|n| Some((n, Handoff::of(n)?)))
+        .max_by(|a, b| (&a.0.created_at, a.0.id).cmp(&(&b.0.created_at, b.0.id)))
+        .map(|(n, h)| (n.clone(), h))
+}
+
+/// What a new session should know first
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionContext {
+    pub branch: Option<String>,
+    pub goal: Option<DecisionNode>,
+    /// Decisions and actions below the goal that are not completed or rejected
+    pub open: Vec<DecisionNode>,
+    pub handoff: Option<(DecisionNode, Handoff)>,
+}
+
+/// The context for a session on `branch` working toward `goal`
+pub fn session_context(
+    nodes: &[DecisionNode],
+    edges: &[DecisionEdge],
+    branch: Option<&str>,
+    goal: Option<DecisionNode>,
+) -> SessionContext {
+    let mut open = Vec::new();
+    if let Some(goal) = &goal {
+        let mut seen = HashSet::from([goal.id]);
+        let mut queue = VecDeque::from([goal.id]);
+        while let Some(id) = queue.pop_front() {
+            for edge in edges.iter().filter(|e| e.from_node_id == id) {
+                if seen.insert(edge.to_node_id) {
+                    queue.push_back(edge.to_node_id);
+                }
+            }
+        }
+        open = nodes
+            .iter()
+            .filter(|n| seen.contains(&n.id) && n.id != goal.id)
+            .filter(|n| n.node_type == "decision" || n.node_type == "action")
+            .filter(|n| n.status != "completed" && n.status != "rejected")
+            .cloned()
+            .collect();
+    }
+    SessionContext {
+        branch: branch.map(String::from),
+        handoff: latest(nodes, edges, goal.as_ref().map(|g| g.id)),
+        goal,
+        open,
+    }
+}
+
+/// The context as text
+pub fn render_context(context: &SessionContext) -> String {
+    let mut out = String::new();
+    if let Some(branch) = &context.branch {
+        out.push_str(&format!("Branch: {}\n", branch));
+    }
+    match &context.goal {
+        Some(goal) => out.push_str(&format!(
+            "Goal: #{} {} ({})\n",
+            goal.id, goal.title, goal.status
+        )),
+        None => out.push_str("Goal: none logged yet\n"),
+    }
+    if !context.open.is_empty() {
+        out.push_str("\nStill open:\n");
+        for node in &context.open {
+            out.push_str(&format!(
+                "- #{} [{}] {} ({})\n",
+                node.id, node.node_type, node.title, node.status
+            ));
+        }
+    }
+    if let Some((note, handoff)) = &context.handoff {
+        out.push_str(&format!(
+            "\nLast handoff (#{}, {}):\n",
+            note.id,
+            note.created_at.get(..16).unwrap_or(&note.created_at)
+        ));
+        out.push_str(&handoff.render());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handoff_and_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let goal = db
+            .node("goal", "Paged graph API")
+            .branch("feature/paging")
+            .insert()
+            .unwrap();
+        let action = db.node("action", "Add cursor").insert().unwrap();
+        let done = db.node("action", "Add limit").insert().unwrap();
+        db.edge(goal, action).insert().unwrap();
+        db.edge(goal, done).insert().unwrap();
+        db.update_node_status(done, "completed").unwrap();
+
+        assert!(write(&db, &Handoff::default(), None, None).is_err());
+        let handoff = Handoff {
+            open_questions: vec!["Cursor by id or created_at?".to_string()],
+            next_steps: vec!["Page edges too".to_string()],
+            gotchas: vec![],
+        };
+        let (note, linked) = write(&db, &handoff, None, Some("feature/paging")).unwrap();
+        assert_eq!(linked, Some(goal));
+        let node = db.get_node_by_id(note).unwrap().unwrap();
+        assert_eq!(node.title, "Handoff: Page edges too");
+        assert_eq!(Handoff::of(&node), Some(handoff.clone()));
+
+        let nodes = db.get_all_nodes().unwrap();
+        let edges = db.get_all_edges().unwrap();
+        let active = crate::focus::active_goal(&db, &nodes, Some("feature/paging"));
+        let context = session_context(&nodes, &edges, Some("feature/paging"), active);
+        assert_eq!(context.goal.as_ref().map(|g| g.id), Some(goal));
+        assert_eq!(
+            context.open.iter().map(|n| n.id).collect::<Vec<_>>(),
+            vec![action]
+        );
+        assert_eq!(context.handoff.as_ref().map(|(n, _)| n.id), Some(note));
+        let text = render_context(&context);
+        assert!(text.contains(&format!(
+            "Still open:\n- #{} [action] Add cursor (pending)\n",
+            action
+        )));
+        assert!(text.contains("Open questions:\n- Cursor by id or created_at?\n\nNext steps:\n"));
+        assert!(latest(&nodes, &edges, Some(action)).is_none());
+    }
+}
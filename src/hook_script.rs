@@ -0,0 +1,66 @@
+//! Blocks deciduous adds to git hook scripts
+//!
+//! `hooks install` and `roadmap hook install` each put their command between
+//! a pair of comment markers in a script under `.git/hooks/`, so it can be
+//! found again and taken out without touching the rest of the hook.
+
+/// One kind of marked block, e.g. the pre-push patch export
+#[derive(Debug, Clone, Copy)]
+pub struct HookBlock {
+    /// The hook the block goes in, e.g. "pre-push"
+    pub hook: &'static str,
+    /// What the block does, for messages, e.g. "the patch export"
+    pub what: &'static str,
+    /// Line opening the block
+    pub start: &'static str,
+    /// Line closing the block
+    pub end: &'static str,
+}
+
+impl HookBlock {
+    /// `snippet` between the markers
+    pub fn block(&self, snippet: &str) -> String {
+        format!("{}\n{}\n{}\n", self.start, snippet.trim_end(), self.end)
+    }
+
+    /// Add the block around `snippet` to a hook's content
+    ///
+    /// Returns `None` when it's already installed. An existing hook that
+    /// isn't ours is only extended when `append` is set.
+    pub fn add(
+        &self,
+        existing: Option<&str>,
+        snippet: &str,
+        append: bool,
+    ) -> Result<Option<String>, String> {
+        match existing {
+            None => Ok(Some(format!("#!/bin/sh\n{}", self.block(snippet)))),
+            Some(content) if content.contains(self.start) => Ok(None),
+            Some(content) if append => Ok(Some(format!(
+                "{}\n\n{}",
+                content.trim_end(),
+                self.block(snippet)
+            ))),
+            Some(_) => Err(format!(
+                "a {} hook already exists; use --append to add {} to it",
+                self.hook, self.what
+            )),
+        }
+    }
+
+    /// Remove the block from a hook's content
+    ///
+    /// Returns `None` when it isn't installed, and `Some("")` when nothing
+    /// but the shebang would be left.
+    pub fn remove(&self, content: &str) -> Option<String> {
+        let start = content.find(self.start)?;
+        let end = content[start..].find(self.end)? + start + self.end.len();
+        let remaining = format!("{}{}", &content[..start], &content[end..]);
+        let remaining = remaining.trim();
+        if remaining.is_empty() || remaining == "#!/bin/sh" {
+            Some(String::new())
+        } else {
+            Some(format!("{}\n", remaining))
+        }
+    }
+}
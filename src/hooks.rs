@@ -0,0 +1,236 @@
+//! Pre-push hook that keeps a branch's patch file up to date
+//!
+//! `deciduous hooks install` adds a block to `.git/hooks/pre-push` that runs
+//! `deciduous hooks pre-push`. That exports the current branch's nodes that
+//! no other patch in `.deciduous/patches/` carries yet to
+//! `<user>-<branch>.json` and stages it, which is the export step the
+//! agent templates otherwise ask for by hand.
+
+use crate::ci::load_patches;
+use crate::db::Database;
+use crate::diff::GraphPatch;
+use crate::hook_script::HookBlock;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+const PUSH_HOOK: HookBlock = HookBlock {
+    hook: "pre-push",
+    what: "the patch export",
+    start: "# >>> deciduous push hook >>>",
+    end: "# <<< deciduous push hook <<<",
+};
+
+/// The pre-push snippet that exports the branch's patch
+fn hook_snippet(strict: bool, sign: bool) -> String {
+    format!(
+        "deciduous hooks pre-push{}{} || exit 1",
+        if strict { " --strict" } else { "" },
+        if sign { " --sign" } else { "" },
+    )
+}
+
+/// Add the export to a pre-push hook's content
+///
+/// Returns `None` when it's already installed. An existing hook that isn't
+/// ours is only extended when `append` is set.
+pub fn add_hook_block(
+    existing: Option<&str>,
+    strict: bool,
+    sign: bool,
+    append: bool,
+) -> Result<Option<String>, String> {
+    PUSH_HOOK.add(existing, &hook_snippet(strict, sign), append)
+}
+
+/// Remove the export from a pre-push hook's content
+///
+/// Returns `None` when it isn't installed, and `Some("")` when nothing but
+/// the shebang would be left.
+pub fn remove_hook_block(content: &str) -> Option<String> {
+    PUSH_HOOK.remove(content)
+}
+
+pub(crate) fn slug(s: &str) -> String {
+    let slug: String = s
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    slug.trim_matches('-').to_string()
+}
+
+/// A user name fit for file and ref names (`unknown` when empty)
+pub(crate) fn user_slug(user: &str) -> String {
+    match slug(user) {
+        s if s.is_empty() => "unknown".to_string(),
+        s => s,
+    }
+}
+
+/// Patch file for a user's branch: `<dir>/<user>-<branch>.json`
+///
+/// Both parts are lowercased, with anything other than letters, digits,
+/// `.`, `_` and `-` (including the `/` in `feature/x`) turned into `-`.
+pub fn branch_patch_path(dir: &Path, user: &str, branch: &str) -> PathBuf {
+    dir.join(format!("{}-{}.json", user_slug(user), slug(branch)))
+}
+
+fn edge_key(e: &crate::diff::PatchEdge) -> (String, String, String) {
+    (
+        e.from_change_id.clone(),
+        e.to_change_id.clone(),
+        e.edge_type.clone(),
+    )
+}
+
+/// Build the branch's patch for `path`, leaving out what other patches carry
+///
+/// Nodes, edges, updates and deletions already in another `.json` file in
+/// `path`'s directory are dropped; the file at `path` itself is what gets
+/// replaced. Returns `None` when there's nothing to export or the result
+/// matches what `path` already holds.
+pub fn branch_patch(
+    db: &Database,
+    path: &Path,
+    branch: &str,
+    author: &str,
+    base_commit: Option<String>,
+) -> Result<Option<GraphPatch>, String> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let (others, _) = load_patches(dir);
+    let others: Vec<GraphPatch> = others
+        .into_iter()
+        .filter(|(p, _)| p.file_name() != path.file_name())
+        .map(|(_, patch)| patch)
+        .collect();
+
+    let nodes: HashSet<&str> = others
+        .iter()
+        .flat_map(|p| p.nodes.iter().map(|n| n.change_id.as_str()))
+        .collect();
+    let edges: HashSet<(String, String, String)> = others
+        .iter()
+        .flat_map(|p| p.edges.iter().map(edge_key))
+        .collect();
+    let updates: HashSet<(&str, &str)> = others
+        .iter()
+        .flat_map(|p| {
+            p.updated_nodes
+                .iter()
+                .map(|u| (u.change_id.as_str(), u.updated_at.as_str()))
+        })
+        .collect();
+    let tombstones: HashSet<&str> = others
+        .iter()
+        .flat_map(|p| p.tombstones.iter().map(|t| t.change_id.as_str()))
+        .collect();
+
+    let mut patch = db
+        .export_patch(None, Some(branch), Some(author.to_string()), base_commit)
+        .map_err(|e| e.to_string())?;
+    patch.branch = Some(branch.to_string());
+    patch
+        .nodes
+        .retain(|n| !nodes.contains(n.change_id.as_str()));
+    patch.edges.retain(|e| !edges.contains(&edge_key(e)));
+    patch
+        .updated_nodes
+        .retain(|u| !updates.contains(&(u.change_id.as_str(), u.updated_at.as_str())));
+    patch
+        .tombstones
+        .retain(|t| !tombstones.contains(t.change_id.as_str()));
+
+    if patch.is_empty() {
+        return Ok(None);
+    }
+
+    // Unchanged content keeps the old file, so pushes don't churn it
+    if GraphPatch::load(path).is_ok_and(|existing| existing.same_content(&patch)) {
+        return Ok(None);
+    }
+    Ok(Some(patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_block_roundtrip() {
+        let installed = add_hook_block(None, true, false, false).unwrap().unwrap();
+        assert!(installed.starts_with("#!/bin/sh\n"));
+        assert!(installed.contains("deciduous hooks pre-push --strict || exit 1"));
+        assert_eq!(
+            add_hook_block(Some(&installed), false, false, false),
+            Ok(None)
+        );
+        assert_eq!(remove_hook_block(&installed), Some(String::new()));
+
+        let theirs = "#!/bin/sh\ncargo test\n";
+        assert!(add_hook_block(Some(theirs), false, false, false).is_err());
+        let appended = add_hook_block(Some(theirs), false, true, true)
+            .unwrap()
+            .unwrap();
+        assert!(appended.contains("deciduous hooks pre-push --sign"));
+        assert_eq!(remove_hook_block(&appended).as_deref(), Some(theirs));
+        assert_eq!(remove_hook_block(theirs), None);
+    }
+
+    #[test]
+    fn test_branch_patch_path() {
+        let dir = Path::new(".deciduous/patches");
+        assert_eq!(
+            branch_patch_path(dir, "Alice Smith", "feature/auth"),
+            dir.join("alice-smith-feature-auth.json")
+        );
+        assert_eq!(
+            branch_patch_path(dir, "", "main"),
+            dir.join("unknown-main.json")
+        );
+    }
+
+    #[test]
+    fn test_branch_patch_skips_exported() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let node = |title: &str, branch: &str| {
+            db.create_node_full("goal", title, None, None, None, None, None, Some(branch))
+                .unwrap()
+        };
+        let old = node("Old", "feature-x");
+        let new = node("New", "feature-x");
+        node("Elsewhere", "main");
+        db.add_edge(old, new, "leads_to", None).unwrap();
+
+        let patches = dir.path().join("patches");
+        std::fs::create_dir_all(&patches).unwrap();
+        let mut earlier = GraphPatch::new(Some("bob".to_string()), None, None);
+        earlier.add_node(&db.get_node_by_id(old).unwrap().unwrap());
+        earlier.save(&patches.join("bob-feature-x.json")).unwrap();
+
+        let path = branch_patch_path(&patches, "alice", "feature-x");
+        let patch = branch_patch(&db, &path, "feature-x", "alice", None)
+            .unwrap()
+            .unwrap();
+        let titles: Vec<&str> = patch.nodes.iter().map(|n| n.title.as_str()).collect();
+        assert_eq!(titles, vec!["New"]);
+        assert_eq!(patch.edges.len(), 1);
+        assert_eq!(patch.branch.as_deref(), Some("feature-x"));
+
+        // Same content as the saved file: nothing to do
+        patch.save(&path).unwrap();
+        assert!(branch_patch(&db, &path, "feature-x", "alice", None)
+            .unwrap()
+            .is_none());
+        assert!(branch_patch(&db, &path, "no-such-branch", "alice", None)
+            .unwrap()
+            .is_none());
+    }
+}
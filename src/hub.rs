@@ -0,0 +1,191 @@
+//! Central patch hub over HTTP
+//!
+//! `deciduous serve --sync` accepts patches at `POST /api/sync/patches`,
+//! applying them to the server's own database, and hands the merged graph
+//! back out as a patch at `GET /api/sync/patch`. `deciduous push` and
+//! `deciduous pull` are the clients. Everything merges by change_id, so
+//! pushing or pulling the same nodes twice is harmless.
+
+use crate::config::{Config, SyncConfig};
+use crate::db::Database;
+use crate::diff::{ApplyResult, ConflictStrategy, GraphPatch};
+use crate::remote_patch::{curl_header, curl_request};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Environment variable that overrides `sync.token` in config.toml
+pub const SYNC_TOKEN_ENV: &str = "DECIDUOUS_SYNC_TOKEN";
+
+/// Where clients POST patches
+pub const PUSH_PATH: &str = "/api/sync/patches";
+
+/// Where clients GET the hub's graph
+pub const PULL_PATH: &str = "/api/sync/patch";
+
+/// Resolve the sync token: environment first, then config.toml
+pub fn sync_token(config: &Config) -> Option<String> {
+    std::env::var(SYNC_TOKEN_ENV)
+        .ok()
+        .or_else(|| config.sync.token.clone())
+        .filter(|s| !s.is_empty())
+}
+
+/// Whether a request's `Authorization` header carries the token
+///
+/// A hub without a token is open by design, like the viewer it runs in:
+/// fine on localhost or a trusted network, and `serve --sync` says so at
+/// startup. Set a token before binding to anything wider.
+pub fn authorized(token: Option<&str>, header: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+    let given = header
+        .and_then(|h| h.trim().strip_prefix("Bearer "))
+        .unwrap_or("")
+        .trim();
+    crate::webhook::constant_time_eq(given.as_bytes(), token.as_bytes())
+}
+
+/// The hub to talk to: the argument, else `sync.server`
+pub fn server_url(arg: Option<String>, config: &Config) -> Result<String, String> {
+    arg.or_else(|| config.sync.server.clone())
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| s.trim().trim_end_matches('/').to_string())
+        .ok_or_else(|| "No sync server given; pass a URL or set [sync] server".to_string())
+}
+
+/// Apply a pushed patch to the hub's database
+///
+/// Errors carry the HTTP status to answer with: 400 for a malformed patch,
+/// 403 for one whose signature isn't acceptable.
+pub fn receive_patch(
+    db: &Database,
+    body: &[u8],
+    sync: &SyncConfig,
+) -> Result<ApplyResult, (u16, String)> {
+    let patch: GraphPatch = serde_json::from_slice(body)
+        .map_err(|e| (400, format!("Failed to parse patch JSON: {}", e)))?;
+    let provenance = crate::signing::verify(&patch, sync);
+    if !provenance.acceptable(sync) {
+        return Err((403, format!("Refusing patch: {}", provenance.describe())));
+    }
+    db.apply_patch(&patch, false, ConflictStrategy::Record)
+        .map_err(|e| (500, format!("Database error: {}", e)))
+}
+
+/// The hub's whole graph as a patch
+pub fn hub_patch(db: &Database) -> Result<GraphPatch, String> {
+    db.export_patch(None, None, None, None)
+        .map_err(|e| format!("Database error: {}", e))
+}
+
+fn request(
+    method: &str,
+    url: &str,
+    body: Option<&[u8]>,
+    token: Option<&str>,
+) -> Result<Value, String> {
+    let mut config = curl_header("Content-Type: application/json");
+    if let Some(token) = token {
+        config.push_str(&curl_header(&format!("Authorization: Bearer {}", token)));
+    }
+    let (status, body) = curl_request(method, url, &config, body).map_err(|e| e.to_string())?;
+    let response: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+    match status {
+        200..=299 => Ok(response["data"].clone()),
+        0 => Err(format!("no response from {}", url)),
+        code => Err(format!(
+            "{} returned HTTP {}: {}",
+            url,
+            code,
+            response["error"].as_str().unwrap_or(body.trim())
+        )),
+    }
+}
+
+fn parse_data<T: DeserializeOwned>(data: Value) -> Result<T, String> {
+    serde_json::from_value(data).map_err(|e| format!("Unexpected response: {}", e))
+}
+
+/// Send a patch to the hub; returns what it applied
+pub fn push_patch(
+    server: &str,
+    patch: &GraphPatch,
+    token: Option<&str>,
+) -> Result<ApplyResult, String> {
+    let body =
+        serde_json::to_vec(patch).map_err(|e| format!("Failed to serialize patch: {}", e))?;
+    let url = format!("{}{}", server, PUSH_PATH);
+    parse_data(request("POST", &url, Some(&body), token)?)
+}
+
+/// Fetch the hub's graph as a patch
+pub fn pull_patch(server: &str, token: Option<&str>) -> Result<GraphPatch, String> {
+    let url = format!("{}{}", server, PULL_PATH);
+    parse_data(request("GET", &url, None, token)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authorized() {
+        // No token: the hub is open
+        assert!(authorized(None, None));
+        assert!(authorized(None, Some("Bearer anything")));
+        assert!(authorized(Some("s3cret"), Some("Bearer s3cret")));
+        assert!(!authorized(Some("s3cret"), Some("Bearer s3cre")));
+        assert!(!authorized(Some("s3cret"), Some("s3cret")));
+        assert!(!authorized(Some("s3cret"), None));
+    }
+
+    #[test]
+    fn test_server_url() {
+        let mut config = Config::default();
+        assert!(server_url(None, &config).is_err());
+        config.sync.server = Some("http://hub:3000/".to_string());
+        assert_eq!(server_url(None, &config).unwrap(), "http://hub:3000");
+        assert_eq!(
+            server_url(Some("http://other".to_string()), &config).unwrap(),
+            "http://other"
+        );
+    }
+
+    #[test]
+    fn test_receive_patch() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = Database::new(dir.path().join("a.db").to_str().unwrap()).unwrap();
+        let hub = Database::new(dir.path().join("hub.db").to_str().unwrap()).unwrap();
+        let goal = source
+            .create_node("goal", "Goal", None, None, None)
+            .unwrap();
+        let action = source
+            .create_node("action", "Action", None, None, None)
+            .unwrap();
+        source.add_edge(goal, action, "leads_to", None).unwrap();
+
+        let patch = source.export_patch(None, None, None, None).unwrap();
+        let body = serde_json::to_vec(&patch).unwrap();
+        let sync = SyncConfig::default();
+        let result = receive_patch(&hub, &body, &sync).unwrap();
+        assert_eq!((result.nodes_added, result.edges_added), (2, 1));
+        let again = receive_patch(&hub, &body, &sync).unwrap();
+        assert_eq!((again.nodes_added, again.nodes_skipped), (0, 2));
+        assert_eq!(hub_patch(&hub).unwrap().nodes.len(), 2);
+
+        assert_eq!(receive_patch(&hub, b"not json", &sync).unwrap_err().0, 400);
+
+        // Tampered after signing
+        #[cfg(feature = "signing")]
+        {
+            let mut patch = patch;
+            let key =
+                crate::signing::SigningKey::load_or_create(&dir.path().join("key.pk8")).unwrap();
+            key.sign(&mut patch).unwrap();
+            patch.nodes[0].title = "Changed".to_string();
+            let body = serde_json::to_vec(&patch).unwrap();
+            assert_eq!(receive_patch(&hub, &body, &sync).unwrap_err().0, 403);
+        }
+    }
+}
@@ -1,8 +1,10 @@
 //! Project initialization for deciduous
 //!
 //! `deciduous init` creates all the files needed for decision graph tracking
-//! Supports multiple editors: Claude Code (--claude) and Windsurf (--windsurf)
+//! Supports multiple editors: Claude Code (--claude), Windsurf (--windsurf),
+//! OpenCode (--opencode), Codex (--codex), Zed (--zed) and Gemini CLI (--gemini)
 
+use crate::managed_section;
 use colored::Colorize;
 use std::fs;
 use std::path::Path;
@@ -14,6 +16,79 @@ pub enum Editor {
     Windsurf,
     Opencode,
     Codex,
+    Zed,
+    Gemini,
+}
+
+impl Editor {
+    /// The editor's name, for output
+    pub fn name(&self) -> &'static str {
+        match self {
+            Editor::Claude => "Claude Code",
+            Editor::Windsurf => "Windsurf",
+            Editor::Opencode => "OpenCode",
+            Editor::Codex => "Codex",
+            Editor::Zed => "Zed",
+            Editor::Gemini => "Gemini CLI",
+        }
+    }
+}
+
+/// "Claude Code", "Claude Code and Codex", "Claude Code, OpenCode and Codex"
+fn editor_names(editors: &[Editor]) -> String {
+    let names: Vec<&str> = editors.iter().map(Editor::name).collect();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        _ => names.concat(),
+    }
+}
+
+/// Whether init/update write files or only report what they would write
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Preview {
+    /// Write the files
+    #[default]
+    Off,
+    /// List what would be created or changed, writing nothing (`--dry-run`)
+    DryRun,
+    /// As `DryRun`, with a unified diff for each existing file that would change (`--diff`)
+    Diff,
+}
+
+/// Which parts of the scaffolding `init` writes
+///
+/// The `.deciduous/` directory, database and editor commands are always
+/// created; the rest can be left out with `--no-workflows`, `--no-pages` or
+/// `--commands-only`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Components {
+    /// The workflow section in CLAUDE.md, AGENTS.md, GEMINI.md or `.rules`
+    pub instructions: bool,
+    /// GitHub Actions workflows under `.github/workflows/`
+    pub workflows: bool,
+    /// The Pages viewer in `docs/` and the workflow that deploys it
+    pub pages: bool,
+}
+
+impl Default for Components {
+    fn default() -> Self {
+        Components {
+            instructions: true,
+            workflows: true,
+            pages: true,
+        }
+    }
+}
+
+impl Components {
+    /// Only the editor commands and rules (`--commands-only`)
+    pub fn commands_only() -> Self {
+        Components {
+            instructions: false,
+            workflows: false,
+            pages: false,
+        }
+    }
 }
 
 /// Static HTML viewer for GitHub Pages (embedded at compile time)
@@ -31,6 +106,18 @@ main_branches = ["main", "master"]
 # Automatically detect and store git branch when creating nodes
 # Set to false to disable branch tracking entirely
 auto_detect = true
+
+# Conventions enforced by `deciduous add` and `deciduous link` (uncomment to use)
+# [rules.confidence]        # used when -c is omitted
+# goal = 90
+# action = 85
+# outcome = 90
+#
+# [rules.require]           # prompt, description, confidence, commit, files, branch, pr
+# goal = ["prompt"]
+#
+# [rules.edge_require]      # rationale
+# rejected = ["rationale"]
 "#;
 
 /// GitHub Pages deploy workflow (deploys to gh-pages branch, safe for project repos)
@@ -311,6 +398,7 @@ deciduous diff apply --dry-run .deciduous/patches/bob-refactor.json
 ### PR Workflow
 1. Create nodes locally while working
 2. Export: `deciduous diff export --branch my-feature -o .deciduous/patches/my-feature.json`
+   (or once per clone, `deciduous hooks install` exports and stages it on every push)
 3. Commit the patch file (NOT the database)
 4. Open PR with patch file included
 5. Teammates pull and apply: `deciduous diff apply .deciduous/patches/my-feature.json`
@@ -319,7 +407,7 @@ deciduous diff apply --dry-run .deciduous/patches/bob-refactor.json
 ### Patch Format (JSON)
 ```json
 {
-  "version": "1.0",
+  "version": "2.0",
   "author": "alice",
   "branch": "feature/auth",
   "nodes": [{ "change_id": "uuid...", "title": "...", ... }],
@@ -525,6 +613,9 @@ deciduous diff export --branch $(git rev-parse --abbrev-ref HEAD) \
 git add .deciduous/patches/
 ```
 
+If `deciduous hooks install` has been run, the pre-push hook does the export
+and `git add` for you; just commit the staged patch.
+
 ## Why This Matters
 
 - Context loss during compaction loses your reasoning
@@ -1742,6 +1833,9 @@ deciduous diff export --branch $(git rev-parse --abbrev-ref HEAD) \
 git add .deciduous/patches/
 ```
 
+If `deciduous hooks install` has been run, the pre-push hook does the export
+and `git add` for you; just commit the staged patch.
+
 ## API Trace Capture
 
 When running through `deciduous proxy`, decisions auto-link to API spans:
@@ -1869,6 +1963,83 @@ Export the current decision graph to docs/graph-data.json so it's deployed to Gi
 This should be run before any push to main to ensure the live site has the latest decisions.
 "#;
 
+// ============================================================================
+// SHARED COMMAND TEMPLATES (Zed, Gemini CLI)
+// ============================================================================
+
+/// Commands for editors without their own templates, mapped from the OpenCode
+/// commands by name
+const SHARED_COMMANDS: &[(&str, &str)] = &[
+    ("decision", OPENCODE_DECISION_CMD),
+    ("recover", OPENCODE_RECOVER_CMD),
+    ("build-test", OPENCODE_BUILD_TEST_CMD),
+    ("serve-ui", OPENCODE_SERVE_UI_CMD),
+    ("sync-graph", OPENCODE_SYNC_GRAPH_CMD),
+];
+
+/// Split a command template into its frontmatter description and body
+fn split_frontmatter(template: &str) -> (Option<&str>, &str) {
+    let Some((frontmatter, body)) = template
+        .strip_prefix("---\n")
+        .and_then(|rest| rest.split_once("\n---\n"))
+    else {
+        return (None, template);
+    };
+    let description = frontmatter
+        .lines()
+        .find_map(|line| line.strip_prefix("description:"))
+        .map(|d| d.trim().trim_matches('"'));
+    (description, body.trim_start())
+}
+
+/// A template body with each `$ARGUMENTS` replaced; lines holding nothing
+/// else are dropped unless `keep_bare` is set
+fn map_arguments(body: &str, replacement: &str, keep_bare: bool) -> String {
+    let mut mapped = String::new();
+    for line in body.lines() {
+        if line.trim() == "$ARGUMENTS" && !keep_bare {
+            continue;
+        }
+        mapped.push_str(&line.replace("$ARGUMENTS", replacement));
+        mapped.push('\n');
+    }
+    format!("{}\n", mapped.trim_end())
+}
+
+// ============================================================================
+// ZED-SPECIFIC TEMPLATES
+// ============================================================================
+
+/// A shared command as a plain Zed prompt file (.zed/prompts/<name>.md)
+///
+/// Zed has no project-level slash commands; its agent reads `.rules` on every
+/// thread, and these are pulled in on demand with `/file .zed/prompts/<name>.md`
+/// (or added to the Rules Library and inserted with `/prompt`).
+fn zed_prompt(template: &str) -> String {
+    let (_, body) = split_frontmatter(template);
+    map_arguments(body, "the user's request", false)
+}
+
+// ============================================================================
+// GEMINI-SPECIFIC TEMPLATES
+// ============================================================================
+
+/// A shared command as a Gemini CLI custom command
+/// (.gemini/commands/deciduous/<name>.toml, invoked as `/deciduous:<name>`)
+///
+/// Gemini substitutes `{{args}}` with whatever follows the command.
+fn gemini_command(template: &str) -> String {
+    let (description, body) = split_frontmatter(template);
+    let description = description
+        .map(|d| d.split(" Usage:").next().unwrap_or(d))
+        .unwrap_or_default();
+    format!(
+        "description = {}\nprompt = '''\n{}'''\n",
+        toml::Value::String(description.to_string()),
+        map_arguments(body, "{{args}}", true)
+    )
+}
+
 // ============================================================================
 // CODEX-SPECIFIC TEMPLATES
 // ============================================================================
@@ -2178,6 +2349,9 @@ deciduous diff export --branch $(git rev-parse --abbrev-ref HEAD) \
 git add .deciduous/patches/
 ```
 
+If `deciduous hooks install` has been run, the pre-push hook does the export
+and `git add` for you; just commit the staged patch.
+
 ## API Trace Capture
 
 When running through `deciduous proxy`, decisions auto-link to API spans:
@@ -2308,17 +2482,20 @@ Export the current decision graph to docs/graph-data.json so it's deployed to Gi
 This should be run before any push to main to ensure the live site has the latest decisions.
 "#;
 
-/// Initialize deciduous in the current directory
-pub fn init_project(editor: Editor, force: bool) -> Result<(), String> {
+/// Initialize deciduous in the current directory for one or more editors
+///
+/// Shared files (`.deciduous/`, workflows, `docs/`) are written once; each
+/// editor gets its own commands. Editors sharing AGENTS.md get one section.
+pub fn init_project(
+    editors: &[Editor],
+    force: bool,
+    preview: Preview,
+    components: Components,
+) -> Result<(), String> {
     let cwd =
         std::env::current_dir().map_err(|e| format!("Could not get current directory: {}", e))?;
 
-    let editor_name = match editor {
-        Editor::Claude => "Claude Code",
-        Editor::Windsurf => "Windsurf",
-        Editor::Opencode => "OpenCode",
-        Editor::Codex => "Codex",
-    };
+    let editor_name = editor_names(editors);
 
     println!(
         "\n{}",
@@ -2327,25 +2504,41 @@ pub fn init_project(editor: Editor, force: bool) -> Result<(), String> {
             .bold()
     );
     println!("   Directory: {}", cwd.display());
-    if force {
-        println!(
+    let before = crate::manifest::Snapshot::take(&cwd);
+    match (force, preview != Preview::Off) {
+        (true, true) => println!(
+            "   Mode: {}, {} (nothing is written)\n",
+            "force".yellow(),
+            "dry run".cyan()
+        ),
+        (true, false) => println!(
             "   Mode: {} (overwriting existing files)\n",
             "force".yellow()
-        );
-    } else {
-        println!();
+        ),
+        (false, true) => println!("   Mode: {} (nothing is written)\n", "dry run".cyan()),
+        (false, false) => println!(),
     }
 
     // 1. Create .deciduous directory (shared between all editors)
     let deciduous_dir = cwd.join(".deciduous");
-    create_dir_if_missing(&deciduous_dir)?;
+    create_dir_if_missing(&deciduous_dir, preview)?;
 
     // 1b. Create default config.toml if it doesn't exist (or overwrite with force)
     let config_path = deciduous_dir.join("config.toml");
     if force {
-        write_file_overwrite(&config_path, DEFAULT_CONFIG, ".deciduous/config.toml")?;
+        write_file_overwrite(
+            &config_path,
+            DEFAULT_CONFIG,
+            ".deciduous/config.toml",
+            preview,
+        )?;
     } else {
-        write_file_if_missing(&config_path, DEFAULT_CONFIG, ".deciduous/config.toml")?;
+        write_file_if_missing(
+            &config_path,
+            DEFAULT_CONFIG,
+            ".deciduous/config.toml",
+            preview,
+        )?;
     }
 
     // 2. Initialize database by opening it (creates tables)
@@ -2355,283 +2548,422 @@ pub fn init_project(editor: Editor, force: bool) -> Result<(), String> {
             "   {} .deciduous/deciduous.db (already exists, preserving data)",
             "Skipping".yellow()
         );
+    } else if preview != Preview::Off {
+        println!("   {} .deciduous/deciduous.db", "Would create".green());
     } else {
         println!("   {} .deciduous/deciduous.db", "Creating".green());
     }
 
     // Set the env var so Database::open() uses this path
     // Database::open() uses CREATE TABLE IF NOT EXISTS - safe for existing DBs
-    std::env::set_var("DECIDUOUS_DB_PATH", &db_path);
+    if preview == Preview::Off {
+        std::env::set_var("DECIDUOUS_DB_PATH", &db_path);
+    }
 
     // 3. Create editor-specific configuration
-    match editor {
-        Editor::Claude => {
-            // Create .claude/commands directory
-            let claude_dir = cwd.join(".claude").join("commands");
-            create_dir_if_missing(&claude_dir)?;
-
-            // Write deciduous.decision.md slash command
-            let decision_path = claude_dir.join("deciduous.decision.md");
-            if force {
-                write_file_overwrite(
-                    &decision_path,
-                    DECISION_MD,
-                    ".claude/commands/deciduous.decision.md",
-                )?;
-            } else {
-                write_file_if_missing(
-                    &decision_path,
-                    DECISION_MD,
-                    ".claude/commands/deciduous.decision.md",
-                )?;
-            }
-
-            // Write deciduous.recover.md slash command (context recovery)
-            let recover_path = claude_dir.join("deciduous.recover.md");
-            if force {
-                write_file_overwrite(
-                    &recover_path,
-                    RECOVER_MD,
-                    ".claude/commands/deciduous.recover.md",
-                )?;
-            } else {
-                write_file_if_missing(
-                    &recover_path,
-                    RECOVER_MD,
-                    ".claude/commands/deciduous.recover.md",
-                )?;
-            }
+    for &editor in editors {
+        match editor {
+            Editor::Claude => {
+                // Create .claude/commands directory
+                let claude_dir = cwd.join(".claude").join("commands");
+                create_dir_if_missing(&claude_dir, preview)?;
+
+                // Write deciduous.decision.md slash command
+                let decision_path = claude_dir.join("deciduous.decision.md");
+                if force {
+                    write_file_overwrite(
+                        &decision_path,
+                        DECISION_MD,
+                        ".claude/commands/deciduous.decision.md",
+                        preview,
+                    )?;
+                } else {
+                    write_file_if_missing(
+                        &decision_path,
+                        DECISION_MD,
+                        ".claude/commands/deciduous.decision.md",
+                        preview,
+                    )?;
+                }
 
-            // Write agents.toml for subagent configuration
-            let claude_base = cwd.join(".claude");
-            let agents_path = claude_base.join("agents.toml");
-            if force {
-                write_file_overwrite(&agents_path, CLAUDE_AGENTS_TOML, ".claude/agents.toml")?;
-            } else {
-                write_file_if_missing(&agents_path, CLAUDE_AGENTS_TOML, ".claude/agents.toml")?;
-            }
+                // Write deciduous.recover.md slash command (context recovery)
+                let recover_path = claude_dir.join("deciduous.recover.md");
+                if force {
+                    write_file_overwrite(
+                        &recover_path,
+                        RECOVER_MD,
+                        ".claude/commands/deciduous.recover.md",
+                        preview,
+                    )?;
+                } else {
+                    write_file_if_missing(
+                        &recover_path,
+                        RECOVER_MD,
+                        ".claude/commands/deciduous.recover.md",
+                        preview,
+                    )?;
+                }
 
-            // Handle CLAUDE.md - append if missing, replace section if force
-            let claude_md_path = cwd.join("CLAUDE.md");
-            if force {
-                write_file_overwrite(&claude_md_path, CLAUDE_MD_SECTION, "CLAUDE.md")?;
-            } else {
-                append_config_md(&claude_md_path, CLAUDE_MD_SECTION, "CLAUDE.md")?;
-            }
+                // Write agents.toml for subagent configuration
+                let claude_base = cwd.join(".claude");
+                let agents_path = claude_base.join("agents.toml");
+                if force {
+                    write_file_overwrite(
+                        &agents_path,
+                        CLAUDE_AGENTS_TOML,
+                        ".claude/agents.toml",
+                        preview,
+                    )?;
+                } else {
+                    write_file_if_missing(
+                        &agents_path,
+                        CLAUDE_AGENTS_TOML,
+                        ".claude/agents.toml",
+                        preview,
+                    )?;
+                }
 
-            // Create .claude/skills/deciduous directory and SKILL.md
-            let skills_dir = cwd.join(".claude").join("skills").join("deciduous");
-            create_dir_if_missing(&skills_dir)?;
+                if components.instructions {
+                    // Handle CLAUDE.md - append if missing, update the managed section if force
+                    let claude_md_path = cwd.join("CLAUDE.md");
+                    if force {
+                        replace_config_md_section(
+                            &claude_md_path,
+                            CLAUDE_MD_SECTION,
+                            "CLAUDE.md",
+                            preview,
+                        )?;
+                    } else {
+                        append_config_md(&claude_md_path, CLAUDE_MD_SECTION, "CLAUDE.md", preview)?;
+                    }
+                }
 
-            let skill_path = skills_dir.join("SKILL.md");
-            if force {
-                write_file_overwrite(
-                    &skill_path,
-                    DECIDUOUS_SKILL,
-                    ".claude/skills/deciduous/SKILL.md",
-                )?;
-            } else {
-                write_file_if_missing(
-                    &skill_path,
-                    DECIDUOUS_SKILL,
-                    ".claude/skills/deciduous/SKILL.md",
-                )?;
-            }
-        }
-        Editor::Windsurf => {
-            // Create .windsurf directory
-            let windsurf_base = cwd.join(".windsurf");
-            create_dir_if_missing(&windsurf_base)?;
-
-            // Create .windsurf/rules directory
-            let windsurf_rules = windsurf_base.join("rules");
-            create_dir_if_missing(&windsurf_rules)?;
-
-            // Write deciduous.md rule (Always On - main workflow)
-            let deciduous_rule_path = windsurf_rules.join("deciduous.md");
-            if force {
-                write_file_overwrite(
-                    &deciduous_rule_path,
-                    WINDSURF_DECIDUOUS_RULE,
-                    ".windsurf/rules/deciduous.md",
-                )?;
-            } else {
-                write_file_if_missing(
-                    &deciduous_rule_path,
-                    WINDSURF_DECIDUOUS_RULE,
-                    ".windsurf/rules/deciduous.md",
-                )?;
-            }
+                // Create .claude/skills/deciduous directory and SKILL.md
+                let skills_dir = cwd.join(".claude").join("skills").join("deciduous");
+                create_dir_if_missing(&skills_dir, preview)?;
 
-            // Write recover.md rule (Model-triggered - for session recovery)
-            let recover_path = windsurf_rules.join("recover.md");
-            if force {
-                write_file_overwrite(
-                    &recover_path,
-                    WINDSURF_RECOVER_RULE,
-                    ".windsurf/rules/recover.md",
-                )?;
-            } else {
-                write_file_if_missing(
-                    &recover_path,
-                    WINDSURF_RECOVER_RULE,
-                    ".windsurf/rules/recover.md",
-                )?;
+                let skill_path = skills_dir.join("SKILL.md");
+                if force {
+                    write_file_overwrite(
+                        &skill_path,
+                        DECIDUOUS_SKILL,
+                        ".claude/skills/deciduous/SKILL.md",
+                        preview,
+                    )?;
+                } else {
+                    write_file_if_missing(
+                        &skill_path,
+                        DECIDUOUS_SKILL,
+                        ".claude/skills/deciduous/SKILL.md",
+                        preview,
+                    )?;
+                }
             }
+            Editor::Windsurf => {
+                // Create .windsurf directory
+                let windsurf_base = cwd.join(".windsurf");
+                create_dir_if_missing(&windsurf_base, preview)?;
 
-            // Write memories.md (project-level memories Cascade auto-retrieves)
-            let memories_path = windsurf_base.join("memories.md");
-            if force {
-                write_file_overwrite(&memories_path, WINDSURF_MEMORIES, ".windsurf/memories.md")?;
-            } else {
-                write_file_if_missing(&memories_path, WINDSURF_MEMORIES, ".windsurf/memories.md")?;
-            }
+                // Create .windsurf/rules directory
+                let windsurf_rules = windsurf_base.join("rules");
+                create_dir_if_missing(&windsurf_rules, preview)?;
 
-            // Handle AGENTS.md - append if missing, overwrite if force
-            let agents_md_path = cwd.join("AGENTS.md");
-            if force {
-                write_file_overwrite(&agents_md_path, AGENTS_MD_SECTION, "AGENTS.md")?;
-            } else {
-                append_config_md(&agents_md_path, AGENTS_MD_SECTION, "AGENTS.md")?;
-            }
-        }
-        Editor::Opencode => {
-            // Create .opencode/command directory (note: singular "command", not "commands")
-            let opencode_cmd_dir = cwd.join(".opencode").join("command");
-            create_dir_if_missing(&opencode_cmd_dir)?;
+                // Write deciduous.md rule (Always On - main workflow)
+                let deciduous_rule_path = windsurf_rules.join("deciduous.md");
+                if force {
+                    write_file_overwrite(
+                        &deciduous_rule_path,
+                        WINDSURF_DECIDUOUS_RULE,
+                        ".windsurf/rules/deciduous.md",
+                        preview,
+                    )?;
+                } else {
+                    write_file_if_missing(
+                        &deciduous_rule_path,
+                        WINDSURF_DECIDUOUS_RULE,
+                        ".windsurf/rules/deciduous.md",
+                        preview,
+                    )?;
+                }
 
-            // Helper to write file with force support
-            let write_cmd = |path: &Path, content: &str, name: &str| -> Result<(), String> {
+                // Write recover.md rule (Model-triggered - for session recovery)
+                let recover_path = windsurf_rules.join("recover.md");
                 if force {
-                    write_file_overwrite(path, content, name)
+                    write_file_overwrite(
+                        &recover_path,
+                        WINDSURF_RECOVER_RULE,
+                        ".windsurf/rules/recover.md",
+                        preview,
+                    )?;
                 } else {
-                    write_file_if_missing(path, content, name)
+                    write_file_if_missing(
+                        &recover_path,
+                        WINDSURF_RECOVER_RULE,
+                        ".windsurf/rules/recover.md",
+                        preview,
+                    )?;
                 }
-            };
 
-            // Write decision.md command
-            let decision_path = opencode_cmd_dir.join("decision.md");
-            write_cmd(
-                &decision_path,
-                OPENCODE_DECISION_CMD,
-                ".opencode/command/decision.md",
-            )?;
+                // Write memories.md (project-level memories Cascade auto-retrieves)
+                let memories_path = windsurf_base.join("memories.md");
+                if force {
+                    write_file_overwrite(
+                        &memories_path,
+                        WINDSURF_MEMORIES,
+                        ".windsurf/memories.md",
+                        preview,
+                    )?;
+                } else {
+                    write_file_if_missing(
+                        &memories_path,
+                        WINDSURF_MEMORIES,
+                        ".windsurf/memories.md",
+                        preview,
+                    )?;
+                }
 
-            // Write recover.md command (context recovery)
-            let recover_path = opencode_cmd_dir.join("recover.md");
-            write_cmd(
-                &recover_path,
-                OPENCODE_RECOVER_CMD,
-                ".opencode/command/recover.md",
-            )?;
+                if components.instructions {
+                    // Handle AGENTS.md - append if missing, update the managed section if force
+                    let agents_md_path = cwd.join("AGENTS.md");
+                    if force {
+                        replace_config_md_section(
+                            &agents_md_path,
+                            AGENTS_MD_SECTION,
+                            "AGENTS.md",
+                            preview,
+                        )?;
+                    } else {
+                        append_config_md(&agents_md_path, AGENTS_MD_SECTION, "AGENTS.md", preview)?;
+                    }
+                }
+            }
+            Editor::Opencode => {
+                // Create .opencode/command directory (note: singular "command", not "commands")
+                let opencode_cmd_dir = cwd.join(".opencode").join("command");
+                create_dir_if_missing(&opencode_cmd_dir, preview)?;
+
+                // Helper to write file with force support
+                let write_cmd = |path: &Path, content: &str, name: &str| -> Result<(), String> {
+                    if force {
+                        write_file_overwrite(path, content, name, preview)
+                    } else {
+                        write_file_if_missing(path, content, name, preview)
+                    }
+                };
+
+                // Write decision.md command
+                let decision_path = opencode_cmd_dir.join("decision.md");
+                write_cmd(
+                    &decision_path,
+                    OPENCODE_DECISION_CMD,
+                    ".opencode/command/decision.md",
+                )?;
 
-            // Write build-test.md command
-            let build_test_path = opencode_cmd_dir.join("build-test.md");
-            write_cmd(
-                &build_test_path,
-                OPENCODE_BUILD_TEST_CMD,
-                ".opencode/command/build-test.md",
-            )?;
+                // Write recover.md command (context recovery)
+                let recover_path = opencode_cmd_dir.join("recover.md");
+                write_cmd(
+                    &recover_path,
+                    OPENCODE_RECOVER_CMD,
+                    ".opencode/command/recover.md",
+                )?;
 
-            // Write serve-ui.md command
-            let serve_ui_path = opencode_cmd_dir.join("serve-ui.md");
-            write_cmd(
-                &serve_ui_path,
-                OPENCODE_SERVE_UI_CMD,
-                ".opencode/command/serve-ui.md",
-            )?;
+                // Write build-test.md command
+                let build_test_path = opencode_cmd_dir.join("build-test.md");
+                write_cmd(
+                    &build_test_path,
+                    OPENCODE_BUILD_TEST_CMD,
+                    ".opencode/command/build-test.md",
+                )?;
 
-            // Write sync-graph.md command
-            let sync_graph_path = opencode_cmd_dir.join("sync-graph.md");
-            write_cmd(
-                &sync_graph_path,
-                OPENCODE_SYNC_GRAPH_CMD,
-                ".opencode/command/sync-graph.md",
-            )?;
+                // Write serve-ui.md command
+                let serve_ui_path = opencode_cmd_dir.join("serve-ui.md");
+                write_cmd(
+                    &serve_ui_path,
+                    OPENCODE_SERVE_UI_CMD,
+                    ".opencode/command/serve-ui.md",
+                )?;
 
-            // Handle AGENTS.md - append if missing, overwrite if force
-            let agents_md_path = cwd.join("AGENTS.md");
-            if force {
-                write_file_overwrite(&agents_md_path, AGENTS_MD_SECTION, "AGENTS.md")?;
-            } else {
-                append_config_md(&agents_md_path, AGENTS_MD_SECTION, "AGENTS.md")?;
-            }
-        }
-        Editor::Codex => {
-            // Create .codex/prompts directory (top-level only, Codex doesn't read subdirs)
-            let codex_prompts_dir = cwd.join(".codex").join("prompts");
-            create_dir_if_missing(&codex_prompts_dir)?;
+                // Write sync-graph.md command
+                let sync_graph_path = opencode_cmd_dir.join("sync-graph.md");
+                write_cmd(
+                    &sync_graph_path,
+                    OPENCODE_SYNC_GRAPH_CMD,
+                    ".opencode/command/sync-graph.md",
+                )?;
 
-            // Helper to write file with force support
-            let write_prompt = |path: &Path, content: &str, name: &str| -> Result<(), String> {
-                if force {
-                    write_file_overwrite(path, content, name)
-                } else {
-                    write_file_if_missing(path, content, name)
+                if components.instructions {
+                    // Handle AGENTS.md - append if missing, update the managed section if force
+                    let agents_md_path = cwd.join("AGENTS.md");
+                    if force {
+                        replace_config_md_section(
+                            &agents_md_path,
+                            AGENTS_MD_SECTION,
+                            "AGENTS.md",
+                            preview,
+                        )?;
+                    } else {
+                        append_config_md(&agents_md_path, AGENTS_MD_SECTION, "AGENTS.md", preview)?;
+                    }
                 }
-            };
+            }
+            Editor::Codex => {
+                // Create .codex/prompts directory (top-level only, Codex doesn't read subdirs)
+                let codex_prompts_dir = cwd.join(".codex").join("prompts");
+                create_dir_if_missing(&codex_prompts_dir, preview)?;
+
+                // Helper to write file with force support
+                let write_prompt = |path: &Path, content: &str, name: &str| -> Result<(), String> {
+                    if force {
+                        write_file_overwrite(path, content, name, preview)
+                    } else {
+                        write_file_if_missing(path, content, name, preview)
+                    }
+                };
+
+                // Write deciduous.decision.md prompt
+                let decision_path = codex_prompts_dir.join("deciduous.decision.md");
+                write_prompt(
+                    &decision_path,
+                    CODEX_DECISION_PROMPT,
+                    ".codex/prompts/deciduous.decision.md",
+                )?;
 
-            // Write deciduous.decision.md prompt
-            let decision_path = codex_prompts_dir.join("deciduous.decision.md");
-            write_prompt(
-                &decision_path,
-                CODEX_DECISION_PROMPT,
-                ".codex/prompts/deciduous.decision.md",
-            )?;
+                // Write deciduous.recover.md prompt (context recovery)
+                let recover_path = codex_prompts_dir.join("deciduous.recover.md");
+                write_prompt(
+                    &recover_path,
+                    CODEX_RECOVER_PROMPT,
+                    ".codex/prompts/deciduous.recover.md",
+                )?;
 
-            // Write deciduous.recover.md prompt (context recovery)
-            let recover_path = codex_prompts_dir.join("deciduous.recover.md");
-            write_prompt(
-                &recover_path,
-                CODEX_RECOVER_PROMPT,
-                ".codex/prompts/deciduous.recover.md",
-            )?;
+                // Write deciduous.build-test.md prompt
+                let build_test_path = codex_prompts_dir.join("deciduous.build-test.md");
+                write_prompt(
+                    &build_test_path,
+                    CODEX_BUILD_TEST_PROMPT,
+                    ".codex/prompts/deciduous.build-test.md",
+                )?;
 
-            // Write deciduous.build-test.md prompt
-            let build_test_path = codex_prompts_dir.join("deciduous.build-test.md");
-            write_prompt(
-                &build_test_path,
-                CODEX_BUILD_TEST_PROMPT,
-                ".codex/prompts/deciduous.build-test.md",
-            )?;
+                // Write deciduous.serve-ui.md prompt
+                let serve_ui_path = codex_prompts_dir.join("deciduous.serve-ui.md");
+                write_prompt(
+                    &serve_ui_path,
+                    CODEX_SERVE_UI_PROMPT,
+                    ".codex/prompts/deciduous.serve-ui.md",
+                )?;
 
-            // Write deciduous.serve-ui.md prompt
-            let serve_ui_path = codex_prompts_dir.join("deciduous.serve-ui.md");
-            write_prompt(
-                &serve_ui_path,
-                CODEX_SERVE_UI_PROMPT,
-                ".codex/prompts/deciduous.serve-ui.md",
-            )?;
+                // Write deciduous.sync-graph.md prompt
+                let sync_graph_path = codex_prompts_dir.join("deciduous.sync-graph.md");
+                write_prompt(
+                    &sync_graph_path,
+                    CODEX_SYNC_GRAPH_PROMPT,
+                    ".codex/prompts/deciduous.sync-graph.md",
+                )?;
 
-            // Write deciduous.sync-graph.md prompt
-            let sync_graph_path = codex_prompts_dir.join("deciduous.sync-graph.md");
-            write_prompt(
-                &sync_graph_path,
-                CODEX_SYNC_GRAPH_PROMPT,
-                ".codex/prompts/deciduous.sync-graph.md",
-            )?;
+                // Add Codex-specific entries to .gitignore (selective ignoring)
+                add_codex_to_gitignore(&cwd, preview)?;
+
+                if components.instructions {
+                    // Handle AGENTS.md - append if missing, update the managed section if force
+                    let agents_md_path = cwd.join("AGENTS.md");
+                    if force {
+                        replace_config_md_section(
+                            &agents_md_path,
+                            AGENTS_MD_SECTION,
+                            "AGENTS.md",
+                            preview,
+                        )?;
+                    } else {
+                        append_config_md(&agents_md_path, AGENTS_MD_SECTION, "AGENTS.md", preview)?;
+                    }
+                }
+            }
+            Editor::Zed => {
+                // Create .zed/prompts directory
+                let zed_prompts_dir = cwd.join(".zed").join("prompts");
+                create_dir_if_missing(&zed_prompts_dir, preview)?;
+
+                for (name, template) in SHARED_COMMANDS {
+                    let path = zed_prompts_dir.join(format!("{}.md", name));
+                    let display_name = format!(".zed/prompts/{}.md", name);
+                    if force {
+                        write_file_overwrite(&path, &zed_prompt(template), &display_name, preview)?;
+                    } else {
+                        write_file_if_missing(
+                            &path,
+                            &zed_prompt(template),
+                            &display_name,
+                            preview,
+                        )?;
+                    }
+                }
 
-            // Add Codex-specific entries to .gitignore (selective ignoring)
-            add_codex_to_gitignore(&cwd)?;
+                if components.instructions {
+                    // Handle .rules - Zed includes it in every agent thread, ahead of AGENTS.md
+                    let rules_path = cwd.join(".rules");
+                    if force {
+                        replace_config_md_section(
+                            &rules_path,
+                            AGENTS_MD_SECTION,
+                            ".rules",
+                            preview,
+                        )?;
+                    } else {
+                        append_config_md(&rules_path, AGENTS_MD_SECTION, ".rules", preview)?;
+                    }
+                }
+            }
+            Editor::Gemini => {
+                // Create .gemini/commands/deciduous directory (commands become /deciduous:<name>)
+                let gemini_cmd_dir = cwd.join(".gemini").join("commands").join("deciduous");
+                create_dir_if_missing(&gemini_cmd_dir, preview)?;
+
+                for (name, template) in SHARED_COMMANDS {
+                    let path = gemini_cmd_dir.join(format!("{}.toml", name));
+                    let display_name = format!(".gemini/commands/deciduous/{}.toml", name);
+                    if force {
+                        write_file_overwrite(
+                            &path,
+                            &gemini_command(template),
+                            &display_name,
+                            preview,
+                        )?;
+                    } else {
+                        write_file_if_missing(
+                            &path,
+                            &gemini_command(template),
+                            &display_name,
+                            preview,
+                        )?;
+                    }
+                }
 
-            // Handle AGENTS.md - append if missing, overwrite if force
-            let agents_md_path = cwd.join("AGENTS.md");
-            if force {
-                write_file_overwrite(&agents_md_path, AGENTS_MD_SECTION, "AGENTS.md")?;
-            } else {
-                append_config_md(&agents_md_path, AGENTS_MD_SECTION, "AGENTS.md")?;
+                if components.instructions {
+                    // Handle GEMINI.md - append if missing, update the managed section if force
+                    let gemini_md_path = cwd.join("GEMINI.md");
+                    if force {
+                        replace_config_md_section(
+                            &gemini_md_path,
+                            AGENTS_MD_SECTION,
+                            "GEMINI.md",
+                            preview,
+                        )?;
+                    } else {
+                        append_config_md(&gemini_md_path, AGENTS_MD_SECTION, "GEMINI.md", preview)?;
+                    }
+                }
             }
         }
     }
 
     // 4. Add .deciduous to .gitignore if not already there
-    add_to_gitignore(&cwd)?;
+    add_to_gitignore(&cwd, preview)?;
 
     // 5. Create GitHub workflows (if .git exists)
     let git_dir = cwd.join(".git");
-    if git_dir.exists() {
+    if components.workflows && git_dir.exists() {
         let workflows_dir = cwd.join(".github").join("workflows");
-        create_dir_if_missing(&workflows_dir)?;
+        create_dir_if_missing(&workflows_dir, preview)?;
 
         // Cleanup workflow for PR graph assets
         let cleanup_path = workflows_dir.join("cleanup-decision-graphs.yml");
@@ -2639,41 +2971,63 @@ pub fn init_project(editor: Editor, force: bool) -> Result<(), String> {
             &cleanup_path,
             CLEANUP_WORKFLOW,
             ".github/workflows/cleanup-decision-graphs.yml",
+            preview,
         )?;
 
         // Deploy workflow for GitHub Pages
-        let deploy_path = workflows_dir.join("deploy-pages.yml");
-        write_file_if_missing(
-            &deploy_path,
-            DEPLOY_PAGES_WORKFLOW,
-            ".github/workflows/deploy-pages.yml",
-        )?;
+        if components.pages {
+            let deploy_path = workflows_dir.join("deploy-pages.yml");
+            write_file_if_missing(
+                &deploy_path,
+                DEPLOY_PAGES_WORKFLOW,
+                ".github/workflows/deploy-pages.yml",
+                preview,
+            )?;
+        }
     }
 
-    // 6. Create docs/ directory for GitHub Pages
-    let docs_dir = cwd.join("docs");
-    create_dir_if_missing(&docs_dir)?;
+    if components.pages {
+        // 6. Create docs/ directory for GitHub Pages
+        let docs_dir = cwd.join("docs");
+        create_dir_if_missing(&docs_dir, preview)?;
 
-    // 7. Write static viewer HTML to docs/index.html
-    let viewer_path = docs_dir.join("index.html");
-    write_file_if_missing(&viewer_path, PAGES_VIEWER_HTML, "docs/index.html")?;
+        // 7. Write static viewer HTML to docs/index.html
+        let viewer_path = docs_dir.join("index.html");
+        write_file_if_missing(&viewer_path, PAGES_VIEWER_HTML, "docs/index.html", preview)?;
 
-    // 8. Create empty graph-data.json (will be populated by sync)
-    let graph_data_path = docs_dir.join("graph-data.json");
-    if !graph_data_path.exists() {
+        // 8. Create empty graph-data.json (will be populated by sync)
+        let graph_data_path = docs_dir.join("graph-data.json");
         let empty_graph = r#"{"nodes":[],"edges":[]}"#;
-        fs::write(&graph_data_path, empty_graph)
-            .map_err(|e| format!("Could not write graph-data.json: {}", e))?;
-        println!("   {} docs/graph-data.json", "Creating".green());
+        if !graph_data_path.exists()
+            && !previewed(
+                &graph_data_path,
+                empty_graph,
+                "docs/graph-data.json",
+                preview,
+            )
+        {
+            fs::write(&graph_data_path, empty_graph)
+                .map_err(|e| format!("Could not write graph-data.json: {}", e))?;
+            println!("   {} docs/graph-data.json", "Creating".green());
+        }
+
+        // 9. Create .nojekyll for GitHub Pages (prevents Jekyll processing)
+        let nojekyll_path = docs_dir.join(".nojekyll");
+        if !nojekyll_path.exists() && !previewed(&nojekyll_path, "", "docs/.nojekyll", preview) {
+            fs::write(&nojekyll_path, "")
+                .map_err(|e| format!("Could not write .nojekyll: {}", e))?;
+            println!("   {} docs/.nojekyll", "Creating".green());
+        }
     }
 
-    // 9. Create .nojekyll for GitHub Pages (prevents Jekyll processing)
-    let nojekyll_path = docs_dir.join(".nojekyll");
-    if !nojekyll_path.exists() {
-        fs::write(&nojekyll_path, "").map_err(|e| format!("Could not write .nojekyll: {}", e))?;
-        println!("   {} docs/.nojekyll", "Creating".green());
+    if preview != Preview::Off {
+        println!("\n{}", "Dry run: nothing was written".cyan().bold());
+        return Ok(());
     }
 
+    // 10. Record what was created so `deciduous uninit` can remove it
+    crate::manifest::record_since(&cwd, &before, true)?;
+
     println!(
         "\n{}",
         format!("Deciduous initialized for {}!", editor_name)
@@ -2685,84 +3039,151 @@ pub fn init_project(editor: Editor, force: bool) -> Result<(), String> {
         "  1. Run {} to start the local graph viewer",
         "deciduous serve".cyan()
     );
-    println!(
-        "  2. Run {} to export graph for GitHub Pages",
-        "deciduous sync".cyan()
-    );
+    if components.pages {
+        println!(
+            "  2. Run {} to export graph for GitHub Pages",
+            "deciduous sync".cyan()
+        );
+    }
 
-    match editor {
-        Editor::Claude => {
-            println!(
-                "  3. Use {} or {} slash commands",
-                "/decision".cyan(),
-                "/recover".cyan()
-            );
-        }
-        Editor::Windsurf => {
-            println!("  3. Rules created in {}", ".windsurf/rules/".cyan());
-            println!("     - {} (set to Always On)", "deciduous.md".cyan());
-            println!("     - {} (model-triggered)", "recover.md".cyan());
-            println!("     - {} (auto-retrieved)", ".windsurf/memories.md".cyan());
-            println!();
-            println!(
-                "{}",
-                "  ⚠️  IMPORTANT: Verify rule activation in Windsurf:"
-                    .yellow()
-                    .bold()
-            );
-            println!("     Open Windsurf → Cascade → Customizations (gear icon)");
-            println!(
-                "     Ensure {} is set to {}",
-                "deciduous.md".cyan(),
-                "\"Always On\"".green()
-            );
-        }
-        Editor::Opencode => {
-            println!("  3. Commands created in {}", ".opencode/command/".cyan());
-            println!("     - {} (decision tracking)", "/decision".cyan());
-            println!("     - {} (context recovery)", "/recover".cyan());
-            println!("     - {} (build & test)", "/build-test".cyan());
-            println!("     - {} (graph viewer)", "/serve-ui".cyan());
-            println!("     - {} (export graph)", "/sync-graph".cyan());
-            println!("  4. Instructions added to {}", "AGENTS.md".cyan());
+    for &editor in editors {
+        if editors.len() > 1 {
+            println!("\n  {}:", editor.name().bold());
         }
-        Editor::Codex => {
-            println!("  3. Prompts created in {}", ".codex/prompts/".cyan());
-            println!("     - {} (decision tracking)", "/prompts:decision".cyan());
-            println!("     - {} (context recovery)", "/prompts:recover".cyan());
-            println!("     - {} (build & test)", "/prompts:build-test".cyan());
-            println!("     - {} (graph viewer)", "/prompts:serve-ui".cyan());
-            println!("     - {} (export graph)", "/prompts:sync-graph".cyan());
-            println!("  4. Instructions added to {}", "AGENTS.md".cyan());
-            println!();
-            println!(
-                "{}",
-                "  Note: Set CODEX_HOME to use project-local prompts:"
-                    .yellow()
-                    .bold()
-            );
-            println!("     {}", "export CODEX_HOME=.codex".cyan());
+        match editor {
+            Editor::Claude => {
+                println!(
+                    "  3. Use {} or {} slash commands",
+                    "/decision".cyan(),
+                    "/recover".cyan()
+                );
+            }
+            Editor::Windsurf => {
+                println!("  3. Rules created in {}", ".windsurf/rules/".cyan());
+                println!("     - {} (set to Always On)", "deciduous.md".cyan());
+                println!("     - {} (model-triggered)", "recover.md".cyan());
+                println!("     - {} (auto-retrieved)", ".windsurf/memories.md".cyan());
+                println!();
+                println!(
+                    "{}",
+                    "  ⚠️  IMPORTANT: Verify rule activation in Windsurf:"
+                        .yellow()
+                        .bold()
+                );
+                println!("     Open Windsurf → Cascade → Customizations (gear icon)");
+                println!(
+                    "     Ensure {} is set to {}",
+                    "deciduous.md".cyan(),
+                    "\"Always On\"".green()
+                );
+            }
+            Editor::Opencode => {
+                println!("  3. Commands created in {}", ".opencode/command/".cyan());
+                println!("     - {} (decision tracking)", "/decision".cyan());
+                println!("     - {} (context recovery)", "/recover".cyan());
+                println!("     - {} (build & test)", "/build-test".cyan());
+                println!("     - {} (graph viewer)", "/serve-ui".cyan());
+                println!("     - {} (export graph)", "/sync-graph".cyan());
+                if components.instructions {
+                    println!("  4. Instructions added to {}", "AGENTS.md".cyan());
+                }
+            }
+            Editor::Codex => {
+                println!("  3. Prompts created in {}", ".codex/prompts/".cyan());
+                println!("     - {} (decision tracking)", "/prompts:decision".cyan());
+                println!("     - {} (context recovery)", "/prompts:recover".cyan());
+                println!("     - {} (build & test)", "/prompts:build-test".cyan());
+                println!("     - {} (graph viewer)", "/prompts:serve-ui".cyan());
+                println!("     - {} (export graph)", "/prompts:sync-graph".cyan());
+                if components.instructions {
+                    println!("  4. Instructions added to {}", "AGENTS.md".cyan());
+                }
+                println!();
+                println!(
+                    "{}",
+                    "  Note: Set CODEX_HOME to use project-local prompts:"
+                        .yellow()
+                        .bold()
+                );
+                println!("     {}", "export CODEX_HOME=.codex".cyan());
+            }
+            Editor::Zed => {
+                println!("  3. Prompts created in {}", ".zed/prompts/".cyan());
+                println!(
+                    "     - {} (decision tracking)",
+                    "/file .zed/prompts/decision.md".cyan()
+                );
+                println!(
+                    "     - {} (context recovery)",
+                    "/file .zed/prompts/recover.md".cyan()
+                );
+                println!(
+                    "     - {} (build & test)",
+                    "/file .zed/prompts/build-test.md".cyan()
+                );
+                println!(
+                    "     - {} (graph viewer)",
+                    "/file .zed/prompts/serve-ui.md".cyan()
+                );
+                println!(
+                    "     - {} (export graph)",
+                    "/file .zed/prompts/sync-graph.md".cyan()
+                );
+                if components.instructions {
+                    println!("  4. Instructions added to {}", ".rules".cyan());
+                }
+                println!();
+                println!(
+                    "{}",
+                    "  Note: Zed reads .rules before AGENTS.md or CLAUDE.md; keep project rules there"
+                        .yellow()
+                        .bold()
+                );
+            }
+            Editor::Gemini => {
+                println!(
+                    "  3. Commands created in {}",
+                    ".gemini/commands/deciduous/".cyan()
+                );
+                println!(
+                    "     - {} (decision tracking)",
+                    "/deciduous:decision".cyan()
+                );
+                println!("     - {} (context recovery)", "/deciduous:recover".cyan());
+                println!("     - {} (build & test)", "/deciduous:build-test".cyan());
+                println!("     - {} (graph viewer)", "/deciduous:serve-ui".cyan());
+                println!("     - {} (export graph)", "/deciduous:sync-graph".cyan());
+                if components.instructions {
+                    println!("  4. Instructions added to {}", "GEMINI.md".cyan());
+                }
+            }
         }
     }
 
     println!();
-    println!(
-        "  4. Commit and push: {}",
-        "git add docs/ .github/ && git push".cyan()
-    );
-    println!("  5. Enable GitHub Pages (Settings → Pages → Source: Deploy from branch, gh-pages)");
-    println!();
-    println!(
-        "Your graph will be live at: {}",
-        "https://<user>.github.io/<repo>/".cyan()
-    );
-    println!();
+    if components.pages {
+        println!(
+            "  4. Commit and push: {}",
+            "git add docs/ .github/ && git push".cyan()
+        );
+        println!(
+            "  5. Enable GitHub Pages (Settings → Pages → Source: Deploy from branch, gh-pages)"
+        );
+        println!();
+        println!(
+            "Your graph will be live at: {}",
+            "https://<user>.github.io/<repo>/".cyan()
+        );
+        println!();
+    }
 
     Ok(())
 }
 
-fn create_dir_if_missing(path: &Path) -> Result<(), String> {
-    if !path.exists() {
+fn create_dir_if_missing(path: &Path, preview: Preview) -> Result<(), String> {
+    if !path.exists() && preview != Preview::Off {
+        println!("   {} {}", "Would create".green(), path.display());
+    } else if !path.exists() {
         fs::create_dir_all(path)
             .map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
         println!("   {} {}", "Creating".green(), path.display());
@@ -2770,7 +3191,12 @@ fn create_dir_if_missing(path: &Path) -> Result<(), String> {
     Ok(())
 }
 
-fn write_file_if_missing(path: &Path, content: &str, display_name: &str) -> Result<(), String> {
+fn write_file_if_missing(
+    path: &Path,
+    content: &str,
+    display_name: &str,
+    preview: Preview,
+) -> Result<(), String> {
     if path.exists() {
         println!(
             "   {} {} (already exists)",
@@ -2778,23 +3204,140 @@ fn write_file_if_missing(path: &Path, content: &str, display_name: &str) -> Resu
             display_name
         );
     } else {
+        if previewed(path, content, display_name, preview) {
+            return Ok(());
+        }
         fs::write(path, content).map_err(|e| format!("Could not write {}: {}", display_name, e))?;
         println!("   {} {}", "Creating".green(), display_name);
     }
     Ok(())
 }
 
-fn write_file_overwrite(path: &Path, content: &str, display_name: &str) -> Result<(), String> {
+fn write_file_overwrite(
+    path: &Path,
+    content: &str,
+    display_name: &str,
+    preview: Preview,
+) -> Result<(), String> {
+    if previewed(path, content, display_name, preview) {
+        return Ok(());
+    }
     fs::write(path, content).map_err(|e| format!("Could not write {}: {}", display_name, e))?;
     println!("   {} {}", "Updated".green(), display_name);
     Ok(())
 }
 
-fn replace_config_md_section(
-    path: &Path,
-    section_content: &str,
-    file_name: &str,
-) -> Result<(), String> {
+/// In a preview, report what writing `content` to `path` would do instead
+///
+/// Returns false when files should really be written.
+fn previewed(path: &Path, content: &str, display_name: &str, preview: Preview) -> bool {
+    if preview == Preview::Off {
+        return false;
+    }
+    let existing = fs::read_to_string(path).ok();
+    match existing.as_deref() {
+        None => println!("   {} {}", "Would create".green(), display_name),
+        Some(old) if old == content => {
+            println!("   {} {} (unchanged)", "Would keep".yellow(), display_name)
+        }
+        Some(old) => {
+            println!("   {} {}", "Would overwrite".red(), display_name);
+            if preview == Preview::Diff {
+                for line in unified_diff(old, content, display_name).lines() {
+                    let line = if line.starts_with("@@") {
+                        line.cyan()
+                    } else if line.starts_with('+') {
+                        line.green()
+                    } else if line.starts_with('-') {
+                        line.red()
+                    } else {
+                        line.normal()
+                    };
+                    println!("      {}", line);
+                }
+            }
+        }
+    }
+    true
+}
+
+/// Unified diff of two texts with three lines of context
+fn unified_diff(old: &str, new: &str, name: &str) -> String {
+    const CONTEXT: usize = 3;
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j]: lines in common between a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // Edit script, with the old/new line number before each step
+    let mut ops: Vec<(char, &str, usize, usize)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((' ', a[i], i, j));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', a[i], i, j));
+            i += 1;
+        } else {
+            ops.push(('+', b[j], i, j));
+            j += 1;
+        }
+    }
+
+    let changes: Vec<usize> = (0..ops.len()).filter(|&k| ops[k].0 != ' ').collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+    let mut out = format!("--- a/{}\n+++ b/{}\n", name, name);
+    let mut k = 0;
+    while k < changes.len() {
+        let start = changes[k].saturating_sub(CONTEXT);
+        let mut end = changes[k] + 1 + CONTEXT;
+        k += 1;
+        // Changes closer than twice the context share a hunk
+        while k < changes.len() && changes[k] <= end + CONTEXT {
+            end = changes[k] + 1 + CONTEXT;
+            k += 1;
+        }
+        let hunk = &ops[start..end.min(ops.len())];
+        let old_len = hunk.iter().filter(|op| op.0 != '+').count();
+        let new_len = hunk.iter().filter(|op| op.0 != '-').count();
+        let (old_start, new_start) = (hunk[0].2, hunk[0].3);
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start + usize::from(old_len > 0),
+            old_len,
+            new_start + usize::from(new_len > 0),
+            new_len
+        ));
+        for (tag, line, _, _) in hunk {
+            out.push_str(&format!("{}{}\n", tag, line));
+        }
+    }
+    out
+}
+
+/// Byte range of the Decision Graph Workflow section in a CLAUDE.md/AGENTS.md
+///
+/// The marked block when there is one, else the section under our heading
+/// as written by versions before the markers.
+pub(crate) fn workflow_section(existing: &str) -> Option<(usize, usize)> {
+    if let Some(found) = managed_section::find(existing) {
+        return Some((found.range.start, found.range.end));
+    }
+
     // Look for either variant of our section header
     let markers = [
         "## Decision Graph Workflow",
@@ -2803,74 +3346,89 @@ fn replace_config_md_section(
     // Our section ends when we hit another ## heading or end of file
     let section_end_pattern = "\n## ";
 
-    if path.exists() {
-        let existing =
-            fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", file_name, e))?;
-
-        // Find the start of our section (try each marker)
-        let start_idx = markers.iter().filter_map(|m| existing.find(m)).min();
-
-        if let Some(start) = start_idx {
-            // Find the end of our section (next ## heading after our section starts)
-            // Need to skip past the marker properly - find the newline after it
-            let after_marker = existing[start..]
-                .find('\n')
-                .map(|i| start + i)
-                .unwrap_or(start + 10);
-            let end_idx = existing[after_marker..]
-                .find(section_end_pattern)
-                .map(|i| after_marker + i + 1) // +1 to keep the newline before next section
-                .unwrap_or(existing.len()); // If no next section, replace to end
-
-            // Rebuild the file: before our section + new section + after our section
-            let before = &existing[..start];
-            let after = &existing[end_idx..];
-
-            let new_content = if after.is_empty() {
-                format!("{}{}", before, section_content.trim_start())
-            } else {
-                format!(
-                    "{}{}\n{}",
-                    before,
-                    section_content.trim(),
-                    after.trim_start()
-                )
-            };
+    // Find the start of our section (try each marker)
+    let start = markers.iter().filter_map(|m| existing.find(m)).min()?;
+
+    // Find the end of our section (next ## heading after our section starts)
+    // Need to skip past the marker properly - find the newline after it
+    let after_marker = existing[start..]
+        .find('\n')
+        .map(|i| start + i)
+        .unwrap_or(existing.len());
+    let end = existing[after_marker..]
+        .find(section_end_pattern)
+        .map(|i| after_marker + i + 1) // +1 to keep the newline before next section
+        .unwrap_or(existing.len()); // If no next section, replace to end
+    Some((start, end))
+}
 
-            fs::write(path, new_content)
-                .map_err(|e| format!("Could not write {}: {}", file_name, e))?;
-            println!("   {} {} (section replaced)", "Updated".green(), file_name);
-        } else {
-            // No existing section, append
-            let mut file = fs::OpenOptions::new()
-                .append(true)
-                .open(path)
-                .map_err(|e| format!("Could not open {} for append: {}", file_name, e))?;
-            use std::io::Write;
-            writeln!(file, "\n{}", section_content.trim())
-                .map_err(|e| format!("Could not append to {}: {}", file_name, e))?;
-            println!("   {} {} (section added)", "Updated".green(), file_name);
+/// Bring the managed workflow block in a CLAUDE.md/AGENTS.md up to date
+///
+/// Everything outside the block is kept. Hand edits inside it are merged
+/// with the new template (see `managed_section`).
+fn replace_config_md_section(
+    path: &Path,
+    section_content: &str,
+    file_name: &str,
+    preview: Preview,
+) -> Result<(), String> {
+    if !path.exists() {
+        let content = managed_section::block(section_content);
+        if previewed(path, &content, file_name, preview) {
+            return Ok(());
         }
-    } else {
-        // File doesn't exist, create it
-        fs::write(path, section_content.trim())
-            .map_err(|e| format!("Could not create {}: {}", file_name, e))?;
+        fs::write(path, content).map_err(|e| format!("Could not create {}: {}", file_name, e))?;
         println!("   {} {}", "Creating".green(), file_name);
+        return managed_section::save_base(path, section_content);
     }
-    Ok(())
+
+    let existing =
+        fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", file_name, e))?;
+    let base = managed_section::load_base(path);
+    let (new_content, outcome) =
+        managed_section::merge_into(&existing, base.as_deref(), section_content);
+    if previewed(path, &new_content, file_name, preview) {
+        return Ok(());
+    }
+    if new_content != existing {
+        fs::write(path, &new_content)
+            .map_err(|e| format!("Could not write {}: {}", file_name, e))?;
+    }
+    match outcome {
+        managed_section::Outcome::Added => {
+            println!("   {} {} (section added)", "Updated".green(), file_name)
+        }
+        managed_section::Outcome::Replaced => {
+            println!("   {} {} (section replaced)", "Updated".green(), file_name)
+        }
+        managed_section::Outcome::Unchanged => {
+            println!(
+                "   {} {} (section up to date)",
+                "Skipping".yellow(),
+                file_name
+            )
+        }
+        managed_section::Outcome::Merged => println!(
+            "   {} {} (section updated, your edits kept)",
+            "Merged".green(),
+            file_name
+        ),
+        managed_section::Outcome::Conflict => println!(
+            "   {} {}: your edits to the workflow section overlap the new version; \
+             resolve the <<<<<<< markers",
+            "Conflict".red(),
+            file_name
+        ),
+    }
+    managed_section::save_base(path, section_content)
 }
 
 /// Update tooling files to the latest version (overwrites existing)
-pub fn update_tooling(editor: Editor) -> Result<(), String> {
+pub fn update_tooling(editors: &[Editor], preview: Preview) -> Result<(), String> {
     let cwd =
         std::env::current_dir().map_err(|e| format!("Could not get current directory: {}", e))?;
 
-    let editor_name = match editor {
-        Editor::Claude => "Claude Code",
-        Editor::Windsurf => "Windsurf",
-        Editor::Opencode => "OpenCode",
-        Editor::Codex => "Codex",
-    };
+    let editor_name = editor_names(editors);
 
     println!(
         "\n{}",
@@ -2878,13 +3436,23 @@ pub fn update_tooling(editor: Editor) -> Result<(), String> {
             .cyan()
             .bold()
     );
-    println!("   Directory: {}\n", cwd.display());
+    println!("   Directory: {}", cwd.display());
+    if preview != Preview::Off {
+        println!("   Mode: {} (nothing is written)", "dry run".cyan());
+    }
+    println!();
+    let before = crate::manifest::Snapshot::take(&cwd);
 
     // Update config.toml (only if .deciduous exists)
     let deciduous_dir = cwd.join(".deciduous");
     if deciduous_dir.exists() {
         let config_path = deciduous_dir.join("config.toml");
-        write_file_overwrite(&config_path, DEFAULT_CONFIG, ".deciduous/config.toml")?;
+        write_file_overwrite(
+            &config_path,
+            DEFAULT_CONFIG,
+            ".deciduous/config.toml",
+            preview,
+        )?;
     } else {
         println!(
             "   {} .deciduous/ not found - run 'deciduous init' first",
@@ -2892,173 +3460,266 @@ pub fn update_tooling(editor: Editor) -> Result<(), String> {
         );
     }
 
-    match editor {
-        Editor::Claude => {
-            // Create .claude/commands directory if needed
-            let claude_dir = cwd.join(".claude").join("commands");
-            create_dir_if_missing(&claude_dir)?;
+    for &editor in editors {
+        match editor {
+            Editor::Claude => {
+                // Create .claude/commands directory if needed
+                let claude_dir = cwd.join(".claude").join("commands");
+                create_dir_if_missing(&claude_dir, preview)?;
 
-            // Overwrite deciduous.decision.md slash command
-            let decision_path = claude_dir.join("deciduous.decision.md");
-            write_file_overwrite(
-                &decision_path,
-                DECISION_MD,
-                ".claude/commands/deciduous.decision.md",
-            )?;
+                // Overwrite deciduous.decision.md slash command
+                let decision_path = claude_dir.join("deciduous.decision.md");
+                write_file_overwrite(
+                    &decision_path,
+                    DECISION_MD,
+                    ".claude/commands/deciduous.decision.md",
+                    preview,
+                )?;
 
-            // Overwrite deciduous.recover.md slash command
-            let recover_path = claude_dir.join("deciduous.recover.md");
-            write_file_overwrite(
-                &recover_path,
-                RECOVER_MD,
-                ".claude/commands/deciduous.recover.md",
-            )?;
+                // Overwrite deciduous.recover.md slash command
+                let recover_path = claude_dir.join("deciduous.recover.md");
+                write_file_overwrite(
+                    &recover_path,
+                    RECOVER_MD,
+                    ".claude/commands/deciduous.recover.md",
+                    preview,
+                )?;
 
-            // Update CLAUDE.md section
-            let claude_md_path = cwd.join("CLAUDE.md");
-            replace_config_md_section(&claude_md_path, CLAUDE_MD_SECTION, "CLAUDE.md")?;
-
-            // Create/update deciduous skill
-            let skills_dir = cwd.join(".claude").join("skills").join("deciduous");
-            create_dir_if_missing(&skills_dir)?;
-            let skill_path = skills_dir.join("SKILL.md");
-            write_file_overwrite(
-                &skill_path,
-                DECIDUOUS_SKILL,
-                ".claude/skills/deciduous/SKILL.md",
-            )?;
-        }
-        Editor::Windsurf => {
-            // Create .windsurf directories if needed
-            let windsurf_base = cwd.join(".windsurf");
-            create_dir_if_missing(&windsurf_base)?;
-            let windsurf_rules = windsurf_base.join("rules");
-            create_dir_if_missing(&windsurf_rules)?;
-
-            // Overwrite deciduous.md rule
-            let deciduous_rule_path = windsurf_rules.join("deciduous.md");
-            write_file_overwrite(
-                &deciduous_rule_path,
-                WINDSURF_DECIDUOUS_RULE,
-                ".windsurf/rules/deciduous.md",
-            )?;
+                // Update CLAUDE.md section
+                let claude_md_path = cwd.join("CLAUDE.md");
+                replace_config_md_section(
+                    &claude_md_path,
+                    CLAUDE_MD_SECTION,
+                    "CLAUDE.md",
+                    preview,
+                )?;
 
-            // Overwrite context.md rule
-            let context_path = windsurf_rules.join("context.md");
-            write_file_overwrite(
-                &context_path,
-                WINDSURF_RECOVER_RULE,
-                ".windsurf/rules/recover.md",
-            )?;
+                // Create/update deciduous skill
+                let skills_dir = cwd.join(".claude").join("skills").join("deciduous");
+                create_dir_if_missing(&skills_dir, preview)?;
+                let skill_path = skills_dir.join("SKILL.md");
+                write_file_overwrite(
+                    &skill_path,
+                    DECIDUOUS_SKILL,
+                    ".claude/skills/deciduous/SKILL.md",
+                    preview,
+                )?;
+            }
+            Editor::Windsurf => {
+                // Create .windsurf directories if needed
+                let windsurf_base = cwd.join(".windsurf");
+                create_dir_if_missing(&windsurf_base, preview)?;
+                let windsurf_rules = windsurf_base.join("rules");
+                create_dir_if_missing(&windsurf_rules, preview)?;
+
+                // Overwrite deciduous.md rule
+                let deciduous_rule_path = windsurf_rules.join("deciduous.md");
+                write_file_overwrite(
+                    &deciduous_rule_path,
+                    WINDSURF_DECIDUOUS_RULE,
+                    ".windsurf/rules/deciduous.md",
+                    preview,
+                )?;
 
-            // Overwrite memories.md
-            let memories_path = windsurf_base.join("memories.md");
-            write_file_overwrite(&memories_path, WINDSURF_MEMORIES, ".windsurf/memories.md")?;
+                // Overwrite context.md rule
+                let context_path = windsurf_rules.join("context.md");
+                write_file_overwrite(
+                    &context_path,
+                    WINDSURF_RECOVER_RULE,
+                    ".windsurf/rules/recover.md",
+                    preview,
+                )?;
 
-            // Update AGENTS.md section
-            let agents_md_path = cwd.join("AGENTS.md");
-            replace_config_md_section(&agents_md_path, AGENTS_MD_SECTION, "AGENTS.md")?;
-        }
-        Editor::Opencode => {
-            // Create .opencode/command directory if needed
-            let opencode_cmd_dir = cwd.join(".opencode").join("command");
-            create_dir_if_missing(&opencode_cmd_dir)?;
-
-            // Overwrite decision.md command
-            let decision_path = opencode_cmd_dir.join("decision.md");
-            write_file_overwrite(
-                &decision_path,
-                OPENCODE_DECISION_CMD,
-                ".opencode/command/decision.md",
-            )?;
+                // Overwrite memories.md
+                let memories_path = windsurf_base.join("memories.md");
+                write_file_overwrite(
+                    &memories_path,
+                    WINDSURF_MEMORIES,
+                    ".windsurf/memories.md",
+                    preview,
+                )?;
 
-            // Overwrite context.md command
-            let context_path = opencode_cmd_dir.join("context.md");
-            write_file_overwrite(
-                &context_path,
-                OPENCODE_RECOVER_CMD,
-                ".opencode/command/recover.md",
-            )?;
+                // Update AGENTS.md section
+                let agents_md_path = cwd.join("AGENTS.md");
+                replace_config_md_section(
+                    &agents_md_path,
+                    AGENTS_MD_SECTION,
+                    "AGENTS.md",
+                    preview,
+                )?;
+            }
+            Editor::Opencode => {
+                // Create .opencode/command directory if needed
+                let opencode_cmd_dir = cwd.join(".opencode").join("command");
+                create_dir_if_missing(&opencode_cmd_dir, preview)?;
 
-            // Overwrite build-test.md command
-            let build_test_path = opencode_cmd_dir.join("build-test.md");
-            write_file_overwrite(
-                &build_test_path,
-                OPENCODE_BUILD_TEST_CMD,
-                ".opencode/command/build-test.md",
-            )?;
+                // Overwrite decision.md command
+                let decision_path = opencode_cmd_dir.join("decision.md");
+                write_file_overwrite(
+                    &decision_path,
+                    OPENCODE_DECISION_CMD,
+                    ".opencode/command/decision.md",
+                    preview,
+                )?;
 
-            // Overwrite serve-ui.md command
-            let serve_ui_path = opencode_cmd_dir.join("serve-ui.md");
-            write_file_overwrite(
-                &serve_ui_path,
-                OPENCODE_SERVE_UI_CMD,
-                ".opencode/command/serve-ui.md",
-            )?;
+                // Overwrite context.md command
+                let context_path = opencode_cmd_dir.join("context.md");
+                write_file_overwrite(
+                    &context_path,
+                    OPENCODE_RECOVER_CMD,
+                    ".opencode/command/recover.md",
+                    preview,
+                )?;
 
-            // Overwrite sync-graph.md command
-            let sync_graph_path = opencode_cmd_dir.join("sync-graph.md");
-            write_file_overwrite(
-                &sync_graph_path,
-                OPENCODE_SYNC_GRAPH_CMD,
-                ".opencode/command/sync-graph.md",
-            )?;
+                // Overwrite build-test.md command
+                let build_test_path = opencode_cmd_dir.join("build-test.md");
+                write_file_overwrite(
+                    &build_test_path,
+                    OPENCODE_BUILD_TEST_CMD,
+                    ".opencode/command/build-test.md",
+                    preview,
+                )?;
 
-            // Update AGENTS.md section
-            let agents_md_path = cwd.join("AGENTS.md");
-            replace_config_md_section(&agents_md_path, AGENTS_MD_SECTION, "AGENTS.md")?;
-        }
-        Editor::Codex => {
-            // Create .codex/prompts directory if needed
-            let codex_prompts_dir = cwd.join(".codex").join("prompts");
-            create_dir_if_missing(&codex_prompts_dir)?;
-
-            // Overwrite deciduous.decision.md prompt
-            let decision_path = codex_prompts_dir.join("deciduous.decision.md");
-            write_file_overwrite(
-                &decision_path,
-                CODEX_DECISION_PROMPT,
-                ".codex/prompts/deciduous.decision.md",
-            )?;
+                // Overwrite serve-ui.md command
+                let serve_ui_path = opencode_cmd_dir.join("serve-ui.md");
+                write_file_overwrite(
+                    &serve_ui_path,
+                    OPENCODE_SERVE_UI_CMD,
+                    ".opencode/command/serve-ui.md",
+                    preview,
+                )?;
 
-            // Overwrite deciduous.recover.md prompt
-            let recover_path = codex_prompts_dir.join("deciduous.recover.md");
-            write_file_overwrite(
-                &recover_path,
-                CODEX_RECOVER_PROMPT,
-                ".codex/prompts/deciduous.recover.md",
-            )?;
+                // Overwrite sync-graph.md command
+                let sync_graph_path = opencode_cmd_dir.join("sync-graph.md");
+                write_file_overwrite(
+                    &sync_graph_path,
+                    OPENCODE_SYNC_GRAPH_CMD,
+                    ".opencode/command/sync-graph.md",
+                    preview,
+                )?;
 
-            // Overwrite deciduous.build-test.md prompt
-            let build_test_path = codex_prompts_dir.join("deciduous.build-test.md");
-            write_file_overwrite(
-                &build_test_path,
-                CODEX_BUILD_TEST_PROMPT,
-                ".codex/prompts/deciduous.build-test.md",
-            )?;
+                // Update AGENTS.md section
+                let agents_md_path = cwd.join("AGENTS.md");
+                replace_config_md_section(
+                    &agents_md_path,
+                    AGENTS_MD_SECTION,
+                    "AGENTS.md",
+                    preview,
+                )?;
+            }
+            Editor::Codex => {
+                // Create .codex/prompts directory if needed
+                let codex_prompts_dir = cwd.join(".codex").join("prompts");
+                create_dir_if_missing(&codex_prompts_dir, preview)?;
 
-            // Overwrite deciduous.serve-ui.md prompt
-            let serve_ui_path = codex_prompts_dir.join("deciduous.serve-ui.md");
-            write_file_overwrite(
-                &serve_ui_path,
-                CODEX_SERVE_UI_PROMPT,
-                ".codex/prompts/deciduous.serve-ui.md",
-            )?;
+                // Overwrite deciduous.decision.md prompt
+                let decision_path = codex_prompts_dir.join("deciduous.decision.md");
+                write_file_overwrite(
+                    &decision_path,
+                    CODEX_DECISION_PROMPT,
+                    ".codex/prompts/deciduous.decision.md",
+                    preview,
+                )?;
 
-            // Overwrite deciduous.sync-graph.md prompt
-            let sync_graph_path = codex_prompts_dir.join("deciduous.sync-graph.md");
-            write_file_overwrite(
-                &sync_graph_path,
-                CODEX_SYNC_GRAPH_PROMPT,
-                ".codex/prompts/deciduous.sync-graph.md",
-            )?;
+                // Overwrite deciduous.recover.md prompt
+                let recover_path = codex_prompts_dir.join("deciduous.recover.md");
+                write_file_overwrite(
+                    &recover_path,
+                    CODEX_RECOVER_PROMPT,
+                    ".codex/prompts/deciduous.recover.md",
+                    preview,
+                )?;
+
+                // Overwrite deciduous.build-test.md prompt
+                let build_test_path = codex_prompts_dir.join("deciduous.build-test.md");
+                write_file_overwrite(
+                    &build_test_path,
+                    CODEX_BUILD_TEST_PROMPT,
+                    ".codex/prompts/deciduous.build-test.md",
+                    preview,
+                )?;
+
+                // Overwrite deciduous.serve-ui.md prompt
+                let serve_ui_path = codex_prompts_dir.join("deciduous.serve-ui.md");
+                write_file_overwrite(
+                    &serve_ui_path,
+                    CODEX_SERVE_UI_PROMPT,
+                    ".codex/prompts/deciduous.serve-ui.md",
+                    preview,
+                )?;
+
+                // Overwrite deciduous.sync-graph.md prompt
+                let sync_graph_path = codex_prompts_dir.join("deciduous.sync-graph.md");
+                write_file_overwrite(
+                    &sync_graph_path,
+                    CODEX_SYNC_GRAPH_PROMPT,
+                    ".codex/prompts/deciduous.sync-graph.md",
+                    preview,
+                )?;
+
+                // Update AGENTS.md section
+                let agents_md_path = cwd.join("AGENTS.md");
+                replace_config_md_section(
+                    &agents_md_path,
+                    AGENTS_MD_SECTION,
+                    "AGENTS.md",
+                    preview,
+                )?;
+            }
+            Editor::Zed => {
+                // Create .zed/prompts directory if needed
+                let zed_prompts_dir = cwd.join(".zed").join("prompts");
+                create_dir_if_missing(&zed_prompts_dir, preview)?;
+
+                // Overwrite every prompt
+                for (name, template) in SHARED_COMMANDS {
+                    write_file_overwrite(
+                        &zed_prompts_dir.join(format!("{}.md", name)),
+                        &zed_prompt(template),
+                        &format!(".zed/prompts/{}.md", name),
+                        preview,
+                    )?;
+                }
 
-            // Update AGENTS.md section
-            let agents_md_path = cwd.join("AGENTS.md");
-            replace_config_md_section(&agents_md_path, AGENTS_MD_SECTION, "AGENTS.md")?;
+                // Update .rules section
+                let rules_path = cwd.join(".rules");
+                replace_config_md_section(&rules_path, AGENTS_MD_SECTION, ".rules", preview)?;
+            }
+            Editor::Gemini => {
+                // Create .gemini/commands/deciduous directory if needed
+                let gemini_cmd_dir = cwd.join(".gemini").join("commands").join("deciduous");
+                create_dir_if_missing(&gemini_cmd_dir, preview)?;
+
+                // Overwrite every command
+                for (name, template) in SHARED_COMMANDS {
+                    write_file_overwrite(
+                        &gemini_cmd_dir.join(format!("{}.toml", name)),
+                        &gemini_command(template),
+                        &format!(".gemini/commands/deciduous/{}.toml", name),
+                        preview,
+                    )?;
+                }
+
+                // Update GEMINI.md section
+                let gemini_md_path = cwd.join("GEMINI.md");
+                replace_config_md_section(
+                    &gemini_md_path,
+                    AGENTS_MD_SECTION,
+                    "GEMINI.md",
+                    preview,
+                )?;
+            }
         }
     }
 
+    if preview != Preview::Off {
+        println!("\n{}", "Dry run: nothing was written".cyan().bold());
+        return Ok(());
+    }
+
+    // Keep the uninit manifest's hashes current for the files rewritten above
+    crate::manifest::record_since(&cwd, &before, false)?;
+
     println!(
         "\n{}",
         format!("Tooling updated for {}!", editor_name)
@@ -3075,14 +3736,19 @@ pub fn update_tooling(editor: Editor) -> Result<(), String> {
 }
 
 /// Append the Decision Graph Workflow section to a config file (CLAUDE.md or AGENTS.md)
-fn append_config_md(path: &Path, section_content: &str, file_name: &str) -> Result<(), String> {
+fn append_config_md(
+    path: &Path,
+    section_content: &str,
+    file_name: &str,
+    preview: Preview,
+) -> Result<(), String> {
     let marker = "## Decision Graph Workflow";
 
     if path.exists() {
         let existing =
             fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", file_name, e))?;
 
-        if existing.contains(marker) {
+        if existing.contains(marker) || managed_section::find(&existing).is_some() {
             println!(
                 "   {} {} (workflow section already present)",
                 "Skipping".yellow(),
@@ -3092,7 +3758,14 @@ fn append_config_md(path: &Path, section_content: &str, file_name: &str) -> Resu
         }
 
         // Append the section
-        let new_content = format!("{}\n{}", existing.trim_end(), section_content);
+        let new_content = format!(
+            "{}\n\n{}",
+            existing.trim_end(),
+            managed_section::block(section_content)
+        );
+        if previewed(path, &new_content, file_name, preview) {
+            return Ok(());
+        }
         fs::write(path, new_content)
             .map_err(|e| format!("Could not update {}: {}", file_name, e))?;
         println!(
@@ -3102,15 +3775,21 @@ fn append_config_md(path: &Path, section_content: &str, file_name: &str) -> Resu
         );
     } else {
         // Create new file
-        let content = format!("# Project Instructions\n{}", section_content);
+        let content = format!(
+            "# Project Instructions\n\n{}",
+            managed_section::block(section_content)
+        );
+        if previewed(path, &content, file_name, preview) {
+            return Ok(());
+        }
         fs::write(path, content).map_err(|e| format!("Could not create {}: {}", file_name, e))?;
         println!("   {} {}", "Creating".green(), file_name);
     }
 
-    Ok(())
+    managed_section::save_base(path, section_content)
 }
 
-fn add_to_gitignore(cwd: &Path) -> Result<(), String> {
+fn add_to_gitignore(cwd: &Path, preview: Preview) -> Result<(), String> {
     let gitignore_path = cwd.join(".gitignore");
     let entry = ".deciduous/";
 
@@ -3132,12 +3811,18 @@ fn add_to_gitignore(cwd: &Path) -> Result<(), String> {
             existing.trim_end(),
             entry
         );
+        if previewed(&gitignore_path, &new_content, ".gitignore", preview) {
+            return Ok(());
+        }
         fs::write(&gitignore_path, new_content)
             .map_err(|e| format!("Could not update .gitignore: {}", e))?;
         println!("   {} .gitignore (added .deciduous/)", "Updated".green());
     } else {
         // Create new .gitignore
         let content = format!("# Deciduous database (local)\n{}\n", entry);
+        if previewed(&gitignore_path, &content, ".gitignore", preview) {
+            return Ok(());
+        }
         fs::write(&gitignore_path, content)
             .map_err(|e| format!("Could not create .gitignore: {}", e))?;
         println!("   {} .gitignore", "Creating".green());
@@ -3148,7 +3833,7 @@ fn add_to_gitignore(cwd: &Path) -> Result<(), String> {
 
 /// Add Codex-specific entries to .gitignore (selective, not entire directory)
 /// Uses negation pattern to allow prompts/ to be committed while ignoring other files
-fn add_codex_to_gitignore(cwd: &Path) -> Result<(), String> {
+fn add_codex_to_gitignore(cwd: &Path, preview: Preview) -> Result<(), String> {
     let gitignore_path = cwd.join(".gitignore");
 
     // The pattern we want to add:
@@ -3182,6 +3867,9 @@ fn add_codex_to_gitignore(cwd: &Path) -> Result<(), String> {
     }
 
     let new_content = format!("{}{}", existing.trim_end(), new_section);
+    if previewed(&gitignore_path, &new_content, ".gitignore", preview) {
+        return Ok(());
+    }
     fs::write(&gitignore_path, new_content)
         .map_err(|e| format!("Could not update .gitignore: {}", e))?;
 
@@ -3340,7 +4028,7 @@ mod tests {
         let new_dir = temp.path().join("new_dir");
 
         assert!(!new_dir.exists());
-        create_dir_if_missing(&new_dir).unwrap();
+        create_dir_if_missing(&new_dir, Preview::Off).unwrap();
         assert!(new_dir.exists());
     }
 
@@ -3348,7 +4036,7 @@ mod tests {
     fn test_create_dir_if_missing_skips_existing() {
         let temp = TempDir::new().unwrap();
         // temp.path() already exists
-        let result = create_dir_if_missing(temp.path());
+        let result = create_dir_if_missing(temp.path(), Preview::Off);
         assert!(result.is_ok());
     }
 
@@ -3358,7 +4046,7 @@ mod tests {
         let file_path = temp.path().join("test.txt");
 
         assert!(!file_path.exists());
-        write_file_if_missing(&file_path, "hello world", "test.txt").unwrap();
+        write_file_if_missing(&file_path, "hello world", "test.txt", Preview::Off).unwrap();
         assert!(file_path.exists());
         assert_eq!(fs::read_to_string(&file_path).unwrap(), "hello world");
     }
@@ -3372,7 +4060,7 @@ mod tests {
         fs::write(&file_path, "original").unwrap();
 
         // Try to write new content - should be skipped
-        write_file_if_missing(&file_path, "new content", "test.txt").unwrap();
+        write_file_if_missing(&file_path, "new content", "test.txt", Preview::Off).unwrap();
 
         // Should still have original content
         assert_eq!(fs::read_to_string(&file_path).unwrap(), "original");
@@ -3387,18 +4075,48 @@ mod tests {
         fs::write(&file_path, "original").unwrap();
 
         // Overwrite with new content
-        write_file_overwrite(&file_path, "new content", "test.txt").unwrap();
+        write_file_overwrite(&file_path, "new content", "test.txt", Preview::Off).unwrap();
 
         // Should have new content
         assert_eq!(fs::read_to_string(&file_path).unwrap(), "new content");
     }
 
+    #[test]
+    fn test_preview_writes_nothing() {
+        let temp = TempDir::new().unwrap();
+        let file_path = temp.path().join("CLAUDE.md");
+        fs::write(&file_path, "# Mine\n").unwrap();
+
+        write_file_overwrite(&file_path, "new content", "CLAUDE.md", Preview::Diff).unwrap();
+        append_config_md(&file_path, CLAUDE_MD_SECTION, "CLAUDE.md", Preview::DryRun).unwrap();
+        add_to_gitignore(temp.path(), Preview::DryRun).unwrap();
+        create_dir_if_missing(&temp.path().join("docs"), Preview::DryRun).unwrap();
+
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "# Mine\n");
+        assert!(!temp.path().join(".gitignore").exists());
+        assert!(!temp.path().join("docs").exists());
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "f"), "");
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n10\n11\n";
+        assert_eq!(
+            unified_diff(old, new, "f"),
+            "--- a/f\n+++ b/f\n\
+             @@ -2,9 +2,10 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n 9\n 10\n+11\n"
+        );
+        let far = "one\n2\n3\n4\n5\n6\n7\n8\n9\nten\n";
+        assert_eq!(unified_diff(old, far, "f").matches("@@ ").count(), 2);
+    }
+
     #[test]
     fn test_add_to_gitignore_creates_new() {
         let temp = TempDir::new().unwrap();
         let gitignore_path = temp.path().join(".gitignore");
 
-        add_to_gitignore(temp.path()).unwrap();
+        add_to_gitignore(temp.path(), Preview::Off).unwrap();
 
         assert!(gitignore_path.exists());
         let content = fs::read_to_string(&gitignore_path).unwrap();
@@ -3413,7 +4131,7 @@ mod tests {
         // Create existing gitignore
         fs::write(&gitignore_path, "node_modules/\n*.log").unwrap();
 
-        add_to_gitignore(temp.path()).unwrap();
+        add_to_gitignore(temp.path(), Preview::Off).unwrap();
 
         let content = fs::read_to_string(&gitignore_path).unwrap();
         assert!(content.contains("node_modules/"));
@@ -3429,7 +4147,7 @@ mod tests {
         let original = "node_modules/\n.deciduous/\n*.log";
         fs::write(&gitignore_path, original).unwrap();
 
-        add_to_gitignore(temp.path()).unwrap();
+        add_to_gitignore(temp.path(), Preview::Off).unwrap();
 
         // Content should be unchanged
         let content = fs::read_to_string(&gitignore_path).unwrap();
@@ -3441,7 +4159,7 @@ mod tests {
         let temp = TempDir::new().unwrap();
         let md_path = temp.path().join("CLAUDE.md");
 
-        append_config_md(&md_path, CLAUDE_MD_SECTION, "CLAUDE.md").unwrap();
+        append_config_md(&md_path, CLAUDE_MD_SECTION, "CLAUDE.md", Preview::Off).unwrap();
 
         assert!(md_path.exists());
         let content = fs::read_to_string(&md_path).unwrap();
@@ -3456,7 +4174,7 @@ mod tests {
         // Create existing file
         fs::write(&md_path, "# My Project\n\nSome instructions.").unwrap();
 
-        append_config_md(&md_path, CLAUDE_MD_SECTION, "CLAUDE.md").unwrap();
+        append_config_md(&md_path, CLAUDE_MD_SECTION, "CLAUDE.md", Preview::Off).unwrap();
 
         let content = fs::read_to_string(&md_path).unwrap();
         assert!(content.contains("# My Project"));
@@ -3472,7 +4190,7 @@ mod tests {
         let original = "# My Project\n\n## Decision Graph Workflow\n\nAlready here.";
         fs::write(&md_path, original).unwrap();
 
-        append_config_md(&md_path, CLAUDE_MD_SECTION, "CLAUDE.md").unwrap();
+        append_config_md(&md_path, CLAUDE_MD_SECTION, "CLAUDE.md", Preview::Off).unwrap();
 
         // Should be unchanged
         let content = fs::read_to_string(&md_path).unwrap();
@@ -3498,7 +4216,7 @@ This should be preserved."#;
         fs::write(&md_path, original).unwrap();
 
         let new_section = "\n## Decision Graph Workflow\n\nNew workflow content!\n";
-        replace_config_md_section(&md_path, new_section, "CLAUDE.md").unwrap();
+        replace_config_md_section(&md_path, new_section, "CLAUDE.md", Preview::Off).unwrap();
 
         let content = fs::read_to_string(&md_path).unwrap();
         assert!(content.contains("New workflow content!"));
@@ -3507,6 +4225,33 @@ This should be preserved."#;
         assert!(content.contains("This should be preserved"));
     }
 
+    #[test]
+    fn test_replace_config_md_section_keeps_edits() {
+        let temp = TempDir::new().unwrap();
+        let md_path = temp.path().join("AGENTS.md");
+        fs::write(&md_path, "# Team rules\n").unwrap();
+
+        let v1 = "\n## Decision Graph Workflow\n\nLog decisions.\n\nSync before push.\n";
+        append_config_md(&md_path, v1, "AGENTS.md", Preview::Off).unwrap();
+        let content = fs::read_to_string(&md_path).unwrap();
+        fs::write(
+            &md_path,
+            format!(
+                "{}\n## Style\n\nTabs.\n",
+                content.replace("Sync before push.", "Sync before push, always.")
+            ),
+        )
+        .unwrap();
+
+        let v2 = "\n## Decision Graph Workflow\n\nLog decisions as you go.\n\nSync before push.\n";
+        replace_config_md_section(&md_path, v2, "AGENTS.md", Preview::Off).unwrap();
+
+        let content = fs::read_to_string(&md_path).unwrap();
+        assert!(content.starts_with("# Team rules\n"));
+        assert!(content.contains("Log decisions as you go.\n\nSync before push, always."));
+        assert!(content.ends_with("## Style\n\nTabs.\n"));
+    }
+
     // === Workflow YAML Tests ===
 
     #[test]
@@ -3536,6 +4281,53 @@ This should be preserved."#;
         assert_eq!(format!("{:?}", Editor::Codex), "Codex");
     }
 
+    #[test]
+    fn test_editor_names() {
+        assert_eq!(editor_names(&[Editor::Claude]), "Claude Code");
+        assert_eq!(
+            editor_names(&[Editor::Claude, Editor::Codex]),
+            "Claude Code and Codex"
+        );
+        assert_eq!(
+            editor_names(&[Editor::Claude, Editor::Opencode, Editor::Codex]),
+            "Claude Code, OpenCode and Codex"
+        );
+    }
+
+    #[test]
+    fn test_zed_prompts_drop_frontmatter_and_arguments() {
+        for (name, template) in SHARED_COMMANDS {
+            let prompt = zed_prompt(template);
+            assert!(
+                prompt.starts_with("# "),
+                "{} should start with a heading",
+                name
+            );
+            assert!(!prompt.contains("description:"), "{}", name);
+            assert!(!prompt.contains("$ARGUMENTS"), "{}", name);
+        }
+        assert!(zed_prompt(OPENCODE_DECISION_CMD).contains("Based on the user's request:"));
+        assert_eq!(zed_prompt("# Title\n\n$ARGUMENTS\n"), "# Title\n");
+    }
+
+    #[test]
+    fn test_gemini_commands_are_valid_toml() {
+        for (name, template) in SHARED_COMMANDS {
+            let command: toml::Value = toml::from_str(&gemini_command(template))
+                .unwrap_or_else(|e| panic!("{} is not valid TOML: {}", name, e));
+            let description = command["description"].as_str().unwrap();
+            assert!(!description.is_empty() && !description.contains("Usage:"));
+            let prompt = command["prompt"].as_str().unwrap();
+            assert!(prompt.starts_with("# "), "{}", name);
+            assert!(!prompt.contains("$ARGUMENTS"), "{}", name);
+        }
+        let decision: toml::Value = toml::from_str(&gemini_command(OPENCODE_DECISION_CMD)).unwrap();
+        assert!(decision["prompt"]
+            .as_str()
+            .unwrap()
+            .contains("Based on {{args}}:"));
+    }
+
     #[test]
     fn test_codex_decision_prompt_has_required_frontmatter() {
         assert!(
@@ -3606,7 +4398,7 @@ This should be preserved."#;
     fn test_add_codex_to_gitignore_creates_new() {
         let temp = TempDir::new().unwrap();
 
-        add_codex_to_gitignore(temp.path()).unwrap();
+        add_codex_to_gitignore(temp.path(), Preview::Off).unwrap();
 
         let gitignore_path = temp.path().join(".gitignore");
         assert!(gitignore_path.exists());
@@ -3626,7 +4418,7 @@ This should be preserved."#;
         // Create existing gitignore
         fs::write(&gitignore_path, "node_modules/\n*.log").unwrap();
 
-        add_codex_to_gitignore(temp.path()).unwrap();
+        add_codex_to_gitignore(temp.path(), Preview::Off).unwrap();
 
         let content = fs::read_to_string(&gitignore_path).unwrap();
         assert!(content.contains("node_modules/"));
@@ -3638,8 +4430,8 @@ This should be preserved."#;
         let temp = TempDir::new().unwrap();
 
         // Run twice
-        add_codex_to_gitignore(temp.path()).unwrap();
-        add_codex_to_gitignore(temp.path()).unwrap();
+        add_codex_to_gitignore(temp.path(), Preview::Off).unwrap();
+        add_codex_to_gitignore(temp.path(), Preview::Off).unwrap();
 
         let gitignore_path = temp.path().join(".gitignore");
         let content = fs::read_to_string(&gitignore_path).unwrap();
@@ -0,0 +1,602 @@
+//! Jira Integration
+//!
+//! Jira implementation of the `Forge` trait, for teams whose tracker is Jira
+//! rather than their Git host. Talks to the REST API (v2, which takes plain
+//! text bodies on both Cloud and Data Center) through `curl`, authenticating
+//! with `JIRA_EMAIL` + `JIRA_API_TOKEN` (Cloud) or a `JIRA_TOKEN` personal
+//! access token (Data Center). The site comes from `[forge] jira_url` or
+//! `JIRA_URL`.
+//!
+//! The "repo" is a project key: issue number 123 in project `PROJ` is
+//! `PROJ-123`. Closing and reopening follow the workflow's transitions into
+//! and out of the "done" status category, milestones are fix versions, and
+//! labels are free-form. Jira has no pull requests; those calls fail with a
+//! pointer back to the Git host.
+
+use crate::forge::{Forge, ForgeKind, Milestone, PullRequest, ReviewThread};
+use crate::github::{CommentAuthor, GitHubComment, GitHubError, GitHubIssue, Result};
+use crate::remote_patch::{curl_auth_from_env, curl_json};
+use serde_json::{json, Value};
+
+/// Default issue type for new issues
+const DEFAULT_ISSUE_TYPE: &str = "Task";
+
+/// Follow at most this many pages when searching
+const MAX_PAGES: usize = 20;
+
+const ISSUE_FIELDS: &str = "summary,description,status,created,updated";
+
+/// Jira client using the REST API via `curl`
+pub struct JiraClient {
+    base_url: Option<String>,
+    project: Option<String>,
+    issue_type: String,
+}
+
+/// Split an issue key like "PROJ-123" into ("PROJ", 123)
+pub fn parse_issue_key(key: &str) -> Option<(String, i32)> {
+    let (project, number) = key.trim().rsplit_once('-')?;
+    let valid = project
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic())
+        && project
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let number = number.parse().ok().filter(|n| *n > 0)?;
+    valid.then(|| (project.to_uppercase(), number))
+}
+
+fn query_string(params: &[(&str, String)]) -> Result<String> {
+    serde_urlencoded::to_string(params).map_err(|e| GitHubError::ParseError {
+        message: e.to_string(),
+    })
+}
+
+/// Quote a value for JQL
+fn jql_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Jira labels can't contain spaces
+fn label(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+fn not_found(number: i32) -> impl Fn(GitHubError) -> GitHubError {
+    move |e| match e {
+        GitHubError::CommandFailed { ref stderr, .. } if stderr.starts_with("HTTP 404") => {
+            GitHubError::IssueNotFound { number }
+        }
+        other => other,
+    }
+}
+
+fn no_pull_requests() -> GitHubError {
+    GitHubError::CommandFailed {
+        command: "jira".to_string(),
+        stderr: "Jira tracks issues only; pull requests stay on your Git host".to_string(),
+    }
+}
+
+fn issue_from_json(v: &Value, base_url: &str) -> Option<GitHubIssue> {
+    let key = v.get("key")?.as_str()?;
+    let (_, number) = parse_issue_key(key)?;
+    let fields = v.get("fields")?;
+    let text = |name: &str| {
+        fields
+            .get(name)
+            .and_then(|f| f.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let done = fields
+        .pointer("/status/statusCategory/key")
+        .and_then(|k| k.as_str())
+        == Some("done");
+    Some(GitHubIssue {
+        number,
+        title: fields.get("summary")?.as_str()?.to_string(),
+        body: text("description"),
+        state: if done { "closed" } else { "open" }.to_string(),
+        html_url: format!("{}/browse/{}", base_url, key),
+        created_at: text("created"),
+        updated_at: text("updated"),
+    })
+}
+
+fn comment_from_json(v: &Value) -> Option<GitHubComment> {
+    let author = v.get("author");
+    let login = author
+        .and_then(|a| a.get("displayName").or_else(|| a.get("name")))
+        .and_then(|n| n.as_str())
+        .unwrap_or("unknown");
+    Some(GitHubComment {
+        id: v.get("id")?.as_str()?.parse().ok()?,
+        body: v.get("body")?.as_str()?.to_string(),
+        author: CommentAuthor {
+            login: login.to_string(),
+        },
+        created_at: v
+            .get("created")
+            .and_then(|d| d.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        created_at_alt: None,
+    })
+}
+
+/// The messages in a Jira error body
+///
+/// `{"errorMessages": [...], "errors": {"field": "message"}}`
+fn error_message(v: &Value) -> Option<String> {
+    let mut messages: Vec<String> = v["errorMessages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|m| m.as_str().map(String::from))
+        .collect();
+    if let Some(errors) = v["errors"].as_object() {
+        messages.extend(
+            errors
+                .iter()
+                .map(|(field, m)| format!("{}: {}", field, m.as_str().unwrap_or_default())),
+        );
+    }
+    Some(messages.join("; "))
+}
+
+/// The transition to take from `transitions` to close (`done`) or reopen an issue
+fn pick_transition(transitions: &Value, done: bool) -> Option<String> {
+    let transitions = transitions.get("transitions")?.as_array()?;
+    let category = |t: &&Value| {
+        t.pointer("/to/statusCategory/key")
+            .and_then(|k| k.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let pick = |wanted: &str| {
+        transitions
+            .iter()
+            .find(|t| category(t) == wanted)
+            .and_then(|t| t.get("id")?.as_str().map(String::from))
+    };
+    if done {
+        pick("done")
+    } else {
+        pick("new").or_else(|| pick("indeterminate"))
+    }
+}
+
+impl JiraClient {
+    /// Create a new client for `project` on the site at `base_url`
+    pub fn new(
+        base_url: Option<String>,
+        project: Option<String>,
+        issue_type: Option<String>,
+    ) -> Self {
+        Self {
+            base_url: base_url
+                .or_else(|| std::env::var("JIRA_URL").ok())
+                .map(|u| u.trim_end_matches('/').to_string())
+                .filter(|u| !u.is_empty()),
+            project: project.map(|p| p.to_uppercase()),
+            issue_type: issue_type.unwrap_or_else(|| DEFAULT_ISSUE_TYPE.to_string()),
+        }
+    }
+
+    /// Get the project key
+    pub fn repo_name(&self) -> Option<&str> {
+        self.project.as_deref()
+    }
+
+    fn base_url(&self) -> Result<&str> {
+        self.base_url
+            .as_deref()
+            .ok_or_else(|| GitHubError::ParseError {
+                message: "Jira site unknown; set [forge] jira_url or JIRA_URL".to_string(),
+            })
+    }
+
+    fn project(&self) -> Result<&str> {
+        self.project
+            .as_deref()
+            .ok_or_else(|| GitHubError::ParseError {
+                message: "Jira project unknown; set [forge] jira_project or pass --repo PROJ"
+                    .to_string(),
+            })
+    }
+
+    /// The issue key for `number`, e.g. "PROJ-123"
+    fn key(&self, number: i32) -> Result<String> {
+        Ok(format!("{}-{}", self.project()?, number))
+    }
+
+    /// A Data Center `JIRA_TOKEN`, else Cloud `JIRA_EMAIL` + `JIRA_API_TOKEN`
+    fn auth_config() -> Option<String> {
+        curl_auth_from_env("JIRA_TOKEN", "JIRA_EMAIL", "JIRA_API_TOKEN")
+    }
+
+    /// Call `path` under the site's `/rest/api/2/`
+    fn request(&self, method: &str, path: &str, body: Option<&Value>) -> Result<Value> {
+        let url = format!("{}/rest/api/2/{}", self.base_url()?, path);
+        curl_json(
+            method,
+            &url,
+            &Self::auth_config().unwrap_or_default(),
+            body,
+            error_message,
+        )
+    }
+
+    /// Issues matching `jql`, following pages
+    ///
+    /// Cloud pages `search/jql` by token; Data Center only has `search`,
+    /// paged by offset.
+    fn search(&self, jql: &str) -> Result<Vec<GitHubIssue>> {
+        let base_url = self.base_url()?.to_string();
+        let mut issues = Vec::new();
+        let mut token: Option<String> = None;
+        let mut start_at: Option<usize> = None;
+        for _ in 0..MAX_PAGES {
+            let mut params = vec![
+                ("jql", jql.to_string()),
+                ("fields", ISSUE_FIELDS.to_string()),
+                ("maxResults", "100".to_string()),
+            ];
+            params.extend(token.clone().map(|t| ("nextPageToken", t)));
+            params.extend(start_at.map(|s| ("startAt", s.to_string())));
+            let params = query_string(&params)?;
+            let page = match start_at {
+                None => match self.request("GET", &format!("search/jql?{}", params), None) {
+                    Err(GitHubError::CommandFailed { stderr, .. })
+                        if stderr.starts_with("HTTP 404") && issues.is_empty() =>
+                    {
+                        start_at = Some(0);
+                        continue;
+                    }
+                    page => page?,
+                },
+                Some(_) => self.request("GET", &format!("search?{}", params), None)?,
+            };
+            let found = page["issues"].as_array().cloned().unwrap_or_default();
+            issues.extend(found.iter().filter_map(|i| issue_from_json(i, &base_url)));
+            match start_at.as_mut() {
+                Some(offset) => {
+                    *offset += found.len();
+                    let total = page["total"].as_u64().unwrap_or(0) as usize;
+                    if found.is_empty() || *offset >= total {
+                        break;
+                    }
+                }
+                None => {
+                    token = page["nextPageToken"].as_str().map(String::from);
+                    if token.is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(issues)
+    }
+
+    fn update_fields(&self, number: i32, fields: Value) -> Result<()> {
+        let path = format!("issue/{}", self.key(number)?);
+        self.request("PUT", &path, Some(&json!({ "fields": fields })))
+            .map(|_| ())
+            .map_err(not_found(number))
+    }
+
+    /// Move an issue into (`done`) or out of the "done" status category
+    fn transition(&self, number: i32, done: bool) -> Result<()> {
+        let key = self.key(number)?;
+        let path = format!("issue/{}/transitions", key);
+        let transitions = self
+            .request("GET", &path, None)
+            .map_err(not_found(number))?;
+        let id = pick_transition(&transitions, done).ok_or_else(|| GitHubError::CommandFailed {
+            command: format!("jira transition {}", key),
+            stderr: format!(
+                "No workflow transition {} {}",
+                if done { "closes" } else { "reopens" },
+                key
+            ),
+        })?;
+        self.request("POST", &path, Some(&json!({ "transition": { "id": id } })))?;
+        Ok(())
+    }
+
+    /// Issue counts for a fix version: (unresolved, fixed)
+    fn version_counts(&self, id: &str) -> Result<(i32, i32)> {
+        let unresolved =
+            self.request("GET", &format!("version/{}/unresolvedIssueCount", id), None)?;
+        let related = self.request("GET", &format!("version/{}/relatedIssueCounts", id), None)?;
+        Ok((
+            unresolved["issuesUnresolvedCount"].as_i64().unwrap_or(0) as i32,
+            related["issuesFixedCount"].as_i64().unwrap_or(0) as i32,
+        ))
+    }
+}
+
+impl Forge for JiraClient {
+    fn kind(&self) -> ForgeKind {
+        ForgeKind::Jira
+    }
+
+    fn repo_name(&self) -> Option<&str> {
+        JiraClient::repo_name(self)
+    }
+
+    fn check_auth(&self) -> Result<bool> {
+        if Self::auth_config().is_none() {
+            return Ok(false);
+        }
+        Ok(self.request("GET", "myself", None).is_ok())
+    }
+
+    fn create_issue(&self, title: &str, body: &str, labels: &[&str]) -> Result<GitHubIssue> {
+        let fields = json!({
+            "project": { "key": self.project()? },
+            "summary": title,
+            "description": body,
+            "issuetype": { "name": self.issue_type },
+            "labels": labels.iter().map(|l| label(l)).collect::<Vec<_>>(),
+        });
+        let created = self.request("POST", "issue", Some(&json!({ "fields": fields })))?;
+        let (_, number) = created
+            .get("key")
+            .and_then(|k| k.as_str())
+            .and_then(parse_issue_key)
+            .ok_or_else(|| GitHubError::ParseError {
+                message: "Unexpected issue response".to_string(),
+            })?;
+        self.get_issue(number)
+    }
+
+    fn get_issue(&self, number: i32) -> Result<GitHubIssue> {
+        let key = self.key(number)?;
+        let path = format!("issue/{}?fields={}", key, ISSUE_FIELDS);
+        let issue = self
+            .request("GET", &path, None)
+            .map_err(not_found(number))?;
+        issue_from_json(&issue, self.base_url()?).ok_or_else(|| GitHubError::ParseError {
+            message: format!("Unexpected response for issue {}", key),
+        })
+    }
+
+    fn update_issue_body(&self, number: i32, body: &str) -> Result<()> {
+        self.update_fields(number, json!({ "description": body }))
+    }
+
+    fn update_issue_title(&self, number: i32, title: &str) -> Result<()> {
+        self.update_fields(number, json!({ "summary": title }))
+    }
+
+    fn close_issue(&self, number: i32) -> Result<()> {
+        self.transition(number, true)
+    }
+
+    fn reopen_issue(&self, number: i32) -> Result<()> {
+        self.transition(number, false)
+    }
+
+    fn get_issue_comments(&self, number: i32) -> Result<Vec<GitHubComment>> {
+        let path = format!("issue/{}/comment?maxResults=100", self.key(number)?);
+        let page = self
+            .request("GET", &path, None)
+            .map_err(not_found(number))?;
+        Ok(page["comments"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(comment_from_json)
+            .collect())
+    }
+
+    fn add_comment(&self, number: i32, body: &str) -> Result<()> {
+        let path = format!("issue/{}/comment", self.key(number)?);
+        self.request("POST", &path, Some(&json!({ "body": body })))?;
+        Ok(())
+    }
+
+    fn list_issues_with_label(&self, name: &str) -> Result<Vec<GitHubIssue>> {
+        self.search(&format!(
+            "project = {} AND labels = {} ORDER BY key",
+            jql_string(self.project()?),
+            jql_string(&label(name))
+        ))
+    }
+
+    fn find_issue_by_title(&self, title: &str) -> Result<Option<GitHubIssue>> {
+        let issues = self.search(&format!(
+            "project = {} AND summary ~ {}",
+            jql_string(self.project()?),
+            jql_string(title)
+        ))?;
+        Ok(issues
+            .into_iter()
+            .find(|i| i.title.eq_ignore_ascii_case(title)))
+    }
+
+    fn label_exists(&self, _name: &str) -> Result<bool> {
+        // Labels come into being when an issue uses them
+        Ok(true)
+    }
+
+    fn create_label(&self, _name: &str, _description: &str, _color: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn list_pull_requests(&self, _state: &str) -> Result<Vec<PullRequest>> {
+        Err(no_pull_requests())
+    }
+
+    fn get_pull_request(&self, _number: i32) -> Result<PullRequest> {
+        Err(no_pull_requests())
+    }
+
+    fn get_pull_request_comments(&self, _number: i32) -> Result<Vec<GitHubComment>> {
+        Err(no_pull_requests())
+    }
+
+    fn add_pull_request_comment(&self, _number: i32, _body: &str) -> Result<()> {
+        Err(no_pull_requests())
+    }
+
+    fn update_pull_request_comment(
+        &self,
+        _number: i32,
+        _comment_id: i64,
+        _body: &str,
+    ) -> Result<()> {
+        Err(no_pull_requests())
+    }
+
+    fn get_review_threads(&self, _number: i32) -> Result<Vec<ReviewThread>> {
+        Err(no_pull_requests())
+    }
+
+    fn list_milestones(&self) -> Result<Vec<Milestone>> {
+        let path = format!("project/{}/versions", self.project()?);
+        let versions = self.request("GET", &path, None)?;
+        let mut milestones = Vec::new();
+        for v in versions.as_array().into_iter().flatten() {
+            let (Some(id), Some(name)) = (
+                v.get("id").and_then(|i| i.as_str()),
+                v.get("name").and_then(|n| n.as_str()),
+            ) else {
+                continue;
+            };
+            let (open_issues, fixed) = self.version_counts(id)?;
+            let released = v.get("released").and_then(|r| r.as_bool()) == Some(true);
+            milestones.push(Milestone {
+                number: id.parse().unwrap_or(0),
+                title: name.to_string(),
+                state: if released { "closed" } else { "open" }.to_string(),
+                open_issues,
+                closed_issues: (fixed - open_issues).max(0),
+            });
+        }
+        Ok(milestones)
+    }
+
+    fn create_milestone(&self, title: &str, description: &str) -> Result<Milestone> {
+        let body = json!({
+            "name": title,
+            "description": description,
+            "project": self.project()?,
+        });
+        let created = self.request("POST", "version", Some(&body))?;
+        Ok(Milestone {
+            number: created["id"]
+                .as_str()
+                .and_then(|i| i.parse().ok())
+                .unwrap_or(0),
+            title: title.to_string(),
+            state: "open".to_string(),
+            open_issues: 0,
+            closed_issues: 0,
+        })
+    }
+
+    fn set_issue_milestone(&self, number: i32, milestone: &str) -> Result<()> {
+        self.update_fields(number, json!({ "fixVersions": [{ "name": milestone }] }))
+    }
+
+    fn issue_ref(&self, number: i32) -> String {
+        self.key(number).unwrap_or_else(|_| format!("#{}", number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_creation() {
+        let client = JiraClient::new(
+            Some("https://acme.atlassian.net/".to_string()),
+            Some("proj".to_string()),
+            None,
+        );
+        assert_eq!(client.repo_name(), Some("PROJ"));
+        assert_eq!(client.kind(), ForgeKind::Jira);
+        assert_eq!(client.base_url().unwrap(), "https://acme.atlassian.net");
+        assert_eq!(client.key(42).unwrap(), "PROJ-42");
+        assert_eq!(client.issue_ref(42), "PROJ-42");
+        assert_eq!(client.issue_type, "Task");
+        assert!(JiraClient::new(None, None, None).project().is_err());
+    }
+
+    #[test]
+    fn test_parse_issue_key() {
+        assert_eq!(parse_issue_key("PROJ-123"), Some(("PROJ".to_string(), 123)));
+        assert_eq!(parse_issue_key("ab_2-7"), Some(("AB_2".to_string(), 7)));
+        assert_eq!(parse_issue_key("123"), None);
+        assert_eq!(parse_issue_key("2PROJ-1"), None);
+        assert_eq!(parse_issue_key("PROJ-0"), None);
+    }
+
+    #[test]
+    fn test_issue_and_comment_conversion() {
+        let issue = issue_from_json(
+            &json!({
+                "key": "PROJ-7",
+                "fields": {
+                    "summary": "Roadmap item",
+                    "description": "Body",
+                    "status": {"name": "Done", "statusCategory": {"key": "done"}},
+                    "created": "2025-01-01T00:00:00.000+0000",
+                    "updated": "2025-01-02T00:00:00.000+0000"
+                }
+            }),
+            "https://acme.atlassian.net",
+        )
+        .unwrap();
+        assert_eq!(issue.number, 7);
+        assert_eq!(issue.state, "closed");
+        assert_eq!(issue.body, "Body");
+        assert_eq!(issue.html_url, "https://acme.atlassian.net/browse/PROJ-7");
+
+        let comment = comment_from_json(&json!({
+            "id": "10001",
+            "body": "Looks good",
+            "author": {"displayName": "Amy"},
+            "created": "2025-01-03T00:00:00.000+0000"
+        }))
+        .unwrap();
+        assert_eq!(comment.id, 10001);
+        assert_eq!(comment.author.login, "Amy");
+    }
+
+    #[test]
+    fn test_pick_transition() {
+        let transitions = json!({"transitions": [
+            {"id": "11", "to": {"statusCategory": {"key": "indeterminate"}}},
+            {"id": "21", "to": {"statusCategory": {"key": "done"}}},
+            {"id": "31", "to": {"statusCategory": {"key": "new"}}}
+        ]});
+        assert_eq!(pick_transition(&transitions, true).as_deref(), Some("21"));
+        assert_eq!(pick_transition(&transitions, false).as_deref(), Some("31"));
+        assert_eq!(pick_transition(&json!({"transitions": []}), true), None);
+    }
+
+    #[test]
+    fn test_jql_and_labels() {
+        assert_eq!(jql_string(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(label("release  notes"), "release-notes");
+    }
+
+    #[test]
+    fn test_error_message() {
+        let body = json!({
+            "errorMessages": ["Issue does not exist"],
+            "errors": {"summary": "Field required"}
+        });
+        assert_eq!(
+            error_message(&body).as_deref(),
+            Some("Issue does not exist; summary: Field required")
+        );
+        assert_eq!(error_message(&json!({})).as_deref(), Some(""));
+    }
+}
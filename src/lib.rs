@@ -19,6 +19,10 @@
 //! | `outcome` | What happened |
 //! | `observation` | Technical insights |
 //!
+//! These are the variants of [`NodeType`]; any other type is kept as
+//! `NodeType::Custom`. Statuses and edge types work the same way
+//! ([`NodeStatus`], [`EdgeType`]).
+//!
 //! # Quick Start
 //!
 //! ```no_run
@@ -27,41 +31,121 @@
 //! let db = Database::new("deciduous.db").unwrap();
 //!
 //! // Add a goal
-//! let goal_id = db.add_node("goal", "Implement feature X", None, Some(90), None).unwrap();
+//! let goal_id = db.node("goal", "Implement feature X").confidence(90).insert().unwrap();
 //!
 //! // Add an action linked to it
-//! let action_id = db.add_node("action", "Writing the code", None, Some(85), None).unwrap();
-//! db.add_edge(goal_id, action_id, "leads_to", None).unwrap();
+//! let action_id = db
+//!     .node("action", "Writing the code")
+//!     .confidence(85)
+//!     .commit_head()
+//!     .files(["src/feature_x.rs"])
+//!     .insert()
+//!     .unwrap();
+//! db.edge(goal_id, action_id).rationale("first step").insert().unwrap();
 //!
 //! // Query the graph
 //! let graph = db.get_graph().unwrap();
 //! println!("Nodes: {}, Edges: {}", graph.nodes.len(), graph.edges.len());
 //! ```
 
+pub mod adr_import;
+pub mod ask;
+#[cfg(feature = "forge")]
+pub mod bitbucket;
+#[cfg(feature = "forge")]
+pub mod cache;
+pub mod capture;
+pub mod ci;
+#[cfg(feature = "server")]
+pub mod claude_import;
 pub mod config;
+pub mod daemon;
 pub mod db;
+pub mod dedupe;
 pub mod diff;
+pub mod digest;
+pub mod embed;
+pub mod encryption;
 pub mod export;
+pub mod federation;
+pub mod file_index;
+pub mod focus;
+#[cfg(feature = "forge")]
+pub mod forge;
+pub mod gc;
+pub mod git_sync;
+#[cfg(feature = "forge")]
 pub mod github;
+#[cfg(feature = "forge")]
+pub mod gitlab;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod handoff;
+pub mod hook_script;
+pub mod hooks;
+pub mod hub;
 pub mod init;
 pub mod interceptor;
+#[cfg(feature = "forge")]
+pub mod jira;
+pub mod lifecycle;
+pub mod managed_section;
+pub mod manifest;
+pub mod mcp;
+pub mod mcp_trace;
+pub mod metrics;
+pub mod model;
+pub mod notion;
+pub mod otlp;
+pub mod patch_history;
+#[cfg(feature = "forge")]
+pub mod pr_merged;
+pub mod pricing;
+pub mod project;
+#[cfg(feature = "server")]
+pub mod proxy;
+pub mod redact;
+pub mod remote_patch;
+pub mod replay;
 pub mod roadmap;
 pub mod schema;
+#[cfg(feature = "server")]
 pub mod serve;
+pub mod signing;
+pub mod snapshot;
+pub mod stale;
+pub mod store;
+pub mod subagents;
+pub mod suggest;
+pub mod tool_stats;
+pub mod trace_commits;
+pub mod trace_diff;
+pub mod trace_share;
+pub mod trailers;
+pub mod transcript;
+#[cfg(feature = "tui")]
 pub mod tui;
+pub mod webhook;
 
 pub use config::Config;
 pub use db::{
-    build_metadata_json, get_current_git_branch, get_current_git_commit, CheckboxState, CommandLog,
-    Database, DbRecord, DbSummary, DecisionContext, DecisionEdge, DecisionGraph, DecisionNode,
-    DecisionSession, GitHubIssueCache, RoadmapConflict, RoadmapItem, RoadmapSyncState,
-    TraceContent, TraceSession, TraceSpan, CURRENT_SCHEMA,
+    build_metadata_json, get_current_git_branch, get_current_git_commit, get_git_user_name,
+    AppliedPatch, CheckboxState, CommandLog, Database, DbEvent, DbRecord, DbSummary,
+    DecisionContext, DecisionEdge, DecisionGraph, DecisionNode, DecisionSession, EdgeBuilder,
+    GitHubIssueCache, GitHubPrCache, GraphPage, NodeBuilder, NodeConflict, NodeExport,
+    RoadmapConflict, RoadmapItem, RoadmapSyncState, TraceContent, TraceSession, TraceSpan,
+    CURRENT_SCHEMA,
+};
+pub use diff::{
+    ApplyResult, ConflictStrategy, GraphPatch, NodeUpdate, PatchEdge, PatchNode, Tombstone,
 };
-pub use diff::{ApplyResult, GraphPatch, PatchEdge, PatchNode};
 pub use export::{
-    filter_graph_by_ids, filter_graph_from_roots, generate_pr_writeup, graph_to_dot,
-    parse_node_range, DotConfig, WriteupConfig,
+    filter_graph_by_ids, filter_graph_from_roots, generate_pr_writeup, generate_trace_writeup,
+    graph_to_dot, parse_node_range, roadmap_badge, scrub_edge, scrub_graph, scrub_node, DotConfig,
+    SessionActivity, WriteupConfig,
 };
+pub use model::{EdgeType, NodeStatus, NodeType};
+pub use store::{open_store, GraphStore};
 
 // Re-export TS trait for downstream use
 #[cfg(feature = "ts-rs")]
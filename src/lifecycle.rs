@@ -0,0 +1,115 @@
+//! Shell hooks for graph events
+//!
+//! `[hooks]` in `.deciduous/config.toml` can name a command for
+//! `on_node_add`, `on_sync`, `on_session_end` and `on_stale`. The command runs through
+//! the shell with a JSON payload on stdin and `DECIDUOUS_EVENT` set, so teams
+//! can send notifications, write their own exports or run checks without
+//! forking the crate. A failing hook is reported; the event still happened.
+
+use crate::config::HooksConfig;
+use serde_json::Value;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// A node was created with `deciduous add`
+pub const ON_NODE_ADD: &str = "on_node_add";
+
+/// `deciduous sync` wrote the graph export
+pub const ON_SYNC: &str = "on_sync";
+
+/// A trace session ended
+pub const ON_SESSION_END: &str = "on_session_end";
+
+/// `deciduous stale --notify` found stuck decisions or actions
+pub const ON_STALE: &str = "on_stale";
+
+/// Environment variable holding the event name while a hook runs
+pub const EVENT_ENV: &str = "DECIDUOUS_EVENT";
+
+/// The JSON a hook receives: `{"event": ..., "timestamp": ..., <data fields>}`
+pub fn payload(event: &str, data: Value) -> Value {
+    let mut payload = serde_json::json!({
+        "event": event,
+        "timestamp": chrono::Local::now().to_rfc3339(),
+    });
+    if let (Some(payload), Value::Object(data)) = (payload.as_object_mut(), data) {
+        payload.extend(data);
+    }
+    payload
+}
+
+/// Run the hook configured for `event`, if any
+///
+/// Returns false when no hook is configured. The hook's output goes to the
+/// terminal; a non-zero exit is an error.
+pub fn run_hook(hooks: &HooksConfig, event: &str, data: Value) -> Result<bool, String> {
+    let Some(command) = hooks.command_for(event) else {
+        return Ok(false);
+    };
+    let body = serde_json::to_vec(&payload(event, data))
+        .map_err(|e| format!("Failed to serialize {} payload: {}", event, e))?;
+
+    let mut shell = if cfg!(windows) {
+        let mut c = Command::new("cmd");
+        c.arg("/C");
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c");
+        c
+    };
+    let mut child = shell
+        .arg(command)
+        .env(EVENT_ENV, event)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {} hook: {}", event, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that ignores its input closes the pipe early; that's fine
+        let _ = stdin.write_all(&body);
+    }
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to run {} hook: {}", event, e))?;
+    if !status.success() {
+        return Err(format!(
+            "{} hook `{}` exited with {}",
+            event,
+            command,
+            status
+                .code()
+                .map_or("a signal".to_string(), |c| format!("code {}", c))
+        ));
+    }
+    Ok(true)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_hook() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("payload.json");
+        let hooks = HooksConfig {
+            on_node_add: Some(format!("cat > '{}'", out.display())),
+            on_sync: Some("echo \"$DECIDUOUS_EVENT\" >&2; exit 3".to_string()),
+            on_session_end: None,
+            on_stale: None,
+        };
+
+        let data = serde_json::json!({ "node": { "id": 7, "title": "Ship it" } });
+        assert_eq!(run_hook(&hooks, ON_NODE_ADD, data), Ok(true));
+        let written: Value = serde_json::from_str(&std::fs::read_to_string(&out).unwrap()).unwrap();
+        assert_eq!(written["event"], ON_NODE_ADD);
+        assert_eq!(written["node"]["title"], "Ship it");
+
+        let err = run_hook(&hooks, ON_SYNC, serde_json::json!({})).unwrap_err();
+        assert!(err.contains("code 3"));
+        assert_eq!(
+            run_hook(&hooks, ON_SESSION_END, serde_json::json!({})),
+            Ok(false)
+        );
+    }
+}
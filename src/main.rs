@@ -1,10 +1,24 @@
 use chrono::Local;
 use clap::{CommandFactory, Parser, Subcommand};
 use colored::Colorize;
-use deciduous::github::{ensure_roadmap_label, GitHubClient};
+use deciduous::capture::CaptureFilter;
+use deciduous::claude_import::{find_transcripts, import_session, parse_transcript};
+use deciduous::config::ExportProfile;
+use deciduous::db::node_touches_file;
+use deciduous::forge::{detect_forge, ensure_milestone, ensure_roadmap_label, CommentUpsert};
+use deciduous::github::GitHubClient;
+use deciduous::mcp_trace;
+use deciduous::pricing::{format_cost, PriceTable};
+use deciduous::redact::{store_content, Redactor};
 use deciduous::roadmap::{
-    generate_issue_body, parse_roadmap, write_roadmap_with_metadata, RoadmapSection,
+    add_hook_block, check_items_in_body, diff_remote_checkboxes, generate_issue_body, goal_prompt,
+    item_branch_name, milestone_groups, parse_issue_body_checkboxes, parse_roadmap,
+    remove_hook_block, roadmap_drift, section_board_status, set_checkbox_lines,
+    split_completed_sections, strip_item_annotations, write_roadmap_with_metadata, BoardStatus,
+    RemoteChange, RoadmapSection,
 };
+use deciduous::subagents::{link_session_subagents, tree_order};
+use deciduous::trace_commits::link_session_commits;
 use deciduous::{
     filter_graph_by_ids, generate_pr_writeup, graph_to_dot, parse_node_range, Config, Database,
     DotConfig, WriteupConfig,
@@ -22,6 +36,11 @@ use std::process::Command as ProcessCommand;
 struct Args {
     #[command(subcommand)]
     command: Command,
+
+    /// Only read and change this sub-project's nodes ([projects] in
+    /// config.toml); `add` records it on the new node instead of the detected one
+    #[arg(long, global = true, value_name = "PROJECT")]
+    scope: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -29,43 +48,106 @@ enum Command {
     /// Initialize deciduous in current directory
     Init {
         /// Initialize for Claude Code (creates .claude/commands/ and CLAUDE.md)
-        #[arg(long, group = "editor")]
+        #[arg(long)]
         claude: bool,
 
         /// Initialize for Windsurf (creates .windsurf/rules/ and AGENTS.md)
-        #[arg(long, group = "editor")]
+        #[arg(long)]
         windsurf: bool,
 
         /// Initialize for OpenCode (creates .opencode/command/ and AGENTS.md)
-        #[arg(long, group = "editor")]
+        #[arg(long)]
         opencode: bool,
 
         /// Initialize for Codex (creates .codex/prompts/ and AGENTS.md)
-        #[arg(long, group = "editor")]
+        #[arg(long)]
         codex: bool,
 
+        /// Initialize for Zed (creates .zed/prompts/ and .rules)
+        #[arg(long)]
+        zed: bool,
+
+        /// Initialize for Gemini CLI (creates .gemini/commands/ and GEMINI.md)
+        #[arg(long)]
+        gemini: bool,
+
         /// Overwrite existing files (useful for updating outdated CLAUDE.md)
         #[arg(long, short = 'f')]
         force: bool,
+
+        /// Show which files would be created or overwritten without writing any
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Like --dry-run, with a unified diff for each existing file that would change
+        #[arg(long)]
+        diff: bool,
+
+        /// Skip the GitHub Actions workflows in .github/workflows/
+        #[arg(long)]
+        no_workflows: bool,
+
+        /// Skip the GitHub Pages viewer in docs/ and its deploy workflow
+        #[arg(long)]
+        no_pages: bool,
+
+        /// Only create .deciduous/ and the editor commands (no instructions file, workflows or docs/)
+        #[arg(long, conflicts_with_all = ["no_workflows", "no_pages"])]
+        commands_only: bool,
     },
 
     /// Update tooling files to latest version (overwrites existing)
     Update {
         /// Update Claude Code files (.claude/commands/, CLAUDE.md)
-        #[arg(long, group = "editor")]
+        #[arg(long)]
         claude: bool,
 
         /// Update Windsurf files (.windsurf/rules/, AGENTS.md)
-        #[arg(long, group = "editor")]
+        #[arg(long)]
         windsurf: bool,
 
         /// Update OpenCode files (.opencode/command/, AGENTS.md)
-        #[arg(long, group = "editor")]
+        #[arg(long)]
         opencode: bool,
 
         /// Update Codex files (.codex/prompts/, AGENTS.md)
-        #[arg(long, group = "editor")]
+        #[arg(long)]
         codex: bool,
+
+        /// Update Zed files (.zed/prompts/, .rules)
+        #[arg(long)]
+        zed: bool,
+
+        /// Update Gemini CLI files (.gemini/commands/, GEMINI.md)
+        #[arg(long)]
+        gemini: bool,
+
+        /// Show which files would be overwritten without writing any
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Like --dry-run, with a unified diff for each file that would change
+        #[arg(long)]
+        diff: bool,
+    },
+
+    /// Remove the files `init` created (as recorded in .deciduous/manifest.json)
+    Uninit {
+        /// Keep .deciduous/ (database, config and patches)
+        #[arg(long)]
+        keep_db: bool,
+
+        /// Also remove files that were edited since init
+        #[arg(long)]
+        force: bool,
+
+        /// Show what would be removed without removing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Remove without asking for confirmation
+        #[arg(long)]
+        yes: bool,
     },
 
     /// Add a new node to the decision graph
@@ -107,6 +189,18 @@ enum Command {
         /// Skip auto-detection of git branch
         #[arg(long)]
         no_branch: bool,
+
+        /// Pull request number to link this node to (its state is cached for TUI/web)
+        #[arg(long)]
+        pr: Option<i32>,
+
+        /// Don't link the node from the focused goal
+        #[arg(long)]
+        no_focus: bool,
+
+        /// Issue this node tracks: a Jira key (PROJ-123) or #N on the configured forge
+        #[arg(long)]
+        issue: Option<String>,
     },
 
     /// Add an edge between nodes
@@ -135,6 +229,22 @@ enum Command {
         status: String,
     },
 
+    /// Delete a node and its edges (the deletion goes out in the next exported patch)
+    Delete {
+        /// Node ID
+        id: i32,
+    },
+
+    /// Merge a duplicate node into another: its edges, span links and any
+    /// description, prompt, commit or files KEEP lacks move over, then it's deleted
+    Merge {
+        /// Node that stays
+        keep: i32,
+
+        /// Node merged into KEEP and deleted
+        drop: i32,
+    },
+
     /// Update or add a prompt to an existing node
     Prompt {
         /// Node ID to update
@@ -155,24 +265,157 @@ enum Command {
         node_type: Option<String>,
     },
 
+    /// Find nodes by their title, description or prompt
+    ///
+    /// With --semantic, nodes are ranked by meaning using the [embeddings]
+    /// model; new and edited nodes are embedded first.
+    Search {
+        /// What to look for
+        #[arg(required_unless_present = "related")]
+        query: Option<String>,
+
+        /// Rank by embedding similarity instead of matching the words
+        #[arg(long)]
+        semantic: bool,
+
+        /// Suggest nodes similar in meaning to this one that it isn't linked to
+        #[arg(long, conflicts_with = "query")]
+        related: Option<i32>,
+
+        /// Maximum number of results
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Also search repos added with `deciduous federate add`
+        #[arg(long, conflicts_with_all = ["semantic", "related"])]
+        federated: bool,
+    },
+
+    /// Answer a question from the decision graph, citing node IDs
+    ///
+    /// Relevant nodes are picked by matching words and by meaning (the
+    /// [embeddings] model, when it's reachable). Needs ANTHROPIC_API_KEY.
+    Ask {
+        /// The question, e.g. "why did we pick sqlite?"
+        question: String,
+
+        /// Model that writes the answer
+        #[arg(long, default_value = "claude-haiku-4-5")]
+        model: String,
+
+        /// Number of nodes given to the model
+        #[arg(short = 'n', long, default_value_t = 15)]
+        limit: usize,
+
+        /// Only match words; skip the embeddings endpoint
+        #[arg(long)]
+        no_semantic: bool,
+
+        /// Print the answer, cited node IDs and nodes used as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// List all edges
     Edges,
 
     /// Export full graph as JSON
-    Graph,
+    Graph {
+        /// Include the graphs of repos added with `deciduous federate add`
+        #[arg(long)]
+        federated: bool,
+    },
+
+    /// Summarize recent work for a team update: new goals, outcomes, open
+    /// decisions, API usage and roadmap movement
+    Digest {
+        /// Start of the window: 7d, 2w, 12h, a date (2025-06-01) or a timestamp
+        #[arg(long, default_value = "7d")]
+        since: String,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Format for pasting into Slack (mrkdwn) instead of Markdown
+        #[arg(long)]
+        slack: bool,
+    },
+
+    /// Graph metrics: per-node depth, fan-in/out and betweenness, the longest
+    /// chain, central decisions, dead-end options, and action-to-outcome
+    /// cycle times per goal and branch
+    Metrics {
+        /// Output format: json (everything), csv (per-node rows) or text (summary)
+        #[arg(long, default_value = "json")]
+        format: String,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 
     /// Start the graph viewer server
     Serve {
         /// Port to listen on
         #[arg(short, long, default_value = "3000")]
         port: u16,
+
+        /// Address to bind (use 0.0.0.0 to serve a team patch hub)
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// Also act as a patch hub for `deciduous push` and `deciduous pull`
+        #[arg(long)]
+        sync: bool,
+
+        /// Serve the gRPC API in proto/deciduous.proto instead of the viewer
+        /// (needs a build with `--features grpc`)
+        #[arg(long, conflicts_with = "sync")]
+        grpc: bool,
+
+        /// Include the graphs of repos added with `deciduous federate add`
+        #[arg(long, conflicts_with = "grpc")]
+        federated: bool,
+    },
+
+    /// Serve the graph to MCP clients (Claude Desktop, Cursor, ...) over stdio
+    ///
+    /// Tools: add_node, link, query, get_context, search. Resources:
+    /// deciduous://graph and deciduous://node/<id>.
+    Mcp,
+
+    /// Answer editor plugins' JSON-RPC requests, one message per line
+    ///
+    /// Methods: nodes/near-file, node/get, node/create, edge/create,
+    /// nodes/query, nodes/search, context/pack.
+    Daemon {
+        /// Listen on this Unix socket instead of stdin/stdout
+        #[arg(long)]
+        socket: Option<PathBuf>,
     },
 
     /// Export graph to JSON file
     Sync {
-        /// Output path (default: .deciduous/web/graph-data.json)
+        /// Output path (default: docs/graph-data.json, or the profile's output)
         #[arg(short, long)]
         output: Option<PathBuf>,
+
+        /// Export profile from [export.profiles.<name>] in .deciduous/config.toml
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Also write each sub-project's graph to projects/<name>/ beside the output
+        #[arg(long)]
+        per_project: bool,
+
+        /// Also mirror goals, decisions and outcomes into the [notion] database
+        #[arg(long)]
+        notion: bool,
     },
 
     /// Create a database backup
@@ -187,6 +430,10 @@ enum Command {
         /// Number of commands to show
         #[arg(short, long, default_value = "20")]
         limit: i64,
+
+        /// List the nodes and edges each command created, changed or deleted
+        #[arg(short, long)]
+        verbose: bool,
     },
 
     /// Export graph as DOT format
@@ -255,12 +502,77 @@ enum Command {
         no_test_plan: bool,
     },
 
+    /// Import issues from GitHub/GitLab into the decision graph
+    Github {
+        #[command(subcommand)]
+        action: GithubAction,
+    },
+
+    /// Import decision records from other tools (MADR, Log4brains)
+    Import {
+        #[command(subcommand)]
+        action: ImportAction,
+    },
+
+    /// Pull request helpers (post the writeup as a PR comment)
+    Pr {
+        #[command(subcommand)]
+        action: PrAction,
+    },
+
     /// Export or apply graph diff patches for multi-user sync
     Diff {
         #[command(subcommand)]
         action: DiffAction,
     },
 
+    /// Send your graph to a patch hub (`deciduous serve --sync`)
+    Push {
+        /// Hub URL (default: [sync] server in config.toml)
+        server: Option<String>,
+
+        /// Only send nodes from this branch
+        #[arg(short, long)]
+        branch: Option<String>,
+
+        /// Sign the patch (see `diff export --sign`)
+        #[arg(long)]
+        sign: bool,
+    },
+
+    /// Fetch and apply the graph from a patch hub
+    Pull {
+        /// Hub URL (default: [sync] server in config.toml)
+        server: Option<String>,
+
+        /// Show what would be applied without making changes
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Settle conflicts: ours, theirs or newest (default: record them for review)
+        #[arg(long)]
+        strategy: Option<String>,
+    },
+
+    /// Share the graph through git refs (refs/deciduous/*) instead of patch files
+    SyncGit {
+        #[command(subcommand)]
+        action: SyncGitAction,
+    },
+
+    /// Read other repositories' graphs alongside this one (graph, search and
+    /// serve with --federated)
+    Federate {
+        #[command(subcommand)]
+        action: FederateAction,
+    },
+
+    /// Labeled copies of the graph to compare later (sprint retros, before cleanups)
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotAction,
+    },
+
     /// Migrate database to add change_id columns (for multi-user sync)
     Migrate,
 
@@ -281,6 +593,105 @@ enum Command {
         /// Auto-apply without confirmation (use with caution)
         #[arg(long)]
         yes: bool,
+
+        /// Find probable duplicate nodes and offer to merge them
+        #[arg(long, conflicts_with = "associate_commits")]
+        dedupe: bool,
+
+        /// With --dedupe: minimum title similarity (0-100)
+        #[arg(long, default_value = "85")]
+        min_similarity: u8,
+
+        /// With --dedupe: minutes apart two similar nodes may be created
+        #[arg(long, default_value = "60")]
+        window: i64,
+
+        /// List decisions and actions that stopped moving (see `deciduous stale`)
+        #[arg(long, conflicts_with_all = ["associate_commits", "dedupe"])]
+        stale: bool,
+
+        /// Link commits to the nodes named in their `Deciduous: #42` trailers
+        #[arg(long, conflicts_with_all = ["associate_commits", "dedupe", "stale"])]
+        from_trailers: bool,
+    },
+
+    /// Decisions with no chosen option and actions left pending for too long
+    /// (thresholds in [stale])
+    Stale {
+        /// Days a decision may wait for a chosen option (default: [stale] decision_days, 7)
+        #[arg(long)]
+        decision_days: Option<i64>,
+
+        /// Days an action may stay pending or active (default: [stale] action_days, 3)
+        #[arg(long)]
+        action_days: Option<i64>,
+
+        /// Send the list to the on_stale hook as a reminder
+        #[arg(long)]
+        notify: bool,
+
+        /// Print the list as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Nodes that touched a file (its `files` metadata or linked commit), newest first
+    Why {
+        /// File path, relative to the current directory or the repo root
+        file: String,
+
+        /// Maximum nodes to list
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+
+        /// Print the nodes as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Leave or read a structured end-of-session note for the active goal
+    Handoff {
+        #[command(subcommand)]
+        action: HandoffAction,
+    },
+
+    /// Start-of-session summary: the active goal, what is open under it and
+    /// its last handoff
+    Context {
+        /// Print the context as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Focus on a goal: new nodes and trace sessions link to it
+    ///
+    /// With no arguments, shows the current focus.
+    Focus {
+        /// Goal node ID to focus on
+        goal: Option<i32>,
+
+        /// End the focus after this long (e.g. 2h, 1d)
+        #[arg(long = "for", value_name = "DURATION", requires = "goal")]
+        duration: Option<String>,
+
+        /// End the current focus
+        #[arg(long, conflicts_with = "goal")]
+        clear: bool,
+    },
+
+    /// Archive the subtrees of options rejected long ago, leaving a stub
+    Gc {
+        /// Only options rejected before this (e.g. 90d, 12w or 2025-06-01)
+        #[arg(long, value_name = "AGE")]
+        rejected_older_than: String,
+
+        /// Directory for the archive patch
+        #[arg(long, default_value = ".deciduous/archive")]
+        dir: PathBuf,
+
+        /// Show what would be archived without changing anything
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Launch the terminal user interface
@@ -296,6 +707,37 @@ enum Command {
         action: RoadmapAction,
     },
 
+    /// Git hooks that keep the branch's patch file up to date
+    Hooks {
+        #[command(subcommand)]
+        action: HooksAction,
+    },
+
+    /// Read, change and check .deciduous/config.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Validate committed patches and the branch's decision graph (for CI)
+    Ci {
+        /// Directory containing patch files (default: .deciduous/patches/)
+        #[arg(long)]
+        patches: Option<PathBuf>,
+
+        /// Branch to check for exported nodes (default: $GITHUB_HEAD_REF or current branch)
+        #[arg(short, long)]
+        branch: Option<String>,
+
+        /// Fail on warnings as well as errors
+        #[arg(long)]
+        strict: bool,
+
+        /// Emit GitHub Actions annotations (default: on when GITHUB_ACTIONS=true)
+        #[arg(long)]
+        annotations: bool,
+    },
+
     /// Generate shell completions
     Completion {
         /// Shell type: bash, zsh, fish, powershell, elvish
@@ -311,12 +753,22 @@ enum Command {
     /// Run a command through the trace-capturing proxy
     Proxy {
         /// Command to run (e.g., "claude")
-        #[arg(trailing_var_arg = true, required = true)]
+        #[arg(trailing_var_arg = true, required_unless_present = "listen")]
         command: Vec<String>,
 
-        /// Auto-link trace session to most recent goal node
+        /// Auto-link the trace session to the focused goal, else the most
+        /// recent open goal on the current git branch (falling back to goals
+        /// on other branches)
         #[arg(long)]
         auto_link: bool,
+
+        /// Link the trace session to this node instead of picking a goal
+        #[arg(long, value_name = "ID")]
+        link_node: Option<i32>,
+
+        /// Run as an HTTPS proxy on this port (or addr:port) instead of wrapping a command
+        #[arg(long, value_name = "PORT")]
+        listen: Option<String>,
     },
 }
 
@@ -384,6 +836,21 @@ enum TraceAction {
         linked: bool,
     },
 
+    /// Follow the active session, printing spans as they complete
+    Tail {
+        /// Follow this session instead of whichever is active
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Completed spans to show when attaching to a session
+        #[arg(short = 'n', long, default_value = "10")]
+        lines: usize,
+
+        /// Poll interval in milliseconds
+        #[arg(long, default_value = "500")]
+        interval: u64,
+    },
+
     /// Show spans in a trace session
     Spans {
         /// Session ID
@@ -412,6 +879,19 @@ enum TraceAction {
         tools: bool,
     },
 
+    /// Add a reviewer note to a span, or list/delete its notes
+    Annotate {
+        /// Span ID
+        span_id: i32,
+
+        /// Note text (omit to list the span's notes)
+        note: Option<String>,
+
+        /// Delete the note with this ID instead
+        #[arg(long, value_name = "NOTE_ID", conflicts_with = "note")]
+        delete: Option<i32>,
+    },
+
     /// Link a trace session or span to a decision node
     Link {
         /// Target decision node ID
@@ -437,58 +917,286 @@ enum TraceAction {
         span: Option<i32>,
     },
 
-    /// Delete old trace data
-    Prune {
-        /// Delete traces older than N days
-        #[arg(long, default_value = "30")]
-        days: u32,
+    /// Report API spend grouped by day, model, or linked decision node
+    Costs {
+        /// Group by "day", "model" or "node"
+        #[arg(long, default_value = "model")]
+        by: String,
 
-        /// Keep linked traces even if old
+        /// Only count spans from the last N days
         #[arg(long)]
-        keep_linked: bool,
+        days: Option<i64>,
 
-        /// Show what would be deleted without deleting
+        /// Only count spans from this session
         #[arg(long)]
-        dry_run: bool,
+        session: Option<String>,
     },
-}
 
-#[derive(Subcommand, Debug)]
-enum DiffAction {
-    /// Export nodes as a patch file
-    Export {
-        /// Output file path (required)
-        #[arg(short, long)]
-        output: PathBuf,
+    /// Tool usage: calls, failure rates and output sizes per tool, and which
+    /// tools each kind of node's work used
+    Tools {
+        /// Only count spans from the last N days
+        #[arg(long)]
+        days: Option<i64>,
 
-        /// Node IDs or ranges to export (e.g., "1-11" or "1,3,5-10")
-        #[arg(short, long)]
-        nodes: Option<String>,
+        /// Only count spans from this session
+        #[arg(long)]
+        session: Option<String>,
+    },
 
-        /// Filter by git branch
+    /// Send a session to an OpenTelemetry collector (OTLP/HTTP JSON)
+    ExportOtlp {
+        /// Session ID to export
+        #[arg(long)]
+        session: String,
+
+        /// Collector endpoint (`/v1/traces` is appended if missing)
+        #[arg(long, default_value = "http://localhost:4318")]
+        endpoint: String,
+
+        /// Extra request header, e.g. "Authorization: Bearer ..." (repeatable)
+        #[arg(long = "header", value_name = "HEADER")]
+        headers: Vec<String>,
+
+        /// Write the OTLP JSON to a file instead of sending it
         #[arg(short, long)]
-        branch: Option<String>,
+        output: Option<PathBuf>,
+    },
 
-        /// Author name to include in patch
+    /// Render a session as a Markdown or HTML transcript
+    Transcript {
+        /// Session ID
+        session_id: String,
+
+        /// Output file (`.html` for HTML, Markdown otherwise; stdout if omitted)
         #[arg(short, long)]
-        author: Option<String>,
+        output: Option<PathBuf>,
 
-        /// Git commit hash at time of export
+        /// Include thinking blocks
         #[arg(long)]
-        base_commit: Option<String>,
+        thinking: bool,
     },
 
-    /// Apply a patch file to local database
-    Apply {
-        /// Patch file(s) to apply
-        files: Vec<PathBuf>,
+    /// Write an anonymized bundle (timings, token counts and tool names
+    /// only, no prompts or code) for attaching to bug reports
+    ExportShare {
+        /// Session ID
+        session_id: String,
 
-        /// Show what would be applied without making changes
-        #[arg(long)]
-        dry_run: bool,
+        /// Output file (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
 
-    /// Show status of unapplied patches
+    /// Write a "what the agent did" report from a session's spans, linked
+    /// nodes and commits
+    Writeup {
+        /// Session ID
+        session_id: String,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Include a DOT graph of the linked nodes
+        #[arg(long)]
+        dot: bool,
+    },
+
+    /// Re-send a session's requests (optionally to another model) and
+    /// record the replies as a new session for comparison
+    Replay {
+        /// Session ID to replay
+        session_id: String,
+
+        /// Model to send to instead of the one each span used
+        #[arg(long)]
+        model: Option<String>,
+
+        /// max_tokens for each replayed request
+        #[arg(long, default_value = "8192")]
+        max_tokens: u32,
+
+        /// Only replay the first N requests
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Show the requests that would be sent without sending them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Compare two sessions: token usage, tools invoked, and responses
+    Diff {
+        /// Baseline session ID
+        a: String,
+
+        /// Session ID to compare against it
+        b: String,
+    },
+
+    /// Propose goal/action/outcome nodes for tool activity that was never
+    /// logged, and create the ones you accept
+    Suggest {
+        /// Only look at this session (default: all sessions)
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Have a model draft the titles (needs ANTHROPIC_API_KEY)
+        #[arg(long)]
+        llm: bool,
+
+        /// Model used with --llm
+        #[arg(long, default_value = "claude-haiku-4-5")]
+        model: String,
+
+        /// Accept every suggestion without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Link spans to the git commits their tool calls made, or find the
+    /// span behind a commit
+    Commits {
+        /// Only link this session (default: all sessions)
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Also record each commit on the span's linked node, if it has none
+        #[arg(long)]
+        update_nodes: bool,
+
+        /// Show which span produced this commit (full hash or prefix)
+        #[arg(long)]
+        commit: Option<String>,
+    },
+
+    /// Import Claude Code session transcripts (~/.claude/projects/...) as
+    /// trace sessions
+    ImportClaude {
+        /// Transcript files, or directories to search for *.jsonl
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+
+    /// Delete old trace data
+    ///
+    /// Limits default to [trace.retention] in config.toml, or 30 days if
+    /// neither is configured.
+    Prune {
+        /// Delete traces older than N days
+        #[arg(long)]
+        days: Option<u32>,
+
+        /// Delete the oldest sessions until the database uses at most N MB
+        #[arg(long)]
+        max_db_size_mb: Option<u64>,
+
+        /// Keep linked traces even if old (default: [trace.retention]
+        /// keep_linked, which defaults to true)
+        #[arg(long)]
+        keep_linked: bool,
+
+        /// Prune linked traces too, overriding [trace.retention] keep_linked
+        #[arg(long, conflicts_with = "keep_linked")]
+        no_keep_linked: bool,
+
+        /// Show what would be deleted without deleting
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum DiffAction {
+    /// Export nodes as a patch file
+    Export {
+        /// Output file path (required)
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Node IDs or ranges to export (e.g., "1-11" or "1,3,5-10")
+        #[arg(short, long)]
+        nodes: Option<String>,
+
+        /// Filter by git branch
+        #[arg(short, long)]
+        branch: Option<String>,
+
+        /// Author name to include in patch
+        #[arg(short, long)]
+        author: Option<String>,
+
+        /// Git commit hash at time of export
+        #[arg(long)]
+        base_commit: Option<String>,
+
+        /// Sign the patch with your key (~/.deciduous/signing-key.pk8)
+        #[arg(long)]
+        sign: bool,
+
+        /// Only nodes not exported before, plus changes to ones that were
+        #[arg(long, conflicts_with = "nodes")]
+        new: bool,
+
+        /// Encrypt the patch with age to this recipient (repeatable; needs the age CLI)
+        #[arg(long = "encrypt-for", value_name = "RECIPIENT")]
+        encrypt_for: Vec<String>,
+    },
+
+    /// Apply a patch file to local database
+    Apply {
+        /// Patch file(s) or https:// URLs (gist pages apply every .json file)
+        files: Vec<PathBuf>,
+
+        /// Expected SHA-256 of a downloaded patch (or append #sha256=<hex> to the URL)
+        #[arg(long)]
+        sha256: Option<String>,
+
+        /// Show what would be applied without making changes
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Settle conflicts: ours, theirs or newest (default: record them for review)
+        #[arg(long)]
+        strategy: Option<String>,
+    },
+
+    /// Find patches in git history that haven't been applied here and apply them
+    Scan {
+        /// Patches directory to look for in history (default: .deciduous/patches)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// Show what would be applied without making changes
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Apply without asking for confirmation
+        #[arg(long)]
+        yes: bool,
+
+        /// Settle conflicts: ours, theirs or newest (default: record them for review)
+        #[arg(long)]
+        strategy: Option<String>,
+    },
+
+    /// Review conflicts between applied patches and local nodes
+    Conflicts {
+        /// Resolve them: ours, theirs or newest
+        #[arg(long)]
+        strategy: Option<String>,
+
+        /// Only resolve this conflict (with --strategy)
+        #[arg(long, requires = "strategy")]
+        id: Option<i32>,
+
+        /// Include resolved conflicts
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Show status of unapplied patches
     Status {
         /// Directory to scan for patches (default: .deciduous/patches/)
         #[arg(short, long)]
@@ -500,6 +1208,129 @@ enum DiffAction {
         /// Patch file(s) to validate
         files: Vec<PathBuf>,
     },
+
+    /// Show your patch signing public key, creating the key if needed
+    Key,
+}
+
+#[derive(Subcommand, Debug)]
+enum GithubAction {
+    /// Create goal nodes from open issues (re-run to pick up edits and closures)
+    Import {
+        /// Only import issues with this label
+        #[arg(short, long, default_value = "roadmap")]
+        label: String,
+
+        /// Repo in owner/repo (or group/project) format (auto-detected from git remote)
+        #[arg(long)]
+        repo: Option<String>,
+
+        /// Also pull new issue comments in as observation nodes under each goal
+        #[arg(long)]
+        comments: bool,
+
+        /// Show what would be imported without changing the graph
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Re-fetch cached issue and PR state (only entries older than the cache TTL unless --all)
+    Refresh {
+        /// Repo in owner/repo (or group/project) format (auto-detected from git remote)
+        #[arg(long)]
+        repo: Option<String>,
+
+        /// Refresh every cached entry, not just expired ones
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Refresh and show the state of pull requests linked to nodes (--pr)
+    PrStatus {
+        /// Repo in owner/repo (or group/project) format (auto-detected from git remote)
+        #[arg(long)]
+        repo: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PrAction {
+    /// Post the PR writeup (with graph image) as a single, self-updating PR comment
+    Comment {
+        /// PR number (default: open PR for the current branch)
+        #[arg(long)]
+        pr: Option<i32>,
+
+        /// Writeup title (default: the PR title)
+        #[arg(short, long)]
+        title: Option<String>,
+
+        /// Root node IDs to include (comma-separated, traverses children)
+        #[arg(short, long)]
+        roots: Option<String>,
+
+        /// Specific node IDs or ranges (e.g., "1-11" or "1,3,5-10")
+        #[arg(short = 'n', long)]
+        nodes: Option<String>,
+
+        /// PNG path in the repo to embed (default: docs/decision-graph-{branch}.png if present)
+        #[arg(long)]
+        png: Option<String>,
+
+        /// Skip DOT graph section
+        #[arg(long)]
+        no_dot: bool,
+
+        /// Skip test plan section
+        #[arg(long)]
+        no_test_plan: bool,
+
+        /// Repo in owner/repo (or group/project) format (auto-detected from git remote)
+        #[arg(long)]
+        repo: Option<String>,
+
+        /// Print the comment instead of posting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Capture review threads as observation/option nodes linked to matching actions
+    ImportReviews {
+        /// PR number
+        pr: i32,
+
+        /// Repo in owner/repo (or group/project) format (auto-detected from git remote)
+        #[arg(long)]
+        repo: Option<String>,
+
+        /// Show what would be imported without changing the graph
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Record a merged PR: an outcome linked to the branch's goals and
+    /// committed actions, with its open actions marked completed (for CI)
+    Merged {
+        /// PR number
+        #[arg(long)]
+        pr: i32,
+
+        /// PR branch (default: looked up from the forge)
+        #[arg(long)]
+        branch: Option<String>,
+
+        /// PR title (default: looked up from the forge)
+        #[arg(long)]
+        title: Option<String>,
+
+        /// Repo in owner/repo (or group/project) format (auto-detected from git remote)
+        #[arg(long)]
+        repo: Option<String>,
+
+        /// Show the outcome that would be added without changing the graph
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -518,13 +1349,16 @@ enum RoadmapAction {
         path: Option<PathBuf>,
     },
 
-    /// Sync ROADMAP.md with GitHub Issues (dry-run by default, use --execute to apply)
+    /// Sync ROADMAP.md with GitHub/GitLab Issues (dry-run by default, use --execute to apply)
+    ///
+    /// Checkbox and open/closed changes made on the issues are pulled back into
+    /// ROADMAP.md first; disagreements are recorded as conflicts instead.
     Sync {
         /// Path to ROADMAP.md (default: ROADMAP.md)
         #[arg(short, long)]
         path: Option<PathBuf>,
 
-        /// GitHub repo in owner/repo format (auto-detected from git remote)
+        /// Repo in owner/repo (or group/project) format (auto-detected from git remote)
         #[arg(short, long)]
         repo: Option<String>,
 
@@ -535,6 +1369,37 @@ enum RoadmapAction {
         /// Create GitHub issues for new sections
         #[arg(long, default_value = "true")]
         create_issues: bool,
+
+        /// Map level-2 sections to milestones and assign their issues
+        #[arg(long)]
+        milestones: bool,
+
+        /// Only push ROADMAP.md to issues; don't pull issue changes back
+        #[arg(long)]
+        no_pull: bool,
+    },
+
+    /// Sync roadmap sections to a GitHub Projects (v2) board (dry-run by default)
+    ProjectSync {
+        /// Project number (from the project URL, e.g. .../projects/3)
+        #[arg(long)]
+        project: u32,
+
+        /// Project owner, user or org (default: the repo owner)
+        #[arg(long)]
+        owner: Option<String>,
+
+        /// Path to ROADMAP.md (default: ROADMAP.md)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// GitHub repo in owner/repo format (auto-detected from git remote)
+        #[arg(short, long)]
+        repo: Option<String>,
+
+        /// Actually apply changes (default is dry-run mode)
+        #[arg(long)]
+        execute: bool,
     },
 
     /// List roadmap items with status
@@ -554,9 +1419,13 @@ enum RoadmapAction {
         /// Show only items without GitHub issues
         #[arg(long)]
         without_issues: bool,
+
+        /// List items flat, sorted by "priority" or "due" date (unset last)
+        #[arg(long)]
+        sort: Option<String>,
     },
 
-    /// Link a roadmap item to a decision graph outcome node
+    /// Link a roadmap item to a decision graph outcome node (repeat to link several)
     Link {
         /// Roadmap item change_id or title (partial match)
         item: String,
@@ -565,10 +1434,29 @@ enum RoadmapAction {
         outcome_id: i32,
     },
 
-    /// Remove outcome link from a roadmap item
+    /// Remove outcome links from a roadmap item
     Unlink {
         /// Roadmap item change_id or title (partial match)
         item: String,
+
+        /// Only remove the link to this outcome node (default: all links)
+        outcome_id: Option<i32>,
+    },
+
+    /// Create a goal node from a roadmap item and link it to the item
+    ///
+    /// The goal's prompt is the item text plus its section context.
+    Start {
+        /// Roadmap item change_id or title (partial match)
+        item: String,
+
+        /// Confidence level for the goal (0-100)
+        #[arg(short, long)]
+        confidence: Option<u8>,
+
+        /// Also create and check out a feature branch (roadmap/<item-slug>)
+        #[arg(long)]
+        branch: bool,
     },
 
     /// Show sync conflicts
@@ -598,33 +1486,312 @@ enum RoadmapAction {
         /// Show only complete items
         #[arg(long)]
         complete: bool,
+
+        /// Close issues whose items are all checked with completed outcomes
+        #[arg(long)]
+        close_issues: bool,
+
+        /// Repo in owner/repo (or group/project) format (auto-detected from git remote)
+        #[arg(short, long)]
+        repo: Option<String>,
     },
-}
 
-fn main() {
-    let args = Args::parse();
+    /// Check off items whose linked outcome is completed, in ROADMAP.md and issues (dry-run by default)
+    Reconcile {
+        /// Path to ROADMAP.md (default: ROADMAP.md)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
 
-    // Handle init separately - it doesn't need an existing database
-    if let Command::Init {
-        claude: _,
-        windsurf,
+        /// Repo in owner/repo (or group/project) format (auto-detected from git remote)
+        #[arg(short, long)]
+        repo: Option<String>,
+
+        /// Actually apply changes (default is dry-run mode)
+        #[arg(long)]
+        execute: bool,
+    },
+
+    /// Pre-commit hook that flags ROADMAP.md edits the database hasn't seen
+    Hook {
+        #[command(subcommand)]
+        action: RoadmapHookAction,
+    },
+
+    /// Move fully-complete sections to ROADMAP_ARCHIVE.md and close their issues (dry-run by default)
+    Archive {
+        /// Path to ROADMAP.md (default: ROADMAP.md)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// Archive file (default: ROADMAP_ARCHIVE.md next to ROADMAP.md)
+        #[arg(long)]
+        archive: Option<PathBuf>,
+
+        /// Repo in owner/repo (or group/project) format (auto-detected from git remote)
+        #[arg(short, long)]
+        repo: Option<String>,
+
+        /// Actually apply changes (default is dry-run mode)
+        #[arg(long)]
+        execute: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SyncGitAction {
+    /// Export the graph to your ref and push it
+    Push {
+        /// Remote to push to
+        #[arg(short, long, default_value = "origin")]
+        remote: String,
+
+        /// Sign the exported patch (see `diff export --sign`)
+        #[arg(long)]
+        sign: bool,
+    },
+
+    /// Fetch everyone's refs and apply them
+    Pull {
+        /// Remote to fetch from
+        #[arg(short, long, default_value = "origin")]
+        remote: String,
+
+        /// Show what would be applied without making changes
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Settle conflicts: ours, theirs or newest (default: record them for review)
+        #[arg(long)]
+        strategy: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum FederateAction {
+    /// Register another repo's database; its change_ids read as NAME:<change_id>
+    Add {
+        /// The repo (containing .deciduous/deciduous.db) or a database file
+        path: PathBuf,
+
+        /// Namespace for its nodes (default: the directory name)
+        #[arg(long)]
+        name: Option<String>,
+    },
+
+    /// List registered repos
+    List,
+
+    /// Stop including a repo
+    Remove { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+enum SnapshotAction {
+    /// Store the graph as it is now under a label
+    Create { label: String },
+
+    /// List snapshots
+    List,
+
+    /// Nodes and edges added, removed or changed between two snapshots
+    Diff {
+        /// Earlier snapshot (label or ID)
+        from: String,
+
+        /// Later snapshot, or "current" for the graph as it is now
+        #[arg(default_value = deciduous::snapshot::CURRENT)]
+        to: String,
+
+        /// Print the diff as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Delete a snapshot (label or ID)
+    Delete { label: String },
+}
+
+#[derive(Subcommand, Debug)]
+enum HandoffAction {
+    /// Store a handoff note, linked from the active goal
+    Write {
+        /// An open question (repeatable)
+        #[arg(short = 'q', long = "question")]
+        questions: Vec<String>,
+
+        /// A next step (repeatable)
+        #[arg(short, long = "next")]
+        next: Vec<String>,
+
+        /// A gotcha for whoever picks this up (repeatable)
+        #[arg(short, long = "gotcha")]
+        gotchas: Vec<String>,
+
+        /// Goal to link the note from (default: this branch's open goal)
+        #[arg(long)]
+        goal: Option<i32>,
+    },
+
+    /// Show the latest handoff note
+    Read {
+        /// Only notes linked from this goal (default: any)
+        #[arg(long)]
+        goal: Option<i32>,
+
+        /// Print the note as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ImportAction {
+    /// A directory of MADR markdown records (re-run to pick up edits)
+    Adr {
+        /// ADR directory
+        #[arg(default_value = "docs/decisions")]
+        dir: PathBuf,
+
+        /// Show what would be imported without changing the graph
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// The ADR folders of a Log4brains project (from .log4brains.yml)
+    Log4brains {
+        /// Project root containing .log4brains.yml
+        #[arg(default_value = ".")]
+        root: PathBuf,
+
+        /// Show what would be imported without changing the graph
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// Print one effective setting (e.g. branch.main_branches)
+    Get { key: String },
+
+    /// Set a setting in the config file, keeping its comments
+    Set {
+        /// Dotted key, e.g. branch.auto_detect or sync.trusted_keys.alice
+        key: String,
+
+        /// TOML value (true, 3, ["main", "trunk"]); anything else is a string
+        value: String,
+    },
+
+    /// List every effective setting and where it comes from
+    List,
+
+    /// Report keys the config file sets that deciduous ignores (typos)
+    Validate,
+}
+
+#[derive(Subcommand, Debug)]
+enum HooksAction {
+    /// Install the pre-push hook (exports the branch's new nodes to .deciduous/patches/)
+    Install {
+        /// Block the push when the patch changed, so it can be committed first (default: only stage it)
+        #[arg(long)]
+        strict: bool,
+
+        /// Sign the exported patch (see `diff export --sign`)
+        #[arg(long)]
+        sign: bool,
+
+        /// Add the export to an existing pre-push hook
+        #[arg(long)]
+        append: bool,
+    },
+
+    /// Remove the patch export from the pre-push hook
+    Uninstall,
+
+    /// Export the current branch's new nodes to <user>-<branch>.json and stage it (run by the hook)
+    PrePush {
+        /// Directory containing patch files (default: .deciduous/patches/)
+        #[arg(long)]
+        patches: Option<PathBuf>,
+
+        /// Exit non-zero when the patch changed
+        #[arg(long)]
+        strict: bool,
+
+        /// Sign the exported patch
+        #[arg(long)]
+        sign: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum RoadmapHookAction {
+    /// Install the pre-commit hook (checks ROADMAP.md whenever it is staged)
+    Install {
+        /// Block the commit when drift is found (default: only warn)
+        #[arg(long)]
+        strict: bool,
+
+        /// Add the check to an existing pre-commit hook
+        #[arg(long)]
+        append: bool,
+    },
+
+    /// Remove the roadmap check from the pre-commit hook
+    Uninstall,
+
+    /// Compare ROADMAP.md against the database and report drift
+    Check {
+        /// Path to ROADMAP.md (default: ROADMAP.md)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// Exit non-zero when drift is found
+        #[arg(long)]
+        strict: bool,
+
+        /// Keep running and re-check whenever ROADMAP.md changes
+        #[arg(long)]
+        watch: bool,
+    },
+}
+
+fn main() {
+    let args = Args::parse();
+
+    // Handle init separately - it doesn't need an existing database
+    if let Command::Init {
+        claude,
+        windsurf,
         opencode,
         codex,
+        zed,
+        gemini,
         force,
+        dry_run,
+        diff,
+        no_workflows,
+        no_pages,
+        commands_only,
     } = args.command
     {
-        // Determine editor type: default to Claude if none specified
-        let editor = if windsurf {
-            deciduous::init::Editor::Windsurf
-        } else if opencode {
-            deciduous::init::Editor::Opencode
-        } else if codex {
-            deciduous::init::Editor::Codex
+        let editors = selected_editors(claude, windsurf, opencode, codex, zed, gemini);
+
+        let components = if commands_only {
+            deciduous::init::Components::commands_only()
         } else {
-            deciduous::init::Editor::Claude
+            deciduous::init::Components {
+                workflows: !no_workflows,
+                pages: !no_pages,
+                ..Default::default()
+            }
         };
 
-        if let Err(e) = deciduous::init::init_project(editor, force) {
+        if let Err(e) =
+            deciduous::init::init_project(&editors, force, init_preview(dry_run, diff), components)
+        {
             eprintln!("{} {}", "Error:".red(), e);
             std::process::exit(1);
         }
@@ -633,24 +1800,19 @@ fn main() {
 
     // Handle update separately - it doesn't need an existing database
     if let Command::Update {
-        claude: _,
+        claude,
         windsurf,
         opencode,
         codex,
+        zed,
+        gemini,
+        dry_run,
+        diff,
     } = args.command
     {
-        // Determine editor type: default to Claude if none specified
-        let editor = if windsurf {
-            deciduous::init::Editor::Windsurf
-        } else if opencode {
-            deciduous::init::Editor::Opencode
-        } else if codex {
-            deciduous::init::Editor::Codex
-        } else {
-            deciduous::init::Editor::Claude
-        };
+        let editors = selected_editors(claude, windsurf, opencode, codex, zed, gemini);
 
-        if let Err(e) = deciduous::init::update_tooling(editor) {
+        if let Err(e) = deciduous::init::update_tooling(&editors, init_preview(dry_run, diff)) {
             eprintln!("{} {}", "Error:".red(), e);
             std::process::exit(1);
         }
@@ -666,6 +1828,42 @@ fn main() {
         return;
     }
 
+    // Handle CI separately - the database is gitignored, so usually absent in CI
+    if let Command::Ci {
+        patches,
+        branch,
+        strict,
+        annotations,
+    } = &args.command
+    {
+        run_ci(patches.clone(), branch.clone(), *strict, *annotations);
+        return;
+    }
+
+    // Handle hooks separately - pre-push must not create a database
+    if let Command::Hooks { action } = args.command {
+        run_hooks(action);
+        return;
+    }
+
+    // Handle config separately - doesn't need database
+    if let Command::Config { action } = args.command {
+        run_config(action);
+        return;
+    }
+
+    // Handle uninit separately - it may remove the database
+    if let Command::Uninit {
+        keep_db,
+        force,
+        dry_run,
+        yes,
+    } = args.command
+    {
+        run_uninit(keep_db, force, dry_run, yes);
+        return;
+    }
+
     // Handle completion separately - doesn't need database
     if let Command::Completion { shell } = args.command {
         clap_complete::generate(
@@ -678,16 +1876,36 @@ fn main() {
     }
 
     let db = match Database::open() {
-        Ok(db) => db,
+        Ok(db) => db.with_project_scope(args.scope.clone()),
         Err(e) => {
             eprintln!("{} Failed to open database: {}", "Error:".red(), e);
             std::process::exit(1);
         }
     };
 
+    // Log the invocation, so `deciduous commands --verbose` can show what it changed
+    let log_id = if matches!(args.command, Command::Commands { .. }) {
+        None
+    } else {
+        let argv: Vec<String> = std::env::args().collect();
+        let cwd = std::env::current_dir()
+            .ok()
+            .map(|d| d.to_string_lossy().to_string());
+        db.log_command(&argv.join(" "), None, cwd.as_deref()).ok()
+    };
+    let db = db.with_command_log(log_id);
+    let _log_guard = log_id.map(|id| CommandLogGuard {
+        id,
+        started: std::time::Instant::now(),
+    });
+
     match args.command {
         Command::Init { .. } => unreachable!(),   // Handled above
         Command::Update { .. } => unreachable!(), // Handled above
+        Command::Ci { .. } => unreachable!(),     // Handled above
+        Command::Hooks { .. } => unreachable!(),  // Handled above
+        Command::Config { .. } => unreachable!(), // Handled above
+        Command::Uninit { .. } => unreachable!(), // Handled above
         Command::Add {
             node_type,
             title,
@@ -699,7 +1917,18 @@ fn main() {
             files,
             branch,
             no_branch,
+            pr,
+            no_focus,
+            issue,
         } => {
+            // Resolve the issue reference before creating anything
+            let issue_meta = issue.as_deref().map(|reference| {
+                resolve_issue_ref(reference).unwrap_or_else(|e| {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                })
+            });
+
             // Handle prompt from stdin if requested
             let effective_prompt = if prompt_stdin {
                 use std::io::{self, Read};
@@ -745,16 +1974,53 @@ fn main() {
                 }
             });
 
-            match db.create_node_full(
-                &node_type,
-                &title,
-                description.as_deref(),
-                confidence,
-                effective_commit.as_deref(),
-                effective_prompt.as_deref(),
-                files.as_deref(),
-                effective_branch.as_deref(),
-            ) {
+            // Sub-project from --scope, else from the working directory
+            let project = args
+                .scope
+                .clone()
+                .or_else(|| deciduous::project::current(&Config::load().projects));
+
+            // Per-type default confidence and required fields from [rules]
+            let rules = Config::load().rules;
+            let confidence = confidence.or_else(|| rules.default_confidence(&node_type));
+
+            // Linked from the focused goal, if there is one
+            let focus = if no_focus || node_type == "goal" {
+                None
+            } else {
+                deciduous::focus::active(&db, Local::now())
+            };
+
+            let mut node = db.node(&node_type, &title).rules(&rules);
+            if focus.is_some() {
+                node = node.link_focus();
+            }
+            if let Some(description) = &description {
+                node = node.description(description);
+            }
+            if let Some(confidence) = confidence {
+                node = node.confidence(confidence);
+            }
+            if let Some(commit) = &effective_commit {
+                node = node.commit(commit);
+            }
+            if let Some(prompt) = &effective_prompt {
+                node = node.prompt(prompt);
+            }
+            if let Some(files) = &files {
+                node = node.files([files]);
+            }
+            if let Some(branch) = &effective_branch {
+                node = node.branch(branch);
+            }
+            if let Some(project) = &project {
+                node = node.project(project);
+            }
+            if let Some(number) = pr {
+                node = node.pr(number);
+            }
+
+            match node.insert() {
                 Ok(id) => {
                     // Auto-link to active trace span if DECIDUOUS_TRACE_SPAN is set
                     let trace_str = if let Ok(span_id_str) = std::env::var("DECIDUOUS_TRACE_SPAN") {
@@ -772,6 +2038,10 @@ fn main() {
                         String::new()
                     };
 
+                    let focus_str = focus
+                        .map(|goal| format!(" [focus: goal #{}]", goal.id).cyan().to_string())
+                        .unwrap_or_default();
+
                     let conf_str = confidence
                         .map(|c| format!(" [confidence: {}%]", c))
                         .unwrap_or_default();
@@ -791,8 +2061,50 @@ fn main() {
                         .as_ref()
                         .map(|b| format!(" [branch: {}]", b))
                         .unwrap_or_default();
+                    let project_str = project
+                        .as_ref()
+                        .map(|p| format!(" [project: {}]", p))
+                        .unwrap_or_default();
+
+                    // Link the PR and cache its state; a forge failure isn't fatal
+                    let pr_str = match pr {
+                        Some(number) => {
+                            match detect_forge(None, &Config::load())
+                                .map_err(|e| e.to_string())
+                                .and_then(|forge| {
+                                    deciduous::cache::refresh_pull_request(
+                                        &db,
+                                        forge.as_ref(),
+                                        number,
+                                    )
+                                }) {
+                                Ok(cached) => format!(" [pr: #{} {}]", number, cached.state),
+                                Err(e) => {
+                                    eprintln!(
+                                        "{} Could not fetch PR #{}: {}",
+                                        "Warning:".yellow(),
+                                        number,
+                                        e
+                                    );
+                                    format!(" [pr: #{}]", number)
+                                }
+                            }
+                        }
+                        None => String::new(),
+                    };
+                    let issue_str = match issue_meta {
+                        Some(meta) => {
+                            let key = meta["key"].as_str().unwrap_or_default().to_string();
+                            if let Err(e) = db.update_node_metadata_field(id, "issue", meta) {
+                                eprintln!("{} Linking issue: {}", "Error:".red(), e);
+                                std::process::exit(1);
+                            }
+                            format!(" [issue: {}]", key)
+                        }
+                        None => String::new(),
+                    };
                     println!(
-                        "{} node {} (type: {}, title: {}){}{}{}{}{}{}",
+                        "{} node {} (type: {}, title: {}){}{}{}{}{}{}{}{}{}{}",
                         "Created".green(),
                         id,
                         node_type,
@@ -802,8 +2114,26 @@ fn main() {
                         prompt_str,
                         files_str,
                         branch_str,
+                        project_str,
+                        pr_str,
+                        issue_str,
+                        focus_str,
                         trace_str
                     );
+
+                    let hooks = Config::load().hooks;
+                    if hooks
+                        .command_for(deciduous::lifecycle::ON_NODE_ADD)
+                        .is_some()
+                    {
+                        if let Ok(Some(node)) = db.get_node_by_id(id) {
+                            fire_hook(
+                                &hooks,
+                                deciduous::lifecycle::ON_NODE_ADD,
+                                serde_json::json!({ "node": node }),
+                            );
+                        }
+                    }
                 }
                 Err(e) => {
                     eprintln!("{} {}", "Error:".red(), e);
@@ -817,25 +2147,58 @@ fn main() {
             to,
             rationale,
             edge_type,
-        } => match db.create_edge(from, to, &edge_type, rationale.as_deref()) {
-            Ok(id) => {
-                println!(
-                    "{} edge {} ({} -> {} via {})",
-                    "Created".green(),
-                    id,
-                    from,
-                    to,
-                    edge_type
-                );
+        } => {
+            let mut edge = db
+                .edge(from, to)
+                .edge_type(&edge_type)
+                .rules(&Config::load().rules);
+            if let Some(rationale) = &rationale {
+                edge = edge.rationale(rationale);
+            }
+            match edge.insert() {
+                Ok(id) => {
+                    println!(
+                        "{} edge {} ({} -> {} via {})",
+                        "Created".green(),
+                        id,
+                        from,
+                        to,
+                        edge_type
+                    );
+                }
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
             }
+        }
+
+        Command::Status { id, status } => match db.update_node_status(id, &status) {
+            Ok(()) => println!("{} node {} status to '{}'", "Updated".green(), id, status),
             Err(e) => {
                 eprintln!("{} {}", "Error:".red(), e);
                 std::process::exit(1);
             }
         },
 
-        Command::Status { id, status } => match db.update_node_status(id, &status) {
-            Ok(()) => println!("{} node {} status to '{}'", "Updated".green(), id, status),
+        Command::Delete { id } => match db.delete_node(id) {
+            Ok(true) => println!("{} node {}", "Deleted".green(), id),
+            Ok(false) => {
+                eprintln!("{} Node {} not found", "Error:".red(), id);
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("{} {}", "Error:".red(), e);
+                std::process::exit(1);
+            }
+        },
+
+        Command::Merge { keep, drop } => match db.merge_nodes(keep, drop) {
+            Ok(true) => println!("{} node {} into {}", "Merged".green(), drop, keep),
+            Ok(false) => {
+                eprintln!("{} Node {} or {} not found", "Error:".red(), keep, drop);
+                std::process::exit(1);
+            }
             Err(e) => {
                 eprintln!("{} {}", "Error:".red(), e);
                 std::process::exit(1);
@@ -957,6 +2320,29 @@ fn main() {
             }
         }
 
+        Command::Search {
+            query,
+            semantic,
+            related,
+            limit,
+            json,
+            federated,
+        } => {
+            if federated {
+                search_federated(&db, query.as_deref().unwrap_or_default(), limit, json)
+            } else {
+                search(&db, query.as_deref(), semantic, related, limit, json)
+            }
+        }
+
+        Command::Ask {
+            question,
+            model,
+            limit,
+            no_semantic,
+            json,
+        } => ask(&db, &question, &model, limit, !no_semantic, json),
+
         Command::Edges => match db.get_all_edges() {
             Ok(edges) => {
                 if edges.is_empty() {
@@ -985,143 +2371,544 @@ fn main() {
             }
         },
 
-        Command::Graph => match db.get_graph() {
-            Ok(graph) => match serde_json::to_string_pretty(&graph) {
-                Ok(json) => println!("{}", json),
-                Err(e) => {
-                    eprintln!("{} Serializing graph: {}", "Error:".red(), e);
-                    std::process::exit(1);
-                }
-            },
-            Err(e) => {
+        Command::Graph { federated } => {
+            use std::io::Write;
+            let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+            let result = if federated {
+                db.get_graph()
+                    .map_err(|e| e.to_string())
+                    .and_then(|graph| deciduous::federation::federate(&db, graph))
+                    .and_then(|graph| {
+                        serde_json::to_writer_pretty(&mut out, &graph).map_err(|e| e.to_string())
+                    })
+                    .and_then(|_| writeln!(out).map_err(|e| e.to_string()))
+            } else {
+                db.write_graph_json(&mut out).map_err(|e| e.to_string())
+            };
+            if let Err(e) = result.and_then(|_| out.flush().map_err(|e| e.to_string())) {
                 eprintln!("{} {}", "Error:".red(), e);
                 std::process::exit(1);
             }
-        },
+        }
 
-        Command::Serve { port } => {
-            println!(
-                "{} Starting graph viewer at http://localhost:{}",
-                "Deciduous".cyan(),
-                port
-            );
-            if let Err(e) = deciduous::serve::start_graph_server(port) {
-                eprintln!("{} Server error: {}", "Error:".red(), e);
-                std::process::exit(1);
+        Command::Stale {
+            decision_days,
+            action_days,
+            notify,
+            json,
+        } => report_stale(&db, decision_days, action_days, notify, json),
+
+        Command::Handoff { action } => match action {
+            HandoffAction::Write {
+                questions,
+                next,
+                gotchas,
+                goal,
+            } => {
+                let handoff = deciduous::handoff::Handoff {
+                    open_questions: questions,
+                    next_steps: next,
+                    gotchas,
+                };
+                let branch = deciduous::get_current_git_branch();
+                match deciduous::handoff::write(&db, &handoff, goal, branch.as_deref()) {
+                    Ok((id, Some(goal))) => println!(
+                        "{} handoff note {} (linked from goal {})",
+                        "Created".green(),
+                        id,
+                        goal
+                    ),
+                    Ok((id, None)) => println!(
+                        "{} handoff note {} (no goal to link it from)",
+                        "Created".green(),
+                        id
+                    ),
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                }
             }
-        }
+            HandoffAction::Read { goal, json } => {
+                let (nodes, edges) = match (db.get_all_nodes(), db.get_all_edges()) {
+                    (Ok(nodes), Ok(edges)) => (nodes, edges),
+                    (Err(e), _) | (_, Err(e)) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                };
+                match deciduous::handoff::latest(&nodes, &edges, goal) {
+                    Some(found) if json => println!(
+                        "{}",
+                        serde_json::to_string_pretty(&found).unwrap_or_default()
+                    ),
+                    Some((note, handoff)) => {
+                        println!("{} ({})\n", note.title.bold(), note.created_at);
+                        print!("{}", handoff.render());
+                    }
+                    None => println!("No handoff notes yet."),
+                }
+            }
+        },
 
-        Command::Sync { output } => {
-            // Default to docs/ for GitHub Pages compatibility
-            let output_path = output.unwrap_or_else(|| PathBuf::from("docs/graph-data.json"));
+        Command::Context { json } => {
+            let (nodes, edges) = match (db.get_all_nodes(), db.get_all_edges()) {
+                (Ok(nodes), Ok(edges)) => (nodes, edges),
+                (Err(e), _) | (_, Err(e)) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            };
+            let branch = deciduous::get_current_git_branch();
+            let goal = deciduous::focus::active_goal(&db, &nodes, branch.as_deref());
+            let context =
+                deciduous::handoff::session_context(&nodes, &edges, branch.as_deref(), goal);
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&context).unwrap_or_default()
+                );
+            } else {
+                print!("{}", deciduous::handoff::render_context(&context));
+            }
+        }
 
-            // Create parent directories if needed
-            if let Some(parent) = output_path.parent() {
-                std::fs::create_dir_all(parent).ok();
+        Command::Gc {
+            rejected_older_than,
+            dir,
+            dry_run,
+        } => {
+            let now = Local::now();
+            let cutoff = match deciduous::digest::parse_since(&rejected_older_than, now) {
+                Ok(cutoff) => cutoff,
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            };
+            let (nodes, edges) = match (db.get_all_nodes(), db.get_all_edges()) {
+                (Ok(nodes), Ok(edges)) => (nodes, edges),
+                (Err(e), _) | (_, Err(e)) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            };
+            let candidates = deciduous::gc::plan(&nodes, &edges, cutoff);
+            if candidates.is_empty() {
+                println!(
+                    "{} No options rejected before {} with anything to archive",
+                    "Result:".cyan(),
+                    cutoff.format("%Y-%m-%d")
+                );
+                return;
+            }
+            for candidate in &candidates {
+                println!(
+                    "  #{} {} (rejected {}): {} node(s)",
+                    candidate.option.id,
+                    truncate(&candidate.option.title, 50),
+                    candidate
+                        .rejected_at
+                        .get(..10)
+                        .unwrap_or(&candidate.rejected_at),
+                    candidate.nodes.len()
+                );
+            }
+            if dry_run {
+                println!("\n{} Dry run: nothing archived", "Note:".yellow());
+                return;
+            }
+            let path = dir.join(format!("gc-{}.json", now.format("%Y%m%d-%H%M%S")));
+            match deciduous::gc::collect(&db, &candidates, &path) {
+                Ok(deleted) => println!(
+                    "\n{} Archived {} node(s) from {} option(s) to {}",
+                    "Success:".green(),
+                    deleted,
+                    candidates.len(),
+                    path.display()
+                ),
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
             }
+        }
 
-            // Load config and include it in export (for external repo support, etc.)
-            let config = Config::load();
-            let include_config = config.github.commit_repo.is_some();
+        Command::Focus {
+            goal,
+            duration,
+            clear,
+        } => {
+            let now = Local::now();
+            if clear {
+                match db.clear_focus() {
+                    Ok(true) => println!("{} Focus cleared", "Done:".green()),
+                    Ok(false) => println!("No focus set."),
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                }
+            } else if let Some(goal) = goal {
+                match deciduous::focus::start(&db, goal, duration.as_deref(), now) {
+                    Ok(focus) => {
+                        let until = focus
+                            .expires_at
+                            .as_deref()
+                            .map(|t| format!(" until {}", t.get(..16).unwrap_or(t)))
+                            .unwrap_or_default();
+                        println!("{} Focused on goal #{}{}", "Done:".green(), goal, until);
+                    }
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                }
+            } else {
+                match db.get_focus() {
+                    Ok(Some(focus)) => {
+                        let title = db
+                            .get_node_by_id(focus.goal_id)
+                            .ok()
+                            .flatten()
+                            .map(|g| g.title)
+                            .unwrap_or_default();
+                        let state = match focus.expires_at.as_deref() {
+                            _ if !deciduous::focus::is_current(&focus, now) => {
+                                "expired".yellow().to_string()
+                            }
+                            Some(t) => format!("until {}", t.get(..16).unwrap_or(t)),
+                            None => "until cleared".to_string(),
+                        };
+                        println!("Focus: goal #{} {} ({})", focus.goal_id, title, state);
+                    }
+                    Ok(None) => println!("No focus set."),
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
 
-            match db.get_graph_with_config(if include_config { Some(config) } else { None }) {
-                Ok(graph) => {
-                    match serde_json::to_string_pretty(&graph) {
-                        Ok(json) => {
-                            match std::fs::write(&output_path, &json) {
-                                Ok(()) => {
-                                    println!(
-                                        "{} graph to {}",
-                                        "Exported".green(),
-                                        output_path.display()
-                                    );
-                                    println!(
-                                        "  {} nodes, {} edges",
-                                        graph.nodes.len(),
-                                        graph.edges.len()
-                                    );
+        Command::Why { file, limit, json } => {
+            let path = deciduous::file_index::repo_path(&file);
+            match deciduous::file_index::why(&db, &path, limit) {
+                Ok(found) if json => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&found).unwrap_or_default()
+                ),
+                Ok(found) if found.is_empty() => {
+                    println!("{} No nodes recorded touching {}", "Result:".cyan(), path)
+                }
+                Ok(found) => {
+                    println!(
+                        "{:<5} {:<12} {:<10} {:<14} TITLE",
+                        "ID", "TYPE", "DATE", "VIA"
+                    );
+                    println!("{}", "-".repeat(70));
+                    for t in &found {
+                        println!(
+                            "{:<5} {:<12} {:<10} {:<14} {}",
+                            t.node.id,
+                            t.node.node_type,
+                            t.node.created_at.get(..10).unwrap_or_default(),
+                            t.source,
+                            truncate(&t.node.title, 40)
+                        );
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
 
-                                    // Also sync to docs/demo/ if it exists (for GitHub Pages demo)
-                                    let demo_path = PathBuf::from("docs/demo/graph-data.json");
-                                    if demo_path.parent().map(|p| p.exists()).unwrap_or(false) {
-                                        if let Err(e) = std::fs::write(&demo_path, &json) {
-                                            eprintln!(
-                                                "{} Also writing to demo/: {}",
-                                                "Warning:".yellow(),
-                                                e
-                                            );
-                                        }
-                                    }
+        Command::Snapshot { action } => match action {
+            SnapshotAction::Create { label } => match deciduous::snapshot::create(&db, &label) {
+                Ok((id, patch)) => println!(
+                    "{} snapshot {} '{}' ({} nodes, {} edges)",
+                    "Created".green(),
+                    id,
+                    label,
+                    patch.nodes.len(),
+                    patch.edges.len()
+                ),
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            },
+            SnapshotAction::List => match db.get_snapshots() {
+                Ok(snapshots) if snapshots.is_empty() => {
+                    println!("No snapshots. Create one with: deciduous snapshot create <label>")
+                }
+                Ok(snapshots) => {
+                    println!("{:<5} {:<26} LABEL", "ID", "CREATED");
+                    println!("{}", "-".repeat(70));
+                    for s in snapshots {
+                        println!("{:<5} {:<26} {}", s.id, s.created_at, s.label);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            },
+            SnapshotAction::Diff { from, to, json } => {
+                use deciduous::snapshot::{diff, load, render};
+                let (before, after) = match (load(&db, &from), load(&db, &to)) {
+                    (Ok(before), Ok(after)) => (before, after),
+                    (Err(e), _) | (_, Err(e)) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                };
+                let changes = diff(&from, &before, &to, &after);
+                if json {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&changes).unwrap_or_default()
+                    );
+                } else {
+                    print!("{}", render(&changes, &before, &after));
+                }
+            }
+            SnapshotAction::Delete { label } => match db.delete_snapshot(&label) {
+                Ok(true) => println!("{} snapshot '{}'", "Deleted".green(), label),
+                Ok(false) => {
+                    eprintln!("{} No snapshot '{}'", "Error:".red(), label);
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            },
+        },
 
-                                    // Export git history for linked commits
-                                    // Skip when external repo is configured (commits won't be in local git)
-                                    if !include_config {
-                                        if let Some(output_dir) = output_path.parent() {
-                                            match export_git_history(&graph.nodes, output_dir) {
-                                                Ok(count) => {
-                                                    if count > 0 {
-                                                        println!(
-                                                            "{} git-history.json ({} commits)",
-                                                            "Exported".green(),
-                                                            count
-                                                        );
-                                                    }
-                                                    // Also sync to docs/demo/ if it exists
-                                                    let demo_dir = PathBuf::from("docs/demo");
-                                                    if demo_dir.exists() {
-                                                        if let Err(e) = export_git_history(
-                                                            &graph.nodes,
-                                                            &demo_dir,
-                                                        ) {
-                                                            eprintln!("{} Also writing git history to demo/: {}", "Warning:".yellow(), e);
-                                                        }
-                                                    }
-                                                }
-                                                Err(e) => {
-                                                    // Non-fatal: git history is optional
-                                                    eprintln!(
-                                                        "{} Exporting git history: {}",
-                                                        "Warning:".yellow(),
-                                                        e
-                                                    );
-                                                }
-                                            }
-                                        }
-                                    } else {
-                                        // External repo mode: preserve existing git-history.json
-                                        if let Some(output_dir) = output_path.parent() {
-                                            let git_history_path =
-                                                output_dir.join("git-history.json");
-                                            if git_history_path.exists() {
-                                                println!(
-                                                    "{} git-history.json (external repo mode - manually managed)",
-                                                    "Preserved".cyan()
-                                                );
-                                            } else {
-                                                println!(
-                                                    "{} Create docs/git-history.json manually for external repo commits",
-                                                    "Note:".yellow()
-                                                );
-                                            }
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!("{} Writing file: {}", "Error:".red(), e);
-                                    std::process::exit(1);
-                                }
-                            }
-                        }
+        Command::Federate { action } => {
+            match action {
+                FederateAction::Add { path, name } => {
+                    match deciduous::federation::register(&db, &path, name.as_deref()) {
+                        Ok(repo) => println!(
+                            "{} Federated '{}' ({})",
+                            "Added".green(),
+                            repo.name,
+                            repo.db_path
+                        ),
                         Err(e) => {
-                            eprintln!("{} Serializing graph: {}", "Error:".red(), e);
+                            eprintln!("{} {}", "Error:".red(), e);
                             std::process::exit(1);
                         }
                     }
                 }
+                FederateAction::List => match db.get_federated_repos() {
+                    Ok(repos) if repos.is_empty() => {
+                        println!("No federated repos. Add one with: deciduous federate add ../other-repo")
+                    }
+                    Ok(repos) => {
+                        println!("{:<20} {:<26} DATABASE", "NAME", "ADDED");
+                        println!("{}", "-".repeat(70));
+                        for repo in repos {
+                            println!("{:<20} {:<26} {}", repo.name, repo.added_at, repo.db_path);
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                },
+                FederateAction::Remove { name } => match db.remove_federated_repo(&name) {
+                    Ok(true) => println!("{} Removed '{}'", "✓".green(), name),
+                    Ok(false) => {
+                        eprintln!("{} No federated repo named '{}'", "Error:".red(), name);
+                        std::process::exit(1);
+                    }
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                },
+            }
+        }
+
+        Command::Digest {
+            since,
+            output,
+            slack,
+        } => {
+            use deciduous::digest::{collect, parse_since, render};
+
+            let since = match parse_since(&since, Local::now()) {
+                Ok(since) => since,
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            };
+            let prices = PriceTable::from_config(&Config::load());
+            let text = match collect(&db, since, &prices) {
+                Ok(digest) => render(&digest, slack),
                 Err(e) => {
                     eprintln!("{} {}", "Error:".red(), e);
                     std::process::exit(1);
                 }
+            };
+            match output {
+                Some(path) => match std::fs::write(&path, text) {
+                    Ok(()) => println!("{} digest to {}", "Wrote".green(), path.display()),
+                    Err(e) => {
+                        eprintln!("{} {}: {}", "Error:".red(), path.display(), e);
+                        std::process::exit(1);
+                    }
+                },
+                None => print!("{}", text),
+            }
+        }
+
+        Command::Metrics { format, output } => {
+            use deciduous::metrics::{compute, to_csv, to_text, FORMATS};
+
+            if !FORMATS.contains(&format.as_str()) {
+                eprintln!(
+                    "{} Unknown format '{}' (expected {})",
+                    "Error:".red(),
+                    format,
+                    FORMATS.join(" or ")
+                );
+                std::process::exit(1);
+            }
+            let metrics = match (db.get_all_nodes(), db.get_all_edges()) {
+                (Ok(nodes), Ok(edges)) => compute(&nodes, &edges),
+                (Err(e), _) | (_, Err(e)) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            };
+            let text = match format.as_str() {
+                "csv" => to_csv(&metrics),
+                "text" => to_text(&metrics),
+                _ => serde_json::to_string_pretty(&metrics).unwrap_or_default() + "\n",
+            };
+            match output {
+                Some(path) => match std::fs::write(&path, text) {
+                    Ok(()) => println!(
+                        "{} metrics for {} nodes to {}",
+                        "Wrote".green(),
+                        metrics.graph.nodes,
+                        path.display()
+                    ),
+                    Err(e) => {
+                        eprintln!("{} {}: {}", "Error:".red(), path.display(), e);
+                        std::process::exit(1);
+                    }
+                },
+                None => print!("{}", text),
+            }
+        }
+
+        Command::Serve {
+            port,
+            host,
+            sync,
+            grpc,
+            federated,
+        } => {
+            if grpc {
+                #[cfg(feature = "grpc")]
+                let result = {
+                    println!("{} Serving gRPC on {}:{}", "Deciduous".cyan(), host, port);
+                    deciduous::grpc::start_grpc_server(&host, port)
+                };
+                #[cfg(not(feature = "grpc"))]
+                let result: Result<(), String> = Err(
+                    "this build has no gRPC support (rebuild with --features grpc)".to_string(),
+                );
+                if let Err(e) = result {
+                    eprintln!("{} Server error: {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+                return;
+            }
+            println!(
+                "{} Starting graph viewer at http://localhost:{}",
+                "Deciduous".cyan(),
+                port
+            );
+            let loopback = host == "127.0.0.1" || host == "localhost" || host == "::1";
+            if sync && !loopback && deciduous::hub::sync_token(&Config::load()).is_none() {
+                eprintln!(
+                    "{} Patch hub is reachable on {} without a token; set {} or [sync] token",
+                    "Warning:".yellow(),
+                    host,
+                    deciduous::hub::SYNC_TOKEN_ENV
+                );
+            }
+            if let Err(e) = deciduous::serve::start_graph_server(
+                &host,
+                port,
+                sync,
+                deciduous::serve::GraphScope {
+                    federated,
+                    project: args.scope.clone(),
+                },
+            ) {
+                eprintln!("{} Server error: {}", "Error:".red(), e);
+                std::process::exit(1);
+            }
+        }
+
+        Command::Mcp => {
+            if let Err(e) = deciduous::mcp::serve(&db) {
+                eprintln!("{} {}", "Error:".red(), e);
+                std::process::exit(1);
+            }
+        }
+
+        Command::Daemon { socket } => {
+            let result = match socket {
+                #[cfg(unix)]
+                Some(path) => deciduous::daemon::serve_socket(&db, &path),
+                #[cfg(not(unix))]
+                Some(_) => Err("--socket needs Unix sockets; use stdin/stdout".to_string()),
+                None => deciduous::daemon::serve_stdio(&db),
+            };
+            if let Err(e) = result {
+                eprintln!("{} {}", "Error:".red(), e);
+                std::process::exit(1);
+            }
+        }
+
+        Command::Sync {
+            output,
+            profile,
+            per_project,
+            notion,
+        } => {
+            sync_export(&db, output, profile, per_project);
+            if notion {
+                let config = Config::load().notion;
+                let result =
+                    deciduous::notion::NotionClient::from_config(&config).and_then(|client| {
+                        let nodes = db.get_all_nodes().map_err(|e| e.to_string())?;
+                        deciduous::notion::mirror(&client, &nodes, &config)
+                    });
+                match result {
+                    Ok(plan) => println!(
+                        "{} Notion: {} created, {} updated, {} archived, {} unchanged",
+                        "Synced".green(),
+                        plan.create.len(),
+                        plan.update.len(),
+                        plan.archive.len(),
+                        plan.unchanged
+                    ),
+                    Err(e) => {
+                        eprintln!("{} Notion: {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                }
             }
         }
 
@@ -1157,7 +2944,7 @@ fn main() {
             }
         }
 
-        Command::Commands { limit } => match db.get_recent_commands(limit) {
+        Command::Commands { limit, verbose } => match db.get_recent_commands(limit) {
             Ok(commands) => {
                 if commands.is_empty() {
                     println!("No commands logged.");
@@ -1169,8 +2956,21 @@ fn main() {
                             truncate(&c.command, 60),
                             c.exit_code
                                 .map(|c| c.to_string())
-                                .unwrap_or_else(|| "running".to_string())
+                                .unwrap_or_else(|| "running or failed".to_string())
                         );
+                        if verbose {
+                            for change in db.get_command_changes(c.id).unwrap_or_default() {
+                                let sign = match change.action.as_str() {
+                                    "created" => "+".green(),
+                                    "deleted" => "-".red(),
+                                    _ => "~".yellow(),
+                                };
+                                println!(
+                                    "    {} {} {} {}",
+                                    sign, change.kind, change.target_id, change.detail
+                                );
+                            }
+                        }
                     }
                 }
             }
@@ -1406,2002 +3206,7966 @@ fn main() {
             }
         }
 
-        Command::Migrate => match db.migrate_add_change_ids() {
-            Ok(true) => {
-                println!(
-                    "{} Database migrated - added change_id columns for multi-user sync",
-                    "Success:".green()
-                );
+        Command::Import { action } => {
+            let (source, dirs, dry_run) = match action {
+                ImportAction::Adr { dir, dry_run } => ("madr", Ok(vec![dir]), dry_run),
+                ImportAction::Log4brains { root, dry_run } => (
+                    "log4brains",
+                    deciduous::adr_import::log4brains_dirs(&root),
+                    dry_run,
+                ),
+            };
+            let adrs = match dirs.and_then(|dirs| {
+                let mut adrs = Vec::new();
+                for dir in dirs {
+                    adrs.extend(deciduous::adr_import::read_dir(&dir)?);
+                }
+                Ok(adrs)
+            }) {
+                Ok(adrs) => adrs,
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            };
+            println!("{} {} decision records", "Import:".cyan(), adrs.len());
+            if dry_run {
+                for adr in &adrs {
+                    println!(
+                        "  {} [{}] {} ({} options{})",
+                        adr.path,
+                        if adr.status.is_empty() {
+                            "no status"
+                        } else {
+                            adr.status.as_str()
+                        },
+                        adr.title,
+                        adr.options.len(),
+                        adr.chosen
+                            .as_ref()
+                            .map(|c| format!(", chose {}", c))
+                            .unwrap_or_default()
+                    );
+                }
+                println!("\n{} Dry run: nothing imported", "Note:".yellow());
+                return;
             }
-            Ok(false) => {
+            match deciduous::adr_import::import(&db, &adrs, source) {
+                Ok(summary) => println!(
+                    "{} {} decisions created, {} updated; {} options/outcomes, {} edges",
+                    "Success:".green(),
+                    summary.decisions_created,
+                    summary.decisions_updated,
+                    summary.nodes_created,
+                    summary.edges_created
+                ),
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        Command::Github { action } => match action {
+            GithubAction::Import {
+                label,
+                repo,
+                comments,
+                dry_run,
+            } => {
+                let config = Config::load();
+                let forge = match detect_forge(repo, &config) {
+                    Ok(f) => f,
+                    Err(e) => {
+                        eprintln!("{} Auto-detecting repo: {}", "Error:".red(), e);
+                        eprintln!("Specify repo with --repo owner/repo");
+                        std::process::exit(1);
+                    }
+                };
+
+                let Some(repo_name) = forge.repo_name().map(|r| r.to_string()) else {
+                    eprintln!("{} Could not determine repository", "Error:".red());
+                    eprintln!("Specify repo with --repo owner/repo");
+                    std::process::exit(1);
+                };
+
+                let issues = match forge.list_issues_with_label(&label) {
+                    Ok(i) => i,
+                    Err(e) => {
+                        eprintln!("{} Listing issues: {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                };
+
                 println!(
-                    "{} Database already has change_id columns - no migration needed",
-                    "Info:".cyan()
+                    "{} {} issues labeled '{}' in {}",
+                    "Import:".cyan(),
+                    issues.len(),
+                    label,
+                    repo_name
                 );
-            }
-            Err(e) => {
-                eprintln!("{} Migration failed: {}", "Error:".red(), e);
-                std::process::exit(1);
-            }
-        },
 
-        Command::Diff { action } => {
-            match action {
-                DiffAction::Export {
-                    output,
-                    nodes,
-                    branch,
-                    author,
-                    base_commit,
-                } => {
-                    // Parse node IDs if provided
-                    let node_ids = nodes.as_ref().map(|n| parse_node_range(n));
+                let mut created = 0;
+                let mut updated = 0;
+                let mut unchanged = 0;
+
+                for issue in &issues {
+                    let is_open = issue.state == "open";
+                    let body = Some(issue.body.trim()).filter(|b| !b.is_empty());
+                    let issue_meta = serde_json::json!({
+                        "repo": repo_name,
+                        "number": issue.number,
+                        "key": forge.issue_ref(issue.number),
+                        "url": issue.html_url,
+                        "state": issue.state,
+                        "forge": forge.kind().name().to_lowercase(),
+                    });
 
-                    match db.export_patch(node_ids, branch.as_deref(), author, base_commit) {
-                        Ok(patch) => match patch.save(&output) {
-                            Ok(()) => {
-                                println!(
-                                    "{} Exported {} nodes and {} edges to {}",
-                                    "Success:".green(),
-                                    patch.nodes.len(),
-                                    patch.edges.len(),
-                                    output.display()
-                                );
-                            }
-                            Err(e) => {
-                                eprintln!("{} {}", "Error:".red(), e);
-                                std::process::exit(1);
-                            }
-                        },
+                    let existing = match db.find_node_by_issue(&repo_name, issue.number) {
+                        Ok(n) => n,
                         Err(e) => {
                             eprintln!("{} {}", "Error:".red(), e);
                             std::process::exit(1);
                         }
-                    }
-                }
+                    };
 
-                DiffAction::Apply { files, dry_run } => {
-                    let mut total_added = 0;
-                    let mut total_skipped = 0;
-                    let mut total_edges_added = 0;
-                    let mut total_edges_skipped = 0;
+                    match existing {
+                        Some(node) => {
+                            let content_changed =
+                                node.title != issue.title || node.description.as_deref() != body;
+                            let newly_closed = !is_open && node.status != "completed";
 
-                    for file in files {
-                        match deciduous::GraphPatch::load(&file) {
-                            Ok(patch) => match db.apply_patch(&patch, dry_run) {
-                                Ok(result) => {
-                                    if dry_run {
-                                        println!(
-                                            "{} {} (dry run)",
-                                            "Would apply:".cyan(),
-                                            file.display()
-                                        );
+                            if !content_changed && !newly_closed {
+                                unchanged += 1;
+                                continue;
+                            }
+
+                            if dry_run {
+                                println!(
+                                    "  {} Would update node {} from #{}: {}",
+                                    "[DRY]".yellow(),
+                                    node.id,
+                                    issue.number,
+                                    issue.title
+                                );
+                                updated += 1;
+                                continue;
+                            }
+
+                            let result = db
+                                .update_node_content(node.id, &issue.title, body)
+                                .and_then(|_| {
+                                    db.update_node_metadata_field(node.id, "issue", issue_meta)
+                                })
+                                .and_then(|_| {
+                                    if newly_closed {
+                                        db.update_node_status(node.id, "completed")
                                     } else {
-                                        println!("{} {}", "Applied:".green(), file.display());
+                                        Ok(())
                                     }
+                                });
+                            match result {
+                                Ok(()) => {
                                     println!(
-                                        "  Nodes: {} added, {} skipped",
-                                        result.nodes_added, result.nodes_skipped
+                                        "  {} Updated node {} from #{}: {}",
+                                        "✓".green(),
+                                        node.id,
+                                        issue.number,
+                                        issue.title
                                     );
+                                    updated += 1;
+                                }
+                                Err(e) => {
+                                    eprintln!("  {} Updating node {}: {}", "✗".red(), node.id, e)
+                                }
+                            }
+                        }
+                        None if !is_open => {
+                            // Closed issues we never imported aren't worth a goal
+                            unchanged += 1;
+                        }
+                        None => {
+                            if dry_run {
+                                println!(
+                                    "  {} Would create goal from #{}: {}",
+                                    "[DRY]".yellow(),
+                                    issue.number,
+                                    issue.title
+                                );
+                                created += 1;
+                                continue;
+                            }
+
+                            let branch = deciduous::get_current_git_branch();
+                            let result = db
+                                .create_node_full(
+                                    "goal",
+                                    &issue.title,
+                                    body,
+                                    None,
+                                    None,
+                                    None,
+                                    None,
+                                    branch.as_deref(),
+                                )
+                                .and_then(|id| {
+                                    db.update_node_metadata_field(id, "issue", issue_meta)
+                                        .map(|_| id)
+                                });
+                            match result {
+                                Ok(id) => {
                                     println!(
-                                        "  Edges: {} added, {} skipped",
-                                        result.edges_added, result.edges_skipped
+                                        "  {} Created goal {} from #{}: {}",
+                                        "✓".green(),
+                                        id,
+                                        issue.number,
+                                        issue.title
                                     );
-                                    if !result.edges_failed.is_empty() {
-                                        println!(
-                                            "  {} edges failed (missing nodes):",
-                                            result.edges_failed.len()
-                                        );
-                                        for msg in &result.edges_failed {
-                                            println!("    - {}", msg);
-                                        }
-                                    }
-                                    total_added += result.nodes_added;
-                                    total_skipped += result.nodes_skipped;
-                                    total_edges_added += result.edges_added;
-                                    total_edges_skipped += result.edges_skipped;
+                                    created += 1;
                                 }
                                 Err(e) => {
-                                    eprintln!(
-                                        "{} Applying {}: {}",
-                                        "Error:".red(),
-                                        file.display(),
-                                        e
-                                    );
+                                    eprintln!("  {} Importing #{}: {}", "✗".red(), issue.number, e)
                                 }
-                            },
-                            Err(e) => {
-                                eprintln!("{} Loading {}: {}", "Error:".red(), file.display(), e);
                             }
                         }
                     }
+                }
 
-                    if !dry_run {
-                        println!(
-                            "\n{} {} nodes added, {} skipped; {} edges added, {} skipped",
-                            "Total:".cyan(),
-                            total_added,
-                            total_skipped,
-                            total_edges_added,
-                            total_edges_skipped
-                        );
+                // Pull discussion from each imported goal's issue
+                let mut observations = 0;
+                if comments {
+                    for issue in &issues {
+                        let node_id = match db.find_node_by_issue(&repo_name, issue.number) {
+                            Ok(Some(node)) => node.id,
+                            _ => continue, // Not imported (or dry run before creation)
+                        };
+                        match import_issue_comments(
+                            &db,
+                            forge.as_ref(),
+                            &repo_name,
+                            issue.number,
+                            node_id,
+                            dry_run,
+                        ) {
+                            Ok(n) => observations += n,
+                            Err(e) => eprintln!(
+                                "  {} Comments for #{}: {}",
+                                "Warning:".yellow(),
+                                issue.number,
+                                e
+                            ),
+                        }
                     }
                 }
 
-                DiffAction::Status { path } => {
-                    let patches_dir = path.unwrap_or_else(|| PathBuf::from(".deciduous/patches"));
-                    if !patches_dir.exists() {
+                println!();
+                println!(
+                    "{} {} created, {} updated, {} unchanged",
+                    if dry_run {
+                        "Would import:".yellow()
+                    } else {
+                        "Imported:".green()
+                    },
+                    created,
+                    updated,
+                    unchanged
+                );
+                if comments {
+                    println!("  {} new comment observations", observations);
+                }
+            }
+
+            GithubAction::Refresh { repo, all } => {
+                let config = Config::load();
+                let forge = match detect_forge(repo, &config) {
+                    Ok(f) => f,
+                    Err(e) => {
+                        eprintln!("{} Auto-detecting repo: {}", "Error:".red(), e);
+                        eprintln!("Specify repo with --repo owner/repo");
+                        std::process::exit(1);
+                    }
+                };
+
+                let max_age = if all {
+                    None
+                } else {
+                    Some(config.github.cache_ttl_hours.max(0))
+                };
+                match deciduous::cache::refresh_cache(&db, forge.as_ref(), max_age) {
+                    Ok(summary) => {
+                        for failure in &summary.failed {
+                            println!("  {} {}", "✗".red(), failure);
+                        }
                         println!(
-                            "{} No patches directory found at {}",
-                            "Info:".cyan(),
-                            patches_dir.display()
+                            "{} {} issues, {} pull requests ({} roadmap items updated)",
+                            "Refreshed:".green(),
+                            summary.issues,
+                            summary.pull_requests,
+                            summary.roadmap_items
                         );
-                        println!("Create one with: mkdir -p {}", patches_dir.display());
-                        return;
                     }
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                }
+            }
 
-                    // List all .json files in the directory
-                    let entries = match std::fs::read_dir(&patches_dir) {
-                        Ok(e) => e,
-                        Err(e) => {
-                            eprintln!("{} Reading directory: {}", "Error:".red(), e);
-                            return;
-                        }
-                    };
+            GithubAction::PrStatus { repo } => {
+                let nodes = match db.get_all_nodes() {
+                    Ok(n) => n,
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                };
 
-                    println!("{} {}", "Patches in:".cyan(), patches_dir.display());
-                    for entry in entries.flatten() {
-                        let path = entry.path();
-                        if path.extension().map(|e| e == "json").unwrap_or(false) {
-                            if let Ok(patch) = deciduous::GraphPatch::load(&path) {
-                                let author = patch.author.as_deref().unwrap_or("unknown");
-                                let branch = patch.branch.as_deref().unwrap_or("unknown");
-                                println!(
-                                    "  {} - {} nodes, {} edges (author: {}, branch: {})",
-                                    path.file_name().unwrap_or_default().to_string_lossy(),
-                                    patch.nodes.len(),
-                                    patch.edges.len(),
-                                    author,
-                                    branch
-                                );
-                            }
-                        }
+                let mut linked: std::collections::BTreeMap<i32, Vec<&deciduous::DecisionNode>> =
+                    std::collections::BTreeMap::new();
+                for node in &nodes {
+                    if let Some(number) = deciduous::db::node_pr_number(node) {
+                        linked.entry(number).or_default().push(node);
                     }
                 }
 
-                DiffAction::Validate { files } => {
-                    use std::collections::HashSet;
+                if linked.is_empty() {
+                    println!(
+                        "No nodes are linked to pull requests (use 'deciduous add ... --pr N')"
+                    );
+                    return;
+                }
 
-                    let mut any_errors = false;
+                let config = Config::load();
+                let forge = match detect_forge(repo, &config) {
+                    Ok(f) => f,
+                    Err(e) => {
+                        eprintln!("{} Auto-detecting repo: {}", "Error:".red(), e);
+                        eprintln!("Specify repo with --repo owner/repo");
+                        std::process::exit(1);
+                    }
+                };
+
+                println!("{} {} linked pull requests", "PRs:".cyan(), linked.len());
+                for (number, linked_nodes) in &linked {
+                    let ids: Vec<String> = linked_nodes.iter().map(|n| n.id.to_string()).collect();
+                    match deciduous::cache::refresh_pull_request(&db, forge.as_ref(), *number) {
+                        Ok(pr) => {
+                            let state = match pr.state.as_str() {
+                                "merged" => pr.state.magenta(),
+                                "closed" => pr.state.red(),
+                                _ => pr.state.green(),
+                            };
+                            println!(
+                                "  #{} [{}] {} (nodes: {})",
+                                number,
+                                state,
+                                pr.title,
+                                ids.join(", ")
+                            );
+                        }
+                        Err(e) => println!(
+                            "  {} #{} (nodes: {}): {}",
+                            "✗".red(),
+                            number,
+                            ids.join(", "),
+                            e
+                        ),
+                    }
+                }
+            }
+        },
 
-                    for file in &files {
-                        match deciduous::GraphPatch::load(file) {
-                            Ok(patch) => {
-                                // Collect all node change_ids in the patch
-                                let node_ids: HashSet<&str> =
-                                    patch.nodes.iter().map(|n| n.change_id.as_str()).collect();
+        Command::Pr { action } => match action {
+            PrAction::Comment {
+                pr,
+                title,
+                roots,
+                nodes,
+                png,
+                no_dot,
+                no_test_plan,
+                repo,
+                dry_run,
+            } => {
+                let graph = match db.get_graph() {
+                    Ok(g) => g,
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                };
+
+                let filtered_graph = if let Some(node_spec) = nodes {
+                    let node_ids = parse_node_range(&node_spec);
+                    filter_graph_by_ids(&graph, &node_ids)
+                } else if let Some(root_spec) = roots {
+                    let root_ids: Vec<i32> = root_spec
+                        .split(',')
+                        .filter_map(|s| s.trim().parse().ok())
+                        .collect();
+                    deciduous::filter_graph_from_roots(&graph, &root_ids)
+                } else {
+                    graph
+                };
 
-                                // Check each edge for missing nodes
-                                let mut missing_edges = Vec::new();
-                                for edge in &patch.edges {
-                                    let from_missing =
-                                        !node_ids.contains(edge.from_change_id.as_str());
-                                    let to_missing = !node_ids.contains(edge.to_change_id.as_str());
+                let config = Config::load();
+                let forge = match detect_forge(repo, &config) {
+                    Ok(f) => f,
+                    Err(e) => {
+                        eprintln!("{} Auto-detecting repo: {}", "Error:".red(), e);
+                        eprintln!("Specify repo with --repo owner/repo");
+                        std::process::exit(1);
+                    }
+                };
 
-                                    if from_missing || to_missing {
-                                        let mut missing = Vec::new();
-                                        if from_missing {
-                                            missing.push(format!(
-                                                "from: {}",
-                                                &edge.from_change_id
-                                                    [..8.min(edge.from_change_id.len())]
-                                            ));
-                                        }
-                                        if to_missing {
-                                            missing.push(format!(
-                                                "to: {}",
-                                                &edge.to_change_id
-                                                    [..8.min(edge.to_change_id.len())]
-                                            ));
-                                        }
-                                        missing_edges
-                                            .push((edge.edge_type.clone(), missing.join(", ")));
-                                    }
-                                }
+                let git_branch = deciduous::get_current_git_branch();
 
-                                println!("{} {}", "Validating:".cyan(), file.display());
-                                println!("  Nodes: {}", patch.nodes.len());
-                                println!(
-                                    "  Edges: {} ({} valid, {} with missing refs)",
-                                    patch.edges.len(),
-                                    patch.edges.len() - missing_edges.len(),
-                                    missing_edges.len()
-                                );
+                // Resolve the PR: explicit number, or the open PR for this branch
+                let pull_request = match pr {
+                    Some(n) => Some((n, None)),
+                    None => {
+                        let Some(branch) = git_branch.as_deref() else {
+                            eprintln!("{} Not on a branch; pass --pr <number>", "Error:".red());
+                            std::process::exit(1);
+                        };
+                        match forge.find_pull_request_for_branch(branch) {
+                            Ok(Some(found)) => Some((found.number, Some(found.title))),
+                            Ok(None) => None,
+                            Err(e) => {
+                                eprintln!("{} Looking up PR: {}", "Error:".red(), e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                };
+                let Some((pr_number, pr_title)) = pull_request else {
+                    eprintln!(
+                        "{} No open PR for branch '{}'",
+                        "Error:".red(),
+                        git_branch.unwrap_or_default()
+                    );
+                    eprintln!("Push the branch and open a PR, or pass --pr <number>");
+                    std::process::exit(1);
+                };
 
-                                if !missing_edges.is_empty() {
-                                    any_errors = true;
-                                    println!(
-                                        "  {} Edges referencing missing nodes:",
-                                        "Warning:".yellow()
-                                    );
-                                    for (edge_type, missing) in &missing_edges {
-                                        println!("    - {} edge: missing {}", edge_type, missing);
-                                    }
-                                    println!();
-                                    println!("  {} This patch has edges that reference nodes not in the patch.", "Note:".cyan());
-                                    println!("  When applied, these edges will fail unless the referenced nodes");
-                                    println!("  already exist in the target database or are imported first.");
-                                    println!();
-                                    println!("  {} Re-export with all dependent nodes, or apply patches in order:", "Fix:".green());
-                                    println!(
-                                        "    1. Apply the patch containing the parent nodes first"
-                                    );
-                                    println!("    2. Then apply this patch");
-                                } else {
-                                    println!(
-                                        "  {} All edges reference nodes within the patch",
-                                        "OK:".green()
-                                    );
-                                }
+                // Embed the graph PNG via a raw URL on the PR branch
+                let png_path = png.or_else(|| {
+                    git_branch.as_ref().and_then(|branch| {
+                        let path = format!("docs/decision-graph-{}.png", branch.replace('/', "-"));
+                        PathBuf::from(&path).exists().then_some(path)
+                    })
+                });
+                let code_host = deciduous::forge::code_host(forge.as_ref());
+                let png_filename = match (&png_path, code_host, &git_branch) {
+                    (Some(path), Some((kind, host, repo_name)), Some(branch)) => Some(
+                        deciduous::forge::raw_file_url(kind, &host, &repo_name, branch, path),
+                    ),
+                    _ => png_path.clone(),
+                };
+                if png_path.is_none() && !no_dot {
+                    println!(
+                        "{} No graph PNG found; run 'deciduous dot --auto' and push it to embed an image",
+                        "Info:".cyan()
+                    );
+                }
+
+                let writeup_config = WriteupConfig {
+                    title: title
+                        .or(pr_title)
+                        .unwrap_or_else(|| "Pull Request".to_string()),
+                    root_ids: vec![], // Already filtered above
+                    include_dot: !no_dot,
+                    include_test_plan: !no_test_plan,
+                    png_filename,
+                    github_repo: None, // png_filename is already a full URL
+                    git_branch,
+                };
+
+                let writeup = generate_pr_writeup(&filtered_graph, &writeup_config);
+
+                if dry_run {
+                    println!(
+                        "{} Would post writeup to {} PR #{}:\n",
+                        "[DRY]".yellow(),
+                        forge.kind(),
+                        pr_number
+                    );
+                    println!("{}", writeup);
+                    return;
+                }
+
+                match deciduous::forge::upsert_pr_comment(forge.as_ref(), pr_number, &writeup) {
+                    Ok(CommentUpsert::Created) => {
+                        println!(
+                            "{} Posted writeup comment on PR #{}",
+                            "Success:".green(),
+                            pr_number
+                        );
+                    }
+                    Ok(CommentUpsert::Updated(id)) => {
+                        println!(
+                            "{} Updated writeup comment {} on PR #{}",
+                            "Success:".green(),
+                            id,
+                            pr_number
+                        );
+                    }
+                    Err(e) => {
+                        eprintln!("{} Posting comment: {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+
+            PrAction::Merged {
+                pr,
+                branch,
+                title,
+                repo,
+                dry_run,
+            } => {
+                let pull_request = match (branch, title) {
+                    (Some(branch), Some(title)) => deciduous::forge::PullRequest {
+                        number: pr,
+                        title,
+                        state: "merged".to_string(),
+                        head_branch: branch,
+                        html_url: String::new(),
+                    },
+                    (branch, title) => {
+                        let config = Config::load();
+                        let forge = match detect_forge(repo, &config) {
+                            Ok(f) => f,
+                            Err(e) => {
+                                eprintln!("{} Auto-detecting repo: {}", "Error:".red(), e);
+                                eprintln!("Specify repo with --repo owner/repo, or pass --branch and --title");
+                                std::process::exit(1);
                             }
+                        };
+                        let mut found = match forge.get_pull_request(pr) {
+                            Ok(found) => found,
                             Err(e) => {
-                                any_errors = true;
-                                eprintln!("{} {}: {}", "Error:".red(), file.display(), e);
+                                eprintln!("{} Looking up PR #{}: {}", "Error:".red(), pr, e);
+                                std::process::exit(1);
                             }
+                        };
+                        if found.state != "merged" {
+                            eprintln!(
+                                "{} PR #{} is {}, not merged",
+                                "Error:".red(),
+                                pr,
+                                found.state
+                            );
+                            std::process::exit(1);
                         }
-                        println!();
+                        found.head_branch = branch.unwrap_or(found.head_branch);
+                        found.title = title.unwrap_or(found.title);
+                        found
                     }
+                };
 
-                    if any_errors {
+                let nodes = match db.get_all_nodes() {
+                    Ok(n) => n,
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
                         std::process::exit(1);
                     }
+                };
+                let plan = deciduous::pr_merged::plan(&nodes, pr, &pull_request.head_branch);
+                if let Some(id) = plan.existing {
+                    println!(
+                        "{} PR #{} already has outcome node {}",
+                        "Info:".cyan(),
+                        pr,
+                        id
+                    );
+                    return;
                 }
-            }
-        }
-
-        Command::Tui { .. } => unreachable!(), // Handled above
-        Command::Completion { .. } => unreachable!(), // Handled above
-
-        Command::Audit {
-            associate_commits,
-            min_score,
-            dry_run,
-            yes,
-        } => {
-            if !associate_commits {
-                eprintln!(
-                    "{} No audit action specified. Use --associate-commits",
-                    "Error:".red()
-                );
-                std::process::exit(1);
-            }
-
-            // Get all nodes
-            let nodes = match db.get_all_nodes() {
-                Ok(n) => n,
-                Err(e) => {
-                    eprintln!("{} {}", "Error:".red(), e);
-                    std::process::exit(1);
+                if dry_run {
+                    println!("{} Would add outcome:\n", "[DRY]".yellow());
+                    println!("{}", deciduous::pr_merged::summary(&pull_request, &plan));
+                    return;
+                }
+                match deciduous::pr_merged::record(&db, &pull_request, &plan) {
+                    Ok(id) => println!(
+                        "{} outcome {}: Merged PR #{} ({} goals and {} actions linked, {} actions completed)",
+                        "Created".green(),
+                        id,
+                        pr,
+                        plan.goals.len(),
+                        plan.committed.len(),
+                        plan.to_complete.len()
+                    ),
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
                 }
-            };
-
-            // Get git commits since Nov 2024
-            let commits = get_git_commits_for_audit();
-            if commits.is_empty() {
-                eprintln!("{} No git commits found", "Error:".red());
-                std::process::exit(1);
             }
 
-            println!(
-                "{} {} nodes, {} commits",
-                "Analyzing:".cyan(),
-                nodes.len(),
-                commits.len()
-            );
-
-            // Find action/outcome nodes without commits
-            let nodes_to_check: Vec<_> = nodes
-                .iter()
-                .filter(|n| n.node_type == "action" || n.node_type == "outcome")
-                .filter(|n| {
-                    // Check if already has commit
-                    !n.metadata_json
-                        .as_ref()
-                        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
-                        .and_then(|v| {
-                            v.get("commit")
-                                .and_then(|c| c.as_str())
-                                .map(|s| !s.is_empty())
-                        })
-                        .unwrap_or(false)
-                })
-                .collect();
-
-            let with_commits = nodes
-                .iter()
-                .filter(|n| n.node_type == "action" || n.node_type == "outcome")
-                .filter(|n| {
-                    n.metadata_json
-                        .as_ref()
-                        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
-                        .and_then(|v| {
-                            v.get("commit")
-                                .and_then(|c| c.as_str())
-                                .map(|s| !s.is_empty())
-                        })
-                        .unwrap_or(false)
-                })
-                .count();
+            PrAction::ImportReviews { pr, repo, dry_run } => {
+                let config = Config::load();
+                let forge = match detect_forge(repo, &config) {
+                    Ok(f) => f,
+                    Err(e) => {
+                        eprintln!("{} Auto-detecting repo: {}", "Error:".red(), e);
+                        eprintln!("Specify repo with --repo owner/repo");
+                        std::process::exit(1);
+                    }
+                };
 
-            println!(
-                "  Action/outcome nodes: {} with commits, {} without",
-                with_commits,
-                nodes_to_check.len()
-            );
+                let Some(repo_name) = forge.repo_name().map(|r| r.to_string()) else {
+                    eprintln!("{} Could not determine repository", "Error:".red());
+                    eprintln!("Specify repo with --repo owner/repo");
+                    std::process::exit(1);
+                };
 
-            // Find matches
-            let mut matches: Vec<CommitMatch> = Vec::new();
-            let threshold = min_score as f64 / 100.0;
+                let threads = match forge.get_review_threads(pr) {
+                    Ok(t) => t,
+                    Err(e) => {
+                        eprintln!("{} Fetching review threads: {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                };
 
-            for node in &nodes_to_check {
-                let mut best_match: Option<(&AuditCommit, f64)> = None;
+                println!(
+                    "{} {} review threads on #{} in {}",
+                    "Import:".cyan(),
+                    threads.len(),
+                    pr,
+                    repo_name
+                );
 
-                for commit in &commits {
-                    let score = keyword_match_score(&node.title, &commit.message);
-                    if score >= threshold && (best_match.is_none() || score > best_match.unwrap().1)
-                    {
-                        best_match = Some((commit, score));
+                match import_review_threads(&db, &repo_name, pr, &threads, dry_run) {
+                    Ok((created, resolved)) => {
+                        println!();
+                        println!(
+                            "{} {} created, {} marked resolved",
+                            if dry_run {
+                                "Would import:".yellow()
+                            } else {
+                                "Imported:".green()
+                            },
+                            created,
+                            resolved
+                        );
+                    }
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
                     }
-                }
-
-                if let Some((commit, score)) = best_match {
-                    matches.push(CommitMatch {
-                        node_id: node.id,
-                        node_title: node.title.clone(),
-                        commit_hash: commit.hash.clone(),
-                        commit_message: commit.message.clone(),
-                        score,
-                    });
                 }
             }
+        },
 
-            if matches.is_empty() {
+        Command::Migrate => match db.migrate_add_change_ids() {
+            Ok(true) => {
                 println!(
-                    "\n{} No matches found above {}% threshold",
-                    "Result:".cyan(),
-                    min_score
+                    "{} Database migrated - added change_id columns for multi-user sync",
+                    "Success:".green()
                 );
-                return;
             }
-
-            // Sort by score descending
-            matches.sort_by(|a, b| {
-                b.score
-                    .partial_cmp(&a.score)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            });
-
-            println!(
-                "\n{} Found {} potential matches (>= {}%):",
-                "Matches:".green(),
-                matches.len(),
-                min_score
-            );
-            println!("{}", "=".repeat(80));
-
-            for m in &matches {
-                println!(
-                    "\nNode #{} ({}%): {}",
-                    m.node_id,
-                    (m.score * 100.0) as u8,
-                    truncate(&m.node_title, 55)
-                );
+            Ok(false) => {
                 println!(
-                    "  -> {}: {}",
-                    &m.commit_hash[..7],
-                    truncate(&m.commit_message, 55)
+                    "{} Database already has change_id columns - no migration needed",
+                    "Info:".cyan()
                 );
             }
-
-            if dry_run {
-                println!("\n{} Dry run - no changes made", "Info:".cyan());
-                return;
+            Err(e) => {
+                eprintln!("{} Migration failed: {}", "Error:".red(), e);
+                std::process::exit(1);
             }
+        },
 
-            // Confirm unless --yes
-            if !yes {
-                println!("\n{}", "=".repeat(80));
-                print!("Apply {} associations? [y/N]: ", matches.len());
-                use std::io::Write;
-                std::io::stdout().flush().ok();
+        Command::Diff { action } => {
+            match action {
+                DiffAction::Export {
+                    output,
+                    nodes,
+                    branch,
+                    author,
+                    base_commit,
+                    sign,
+                    new,
+                    encrypt_for,
+                } => {
+                    // Parse node IDs if provided
+                    let node_ids = nodes.as_ref().map(|n| parse_node_range(n));
+                    let config = Config::load();
+                    let author = author.or_else(|| config.author());
 
-                let mut input = String::new();
-                if std::io::stdin().read_line(&mut input).is_err()
-                    || input.trim().to_lowercase() != "y"
-                {
-                    println!("{}", "Aborted".yellow());
-                    return;
-                }
-            }
-
-            // Apply matches
-            let mut applied = 0;
-            let mut failed = 0;
-
-            for m in &matches {
-                match db.update_node_commit(m.node_id, &m.commit_hash) {
-                    Ok(()) => {
-                        applied += 1;
-                        println!(
-                            "{} Node #{} <- {}",
-                            "Linked:".green(),
-                            m.node_id,
-                            &m.commit_hash[..7]
-                        );
-                    }
-                    Err(e) => {
-                        failed += 1;
-                        eprintln!("{} Node #{}: {}", "Failed:".red(), m.node_id, e);
+                    let exported = if new {
+                        db.export_new_patch(branch.as_deref(), author, base_commit)
+                    } else {
+                        db.export_patch(node_ids, branch.as_deref(), author, base_commit)
+                    };
+                    match exported {
+                        Ok(mut patch) => {
+                            if new && patch.is_empty() {
+                                println!(
+                                    "{} Nothing new to export since the last export",
+                                    "Info:".cyan()
+                                );
+                                return;
+                            }
+                            if sign {
+                                let signed = deciduous::signing::key_path(&config)
+                                    .and_then(|path| {
+                                        deciduous::signing::SigningKey::load_or_create(&path)
+                                    })
+                                    .and_then(|key| key.sign(&mut patch));
+                                if let Err(e) = signed {
+                                    eprintln!("{} {}", "Error:".red(), e);
+                                    std::process::exit(1);
+                                }
+                            }
+                            match patch.save_encrypted(&output, &encrypt_for) {
+                                Ok(()) => {
+                                    record_exports(&db, &patch, &output, branch.as_deref());
+                                    println!(
+                                        "{} Exported {} nodes and {} edges to {}",
+                                        "Success:".green(),
+                                        patch.nodes.len(),
+                                        patch.edges.len(),
+                                        output.display()
+                                    );
+                                    if !patch.updated_nodes.is_empty()
+                                        || !patch.tombstones.is_empty()
+                                    {
+                                        println!(
+                                            "  Including {} updates and {} deletions",
+                                            patch.updated_nodes.len(),
+                                            patch.tombstones.len()
+                                        );
+                                    }
+                                    if let Some(sig) = &patch.signature {
+                                        println!("  Signed with key {}", sig.public_key);
+                                    }
+                                    if !encrypt_for.is_empty() {
+                                        println!(
+                                            "  Encrypted for {} age recipients",
+                                            encrypt_for.len()
+                                        );
+                                    }
+                                }
+                                Err(e) => {
+                                    eprintln!("{} {}", "Error:".red(), e);
+                                    std::process::exit(1);
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
                     }
                 }
-            }
-
-            println!(
-                "\n{} {} linked, {} failed",
-                "Done:".green(),
-                applied,
-                failed
-            );
-        }
 
-        Command::Roadmap { action } => {
-            match action {
-                RoadmapAction::Init { path } => {
-                    let roadmap_path = path.unwrap_or_else(|| PathBuf::from("ROADMAP.md"));
+                DiffAction::Apply {
+                    files,
+                    sha256,
+                    dry_run,
+                    strategy,
+                } => {
+                    let strategy = parse_conflict_strategy(strategy.as_deref());
+                    let sync_config = Config::load().sync_with_team();
+                    let mut results = Vec::new();
 
-                    if !roadmap_path.exists() {
-                        eprintln!(
-                            "{} File not found: {}",
-                            "Error:".red(),
-                            roadmap_path.display()
-                        );
-                        std::process::exit(1);
+                    for file in files {
+                        let arg = file.to_string_lossy();
+                        if deciduous::remote_patch::is_url(&arg) {
+                            let fetched = match deciduous::remote_patch::fetch_patches(
+                                &arg,
+                                sha256.as_deref(),
+                            ) {
+                                Ok(f) => f,
+                                Err(e) => {
+                                    eprintln!("{} {}", "Error:".red(), e);
+                                    continue;
+                                }
+                            };
+                            for f in fetched {
+                                let checked = sha256.is_some()
+                                    || deciduous::remote_patch::split_checksum(&arg).1.is_some();
+                                if !checked {
+                                    eprintln!(
+                                        "{} {} wasn't checked against a checksum (sha256 {})",
+                                        "Warning:".yellow(),
+                                        f.source,
+                                        f.sha256
+                                    );
+                                }
+                                if let Some(result) = apply_verified_patch(
+                                    &db,
+                                    &f.source,
+                                    &f.patch,
+                                    dry_run,
+                                    strategy,
+                                    &sync_config,
+                                ) {
+                                    if !dry_run {
+                                        record_applied(&db, &f.source, &f.sha256, None, &f.patch);
+                                    }
+                                    results.push(result);
+                                }
+                            }
+                            continue;
+                        }
+                        let loaded = std::fs::read(&file)
+                            .map_err(|e| format!("Failed to read patch file: {}", e))
+                            .and_then(|bytes| {
+                                let patch = deciduous::GraphPatch::from_bytes(&bytes)?;
+                                Ok((patch, deciduous::remote_patch::sha256_hex(&bytes)))
+                            });
+                        match loaded {
+                            Ok((patch, sha256)) => {
+                                let label = file.display().to_string();
+                                if let Some(result) = apply_verified_patch(
+                                    &db,
+                                    &label,
+                                    &patch,
+                                    dry_run,
+                                    strategy,
+                                    &sync_config,
+                                ) {
+                                    if !dry_run {
+                                        let path = deciduous::patch_history::ledger_path(&file);
+                                        record_applied(&db, &path, &sha256, None, &patch);
+                                    }
+                                    results.push(result);
+                                }
+                            }
+                            Err(e) => {
+                                eprintln!("{} Loading {}: {}", "Error:".red(), file.display(), e);
+                            }
+                        }
                     }
+                    print_apply_totals(&results, dry_run);
+                }
 
-                    // Parse the roadmap
-                    let parsed = match parse_roadmap(&roadmap_path) {
-                        Ok(p) => p,
+                DiffAction::Scan {
+                    path,
+                    dry_run,
+                    yes,
+                    strategy,
+                } => {
+                    let strategy = parse_conflict_strategy(strategy.as_deref());
+                    let dir = path.unwrap_or_else(|| PathBuf::from(".deciduous/patches"));
+                    let applied: std::collections::HashSet<String> = db
+                        .get_applied_patches()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|p| p.content_hash)
+                        .collect();
+                    let (found, errors) = match deciduous::patch_history::unapplied_patches(
+                        std::path::Path::new("."),
+                        &dir.to_string_lossy(),
+                        &applied,
+                    ) {
+                        Ok(found) => found,
                         Err(e) => {
-                            eprintln!("{} Parsing roadmap: {}", "Error:".red(), e);
+                            eprintln!("{} {}", "Error:".red(), e);
                             std::process::exit(1);
                         }
                     };
+                    for e in &errors {
+                        eprintln!("{} Skipping {}", "Warning:".yellow(), e);
+                    }
+                    if found.is_empty() {
+                        println!(
+                            "{} No unapplied patches in git history under {}",
+                            "Success:".green(),
+                            dir.display()
+                        );
+                        return;
+                    }
 
                     println!(
-                        "{} Found {} sections in {}",
-                        "Parsed:".green(),
-                        parsed.sections.len(),
-                        roadmap_path.display()
+                        "{} {} unapplied patches in git history (oldest first):",
+                        "Found:".cyan(),
+                        found.len()
                     );
+                    let config = Config::load();
+                    for p in &found {
+                        println!(
+                            "  {} - {} nodes, {} edges (author: {})",
+                            p.label(),
+                            p.patch.nodes.len(),
+                            p.patch.edges.len(),
+                            config.display_name(p.patch.author.as_deref().unwrap_or("unknown"))
+                        );
+                    }
 
-                    // Read original content for rewriting
-                    let content = match std::fs::read_to_string(&roadmap_path) {
-                        Ok(c) => c,
-                        Err(e) => {
-                            eprintln!("{} Reading file: {}", "Error:".red(), e);
-                            std::process::exit(1);
+                    if !dry_run && !yes {
+                        print!("\nApply {} patches? [y/N]: ", found.len());
+                        use std::io::Write;
+                        std::io::stdout().flush().ok();
+                        let mut input = String::new();
+                        if std::io::stdin().read_line(&mut input).is_err()
+                            || input.trim().to_lowercase() != "y"
+                        {
+                            println!("{}", "Aborted".yellow());
+                            return;
                         }
-                    };
+                    }
 
-                    // Write back with metadata
-                    let updated = match write_roadmap_with_metadata(
-                        &roadmap_path,
-                        &parsed.sections,
-                        &content,
-                    ) {
-                        Ok(u) => u,
-                        Err(e) => {
-                            eprintln!("{} Writing metadata: {}", "Error:".red(), e);
-                            std::process::exit(1);
+                    let sync_config = Config::load().sync_with_team();
+                    let mut results = Vec::new();
+                    for p in &found {
+                        let Some(result) = apply_verified_patch(
+                            &db,
+                            &p.label(),
+                            &p.patch,
+                            dry_run,
+                            strategy,
+                            &sync_config,
+                        ) else {
+                            continue;
+                        };
+                        if !dry_run {
+                            record_applied(&db, &p.path, &p.sha256, Some(&p.commit), &p.patch);
                         }
-                    };
-                    if let Err(e) = std::fs::write(&roadmap_path, &updated) {
-                        eprintln!("{} Writing file: {}", "Error:".red(), e);
-                        std::process::exit(1);
+                        results.push(result);
                     }
+                    print_apply_totals(&results, dry_run);
+                }
 
-                    // Track current level-2 parent section for grouping
-                    let mut current_l2_parent: Option<String> = None;
-
-                    // Store sections in database
-                    for section in &parsed.sections {
-                        // Level 2 headers (## Section) are top-level groupings
-                        // Level 3 headers (### Subsection) contain the actual tasks
-                        let (section_parent, items_section) = if section.level == 2 {
-                            current_l2_parent = Some(section.title.clone());
-                            // Level 2 sections have no parent, their items go under them
-                            (None, Some(section.title.as_str()))
-                        } else {
-                            // Level 3 sections belong to the current L2 parent
-                            // Their items belong directly to this L3 section
-                            (current_l2_parent.as_deref(), Some(section.title.as_str()))
-                        };
+                DiffAction::Conflicts { strategy, id, all } => {
+                    let strategy = strategy.map(|s| parse_conflict_strategy(Some(&s)));
+                    let conflicts = match id {
+                        Some(id) => match db.get_node_conflict(id) {
+                            Ok(Some(c)) => vec![c],
+                            Ok(None) => {
+                                eprintln!("{} Conflict {} not found", "Error:".red(), id);
+                                std::process::exit(1);
+                            }
+                            Err(e) => {
+                                eprintln!("{} {}", "Error:".red(), e);
+                                std::process::exit(1);
+                            }
+                        },
+                        None => match db.get_node_conflicts(all) {
+                            Ok(c) => c,
+                            Err(e) => {
+                                eprintln!("{} {}", "Error:".red(), e);
+                                std::process::exit(1);
+                            }
+                        },
+                    };
 
-                        // Create the section header entry (checkbox_state = "none")
-                        if let Err(e) = db.create_roadmap_item(
-                            &section.title,
-                            section.description.as_deref(),
-                            section_parent,
-                            None, // parent_id - we don't track hierarchy by ID yet
-                            "none",
-                        ) {
-                            eprintln!("{} Creating roadmap item: {}", "Warning:".yellow(), e);
+                    if let Some(strategy) = strategy {
+                        match db.resolve_conflicts(&conflicts, strategy) {
+                            Ok(n) => println!("{} {} conflicts", "Resolved".green(), n),
+                            Err(e) => {
+                                eprintln!("{} {}", "Error:".red(), e);
+                                std::process::exit(1);
+                            }
                         }
+                        return;
+                    }
 
-                        // Create items for checkboxes - they belong to THIS section
-                        for item in &section.items {
-                            let state = if item.checked { "checked" } else { "unchecked" };
-                            if let Err(e) = db.create_roadmap_item(
-                                &item.text,
-                                None,
-                                items_section, // Items belong to the section that contains them
-                                None,          // parent_id
-                                state,
-                            ) {
-                                eprintln!("{} Creating roadmap item: {}", "Warning:".yellow(), e);
+                    if conflicts.is_empty() {
+                        println!("{} No patch conflicts", "Success:".green());
+                        return;
+                    }
+
+                    let titles: std::collections::HashMap<String, (i32, String)> = db
+                        .get_all_nodes()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|n| (n.change_id, (n.id, n.title)))
+                        .collect();
+                    let show = |value: Option<&str>| truncate(value.unwrap_or("(none)"), 70);
+                    let config = Config::load();
+                    for conflict in &conflicts {
+                        let node = match titles.get(&conflict.change_id) {
+                            Some((id, title)) => format!("#{} {}", id, title),
+                            None => {
+                                conflict.change_id[..8.min(conflict.change_id.len())].to_string()
                             }
+                        };
+                        println!(
+                            "{} {} {}",
+                            format!("[{}]", conflict.id).cyan(),
+                            node,
+                            format!("({})", conflict.field).dimmed()
+                        );
+                        println!("    Ours:   {}", show(conflict.local_value.as_deref()));
+                        println!(
+                            "    Theirs: {} ({}, {})",
+                            show(conflict.remote_value.as_deref()),
+                            config.display_name(
+                                conflict.patch_author.as_deref().unwrap_or("unknown")
+                            ),
+                            conflict.remote_updated_at
+                        );
+                        if let Some(ref res) = conflict.resolution {
+                            println!("    Resolution: {}", res);
                         }
                     }
-
-                    // Count items
-                    let total_items: usize = parsed.sections.iter().map(|s| s.items.len()).sum();
-                    println!(
-                        "{} Initialized {} sections with {} items",
-                        "Success:".green(),
-                        parsed.sections.len(),
-                        total_items
-                    );
-                    println!("  Metadata comments added to {}", roadmap_path.display());
+                    let open = conflicts.iter().filter(|c| c.resolution.is_none()).count();
+                    if open > 0 {
+                        println!(
+                            "\nResolve with: deciduous diff conflicts --strategy ours|theirs|newest [--id N]"
+                        );
+                    }
                 }
 
-                RoadmapAction::Refresh { path } => {
-                    let roadmap_path = path.unwrap_or_else(|| PathBuf::from("ROADMAP.md"));
-
-                    if !roadmap_path.exists() {
-                        eprintln!(
-                            "{} File not found: {}",
-                            "Error:".red(),
-                            roadmap_path.display()
+                DiffAction::Status { path } => {
+                    let patches_dir = path.unwrap_or_else(|| PathBuf::from(".deciduous/patches"));
+                    if !patches_dir.exists() {
+                        println!(
+                            "{} No patches directory found at {}",
+                            "Info:".cyan(),
+                            patches_dir.display()
                         );
-                        std::process::exit(1);
+                        println!("Create one with: mkdir -p {}", patches_dir.display());
+                        return;
                     }
 
-                    // Clear existing roadmap items
-                    let cleared = match db.clear_roadmap_items() {
-                        Ok(n) => n,
+                    // List all .json files in the directory
+                    let mut files: Vec<PathBuf> = match std::fs::read_dir(&patches_dir) {
+                        Ok(entries) => entries
+                            .flatten()
+                            .map(|e| e.path())
+                            .filter(|p| p.extension().map(|e| e == "json").unwrap_or(false))
+                            .collect(),
                         Err(e) => {
-                            eprintln!("{} Clearing roadmap items: {}", "Error:".red(), e);
-                            std::process::exit(1);
+                            eprintln!("{} Reading directory: {}", "Error:".red(), e);
+                            return;
                         }
                     };
-                    println!(
-                        "{} Cleared {} existing roadmap items",
-                        "Info:".cyan(),
-                        cleared
-                    );
-
-                    // Re-parse the roadmap
-                    let parsed = match parse_roadmap(&roadmap_path) {
-                        Ok(p) => p,
-                        Err(e) => {
-                            eprintln!("{} Parsing roadmap: {}", "Error:".red(), e);
-                            std::process::exit(1);
-                        }
-                    };
-
-                    // Track current level-2 parent section for grouping
-                    let mut current_l2_parent: Option<String> = None;
-
-                    // Store sections in database
-                    for section in &parsed.sections {
-                        let (section_parent, items_section) = if section.level == 2 {
-                            current_l2_parent = Some(section.title.clone());
-                            (None, Some(section.title.as_str()))
-                        } else {
-                            (current_l2_parent.as_deref(), Some(section.title.as_str()))
-                        };
-
-                        // Create the section header entry
-                        if let Err(e) = db.create_roadmap_item(
-                            &section.title,
-                            section.description.as_deref(),
-                            section_parent,
-                            None,
-                            "none",
-                        ) {
-                            eprintln!("{} Creating roadmap item: {}", "Warning:".yellow(), e);
-                        }
+                    files.sort();
 
-                        // Create items for checkboxes
-                        for item in &section.items {
-                            let state = if item.checked { "checked" } else { "unchecked" };
-                            if let Err(e) =
-                                db.create_roadmap_item(&item.text, None, items_section, None, state)
-                            {
-                                eprintln!("{} Creating roadmap item: {}", "Warning:".yellow(), e);
+                    println!("{} {}", "Patches in:".cyan(), patches_dir.display());
+                    let applied = db.get_applied_patches().unwrap_or_default();
+                    let config = Config::load();
+                    let mut counts = std::collections::BTreeMap::new();
+                    let exported: std::collections::HashSet<String> = db
+                        .get_node_exports()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|e| e.change_id)
+                        .collect();
+                    let unexported = db
+                        .get_all_nodes()
+                        .unwrap_or_default()
+                        .iter()
+                        .filter(|n| !exported.contains(&n.change_id))
+                        .count();
+                    for path in files {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        let bytes = match std::fs::read(&path) {
+                            Ok(b) => b,
+                            Err(e) => {
+                                println!("  {} {} - {}", "[unreadable]".red(), name, e);
+                                continue;
                             }
-                        }
-                    }
-
-                    let total_items: usize = parsed.sections.iter().map(|s| s.items.len()).sum();
-                    println!(
-                        "{} Refreshed {} sections with {} items",
-                        "Success:".green(),
-                        parsed.sections.len(),
-                        total_items
-                    );
-                }
-
-                RoadmapAction::Sync {
-                    path,
-                    repo,
-                    execute,
-                    create_issues,
-                } => {
-                    let dry_run = !execute; // Default is dry-run mode
-                    let roadmap_path = path.unwrap_or_else(|| PathBuf::from("ROADMAP.md"));
-
-                    if !roadmap_path.exists() {
-                        eprintln!(
-                            "{} File not found: {}",
-                            "Error:".red(),
-                            roadmap_path.display()
+                        };
+                        let state = deciduous::patch_history::ledger_state(
+                            &applied,
+                            &deciduous::patch_history::ledger_path(&path),
+                            &deciduous::remote_patch::sha256_hex(&bytes),
                         );
-                        eprintln!("Run 'deciduous roadmap init' first");
-                        std::process::exit(1);
-                    }
-
-                    // Initialize GitHub client
-                    let gh_client = match repo {
-                        Some(r) => GitHubClient::new(Some(r)),
-                        None => match GitHubClient::auto_detect() {
-                            Ok(c) => c,
+                        *counts.entry(state.as_str()).or_insert(0) += 1;
+                        let tag = match state {
+                            deciduous::patch_history::LedgerState::Applied => "[applied]".green(),
+                            deciduous::patch_history::LedgerState::Modified => {
+                                "[modified]".yellow()
+                            }
+                            deciduous::patch_history::LedgerState::Unapplied => {
+                                "[unapplied]".cyan()
+                            }
+                        };
+                        let patch = match deciduous::GraphPatch::from_bytes(&bytes) {
+                            Ok(p) => p,
                             Err(e) => {
-                                eprintln!("{} Auto-detecting repo: {}", "Error:".red(), e);
-                                eprintln!("Specify repo with --repo owner/repo");
-                                std::process::exit(1);
+                                println!("  {} {} - {}", tag, name, e);
+                                continue;
                             }
-                        },
-                    };
-
-                    // Check auth
-                    match GitHubClient::check_auth() {
-                        Ok(true) => {}
-                        Ok(false) | Err(_) => {
-                            eprintln!("{} Not authenticated with GitHub", "Error:".red());
-                            eprintln!("Run 'gh auth login' first");
-                            std::process::exit(1);
-                        }
+                        };
+                        let author =
+                            config.display_name(patch.author.as_deref().unwrap_or("unknown"));
+                        let branch = patch.branch.as_deref().unwrap_or("unknown");
+                        let changes =
+                            if patch.updated_nodes.is_empty() && patch.tombstones.is_empty() {
+                                String::new()
+                            } else {
+                                format!(
+                                    ", {} updates, {} deletions",
+                                    patch.updated_nodes.len(),
+                                    patch.tombstones.len()
+                                )
+                            };
+                        println!(
+                            "  {} {} - {} nodes, {} edges{} (author: {}, branch: {})",
+                            tag,
+                            name,
+                            patch.nodes.len(),
+                            patch.edges.len(),
+                            changes,
+                            author,
+                            branch
+                        );
                     }
-
-                    // Parse roadmap
-                    let parsed = match parse_roadmap(&roadmap_path) {
-                        Ok(p) => p,
-                        Err(e) => {
-                            eprintln!("{} Parsing roadmap: {}", "Error:".red(), e);
-                            std::process::exit(1);
-                        }
-                    };
-
-                    // Only sync level 3 sections (actual items, not parent headers)
-                    let syncable_sections: Vec<&RoadmapSection> =
-                        parsed.sections.iter().filter(|s| s.level == 3).collect();
-
-                    if dry_run {
+                    if !counts.is_empty() {
+                        let summary: Vec<String> = counts
+                            .iter()
+                            .map(|(state, n)| format!("{} {}", n, state))
+                            .collect();
+                        println!("{} {}", "Summary:".cyan(), summary.join(", "));
+                    }
+                    let pending = counts.get("unapplied").unwrap_or(&0)
+                        + counts.get("modified").unwrap_or(&0);
+                    if pending > 0 {
                         println!(
-                            "{} {} sections (use --execute to apply changes)",
-                            "Roadmap (dry run):".yellow(),
-                            syncable_sections.len()
+                            "Apply with: deciduous diff apply {}/*.json",
+                            patches_dir.display()
                         );
-                    } else {
+                    }
+                    if unexported > 0 {
                         println!(
-                            "{} Syncing {} sections",
-                            "Roadmap:".cyan(),
-                            syncable_sections.len()
+                            "{} {} nodes not exported yet (deciduous diff export --new -o <file>)",
+                            "Info:".cyan(),
+                            unexported
                         );
                     }
+                }
 
-                    if let Some(repo_name) = gh_client.repo_name() {
-                        println!("  Repository: {}", repo_name);
-                    }
+                DiffAction::Validate { files } => {
+                    use std::collections::HashSet;
 
-                    // Ensure 'roadmap' label exists if we're creating issues
-                    if !dry_run && create_issues {
-                        match ensure_roadmap_label(&gh_client) {
-                            Ok(true) => println!("  {} Created 'roadmap' label", "✓".green()),
-                            Ok(false) => {} // Label already exists
-                            Err(e) => eprintln!(
-                                "  {} Creating label: {} (issues may fail)",
-                                "Warning:".yellow(),
-                                e
-                            ),
-                        }
-                    }
+                    let sync_config = Config::load().sync_with_team();
+                    let mut any_errors = false;
 
-                    let mut created = 0;
-                    let mut updated = 0;
-                    let mut skipped = 0;
+                    for file in &files {
+                        match deciduous::GraphPatch::load(file) {
+                            Ok(patch) => {
+                                // Collect all node change_ids in the patch
+                                let node_ids: HashSet<&str> =
+                                    patch.nodes.iter().map(|n| n.change_id.as_str()).collect();
 
-                    for section in &syncable_sections {
-                        // Check if section already has an issue
-                        if section.github_issue_number.is_some() {
-                            // Update existing issue
-                            let issue_num = section.github_issue_number.unwrap();
-                            let body = generate_issue_body(section);
+                                // Check each edge for missing nodes
+                                let mut missing_edges = Vec::new();
+                                for edge in &patch.edges {
+                                    let from_missing =
+                                        !node_ids.contains(edge.from_change_id.as_str());
+                                    let to_missing = !node_ids.contains(edge.to_change_id.as_str());
 
-                            if dry_run {
-                                println!(
-                                    "  {} Would update issue #{}: {}",
-                                    "[DRY]".yellow(),
-                                    issue_num,
-                                    section.title
-                                );
-                                updated += 1;
-                            } else {
-                                match gh_client.update_issue_body(issue_num, &body) {
-                                    Ok(()) => {
-                                        println!(
-                                            "  {} Updated issue #{}: {}",
-                                            "✓".green(),
-                                            issue_num,
-                                            section.title
-                                        );
-                                        updated += 1;
-                                    }
-                                    Err(e) => {
-                                        eprintln!(
-                                            "  {} Updating issue #{}: {}",
-                                            "✗".red(),
-                                            issue_num,
-                                            e
-                                        );
+                                    if from_missing || to_missing {
+                                        let mut missing = Vec::new();
+                                        if from_missing {
+                                            missing.push(format!(
+                                                "from: {}",
+                                                &edge.from_change_id
+                                                    [..8.min(edge.from_change_id.len())]
+                                            ));
+                                        }
+                                        if to_missing {
+                                            missing.push(format!(
+                                                "to: {}",
+                                                &edge.to_change_id
+                                                    [..8.min(edge.to_change_id.len())]
+                                            ));
+                                        }
+                                        missing_edges
+                                            .push((edge.edge_type.clone(), missing.join(", ")));
                                     }
                                 }
-                            }
-                        } else if create_issues {
-                            // Create new issue
-                            let body = generate_issue_body(section);
 
-                            if dry_run {
+                                println!("{} {}", "Validating:".cyan(), file.display());
+                                let provenance = deciduous::signing::verify(&patch, &sync_config);
+                                if provenance.acceptable(&sync_config) {
+                                    println!("  Signature: {}", provenance.describe());
+                                } else {
+                                    any_errors = true;
+                                    println!("  {} {}", "Signature:".red(), provenance.describe());
+                                }
+                                println!("  Nodes: {}", patch.nodes.len());
                                 println!(
-                                    "  {} Would create issue: {}",
-                                    "[DRY]".yellow(),
-                                    section.title
+                                    "  Edges: {} ({} valid, {} with missing refs)",
+                                    patch.edges.len(),
+                                    patch.edges.len() - missing_edges.len(),
+                                    missing_edges.len()
                                 );
-                                created += 1;
-                            } else {
-                                match gh_client.create_issue(&section.title, &body, &["roadmap"]) {
-                                    Ok(issue) => {
-                                        println!(
-                                            "  {} Created issue #{}: {}",
-                                            "✓".green(),
-                                            issue.number,
-                                            section.title
-                                        );
-                                        created += 1;
-
-                                        // Update database with issue number
-                                        if let Err(e) = db.update_roadmap_item_github_by_title(
-                                            &section.title,
-                                            issue.number,
-                                            &issue.state,
-                                        ) {
-                                            eprintln!(
-                                                "    {} Updating database: {}",
-                                                "Warning:".yellow(),
-                                                e
-                                            );
-                                        }
 
-                                        // Cache issue for TUI/Web display
-                                        if let Some(repo_name) = gh_client.repo_name() {
-                                            if let Err(e) = db.cache_github_issue(
-                                                issue.number,
-                                                repo_name,
-                                                &issue.title,
-                                                Some(&issue.body),
-                                                &issue.state,
-                                                &issue.html_url,
-                                                &issue.created_at,
-                                                &issue.updated_at,
-                                            ) {
-                                                eprintln!(
-                                                    "    {} Caching issue: {}",
-                                                    "Warning:".yellow(),
-                                                    e
-                                                );
-                                            }
-                                        }
-                                    }
-                                    Err(e) => {
-                                        eprintln!(
-                                            "  {} Creating issue for '{}': {}",
-                                            "✗".red(),
-                                            section.title,
-                                            e
-                                        );
+                                if !missing_edges.is_empty() {
+                                    any_errors = true;
+                                    println!(
+                                        "  {} Edges referencing missing nodes:",
+                                        "Warning:".yellow()
+                                    );
+                                    for (edge_type, missing) in &missing_edges {
+                                        println!("    - {} edge: missing {}", edge_type, missing);
                                     }
+                                    println!();
+                                    println!("  {} This patch has edges that reference nodes not in the patch.", "Note:".cyan());
+                                    println!("  When applied, these edges will fail unless the referenced nodes");
+                                    println!("  already exist in the target database or are imported first.");
+                                    println!();
+                                    println!("  {} Re-export with all dependent nodes, or apply patches in order:", "Fix:".green());
+                                    println!(
+                                        "    1. Apply the patch containing the parent nodes first"
+                                    );
+                                    println!("    2. Then apply this patch");
+                                } else {
+                                    println!(
+                                        "  {} All edges reference nodes within the patch",
+                                        "OK:".green()
+                                    );
                                 }
                             }
-                        } else {
-                            println!("  {} Skipping (no issue): {}", "-".dimmed(), section.title);
-                            skipped += 1;
-                        }
-                    }
-
-                    // Write updated roadmap with issue metadata
-                    if !dry_run && created > 0 {
-                        let content = std::fs::read_to_string(&roadmap_path).unwrap_or_default();
-                        match write_roadmap_with_metadata(&roadmap_path, &parsed.sections, &content)
-                        {
-                            Ok(updated_content) => {
-                                if let Err(e) = std::fs::write(&roadmap_path, &updated_content) {
-                                    eprintln!("{} Writing roadmap: {}", "Warning:".yellow(), e);
-                                }
+                            Err(e) => {
+                                any_errors = true;
+                                eprintln!("{} {}: {}", "Error:".red(), file.display(), e);
                             }
-                            Err(e) => eprintln!("{} Updating metadata: {}", "Warning:".yellow(), e),
                         }
+                        println!();
                     }
 
-                    println!(
-                        "\n{} {} created, {} updated, {} skipped",
-                        if dry_run {
-                            "Summary (dry run):".yellow()
-                        } else {
-                            "Summary:".green()
-                        },
-                        created,
-                        updated,
-                        skipped
-                    );
-                }
-
-                RoadmapAction::List {
-                    path,
-                    section,
-                    with_issues,
-                    without_issues,
-                } => {
-                    let roadmap_path = path.unwrap_or_else(|| PathBuf::from("ROADMAP.md"));
-
-                    if !roadmap_path.exists() {
-                        eprintln!(
-                            "{} File not found: {}",
-                            "Error:".red(),
-                            roadmap_path.display()
-                        );
+                    if any_errors {
                         std::process::exit(1);
                     }
+                }
 
-                    let parsed = match parse_roadmap(&roadmap_path) {
-                        Ok(p) => p,
+                DiffAction::Key => {
+                    let key = deciduous::signing::key_path(&Config::load()).and_then(|path| {
+                        deciduous::signing::SigningKey::load_or_create(&path).map(|k| (path, k))
+                    });
+                    match key {
+                        Ok((path, key)) => {
+                            println!("{}", key.public_key_hex());
+                            eprintln!(
+                                "{} Private key: {}. Teammates trust your patches by adding the line above under [sync.trusted_keys] in .deciduous/config.toml",
+                                "Info:".cyan(),
+                                path.display()
+                            );
+                        }
                         Err(e) => {
-                            eprintln!("{} Parsing roadmap: {}", "Error:".red(), e);
+                            eprintln!("{} {}", "Error:".red(), e);
                             std::process::exit(1);
                         }
-                    };
+                    }
+                }
+            }
+        }
 
-                    // Filter sections
-                    let filtered: Vec<_> = parsed
-                        .sections
-                        .iter()
-                        .filter(|s| {
-                            if let Some(ref sect) = section {
-                                s.title.to_lowercase().contains(&sect.to_lowercase())
-                            } else {
-                                true
-                            }
-                        })
-                        .filter(|s| {
-                            if with_issues {
-                                s.github_issue_number.is_some()
-                            } else if without_issues {
-                                s.github_issue_number.is_none()
-                            } else {
-                                true
-                            }
-                        })
-                        .collect();
+        Command::Tui { .. } => unreachable!(), // Handled above
+        Command::Completion { .. } => unreachable!(), // Handled above
 
-                    if filtered.is_empty() {
-                        println!("No roadmap items found matching filters.");
-                        return;
-                    }
+        Command::Audit {
+            associate_commits,
+            min_score,
+            dry_run,
+            yes,
+            dedupe,
+            min_similarity,
+            window,
+            stale,
+            from_trailers,
+        } => {
+            if stale {
+                report_stale(&db, None, None, false, false);
+                return;
+            }
+            if from_trailers {
+                audit_from_trailers(&db, dry_run);
+                return;
+            }
+            if dedupe {
+                audit_dedupe(&db, min_similarity, window, dry_run, yes);
+                return;
+            }
+            if !associate_commits {
+                eprintln!(
+                    "{} No audit action specified. Use --associate-commits, --from-trailers, --dedupe or --stale",
+                    "Error:".red()
+                );
+                std::process::exit(1);
+            }
 
-                    println!("{} ({} sections)\n", "ROADMAP.md".cyan(), filtered.len());
+            // Get all nodes
+            let nodes = match db.get_all_nodes() {
+                Ok(n) => n,
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            };
 
-                    for s in &filtered {
-                        // Show section header based on level
-                        let header_prefix = if s.level == 2 { "##" } else { "###" };
+            // Get git commits since Nov 2024
+            let commits = get_git_commits_for_audit();
+            if commits.is_empty() {
+                eprintln!("{} No git commits found", "Error:".red());
+                std::process::exit(1);
+            }
 
-                        let issue_str = match s.github_issue_number {
-                            Some(n) => format!("#{}", n).green().to_string(),
-                            None => "no issue".dimmed().to_string(),
-                        };
+            println!(
+                "{} {} nodes, {} commits",
+                "Analyzing:".cyan(),
+                nodes.len(),
+                commits.len()
+            );
 
-                        let completed: usize = s.items.iter().filter(|i| i.checked).count();
-                        let total = s.items.len();
+            // Find action/outcome nodes without commits
+            let nodes_to_check: Vec<_> = nodes
+                .iter()
+                .filter(|n| n.node_type == "action" || n.node_type == "outcome")
+                .filter(|n| {
+                    // Check if already has commit
+                    !n.metadata_json
+                        .as_ref()
+                        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+                        .and_then(|v| {
+                            v.get("commit")
+                                .and_then(|c| c.as_str())
+                                .map(|s| !s.is_empty())
+                        })
+                        .unwrap_or(false)
+                })
+                .collect();
 
-                        if total > 0 {
-                            println!(
-                                "{} {} [{}/{}] ({})",
-                                header_prefix.yellow(),
-                                s.title,
-                                completed,
-                                total,
-                                issue_str
-                            );
-                        } else {
-                            println!("{} {} ({})", header_prefix.yellow(), s.title, issue_str);
-                        }
+            let with_commits = nodes
+                .iter()
+                .filter(|n| n.node_type == "action" || n.node_type == "outcome")
+                .filter(|n| {
+                    n.metadata_json
+                        .as_ref()
+                        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+                        .and_then(|v| {
+                            v.get("commit")
+                                .and_then(|c| c.as_str())
+                                .map(|s| !s.is_empty())
+                        })
+                        .unwrap_or(false)
+                })
+                .count();
 
-                        // Show checkbox items
-                        for item in &s.items {
-                            let check = if item.checked {
-                                "✓".green()
-                            } else {
-                                "○".dimmed()
-                            };
-                            println!("    {} {}", check, item.text);
-                        }
-                    }
-                }
+            println!(
+                "  Action/outcome nodes: {} with commits, {} without",
+                with_commits,
+                nodes_to_check.len()
+            );
 
-                RoadmapAction::Link { item, outcome_id } => {
-                    // Find roadmap item by title or change_id
-                    let items = match db.get_all_roadmap_items() {
-                        Ok(i) => i,
-                        Err(e) => {
-                            eprintln!("{} {}", "Error:".red(), e);
-                            std::process::exit(1);
-                        }
-                    };
+            // Find matches
+            let mut matches: Vec<CommitMatch> = Vec::new();
+            let threshold = min_score as f64 / 100.0;
 
-                    let target = items.iter().find(|i| {
-                        i.change_id == item || i.title.to_lowercase().contains(&item.to_lowercase())
-                    });
+            for node in &nodes_to_check {
+                let mut best_match: Option<(&AuditCommit, f64)> = None;
 
-                    match target {
-                        Some(roadmap_item) => {
-                            // Verify outcome node exists and is an outcome
-                            match db.get_all_nodes() {
-                                Ok(nodes) => {
-                                    let node = nodes.iter().find(|n| n.id == outcome_id);
-                                    match node {
-                                        Some(n) if n.node_type == "outcome" => {
-                                            // Link them
-                                            match db.link_roadmap_to_outcome(
-                                                roadmap_item.id,
-                                                outcome_id,
-                                                &n.change_id,
-                                            ) {
-                                                Ok(()) => {
-                                                    println!(
-                                                        "{} Linked '{}' to outcome #{}: {}",
-                                                        "Success:".green(),
-                                                        roadmap_item.title,
-                                                        outcome_id,
-                                                        n.title
-                                                    );
-                                                }
-                                                Err(e) => {
-                                                    eprintln!("{} {}", "Error:".red(), e);
-                                                    std::process::exit(1);
-                                                }
-                                            }
-                                        }
-                                        Some(n) => {
-                                            eprintln!(
-                                                "{} Node #{} is a {}, not an outcome",
-                                                "Error:".red(),
-                                                outcome_id,
-                                                n.node_type
-                                            );
-                                            std::process::exit(1);
-                                        }
-                                        None => {
-                                            eprintln!(
-                                                "{} Node #{} not found",
-                                                "Error:".red(),
-                                                outcome_id
-                                            );
-                                            std::process::exit(1);
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!("{} {}", "Error:".red(), e);
-                                    std::process::exit(1);
-                                }
+                for commit in &commits {
+                    let score = keyword_match_score(&node.title, &commit.message);
+                    if score >= threshold && (best_match.is_none() || score > best_match.unwrap().1)
+                    {
+                        best_match = Some((commit, score));
+                    }
+                }
+
+                if let Some((commit, score)) = best_match {
+                    matches.push(CommitMatch {
+                        node_id: node.id,
+                        node_title: node.title.clone(),
+                        commit_hash: commit.hash.clone(),
+                        commit_message: commit.message.clone(),
+                        score,
+                    });
+                }
+            }
+
+            if matches.is_empty() {
+                println!(
+                    "\n{} No matches found above {}% threshold",
+                    "Result:".cyan(),
+                    min_score
+                );
+                return;
+            }
+
+            // Sort by score descending
+            matches.sort_by(|a, b| {
+                b.score
+                    .partial_cmp(&a.score)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+
+            println!(
+                "\n{} Found {} potential matches (>= {}%):",
+                "Matches:".green(),
+                matches.len(),
+                min_score
+            );
+            println!("{}", "=".repeat(80));
+
+            for m in &matches {
+                println!(
+                    "\nNode #{} ({}%): {}",
+                    m.node_id,
+                    (m.score * 100.0) as u8,
+                    truncate(&m.node_title, 55)
+                );
+                println!(
+                    "  -> {}: {}",
+                    &m.commit_hash[..7],
+                    truncate(&m.commit_message, 55)
+                );
+            }
+
+            if dry_run {
+                println!("\n{} Dry run - no changes made", "Info:".cyan());
+                return;
+            }
+
+            // Confirm unless --yes
+            if !yes {
+                println!("\n{}", "=".repeat(80));
+                print!("Apply {} associations? [y/N]: ", matches.len());
+                use std::io::Write;
+                std::io::stdout().flush().ok();
+
+                let mut input = String::new();
+                if std::io::stdin().read_line(&mut input).is_err()
+                    || input.trim().to_lowercase() != "y"
+                {
+                    println!("{}", "Aborted".yellow());
+                    return;
+                }
+            }
+
+            // Apply matches
+            let mut applied = 0;
+            let mut failed = 0;
+
+            for m in &matches {
+                match db.update_node_commit(m.node_id, &m.commit_hash) {
+                    Ok(()) => {
+                        applied += 1;
+                        println!(
+                            "{} Node #{} <- {}",
+                            "Linked:".green(),
+                            m.node_id,
+                            &m.commit_hash[..7]
+                        );
+                    }
+                    Err(e) => {
+                        failed += 1;
+                        eprintln!("{} Node #{}: {}", "Failed:".red(), m.node_id, e);
+                    }
+                }
+            }
+
+            println!(
+                "\n{} {} linked, {} failed",
+                "Done:".green(),
+                applied,
+                failed
+            );
+        }
+
+        Command::Push {
+            server,
+            branch,
+            sign,
+        } => {
+            let config = Config::load();
+            let server = match deciduous::hub::server_url(server, &config) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            };
+            let mut patch = match db.export_patch(
+                None,
+                branch.as_deref(),
+                config.author(),
+                deciduous::get_current_git_commit(),
+            ) {
+                Ok(p) => p,
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            };
+            if sign {
+                let signed = deciduous::signing::key_path(&config)
+                    .and_then(|path| deciduous::signing::SigningKey::load_or_create(&path))
+                    .and_then(|key| key.sign(&mut patch));
+                if let Err(e) = signed {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            }
+
+            let token = deciduous::hub::sync_token(&config);
+            match deciduous::hub::push_patch(&server, &patch, token.as_deref()) {
+                Ok(result) => {
+                    println!(
+                        "{} Pushed {} nodes and {} edges to {}",
+                        "Success:".green(),
+                        patch.nodes.len(),
+                        patch.edges.len(),
+                        server
+                    );
+                    println!(
+                        "  Hub: {} nodes added, {} already there; {} edges added; {} updated, {} deleted",
+                        result.nodes_added,
+                        result.nodes_skipped,
+                        result.edges_added,
+                        result.nodes_updated,
+                        result.nodes_deleted
+                    );
+                    if result.conflicts > 0 {
+                        println!(
+                            "  {} {} conflicting fields kept the hub's values",
+                            "Conflicts:".yellow(),
+                            result.conflicts
+                        );
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        Command::Pull {
+            server,
+            dry_run,
+            strategy,
+        } => {
+            let config = Config::load();
+            let strategy = parse_conflict_strategy(strategy.as_deref());
+            let server = match deciduous::hub::server_url(server, &config) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            };
+            let token = deciduous::hub::sync_token(&config);
+            let patch = match deciduous::hub::pull_patch(&server, token.as_deref()) {
+                Ok(p) => p,
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            };
+            // The hub checked signatures as patches arrived; its export is unsigned
+            let from_hub = deciduous::config::SyncConfig {
+                require_signatures: false,
+                ..config.sync_with_team()
+            };
+            let results: Vec<_> =
+                apply_verified_patch(&db, &server, &patch, dry_run, strategy, &from_hub)
+                    .into_iter()
+                    .collect();
+            if results.is_empty() {
+                std::process::exit(1);
+            }
+            print_apply_totals(&results, dry_run);
+        }
+
+        Command::SyncGit { action } => {
+            let repo = std::path::Path::new(".");
+            let config = Config::load();
+            let sync_config = config.sync_with_team();
+            match action {
+                SyncGitAction::Push { remote, sign } => {
+                    let user = config.author().unwrap_or_default();
+                    if let Err(e) = deciduous::git_sync::fetch(repo, &remote) {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+
+                    // Our ref moved on elsewhere (another clone): take its
+                    // changes first so the push includes them
+                    let merge = deciduous::git_sync::unmerged_remote(repo, &remote, &user);
+                    if let Some(theirs) = &merge {
+                        let patch = match deciduous::git_sync::read_patch(repo, theirs) {
+                            Ok(p) => p,
+                            Err(e) => {
+                                eprintln!("{} {}", "Error:".red(), e);
+                                std::process::exit(1);
                             }
-                        }
-                        None => {
-                            eprintln!("{} Roadmap item '{}' not found", "Error:".red(), item);
-                            eprintln!("Run 'deciduous roadmap list' to see available items");
+                        };
+                        let label = format!("{}/{}", remote, deciduous::git_sync::user_ref(&user));
+                        if apply_verified_patch(
+                            &db,
+                            &label,
+                            &patch,
+                            false,
+                            deciduous::ConflictStrategy::Record,
+                            &sync_config,
+                        )
+                        .is_none()
+                        {
                             std::process::exit(1);
                         }
                     }
-                }
 
-                RoadmapAction::Unlink { item } => {
-                    let items = match db.get_all_roadmap_items() {
-                        Ok(i) => i,
+                    let mut patch = match db.export_patch(
+                        None,
+                        None,
+                        Some(user.clone()),
+                        deciduous::get_current_git_commit(),
+                    ) {
+                        Ok(p) => p,
                         Err(e) => {
                             eprintln!("{} {}", "Error:".red(), e);
                             std::process::exit(1);
                         }
                     };
-
-                    let target = items.iter().find(|i| {
-                        i.change_id == item || i.title.to_lowercase().contains(&item.to_lowercase())
-                    });
-
-                    match target {
-                        Some(roadmap_item) => {
-                            match db.unlink_roadmap_from_outcome(roadmap_item.id) {
-                                Ok(()) => {
-                                    println!(
-                                        "{} Unlinked '{}' from outcome",
-                                        "Success:".green(),
-                                        roadmap_item.title
-                                    );
-                                }
-                                Err(e) => {
-                                    eprintln!("{} {}", "Error:".red(), e);
-                                    std::process::exit(1);
-                                }
-                            }
+                    if sign {
+                        let signed = deciduous::signing::key_path(&config)
+                            .and_then(|path| deciduous::signing::SigningKey::load_or_create(&path))
+                            .and_then(|key| key.sign(&mut patch));
+                        if let Err(e) = signed {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
                         }
-                        None => {
-                            eprintln!("{} Roadmap item '{}' not found", "Error:".red(), item);
+                    }
+
+                    let committed =
+                        deciduous::git_sync::commit_patch(repo, &user, &patch, merge.as_deref())
+                            .and_then(|c| {
+                                deciduous::git_sync::push(repo, &remote, &user).map(|()| c)
+                            });
+                    match committed {
+                        Ok(Some(_)) => println!(
+                            "{} Pushed {} nodes and {} edges to {} ({})",
+                            "Success:".green(),
+                            patch.nodes.len(),
+                            patch.edges.len(),
+                            remote,
+                            deciduous::git_sync::user_ref(&user)
+                        ),
+                        Ok(None) => println!(
+                            "{} {} is up to date on {}",
+                            "Info:".cyan(),
+                            deciduous::git_sync::user_ref(&user),
+                            remote
+                        ),
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
                             std::process::exit(1);
                         }
                     }
                 }
 
-                RoadmapAction::Conflicts { resolve } => {
-                    let conflicts = match db.get_unresolved_conflicts() {
-                        Ok(c) => c,
+                SyncGitAction::Pull {
+                    remote,
+                    dry_run,
+                    strategy,
+                } => {
+                    let strategy = parse_conflict_strategy(strategy.as_deref());
+                    let refs = match deciduous::git_sync::fetch(repo, &remote) {
+                        Ok(r) => r,
                         Err(e) => {
                             eprintln!("{} {}", "Error:".red(), e);
                             std::process::exit(1);
                         }
                     };
-
-                    if conflicts.is_empty() {
-                        println!("{} No sync conflicts", "Success:".green());
+                    if refs.is_empty() {
+                        println!("{} No graph refs on {}", "Info:".cyan(), remote);
                         return;
                     }
 
-                    println!(
-                        "{} {} conflicts found:\n",
-                        "Conflicts:".yellow(),
-                        conflicts.len()
-                    );
-
-                    for conflict in &conflicts {
-                        println!(
-                            "  Item: {} ({})",
-                            conflict.item_change_id, conflict.conflict_type
-                        );
-                        println!(
-                            "    Local:  {}",
-                            conflict.local_value.as_deref().unwrap_or("(none)")
-                        );
-                        println!(
-                            "    Remote: {}",
-                            conflict.remote_value.as_deref().unwrap_or("(none)")
-                        );
-                        if let Some(ref res) = conflict.resolution {
-                            println!("    Resolution: {}", res);
+                    let mut results = Vec::new();
+                    for (user, refname) in refs {
+                        let label = format!("{}/{}", remote, user);
+                        match deciduous::git_sync::read_patch(repo, &refname) {
+                            Ok(patch) => results.extend(apply_verified_patch(
+                                &db,
+                                &label,
+                                &patch,
+                                dry_run,
+                                strategy,
+                                &sync_config,
+                            )),
+                            Err(e) => eprintln!("{} Loading {}: {}", "Error:".red(), label, e),
                         }
-                        println!();
-                    }
-
-                    if resolve {
-                        println!(
-                            "{} Interactive conflict resolution not yet implemented",
-                            "TODO:".yellow()
-                        );
-                        println!(
-                            "For now, manually edit ROADMAP.md and run 'deciduous roadmap sync'"
-                        );
                     }
+                    print_apply_totals(&results, dry_run);
                 }
+            }
+        }
 
-                RoadmapAction::Status { path } => {
+        Command::Roadmap { action } => {
+            match action {
+                RoadmapAction::Init { path } => {
                     let roadmap_path = path.unwrap_or_else(|| PathBuf::from("ROADMAP.md"));
 
-                    // Get sync state from database
-                    match db.get_roadmap_sync_state(&roadmap_path.to_string_lossy()) {
-                        Ok(Some(state)) => {
-                            println!("{}", "Roadmap Sync Status".cyan());
-                            println!("  Path: {}", roadmap_path.display());
-                            if let Some(ref repo) = state.github_repo {
-                                println!("  GitHub Repo: {}", repo);
-                            }
-                            if let Some(ref last_sync) = state.last_github_sync {
-                                println!("  Last GitHub Sync: {}", last_sync);
-                            }
-                            if let Some(ref last_parse) = state.last_markdown_parse {
-                                println!("  Last Parse: {}", last_parse);
-                            }
-                            if state.conflict_count > 0 {
-                                println!("  {} {} conflicts", "⚠".yellow(), state.conflict_count);
-                            } else {
-                                println!("  {} No conflicts", "✓".green());
-                            }
-                        }
-                        Ok(None) => {
-                            println!("{} Roadmap not initialized", "Status:".yellow());
-                            println!("Run 'deciduous roadmap init' to get started");
-                        }
+                    if !roadmap_path.exists() {
+                        eprintln!(
+                            "{} File not found: {}",
+                            "Error:".red(),
+                            roadmap_path.display()
+                        );
+                        std::process::exit(1);
+                    }
+
+                    // Parse the roadmap
+                    let parsed = match parse_roadmap(&roadmap_path) {
+                        Ok(p) => p,
                         Err(e) => {
-                            eprintln!("{} {}", "Error:".red(), e);
+                            eprintln!("{} Parsing roadmap: {}", "Error:".red(), e);
                             std::process::exit(1);
                         }
-                    }
+                    };
 
-                    // Show item counts from database
-                    match db.get_all_roadmap_items() {
-                        Ok(items) => {
-                            let with_issues = items
-                                .iter()
-                                .filter(|i| i.github_issue_number.is_some())
-                                .count();
-                            let with_outcomes =
-                                items.iter().filter(|i| i.outcome_node_id.is_some()).count();
-                            let completed = items
-                                .iter()
-                                .filter(|i| i.checkbox_state == "checked")
-                                .count();
+                    println!(
+                        "{} Found {} sections in {}",
+                        "Parsed:".green(),
+                        parsed.sections.len(),
+                        roadmap_path.display()
+                    );
 
-                            println!("\n{}", "Items:".cyan());
-                            println!("  Total: {}", items.len());
-                            println!("  With GitHub Issues: {}", with_issues);
-                            println!("  With Outcome Links: {}", with_outcomes);
-                            println!("  Completed: {}", completed);
-                        }
-                        Err(_) => {
-                            println!("\n{} No items in database yet", "Items:".dimmed());
+                    // Read original content for rewriting
+                    let content = match std::fs::read_to_string(&roadmap_path) {
+                        Ok(c) => c,
+                        Err(e) => {
+                            eprintln!("{} Reading file: {}", "Error:".red(), e);
+                            std::process::exit(1);
                         }
-                    }
-                }
+                    };
 
-                RoadmapAction::Check {
-                    path: _,
-                    incomplete,
-                    complete,
-                } => {
-                    // Get all roadmap items from database
-                    let items = match db.get_all_roadmap_items() {
-                        Ok(i) => i,
+                    // Write back with metadata
+                    let updated = match write_roadmap_with_metadata(
+                        &roadmap_path,
+                        &parsed.sections,
+                        &content,
+                    ) {
+                        Ok(u) => u,
                         Err(e) => {
-                            eprintln!("{} {}", "Error:".red(), e);
+                            eprintln!("{} Writing metadata: {}", "Error:".red(), e);
                             std::process::exit(1);
                         }
                     };
-
-                    if items.is_empty() {
-                        println!("{} No roadmap items in database", "Status:".yellow());
-                        println!("Run 'deciduous roadmap init' first");
-                        return;
+                    if let Err(e) = std::fs::write(&roadmap_path, &updated) {
+                        eprintln!("{} Writing file: {}", "Error:".red(), e);
+                        std::process::exit(1);
                     }
 
-                    // Check completion for each item
-                    let mut complete_count = 0;
-                    let mut incomplete_count = 0;
-                    let mut results: Vec<(String, bool, bool, bool, bool)> = Vec::new();
-
-                    for item in &items {
-                        match db.check_roadmap_item_completion(item.id) {
-                            Ok((is_complete, has_outcome, issue_closed)) => {
-                                let checkbox_checked = item.checkbox_state == "checked";
+                    // Track current level-2 parent section for grouping
+                    let mut current_l2_parent: Option<String> = None;
 
-                                if is_complete && checkbox_checked {
-                                    complete_count += 1;
-                                } else {
-                                    incomplete_count += 1;
-                                }
+                    // Store sections in database
+                    for section in &parsed.sections {
+                        // Level 2 headers (## Section) are top-level groupings
+                        // Level 3 headers (### Subsection) contain the actual tasks
+                        let (section_parent, items_section) = if section.level == 2 {
+                            current_l2_parent = Some(section.title.clone());
+                            // Level 2 sections have no parent, their items go under them
+                            (None, Some(section.title.as_str()))
+                        } else {
+                            // Level 3 sections belong to the current L2 parent
+                            // Their items belong directly to this L3 section
+                            (current_l2_parent.as_deref(), Some(section.title.as_str()))
+                        };
 
-                                results.push((
-                                    item.title.clone(),
-                                    is_complete && checkbox_checked,
-                                    checkbox_checked,
-                                    has_outcome,
-                                    issue_closed,
-                                ));
-                            }
-                            Err(e) => {
-                                eprintln!("{} Checking {}: {}", "Warning:".yellow(), item.title, e);
-                            }
+                        // Create the section header entry (checkbox_state = "none")
+                        if let Err(e) = db.create_roadmap_item(
+                            &section.title,
+                            section.description.as_deref(),
+                            section_parent,
+                            None, // parent_id - we don't track hierarchy by ID yet
+                            "none",
+                        ) {
+                            eprintln!("{} Creating roadmap item: {}", "Warning:".yellow(), e);
                         }
+
+                        // Create items for checkboxes - they belong to THIS section
+                        store_roadmap_items(&db, section, items_section);
                     }
 
-                    // Print header
-                    println!("{}", "Roadmap Completion Audit".cyan().bold());
-                    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-                    println!();
+                    // Count items
+                    let total_items: usize = parsed.sections.iter().map(|s| s.items.len()).sum();
+                    println!(
+                        "{} Initialized {} sections with {} items",
+                        "Success:".green(),
+                        parsed.sections.len(),
+                        total_items
+                    );
+                    println!("  Metadata comments added to {}", roadmap_path.display());
+                }
 
-                    // Print results based on filters
-                    for (title, is_complete, checkbox, outcome, issue) in &results {
-                        // Apply filters
-                        if incomplete && *is_complete {
-                            continue;
+                RoadmapAction::Refresh { path } => {
+                    let roadmap_path = path.unwrap_or_else(|| PathBuf::from("ROADMAP.md"));
+
+                    if !roadmap_path.exists() {
+                        eprintln!(
+                            "{} File not found: {}",
+                            "Error:".red(),
+                            roadmap_path.display()
+                        );
+                        std::process::exit(1);
+                    }
+
+                    // Clear existing roadmap items
+                    let cleared = match db.clear_roadmap_items() {
+                        Ok(n) => n,
+                        Err(e) => {
+                            eprintln!("{} Clearing roadmap items: {}", "Error:".red(), e);
+                            std::process::exit(1);
                         }
-                        if complete && !*is_complete {
-                            continue;
+                    };
+                    println!(
+                        "{} Cleared {} existing roadmap items",
+                        "Info:".cyan(),
+                        cleared
+                    );
+
+                    // Re-parse the roadmap
+                    let parsed = match parse_roadmap(&roadmap_path) {
+                        Ok(p) => p,
+                        Err(e) => {
+                            eprintln!("{} Parsing roadmap: {}", "Error:".red(), e);
+                            std::process::exit(1);
                         }
+                    };
 
-                        let status_icon = if *is_complete {
-                            "✓".green()
+                    // Track current level-2 parent section for grouping
+                    let mut current_l2_parent: Option<String> = None;
+
+                    // Store sections in database
+                    for section in &parsed.sections {
+                        let (section_parent, items_section) = if section.level == 2 {
+                            current_l2_parent = Some(section.title.clone());
+                            (None, Some(section.title.as_str()))
                         } else {
-                            "○".yellow()
+                            (current_l2_parent.as_deref(), Some(section.title.as_str()))
                         };
 
-                        let checkbox_icon = if *checkbox {
-                            "☑".green()
-                        } else {
-                            "☐".dimmed()
-                        };
-                        let outcome_icon = if *outcome {
-                            "⚡".green()
-                        } else {
-                            "⚡".dimmed()
-                        };
-                        let issue_icon = if *issue {
-                            "🔒".green()
-                        } else {
-                            "🔓".dimmed()
-                        };
+                        // Create the section header entry
+                        if let Err(e) = db.create_roadmap_item(
+                            &section.title,
+                            section.description.as_deref(),
+                            section_parent,
+                            None,
+                            "none",
+                        ) {
+                            eprintln!("{} Creating roadmap item: {}", "Warning:".yellow(), e);
+                        }
 
-                        println!(
-                            "{} {} {} {} {}",
-                            status_icon,
-                            checkbox_icon,
-                            outcome_icon,
-                            issue_icon,
-                            truncate(title, 60)
-                        );
+                        // Create items for checkboxes
+                        store_roadmap_items(&db, section, items_section);
                     }
 
-                    // Print summary
-                    println!();
-                    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-                    println!();
-                    println!("{}", "Legend:".dimmed());
+                    let total_items: usize = parsed.sections.iter().map(|s| s.items.len()).sum();
                     println!(
-                        "  {} = checkbox checked    {} = outcome linked    {} = issue closed",
-                        "☑".green(),
-                        "⚡".green(),
-                        "🔒".green()
+                        "{} Refreshed {} sections with {} items",
+                        "Success:".green(),
+                        parsed.sections.len(),
+                        total_items
                     );
-                    println!();
-                    println!("{}", "Summary:".cyan());
-                    println!("  {} {} complete", "✓".green(), complete_count);
-                    println!("  {} {} incomplete", "○".yellow(), incomplete_count);
-                    println!("  {} total items", items.len());
-
-                    if incomplete_count > 0 {
-                        println!();
-                        println!(
-                            "{} Completion requires: checkbox ☑ AND outcome ⚡ AND issue closed 🔒",
-                            "Note:".dimmed()
-                        );
-                    }
                 }
-            }
-        }
-
-        Command::Trace { action } => {
-            match action {
-                TraceAction::Start { cwd, command } => {
-                    let session_id = uuid::Uuid::new_v4().to_string();
-                    let working_dir = cwd.map(|p| p.to_string_lossy().to_string()).or_else(|| {
-                        std::env::current_dir()
-                            .ok()
-                            .map(|p| p.to_string_lossy().to_string())
-                    });
 
-                    // Get git branch
-                    let git_branch = std::process::Command::new("git")
-                        .args(["branch", "--show-current"])
-                        .output()
-                        .ok()
-                        .filter(|o| o.status.success())
-                        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string());
+                RoadmapAction::ProjectSync {
+                    project,
+                    owner,
+                    path,
+                    repo,
+                    execute,
+                } => {
+                    let dry_run = !execute;
+                    let roadmap_path = path.unwrap_or_else(|| PathBuf::from("ROADMAP.md"));
 
-                    match db.start_trace_session(
-                        &session_id,
-                        working_dir.as_deref(),
-                        git_branch.as_deref(),
-                        command.as_deref(),
-                    ) {
-                        Ok(_id) => {
-                            // Output JSON for the interceptor to parse
-                            println!(r#"{{"session_id": "{}"}}"#, session_id);
-                        }
+                    let parsed = match parse_roadmap(&roadmap_path) {
+                        Ok(p) => p,
                         Err(e) => {
-                            eprintln!("{} {}", "Error:".red(), e);
+                            eprintln!("{} Parsing roadmap: {}", "Error:".red(), e);
                             std::process::exit(1);
                         }
-                    }
-                }
-
-                TraceAction::End {
-                    session_id,
-                    summary,
-                } => match db.end_trace_session(&session_id, summary.as_deref()) {
-                    Ok(()) => {
-                        println!("{} Trace session ended", "Success:".green());
-                    }
-                    Err(e) => {
-                        eprintln!("{} {}", "Error:".red(), e);
-                        std::process::exit(1);
-                    }
-                },
+                    };
 
-                TraceAction::Record {
-                    session,
-                    span_id: existing_span_id,
-                    stdin,
-                } => {
-                    if !stdin {
-                        eprintln!("{} --stdin is required", "Error:".red());
-                        std::process::exit(1);
-                    }
+                    // Projects v2 is GitHub-only
+                    let gh_client = match repo {
+                        Some(r) => GitHubClient::new(Some(r)),
+                        None => match GitHubClient::auto_detect() {
+                            Ok(c) => c,
+                            Err(e) => {
+                                eprintln!("{} Auto-detecting repo: {}", "Error:".red(), e);
+                                eprintln!("Specify repo with --repo owner/repo");
+                                std::process::exit(1);
+                            }
+                        },
+                    };
 
-                    let mut input = String::new();
-                    if let Err(e) = std::io::stdin().read_line(&mut input) {
-                        eprintln!("{} Reading stdin: {}", "Error:".red(), e);
+                    let Some(owner) = owner.or_else(|| {
+                        gh_client
+                            .repo_name()
+                            .and_then(|r| r.split('/').next())
+                            .map(|o| o.to_string())
+                    }) else {
+                        eprintln!("{} Could not determine project owner", "Error:".red());
+                        eprintln!("Specify owner with --owner <user-or-org>");
                         std::process::exit(1);
-                    }
+                    };
 
-                    // Parse span data from JSON
-                    let span_data: serde_json::Value = match serde_json::from_str(&input) {
-                        Ok(v) => v,
+                    let board = match gh_client.get_project(&owner, project) {
+                        Ok(b) => b,
                         Err(e) => {
-                            eprintln!("{} Parsing JSON: {}", "Error:".red(), e);
+                            eprintln!("{} Loading project: {}", "Error:".red(), e);
+                            eprintln!(
+                                "Projects need the 'project' scope: gh auth refresh -s project"
+                            );
                             std::process::exit(1);
                         }
                     };
 
-                    let model = span_data["model"].as_str();
-                    let user_preview = span_data["user_preview"].as_str();
-
-                    // Use existing span or create new one
-                    let span_id = if let Some(sid) = existing_span_id {
-                        // Update model if provided (span-start might not have had it)
-                        if model.is_some() {
-                            let _ = db.update_trace_span_model(sid, model);
-                        }
-                        sid
-                    } else {
-                        // Create new span (legacy single-call mode)
-                        match db.create_trace_span(&session, model, user_preview) {
-                            Ok(id) => id,
-                            Err(e) => {
-                                eprintln!("{} Creating span: {}", "Error:".red(), e);
-                                std::process::exit(1);
-                            }
-                        }
+                    let Some(field_id) = board.status_field_id.clone() else {
+                        eprintln!(
+                            "{} Project '{}' has no single-select Status field",
+                            "Error:".red(),
+                            board.title
+                        );
+                        std::process::exit(1);
                     };
 
-                    // Complete span if response data is included
-                    if span_data.get("duration_ms").is_some() {
-                        let duration_ms = span_data["duration_ms"].as_i64().unwrap_or(0) as i32;
-                        let request_id = span_data["request_id"].as_str();
-                        let stop_reason = span_data["stop_reason"].as_str();
-                        let input_tokens = span_data["input_tokens"].as_i64().map(|v| v as i32);
-                        let output_tokens = span_data["output_tokens"].as_i64().map(|v| v as i32);
-                        let cache_read = span_data["cache_read"].as_i64().map(|v| v as i32);
-                        let cache_write = span_data["cache_write"].as_i64().map(|v| v as i32);
-                        let thinking_preview = span_data["thinking_preview"].as_str();
-                        let response_preview = span_data["response_preview"].as_str();
-                        let tool_names = span_data["tool_names"].as_str();
+                    // Sections become board items once they have an issue
+                    let sections: Vec<&RoadmapSection> = parsed
+                        .sections
+                        .iter()
+                        .filter(|s| s.level == 3 && s.github_issue_number.is_some())
+                        .collect();
 
-                        if let Err(e) = db.complete_trace_span(
-                            span_id,
-                            duration_ms,
-                            request_id,
-                            stop_reason,
-                            input_tokens,
-                            output_tokens,
-                            cache_read,
-                            cache_write,
-                            thinking_preview,
-                            response_preview,
-                            tool_names,
-                            user_preview,
-                        ) {
-                            eprintln!("{} Completing span: {}", "Error:".red(), e);
-                            std::process::exit(1);
+                    println!(
+                        "{} {} sections -> project '{}'{}",
+                        "Project sync:".cyan(),
+                        sections.len(),
+                        board.title,
+                        if dry_run {
+                            " (dry run, use --execute to apply)"
+                        } else {
+                            ""
                         }
+                    );
 
-                        // Store full content if provided
-                        if let Some(thinking) = span_data["thinking"].as_str() {
-                            let _ = db.add_trace_content(span_id, "thinking", thinking, None, None);
-                        }
-                        if let Some(response) = span_data["response"].as_str() {
-                            let _ = db.add_trace_content(span_id, "response", response, None, None);
-                        }
-                        if let Some(tools) = span_data["tool_calls"].as_array() {
-                            for tool in tools {
-                                let tool_name = tool["name"].as_str();
-                                let tool_use_id = tool["id"].as_str();
-                                if let Some(input) = tool["input"].as_str() {
-                                    let _ = db.add_trace_content(
-                                        span_id,
-                                        "tool_input",
-                                        input,
-                                        tool_name,
-                                        tool_use_id,
-                                    );
-                                }
-                                if let Some(output) = tool["output"].as_str() {
-                                    let _ = db.add_trace_content(
-                                        span_id,
-                                        "tool_output",
-                                        output,
-                                        tool_name,
-                                        tool_use_id,
-                                    );
-                                }
-                            }
-                        }
+                    let mut synced = 0;
+                    for section in sections {
+                        let issue_num = section.github_issue_number.unwrap_or_default();
+                        let status = section_board_status(section);
+
+                        let Some(option_id) = status
+                            .option_names()
+                            .iter()
+                            .find_map(|name| board.status_option_id(name))
+                        else {
+                            eprintln!(
+                                "  {} No '{}' column on the board; skipping #{}",
+                                "Warning:".yellow(),
+                                status,
+                                issue_num
+                            );
+                            continue;
+                        };
 
-                        // Store system prompt if provided (captured from request)
-                        if let Some(system_prompt) = span_data["system_prompt"].as_str() {
-                            let _ =
-                                db.add_trace_content(span_id, "system", system_prompt, None, None);
+                        if dry_run {
+                            println!(
+                                "  {} #{} {} -> {}",
+                                "[DRY]".yellow(),
+                                issue_num,
+                                section.title,
+                                status
+                            );
+                            synced += 1;
+                            continue;
                         }
 
-                        // Store tool definitions if provided (captured from request)
-                        if let Some(tool_defs) = span_data["tool_definitions"].as_array() {
-                            let tool_defs_json =
-                                serde_json::to_string(tool_defs).unwrap_or_default();
-                            if !tool_defs_json.is_empty() && tool_defs_json != "[]" {
-                                let _ = db.add_trace_content(
-                                    span_id,
-                                    "tool_definitions",
-                                    &tool_defs_json,
-                                    None,
-                                    None,
+                        let result = gh_client
+                            .issue_node_id(issue_num)
+                            .and_then(|content_id| gh_client.add_to_project(&board.id, &content_id))
+                            .and_then(|item_id| {
+                                gh_client.set_project_item_status(
+                                    &board.id, &item_id, &field_id, option_id,
+                                )
+                            });
+
+                        match result {
+                            Ok(()) => {
+                                println!(
+                                    "  {} #{} {} -> {}",
+                                    "✓".green(),
+                                    issue_num,
+                                    section.title,
+                                    status
                                 );
+                                synced += 1;
+                            }
+                            Err(e) => {
+                                eprintln!("  {} #{}: {}", "✗".red(), issue_num, e);
                             }
                         }
+                    }
 
-                        // Store tool results if provided (from previous tool calls in request)
-                        if let Some(tool_results) = span_data["tool_results"].as_array() {
-                            for result in tool_results {
-                                let tool_use_id = result["tool_use_id"].as_str();
-                                if let Some(content) = result["content"].as_str() {
-                                    let is_error = result["is_error"].as_bool().unwrap_or(false);
-                                    let content_type = if is_error {
-                                        "tool_error"
-                                    } else {
-                                        "tool_output"
-                                    };
-                                    let _ = db.add_trace_content(
-                                        span_id,
-                                        content_type,
-                                        content,
-                                        None,
-                                        tool_use_id,
-                                    );
-                                }
-                            }
-                        }
-                    }
-
-                    // Output JSON for the interceptor
-                    println!(r#"{{"span_id": {}}}"#, span_id);
+                    println!();
+                    println!(
+                        "{} {} items {}",
+                        if dry_run {
+                            "Dry run:".yellow()
+                        } else {
+                            "Done:".green()
+                        },
+                        synced,
+                        if dry_run { "would be placed" } else { "placed" }
+                    );
                 }
 
-                TraceAction::SpanStart {
-                    session,
-                    model,
-                    user_preview,
+                RoadmapAction::Sync {
+                    path,
+                    repo,
+                    execute,
+                    create_issues,
+                    milestones,
+                    no_pull,
                 } => {
-                    // Create a pending span and return its ID
-                    // This enables active span tracking - the interceptor sets
-                    // DECIDUOUS_TRACE_SPAN so nodes created during the span
-                    // can be automatically linked
-                    match db.create_trace_span(&session, model.as_deref(), user_preview.as_deref())
-                    {
-                        Ok(span_id) => {
-                            // Output JSON for the interceptor to parse
-                            println!(r#"{{"span_id": {}}}"#, span_id);
-                        }
+                    let dry_run = !execute; // Default is dry-run mode
+                    let roadmap_path = path.unwrap_or_else(|| PathBuf::from("ROADMAP.md"));
+
+                    if !roadmap_path.exists() {
+                        eprintln!(
+                            "{} File not found: {}",
+                            "Error:".red(),
+                            roadmap_path.display()
+                        );
+                        eprintln!("Run 'deciduous roadmap init' first");
+                        std::process::exit(1);
+                    }
+
+                    // Initialize forge client (GitHub or GitLab)
+                    let config = Config::load();
+                    let forge = match detect_forge(repo, &config) {
+                        Ok(f) => f,
                         Err(e) => {
-                            eprintln!("{} {}", "Error:".red(), e);
+                            eprintln!("{} Auto-detecting repo: {}", "Error:".red(), e);
+                            eprintln!("Specify repo with --repo owner/repo");
                             std::process::exit(1);
                         }
-                    }
-                }
-
-                TraceAction::Sessions { limit, linked } => {
-                    let sessions = if linked {
-                        db.get_linked_trace_sessions(limit)
-                    } else {
-                        db.get_trace_sessions(limit)
                     };
 
-                    match sessions {
-                        Ok(sessions) => {
-                            if sessions.is_empty() {
-                                println!("No trace sessions found.");
-                                return;
-                            }
-
-                            println!(
-                                "{} ({} sessions)\n",
-                                "Trace Sessions".cyan(),
-                                sessions.len()
-                            );
-
-                            for session in &sessions {
-                                let status = if session.ended_at.is_some() {
-                                    "ended".dimmed()
-                                } else {
-                                    "active".green()
-                                };
-
-                                let linked_str = match session.linked_node_id {
-                                    Some(id) => format!("→ node #{}", id).yellow().to_string(),
-                                    None => "".to_string(),
-                                };
-
-                                let tokens = format!(
-                                    "{}↓ {}↑",
-                                    session.total_input_tokens, session.total_output_tokens
-                                );
-
-                                println!(
-                                    "  {} [{}] {} {} {}",
-                                    &session.session_id[..8],
-                                    status,
-                                    tokens.dimmed(),
-                                    session.command.as_deref().unwrap_or(""),
-                                    linked_str
-                                );
-                            }
+                    // Check auth
+                    match forge.check_auth() {
+                        Ok(true) => {}
+                        Ok(false) | Err(_) => {
+                            eprintln!("{} Not authenticated with {}", "Error:".red(), forge.kind());
+                            eprintln!("{}", forge.kind().auth_hint());
+                            std::process::exit(1);
                         }
+                    }
+
+                    // Parse roadmap
+                    let mut parsed = match parse_roadmap(&roadmap_path) {
+                        Ok(p) => p,
                         Err(e) => {
-                            eprintln!("{} {}", "Error:".red(), e);
+                            eprintln!("{} Parsing roadmap: {}", "Error:".red(), e);
                             std::process::exit(1);
                         }
-                    }
-                }
+                    };
 
-                TraceAction::Spans {
-                    session_id,
-                    show_thinking,
-                } => match db.get_trace_spans(&session_id) {
-                    Ok(spans) => {
-                        if spans.is_empty() {
-                            println!("No spans found for session {}.", &session_id[..8]);
-                            return;
+                    // Pull issue-side edits into ROADMAP.md before pushing it back out
+                    let pull = if no_pull {
+                        RoadmapPull::default()
+                    } else {
+                        pull_roadmap_from_issues(&db, forge.as_ref(), &mut parsed.sections, dry_run)
+                    };
+                    if !dry_run && (!pull.line_changes.is_empty() || pull.states > 0) {
+                        let content = std::fs::read_to_string(&roadmap_path).unwrap_or_default();
+                        let content = set_checkbox_lines(&content, &pull.line_changes);
+                        if let Err(e) =
+                            write_roadmap_with_metadata(&roadmap_path, &parsed.sections, &content)
+                        {
+                            eprintln!("{} Writing pulled changes: {}", "Error:".red(), e);
+                            std::process::exit(1);
                         }
+                    }
+
+                    // Only sync level 3 sections (actual items, not parent headers)
+                    let syncable_sections: Vec<&RoadmapSection> =
+                        parsed.sections.iter().filter(|s| s.level == 3).collect();
 
+                    if dry_run {
                         println!(
-                            "{} ({} spans)\n",
-                            format!("Session {}", &session_id[..8]).cyan(),
-                            spans.len()
+                            "{} {} sections (use --execute to apply changes)",
+                            "Roadmap (dry run):".yellow(),
+                            syncable_sections.len()
                         );
+                    } else {
+                        println!(
+                            "{} Syncing {} sections",
+                            "Roadmap:".cyan(),
+                            syncable_sections.len()
+                        );
+                    }
 
-                        for span in &spans {
-                            let duration = span
-                                .duration_ms
-                                .map(|d| format!("{}ms", d))
-                                .unwrap_or_else(|| "...".to_string());
-
-                            let tokens = match (span.input_tokens, span.output_tokens) {
-                                (Some(i), Some(o)) => format!("{}↓ {}↑", i, o),
-                                _ => "".to_string(),
-                            };
+                    if let Some(repo_name) = forge.repo_name() {
+                        println!("  Repository: {}", repo_name);
+                    }
 
-                            let linked_str = match span.linked_node_id {
-                                Some(id) => format!("→ #{}", id).yellow().to_string(),
-                                None => "".to_string(),
-                            };
+                    // Ensure 'roadmap' label exists if we're creating issues
+                    if !dry_run && create_issues {
+                        match ensure_roadmap_label(forge.as_ref()) {
+                            Ok(true) => println!("  {} Created 'roadmap' label", "✓".green()),
+                            Ok(false) => {} // Label already exists
+                            Err(e) => eprintln!(
+                                "  {} Creating label: {} (issues may fail)",
+                                "Warning:".yellow(),
+                                e
+                            ),
+                        }
+                    }
 
-                            println!(
-                                "  #{} [{}] {} {} {}",
-                                span.id,
-                                duration.dimmed(),
-                                tokens.dimmed(),
-                                span.model.as_deref().unwrap_or(""),
-                                linked_str
-                            );
+                    let mut created = 0;
+                    let mut updated = 0;
+                    let mut skipped = 0;
+                    // Section change_id -> issue number, for milestone assignment
+                    let mut section_issues: std::collections::HashMap<String, i32> =
+                        std::collections::HashMap::new();
 
-                            if let Some(ref tools) = span.tool_names {
-                                println!("      tools: {}", tools.dimmed());
-                            }
+                    for section in &syncable_sections {
+                        // Check if section already has an issue
+                        if section.github_issue_number.is_some() {
+                            // Update existing issue
+                            let issue_num = section.github_issue_number.unwrap();
+                            let body = generate_issue_body(section);
+                            section_issues.insert(section.change_id.clone(), issue_num);
 
-                            if show_thinking {
-                                if let Some(ref thinking) = span.thinking_preview {
-                                    let preview = if thinking.len() > 100 {
-                                        format!("{}...", &thinking[..100])
-                                    } else {
-                                        thinking.clone()
-                                    };
-                                    println!("      thinking: {}", preview.dimmed());
+                            if pull.held.contains(&section.change_id) {
+                                println!(
+                                    "  {} Holding issue {} (see 'deciduous roadmap conflicts'): {}",
+                                    "!".yellow(),
+                                    forge.issue_ref(issue_num),
+                                    section.title
+                                );
+                                skipped += 1;
+                            } else if dry_run {
+                                println!(
+                                    "  {} Would update issue {}: {}",
+                                    "[DRY]".yellow(),
+                                    forge.issue_ref(issue_num),
+                                    section.title
+                                );
+                                updated += 1;
+                            } else {
+                                match forge.update_issue_body(issue_num, &body) {
+                                    Ok(()) => {
+                                        println!(
+                                            "  {} Updated issue {}: {}",
+                                            "✓".green(),
+                                            forge.issue_ref(issue_num),
+                                            section.title
+                                        );
+                                        updated += 1;
+                                        record_roadmap_baseline(&db, section);
+                                    }
+                                    Err(e) => {
+                                        eprintln!(
+                                            "  {} Updating issue {}: {}",
+                                            "✗".red(),
+                                            forge.issue_ref(issue_num),
+                                            e
+                                        );
+                                    }
                                 }
                             }
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("{} {}", "Error:".red(), e);
-                        std::process::exit(1);
-                    }
-                },
+                        } else if create_issues {
+                            // Create new issue
+                            let body = generate_issue_body(section);
 
-                TraceAction::Show {
-                    span_id,
-                    thinking,
-                    response,
-                    tools,
-                } => {
-                    let show_all = !thinking && !response && !tools;
+                            if dry_run {
+                                println!(
+                                    "  {} Would create issue: {}",
+                                    "[DRY]".yellow(),
+                                    section.title
+                                );
+                                created += 1;
+                            } else {
+                                match forge.create_issue(&section.title, &body, &["roadmap"]) {
+                                    Ok(issue) => {
+                                        println!(
+                                            "  {} Created issue {}: {}",
+                                            "✓".green(),
+                                            forge.issue_ref(issue.number),
+                                            section.title
+                                        );
+                                        created += 1;
+                                        section_issues
+                                            .insert(section.change_id.clone(), issue.number);
+                                        record_roadmap_baseline(&db, section);
 
-                    match db.get_trace_span(span_id) {
-                        Ok(Some(span)) => {
-                            println!("{}", format!("Span #{}", span_id).cyan());
-                            println!("  Session: {}", &span.session_id[..8]);
-                            if let Some(model) = &span.model {
-                                println!("  Model: {}", model);
-                            }
-                            if let Some(duration) = span.duration_ms {
-                                println!("  Duration: {}ms", duration);
-                            }
-                            if let (Some(i), Some(o)) = (span.input_tokens, span.output_tokens) {
-                                println!("  Tokens: {}↓ {}↑", i, o);
-                            }
-                            println!();
-                        }
-                        Ok(None) => {
-                            eprintln!("{} Span #{} not found", "Error:".red(), span_id);
-                            std::process::exit(1);
-                        }
-                        Err(e) => {
-                            eprintln!("{} {}", "Error:".red(), e);
-                            std::process::exit(1);
-                        }
-                    }
-
-                    // Get content
-                    match db.get_trace_content(span_id) {
-                        Ok(content) => {
-                            for item in &content {
-                                let show = show_all
-                                    || (thinking && item.content_type == "thinking")
-                                    || (response && item.content_type == "response")
-                                    || (tools
-                                        && (item.content_type == "tool_input"
-                                            || item.content_type == "tool_output"));
-
-                                if show {
-                                    let label = match item.content_type.as_str() {
-                                        "thinking" => "Thinking".magenta(),
-                                        "response" => "Response".green(),
-                                        "tool_input" => format!(
-                                            "Tool Input ({})",
-                                            item.tool_name.as_deref().unwrap_or("?")
-                                        )
-                                        .yellow(),
-                                        "tool_output" => format!(
-                                            "Tool Output ({})",
-                                            item.tool_name.as_deref().unwrap_or("?")
-                                        )
-                                        .cyan(),
-                                        _ => item.content_type.clone().normal(),
-                                    };
+                                        // Update database with issue number
+                                        if let Err(e) = db.update_roadmap_item_github_by_title(
+                                            &section.title,
+                                            issue.number,
+                                            &issue.state,
+                                        ) {
+                                            eprintln!(
+                                                "    {} Updating database: {}",
+                                                "Warning:".yellow(),
+                                                e
+                                            );
+                                        }
 
-                                    println!("{}", label);
-                                    println!("{}", "─".repeat(60));
-                                    println!("{}", item.content);
-                                    println!();
+                                        // Cache issue for TUI/Web display
+                                        if let Some(repo_name) = forge.repo_name() {
+                                            if let Err(e) = db.cache_github_issue(
+                                                issue.number,
+                                                repo_name,
+                                                &issue.title,
+                                                Some(&issue.body),
+                                                &issue.state,
+                                                &issue.html_url,
+                                                &issue.created_at,
+                                                &issue.updated_at,
+                                            ) {
+                                                eprintln!(
+                                                    "    {} Caching issue: {}",
+                                                    "Warning:".yellow(),
+                                                    e
+                                                );
+                                            }
+                                        }
+                                    }
+                                    Err(e) => {
+                                        eprintln!(
+                                            "  {} Creating issue for '{}': {}",
+                                            "✗".red(),
+                                            section.title,
+                                            e
+                                        );
+                                    }
                                 }
                             }
-                        }
-                        Err(e) => {
-                            eprintln!("{} {}", "Error:".red(), e);
+                        } else {
+                            println!("  {} Skipping (no issue): {}", "-".dimmed(), section.title);
+                            skipped += 1;
                         }
                     }
-                }
 
-                TraceAction::Link {
-                    node_id,
-                    session,
-                    span,
-                } => {
-                    if session.is_none() && span.is_none() {
-                        eprintln!("{} Specify --session or --span", "Error:".red());
-                        std::process::exit(1);
-                    }
+                    // Roll level-3 issues up into a milestone per level-2 section
+                    if milestones {
+                        let mut known = match forge.list_milestones() {
+                            Ok(m) => m,
+                            Err(e) => {
+                                eprintln!("{} Listing milestones: {}", "Error:".red(), e);
+                                std::process::exit(1);
+                            }
+                        };
 
-                    if let Some(session_id) = session {
-                        match db.link_trace_session_to_node(&session_id, node_id) {
-                            Ok(()) => {
+                        for (parent, children) in milestone_groups(&parsed.sections) {
+                            let issues: Vec<i32> = children
+                                .iter()
+                                .filter_map(|c| section_issues.get(&c.change_id).copied())
+                                .collect();
+                            if issues.is_empty() {
+                                continue;
+                            }
+
+                            if dry_run {
+                                let exists = known.iter().any(|m| m.title == parent.title);
                                 println!(
-                                    "{} Linked session {} to node #{}",
-                                    "Success:".green(),
-                                    &session_id[..8],
-                                    node_id
+                                    "  {} Would {}milestone '{}' with {} issues",
+                                    "[DRY]".yellow(),
+                                    if exists { "use " } else { "create " },
+                                    parent.title,
+                                    issues.len()
                                 );
+                                continue;
                             }
-                            Err(e) => {
-                                eprintln!("{} {}", "Error:".red(), e);
-                                std::process::exit(1);
+
+                            let description = parent
+                                .description
+                                .clone()
+                                .unwrap_or_else(|| "Synced from ROADMAP.md by deciduous".into());
+                            let milestone = match ensure_milestone(
+                                forge.as_ref(),
+                                &mut known,
+                                &parent.title,
+                                &description,
+                            ) {
+                                Ok((m, was_created)) => {
+                                    if was_created {
+                                        println!(
+                                            "  {} Created milestone '{}'",
+                                            "✓".green(),
+                                            m.title
+                                        );
+                                    }
+                                    m
+                                }
+                                Err(e) => {
+                                    eprintln!(
+                                        "  {} Milestone '{}': {}",
+                                        "✗".red(),
+                                        parent.title,
+                                        e
+                                    );
+                                    continue;
+                                }
+                            };
+
+                            for issue_num in issues {
+                                if let Err(e) =
+                                    forge.set_issue_milestone(issue_num, &milestone.title)
+                                {
+                                    eprintln!(
+                                        "  {} Assigning #{} to '{}': {}",
+                                        "✗".red(),
+                                        issue_num,
+                                        milestone.title,
+                                        e
+                                    );
+                                }
                             }
                         }
                     }
 
-                    if let Some(span_id) = span {
-                        match db.link_trace_span_to_node(span_id, node_id) {
-                            Ok(()) => {
-                                println!(
-                                    "{} Linked span #{} to node #{}",
-                                    "Success:".green(),
-                                    span_id,
-                                    node_id
-                                );
-                            }
-                            Err(e) => {
-                                eprintln!("{} {}", "Error:".red(), e);
-                                std::process::exit(1);
+                    // Write updated roadmap with issue metadata
+                    if !dry_run && created > 0 {
+                        let content = std::fs::read_to_string(&roadmap_path).unwrap_or_default();
+                        match write_roadmap_with_metadata(&roadmap_path, &parsed.sections, &content)
+                        {
+                            Ok(updated_content) => {
+                                if let Err(e) = std::fs::write(&roadmap_path, &updated_content) {
+                                    eprintln!("{} Writing roadmap: {}", "Warning:".yellow(), e);
+                                }
                             }
+                            Err(e) => eprintln!("{} Updating metadata: {}", "Warning:".yellow(), e),
+                        }
+                    }
+
+                    if !dry_run {
+                        let roadmap_key = roadmap_path.to_string_lossy().to_string();
+                        let hash = std::fs::read_to_string(&roadmap_path)
+                            .map(|c| deciduous::roadmap::compute_hash(&c))
+                            .ok();
+                        let open_conflicts = db
+                            .get_unresolved_conflicts()
+                            .map(|c| c.len() as i32)
+                            .unwrap_or(0);
+                        if let Err(e) =
+                            db.get_or_create_sync_state(&roadmap_key).and_then(|state| {
+                                db.update_sync_state(
+                                    state.id,
+                                    hash.as_deref(),
+                                    forge.repo_name(),
+                                    true,
+                                    true,
+                                    open_conflicts,
+                                )
+                            })
+                        {
+                            eprintln!("{} Recording sync state: {}", "Warning:".yellow(), e);
                         }
                     }
+
+                    println!(
+                        "\n{} {} created, {} updated, {} skipped",
+                        if dry_run {
+                            "Summary (dry run):".yellow()
+                        } else {
+                            "Summary:".green()
+                        },
+                        created,
+                        updated,
+                        skipped
+                    );
+                    if !no_pull {
+                        println!(
+                            "  {} checkboxes and {} issue states pulled, {} new conflicts",
+                            pull.checkboxes, pull.states, pull.conflicts
+                        );
+                    }
                 }
 
-                TraceAction::Unlink { session, span } => {
-                    if session.is_none() && span.is_none() {
-                        eprintln!("{} Specify --session or --span", "Error:".red());
+                RoadmapAction::List {
+                    path,
+                    section,
+                    with_issues,
+                    without_issues,
+                    sort,
+                } => {
+                    let roadmap_path = path.unwrap_or_else(|| PathBuf::from("ROADMAP.md"));
+                    if let Some(key) = sort.as_deref() {
+                        if key != "priority" && key != "due" {
+                            eprintln!(
+                                "{} Unknown sort '{}' (use 'priority' or 'due')",
+                                "Error:".red(),
+                                key
+                            );
+                            std::process::exit(1);
+                        }
+                    }
+
+                    if !roadmap_path.exists() {
+                        eprintln!(
+                            "{} File not found: {}",
+                            "Error:".red(),
+                            roadmap_path.display()
+                        );
                         std::process::exit(1);
                     }
 
-                    if let Some(session_id) = session {
-                        match db.unlink_trace_session(&session_id) {
-                            Ok(()) => {
-                                println!(
-                                    "{} Unlinked session {}",
-                                    "Success:".green(),
-                                    &session_id[..8]
-                                );
-                            }
-                            Err(e) => {
-                                eprintln!("{} {}", "Error:".red(), e);
-                                std::process::exit(1);
-                            }
+                    let parsed = match parse_roadmap(&roadmap_path) {
+                        Ok(p) => p,
+                        Err(e) => {
+                            eprintln!("{} Parsing roadmap: {}", "Error:".red(), e);
+                            std::process::exit(1);
                         }
-                    }
+                    };
 
-                    if let Some(span_id) = span {
-                        match db.unlink_trace_span(span_id) {
-                            Ok(()) => {
-                                println!("{} Unlinked span #{}", "Success:".green(), span_id);
+                    // Filter sections
+                    let filtered: Vec<_> = parsed
+                        .sections
+                        .iter()
+                        .filter(|s| {
+                            if let Some(ref sect) = section {
+                                s.title.to_lowercase().contains(&sect.to_lowercase())
+                            } else {
+                                true
                             }
-                            Err(e) => {
-                                eprintln!("{} {}", "Error:".red(), e);
-                                std::process::exit(1);
+                        })
+                        .filter(|s| {
+                            if with_issues {
+                                s.github_issue_number.is_some()
+                            } else if without_issues {
+                                s.github_issue_number.is_none()
+                            } else {
+                                true
                             }
-                        }
+                        })
+                        .collect();
+
+                    if filtered.is_empty() {
+                        println!("No roadmap items found matching filters.");
+                        return;
                     }
+
+                    let schedule = |item: &deciduous::roadmap::RoadmapCheckItem| {
+                        let mut tags = String::new();
+                        if let Some(p) = item.priority {
+                            tags.push_str(&format!(" {}", format!("P{}", p).magenta()));
+                        }
+                        if let Some(ref due) = item.due {
+                            tags.push_str(&format!(" {}", format!("due {}", due).cyan()));
+                        }
+                        tags
+                    };
+
+                    if let Some(key) = sort.as_deref() {
+                        let mut items: Vec<_> = filtered
+                            .iter()
+                            .flat_map(|s| s.items.iter().map(move |i| (s.title.as_str(), i)))
+                            .collect();
+                        // Unset values sort last; ties keep file order
+                        items.sort_by(|(_, a), (_, b)| {
+                            let priority = |i: &deciduous::roadmap::RoadmapCheckItem| {
+                                i.priority.unwrap_or(u8::MAX)
+                            };
+                            let due = |i: &deciduous::roadmap::RoadmapCheckItem| {
+                                i.due.clone().unwrap_or_else(|| "~".to_string())
+                            };
+                            if key == "priority" {
+                                (priority(a), due(a)).cmp(&(priority(b), due(b)))
+                            } else {
+                                (due(a), priority(a)).cmp(&(due(b), priority(b)))
+                            }
+                        });
+
+                        println!(
+                            "{} ({} items by {})\n",
+                            "ROADMAP.md".cyan(),
+                            items.len(),
+                            key
+                        );
+                        for (section_title, item) in items {
+                            let check = if item.checked {
+                                "✓".green()
+                            } else {
+                                "○".dimmed()
+                            };
+                            println!(
+                                "  {} {}{} {}",
+                                check,
+                                strip_item_annotations(&item.text),
+                                schedule(item),
+                                format!("[{}]", section_title).dimmed()
+                            );
+                        }
+                        return;
+                    }
+
+                    println!("{} ({} sections)\n", "ROADMAP.md".cyan(), filtered.len());
+
+                    for s in &filtered {
+                        // Show section header based on level
+                        let header_prefix = if s.level == 2 { "##" } else { "###" };
+
+                        let issue_str = match s.github_issue_number {
+                            Some(n) => format!("#{}", n).green().to_string(),
+                            None => "no issue".dimmed().to_string(),
+                        };
+
+                        let completed: usize = s.items.iter().filter(|i| i.checked).count();
+                        let total = s.items.len();
+
+                        if total > 0 {
+                            println!(
+                                "{} {} [{}/{}] ({})",
+                                header_prefix.yellow(),
+                                s.title,
+                                completed,
+                                total,
+                                issue_str
+                            );
+                        } else {
+                            println!("{} {} ({})", header_prefix.yellow(), s.title, issue_str);
+                        }
+
+                        // Show checkbox items
+                        for item in &s.items {
+                            let check = if item.checked {
+                                "✓".green()
+                            } else {
+                                "○".dimmed()
+                            };
+                            println!("    {} {}", check, item.text);
+                        }
+                    }
+                }
+
+                RoadmapAction::Link { item, outcome_id } => {
+                    // Find roadmap item by title or change_id
+                    let items = match db.get_all_roadmap_items() {
+                        Ok(i) => i,
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    };
+
+                    let target = items.iter().find(|i| {
+                        i.change_id == item || i.title.to_lowercase().contains(&item.to_lowercase())
+                    });
+
+                    match target {
+                        Some(roadmap_item) => {
+                            // Verify outcome node exists and is an outcome
+                            match db.get_all_nodes() {
+                                Ok(nodes) => {
+                                    let node = nodes.iter().find(|n| n.id == outcome_id);
+                                    match node {
+                                        Some(n) if n.node_type == "outcome" => {
+                                            // Link them
+                                            match db.link_roadmap_to_outcome(
+                                                roadmap_item.id,
+                                                outcome_id,
+                                                &n.change_id,
+                                            ) {
+                                                Ok(()) => {
+                                                    println!(
+                                                        "{} Linked '{}' to outcome #{}: {}",
+                                                        "Success:".green(),
+                                                        roadmap_item.title,
+                                                        outcome_id,
+                                                        n.title
+                                                    );
+                                                    let linked = db
+                                                        .get_roadmap_item_outcomes(roadmap_item.id)
+                                                        .map(|l| l.len())
+                                                        .unwrap_or(0);
+                                                    if linked > 1 {
+                                                        println!(
+                                                            "  {} outcomes linked; the item is done when all are completed",
+                                                            linked
+                                                        );
+                                                    }
+                                                }
+                                                Err(e) => {
+                                                    eprintln!("{} {}", "Error:".red(), e);
+                                                    std::process::exit(1);
+                                                }
+                                            }
+                                        }
+                                        Some(n) => {
+                                            eprintln!(
+                                                "{} Node #{} is a {}, not an outcome",
+                                                "Error:".red(),
+                                                outcome_id,
+                                                n.node_type
+                                            );
+                                            std::process::exit(1);
+                                        }
+                                        None => {
+                                            eprintln!(
+                                                "{} Node #{} not found",
+                                                "Error:".red(),
+                                                outcome_id
+                                            );
+                                            std::process::exit(1);
+                                        }
+                                    }
+                                }
+                                Err(e) => {
+                                    eprintln!("{} {}", "Error:".red(), e);
+                                    std::process::exit(1);
+                                }
+                            }
+                        }
+                        None => {
+                            eprintln!("{} Roadmap item '{}' not found", "Error:".red(), item);
+                            eprintln!("Run 'deciduous roadmap list' to see available items");
+                            std::process::exit(1);
+                        }
+                    }
+                }
+
+                RoadmapAction::Unlink { item, outcome_id } => {
+                    let items = match db.get_all_roadmap_items() {
+                        Ok(i) => i,
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    };
+
+                    let target = items.iter().find(|i| {
+                        i.change_id == item || i.title.to_lowercase().contains(&item.to_lowercase())
+                    });
+
+                    match target {
+                        Some(roadmap_item) => {
+                            let result = match outcome_id {
+                                Some(id) => db.unlink_roadmap_outcome(roadmap_item.id, id),
+                                None => db
+                                    .unlink_roadmap_from_outcome(roadmap_item.id)
+                                    .map(|_| true),
+                            };
+                            match result {
+                                Ok(true) => {
+                                    println!(
+                                        "{} Unlinked '{}' from {}",
+                                        "Success:".green(),
+                                        roadmap_item.title,
+                                        outcome_id
+                                            .map(|id| format!("outcome #{}", id))
+                                            .unwrap_or_else(|| "all outcomes".to_string())
+                                    );
+                                }
+                                Ok(false) => {
+                                    eprintln!(
+                                        "{} '{}' is not linked to outcome #{}",
+                                        "Error:".red(),
+                                        roadmap_item.title,
+                                        outcome_id.unwrap_or_default()
+                                    );
+                                    std::process::exit(1);
+                                }
+                                Err(e) => {
+                                    eprintln!("{} {}", "Error:".red(), e);
+                                    std::process::exit(1);
+                                }
+                            }
+                        }
+                        None => {
+                            eprintln!("{} Roadmap item '{}' not found", "Error:".red(), item);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+
+                RoadmapAction::Start {
+                    item,
+                    confidence,
+                    branch,
+                } => {
+                    let items = match db.get_all_roadmap_items() {
+                        Ok(i) => i,
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    };
+
+                    let Some(target) = items.iter().find(|i| {
+                        i.change_id == item || i.title.to_lowercase().contains(&item.to_lowercase())
+                    }) else {
+                        eprintln!("{} Roadmap item '{}' not found", "Error:".red(), item);
+                        eprintln!("Run 'deciduous roadmap list' to see available items");
+                        std::process::exit(1);
+                    };
+
+                    if let Some(goal_id) = target.goal_node_id {
+                        eprintln!(
+                            "{} '{}' was already started as goal #{}",
+                            "Error:".red(),
+                            target.title,
+                            goal_id
+                        );
+                        std::process::exit(1);
+                    }
+
+                    let section_description = items
+                        .iter()
+                        .find(|i| !i.is_task() && target.section.as_ref() == Some(&i.title))
+                        .and_then(|i| i.description.as_deref());
+                    let siblings: Vec<String> = items
+                        .iter()
+                        .filter(|i| i.is_task() && i.id != target.id && i.section == target.section)
+                        .map(|i| i.title.clone())
+                        .collect();
+                    let prompt = goal_prompt(
+                        &target.title,
+                        target.section.as_deref(),
+                        section_description,
+                        &siblings,
+                    );
+
+                    let node_branch = if branch {
+                        let name = item_branch_name(&target.title);
+                        let created = ProcessCommand::new("git")
+                            .args(["checkout", "-b", &name])
+                            .output();
+                        match created {
+                            Ok(out) if out.status.success() => {
+                                println!("{} Created branch {}", "✓".green(), name.cyan());
+                                Some(name)
+                            }
+                            Ok(out) => {
+                                eprintln!(
+                                    "{} git checkout -b {}: {}",
+                                    "Error:".red(),
+                                    name,
+                                    String::from_utf8_lossy(&out.stderr).trim()
+                                );
+                                std::process::exit(1);
+                            }
+                            Err(e) => {
+                                eprintln!("{} Running git: {}", "Error:".red(), e);
+                                std::process::exit(1);
+                            }
+                        }
+                    } else {
+                        deciduous::get_current_git_branch()
+                    };
+
+                    let title = strip_item_annotations(&target.title);
+                    let goal_id = match db.create_node_full(
+                        "goal",
+                        &title,
+                        None,
+                        confidence,
+                        None,
+                        Some(&prompt),
+                        None,
+                        node_branch.as_deref(),
+                    ) {
+                        Ok(id) => id,
+                        Err(e) => {
+                            eprintln!("{} Creating goal: {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    };
+                    let goal_change_id = match db.get_node_by_id(goal_id) {
+                        Ok(Some(n)) => n.change_id,
+                        Ok(None) => {
+                            eprintln!(
+                                "{} Goal #{} not found after insert",
+                                "Error:".red(),
+                                goal_id
+                            );
+                            std::process::exit(1);
+                        }
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    };
+                    if let Err(e) = db.update_node_metadata_field(
+                        goal_id,
+                        "roadmap_item",
+                        serde_json::json!(target.change_id),
+                    ) {
+                        eprintln!("{} Recording roadmap item: {}", "Warning:".yellow(), e);
+                    }
+                    if let Err(e) = db.link_roadmap_to_goal(target.id, goal_id, &goal_change_id) {
+                        eprintln!("{} Linking goal: {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+
+                    println!(
+                        "{} Started goal #{}: {}",
+                        "Success:".green(),
+                        goal_id,
+                        title
+                    );
+                    println!(
+                        "Link the outcome when it lands: deciduous roadmap link \"{}\" <outcome_id>",
+                        target.change_id
+                    );
+                }
+
+                RoadmapAction::Conflicts { resolve } => {
+                    let conflicts = match db.get_unresolved_conflicts() {
+                        Ok(c) => c,
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    };
+
+                    if conflicts.is_empty() {
+                        println!("{} No sync conflicts", "Success:".green());
+                        return;
+                    }
+
+                    println!(
+                        "{} {} conflicts found:\n",
+                        "Conflicts:".yellow(),
+                        conflicts.len()
+                    );
+
+                    for conflict in &conflicts {
+                        println!(
+                            "  Item: {} ({})",
+                            conflict.item_change_id, conflict.conflict_type
+                        );
+                        println!(
+                            "    Local:  {}",
+                            conflict.local_value.as_deref().unwrap_or("(none)")
+                        );
+                        println!(
+                            "    Remote: {}",
+                            conflict.remote_value.as_deref().unwrap_or("(none)")
+                        );
+                        if let Some(ref res) = conflict.resolution {
+                            println!("    Resolution: {}", res);
+                        }
+                        println!();
+                    }
+
+                    if resolve {
+                        println!(
+                            "{} Interactive conflict resolution not yet implemented",
+                            "TODO:".yellow()
+                        );
+                        println!(
+                            "For now, manually edit ROADMAP.md and run 'deciduous roadmap sync'"
+                        );
+                    }
+                }
+
+                RoadmapAction::Status { path } => {
+                    let roadmap_path = path.unwrap_or_else(|| PathBuf::from("ROADMAP.md"));
+
+                    // Get sync state from database
+                    match db.get_roadmap_sync_state(&roadmap_path.to_string_lossy()) {
+                        Ok(Some(state)) => {
+                            println!("{}", "Roadmap Sync Status".cyan());
+                            println!("  Path: {}", roadmap_path.display());
+                            if let Some(ref repo) = state.github_repo {
+                                println!("  GitHub Repo: {}", repo);
+                            }
+                            if let Some(ref last_sync) = state.last_github_sync {
+                                println!("  Last GitHub Sync: {}", last_sync);
+                            }
+                            if let Some(ref last_parse) = state.last_markdown_parse {
+                                println!("  Last Parse: {}", last_parse);
+                            }
+                            if state.conflict_count > 0 {
+                                println!("  {} {} conflicts", "⚠".yellow(), state.conflict_count);
+                            } else {
+                                println!("  {} No conflicts", "✓".green());
+                            }
+                        }
+                        Ok(None) => {
+                            println!("{} Roadmap not initialized", "Status:".yellow());
+                            println!("Run 'deciduous roadmap init' to get started");
+                        }
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    }
+
+                    // Show item counts from database
+                    match db.get_all_roadmap_items() {
+                        Ok(items) => {
+                            let with_issues = items
+                                .iter()
+                                .filter(|i| i.github_issue_number.is_some())
+                                .count();
+                            let with_outcomes =
+                                items.iter().filter(|i| i.outcome_node_id.is_some()).count();
+                            let completed = items
+                                .iter()
+                                .filter(|i| i.checkbox_state == "checked")
+                                .count();
+
+                            println!("\n{}", "Items:".cyan());
+                            println!("  Total: {}", items.len());
+                            println!("  With GitHub Issues: {}", with_issues);
+                            println!("  With Outcome Links: {}", with_outcomes);
+                            println!("  Completed: {}", completed);
+                        }
+                        Err(_) => {
+                            println!("\n{} No items in database yet", "Items:".dimmed());
+                        }
+                    }
+
+                    // Milestone progress from ROADMAP.md (level-2 sections)
+                    if let Ok(parsed) = parse_roadmap(&roadmap_path) {
+                        let groups = milestone_groups(&parsed.sections);
+                        if !groups.is_empty() {
+                            println!("\n{}", "Milestones:".cyan());
+                        }
+                        for (parent, children) in groups {
+                            let done = children
+                                .iter()
+                                .filter(|c| section_board_status(c) == BoardStatus::Done)
+                                .count();
+                            let total = children.len();
+                            let pct = done * 100 / total;
+                            let marker = if done == total {
+                                "✓".green()
+                            } else {
+                                "○".yellow()
+                            };
+                            println!(
+                                "  {} {} {}/{} ({}%)",
+                                marker, parent.title, done, total, pct
+                            );
+                        }
+                    }
+                }
+
+                RoadmapAction::Check {
+                    path: _,
+                    incomplete,
+                    complete,
+                    close_issues,
+                    repo,
+                } => {
+                    // Get all roadmap items from database
+                    let items = match db.get_all_roadmap_items() {
+                        Ok(i) => i,
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    };
+
+                    if items.is_empty() {
+                        println!("{} No roadmap items in database", "Status:".yellow());
+                        println!("Run 'deciduous roadmap init' first");
+                        return;
+                    }
+
+                    // Check completion for each item
+                    let mut complete_count = 0;
+                    let mut incomplete_count = 0;
+                    let mut results: Vec<(String, bool, bool, bool, bool)> = Vec::new();
+
+                    for item in &items {
+                        match db.check_roadmap_item_completion(item.id) {
+                            Ok((is_complete, has_outcome, issue_closed)) => {
+                                let checkbox_checked = item.checkbox_state == "checked";
+
+                                if is_complete && checkbox_checked {
+                                    complete_count += 1;
+                                } else {
+                                    incomplete_count += 1;
+                                }
+
+                                results.push((
+                                    item.title.clone(),
+                                    is_complete && checkbox_checked,
+                                    checkbox_checked,
+                                    has_outcome,
+                                    issue_closed,
+                                ));
+                            }
+                            Err(e) => {
+                                eprintln!("{} Checking {}: {}", "Warning:".yellow(), item.title, e);
+                            }
+                        }
+                    }
+
+                    // Print header
+                    println!("{}", "Roadmap Completion Audit".cyan().bold());
+                    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+                    println!();
+
+                    // Print results based on filters
+                    for (title, is_complete, checkbox, outcome, issue) in &results {
+                        // Apply filters
+                        if incomplete && *is_complete {
+                            continue;
+                        }
+                        if complete && !*is_complete {
+                            continue;
+                        }
+
+                        let status_icon = if *is_complete {
+                            "✓".green()
+                        } else {
+                            "○".yellow()
+                        };
+
+                        let checkbox_icon = if *checkbox {
+                            "☑".green()
+                        } else {
+                            "☐".dimmed()
+                        };
+                        let outcome_icon = if *outcome {
+                            "⚡".green()
+                        } else {
+                            "⚡".dimmed()
+                        };
+                        let issue_icon = if *issue {
+                            "🔒".green()
+                        } else {
+                            "🔓".dimmed()
+                        };
+
+                        println!(
+                            "{} {} {} {} {}",
+                            status_icon,
+                            checkbox_icon,
+                            outcome_icon,
+                            issue_icon,
+                            truncate(title, 60)
+                        );
+                    }
+
+                    // Print summary
+                    println!();
+                    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+                    println!();
+                    println!("{}", "Legend:".dimmed());
+                    println!(
+                        "  {} = checkbox checked    {} = outcome linked    {} = issue closed",
+                        "☑".green(),
+                        "⚡".green(),
+                        "🔒".green()
+                    );
+                    println!();
+                    println!("{}", "Summary:".cyan());
+                    println!("  {} {} complete", "✓".green(), complete_count);
+                    println!("  {} {} incomplete", "○".yellow(), incomplete_count);
+                    println!("  {} total items", items.len());
+
+                    if incomplete_count > 0 {
+                        println!();
+                        println!(
+                            "{} Completion requires: checkbox ☑ AND outcome ⚡ AND issue closed 🔒",
+                            "Note:".dimmed()
+                        );
+                    }
+
+                    let ready_to_check = db
+                        .get_roadmap_items_ready_to_check()
+                        .map(|r| r.len())
+                        .unwrap_or(0);
+                    if ready_to_check > 0 {
+                        println!();
+                        println!(
+                            "{} {} unchecked items have completed outcomes; run 'deciduous roadmap reconcile'",
+                            "Note:".dimmed(),
+                            ready_to_check
+                        );
+                    }
+
+                    if close_issues {
+                        close_completed_roadmap_issues(&db, repo);
+                    }
+                }
+
+                RoadmapAction::Reconcile {
+                    path,
+                    repo,
+                    execute,
+                } => {
+                    let roadmap_path = path.unwrap_or_else(|| PathBuf::from("ROADMAP.md"));
+                    reconcile_roadmap(&db, &roadmap_path, repo, !execute);
+                }
+
+                RoadmapAction::Hook { action } => match action {
+                    RoadmapHookAction::Install { strict, append } => {
+                        let hook_path = git_hook_path("pre-commit");
+                        let existing = std::fs::read_to_string(&hook_path).ok();
+                        let content = match add_hook_block(existing.as_deref(), strict, append) {
+                            Ok(Some(c)) => c,
+                            Ok(None) => {
+                                println!(
+                                    "{} Roadmap hook already installed in {}",
+                                    "Info:".cyan(),
+                                    hook_path.display()
+                                );
+                                return;
+                            }
+                            Err(e) => {
+                                eprintln!("{} {}", "Error:".red(), e);
+                                std::process::exit(1);
+                            }
+                        };
+                        write_hook(&hook_path, &content);
+                        println!(
+                            "{} Installed roadmap hook in {}{}",
+                            "Success:".green(),
+                            hook_path.display(),
+                            if strict { " (strict)" } else { "" }
+                        );
+                    }
+                    RoadmapHookAction::Uninstall => {
+                        let hook_path = git_hook_path("pre-commit");
+                        let removed = std::fs::read_to_string(&hook_path)
+                            .ok()
+                            .and_then(|c| remove_hook_block(&c));
+                        let result = match removed {
+                            None => {
+                                println!("{} Roadmap hook not installed", "Info:".cyan());
+                                return;
+                            }
+                            Some(rest) if rest.is_empty() => std::fs::remove_file(&hook_path),
+                            Some(rest) => std::fs::write(&hook_path, rest),
+                        };
+                        if let Err(e) = result {
+                            eprintln!("{} Updating {}: {}", "Error:".red(), hook_path.display(), e);
+                            std::process::exit(1);
+                        }
+                        println!(
+                            "{} Removed roadmap hook from {}",
+                            "Success:".green(),
+                            hook_path.display()
+                        );
+                    }
+                    RoadmapHookAction::Check {
+                        path,
+                        strict,
+                        watch,
+                    } => {
+                        let roadmap_path = path.unwrap_or_else(|| PathBuf::from("ROADMAP.md"));
+                        if watch {
+                            watch_roadmap_drift(&db, &roadmap_path);
+                        } else if !check_roadmap_drift(&db, &roadmap_path) && strict {
+                            std::process::exit(1);
+                        }
+                    }
+                },
+
+                RoadmapAction::Archive {
+                    path,
+                    archive,
+                    repo,
+                    execute,
+                } => {
+                    let roadmap_path = path.unwrap_or_else(|| PathBuf::from("ROADMAP.md"));
+                    let archive_path = archive
+                        .unwrap_or_else(|| roadmap_path.with_file_name("ROADMAP_ARCHIVE.md"));
+                    archive_roadmap(&db, &roadmap_path, &archive_path, repo, !execute);
+                }
+            }
+        }
+
+        Command::Trace { action } => {
+            match action {
+                TraceAction::Start { cwd, command } => {
+                    let session_id = uuid::Uuid::new_v4().to_string();
+                    let working_dir = cwd.map(|p| p.to_string_lossy().to_string()).or_else(|| {
+                        std::env::current_dir()
+                            .ok()
+                            .map(|p| p.to_string_lossy().to_string())
+                    });
+
+                    // Get git branch
+                    let git_branch = std::process::Command::new("git")
+                        .args(["branch", "--show-current"])
+                        .output()
+                        .ok()
+                        .filter(|o| o.status.success())
+                        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string());
+
+                    match db.start_trace_session(
+                        &session_id,
+                        working_dir.as_deref(),
+                        git_branch.as_deref(),
+                        command.as_deref(),
+                    ) {
+                        Ok(_id) => {
+                            // Output JSON for the interceptor to parse
+                            println!(r#"{{"session_id": "{}"}}"#, session_id);
+                        }
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+
+                TraceAction::End {
+                    session_id,
+                    summary,
+                } => match db.end_trace_session(&session_id, summary.as_deref()) {
+                    Ok(()) => {
+                        println!("{} Trace session ended", "Success:".green());
+                        let _ = link_session_subagents(&db, &session_id);
+                        if let Ok(linked) = link_session_commits(&db, &session_id, false) {
+                            if !linked.is_empty() {
+                                println!("  Linked {} commit(s) to spans", linked.len());
+                            }
+                        }
+                        session_end_hook(&db, &session_id);
+                        apply_trace_retention(&db);
+                    }
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                },
+
+                TraceAction::Record {
+                    session,
+                    span_id: existing_span_id,
+                    stdin,
+                } => {
+                    if !stdin {
+                        eprintln!("{} --stdin is required", "Error:".red());
+                        std::process::exit(1);
+                    }
+
+                    let mut input = String::new();
+                    if let Err(e) = std::io::stdin().read_line(&mut input) {
+                        eprintln!("{} Reading stdin: {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+
+                    // Parse span data from JSON
+                    let span_data: serde_json::Value = match serde_json::from_str(&input) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            eprintln!("{} Parsing JSON: {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    };
+
+                    // Secrets are stripped before anything reaches the database. An
+                    // invalid custom pattern falls back to the built-in rules, since
+                    // the interceptor has nowhere to show an error.
+                    let config = Config::load();
+                    let redactor = Redactor::from_config(&config.trace.redact)
+                        .unwrap_or_default()
+                        .with_capture(CaptureFilter::from_config(&config.trace.capture));
+
+                    let model = span_data["model"].as_str();
+                    let user_preview = redactor.redact_opt(span_data["user_preview"].as_str());
+                    let user_preview = user_preview.as_deref();
+
+                    // Use existing span or create new one
+                    let span_id = if let Some(sid) = existing_span_id {
+                        // Update model if provided (span-start might not have had it)
+                        if model.is_some() {
+                            let _ = db.update_trace_span_model(sid, model);
+                        }
+                        sid
+                    } else {
+                        // Create new span (legacy single-call mode)
+                        match db.create_trace_span(&session, model, user_preview) {
+                            Ok(id) => id,
+                            Err(e) => {
+                                eprintln!("{} Creating span: {}", "Error:".red(), e);
+                                std::process::exit(1);
+                            }
+                        }
+                    };
+
+                    // Complete span if response data is included
+                    if span_data.get("duration_ms").is_some() {
+                        let duration_ms = span_data["duration_ms"].as_i64().unwrap_or(0) as i32;
+                        let request_id = span_data["request_id"].as_str();
+                        let stop_reason = span_data["stop_reason"].as_str();
+                        let input_tokens = span_data["input_tokens"].as_i64().map(|v| v as i32);
+                        let output_tokens = span_data["output_tokens"].as_i64().map(|v| v as i32);
+                        let cache_read = span_data["cache_read"].as_i64().map(|v| v as i32);
+                        let cache_write = span_data["cache_write"].as_i64().map(|v| v as i32);
+                        let thinking_preview =
+                            redactor.redact_opt(span_data["thinking_preview"].as_str());
+                        let response_preview =
+                            redactor.redact_opt(span_data["response_preview"].as_str());
+                        let tool_names = span_data["tool_names"].as_str();
+
+                        if let Err(e) = db.complete_trace_span(
+                            span_id,
+                            duration_ms,
+                            request_id,
+                            stop_reason,
+                            input_tokens,
+                            output_tokens,
+                            cache_read,
+                            cache_write,
+                            thinking_preview.as_deref(),
+                            response_preview.as_deref(),
+                            tool_names,
+                            user_preview,
+                        ) {
+                            eprintln!("{} Completing span: {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+
+                        // Streamed responses report how they arrived
+                        if let Some(chunks) = span_data["chunk_count"].as_i64() {
+                            let _ = db.set_span_stream(
+                                span_id,
+                                chunks as i32,
+                                span_data["first_token_ms"].as_i64().map(|v| v as i32),
+                                span_data["stream_complete"].as_bool().unwrap_or(true),
+                            );
+                        }
+
+                        // Store full content if provided
+                        if let Some(thinking) = span_data["thinking"].as_str() {
+                            let _ = store_content(
+                                &db, &redactor, span_id, "thinking", thinking, None, None,
+                            );
+                        }
+                        if let Some(response) = span_data["response"].as_str() {
+                            let _ = store_content(
+                                &db, &redactor, span_id, "response", response, None, None,
+                            );
+                        }
+                        if let Some(tools) = span_data["tool_calls"].as_array() {
+                            for tool in tools {
+                                let tool_name = tool["name"].as_str();
+                                let tool_use_id = tool["id"].as_str();
+                                if let Some(input) = tool["input"].as_str() {
+                                    let _ = store_content(
+                                        &db,
+                                        &redactor,
+                                        span_id,
+                                        "tool_input",
+                                        input,
+                                        tool_name,
+                                        tool_use_id,
+                                    );
+                                }
+                                if let Some(output) = tool["output"].as_str() {
+                                    let _ = store_content(
+                                        &db,
+                                        &redactor,
+                                        span_id,
+                                        "tool_output",
+                                        output,
+                                        tool_name,
+                                        tool_use_id,
+                                    );
+                                }
+                                if let Some((server, name)) =
+                                    tool_name.and_then(mcp_trace::split_tool_name)
+                                {
+                                    let call = mcp_trace::McpCapture {
+                                        id: tool_use_id.map(str::to_string),
+                                        server: server.to_string(),
+                                        tool: name.to_string(),
+                                        input: tool["input"].as_str().map(str::to_string),
+                                        result: tool["output"].as_str().map(str::to_string),
+                                        ..Default::default()
+                                    };
+                                    let _ = mcp_trace::store(&db, &redactor, span_id, &call);
+                                }
+                            }
+                        }
+
+                        // Store system prompt if provided (captured from request)
+                        if let Some(system_prompt) = span_data["system_prompt"].as_str() {
+                            let _ = store_content(
+                                &db,
+                                &redactor,
+                                span_id,
+                                "system",
+                                system_prompt,
+                                None,
+                                None,
+                            );
+                        }
+
+                        // Store tool definitions if provided (captured from request)
+                        if let Some(tool_defs) = span_data["tool_definitions"].as_array() {
+                            let tool_defs_json =
+                                serde_json::to_string(tool_defs).unwrap_or_default();
+                            if !tool_defs_json.is_empty() && tool_defs_json != "[]" {
+                                let _ = store_content(
+                                    &db,
+                                    &redactor,
+                                    span_id,
+                                    "tool_definitions",
+                                    &tool_defs_json,
+                                    None,
+                                    None,
+                                );
+                            }
+                        }
+
+                        // Store tool results if provided (from previous tool calls in request)
+                        if let Some(tool_results) = span_data["tool_results"].as_array() {
+                            for result in tool_results {
+                                let tool_use_id = result["tool_use_id"].as_str();
+                                if let Some(content) = result["content"].as_str() {
+                                    let is_error = result["is_error"].as_bool().unwrap_or(false);
+                                    let content_type = if is_error {
+                                        "tool_error"
+                                    } else {
+                                        "tool_output"
+                                    };
+                                    let _ = store_content(
+                                        &db,
+                                        &redactor,
+                                        span_id,
+                                        content_type,
+                                        content,
+                                        None,
+                                        tool_use_id,
+                                    );
+                                }
+                            }
+                        }
+                    }
+
+                    // Check the trace budget once the span has usage recorded
+                    let budget = if span_data.get("duration_ms").is_some() {
+                        deciduous::pricing::check_budget(&db, &config, &session)
+                            .ok()
+                            .flatten()
+                    } else {
+                        None
+                    };
+
+                    // Output JSON for the interceptor
+                    match budget {
+                        Some(alert) => println!(
+                            "{}",
+                            serde_json::json!({
+                                "span_id": span_id,
+                                "budget": {"message": alert.message, "block": alert.block},
+                            })
+                        ),
+                        None => println!(r#"{{"span_id": {}}}"#, span_id),
+                    }
+                }
+
+                TraceAction::SpanStart {
+                    session,
+                    model,
+                    user_preview,
+                } => {
+                    // Create a pending span and return its ID
+                    // This enables active span tracking - the interceptor sets
+                    // DECIDUOUS_TRACE_SPAN so nodes created during the span
+                    // can be automatically linked
+                    let redactor =
+                        Redactor::from_config(&Config::load().trace.redact).unwrap_or_default();
+                    let user_preview = redactor.redact_opt(user_preview.as_deref());
+                    match db.create_trace_span(&session, model.as_deref(), user_preview.as_deref())
+                    {
+                        Ok(span_id) => {
+                            // Output JSON for the interceptor to parse
+                            println!(r#"{{"span_id": {}}}"#, span_id);
+                        }
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+
+                TraceAction::Sessions { limit, linked } => {
+                    let sessions = if linked {
+                        db.get_linked_trace_sessions(limit)
+                    } else {
+                        db.get_trace_sessions(limit)
+                    };
+
+                    match sessions {
+                        Ok(sessions) => {
+                            if sessions.is_empty() {
+                                println!("No trace sessions found.");
+                                return;
+                            }
+
+                            println!(
+                                "{} ({} sessions)\n",
+                                "Trace Sessions".cyan(),
+                                sessions.len()
+                            );
+
+                            let prices = PriceTable::from_config(&Config::load());
+                            for session in &sessions {
+                                let status = if session.ended_at.is_some() {
+                                    "ended".dimmed()
+                                } else {
+                                    "active".green()
+                                };
+
+                                let linked_str = match session.linked_node_id {
+                                    Some(id) => format!("→ node #{}", id).yellow().to_string(),
+                                    None => "".to_string(),
+                                };
+
+                                let tokens = format!(
+                                    "{}↓ {}↑",
+                                    session.total_input_tokens, session.total_output_tokens
+                                );
+                                let cost = db
+                                    .get_trace_spans(&session.session_id)
+                                    .map(|spans| prices.total_cost(&spans))
+                                    .unwrap_or(0.0);
+
+                                println!(
+                                    "  {} [{}] {} {} {} {}",
+                                    &session.session_id[..8],
+                                    status,
+                                    tokens.dimmed(),
+                                    format_cost(cost).green(),
+                                    session.command.as_deref().unwrap_or(""),
+                                    linked_str
+                                );
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+
+                TraceAction::Tail {
+                    session,
+                    lines,
+                    interval,
+                } => {
+                    trace_tail(&db, session.as_deref(), lines, interval);
+                }
+
+                TraceAction::Spans {
+                    session_id,
+                    show_thinking,
+                } => match db.get_trace_spans(&session_id) {
+                    Ok(spans) => {
+                        if spans.is_empty() {
+                            println!("No spans found for session {}.", &session_id[..8]);
+                            return;
+                        }
+
+                        let prices = PriceTable::from_config(&Config::load());
+                        println!(
+                            "{} ({} spans, {})\n",
+                            format!("Session {}", &session_id[..8]).cyan(),
+                            spans.len(),
+                            format_cost(prices.total_cost(&spans))
+                        );
+
+                        let parents = link_session_subagents(&db, &session_id).unwrap_or_default();
+                        let span_ids: Vec<i32> = spans.iter().map(|s| s.id).collect();
+                        let annotations =
+                            db.get_annotations_for_spans(&span_ids).unwrap_or_default();
+                        for (span, depth) in tree_order(&spans, &parents) {
+                            let indent = if depth > 0 {
+                                format!("{}└ ", "  ".repeat(depth - 1))
+                            } else {
+                                String::new()
+                            };
+                            let duration = span
+                                .duration_ms
+                                .map(|d| format!("{}ms", d))
+                                .unwrap_or_else(|| "...".to_string());
+
+                            let tokens = match (span.input_tokens, span.output_tokens) {
+                                (Some(i), Some(o)) => format!("{}↓ {}↑", i, o),
+                                _ => "".to_string(),
+                            };
+
+                            let linked_str = match span.linked_node_id {
+                                Some(id) => format!("→ #{}", id).yellow().to_string(),
+                                None => "".to_string(),
+                            };
+
+                            let cost = prices.span_cost(span).map(format_cost).unwrap_or_default();
+
+                            println!(
+                                "  {}#{} [{}] {} {} {} {}",
+                                indent.dimmed(),
+                                span.id,
+                                duration.dimmed(),
+                                tokens.dimmed(),
+                                cost.green(),
+                                span.model.as_deref().unwrap_or(""),
+                                linked_str
+                            );
+
+                            let pad = " ".repeat(indent.chars().count());
+                            if let Some(ref tools) = span.tool_names {
+                                println!("      {}tools: {}", pad, tools.dimmed());
+                            }
+
+                            if show_thinking {
+                                if let Some(ref thinking) = span.thinking_preview {
+                                    let preview = if thinking.len() > 100 {
+                                        format!("{}...", &thinking[..100])
+                                    } else {
+                                        thinking.clone()
+                                    };
+                                    println!("      {}thinking: {}", pad, preview.dimmed());
+                                }
+                            }
+
+                            for a in annotations.get(&span.id).into_iter().flatten() {
+                                println!("      {}{} {}", pad, "note:".magenta(), a.note);
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                },
+
+                TraceAction::Annotate {
+                    span_id,
+                    note,
+                    delete,
+                } => {
+                    trace_annotate(&db, span_id, note.as_deref(), delete);
+                }
+
+                TraceAction::Show {
+                    span_id,
+                    thinking,
+                    response,
+                    tools,
+                } => {
+                    let show_all = !thinking && !response && !tools;
+
+                    match db.get_trace_span(span_id) {
+                        Ok(Some(span)) => {
+                            println!("{}", format!("Span #{}", span_id).cyan());
+                            println!("  Session: {}", &span.session_id[..8]);
+                            if let Some(model) = &span.model {
+                                println!("  Model: {}", model);
+                            }
+                            if let Some(duration) = span.duration_ms {
+                                println!("  Duration: {}ms", duration);
+                            }
+                            if let (Some(i), Some(o)) = (span.input_tokens, span.output_tokens) {
+                                println!("  Tokens: {}↓ {}↑", i, o);
+                            }
+                            if let Some(cost) =
+                                PriceTable::from_config(&Config::load()).span_cost(&span)
+                            {
+                                println!("  Cost: {}", format_cost(cost));
+                            }
+                            if let Ok(redactions) = db.get_trace_redactions(span_id) {
+                                if !redactions.is_empty() {
+                                    let list: Vec<String> = redactions
+                                        .iter()
+                                        .map(|r| {
+                                            format!("{} ×{} ({})", r.rule, r.count, r.content_type)
+                                        })
+                                        .collect();
+                                    println!("  Redacted: {}", list.join(", ").yellow());
+                                }
+                            }
+                            if let Ok(Some(stream)) = db.get_span_stream(span_id) {
+                                let first_token = stream
+                                    .first_token_ms
+                                    .map(|ms| format!(", first token after {}ms", ms))
+                                    .unwrap_or_default();
+                                let cut_off = if stream.complete {
+                                    String::new()
+                                } else {
+                                    format!(" {}", "(cut off before the end)".yellow())
+                                };
+                                println!(
+                                    "  Stream: {} chunks{}{}",
+                                    stream.chunk_count, first_token, cut_off
+                                );
+                            }
+                            if let Ok(commits) = db.get_commits_for_span(span_id) {
+                                for c in &commits {
+                                    println!(
+                                        "  Commit: {}",
+                                        c.commit_hash[..8.min(c.commit_hash.len())].yellow()
+                                    );
+                                }
+                            }
+                            if let Ok(annotations) = db.get_annotations_for_spans(&[span_id]) {
+                                for a in annotations.get(&span_id).into_iter().flatten() {
+                                    println!("  {} {}", "Note:".magenta(), a.note);
+                                }
+                            }
+                            println!();
+                        }
+                        Ok(None) => {
+                            eprintln!("{} Span #{} not found", "Error:".red(), span_id);
+                            std::process::exit(1);
+                        }
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    }
+
+                    // Get content
+                    match db.get_trace_content(span_id) {
+                        Ok(content) => {
+                            for item in &content {
+                                let show = show_all
+                                    || (thinking && item.content_type == "thinking")
+                                    || (response && item.content_type == "response")
+                                    || (tools
+                                        && (item.content_type == "tool_input"
+                                            || item.content_type == "tool_output"
+                                            || item.content_type.starts_with("mcp_")));
+
+                                if show {
+                                    let label = match item.content_type.as_str() {
+                                        "thinking" => "Thinking".magenta(),
+                                        "response" => "Response".green(),
+                                        "tool_input" => format!(
+                                            "Tool Input ({})",
+                                            item.tool_name.as_deref().unwrap_or("?")
+                                        )
+                                        .yellow(),
+                                        "tool_output" => format!(
+                                            "Tool Output ({})",
+                                            item.tool_name.as_deref().unwrap_or("?")
+                                        )
+                                        .cyan(),
+                                        mcp_trace::MCP_CALL => format!(
+                                            "MCP Call ({})",
+                                            item.tool_name.as_deref().unwrap_or("?")
+                                        )
+                                        .blue(),
+                                        mcp_trace::MCP_RESULT | mcp_trace::MCP_ERROR => format!(
+                                            "MCP {} ({})",
+                                            if item.content_type == mcp_trace::MCP_ERROR {
+                                                "Error"
+                                            } else {
+                                                "Result"
+                                            },
+                                            item.tool_name.as_deref().unwrap_or("?")
+                                        )
+                                        .blue(),
+                                        _ => item.content_type.clone().normal(),
+                                    };
+
+                                    println!("{}", label);
+                                    println!("{}", "─".repeat(60));
+                                    println!("{}", item.content);
+                                    println!();
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                        }
+                    }
+                }
+
+                TraceAction::Link {
+                    node_id,
+                    session,
+                    span,
+                } => {
+                    if session.is_none() && span.is_none() {
+                        eprintln!("{} Specify --session or --span", "Error:".red());
+                        std::process::exit(1);
+                    }
+
+                    if let Some(session_id) = session {
+                        match db.link_trace_session_to_node(&session_id, node_id) {
+                            Ok(()) => {
+                                println!(
+                                    "{} Linked session {} to node #{}",
+                                    "Success:".green(),
+                                    &session_id[..8],
+                                    node_id
+                                );
+                            }
+                            Err(e) => {
+                                eprintln!("{} {}", "Error:".red(), e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+
+                    if let Some(span_id) = span {
+                        match db.link_trace_span_to_node(span_id, node_id) {
+                            Ok(()) => {
+                                println!(
+                                    "{} Linked span #{} to node #{}",
+                                    "Success:".green(),
+                                    span_id,
+                                    node_id
+                                );
+                            }
+                            Err(e) => {
+                                eprintln!("{} {}", "Error:".red(), e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                }
+
+                TraceAction::Unlink { session, span } => {
+                    if session.is_none() && span.is_none() {
+                        eprintln!("{} Specify --session or --span", "Error:".red());
+                        std::process::exit(1);
+                    }
+
+                    if let Some(session_id) = session {
+                        match db.unlink_trace_session(&session_id) {
+                            Ok(()) => {
+                                println!(
+                                    "{} Unlinked session {}",
+                                    "Success:".green(),
+                                    &session_id[..8]
+                                );
+                            }
+                            Err(e) => {
+                                eprintln!("{} {}", "Error:".red(), e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+
+                    if let Some(span_id) = span {
+                        match db.unlink_trace_span(span_id) {
+                            Ok(()) => {
+                                println!("{} Unlinked span #{}", "Success:".green(), span_id);
+                            }
+                            Err(e) => {
+                                eprintln!("{} {}", "Error:".red(), e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                }
+
+                TraceAction::ExportOtlp {
+                    session,
+                    endpoint,
+                    headers,
+                    output,
+                } => {
+                    let trace_session = match db.get_trace_session(&session) {
+                        Ok(Some(s)) => s,
+                        Ok(None) => {
+                            eprintln!("{} Session {} not found", "Error:".red(), session);
+                            std::process::exit(1);
+                        }
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    };
+                    let spans = match db.get_trace_spans(&trace_session.session_id) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    };
+
+                    let prices = PriceTable::from_config(&Config::load());
+                    let payload = deciduous::otlp::session_to_otlp(&trace_session, &spans, &prices);
+
+                    if let Some(path) = output {
+                        let json = serde_json::to_string_pretty(&payload).unwrap_or_default();
+                        if let Err(e) = std::fs::write(&path, json) {
+                            eprintln!("{} Writing {}: {}", "Error:".red(), path.display(), e);
+                            std::process::exit(1);
+                        }
+                        println!(
+                            "{} Wrote {} spans to {}",
+                            "Success:".green(),
+                            spans.len() + 1,
+                            path.display()
+                        );
+                        return;
+                    }
+
+                    match deciduous::otlp::send(&endpoint, &payload, &headers) {
+                        Ok(()) => println!(
+                            "{} Exported session {} ({} spans) as trace {}",
+                            "Success:".green(),
+                            &trace_session.session_id[..8],
+                            spans.len() + 1,
+                            deciduous::otlp::trace_id(&trace_session.session_id)
+                        ),
+                        Err(e) => {
+                            eprintln!("{} Exporting to {}: {}", "Error:".red(), endpoint, e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+
+                TraceAction::Transcript {
+                    session_id,
+                    output,
+                    thinking,
+                } => {
+                    use deciduous::transcript::{session_transcript, TranscriptFormat};
+
+                    let (trace_session, spans, content) = load_trace_session(&db, &session_id);
+
+                    let format = output
+                        .as_deref()
+                        .map(TranscriptFormat::from_path)
+                        .unwrap_or(TranscriptFormat::Markdown);
+                    let prices = PriceTable::from_config(&Config::load());
+                    let rendered = session_transcript(
+                        &trace_session,
+                        &spans,
+                        &content,
+                        format,
+                        thinking,
+                        &prices,
+                    );
+
+                    match output {
+                        Some(path) => {
+                            if let Err(e) = std::fs::write(&path, rendered) {
+                                eprintln!("{} Writing {}: {}", "Error:".red(), path.display(), e);
+                                std::process::exit(1);
+                            }
+                            println!(
+                                "{} Wrote transcript of {} spans to {}",
+                                "Success:".green(),
+                                spans.len(),
+                                path.display()
+                            );
+                        }
+                        None => print!("{}", rendered),
+                    }
+                }
+
+                TraceAction::ExportShare { session_id, output } => {
+                    let (trace_session, spans, content) = load_trace_session(&db, &session_id);
+                    let parents = db
+                        .get_span_parents(&trace_session.session_id)
+                        .unwrap_or_default();
+                    let bundle = deciduous::trace_share::build_bundle(
+                        &trace_session,
+                        &spans,
+                        &parents,
+                        &content,
+                    );
+                    let json =
+                        serde_json::to_string_pretty(&bundle).expect("bundle serializes to JSON");
+
+                    match output {
+                        Some(path) => {
+                            if let Err(e) = std::fs::write(&path, json) {
+                                eprintln!("{} Writing {}: {}", "Error:".red(), path.display(), e);
+                                std::process::exit(1);
+                            }
+                            println!(
+                                "{} Wrote anonymized bundle of {} spans to {}",
+                                "Success:".green(),
+                                spans.len(),
+                                path.display()
+                            );
+                        }
+                        None => println!("{}", json),
+                    }
+                }
+
+                TraceAction::Writeup {
+                    session_id,
+                    output,
+                    dot,
+                } => {
+                    trace_writeup(&db, &session_id, output, dot);
+                }
+
+                TraceAction::Replay {
+                    session_id,
+                    model,
+                    max_tokens,
+                    limit,
+                    dry_run,
+                } => {
+                    trace_replay(
+                        &db,
+                        &session_id,
+                        model.as_deref(),
+                        max_tokens,
+                        limit,
+                        dry_run,
+                    );
+                }
+
+                TraceAction::Suggest {
+                    session,
+                    llm,
+                    model,
+                    yes,
+                } => {
+                    trace_suggest(&db, session.as_deref(), llm.then_some(model.as_str()), yes);
+                }
+
+                TraceAction::Diff { a, b } => {
+                    trace_diff(&db, &a, &b);
+                }
+
+                TraceAction::Commits {
+                    session,
+                    update_nodes,
+                    commit,
+                } => {
+                    trace_commits(&db, session.as_deref(), update_nodes, commit.as_deref());
+                }
+
+                TraceAction::ImportClaude { paths } => {
+                    trace_import_claude(&db, &paths);
+                }
+
+                TraceAction::Costs { by, days, session } => {
+                    trace_costs(&db, &by, days, session.as_deref());
+                }
+
+                TraceAction::Tools { days, session } => {
+                    trace_tools(&db, days, session.as_deref());
+                }
+
+                TraceAction::Prune {
+                    days,
+                    max_db_size_mb,
+                    keep_linked,
+                    no_keep_linked,
+                    dry_run,
+                } => {
+                    let retention = Config::load().trace.retention;
+                    let (days, max_db_size_mb) = match (days, max_db_size_mb) {
+                        (None, None) if retention.is_enabled() => {
+                            (retention.max_age_days, retention.max_db_size_mb)
+                        }
+                        (None, None) => (Some(30), None),
+                        limits => limits,
+                    };
+                    let keep_linked = match (keep_linked, no_keep_linked) {
+                        (true, _) => true,
+                        (_, true) => false,
+                        _ => retention.keep_linked,
+                    };
+
+                    let session_ids = match db.trace_sessions_to_prune(
+                        days,
+                        max_db_size_mb.map(|mb| mb * 1024 * 1024),
+                        keep_linked,
+                    ) {
+                        Ok(ids) => ids,
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    };
+
+                    if dry_run {
+                        match db.count_trace_sessions_data(&session_ids) {
+                            Ok((sessions, spans, content)) => {
+                                println!(
+                                    "{} Would prune {} sessions, {} spans, {} content items{}",
+                                    "[DRY RUN]".yellow(),
+                                    sessions,
+                                    spans,
+                                    content,
+                                    if keep_linked { " (keeping linked)" } else { "" }
+                                );
+                            }
+                            Err(e) => {
+                                eprintln!("{} {}", "Error:".red(), e);
+                                std::process::exit(1);
+                            }
+                        }
+                        return;
+                    }
+
+                    match db.delete_trace_sessions(&session_ids) {
+                        Ok((sessions, spans, content)) => {
+                            println!(
+                                "{} Pruned {} sessions, {} spans, {} content items",
+                                "Success:".green(),
+                                sessions,
+                                spans,
+                                content
+                            );
+                        }
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+        }
+
+        Command::Proxy {
+            command,
+            auto_link,
+            link_node,
+            listen,
+        } => {
+            if command.is_empty() && listen.is_none() {
+                eprintln!("{} No command specified", "Error:".red());
+                std::process::exit(1);
+            }
+
+            // Resolve the node to link before anything is started
+            let link_target = match link_node {
+                Some(id) => match db.get_node_by_id(id) {
+                    Ok(Some(node)) => Some(node),
+                    Ok(None) => {
+                        eprintln!("{} Node {} not found", "Error:".red(), id);
+                        std::process::exit(1);
+                    }
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                },
+                None => None,
+            };
+
+            // Load the CA up front so a bad CA dir fails before a session is started
+            let listen_ca = listen.as_ref().map(|_| {
+                let ca_dir = match deciduous::proxy::default_ca_dir() {
+                    Ok(dir) => dir,
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                };
+                match deciduous::proxy::LocalCa::load_or_create(&ca_dir) {
+                    Ok(ca) => ca,
+                    Err(e) => {
+                        eprintln!(
+                            "{} Loading proxy CA from {}: {}",
+                            "Error:".red(),
+                            ca_dir.display(),
+                            e
+                        );
+                        std::process::exit(1);
+                    }
+                }
+            });
+
+            // Ensure the embedded interceptor is installed
+            let interceptor_path = if listen.is_some() {
+                PathBuf::new()
+            } else {
+                match deciduous::interceptor::ensure_interceptor_installed() {
+                    Ok(path) => path,
+                    Err(e) => {
+                        eprintln!("{} Installing trace interceptor: {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                }
+            };
+
+            // Check if debug output is enabled (default: silent to avoid TUI interference)
+            let trace_debug = std::env::var("DECIDUOUS_TRACE_DEBUG")
+                .map(|v| v == "1" || v == "true")
+                .unwrap_or(false);
+
+            // Generate session ID and start trace session
+            let session_id = uuid::Uuid::new_v4().to_string();
+            let working_dir = std::env::current_dir()
+                .ok()
+                .map(|p| p.to_string_lossy().to_string());
+            let git_branch = std::process::Command::new("git")
+                .args(["branch", "--show-current"])
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string());
+            let cmd_str = match &listen {
+                Some(addr) => format!("proxy --listen {}", addr),
+                None => command.join(" "),
+            };
+
+            match db.start_trace_session(
+                &session_id,
+                working_dir.as_deref(),
+                git_branch.as_deref(),
+                Some(&cmd_str),
+            ) {
+                Ok(_) => {
+                    if trace_debug {
+                        println!(
+                            "{} Started trace session {}",
+                            "Trace:".cyan(),
+                            &session_id[..8]
+                        );
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{} Starting trace session: {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            }
+
+            // Link to the given node, else the focused goal, else (with
+            // --auto-link) pick a goal for this branch
+            let link_target = match link_target {
+                Some(node) => Some(node),
+                None if auto_link => db.get_all_nodes().ok().and_then(|nodes| {
+                    deciduous::focus::active_goal(&db, &nodes, git_branch.as_deref())
+                }),
+                None => deciduous::focus::active(&db, Local::now()),
+            };
+            if let Some(node) = link_target {
+                if let Err(e) = db.link_trace_session_to_node(&session_id, node.id) {
+                    if trace_debug {
+                        eprintln!(
+                            "{} Linking to {} #{}: {}",
+                            "Warning:".yellow(),
+                            node.node_type,
+                            node.id,
+                            e
+                        );
+                    }
+                } else if trace_debug {
+                    println!(
+                        "  {} Linked to {} #{}: {}",
+                        "→".yellow(),
+                        node.node_type,
+                        node.id,
+                        truncate(&node.title, 50)
+                    );
+                }
+            }
+
+            if let (Some(addr), Some(ca)) = (listen, listen_ca) {
+                let addr = if addr.contains(':') {
+                    addr
+                } else {
+                    format!("127.0.0.1:{}", addr)
+                };
+                println!(
+                    "{} Listening on {} (session {})",
+                    "Proxy:".cyan(),
+                    addr,
+                    &session_id[..8]
+                );
+                println!("\nPoint clients at it and trust the local CA:");
+                println!("  export HTTPS_PROXY=http://{}", addr);
+                println!("  export NODE_EXTRA_CA_CERTS={}", ca.cert_path().display());
+                println!(
+                    "  export SSL_CERT_FILE={}   # curl, Python, Go (replaces system roots)",
+                    ca.cert_path().display()
+                );
+                println!(
+                    "\nAnthropic and OpenAI API calls are recorded; other hosts pass through untouched."
+                );
+                println!(
+                    "Stop with Ctrl+C, then: deciduous trace end {}\n",
+                    session_id
+                );
+
+                if let Err(e) = deciduous::proxy::run(&addr, ca, db, &session_id, Config::load()) {
+                    eprintln!("{} Proxy on {}: {}", "Error:".red(), addr, e);
+                    std::process::exit(1);
+                }
+                return;
+            }
+
+            // Build environment with NODE_OPTIONS
+            let node_options = format!("--require {}", interceptor_path.to_string_lossy());
+            let existing_node_options = std::env::var("NODE_OPTIONS").unwrap_or_default();
+            let full_node_options = if existing_node_options.is_empty() {
+                node_options
+            } else {
+                format!("{} {}", existing_node_options, node_options)
+            };
+
+            // Get path to this deciduous binary for the interceptor
+            let deciduous_bin = std::env::current_exe()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|_| "deciduous".to_string());
+
+            // Spawn child process
+            let (cmd, args) = command.split_first().unwrap();
+            let mut child = match std::process::Command::new(cmd)
+                .args(args)
+                .env("NODE_OPTIONS", &full_node_options)
+                .env("DECIDUOUS_TRACE_SESSION", &session_id)
+                .env("DECIDUOUS_BIN", &deciduous_bin)
+                .spawn()
+            {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("{} Spawning command '{}': {}", "Error:".red(), cmd, e);
+                    let _ = db.end_trace_session(&session_id, Some("Failed to spawn"));
+                    std::process::exit(1);
+                }
+            };
+
+            // Wait for child to complete
+            let exit_status = match child.wait() {
+                Ok(status) => status,
+                Err(e) => {
+                    eprintln!("{} Waiting for command: {}", "Error:".red(), e);
+                    let _ = db.end_trace_session(&session_id, Some("Wait failed"));
+                    std::process::exit(1);
+                }
+            };
+
+            // End trace session
+            let summary = if exit_status.success() {
+                format!("Completed successfully ({})", cmd_str)
+            } else {
+                format!(
+                    "Exited with code {} ({})",
+                    exit_status.code().unwrap_or(-1),
+                    cmd_str
+                )
+            };
+
+            if let Err(e) = db.end_trace_session(&session_id, Some(&summary)) {
+                eprintln!("{} Ending trace session: {}", "Warning:".yellow(), e);
+            }
+            if let Err(e) = link_session_subagents(&db, &session_id) {
+                eprintln!("{} Linking subagent spans: {}", "Warning:".yellow(), e);
+            }
+            if let Err(e) = link_session_commits(&db, &session_id, false) {
+                eprintln!("{} Linking commits: {}", "Warning:".yellow(), e);
+            }
+            session_end_hook(&db, &session_id);
+            apply_trace_retention(&db);
+
+            if let Ok(Some(alert)) =
+                deciduous::pricing::check_budget(&db, &Config::load(), &session_id)
+            {
+                eprintln!("{} {}", "Warning:".yellow(), alert.message);
+            }
+
+            // Get session stats (only if debug enabled)
+            if trace_debug {
+                if let Ok(Some(session)) = db.get_trace_session(&session_id) {
+                    println!("\n{} Session {} ended", "Trace:".cyan(), &session_id[..8]);
+                    println!(
+                        "  Tokens: {}↓ {}↑ (cache: {}r {}w)",
+                        session.total_input_tokens,
+                        session.total_output_tokens,
+                        session.total_cache_read,
+                        session.total_cache_write
+                    );
+
+                    if let Ok(spans) = db.get_trace_spans(&session_id) {
+                        println!("  Spans: {}", spans.len());
+                    }
+
+                    if let Some(node_id) = session.linked_node_id {
+                        println!("  Linked: node #{}", node_id);
+                    }
+                }
+            }
+
+            // Exit with same code as child
+            std::process::exit(exit_status.code().unwrap_or(1));
+        }
+    }
+}
+
+/// Marks a logged invocation finished with exit code 0 when `main` returns;
+/// `process::exit` skips it, so failed commands are left without one
+struct CommandLogGuard {
+    id: i32,
+    started: std::time::Instant,
+}
+
+impl Drop for CommandLogGuard {
+    fn drop(&mut self) {
+        let elapsed = self.started.elapsed().as_millis() as i32;
+        if let Ok(db) = Database::open() {
+            let _ = db.complete_command(self.id, 0, None, None, elapsed);
+        }
+    }
+}
+
+/// Fire the `on_session_end` hook with the ended session
+fn session_end_hook(db: &Database, session_id: &str) {
+    let hooks = Config::load().hooks;
+    if hooks
+        .command_for(deciduous::lifecycle::ON_SESSION_END)
+        .is_none()
+    {
+        return;
+    }
+    if let Ok(Some(session)) = db.get_trace_session(session_id) {
+        fire_hook(
+            &hooks,
+            deciduous::lifecycle::ON_SESSION_END,
+            serde_json::json!({ "session": session }),
+        );
+    }
+}
+
+/// Prune traces according to [trace.retention], if any limit is configured
+fn apply_trace_retention(db: &Database) {
+    let retention = Config::load().trace.retention;
+    if !retention.is_enabled() {
+        return;
+    }
+    let pruned = db
+        .trace_sessions_to_prune(
+            retention.max_age_days,
+            retention.max_db_size_mb.map(|mb| mb * 1024 * 1024),
+            retention.keep_linked,
+        )
+        .and_then(|ids| db.delete_trace_sessions(&ids));
+    match pruned {
+        Ok((0, _, _)) => {}
+        Ok((sessions, spans, _)) => {
+            eprintln!(
+                "{} Pruned {} old sessions ({} spans) per retention policy",
+                "Trace:".cyan(),
+                sessions,
+                spans
+            );
+        }
+        Err(e) => eprintln!("{} Pruning traces: {}", "Warning:".yellow(), e),
+    }
+}
+
+/// Load a trace session with its spans and their content, exiting if missing
+fn load_trace_session(
+    db: &Database,
+    session_id: &str,
+) -> (
+    deciduous::db::TraceSession,
+    Vec<deciduous::db::TraceSpan>,
+    std::collections::HashMap<i32, Vec<deciduous::db::TraceContent>>,
+) {
+    let session = match db.get_trace_session(session_id) {
+        Ok(Some(s)) => s,
+        Ok(None) => {
+            eprintln!("{} Session {} not found", "Error:".red(), session_id);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+    let spans = match db.get_trace_spans(&session.session_id) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+    let mut content = std::collections::HashMap::new();
+    for span in &spans {
+        match db.get_trace_content(span.id) {
+            Ok(c) => {
+                content.insert(span.id, c);
+            }
+            Err(e) => {
+                eprintln!("{} {}", "Error:".red(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+    (session, spans, content)
+}
+
+/// Replay a session's requests into a new session
+fn trace_replay(
+    db: &Database,
+    session_id: &str,
+    model: Option<&str>,
+    max_tokens: u32,
+    limit: Option<usize>,
+    dry_run: bool,
+) {
+    use deciduous::proxy::{parse_response, record_span, Api, SpanCapture};
+    use deciduous::replay::{build_requests, send};
+
+    let (original, spans, content) = load_trace_session(db, session_id);
+    let mut requests = build_requests(&spans, &content, model, max_tokens);
+    if let Some(limit) = limit {
+        requests.truncate(limit);
+    }
+    if requests.is_empty() {
+        println!(
+            "Nothing to replay in session {}.",
+            &original.session_id[..8]
+        );
+        return;
+    }
+
+    let model_of = |body: &serde_json::Value| body["model"].as_str().unwrap_or("").to_string();
+    if let Some(other) = requests
+        .iter()
+        .map(|r| model_of(&r.body))
+        .find(|m| !m.starts_with("claude"))
+    {
+        eprintln!(
+            "{} Replay only supports Anthropic models (found '{}'); pick one with --model",
+            "Error:".red(),
+            other
+        );
+        std::process::exit(1);
+    }
+
+    if dry_run {
+        println!(
+            "{} Would replay {} requests from session {}\n",
+            "[DRY RUN]".yellow(),
+            requests.len(),
+            &original.session_id[..8]
+        );
+        for request in &requests {
+            println!(
+                "  span #{} → {} ({} messages)",
+                request.span_id,
+                model_of(&request.body),
+                request.body["messages"]
+                    .as_array()
+                    .map(Vec::len)
+                    .unwrap_or(0)
+            );
+        }
+        return;
+    }
+
+    let config = Config::load();
+    let redactor = match Redactor::from_config(&config.trace.redact) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let replay_id = uuid::Uuid::new_v4().to_string();
+    let command = format!(
+        "replay {}{}",
+        &original.session_id[..8],
+        model.map(|m| format!(" --model {}", m)).unwrap_or_default()
+    );
+    if let Err(e) = db.start_trace_session(
+        &replay_id,
+        original.working_dir.as_deref(),
+        original.git_branch.as_deref(),
+        Some(&command),
+    ) {
+        eprintln!("{} {}", "Error:".red(), e);
+        std::process::exit(1);
+    }
+
+    println!(
+        "{} {} requests from session {} into {}\n",
+        "Replaying".cyan(),
+        requests.len(),
+        &original.session_id[..8],
+        &replay_id[..8]
+    );
+    let originals: std::collections::HashMap<i32, &deciduous::db::TraceSpan> =
+        spans.iter().map(|s| (s.id, s)).collect();
+    let mut failed = None;
+    for request in &requests {
+        let started = std::time::Instant::now();
+        let reply = match send(&request.body) {
+            Ok(reply) => reply,
+            Err(e) => {
+                failed = Some(format!("span #{}: {}", request.span_id, e));
+                break;
+            }
+        };
+        let mut capture = SpanCapture {
+            model: Some(model_of(&request.body)),
+            user_preview: request.user_preview.clone(),
+            system_prompt: request.body["system"].as_str().map(str::to_string),
+            ..Default::default()
+        };
+        parse_response(Api::Anthropic, &reply, &mut capture);
+        let duration_ms = started.elapsed().as_millis() as i32;
+        if let Err(e) = record_span(db, &redactor, &replay_id, &capture, duration_ms) {
+            failed = Some(format!("recording span #{}: {}", request.span_id, e));
+            break;
+        }
+
+        let tokens =
+            |i: Option<i32>, o: Option<i32>| format!("{}↓ {}↑", i.unwrap_or(0), o.unwrap_or(0));
+        let before = originals
+            .get(&request.span_id)
+            .map(|s| tokens(s.input_tokens, s.output_tokens))
+            .unwrap_or_default();
+        println!(
+            "  span #{}: {} (was {})",
+            request.span_id,
+            tokens(capture.input_tokens, capture.output_tokens),
+            before
+        );
+    }
+
+    let _ = db.end_trace_session(
+        &replay_id,
+        Some(&format!("Replay of session {}", original.session_id)),
+    );
+    match failed {
+        Some(e) => {
+            eprintln!("{} Replay stopped at {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+        None => {
+            println!(
+                "\n{} Replay recorded as session {}",
+                "Success:".green(),
+                replay_id
+            );
+            println!(
+                "Compare with: deciduous trace diff {} {}",
+                original.session_id, replay_id
+            );
+        }
+    }
+}
+
+/// Walk through node suggestions for unlinked spans, creating accepted ones
+///
+/// `llm_model` has a model redraft the heuristic titles.
+fn trace_suggest(db: &Database, session: Option<&str>, llm_model: Option<&str>, yes: bool) {
+    use deciduous::suggest::{apply_llm_reply, draft, llm_request, unlinked_turns};
+    use std::io::Write;
+
+    let spans = match session {
+        Some(id) => db.get_trace_spans(id),
+        None => db.get_all_trace_spans(),
+    };
+    let mut spans = match spans {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+    // Keep each session's spans together; the sort is stable so order holds
+    spans.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+
+    let ids: Vec<i32> = spans.iter().map(|s| s.id).collect();
+    let counts = db.get_node_counts_for_spans(&ids).unwrap_or_default();
+    let turns = unlinked_turns(&spans, &counts);
+    if turns.is_empty() {
+        println!("No unlinked tool activity found.");
+        return;
+    }
+    println!(
+        "{} {} stretches of tool activity with no linked node\n",
+        "Found".cyan(),
+        turns.len()
+    );
+
+    let ask = |prompt: &str| -> Option<String> {
+        print!("{}", prompt);
+        std::io::stdout().flush().ok();
+        let mut input = String::new();
+        match std::io::stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(input.trim().to_string()),
+        }
+    };
+
+    let mut llm_failed = false;
+    let mut created = 0;
+    for turn in &turns {
+        let mut content = std::collections::HashMap::new();
+        for span in turn {
+            content.insert(span.id, db.get_trace_content(span.id).unwrap_or_default());
+        }
+        let mut suggestion = draft(turn, &content);
+
+        if let Some(model) = llm_model.filter(|_| !llm_failed) {
+            let last = turn[turn.len() - 1];
+            let response = deciduous::trace_diff::response_text(last, &content);
+            let request = llm_request(&suggestion, Some(&response), model);
+            match deciduous::replay::send(&request) {
+                Ok(reply) => {
+                    let mut capture = deciduous::proxy::SpanCapture::default();
+                    deciduous::proxy::parse_response(
+                        deciduous::proxy::Api::Anthropic,
+                        &reply,
+                        &mut capture,
+                    );
+                    apply_llm_reply(&mut suggestion, &capture.response);
+                }
+                Err(e) => {
+                    eprintln!(
+                        "{} Drafting with {} failed, using heuristics: {}",
+                        "Warning:".yellow(),
+                        model,
+                        e
+                    );
+                    llm_failed = true;
+                }
+            }
+        }
+
+        let tools: Vec<String> = suggestion
+            .tools
+            .iter()
+            .map(|(name, n)| format!("{}×{}", name, n))
+            .collect();
+        let span_range = match (suggestion.span_ids.first(), suggestion.span_ids.last()) {
+            (Some(a), Some(b)) if a != b => format!("Spans #{}–#{}", a, b),
+            (Some(a), _) => format!("Span #{}", a),
+            _ => String::new(),
+        };
+        println!(
+            "{} (session {}) · {}",
+            span_range.bold(),
+            &suggestion.session_id[..8.min(suggestion.session_id.len())],
+            tools.join(", ").dimmed()
+        );
+        println!("  goal     {}", suggestion.goal);
+        println!("  action   {}", suggestion.action);
+        if let Some(outcome) = &suggestion.outcome {
+            println!("  outcome  {}", outcome);
+        }
+
+        if !yes {
+            match ask("Create these nodes? [y]es / [n]o / [e]dit / [q]uit: ").as_deref() {
+                Some("y") | Some("Y") => {}
+                Some("e") | Some("E") => {
+                    let edit = |label: &str, current: &str| -> String {
+                        match ask(&format!("  {} [{}]: ", label, current)) {
+                            Some(s) if !s.is_empty() => s,
+                            _ => current.to_string(),
+                        }
+                    };
+                    suggestion.goal = edit("goal", &suggestion.goal);
+                    suggestion.action = edit("action", &suggestion.action);
+                    let outcome = edit(
+                        "outcome ('-' for none)",
+                        suggestion.outcome.as_deref().unwrap_or("-"),
+                    );
+                    suggestion.outcome = (outcome != "-").then_some(outcome);
+                }
+                Some("q") | Some("Q") | None => break,
+                _ => {
+                    println!();
+                    continue;
+                }
+            }
+        }
+
+        let branch = db
+            .get_trace_session(&suggestion.session_id)
+            .ok()
+            .flatten()
+            .and_then(|s| s.git_branch);
+        match create_suggested_nodes(db, &suggestion, branch.as_deref()) {
+            Ok((goal, action, outcome)) => {
+                created += 1;
+                let outcome = outcome
+                    .map(|id| format!(", outcome #{}", id))
+                    .unwrap_or_default();
+                println!(
+                    "{} goal #{}, action #{}{}\n",
+                    "Created".green(),
+                    goal,
+                    action,
+                    outcome
+                );
+            }
+            Err(e) => {
+                eprintln!("{} {}", "Error:".red(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    println!(
+        "{} Logged {} of {} suggestions",
+        "Done:".green(),
+        created,
+        turns.len()
+    );
+}
+
+/// Create a suggestion's goal → action → outcome chain and link its spans
+fn create_suggested_nodes(
+    db: &Database,
+    suggestion: &deciduous::suggest::Suggestion,
+    branch: Option<&str>,
+) -> deciduous::db::Result<(i32, i32, Option<i32>)> {
+    let files = (!suggestion.files.is_empty()).then(|| suggestion.files.join(","));
+    let goal = db.create_node_full(
+        "goal",
+        &suggestion.goal,
+        None,
+        None,
+        None,
+        suggestion.prompt.as_deref(),
+        None,
+        branch,
+    )?;
+    let action = db.create_node_full(
+        "action",
+        &suggestion.action,
+        None,
+        None,
+        None,
+        None,
+        files.as_deref(),
+        branch,
+    )?;
+    db.create_edge(goal, action, "leads_to", None)?;
+    let outcome = match &suggestion.outcome {
+        Some(title) => {
+            let id = db.create_node_full("outcome", title, None, None, None, None, None, branch)?;
+            db.create_edge(action, id, "leads_to", None)?;
+            Some(id)
+        }
+        None => None,
+    };
+    for span_id in &suggestion.span_ids {
+        db.link_trace_span_to_node(*span_id, action)?;
+    }
+    Ok((goal, action, outcome))
+}
+
+/// Percentage change from `a` to `b`, e.g. "+12%"
+fn percent_change(a: f64, b: f64) -> String {
+    if a == 0.0 {
+        return if b == 0.0 {
+            "±0%".to_string()
+        } else {
+            "new".to_string()
+        };
+    }
+    format!("{:+.0}%", (b - a) / a * 100.0)
+}
+
+/// Print spans of the active session as they complete, like `tail -f`
+///
+/// Without `--session`, waits for a session to start and moves on to the
+/// next one when it ends.
+fn trace_tail(db: &Database, session: Option<&str>, lines: usize, interval_ms: u64) {
+    let prices = PriceTable::from_config(&Config::load());
+    let mut current: Option<String> = session.map(str::to_string);
+    let mut attached = false;
+    let mut printed: std::collections::HashSet<i32> = std::collections::HashSet::new();
+
+    if session.is_none() {
+        println!(
+            "{} (Ctrl+C to stop)",
+            "Waiting for a trace session...".dimmed()
+        );
+    }
+    loop {
+        if current.is_none() {
+            match db.get_active_trace_session() {
+                Ok(active) => current = active.map(|s| s.session_id),
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        if let Some(session_id) = current.clone() {
+            let trace = match db.get_trace_session(&session_id) {
+                Ok(Some(trace)) => trace,
+                Ok(None) => {
+                    eprintln!("{} Session {} not found", "Error:".red(), session_id);
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            };
+            let spans = db.get_trace_spans(&session_id).unwrap_or_default();
+            let completed: Vec<&deciduous::db::TraceSpan> = spans
+                .iter()
+                .filter(|s| s.completed_at.is_some() && !printed.contains(&s.id))
+                .collect();
+
+            // Like tail: only the last few spans from before we attached
+            let skip = if attached {
+                0
+            } else {
+                attached = true;
+                println!(
+                    "{} {} {}",
+                    "Following".cyan(),
+                    &session_id[..8.min(session_id.len())],
+                    trace.command.as_deref().unwrap_or("").dimmed()
+                );
+                completed.len().saturating_sub(lines)
+            };
+            for (i, span) in completed.iter().enumerate() {
+                if i >= skip {
+                    print_tail_span(span, &prices);
+                }
+                printed.insert(span.id);
+            }
+
+            if trace.ended_at.is_some() {
+                println!(
+                    "{} {} {}",
+                    "Ended".dimmed(),
+                    &session_id[..8.min(session_id.len())],
+                    trace.summary.as_deref().unwrap_or("").dimmed()
+                );
+                if session.is_some() {
+                    return;
+                }
+                current = None;
+                attached = false;
+                printed.clear();
+            }
+        }
+
+        std::thread::sleep(std::time::Duration::from_millis(interval_ms));
+    }
+}
+
+/// One line per completed span for `trace tail`
+fn print_tail_span(span: &deciduous::db::TraceSpan, prices: &PriceTable) {
+    let time = span
+        .completed_at
+        .as_deref()
+        .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&Local).format("%H:%M:%S").to_string())
+        .unwrap_or_default();
+    let duration = span
+        .duration_ms
+        .map(|d| format!("{:.1}s", d as f64 / 1000.0))
+        .unwrap_or_default();
+    let tokens = match (span.input_tokens, span.output_tokens) {
+        (Some(i), Some(o)) => format!("{}↓ {}↑", i, o),
+        _ => String::new(),
+    };
+    let cost = prices.span_cost(span).map(format_cost).unwrap_or_default();
+    let linked = match span.linked_node_id {
+        Some(id) => format!("→ #{}", id).yellow().to_string(),
+        None => String::new(),
+    };
+
+    println!(
+        "{} #{} {} {} {} {} {}",
+        time.dimmed(),
+        span.id,
+        span.model.as_deref().unwrap_or("?"),
+        duration.dimmed(),
+        tokens.dimmed(),
+        cost.green(),
+        linked
+    );
+    if let Some(tools) = span.tool_names.as_deref().filter(|t| !t.is_empty()) {
+        println!("         tools: {}", tools.dimmed());
+    }
+}
+
+/// Add, list or delete reviewer notes on a span
+fn trace_annotate(db: &Database, span_id: i32, note: Option<&str>, delete: Option<i32>) {
+    if let Some(note_id) = delete {
+        match db.delete_span_annotation(note_id) {
+            Ok(true) => println!("{} Deleted note {}", "Success:".green(), note_id),
+            Ok(false) => {
+                eprintln!("{} Note {} not found", "Error:".red(), note_id);
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("{} {}", "Error:".red(), e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if !matches!(db.get_trace_span(span_id), Ok(Some(_))) {
+        eprintln!("{} Span #{} not found", "Error:".red(), span_id);
+        std::process::exit(1);
+    }
+
+    if let Some(note) = note.map(str::trim).filter(|n| !n.is_empty()) {
+        match db.annotate_span(span_id, note) {
+            Ok(id) => println!(
+                "{} Added note {} to span #{}",
+                "Success:".green(),
+                id,
+                span_id
+            ),
+            Err(e) => {
+                eprintln!("{} {}", "Error:".red(), e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let annotations = db.get_annotations_for_spans(&[span_id]).unwrap_or_default();
+    let notes = annotations.get(&span_id).map(Vec::as_slice).unwrap_or(&[]);
+    if notes.is_empty() {
+        println!("No notes on span #{}.", span_id);
+    }
+    for a in notes {
+        println!(
+            "  [{}] {} {}",
+            a.id,
+            a.created_at.get(..16).unwrap_or(&a.created_at).dimmed(),
+            a.note
+        );
+    }
+}
+
+/// Import Claude Code transcripts, skipping sessions already imported
+fn trace_import_claude(db: &Database, paths: &[PathBuf]) {
+    let config = Config::load();
+    let redactor = match Redactor::from_config(&config.trace.redact) {
+        Ok(r) => r.with_capture(CaptureFilter::from_config(&config.trace.capture)),
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let files: Vec<PathBuf> = paths.iter().flat_map(|p| find_transcripts(p)).collect();
+    if files.is_empty() {
+        eprintln!("{} No transcripts (*.jsonl) found", "Error:".red());
+        std::process::exit(1);
+    }
+
+    let (mut imported, mut skipped) = (0, 0);
+    for file in &files {
+        let text = match std::fs::read_to_string(file) {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("{} {}: {}", "Warning:".yellow(), file.display(), e);
+                continue;
+            }
+        };
+        let id = file
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let session = parse_transcript(&id, &text);
+        if session.spans.is_empty() {
+            skipped += 1;
+            continue;
+        }
+
+        match import_session(db, &redactor, &session) {
+            Ok(Some(spans)) => {
+                imported += 1;
+                let _ = link_session_subagents(db, &session.session_id);
+                println!(
+                    "  {} {} ({} spans{})",
+                    "+".green(),
+                    session.session_id,
+                    spans,
+                    session
+                        .summary
+                        .as_deref()
+                        .map(|s| format!(", {}", truncate(s, 50)))
+                        .unwrap_or_default()
+                );
+            }
+            Ok(None) => skipped += 1,
+            Err(e) => {
+                eprintln!("{} {}: {}", "Error:".red(), file.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    println!(
+        "{} Imported {} session(s), skipped {} (already imported or empty)",
+        "Success:".green(),
+        imported,
+        skipped
+    );
+}
+
+/// Link sessions' spans to their commits, or look up the span behind a commit
+fn trace_writeup(db: &Database, session_id: &str, output: Option<PathBuf>, dot: bool) {
+    use deciduous::trace_commits::{commit_subject, commits_since, match_commits};
+    use deciduous::{generate_trace_writeup, SessionActivity};
+    use std::collections::HashMap;
+
+    let (session, spans, _) = load_trace_session(db, session_id);
+    let graph = match db.get_graph() {
+        Ok(g) => g,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut node_ids: Vec<i32> = session.linked_node_id.into_iter().collect();
+    let mut span_nodes = HashMap::new();
+    let mut span_commits: HashMap<i32, Vec<(String, String)>> = HashMap::new();
+    for span in &spans {
+        node_ids.extend(span.linked_node_id);
+        let nodes = db.get_nodes_for_span(span.id).unwrap_or_default();
+        node_ids.extend(nodes.iter().map(|n| n.id));
+        span_nodes.insert(span.id, nodes.iter().map(|n| n.id).collect::<Vec<_>>());
+
+        for commit in db.get_commits_for_span(span.id).unwrap_or_default() {
+            let subject = commit_subject(&commit.commit_hash).unwrap_or_default();
+            span_commits
+                .entry(span.id)
+                .or_default()
+                .push((commit.commit_hash, subject));
+        }
+    }
+
+    // Nothing linked yet: match commits for the report without storing them
+    if span_commits.is_empty() {
+        let start = spans
+            .iter()
+            .filter_map(|s| chrono::DateTime::parse_from_rfc3339(&s.started_at).ok())
+            .min();
+        if let Some(start) = start {
+            let commits = commits_since(&start);
+            for (span, commit) in match_commits(&spans, &commits) {
+                span_commits
+                    .entry(span.id)
+                    .or_default()
+                    .push((commit.hash.clone(), commit.subject.clone()));
+            }
+        }
+    }
+
+    node_ids.sort_unstable();
+    node_ids.dedup();
+    let graph = filter_graph_by_ids(&graph, &node_ids);
+    let activity = SessionActivity {
+        session: &session,
+        spans: &spans,
+        span_nodes,
+        span_commits,
+    };
+    let config = WriteupConfig {
+        title: format!(
+            "Session {}",
+            &session.session_id[..8.min(session.session_id.len())]
+        ),
+        root_ids: vec![],
+        include_dot: dot,
+        include_test_plan: false,
+        png_filename: None,
+        github_repo: None,
+        git_branch: session.git_branch.clone(),
+    };
+    let writeup = generate_trace_writeup(&graph, &activity, &config);
+
+    match output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(&path, &writeup) {
+                eprintln!("{} Writing {}: {}", "Error:".red(), path.display(), e);
+                std::process::exit(1);
+            }
+            println!(
+                "{} Session writeup to {}",
+                "Generated".green(),
+                path.display()
+            );
+        }
+        None => println!("{}", writeup),
+    }
+}
+
+fn trace_commits(db: &Database, session: Option<&str>, update_nodes: bool, commit: Option<&str>) {
+    if let Some(commit) = commit {
+        let spans = match db.get_spans_for_commit(commit) {
+            Ok(spans) => spans,
+            Err(e) => {
+                eprintln!("{} {}", "Error:".red(), e);
+                std::process::exit(1);
+            }
+        };
+        if spans.is_empty() {
+            println!(
+                "No span linked to commit {} (run `deciduous trace commits` to link)",
+                commit
+            );
+            return;
+        }
+        for span in &spans {
+            println!(
+                "{} {} {}",
+                format!("Span #{}", span.id).cyan(),
+                &span.session_id[..8.min(span.session_id.len())],
+                span.started_at.dimmed()
+            );
+            if let Some(model) = &span.model {
+                println!("  Model: {}", model);
+            }
+            if let Some(tools) = &span.tool_names {
+                println!("  Tools: {}", tools);
+            }
+            if let Some(user) = &span.user_preview {
+                println!("  User: {}", truncate(user, 80));
+            }
+            if let Some(node) = span.linked_node_id {
+                println!("  Node: #{}", node);
+            }
+        }
+        return;
+    }
+
+    let session_ids: Vec<String> = match session {
+        Some(id) => vec![id.to_string()],
+        None => match db.get_trace_sessions(i64::MAX) {
+            Ok(sessions) => sessions.into_iter().map(|s| s.session_id).collect(),
+            Err(e) => {
+                eprintln!("{} {}", "Error:".red(), e);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    let mut total = 0;
+    for session_id in &session_ids {
+        match link_session_commits(db, session_id, update_nodes) {
+            Ok(linked) => {
+                for (span_id, commit) in &linked {
+                    println!(
+                        "{} {} → span #{}  {}",
+                        "Linked".green(),
+                        &commit.hash[..8.min(commit.hash.len())],
+                        span_id,
+                        commit.subject
+                    );
+                }
+                total += linked.len();
+            }
+            Err(e) => {
+                eprintln!("{} {}: {}", "Error:".red(), session_id, e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if total == 0 {
+        println!("No new commits to link");
+    }
+}
+
+/// Print a span-by-span comparison of two trace sessions
+fn trace_diff(db: &Database, a: &str, b: &str) {
+    use deciduous::trace_diff::{align, response_text, similarity, tool_changes};
+
+    let (session_a, spans_a, content_a) = load_trace_session(db, a);
+    let (session_b, spans_b, content_b) = load_trace_session(db, b);
+    let prices = PriceTable::from_config(&Config::load());
+
+    let short = |id: &str| id.chars().take(8).collect::<String>();
+    let models = |spans: &[deciduous::db::TraceSpan]| {
+        let mut models: Vec<&str> = spans.iter().filter_map(|s| s.model.as_deref()).collect();
+        models.sort_unstable();
+        models.dedup();
+        models.join(", ")
+    };
+    let tools_used = |spans: &[deciduous::db::TraceSpan]| {
+        spans
+            .iter()
+            .filter_map(|s| s.tool_names.as_deref())
+            .flat_map(|t| t.split(','))
+            .filter(|t| !t.trim().is_empty())
+            .count()
+    };
+    let (cost_a, cost_b) = (prices.total_cost(&spans_a), prices.total_cost(&spans_b));
+    let (in_a, in_b) = (
+        f64::from(session_a.total_input_tokens),
+        f64::from(session_b.total_input_tokens),
+    );
+    let (out_a, out_b) = (
+        f64::from(session_a.total_output_tokens),
+        f64::from(session_b.total_output_tokens),
+    );
+
+    println!(
+        "{} {} → {}\n",
+        "Trace diff".cyan(),
+        short(&session_a.session_id),
+        short(&session_b.session_id)
+    );
+    let rows = [
+        ("", "A".to_string(), "B".to_string(), None),
+        ("Model", models(&spans_a), models(&spans_b), None),
+        (
+            "Spans",
+            spans_a.len().to_string(),
+            spans_b.len().to_string(),
+            None,
+        ),
+        (
+            "Input tokens",
+            session_a.total_input_tokens.to_string(),
+            session_b.total_input_tokens.to_string(),
+            Some(percent_change(in_a, in_b)),
+        ),
+        (
+            "Output tokens",
+            session_a.total_output_tokens.to_string(),
+            session_b.total_output_tokens.to_string(),
+            Some(percent_change(out_a, out_b)),
+        ),
+        (
+            "Tool calls",
+            tools_used(&spans_a).to_string(),
+            tools_used(&spans_b).to_string(),
+            None,
+        ),
+        (
+            "Cost",
+            format_cost(cost_a),
+            format_cost(cost_b),
+            Some(percent_change(cost_a, cost_b)),
+        ),
+    ];
+    for (label, value_a, value_b, change) in rows {
+        match change {
+            Some(change) => println!("  {:<14} {:<28} {}  ({})", label, value_a, value_b, change),
+            None => println!("  {:<14} {:<28} {}", label, value_a, value_b),
+        }
+    }
+
+    let mut turn: Option<Option<&str>> = None;
+    for pair in align(&spans_a, &spans_b) {
+        if turn != Some(pair.user()) {
+            turn = Some(pair.user());
+            let user: String = pair
+                .user()
+                .unwrap_or("(no user message)")
+                .lines()
+                .next()
+                .unwrap_or("")
+                .chars()
+                .take(70)
+                .collect();
+            println!("\n{} {}", "▸".cyan(), user.bold());
+        }
+
+        match (pair.a, pair.b) {
+            (Some(sa), Some(sb)) => {
+                let tokens = |s: &deciduous::db::TraceSpan| {
+                    format!(
+                        "{}↓ {}↑",
+                        s.input_tokens.unwrap_or(0),
+                        s.output_tokens.unwrap_or(0)
+                    )
+                };
+                let delta = percent_change(
+                    f64::from(sa.output_tokens.unwrap_or(0)),
+                    f64::from(sb.output_tokens.unwrap_or(0)),
+                );
+                println!(
+                    "  #{} ↔ #{}  {} → {}  (output {})",
+                    sa.id,
+                    sb.id,
+                    tokens(sa),
+                    tokens(sb),
+                    delta
+                );
+
+                let (added, removed) = tool_changes(sa, sb);
+                if !added.is_empty() || !removed.is_empty() {
+                    let mut changes: Vec<String> = added
+                        .iter()
+                        .map(|t| format!("+{}", t).green().to_string())
+                        .collect();
+                    changes.extend(removed.iter().map(|t| format!("-{}", t).red().to_string()));
+                    println!("    tools: {}", changes.join(" "));
+                }
+
+                let (ra, rb) = (response_text(sa, &content_a), response_text(sb, &content_b));
+                if ra != rb {
+                    let preview = |text: &str| -> String {
+                        let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                        line.chars().take(70).collect()
+                    };
+                    println!("    response: {:.0}% similar", similarity(&ra, &rb) * 100.0);
+                    println!("      {} {}", "A".dimmed(), preview(&ra));
+                    println!("      {} {}", "B".dimmed(), preview(&rb));
+                }
+            }
+            (Some(sa), None) => println!("  #{} {}", sa.id, "only in A".yellow()),
+            (None, Some(sb)) => println!("  #{} {}", sb.id, "only in B".yellow()),
+            (None, None) => {}
+        }
+    }
+}
+
+/// Print API spend grouped by day, model, or the decision node it's linked to
+///
+/// Spans count toward their own linked node, falling back to their session's.
+fn trace_costs(db: &Database, by: &str, days: Option<i64>, session: Option<&str>) {
+    if !matches!(by, "day" | "model" | "node") {
+        eprintln!(
+            "{} Unknown grouping '{}' (use 'day', 'model' or 'node')",
+            "Error:".red(),
+            by
+        );
+        std::process::exit(1);
+    }
+
+    let spans = match session {
+        Some(id) => db.get_trace_spans(id),
+        None => db.get_all_trace_spans(),
+    };
+    let sessions = db.get_trace_sessions(i64::MAX);
+    let nodes = db.get_all_nodes();
+    let (spans, sessions, nodes) = match (spans, sessions, nodes) {
+        (Ok(s), Ok(ss), Ok(n)) => (s, ss, n),
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let cutoff = days.map(|d| Local::now() - chrono::Duration::days(d));
+    let spans: Vec<_> = spans
+        .into_iter()
+        .filter(|s| match cutoff {
+            Some(cutoff) => chrono::DateTime::parse_from_rfc3339(&s.started_at)
+                .map(|t| t >= cutoff)
+                .unwrap_or(false),
+            None => true,
+        })
+        .collect();
+    if spans.is_empty() {
+        println!("No trace spans found.");
+        return;
+    }
+
+    let session_nodes: std::collections::HashMap<&str, i32> = sessions
+        .iter()
+        .filter_map(|s| s.linked_node_id.map(|n| (s.session_id.as_str(), n)))
+        .collect();
+    let node_titles: std::collections::HashMap<i32, &str> =
+        nodes.iter().map(|n| (n.id, n.title.as_str())).collect();
+
+    let prices = PriceTable::from_config(&Config::load());
+    // group -> (spans, input tokens, output tokens, cost)
+    let mut groups: std::collections::BTreeMap<String, (usize, i64, i64, f64)> =
+        std::collections::BTreeMap::new();
+    let mut unpriced: std::collections::BTreeSet<String> = std::collections::BTreeSet::new();
+    for span in &spans {
+        let key = match by {
+            "day" => span.started_at.chars().take(10).collect(),
+            "model" => span
+                .model
+                .clone()
+                .unwrap_or_else(|| "(unknown)".to_string()),
+            _ => match span
+                .linked_node_id
+                .or_else(|| session_nodes.get(span.session_id.as_str()).copied())
+            {
+                Some(id) => format!(
+                    "#{} {}",
+                    id,
+                    truncate(node_titles.get(&id).copied().unwrap_or("(deleted)"), 50)
+                ),
+                None => "(unlinked)".to_string(),
+            },
+        };
+        let cost = prices.span_cost(span);
+        if cost.is_none() {
+            unpriced.insert(
+                span.model
+                    .clone()
+                    .unwrap_or_else(|| "(unknown)".to_string()),
+            );
+        }
+
+        let entry = groups.entry(key).or_insert((0, 0, 0, 0.0));
+        entry.0 += 1;
+        entry.1 += i64::from(span.input_tokens.unwrap_or(0));
+        entry.2 += i64::from(span.output_tokens.unwrap_or(0));
+        entry.3 += cost.unwrap_or(0.0);
+    }
+
+    let mut rows: Vec<_> = groups.into_iter().collect();
+    if by != "day" {
+        rows.sort_by(|a, b| b.1 .3.total_cmp(&a.1 .3));
+    }
+
+    println!("{} by {}\n", "Trace Costs".cyan(), by);
+    for (key, (count, input, output, cost)) in &rows {
+        println!(
+            "  {:>10}  {:>6} spans  {:>10}  {}",
+            format_cost(*cost).green(),
+            count,
+            format!("{}↓ {}↑", input, output).dimmed(),
+            key
+        );
+    }
+    let total: f64 = rows.iter().map(|(_, r)| r.3).sum();
+    println!("\n  {:>10}  total", format_cost(total).green().bold());
+
+    if !unpriced.is_empty() {
+        println!(
+            "\n{} No price for: {} (add them under [trace.pricing] in .deciduous/config.toml)",
+            "Note:".yellow(),
+            unpriced.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+}
+
+/// Print tool usage analytics for `deciduous trace tools`
+fn trace_tools(db: &Database, days: Option<i64>, session: Option<&str>) {
+    let spans = match session {
+        Some(id) => db.get_trace_spans(id),
+        None => db.get_all_trace_spans(),
+    };
+    let sessions = db.get_trace_sessions(i64::MAX);
+    let nodes = db.get_all_nodes();
+    let content = db.get_all_trace_content_of_types(&["tool_input", "tool_output", "tool_error"]);
+    let (spans, sessions, nodes, content) = match (spans, sessions, nodes, content) {
+        (Ok(s), Ok(ss), Ok(n), Ok(c)) => (s, ss, n, c),
+        (Err(e), ..) | (_, Err(e), ..) | (_, _, Err(e), _) | (.., Err(e)) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let cutoff = days.map(|d| Local::now() - chrono::Duration::days(d));
+    let spans: Vec<_> = spans
+        .into_iter()
+        .filter(|s| match cutoff {
+            Some(cutoff) => chrono::DateTime::parse_from_rfc3339(&s.started_at)
+                .map(|t| t >= cutoff)
+                .unwrap_or(false),
+            None => true,
+        })
+        .collect();
+    if spans.is_empty() {
+        println!("No trace spans found.");
+        return;
+    }
+    let span_ids: std::collections::HashSet<i32> = spans.iter().map(|s| s.id).collect();
+    let content: Vec<_> = content
+        .into_iter()
+        .filter(|c| span_ids.contains(&c.span_id))
+        .collect();
+
+    let session_nodes: std::collections::HashMap<&str, i32> = sessions
+        .iter()
+        .filter_map(|s| s.linked_node_id.map(|n| (s.session_id.as_str(), n)))
+        .collect();
+    let node_types: std::collections::HashMap<i32, &str> =
+        nodes.iter().map(|n| (n.id, n.node_type.as_str())).collect();
+    let span_node_types: std::collections::HashMap<i32, String> = spans
+        .iter()
+        .filter_map(|s| {
+            let node = s
+                .linked_node_id
+                .or_else(|| session_nodes.get(s.session_id.as_str()).copied())?;
+            Some((s.id, node_types.get(&node)?.to_string()))
+        })
+        .collect();
+
+    let report = deciduous::tool_stats::tool_report(&spans, &content, &span_node_types);
+    if report.by_tool.is_empty() {
+        println!("No tool calls in {} spans.", spans.len());
+        return;
+    }
+
+    println!("{} ({} spans)\n", "Tool Usage".cyan(), spans.len());
+    println!(
+        "  {:<24} {:>7} {:>8} {:>10}",
+        "tool".dimmed(),
+        "calls".dimmed(),
+        "failed".dimmed(),
+        "avg output".dimmed()
+    );
+    for (name, stats) in report.ranked() {
+        let failed = match stats.failure_rate() {
+            Some(rate) if rate > 0.25 => format!("{:.0}%", rate * 100.0).red(),
+            Some(rate) if rate > 0.0 => format!("{:.0}%", rate * 100.0).yellow(),
+            Some(_) => "0%".normal(),
+            None => "-".dimmed(),
+        };
+        let avg = stats
+            .avg_output()
+            .map(|b| {
+                if b >= 1024 {
+                    format!("{:.1} KB", b as f64 / 1024.0)
+                } else {
+                    format!("{} B", b)
+                }
+            })
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "  {:<24} {:>7} {:>8} {:>10}",
+            truncate(name, 24),
+            stats.calls,
+            failed,
+            avg
+        );
+    }
+
+    println!("\n{}", "By node type".cyan());
+    for (node_type, tools) in &report.by_node_type {
+        let mut tools: Vec<(&String, &usize)> = tools.iter().collect();
+        tools.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let list: Vec<String> = tools
+            .iter()
+            .take(6)
+            .map(|(name, n)| format!("{} ×{}", name, n))
+            .collect();
+        let total: usize = tools.iter().map(|(_, n)| **n).sum();
+        println!(
+            "  {:<12} {:>6} calls  {}",
+            node_type,
+            total,
+            list.join(", ").dimmed()
+        );
+    }
+}
+
+/// Check a patch's signature and apply it, printing what changed
+///
+/// Returns None when the patch was refused or failed to apply.
+fn apply_verified_patch(
+    db: &Database,
+    label: &str,
+    patch: &deciduous::GraphPatch,
+    dry_run: bool,
+    strategy: deciduous::ConflictStrategy,
+    sync_config: &deciduous::config::SyncConfig,
+) -> Option<deciduous::ApplyResult> {
+    let provenance = deciduous::signing::verify(patch, sync_config);
+    if !provenance.acceptable(sync_config) {
+        eprintln!(
+            "{} Refusing {}: {}",
+            "Error:".red(),
+            label,
+            provenance.describe()
+        );
+        return None;
+    }
+    if matches!(provenance, deciduous::signing::Provenance::Untrusted(_)) {
+        eprintln!(
+            "{} {} is {} (add the key to [sync.trusted_keys] to trust it)",
+            "Warning:".yellow(),
+            label,
+            provenance.describe()
+        );
+    }
+    let result = match db.apply_patch(patch, dry_run, strategy) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{} Applying {}: {}", "Error:".red(), label, e);
+            return None;
+        }
+    };
+
+    if dry_run {
+        println!("{} {} (dry run)", "Would apply:".cyan(), label);
+    } else {
+        println!("{} {}", "Applied:".green(), label);
+    }
+    if let Some(author) = &patch.author {
+        println!("  Author: {}", Config::load().display_name(author));
+    }
+    if let deciduous::signing::Provenance::Trusted(name) = &provenance {
+        println!("  Verified: signed by {}", name);
+    }
+    println!(
+        "  Nodes: {} added, {} skipped",
+        result.nodes_added, result.nodes_skipped
+    );
+    println!(
+        "  Edges: {} added, {} skipped",
+        result.edges_added, result.edges_skipped
+    );
+    if result.nodes_updated + result.updates_skipped > 0 {
+        println!(
+            "  Updates: {} applied, {} skipped (local is newer)",
+            result.nodes_updated, result.updates_skipped
+        );
+    }
+    if result.nodes_deleted + result.tombstones_skipped > 0 {
+        println!(
+            "  Deletions: {} applied, {} skipped (local is newer)",
+            result.nodes_deleted, result.tombstones_skipped
+        );
+    }
+    if result.conflicts > 0 {
+        println!(
+            "  {} {} ({} resolved)",
+            "Conflicts:".yellow(),
+            result.conflicts,
+            result.conflicts_resolved
+        );
+    }
+    if !result.edges_failed.is_empty() {
+        println!(
+            "  {} edges failed (missing nodes):",
+            result.edges_failed.len()
+        );
+        for msg in &result.edges_failed {
+            println!("    - {}", msg);
+        }
+    }
+    Some(result)
+}
+
+/// Totals line after applying several patches
+fn print_apply_totals(results: &[deciduous::ApplyResult], dry_run: bool) {
+    if dry_run {
+        return;
+    }
+    let sum = |f: fn(&deciduous::ApplyResult) -> usize| results.iter().map(f).sum::<usize>();
+    println!(
+        "\n{} {} nodes added, {} skipped; {} edges added, {} skipped",
+        "Total:".cyan(),
+        sum(|r| r.nodes_added),
+        sum(|r| r.nodes_skipped),
+        sum(|r| r.edges_added),
+        sum(|r| r.edges_skipped)
+    );
+    let (updated, deleted) = (sum(|r| r.nodes_updated), sum(|r| r.nodes_deleted));
+    if updated + deleted > 0 {
+        println!("       {} nodes updated, {} deleted", updated, deleted);
+    }
+    let conflicts = sum(|r| r.conflicts - r.conflicts_resolved);
+    if conflicts > 0 {
+        println!(
+            "{} {} conflicting fields kept local values; review with 'deciduous diff conflicts'",
+            "Warning:".yellow(),
+            conflicts
+        );
+    }
+}
+
+/// Run `deciduous ci` and exit non-zero if the graph fails validation
+/// Parse `--strategy`, exiting on an unknown value; None records conflicts
+fn parse_conflict_strategy(strategy: Option<&str>) -> deciduous::ConflictStrategy {
+    let Some(strategy) = strategy else {
+        return deciduous::ConflictStrategy::default();
+    };
+    match deciduous::ConflictStrategy::parse(strategy) {
+        Some(s) => s,
+        None => {
+            eprintln!(
+                "{} Unknown strategy '{}' (use 'ours', 'theirs' or 'newest')",
+                "Error:".red(),
+                strategy
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run_ci(patches: Option<PathBuf>, branch: Option<String>, strict: bool, annotations: bool) {
+    let config = Config::load();
+    let patches_dir = patches.unwrap_or_else(|| PathBuf::from(".deciduous/patches"));
+    let annotations = annotations || std::env::var("GITHUB_ACTIONS").is_ok_and(|v| v == "true");
+
+    // PR builds check out a detached merge ref, so prefer the Actions head ref
+    let branch = branch
+        .or_else(|| {
+            std::env::var("GITHUB_HEAD_REF")
+                .ok()
+                .filter(|b| !b.is_empty())
+        })
+        .or_else(deciduous::get_current_git_branch)
+        .filter(|b| !config.is_main_branch(b));
+
+    // Only read the local database if it already exists; don't create one
+    let local_nodes = if Database::db_path().exists() {
+        Database::open()
+            .and_then(|db| db.get_all_nodes())
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    let report = deciduous::ci::run_checks(
+        &patches_dir,
+        branch.as_deref(),
+        &local_nodes,
+        &config.sync_with_team(),
+    );
+
+    println!(
+        "{} {} patches, {} nodes{}",
+        "CI:".cyan(),
+        report.patches_checked,
+        report.nodes_checked,
+        branch
+            .as_deref()
+            .map(|b| format!(", branch '{}'", b))
+            .unwrap_or_default()
+    );
+
+    for finding in &report.findings {
+        if annotations {
+            println!("{}", finding.annotation());
+        } else {
+            let label = match finding.severity {
+                deciduous::ci::Severity::Error => "Error:".red(),
+                deciduous::ci::Severity::Warning => "Warning:".yellow(),
+            };
+            match &finding.file {
+                Some(file) => println!("  {} {}: {}", label, file.display(), finding.message),
+                None => println!("  {} {}", label, finding.message),
+            }
+        }
+    }
+
+    println!(
+        "\n{} {} errors, {} warnings",
+        if report.passed(strict) {
+            "Passed:".green()
+        } else {
+            "Failed:".red()
+        },
+        report.errors(),
+        report.warnings()
+    );
+
+    if !report.passed(strict) {
+        std::process::exit(1);
+    }
+}
+
+/// Close roadmap issues whose items are all checked with completed outcomes
+///
+/// Leaves a comment on each issue linking the outcome nodes and their commits.
+fn close_completed_roadmap_issues(db: &Database, repo: Option<String>) {
+    let ready = match db.get_roadmap_issues_ready_to_close() {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+
+    println!();
+    if ready.is_empty() {
+        println!("{} No open issues ready to close", "Issues:".cyan());
+        return;
+    }
+
+    let config = Config::load();
+    let forge = match detect_forge(repo, &config) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("{} Auto-detecting repo: {}", "Error:".red(), e);
+            eprintln!("Specify repo with --repo owner/repo");
+            std::process::exit(1);
+        }
+    };
+    let code_host = deciduous::forge::code_host(forge.as_ref());
+
+    println!(
+        "{} Closing {} completed issues",
+        "Issues:".cyan(),
+        ready.len()
+    );
+
+    for (section, tasks) in ready {
+        let Some(issue_num) = section.github_issue_number else {
+            continue;
+        };
+
+        let mut comment = String::from("All roadmap items are complete:\n\n");
+        for task in &tasks {
+            let outcome = task
+                .outcome_node_id
+                .and_then(|id| db.get_node_by_id(id).ok().flatten());
+            let Some(outcome) = outcome else {
+                comment.push_str(&format!("- [x] {}\n", task.title));
+                continue;
+            };
+
+            let commit = outcome
+                .metadata_json
+                .as_deref()
+                .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+                .and_then(|m| m.get("commit").and_then(|c| c.as_str()).map(String::from));
+            let commit_ref = match (commit, &code_host) {
+                (Some(hash), Some((kind, host, repo_name))) => format!(
+                    " ([`{}`]({}))",
+                    &hash[..hash.len().min(7)],
+                    deciduous::forge::commit_url(*kind, host, repo_name, &hash)
+                ),
+                (Some(hash), None) => format!(" (`{}`)", &hash[..hash.len().min(7)]),
+                _ => String::new(),
+            };
+
+            comment.push_str(&format!(
+                "- [x] {} — outcome #{}: {}{}\n",
+                task.title, outcome.id, outcome.title, commit_ref
+            ));
+        }
+        comment.push_str("\n_Closed by `deciduous roadmap check --close-issues`_\n");
+
+        let result = forge
+            .add_comment(issue_num, &comment)
+            .and_then(|_| forge.close_issue(issue_num));
+
+        match result {
+            Ok(()) => {
+                println!(
+                    "  {} Closed {}: {}",
+                    "✓".green(),
+                    forge.issue_ref(issue_num),
+                    section.title
+                );
+                if let Err(e) =
+                    db.update_roadmap_item_github(section.id, Some(issue_num), Some("closed"))
+                {
+                    eprintln!("    {} Updating database: {}", "Warning:".yellow(), e);
+                }
+            }
+            Err(e) => eprintln!(
+                "  {} Closing {}: {}",
+                "✗".red(),
+                forge.issue_ref(issue_num),
+                e
+            ),
+        }
+    }
+}
+
+/// `deciduous search`: word matches newest first, or semantic matches best first
+fn search(
+    db: &Database,
+    query: Option<&str>,
+    semantic: bool,
+    related: Option<i32>,
+    limit: usize,
+    json: bool,
+) {
+    use deciduous::embed::{self, ApiEmbedder, SearchHit};
+
+    let embedder = ApiEmbedder::new(Config::load().embeddings);
+    let result = match (related, query) {
+        (Some(id), _) => embed::related(db, &embedder, id, limit),
+        (None, Some(query)) if semantic => embed::search(db, &embedder, query, limit),
+        (None, query) => {
+            deciduous::mcp::search_nodes(db, query.unwrap_or_default(), limit).map(|nodes| {
+                nodes
+                    .into_iter()
+                    .map(|node| SearchHit { score: 1.0, node })
+                    .collect()
+            })
+        }
+    };
+    let hits = match result {
+        Ok(hits) => hits,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&hits).unwrap_or_default()
+        );
+        return;
+    }
+    if hits.is_empty() {
+        println!("No matching nodes.");
+        return;
+    }
+    let ranked = semantic || related.is_some();
+    if ranked {
+        println!(
+            "{:<6} {:<5} {:<12} {:<10} TITLE",
+            "SCORE", "ID", "TYPE", "STATUS"
+        );
+    } else {
+        println!("{:<5} {:<12} {:<10} TITLE", "ID", "TYPE", "STATUS");
+    }
+    println!("{}", "-".repeat(70));
+    for SearchHit { score, node } in hits {
+        let row = format!(
+            "{:<5} {:<12} {:<10} {}",
+            node.id, node.node_type, node.status, node.title
+        );
+        if ranked {
+            println!("{:<6.3} {}", score, row);
+        } else {
+            println!("{}", row);
+        }
+    }
+}
+
+/// `deciduous search --federated`: word matches across this repo and
+/// federated ones, newest first
+fn search_federated(db: &Database, query: &str, limit: usize, json: bool) {
+    let hits = match deciduous::federation::search(db, query, limit) {
+        Ok(hits) => hits,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+
+    if json {
+        let hits: Vec<serde_json::Value> = hits
+            .iter()
+            .map(|(repo, node)| serde_json::json!({ "repo": repo, "node": node }))
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&hits).unwrap_or_default()
+        );
+        return;
+    }
+    if hits.is_empty() {
+        println!("No matching nodes.");
+        return;
+    }
+    println!(
+        "{:<16} {:<5} {:<12} {:<10} TITLE",
+        "REPO", "ID", "TYPE", "STATUS"
+    );
+    println!("{}", "-".repeat(70));
+    for (repo, node) in hits {
+        println!(
+            "{:<16} {:<5} {:<12} {:<10} {}",
+            repo, node.id, node.node_type, node.status, node.title
+        );
+    }
+}
+
+/// `deciduous ask`: retrieve nodes for the question and have a model answer
+fn ask(db: &Database, question: &str, model: &str, limit: usize, semantic: bool, json: bool) {
+    use deciduous::ask::{citations, context, llm_request, reply_text, retrieve};
+    use deciduous::embed::{self, ApiEmbedder};
+
+    let fail = |e: String| -> ! {
+        eprintln!("{} {}", "Error:".red(), e);
+        std::process::exit(1);
+    };
+
+    let hits = if semantic {
+        let embedder = ApiEmbedder::new(Config::load().embeddings);
+        embed::search(db, &embedder, question, limit).unwrap_or_else(|e| {
+            eprintln!(
+                "{} Semantic matching unavailable, using keywords only: {}",
+                "Warning:".yellow(),
+                e
+            );
+            Vec::new()
+        })
+    } else {
+        Vec::new()
+    };
+    let nodes = retrieve(db, question, &hits, limit).unwrap_or_else(|e| fail(e));
+    if nodes.is_empty() {
+        fail("No nodes match the question".to_string());
+    }
+    let context = context(db, &nodes).unwrap_or_else(|e| fail(e));
+    let reply = deciduous::replay::send(&llm_request(question, &context, model))
+        .unwrap_or_else(|e| fail(e));
+    let answer = reply_text(&reply).unwrap_or_else(|e| fail(e));
+    let cited = citations(&answer, &nodes);
+
+    if json {
+        let output = serde_json::json!({
+            "answer": answer,
+            "citations": cited,
+            "nodes": nodes,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output).unwrap_or_default()
+        );
+        return;
+    }
+    println!("{}", answer.trim());
+    if !cited.is_empty() {
+        println!();
+        println!("{}", "Sources:".cyan());
+        for node in nodes.iter().filter(|n| cited.contains(&n.id)) {
+            println!("  #{} {} ({})", node.id, node.title, node.node_type);
+        }
+    }
+}
+
+/// `deciduous audit --from-trailers`: link commits to the nodes their
+/// `Deciduous:` trailers name
+fn audit_from_trailers(db: &Database, dry_run: bool) {
+    let nodes = match db.get_all_nodes() {
+        Ok(n) => n,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+    let commits = deciduous::trailers::commits_with_trailers();
+    if commits.is_empty() {
+        println!(
+            "{} No commits with {}: trailers",
+            "Result:".cyan(),
+            deciduous::trailers::TRAILER
+        );
+        return;
+    }
+    let subjects: std::collections::HashMap<&str, &str> = commits
+        .iter()
+        .map(|c| (c.hash.as_str(), c.subject.as_str()))
+        .collect();
+    let plan = deciduous::trailers::plan(&nodes, &commits);
+    let short = |hash: &str| hash.chars().take(7).collect::<String>();
+
+    for (id, hash) in &plan.unknown {
+        eprintln!(
+            "{} {} names node #{}, which doesn't exist",
+            "Warning:".yellow(),
+            short(hash),
+            id
+        );
+    }
+    if !plan.already_linked.is_empty() {
+        println!(
+            "  {} referenced nodes already have a commit",
+            plan.already_linked.len()
+        );
+    }
+    if plan.links.is_empty() {
+        println!("{} Nothing to link", "Result:".cyan());
+        return;
+    }
+
+    for (id, hash) in &plan.links {
+        let subject = subjects.get(hash.as_str()).copied().unwrap_or_default();
+        if dry_run {
+            println!(
+                "{} Node #{} <- {}: {}",
+                "[DRY]".yellow(),
+                id,
+                short(hash),
+                truncate(subject, 55)
+            );
+            continue;
+        }
+        match db.update_node_commit(*id, hash) {
+            Ok(()) => println!(
+                "{} Node #{} <- {}: {}",
+                "Linked:".green(),
+                id,
+                short(hash),
+                truncate(subject, 55)
+            ),
+            Err(e) => eprintln!("{} Node #{}: {}", "Error:".red(), id, e),
+        }
+    }
+    if dry_run {
+        println!("\n{} Dry run - no changes made", "Info:".cyan());
+    }
+}
+
+/// `deciduous audit --dedupe`: list probable duplicates and merge them
+fn audit_dedupe(db: &Database, min_similarity: u8, window: i64, dry_run: bool, yes: bool) {
+    let nodes = match db.get_all_nodes() {
+        Ok(n) => n,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+    let found = deciduous::dedupe::find_duplicates(&nodes, min_similarity as f64 / 100.0, window);
+    if found.is_empty() {
+        println!("{} No probable duplicates", "Result:".cyan());
+        return;
+    }
+
+    let title = |id: i32| {
+        nodes
+            .iter()
+            .find(|n| n.id == id)
+            .map(|n| format!("#{} {} \"{}\"", n.id, n.node_type, truncate(&n.title, 50)))
+            .unwrap_or_default()
+    };
+    println!(
+        "{} {} probable duplicate(s):",
+        "Duplicates:".yellow(),
+        found.len()
+    );
+    for d in &found {
+        println!(
+            "\n  {}\n  {} ({}%, {})",
+            title(d.keep),
+            title(d.drop),
+            (d.similarity * 100.0) as u8,
+            d.reason
+        );
+        println!("    deciduous merge {} {}", d.keep, d.drop);
+    }
+
+    if dry_run {
+        println!("\n{} Dry run - no changes made", "Info:".cyan());
+        return;
+    }
+    if !yes {
+        print!("\nMerge {} pair(s), newer into older? [y/N]: ", found.len());
+        use std::io::Write;
+        std::io::stdout().flush().ok();
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input).is_err() || input.trim().to_lowercase() != "y" {
+            println!("{}", "Aborted".yellow());
+            return;
+        }
+    }
+
+    for d in &found {
+        match db.merge_nodes(d.keep, d.drop) {
+            Ok(_) => println!("{} node {} into {}", "Merged".green(), d.drop, d.keep),
+            Err(e) => eprintln!("{} #{}: {}", "Failed:".red(), d.drop, e),
+        }
+    }
+}
+
+/// `deciduous stale` and `audit --stale`: list stuck decisions and actions,
+/// optionally sending them to the on_stale hook
+fn report_stale(
+    db: &Database,
+    decision_days: Option<i64>,
+    action_days: Option<i64>,
+    notify: bool,
+    json: bool,
+) {
+    let config = Config::load();
+    let thresholds = deciduous::config::StaleConfig {
+        decision_days: decision_days.unwrap_or(config.stale.decision_days),
+        action_days: action_days.unwrap_or(config.stale.action_days),
+    };
+    let (nodes, edges) = match (db.get_all_nodes(), db.get_all_edges()) {
+        (Ok(nodes), Ok(edges)) => (nodes, edges),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+    let stale =
+        deciduous::stale::find_stale(&nodes, &edges, &thresholds, Local::now().fixed_offset());
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&stale).unwrap_or_default()
+        );
+    } else if stale.is_empty() {
+        println!(
+            "{} No decisions waiting over {} days or actions pending over {} days",
+            "Result:".cyan(),
+            thresholds.decision_days,
+            thresholds.action_days
+        );
+    } else {
+        println!("{:<5} {:<10} {:<34} TITLE", "ID", "TYPE", "WHY");
+        println!("{}", "-".repeat(70));
+        for s in &stale {
+            println!(
+                "{:<5} {:<10} {:<34} {}",
+                s.id,
+                s.node_type,
+                s.reason,
+                truncate(&s.title, 40)
+            );
+        }
+    }
+
+    if notify && !stale.is_empty() {
+        let hooks = config.hooks;
+        if hooks.command_for(deciduous::lifecycle::ON_STALE).is_none() {
+            eprintln!(
+                "{} --notify needs an on_stale command in [hooks]",
+                "Warning:".yellow()
+            );
+            return;
+        }
+        fire_hook(
+            &hooks,
+            deciduous::lifecycle::ON_STALE,
+            serde_json::json!({
+                "decision_days": thresholds.decision_days,
+                "action_days": thresholds.action_days,
+                "stale": stale,
+            }),
+        );
+    }
+}
+
+/// `deciduous sync`: write the graph export for a profile
+///
+/// Without a profile this is the GitHub Pages layout: docs/graph-data.json
+/// with the roadmap and git history next to it, copied to docs/demo/ when
+/// that directory exists.
+fn sync_export(
+    db: &Database,
+    output: Option<PathBuf>,
+    profile_name: Option<String>,
+    per_project: bool,
+) {
+    let config = Config::load();
+    let profile = match &profile_name {
+        Some(name) => match config.export.profile(name) {
+            Ok(profile) => profile,
+            Err(e) => {
+                eprintln!("{} {}", "Error:".red(), e);
+                std::process::exit(1);
+            }
+        },
+        None => ExportProfile {
+            mirrors: if std::path::Path::new("docs/demo").is_dir() {
+                vec!["docs/demo".to_string()]
+            } else {
+                Vec::new()
+            },
+            ..ExportProfile::default()
+        },
+    };
+    let redactor = if profile.redact {
+        match Redactor::from_config(&config.trace.redact) {
+            Ok(redactor) => Some(redactor),
+            Err(e) => {
+                eprintln!("{} {}", "Error:".red(), e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    let output_path = output.unwrap_or_else(|| PathBuf::from(&profile.output));
+    let file_name = output_path
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_else(|| "graph-data.json".into());
+    let output_dir = output_path
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_default();
+    let mirrors: Vec<PathBuf> = profile.mirrors.iter().map(PathBuf::from).collect();
+    for dir in std::iter::once(&output_dir).chain(&mirrors) {
+        std::fs::create_dir_all(dir).ok();
+    }
+
+    // Include config in the export (for external repo support, etc.)
+    let include_config = config.github.commit_repo.is_some();
+    let scrub = profile.redact || profile.strip_prompts;
+    // The graph is streamed from the database a batch at a time, so only the
+    // commit hashes and project names are kept from it
+    let (mut node_hits, mut edge_hits) = (0, 0);
+    let mut hashes = std::collections::BTreeSet::new();
+    let mut projects = std::collections::BTreeSet::new();
+    let written = write_graph_file(
+        db,
+        &output_path,
+        include_config.then_some(&config),
+        |node| {
+            if scrub {
+                node_hits += deciduous::scrub_node(node, redactor.as_ref(), profile.strip_prompts);
+            }
+            hashes.extend(commit_hash(node));
+            projects.extend(deciduous::project::project_of(node));
+            true
+        },
+        |edge| {
+            if scrub {
+                edge_hits += deciduous::scrub_edge(edge, redactor.as_ref());
+            }
+        },
+    );
+    let (node_count, edge_count) = match written {
+        Ok(counts) => counts,
+        Err(e) => {
+            eprintln!("{} Writing file: {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+    if node_hits + edge_hits > 0 {
+        println!(
+            "{} {} secret(s) in node text",
+            "Redacted".cyan(),
+            node_hits + edge_hits
+        );
+    }
+    println!("{} graph to {}", "Exported".green(), output_path.display());
+    println!("  {} nodes, {} edges", node_count, edge_count);
+    for dir in &mirrors {
+        if let Err(e) = std::fs::copy(&output_path, dir.join(&file_name)) {
+            eprintln!(
+                "{} Also writing to {}: {}",
+                "Warning:".yellow(),
+                dir.display(),
+                e
+            );
+        }
+    }
+
+    if per_project {
+        for project in &projects {
+            let dir = output_dir.join("projects").join(project);
+            let path = dir.join(&file_name);
+            let result = std::fs::create_dir_all(&dir).and_then(|_| {
+                write_graph_file(
+                    db,
+                    &path,
+                    include_config.then_some(&config),
+                    |node| {
+                        if deciduous::project::project_of(node).as_ref() != Some(project) {
+                            return false;
+                        }
+                        if scrub {
+                            deciduous::scrub_node(node, redactor.as_ref(), profile.strip_prompts);
+                        }
+                        true
+                    },
+                    |edge| {
+                        if scrub {
+                            deciduous::scrub_edge(edge, redactor.as_ref());
+                        }
+                    },
+                )
+            });
+            match result {
+                Ok((nodes, edges)) => println!(
+                    "{} {} to {} ({} nodes, {} edges)",
+                    "Exported".green(),
+                    project,
+                    path.display(),
+                    nodes,
+                    edges
+                ),
+                Err(e) => {
+                    eprintln!("{} Writing {}: {}", "Error:".red(), path.display(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    // Roadmap items and progress badge for the Pages viewer/README
+    if profile.includes("roadmap") {
+        let dirs: Vec<&std::path::Path> = std::iter::once(&output_dir)
+            .chain(&mirrors)
+            .map(|d| d.as_path())
+            .collect();
+        export_roadmap(db, &dirs);
+    }
+
+    // Export git history for linked commits
+    // Skip when external repo is configured (commits won't be in local git)
+    if profile.includes("git_history") && !include_config {
+        match export_git_history(&hashes, &output_dir) {
+            Ok(count) => {
+                if count > 0 {
+                    println!(
+                        "{} git-history.json ({} commits)",
+                        "Exported".green(),
+                        count
+                    );
+                }
+                for dir in &mirrors {
+                    if let Err(e) = export_git_history(&hashes, dir) {
+                        eprintln!(
+                            "{} Also writing git history to {}: {}",
+                            "Warning:".yellow(),
+                            dir.display(),
+                            e
+                        );
+                    }
+                }
+            }
+            Err(e) => {
+                // Non-fatal: git history is optional
+                eprintln!("{} Exporting git history: {}", "Warning:".yellow(), e);
+            }
+        }
+    } else if profile.includes("git_history") {
+        // External repo mode: preserve existing git-history.json
+        if output_dir.join("git-history.json").exists() {
+            println!(
+                "{} git-history.json (external repo mode - manually managed)",
+                "Preserved".cyan()
+            );
+        } else {
+            println!(
+                "{} Create {} manually for external repo commits",
+                "Note:".yellow(),
+                output_dir.join("git-history.json").display()
+            );
+        }
+    }
+
+    fire_hook(
+        &config.hooks,
+        deciduous::lifecycle::ON_SYNC,
+        serde_json::json!({
+            "output": output_path,
+            "profile": profile_name,
+            "nodes": node_count,
+            "edges": edge_count,
+        }),
+    );
+}
+
+/// Stream the graph export to `path` through `Database::stream_graph_json`
+fn write_graph_file(
+    db: &Database,
+    path: &std::path::Path,
+    config: Option<&Config>,
+    node: impl FnMut(&mut deciduous::DecisionNode) -> bool,
+    edge: impl FnMut(&mut deciduous::DecisionEdge),
+) -> std::io::Result<(usize, usize)> {
+    use std::io::Write;
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let counts = db.stream_graph_json(&mut out, config, node, edge)?;
+    out.flush()?;
+    Ok(counts)
+}
+
+/// Write roadmap-items.json and the shields.io badge.json next to graph-data.json
+/// (and into each mirror directory)
+fn export_roadmap(db: &Database, output_dirs: &[&std::path::Path]) {
+    let items = match db.get_all_roadmap_items() {
+        Ok(items) if !items.is_empty() => items,
+        Ok(_) => return,
+        Err(e) => {
+            eprintln!("{} Loading roadmap items: {}", "Warning:".yellow(), e);
+            return;
+        }
+    };
+
+    let badge = deciduous::roadmap_badge(&items);
+    let result = serde_json::to_string_pretty(&items)
+        .map_err(|e| e.to_string())
+        .and_then(|json| {
+            output_dirs.iter().try_for_each(|dir| {
+                std::fs::write(dir.join("roadmap-items.json"), &json)
+                    .and_then(|_| std::fs::write(dir.join("badge.json"), badge.to_string()))
+                    .map_err(|e| format!("{}: {}", dir.display(), e))
+            })
+        });
+
+    match result {
+        Ok(()) => println!(
+            "{} roadmap-items.json and badge.json (roadmap {})",
+            "Exported".green(),
+            badge["message"].as_str().unwrap_or_default()
+        ),
+        Err(e) => eprintln!("{} Exporting roadmap: {}", "Warning:".yellow(), e),
+    }
+}
+
+/// Tick ROADMAP.md checkboxes (and their issue checklists) for completed outcomes
+///
+/// The database checkbox is only updated once the issue has the tick too, so a
+/// failed issue update is pushed by the next `roadmap sync` instead of being
+/// mistaken for an edit made on the issue.
+fn reconcile_roadmap(
+    db: &Database,
+    roadmap_path: &std::path::Path,
+    repo: Option<String>,
+    dry_run: bool,
+) {
+    if !roadmap_path.exists() {
+        eprintln!(
+            "{} File not found: {}",
+            "Error:".red(),
+            roadmap_path.display()
+        );
+        std::process::exit(1);
+    }
+
+    let ready = match db.get_roadmap_items_ready_to_check() {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+    if ready.is_empty() {
+        println!(
+            "{} No unchecked items with completed outcomes",
+            "Reconcile:".cyan()
+        );
+        return;
+    }
+
+    let mut parsed = match parse_roadmap(roadmap_path) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{} Parsing roadmap: {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+
+    println!(
+        "{} {} items with completed outcomes",
+        if dry_run {
+            "Reconcile (dry run):".yellow()
+        } else {
+            "Reconcile:".cyan()
+        },
+        ready.len()
+    );
+
+    let mut line_changes = Vec::new();
+    // (issue number, checked texts, database item ids) per section with an issue
+    let mut issue_updates: Vec<(i32, Vec<String>, Vec<i32>)> = Vec::new();
+    let mut local_only: Vec<i32> = Vec::new();
+
+    for item in &ready {
+        let found = parsed
+            .sections
+            .iter_mut()
+            .filter(|s| item.section.as_deref() == Some(s.title.as_str()))
+            .find_map(|s| {
+                let issue = s.github_issue_number;
+                s.items
+                    .iter_mut()
+                    .find(|i| i.text == item.title && !i.checked)
+                    .map(|i| (issue, i))
+            });
+        let Some((issue, check_item)) = found else {
+            println!(
+                "  {} Not found in {}: {}",
+                "-".dimmed(),
+                roadmap_path.display(),
+                item.title
+            );
+            continue;
+        };
+
+        check_item.checked = true;
+        line_changes.push((check_item.line_number, true));
+        println!(
+            "  {} [x] {}{}",
+            if dry_run {
+                "[DRY]".yellow()
+            } else {
+                "✓".green()
+            },
+            item.title,
+            issue.map(|n| format!(" (#{})", n)).unwrap_or_default()
+        );
+
+        match issue {
+            Some(number) => match issue_updates.iter_mut().find(|(n, _, _)| *n == number) {
+                Some((_, texts, ids)) => {
+                    texts.push(item.title.clone());
+                    ids.push(item.id);
+                }
+                None => issue_updates.push((number, vec![item.title.clone()], vec![item.id])),
+            },
+            None => local_only.push(item.id),
+        }
+    }
+
+    if line_changes.is_empty() {
+        return;
+    }
+    if dry_run {
+        println!(
+            "\n{} {} checkboxes to tick (use --execute to apply)",
+            "Summary (dry run):".yellow(),
+            line_changes.len()
+        );
+        return;
+    }
+
+    let content = std::fs::read_to_string(roadmap_path).unwrap_or_default();
+    if let Err(e) = std::fs::write(roadmap_path, set_checkbox_lines(&content, &line_changes)) {
+        eprintln!("{} Writing roadmap: {}", "Error:".red(), e);
+        std::process::exit(1);
+    }
+
+    let mark_checked = |ids: &[i32]| {
+        for id in ids {
+            if let Err(e) = db.update_roadmap_item_checkbox(*id, "checked") {
+                eprintln!("    {} Updating database: {}", "Warning:".yellow(), e);
+            }
+        }
+    };
+    mark_checked(&local_only);
+
+    let mut issues_updated = 0;
+    if !issue_updates.is_empty() {
+        let config = Config::load();
+        match detect_forge(repo, &config) {
+            Ok(forge) => {
+                for (number, texts, ids) in &issue_updates {
+                    let result = forge.get_issue(*number).and_then(|issue| {
+                        forge.update_issue_body(*number, &check_items_in_body(&issue.body, texts))
+                    });
+                    match result {
+                        Ok(()) => {
+                            println!(
+                                "  {} Updated issue {}",
+                                "✓".green(),
+                                forge.issue_ref(*number)
+                            );
+                            mark_checked(ids);
+                            issues_updated += 1;
+                        }
+                        Err(e) => eprintln!(
+                            "  {} Updating issue {}: {}",
+                            "✗".red(),
+                            forge.issue_ref(*number),
+                            e
+                        ),
+                    }
+                }
+            }
+            Err(e) => eprintln!("{} Auto-detecting repo: {}", "Warning:".yellow(), e),
+        }
+    }
+
+    println!(
+        "\n{} {} checkboxes ticked, {} issues updated",
+        "Summary:".green(),
+        line_changes.len(),
+        issues_updated
+    );
+    if issues_updated < issue_updates.len() {
+        println!(
+            "  Run 'deciduous roadmap sync --execute' to push the remaining ticks to their issues"
+        );
+    }
+}
+
+/// Move fully-complete sections from ROADMAP.md into the archive file
+///
+/// Blocks move verbatim, metadata comments included, so archived sections
+/// keep their ids and issue links. Open issues are closed first and their
+/// archived status updated to match.
+fn archive_roadmap(
+    db: &Database,
+    roadmap_path: &std::path::Path,
+    archive_path: &std::path::Path,
+    repo: Option<String>,
+    dry_run: bool,
+) {
+    let content = match std::fs::read_to_string(roadmap_path) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!(
+                "{} Reading {}: {}",
+                "Error:".red(),
+                roadmap_path.display(),
+                e
+            );
+            std::process::exit(1);
+        }
+    };
+    let parsed = match parse_roadmap(roadmap_path) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{} Parsing roadmap: {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let split = split_completed_sections(&content, &parsed.sections);
+    if split.sections.is_empty() {
+        println!("{} No fully-complete sections", "Archive:".cyan());
+        return;
+    }
+
+    println!(
+        "{} {} sections -> {}",
+        if dry_run {
+            "Archive (dry run):".yellow()
+        } else {
+            "Archive:".cyan()
+        },
+        split.sections.len(),
+        archive_path.display()
+    );
+    for section in &split.sections {
+        let issue = match (
+            section.github_issue_number,
+            section.github_issue_state.as_deref(),
+        ) {
+            (Some(n), Some("closed")) => format!(" (#{}, closed)", n),
+            (Some(n), _) => format!(" (close #{})", n),
+            (None, _) => String::new(),
+        };
+        println!(
+            "  {} {} [{} items]{}",
+            if dry_run {
+                "[DRY]".yellow()
+            } else {
+                "→".cyan()
+            },
+            section.title,
+            section.items.len(),
+            issue
+        );
+    }
+
+    if dry_run {
+        println!("\nRun with --execute to archive");
+        return;
+    }
+
+    let to_close: Vec<i32> = split
+        .sections
+        .iter()
+        .filter(|s| s.github_issue_state.as_deref() != Some("closed"))
+        .filter_map(|s| s.github_issue_number)
+        .collect();
+    let mut archived = split.archived.clone();
+    let mut closed = 0;
+    if !to_close.is_empty() {
+        let config = Config::load();
+        match detect_forge(repo, &config) {
+            Ok(forge) => {
+                for number in &to_close {
+                    match forge.close_issue(*number) {
+                        Ok(()) => {
+                            println!(
+                                "  {} Closed issue {}",
+                                "✓".green(),
+                                forge.issue_ref(*number)
+                            );
+                            archived = archived.replace(
+                                &format!(r#"issue="{}" status="open""#, number),
+                                &format!(r#"issue="{}" status="closed""#, number),
+                            );
+                            if let Err(e) = db.update_roadmap_issue_state(*number, "closed") {
+                                eprintln!("    {} Updating database: {}", "Warning:".yellow(), e);
+                            }
+                            closed += 1;
+                        }
+                        Err(e) => eprintln!(
+                            "  {} Closing issue {}: {}",
+                            "✗".red(),
+                            forge.issue_ref(*number),
+                            e
+                        ),
+                    }
+                }
+            }
+            Err(e) => eprintln!("{} Auto-detecting repo: {}", "Warning:".yellow(), e),
+        }
+    }
+
+    let mut archive_content = std::fs::read_to_string(archive_path).unwrap_or_else(|_| {
+        "# Roadmap Archive\n\nCompleted sections moved out of ROADMAP.md by `deciduous roadmap archive`.".to_string()
+    });
+    archive_content.truncate(archive_content.trim_end().len());
+    archive_content.push_str("\n\n");
+    archive_content.push_str(archived.trim_end());
+    archive_content.push('\n');
+    if let Err(e) = std::fs::write(archive_path, archive_content) {
+        eprintln!(
+            "{} Writing {}: {}",
+            "Error:".red(),
+            archive_path.display(),
+            e
+        );
+        std::process::exit(1);
+    }
+    if let Err(e) = std::fs::write(roadmap_path, &split.remaining) {
+        eprintln!(
+            "{} Writing {}: {}",
+            "Error:".red(),
+            roadmap_path.display(),
+            e
+        );
+        std::process::exit(1);
+    }
+
+    let mut rows = 0;
+    for section in &split.sections {
+        match db.archive_roadmap_section(&section.title) {
+            Ok(n) => rows += n,
+            Err(e) => eprintln!("  {} Marking archived: {}", "Warning:".yellow(), e),
+        }
+    }
+
+    println!(
+        "\n{} {} sections archived, {} issues closed, {} database rows marked",
+        "Summary:".green(),
+        split.sections.len(),
+        closed,
+        rows
+    );
+    if closed < to_close.len() {
+        println!("  Close the remaining issues by hand; the archive still lists them as open");
+    }
+}
+
+/// Location of a git hook, honoring `core.hooksPath`
+fn git_hook_path(name: &str) -> PathBuf {
+    let hook = format!("hooks/{}", name);
+    ProcessCommand::new("git")
+        .args(["rev-parse", "--git-path", &hook])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| PathBuf::from(String::from_utf8_lossy(&o.stdout).trim()))
+        .unwrap_or_else(|| PathBuf::from(".git").join(hook))
+}
+
+/// Run the `[hooks]` command for a lifecycle event, warning if it fails
+/// Metadata for `add --issue`: a Jira key stands alone, `#N` or `N` is an
+/// issue on the configured forge
+fn resolve_issue_ref(reference: &str) -> Result<serde_json::Value, String> {
+    if let Some((project, number)) = deciduous::jira::parse_issue_key(reference) {
+        return Ok(serde_json::json!({
+            "repo": project,
+            "number": number,
+            "key": format!("{}-{}", project, number),
+            "forge": "jira",
+        }));
+    }
+    let number: i32 = reference
+        .trim()
+        .trim_start_matches('#')
+        .parse()
+        .map_err(|_| format!("'{}' is not an issue key (PROJ-123) or #number", reference))?;
+    let forge = detect_forge(None, &Config::load()).map_err(|e| e.to_string())?;
+    let repo = forge
+        .repo_name()
+        .ok_or("Could not tell which repository the issue is in")?;
+    Ok(serde_json::json!({
+        "repo": repo,
+        "number": number,
+        "key": forge.issue_ref(number),
+        "forge": forge.kind().name().to_lowercase(),
+    }))
+}
+
+fn fire_hook(hooks: &deciduous::config::HooksConfig, event: &str, data: serde_json::Value) {
+    if let Err(e) = deciduous::lifecycle::run_hook(hooks, event, data) {
+        eprintln!("{} {}", "Warning:".yellow(), e);
+    }
+}
+
+/// Add an applied patch to the ledger, warning if that fails
+///
+/// The author is recorded under their team roster name.
+fn record_applied(
+    db: &Database,
+    path: &str,
+    sha256: &str,
+    commit: Option<&str>,
+    patch: &deciduous::GraphPatch,
+) {
+    let author = patch
+        .author
+        .as_deref()
+        .map(|a| Config::load().display_name(a));
+    if let Err(e) = db.record_applied_patch(path, sha256, commit, author.as_deref()) {
+        eprintln!(
+            "{} Recording {} as applied: {}",
+            "Warning:".yellow(),
+            path,
+            e
+        );
+    }
+}
+
+/// Remember which nodes a patch file carries, for `diff export --new`
+fn record_exports(
+    db: &Database,
+    patch: &deciduous::GraphPatch,
+    path: &std::path::Path,
+    branch: Option<&str>,
+) {
+    let change_ids: Vec<&str> = patch
+        .nodes
+        .iter()
+        .map(|n| n.change_id.as_str())
+        .chain(patch.updated_nodes.iter().map(|u| u.change_id.as_str()))
+        .collect();
+    if let Err(e) = db.record_node_exports(&change_ids, &path.to_string_lossy(), branch) {
+        eprintln!(
+            "{} Couldn't record the export ({}); the next --new export will repeat these nodes",
+            "Warning:".yellow(),
+            e
+        );
+    }
+}
+
+/// Write an executable hook script, exiting on failure
+fn write_hook(hook_path: &std::path::Path, content: &str) {
+    if let Some(dir) = hook_path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Err(e) = std::fs::write(hook_path, content) {
+        eprintln!("{} Writing {}: {}", "Error:".red(), hook_path.display(), e);
+        std::process::exit(1);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(hook_path, std::fs::Permissions::from_mode(0o755));
+    }
+}
+
+/// The editors picked with init/update flags; Claude Code when none are
+fn selected_editors(
+    claude: bool,
+    windsurf: bool,
+    opencode: bool,
+    codex: bool,
+    zed: bool,
+    gemini: bool,
+) -> Vec<deciduous::init::Editor> {
+    use deciduous::init::Editor;
+    let flags = [
+        (claude, Editor::Claude),
+        (windsurf, Editor::Windsurf),
+        (opencode, Editor::Opencode),
+        (codex, Editor::Codex),
+        (zed, Editor::Zed),
+        (gemini, Editor::Gemini),
+    ];
+    let editors: Vec<Editor> = flags
+        .into_iter()
+        .filter(|(on, _)| *on)
+        .map(|(_, editor)| editor)
+        .collect();
+    if editors.is_empty() {
+        vec![Editor::Claude]
+    } else {
+        editors
+    }
+}
+
+/// The init/update preview mode for `--dry-run` and `--diff`
+fn init_preview(dry_run: bool, diff: bool) -> deciduous::init::Preview {
+    if diff {
+        deciduous::init::Preview::Diff
+    } else if dry_run {
+        deciduous::init::Preview::DryRun
+    } else {
+        deciduous::init::Preview::Off
+    }
+}
+
+fn run_uninit(keep_db: bool, force: bool, dry_run: bool, yes: bool) {
+    use deciduous::manifest::{Manifest, Removal, MANIFEST_PATH};
+
+    let root = match std::env::current_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("{} Could not get current directory: {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+    let manifest = match Manifest::load(&root) {
+        Ok(Some(manifest)) => manifest,
+        Ok(None) => {
+            eprintln!(
+                "{} No {} here. Projects initialized before deciduous recorded what \
+                 init creates have to be cleaned up by hand.",
+                "Error:".red(),
+                MANIFEST_PATH
+            );
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let steps = deciduous::manifest::plan(&root, &manifest, keep_db, force);
+    if steps.is_empty() {
+        println!("Nothing to remove");
+        return;
+    }
+    for step in &steps {
+        let line = step.describe();
+        match step {
+            Removal::Modified(_) => println!("  {}", line.yellow()),
+            _ => println!("  {}", line),
+        }
+    }
+    if keep_db {
+        println!("  Keep .deciduous/");
+    }
+    if dry_run {
+        println!("\n{} nothing removed", "Dry run:".cyan());
+        return;
+    }
+
+    if !yes {
+        print!("\nRemove these? [y/N]: ");
+        use std::io::Write;
+        std::io::stdout().flush().ok();
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input).is_err() || input.trim().to_lowercase() != "y" {
+            println!("{}", "Aborted".yellow());
+            return;
+        }
+    }
+
+    match deciduous::manifest::apply(&root, &steps, manifest.gitignore_created) {
+        Ok(kept) => {
+            for dir in kept {
+                println!("{} {}/ (not empty)", "Kept".yellow(), dir);
+            }
+            println!("{} deciduous removed from this project", "Success:".green());
+        }
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run_config(action: ConfigAction) {
+    let path = Config::find_config_path();
+    let content = path.as_ref().and_then(|p| std::fs::read_to_string(p).ok());
+    let config: Config = content
+        .as_deref()
+        .and_then(|c| toml::from_str(c).ok())
+        .unwrap_or_default();
+
+    match action {
+        ConfigAction::Get { key } => {
+            let values = config.effective_values(content.as_deref());
+            let matching: Vec<_> = values
+                .iter()
+                .filter(|(k, _, _)| *k == key || k.starts_with(&format!("{}.", key)))
+                .collect();
+            if matching.is_empty() {
+                eprintln!("{} {} is not set", "Error:".red(), key);
+                std::process::exit(1);
+            }
+            match matching.as_slice() {
+                [(k, value, _)] if *k == key => println!("{}", value),
+                _ => {
+                    for (k, value, _) in matching {
+                        println!("{} = {}", k, value);
+                    }
+                }
+            }
+        }
+
+        ConfigAction::Set { key, value } => {
+            let path = path.unwrap_or_else(|| PathBuf::from(".deciduous").join("config.toml"));
+            let updated = match deciduous::config::set_config_value(
+                content.as_deref().unwrap_or(""),
+                &key,
+                &value,
+            ) {
+                Ok(u) => u,
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            };
+            if let Some(parent) = path.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+            if let Err(e) = std::fs::write(&path, updated) {
+                eprintln!("{} Writing {}: {}", "Error:".red(), path.display(), e);
+                std::process::exit(1);
+            }
+            println!("{} {} in {}", "Set".green(), key, path.display());
+        }
+
+        ConfigAction::List => {
+            let file = path
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|| "no config file".to_string());
+            println!("{} {}", "Config:".cyan(), file);
+            if content
+                .as_deref()
+                .is_some_and(|c| !deciduous::config::validate_config(c).is_empty())
+            {
+                eprintln!(
+                    "{} The config file has problems; see 'deciduous config validate'",
+                    "Warning:".yellow()
+                );
+            }
+            for (key, value, source) in config.effective_values(content.as_deref()) {
+                let source = match source {
+                    deciduous::config::ConfigSource::Default => "default".to_string(),
+                    deciduous::config::ConfigSource::File => "file".to_string(),
+                    deciduous::config::ConfigSource::Env(var) => format!("env {}", var),
+                };
+                println!("{} = {} {}", key, value, format!("({})", source).dimmed());
+            }
+        }
+
+        ConfigAction::Validate => {
+            let (Some(path), Some(content)) = (&path, &content) else {
+                println!(
+                    "{} No .deciduous/config.toml found; defaults apply",
+                    "Info:".cyan()
+                );
+                return;
+            };
+            let problems = deciduous::config::validate_config(content);
+            if problems.is_empty() {
+                println!("{} {} is valid", "Success:".green(), path.display());
+                return;
+            }
+            for problem in &problems {
+                eprintln!("{} {}: {}", "Error:".red(), path.display(), problem);
+            }
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run_hooks(action: HooksAction) {
+    match action {
+        HooksAction::Install {
+            strict,
+            sign,
+            append,
+        } => {
+            let hook_path = git_hook_path("pre-push");
+            let existing = std::fs::read_to_string(&hook_path).ok();
+            let content =
+                match deciduous::hooks::add_hook_block(existing.as_deref(), strict, sign, append) {
+                    Ok(Some(c)) => c,
+                    Ok(None) => {
+                        println!(
+                            "{} Push hook already installed in {}",
+                            "Info:".cyan(),
+                            hook_path.display()
+                        );
+                        return;
+                    }
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                };
+            write_hook(&hook_path, &content);
+            println!(
+                "{} Installed push hook in {}{}",
+                "Success:".green(),
+                hook_path.display(),
+                if strict { " (strict)" } else { "" }
+            );
+        }
+
+        HooksAction::Uninstall => {
+            let hook_path = git_hook_path("pre-push");
+            let removed = std::fs::read_to_string(&hook_path)
+                .ok()
+                .and_then(|c| deciduous::hooks::remove_hook_block(&c));
+            let result = match removed {
+                None => {
+                    println!("{} Push hook not installed", "Info:".cyan());
+                    return;
+                }
+                Some(rest) if rest.is_empty() => std::fs::remove_file(&hook_path),
+                Some(rest) => std::fs::write(&hook_path, rest),
+            };
+            if let Err(e) = result {
+                eprintln!("{} Updating {}: {}", "Error:".red(), hook_path.display(), e);
+                std::process::exit(1);
+            }
+            println!(
+                "{} Removed push hook from {}",
+                "Success:".green(),
+                hook_path.display()
+            );
+        }
+
+        HooksAction::PrePush {
+            patches,
+            strict,
+            sign,
+        } => {
+            // Nothing to export without a database, and main branches don't get patches
+            if !Database::db_path().exists() {
+                return;
+            }
+            let Some(branch) = deciduous::get_current_git_branch() else {
+                return;
+            };
+            let config = Config::load();
+            if config.is_main_branch(&branch) {
+                return;
+            }
+            let db = match Database::open() {
+                Ok(db) => db,
+                Err(e) => {
+                    eprintln!("{} Failed to open database: {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            };
+
+            let user = config.author().unwrap_or_default();
+            let patches_dir = patches.unwrap_or_else(|| PathBuf::from(".deciduous/patches"));
+            let path = deciduous::hooks::branch_patch_path(&patches_dir, &user, &branch);
+
+            let mut patch = match deciduous::hooks::branch_patch(
+                &db,
+                &path,
+                &branch,
+                &user,
+                deciduous::get_current_git_commit(),
+            ) {
+                Ok(Some(p)) => p,
+                Ok(None) => return,
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            };
+            if sign {
+                let signed = deciduous::signing::key_path(&config)
+                    .and_then(|key_path| deciduous::signing::SigningKey::load_or_create(&key_path))
+                    .and_then(|key| key.sign(&mut patch));
+                if let Err(e) = signed {
+                    eprintln!("{} {}", "Error:".red(), e);
+                    std::process::exit(1);
+                }
+            }
+            if let Err(e) = patch.save(&path) {
+                eprintln!("{} {}", "Error:".red(), e);
+                std::process::exit(1);
+            }
+            record_exports(&db, &patch, &path, Some(branch.as_str()));
+            let staged = ProcessCommand::new("git")
+                .arg("add")
+                .arg(&path)
+                .status()
+                .is_ok_and(|s| s.success());
+
+            println!(
+                "{} Exported {} nodes and {} edges to {}{}",
+                "Success:".green(),
+                patch.nodes.len(),
+                patch.edges.len(),
+                path.display(),
+                if staged { " (staged)" } else { "" }
+            );
+            if !staged {
+                eprintln!(
+                    "{} Couldn't stage {}; add it by hand",
+                    "Warning:".yellow(),
+                    path.display()
+                );
+            }
+            // The push was already decided before the hook ran, so the patch
+            // only travels with the next commit
+            if strict {
+                eprintln!("{} Patch changed; commit it and push again", "Error:".red());
+                std::process::exit(1);
+            }
+            println!("  Commit it so the next push carries it");
+        }
+    }
+}
+
+/// Report differences between ROADMAP.md and the database; true when in sync
+fn check_roadmap_drift(db: &Database, roadmap_path: &std::path::Path) -> bool {
+    let items = match db.get_all_roadmap_items() {
+        Ok(i) => i,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+    if items.is_empty() {
+        println!(
+            "{} Roadmap not tracked yet; run 'deciduous roadmap init'",
+            "Info:".cyan()
+        );
+        return true;
+    }
+    let parsed = match parse_roadmap(roadmap_path) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{} Parsing roadmap: {}", "Error:".red(), e);
+            return false;
+        }
+    };
+
+    let drift = roadmap_drift(&parsed.sections, &items);
+    if drift.is_empty() {
+        println!(
+            "{} {} matches the database",
+            "✓".green(),
+            roadmap_path.display()
+        );
+        return true;
+    }
+
+    println!(
+        "{} {} differs from the database in {} places:",
+        "Warning:".yellow(),
+        roadmap_path.display(),
+        drift.len()
+    );
+    for change in &drift {
+        println!("  {}", change);
+    }
+    println!("Run 'deciduous roadmap refresh' to record the edits, or 'deciduous roadmap sync' to push them");
+    false
+}
+
+/// Re-run the drift check whenever ROADMAP.md changes, until interrupted
+fn watch_roadmap_drift(db: &Database, roadmap_path: &std::path::Path) {
+    use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+    use std::time::Duration;
+
+    // Editors often replace the file rather than modify it, so watch the directory
+    let dir = match roadmap_path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let file_name = roadmap_path.file_name().map(|n| n.to_os_string());
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let watcher = RecommendedWatcher::new(
+        move |res: Result<notify::Event, notify::Error>| {
+            if let Ok(event) = res {
+                let touches_roadmap = event
+                    .paths
+                    .iter()
+                    .any(|p| p.file_name().map(|n| n.to_os_string()) == file_name);
+                if touches_roadmap && !event.kind.is_access() {
+                    let _ = tx.send(());
                 }
+            }
+        },
+        notify::Config::default(),
+    );
+    let mut watcher = match watcher {
+        Ok(w) => w,
+        Err(e) => {
+            eprintln!("{} Starting watcher: {}", "Error:".red(), e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+        eprintln!("{} Watching {}: {}", "Error:".red(), dir.display(), e);
+        std::process::exit(1);
+    }
 
-                TraceAction::Prune {
-                    days,
-                    keep_linked,
-                    dry_run,
-                } => {
-                    if dry_run {
-                        println!(
-                            "{} Would prune traces older than {} days{}",
-                            "[DRY RUN]".yellow(),
-                            days,
-                            if keep_linked { " (keeping linked)" } else { "" }
-                        );
-                        // TODO: Add count of what would be deleted
-                        return;
-                    }
+    println!(
+        "{} {} (Ctrl+C to stop)\n",
+        "Watching".cyan(),
+        roadmap_path.display()
+    );
+    check_roadmap_drift(db, roadmap_path);
+    while rx.recv().is_ok() {
+        // Let a burst of save events settle before re-parsing
+        while rx.recv_timeout(Duration::from_millis(200)).is_ok() {}
+        println!(
+            "\n{} {}",
+            "Changed:".cyan(),
+            Local::now().format("%H:%M:%S")
+        );
+        check_roadmap_drift(db, roadmap_path);
+    }
+}
 
-                    match db.prune_traces(days, keep_linked) {
-                        Ok((sessions, spans, content)) => {
-                            println!(
-                                "{} Pruned {} sessions, {} spans, {} content items",
-                                "Success:".green(),
-                                sessions,
-                                spans,
-                                content
-                            );
-                        }
-                        Err(e) => {
-                            eprintln!("{} {}", "Error:".red(), e);
-                            std::process::exit(1);
-                        }
+/// Store a section's checkbox items, linking sub-items to their parent item
+fn store_roadmap_items(db: &Database, section: &RoadmapSection, items_section: Option<&str>) {
+    let mut ids: std::collections::HashMap<&str, i32> = std::collections::HashMap::new();
+
+    for item in &section.items {
+        let state = if item.checked { "checked" } else { "unchecked" };
+        let parent_id = item
+            .parent_change_id
+            .as_deref()
+            .and_then(|p| ids.get(p).copied());
+        match db.create_roadmap_item(&item.text, None, items_section, parent_id, state) {
+            Ok(id) => {
+                ids.insert(&item.change_id, id);
+                if item.priority.is_some() || item.due.is_some() {
+                    if let Err(e) = db.set_roadmap_item_schedule(
+                        id,
+                        item.priority.map(i32::from),
+                        item.due.as_deref(),
+                    ) {
+                        eprintln!("{} Setting priority/due: {}", "Warning:".yellow(), e);
                     }
                 }
             }
+            Err(e) => eprintln!("{} Creating roadmap item: {}", "Warning:".yellow(), e),
         }
+    }
+}
 
-        Command::Proxy { command, auto_link } => {
-            if command.is_empty() {
-                eprintln!("{} No command specified", "Error:".red());
-                std::process::exit(1);
-            }
+/// Issue-side changes found by `pull_roadmap_from_issues`
+#[derive(Default)]
+struct RoadmapPull {
+    /// ROADMAP.md checkbox lines to rewrite (1-indexed line, checked)
+    line_changes: Vec<(usize, bool)>,
+    checkboxes: usize,
+    states: usize,
+    conflicts: usize,
+    /// Sections whose issue must not be overwritten (open conflicts or fetch failures)
+    held: std::collections::HashSet<String>,
+}
 
-            // Ensure the embedded interceptor is installed
-            let interceptor_path = match deciduous::interceptor::ensure_interceptor_installed() {
-                Ok(path) => path,
-                Err(e) => {
-                    eprintln!("{} Installing trace interceptor: {}", "Error:".red(), e);
-                    std::process::exit(1);
-                }
-            };
+fn checkbox_label(checked: bool, text: &str) -> String {
+    format!("[{}] {}", if checked { "x" } else { " " }, text)
+}
 
-            // Check if debug output is enabled (default: silent to avoid TUI interference)
-            let trace_debug = std::env::var("DECIDUOUS_TRACE_DEBUG")
-                .map(|v| v == "1" || v == "true")
-                .unwrap_or(false);
+/// Pull checkbox and open/closed changes made on issues into the parsed roadmap
+///
+/// Each section with an issue is compared three ways against the checkbox
+/// state stored in `roadmap_items` at the last sync. Changes made only on the
+/// issue are applied to `sections` (and the database, outside dry runs);
+/// disagreements go to `roadmap_conflicts` and hold back that section's push.
+fn pull_roadmap_from_issues(
+    db: &Database,
+    forge: &dyn deciduous::forge::Forge,
+    sections: &mut [RoadmapSection],
+    dry_run: bool,
+) -> RoadmapPull {
+    let mut pull = RoadmapPull::default();
+    let open_conflicts = db.get_unresolved_conflicts().unwrap_or_default();
+    let marker = if dry_run {
+        "[DRY]".yellow()
+    } else {
+        "↓".cyan()
+    };
 
-            // Generate session ID and start trace session
-            let session_id = uuid::Uuid::new_v4().to_string();
-            let working_dir = std::env::current_dir()
-                .ok()
-                .map(|p| p.to_string_lossy().to_string());
-            let git_branch = std::process::Command::new("git")
-                .args(["branch", "--show-current"])
-                .output()
-                .ok()
-                .filter(|o| o.status.success())
-                .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string());
-            let cmd_str = command.join(" ");
+    for section in sections.iter_mut().filter(|s| s.level == 3) {
+        let Some(issue_num) = section.github_issue_number else {
+            continue;
+        };
+        let issue = match forge.get_issue(issue_num) {
+            Ok(issue) => issue,
+            Err(e) => {
+                eprintln!(
+                    "  {} Fetching issue {}: {}",
+                    "✗".red(),
+                    forge.issue_ref(issue_num),
+                    e
+                );
+                pull.held.insert(section.change_id.clone());
+                continue;
+            }
+        };
 
-            match db.start_trace_session(
-                &session_id,
-                working_dir.as_deref(),
-                git_branch.as_deref(),
-                Some(&cmd_str),
-            ) {
-                Ok(_) => {
-                    if trace_debug {
-                        println!(
-                            "{} Started trace session {}",
-                            "Trace:".cyan(),
-                            &session_id[..8]
-                        );
-                    }
+        if section.github_issue_state.as_deref() != Some(issue.state.as_str()) {
+            println!(
+                "  {} Issue {} is {}: {}",
+                marker,
+                forge.issue_ref(issue_num),
+                issue.state,
+                section.title
+            );
+            section.github_issue_state = Some(issue.state.clone());
+            pull.states += 1;
+            if !dry_run {
+                if let Err(e) = db.update_roadmap_issue_state(issue_num, &issue.state) {
+                    eprintln!("    {} Updating database: {}", "Warning:".yellow(), e);
                 }
-                Err(e) => {
-                    eprintln!("{} Starting trace session: {}", "Error:".red(), e);
-                    std::process::exit(1);
+            }
+        }
+
+        let db_items: Vec<_> = db
+            .get_roadmap_items_by_section(&section.title)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|i| i.checkbox_state != "none")
+            .collect();
+        let baseline: std::collections::HashMap<String, bool> = db_items
+            .iter()
+            .map(|i| (i.title.clone(), i.is_checked()))
+            .collect();
+
+        // Conflicts from earlier runs clear once both sides agree again
+        let remote = parse_issue_body_checkboxes(&issue.body);
+        let mut still_open = false;
+        for conflict in &open_conflicts {
+            let converged = match conflict.conflict_type.as_str() {
+                "checkbox" => section
+                    .items
+                    .iter()
+                    .find(|i| i.change_id == conflict.item_change_id)
+                    .map(|i| remote.iter().any(|(t, c)| *t == i.text && *c == i.checked)),
+                "remote_item" if conflict.item_change_id == section.change_id => {
+                    conflict.remote_value.as_deref().map(|text| {
+                        section.items.iter().any(|i| i.text == text)
+                            || !remote.iter().any(|(t, _)| t == text)
+                    })
+                }
+                _ => None,
+            };
+            match converged {
+                Some(true) if !dry_run => {
+                    if let Err(e) = db.resolve_roadmap_conflict(conflict.id, "converged") {
+                        eprintln!("    {} Resolving conflict: {}", "Warning:".yellow(), e);
+                    }
                 }
+                Some(false) => still_open = true,
+                _ => {}
             }
+        }
 
-            // Auto-link to most recent goal if requested
-            if auto_link {
-                if let Ok(nodes) = db.get_all_nodes() {
-                    // Find most recent goal node
-                    if let Some(goal) = nodes
-                        .iter()
-                        .filter(|n| n.node_type == "goal")
-                        .max_by_key(|n| &n.created_at)
-                    {
-                        if let Err(e) = db.link_trace_session_to_node(&session_id, goal.id) {
-                            if trace_debug {
-                                eprintln!(
-                                    "{} Auto-linking to goal #{}: {}",
-                                    "Warning:".yellow(),
-                                    goal.id,
-                                    e
-                                );
-                            }
-                        } else if trace_debug {
-                            println!(
-                                "  {} Linked to goal #{}: {}",
-                                "→".yellow(),
-                                goal.id,
-                                truncate(&goal.title, 50)
-                            );
+        let mut record_conflict =
+            |item_change_id: &str, kind: &str, local: Option<String>, remote: Option<String>| {
+                pull.conflicts += 1;
+                still_open = true;
+                let known = open_conflicts.iter().any(|c| {
+                    c.item_change_id == item_change_id
+                        && c.conflict_type == kind
+                        && c.remote_value == remote
+                });
+                if !dry_run && !known {
+                    if let Err(e) = db.create_roadmap_conflict(
+                        item_change_id,
+                        kind,
+                        local.as_deref(),
+                        remote.as_deref(),
+                    ) {
+                        eprintln!("    {} Recording conflict: {}", "Warning:".yellow(), e);
+                    }
+                }
+            };
+
+        for change in diff_remote_checkboxes(section, &issue.body, &baseline) {
+            match change {
+                RemoteChange::Checkbox { text, checked } => {
+                    println!(
+                        "  {} Pull from #{}: {}",
+                        marker,
+                        issue_num,
+                        checkbox_label(checked, &text)
+                    );
+                    if let Some(item) = section.items.iter_mut().find(|i| i.text == text) {
+                        item.checked = checked;
+                        pull.line_changes.push((item.line_number, checked));
+                    }
+                    pull.checkboxes += 1;
+                    let db_item = db_items.iter().find(|i| i.title == text);
+                    if let Some(db_item) = db_item.filter(|_| !dry_run) {
+                        let state = if checked { "checked" } else { "unchecked" };
+                        if let Err(e) = db.update_roadmap_item_checkbox(db_item.id, state) {
+                            eprintln!("    {} Updating database: {}", "Warning:".yellow(), e);
                         }
                     }
                 }
+                RemoteChange::Conflict {
+                    text,
+                    local,
+                    remote,
+                } => {
+                    println!(
+                        "  {} Conflict on #{}: '{}' is {} locally, {} on the issue",
+                        "!".yellow(),
+                        issue_num,
+                        text,
+                        if local { "checked" } else { "unchecked" },
+                        if remote { "checked" } else { "unchecked" }
+                    );
+                    let item_change_id = section
+                        .items
+                        .iter()
+                        .find(|i| i.text == text)
+                        .map(|i| i.change_id.clone())
+                        .unwrap_or_else(|| section.change_id.clone());
+                    record_conflict(
+                        &item_change_id,
+                        "checkbox",
+                        Some(checkbox_label(local, &text)),
+                        Some(checkbox_label(remote, &text)),
+                    );
+                }
+                RemoteChange::RemoteOnly { text, checked } => {
+                    println!(
+                        "  {} Conflict on #{}: {} only exists on the issue",
+                        "!".yellow(),
+                        issue_num,
+                        checkbox_label(checked, &text)
+                    );
+                    record_conflict(&section.change_id, "remote_item", None, Some(text));
+                }
             }
+        }
 
-            // Build environment with NODE_OPTIONS
-            let node_options = format!("--require {}", interceptor_path.to_string_lossy());
-            let existing_node_options = std::env::var("NODE_OPTIONS").unwrap_or_default();
-            let full_node_options = if existing_node_options.is_empty() {
-                node_options
-            } else {
-                format!("{} {}", existing_node_options, node_options)
-            };
+        if still_open {
+            pull.held.insert(section.change_id.clone());
+        }
+    }
 
-            // Get path to this deciduous binary for the interceptor
-            let deciduous_bin = std::env::current_exe()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|_| "deciduous".to_string());
+    pull
+}
 
-            // Spawn child process
-            let (cmd, args) = command.split_first().unwrap();
-            let mut child = match std::process::Command::new(cmd)
-                .args(args)
-                .env("NODE_OPTIONS", &full_node_options)
-                .env("DECIDUOUS_TRACE_SESSION", &session_id)
-                .env("DECIDUOUS_BIN", &deciduous_bin)
-                .spawn()
-            {
-                Ok(c) => c,
-                Err(e) => {
-                    eprintln!("{} Spawning command '{}': {}", "Error:".red(), cmd, e);
-                    let _ = db.end_trace_session(&session_id, Some("Failed to spawn"));
-                    std::process::exit(1);
-                }
-            };
+/// Record a section's checkboxes as the state both sides agree on
+fn record_roadmap_baseline(db: &Database, section: &RoadmapSection) {
+    let Ok(db_items) = db.get_roadmap_items_by_section(&section.title) else {
+        return;
+    };
+    for item in &section.items {
+        let Some(db_item) = db_items
+            .iter()
+            .find(|i| i.title == item.text && i.checkbox_state != "none")
+        else {
+            continue;
+        };
+        let state = if item.checked { "checked" } else { "unchecked" };
+        let result = db
+            .update_roadmap_item_checkbox(db_item.id, state)
+            .and_then(|_| db.update_roadmap_item_synced(db_item.id));
+        if let Err(e) = result {
+            eprintln!("    {} Updating database: {}", "Warning:".yellow(), e);
+        }
+    }
+}
 
-            // Wait for child to complete
-            let exit_status = match child.wait() {
-                Ok(status) => status,
-                Err(e) => {
-                    eprintln!("{} Waiting for command: {}", "Error:".red(), e);
-                    let _ = db.end_trace_session(&session_id, Some("Wait failed"));
-                    std::process::exit(1);
-                }
-            };
+/// Create observation nodes for issue comments not yet in the graph
+///
+/// Each observation hangs off `node_id` and records the comment id in its
+/// metadata so re-runs only pick up new comments. Returns how many were added.
+fn import_issue_comments(
+    db: &Database,
+    forge: &dyn deciduous::forge::Forge,
+    repo: &str,
+    issue_number: i32,
+    node_id: i32,
+    dry_run: bool,
+) -> Result<usize, String> {
+    let comments = forge
+        .get_issue_comments(issue_number)
+        .map_err(|e| e.to_string())?;
+
+    let mut added = 0;
+    for comment in comments {
+        // Our own sync comments aren't discussion
+        if comment.body.contains(deciduous::forge::PR_COMMENT_MARKER) {
+            continue;
+        }
+        if db
+            .find_node_by_issue_comment(repo, comment.id)
+            .map_err(|e| e.to_string())?
+            .is_some()
+        {
+            continue;
+        }
 
-            // End trace session
-            let summary = if exit_status.success() {
-                format!("Completed successfully ({})", cmd_str)
-            } else {
-                format!(
-                    "Exited with code {} ({})",
-                    exit_status.code().unwrap_or(-1),
-                    cmd_str
-                )
-            };
+        let first_line = comment.body.lines().next().unwrap_or("").trim();
+        let title = format!("@{}: {}", comment.author.login, truncate(first_line, 80));
 
-            if let Err(e) = db.end_trace_session(&session_id, Some(&summary)) {
-                eprintln!("{} Ending trace session: {}", "Warning:".yellow(), e);
-            }
+        if dry_run {
+            println!(
+                "  {} Would add observation on #{}: {}",
+                "[DRY]".yellow(),
+                issue_number,
+                title
+            );
+            added += 1;
+            continue;
+        }
 
-            // Get session stats (only if debug enabled)
-            if trace_debug {
-                if let Ok(Some(session)) = db.get_trace_session(&session_id) {
-                    println!("\n{} Session {} ended", "Trace:".cyan(), &session_id[..8]);
+        let obs_id = db
+            .create_node("observation", &title, Some(&comment.body), None, None)
+            .map_err(|e| e.to_string())?;
+        db.update_node_metadata_field(
+            obs_id,
+            "issue_comment",
+            serde_json::json!({
+                "repo": repo,
+                "issue": issue_number,
+                "id": comment.id,
+                "author": comment.author.login,
+                "created_at": comment.created_at,
+            }),
+        )
+        .map_err(|e| e.to_string())?;
+        db.create_edge(node_id, obs_id, "leads_to", Some("Issue comment"))
+            .map_err(|e| e.to_string())?;
+
+        println!(
+            "  {} Observation {} from #{}: {}",
+            "✓".green(),
+            obs_id,
+            issue_number,
+            title
+        );
+        added += 1;
+    }
+
+    Ok(added)
+}
+
+/// Create nodes for PR review threads not yet in the graph
+///
+/// Threads carrying a suggestion become option nodes, the rest observations.
+/// Each is linked from the action nodes whose `files` include the thread's
+/// file. Threads already imported are only updated when they get resolved.
+/// Returns (created, newly resolved).
+fn import_review_threads(
+    db: &Database,
+    repo: &str,
+    pr: i32,
+    threads: &[deciduous::forge::ReviewThread],
+    dry_run: bool,
+) -> Result<(usize, usize), String> {
+    let actions: Vec<deciduous::DecisionNode> = db
+        .get_all_nodes()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|n| n.node_type == "action")
+        .collect();
+
+    let mut created = 0;
+    let mut resolved = 0;
+    for thread in threads {
+        let Some(first) = thread.comments.first() else {
+            continue;
+        };
+
+        if let Some(existing) = db
+            .find_node_by_review_thread(repo, &thread.id)
+            .map_err(|e| e.to_string())?
+        {
+            if thread.resolved && existing.status != "completed" {
+                if dry_run {
                     println!(
-                        "  Tokens: {}↓ {}↑ (cache: {}r {}w)",
-                        session.total_input_tokens,
-                        session.total_output_tokens,
-                        session.total_cache_read,
-                        session.total_cache_write
+                        "  {} Would mark node {} resolved",
+                        "[DRY]".yellow(),
+                        existing.id
                     );
+                } else {
+                    db.update_node_status(existing.id, "completed")
+                        .map_err(|e| e.to_string())?;
+                    println!("  {} Node {} resolved", "✓".green(), existing.id);
+                }
+                resolved += 1;
+            }
+            continue;
+        }
 
-                    if let Ok(spans) = db.get_trace_spans(&session_id) {
-                        println!("  Spans: {}", spans.len());
-                    }
+        let node_type = if thread
+            .comments
+            .iter()
+            .any(|c| c.body.contains("```suggestion"))
+        {
+            "option"
+        } else {
+            "observation"
+        };
+        let location = match (&thread.path, thread.line) {
+            (Some(path), Some(line)) => format!("{}:{}", path, line),
+            (Some(path), None) => path.clone(),
+            _ => format!("#{}", pr),
+        };
+        let first_line = first.body.lines().next().unwrap_or("").trim();
+        let title = format!(
+            "@{} on {}: {}",
+            first.author.login,
+            location,
+            truncate(first_line, 60)
+        );
 
-                    if let Some(node_id) = session.linked_node_id {
-                        println!("  Linked: node #{}", node_id);
-                    }
-                }
+        let linked: Vec<&deciduous::DecisionNode> = match &thread.path {
+            Some(path) => actions
+                .iter()
+                .filter(|a| node_touches_file(a, path))
+                .collect(),
+            None => vec![],
+        };
+
+        if dry_run {
+            println!(
+                "  {} Would add {}: {} (linked from {} actions)",
+                "[DRY]".yellow(),
+                node_type,
+                title,
+                linked.len()
+            );
+            created += 1;
+            continue;
+        }
+
+        let mut description = format!(
+            "{} ({})\n",
+            location,
+            if thread.resolved {
+                "resolved"
+            } else {
+                "unresolved"
             }
+        );
+        if let Some(excerpt) = &thread.excerpt {
+            // The tail of the hunk is the commented line and its context
+            let lines: Vec<&str> = excerpt.lines().collect();
+            let tail = &lines[lines.len().saturating_sub(6)..];
+            description.push_str(&format!("\n```diff\n{}\n```\n", tail.join("\n")));
+        }
+        for comment in &thread.comments {
+            description.push_str(&format!("\n@{}: {}\n", comment.author.login, comment.body));
+        }
 
-            // Exit with same code as child
-            std::process::exit(exit_status.code().unwrap_or(1));
+        let node_id = db
+            .create_node_full(
+                node_type,
+                &title,
+                Some(&description),
+                None,
+                None,
+                None,
+                thread.path.as_deref(),
+                None,
+            )
+            .map_err(|e| e.to_string())?;
+        db.update_node_metadata_field(
+            node_id,
+            "review_thread",
+            serde_json::json!({
+                "repo": repo,
+                "pr": pr,
+                "id": thread.id,
+                "path": thread.path,
+                "line": thread.line,
+                "resolved": thread.resolved,
+            }),
+        )
+        .map_err(|e| e.to_string())?;
+        db.update_node_metadata_field(node_id, "pr", serde_json::json!(pr))
+            .map_err(|e| e.to_string())?;
+        if thread.resolved {
+            db.update_node_status(node_id, "completed")
+                .map_err(|e| e.to_string())?;
         }
+        for action in &linked {
+            db.create_edge(action.id, node_id, "leads_to", Some("Review thread"))
+                .map_err(|e| e.to_string())?;
+        }
+
+        let link_note = if linked.is_empty() {
+            " (no matching action)".dimmed().to_string()
+        } else {
+            let ids: Vec<String> = linked.iter().map(|a| a.id.to_string()).collect();
+            format!(" <- {}", ids.join(", "))
+        };
+        println!(
+            "  {} {} {}: {}{}",
+            "✓".green(),
+            node_type,
+            node_id,
+            title,
+            link_note
+        );
+        created += 1;
     }
+
+    Ok((created, resolved))
 }
 
 fn truncate(s: &str, max_len: usize) -> String {
@@ -3506,29 +11270,23 @@ struct GitCommit {
     files_changed: Option<u32>,
 }
 
-/// Extract all unique commit hashes from nodes' metadata_json
-fn extract_commit_hashes(nodes: &[deciduous::DecisionNode]) -> Vec<String> {
-    let mut hashes = std::collections::HashSet::new();
-    for node in nodes {
-        if let Some(ref meta_json) = node.metadata_json {
-            if let Ok(meta) = serde_json::from_str::<serde_json::Value>(meta_json) {
-                if let Some(commit) = meta.get("commit").and_then(|c| c.as_str()) {
-                    if !commit.is_empty() {
-                        hashes.insert(commit.to_string());
-                    }
-                }
-            }
-        }
-    }
-    hashes.into_iter().collect()
+/// The commit hash in a node's metadata_json, if any
+fn commit_hash(node: &deciduous::DecisionNode) -> Option<String> {
+    let meta: serde_json::Value = serde_json::from_str(node.metadata_json.as_deref()?).ok()?;
+    meta.get("commit")
+        .and_then(|c| c.as_str())
+        .filter(|c| !c.is_empty())
+        .map(String::from)
 }
 
 /// Get commit info from git for a given hash
-fn get_git_commit_info(hash: &str) -> Option<GitCommit> {
-    // Get commit info: hash, author, date (ISO), full message body
+///
+/// Authors on the team roster get their roster name.
+fn get_git_commit_info(hash: &str, config: &Config) -> Option<GitCommit> {
+    // Get commit info: hash, author, email, date (ISO), full message body
     // Use %x00 (null byte) as separator since message can have newlines
     let output = ProcessCommand::new("git")
-        .args(["log", "-1", "--format=%H%x00%an%x00%aI%x00%B", hash])
+        .args(["log", "-1", "--format=%H%x00%an%x00%ae%x00%aI%x00%B", hash])
         .output()
         .ok()?;
 
@@ -3538,12 +11296,14 @@ fn get_git_commit_info(hash: &str) -> Option<GitCommit> {
 
     let stdout = String::from_utf8_lossy(&output.stdout);
     let parts: Vec<&str> = stdout.trim().split('\x00').collect();
-    if parts.len() < 4 {
+    if parts.len() < 5 {
         return None;
     }
 
+    let author = config.commit_author(parts[1], parts[2]);
+
     // Clean up the message - trim whitespace
-    let message = parts[3].trim().to_string();
+    let message = parts[4].trim().to_string();
 
     // Get files changed count
     let files_output = ProcessCommand::new("git")
@@ -3563,8 +11323,8 @@ fn get_git_commit_info(hash: &str) -> Option<GitCommit> {
     Some(GitCommit {
         hash: parts[0].to_string(),
         short_hash: parts[0].chars().take(7).collect(),
-        author: parts[1].to_string(),
-        date: parts[2].to_string(),
+        author,
+        date: parts[3].to_string(),
         message,
         files_changed,
     })
@@ -3572,14 +11332,14 @@ fn get_git_commit_info(hash: &str) -> Option<GitCommit> {
 
 /// Generate git-history.json for all commits linked to nodes
 fn export_git_history(
-    nodes: &[deciduous::DecisionNode],
+    hashes: &std::collections::BTreeSet<String>,
     output_dir: &std::path::Path,
 ) -> Result<usize, Box<dyn std::error::Error>> {
-    let hashes = extract_commit_hashes(nodes);
+    let config = Config::load();
     let mut commits: Vec<GitCommit> = Vec::new();
 
-    for hash in &hashes {
-        if let Some(commit) = get_git_commit_info(hash) {
+    for hash in hashes {
+        if let Some(commit) = get_git_commit_info(hash, &config) {
             commits.push(commit);
         }
     }
@@ -0,0 +1,322 @@
+//! The deciduous-managed block in CLAUDE.md, AGENTS.md, GEMINI.md and .rules
+//!
+//! The workflow section is written between `<!-- deciduous:begin <hash> -->`
+//! and `<!-- deciduous:end -->`, where the hash is of the template text that
+//! was written. `init --force` and `update` replace only that block and leave
+//! the rest of the file alone. If the block was edited by hand, the edits are
+//! merged three ways against the template they started from, a copy of which
+//! is kept in `.deciduous/sections/`; changes that overlap are left between
+//! git-style conflict markers for the user to resolve.
+
+use crate::remote_patch::sha256_hex;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Start of the line opening the managed block
+pub const BEGIN_MARKER: &str = "<!-- deciduous:begin";
+
+/// The line closing the managed block
+pub const END_MARKER: &str = "<!-- deciduous:end -->";
+
+const CONFLICT_OURS: &str = "<<<<<<< yours";
+const CONFLICT_SEP: &str = "=======";
+const CONFLICT_THEIRS: &str = ">>>>>>> deciduous";
+
+/// A managed block found in a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Found {
+    /// Byte range of the whole block, markers included
+    pub range: Range<usize>,
+    /// Byte range of the text between the markers
+    pub body: Range<usize>,
+    /// Hash of the template the block was written from
+    pub hash: String,
+}
+
+/// What merging a new template into a file did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The file had no workflow section; the block was appended
+    Added,
+    /// The block (or an unmarked section from an older version) was replaced
+    Replaced,
+    /// Nothing changed: the block was current, or only the user had edited it
+    Unchanged,
+    /// The user's edits and the new template were merged cleanly
+    Merged,
+    /// The user's edits overlap the template's changes; conflict markers were left
+    Conflict,
+}
+
+/// Short hash identifying a template's text
+pub fn template_hash(section: &str) -> String {
+    sha256_hex(section.trim().as_bytes())[..12].to_string()
+}
+
+/// The managed block for a template
+pub fn block(section: &str) -> String {
+    block_with(&template_hash(section), section)
+}
+
+fn block_with(hash: &str, body: &str) -> String {
+    format!(
+        "{} {} -->\n{}\n{}\n",
+        BEGIN_MARKER,
+        hash,
+        body.trim(),
+        END_MARKER
+    )
+}
+
+/// The managed block in `content`, if it has one
+pub fn find(content: &str) -> Option<Found> {
+    let start = content.find(BEGIN_MARKER)?;
+    let body_start = content[start..].find('\n').map(|i| start + i + 1)?;
+    let hash = content[start..body_start]
+        .trim()
+        .strip_prefix(BEGIN_MARKER)?
+        .strip_suffix("-->")?
+        .trim()
+        .to_string();
+    let body_end = content[body_start..]
+        .find(END_MARKER)
+        .map(|i| body_start + i)?;
+    let end = content[body_end..]
+        .find('\n')
+        .map(|i| body_end + i + 1)
+        .unwrap_or(content.len());
+    Some(Found {
+        range: start..end,
+        body: body_start..body_end,
+        hash,
+    })
+}
+
+/// Where the template last written to an instruction file is kept
+pub fn base_path(file: &Path) -> PathBuf {
+    let root = file.parent().unwrap_or_else(|| Path::new("."));
+    let name = file
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_default();
+    root.join(".deciduous").join("sections").join(name)
+}
+
+/// The template last written to `file`, if it was kept
+pub fn load_base(file: &Path) -> Option<String> {
+    fs::read_to_string(base_path(file)).ok()
+}
+
+/// Keep the template just written to `file` as the base of the next merge
+pub fn save_base(file: &Path, section: &str) -> Result<(), String> {
+    let path = base_path(file);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    }
+    fs::write(&path, section.trim())
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+/// Bring the workflow section in `existing` up to `section`
+///
+/// `base` is the template the block was last written from, if known. Text
+/// outside the block is never touched.
+pub fn merge_into(existing: &str, base: Option<&str>, section: &str) -> (String, Outcome) {
+    let section = section.trim();
+    let Some(found) = find(existing) else {
+        return match crate::init::workflow_section(existing) {
+            // An unmarked section from before the markers: replace it
+            Some((start, end)) => {
+                let after = existing[end..].trim_start();
+                let separator = if after.is_empty() { "" } else { "\n" };
+                let content = format!(
+                    "{}{}{}{}",
+                    &existing[..start],
+                    block(section),
+                    separator,
+                    after
+                );
+                (content, Outcome::Replaced)
+            }
+            None => (
+                format!("{}\n\n{}", existing.trim_end(), block(section)),
+                Outcome::Added,
+            ),
+        };
+    };
+
+    let ours = existing[found.body.clone()].trim();
+    let new_hash = template_hash(section);
+    let (body, outcome) = if template_hash(ours) == found.hash {
+        // Untouched since it was written
+        let outcome = if ours == section {
+            Outcome::Unchanged
+        } else {
+            Outcome::Replaced
+        };
+        (section.to_string(), outcome)
+    } else if found.hash == new_hash {
+        // Only the user changed it
+        (ours.to_string(), Outcome::Unchanged)
+    } else {
+        match base.filter(|b| template_hash(b) == found.hash) {
+            Some(base) => match merge3(base.trim(), ours, section) {
+                (merged, true) => (merged, Outcome::Merged),
+                (merged, false) => (merged, Outcome::Conflict),
+            },
+            // Without the original template, every edit is a conflict
+            None => (conflict(&[ours], &[section]).join("\n"), Outcome::Conflict),
+        }
+    };
+
+    let content = format!(
+        "{}{}{}",
+        &existing[..found.range.start],
+        block_with(&new_hash, &body),
+        &existing[found.range.end..]
+    );
+    (content, outcome)
+}
+
+fn conflict<'a>(ours: &[&'a str], theirs: &[&'a str]) -> Vec<&'a str> {
+    let mut lines = vec![CONFLICT_OURS];
+    lines.extend_from_slice(ours);
+    lines.push(CONFLICT_SEP);
+    lines.extend_from_slice(theirs);
+    lines.push(CONFLICT_THEIRS);
+    lines
+}
+
+/// For each line of `a`, the line of `b` it pairs with in a longest common
+/// subsequence
+fn matches(a: &[&str], b: &[&str]) -> Vec<Option<usize>> {
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut paired = vec![None; a.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            paired[i] = Some(j);
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    paired
+}
+
+/// Line-based three-way merge; returns the result and whether it was clean
+///
+/// Between lines that neither side touched, a change made on one side only
+/// is taken; where both sides changed the same lines differently, both are
+/// kept between conflict markers.
+pub fn merge3(base: &str, ours: &str, theirs: &str) -> (String, bool) {
+    let base: Vec<&str> = base.lines().collect();
+    let ours: Vec<&str> = ours.lines().collect();
+    let theirs: Vec<&str> = theirs.lines().collect();
+    let to_ours = matches(&base, &ours);
+    let to_theirs = matches(&base, &theirs);
+
+    let mut out: Vec<&str> = Vec::new();
+    let mut clean = true;
+    let (mut i, mut j, mut k) = (0, 0, 0);
+    loop {
+        // The next base line both sides kept
+        let sync = (i..base.len()).find_map(|b| Some((b, to_ours[b]?, to_theirs[b]?)));
+        let (b, jo, kt) = sync.unwrap_or((base.len(), ours.len(), theirs.len()));
+        let (original, o, t) = (&base[i..b], &ours[j..jo], &theirs[k..kt]);
+        if o == original {
+            out.extend_from_slice(t);
+        } else if t == original || o == t {
+            out.extend_from_slice(o);
+        } else {
+            clean = false;
+            out.extend(conflict(o, t));
+        }
+        if sync.is_none() {
+            break;
+        }
+        out.push(base[b]);
+        (i, j, k) = (b + 1, jo + 1, kt + 1);
+    }
+    (out.join("\n"), clean)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge3() {
+        let base = "a\nb\nc\nd\ne";
+        let (merged, clean) = merge3(base, "a\nB\nc\nd\ne", "a\nb\nc\nd\nE\nf");
+        assert!(clean);
+        assert_eq!(merged, "a\nB\nc\nd\nE\nf");
+
+        let (merged, clean) = merge3(base, "a\nmine\nc\nd\ne", "a\ntheirs\nc\nd\ne");
+        assert!(!clean);
+        assert_eq!(
+            merged,
+            "a\n<<<<<<< yours\nmine\n=======\ntheirs\n>>>>>>> deciduous\nc\nd\ne"
+        );
+
+        // Both sides making the same change is not a conflict
+        assert_eq!(
+            merge3(base, "a\nc\nd\ne", "a\nc\nd\ne"),
+            ("a\nc\nd\ne".to_string(), true)
+        );
+    }
+
+    #[test]
+    fn test_merge_into() {
+        let v1 = "## Decision Graph Workflow\n\nLog it.\n\nPush it.";
+        let v2 = "## Decision Graph Workflow\n\nLog it now.\n\nPush it.";
+
+        let (content, outcome) = merge_into("# Mine\n", None, v1);
+        assert_eq!(outcome, Outcome::Added);
+        assert!(content.starts_with("# Mine\n\n<!-- deciduous:begin "));
+        let content = format!("{}\n## Notes\n\nKeep me.\n", content);
+
+        let (updated, outcome) = merge_into(&content, Some(v1), v2);
+        assert_eq!(outcome, Outcome::Replaced);
+        assert!(updated.contains("Log it now.") && updated.ends_with("## Notes\n\nKeep me.\n"));
+        assert_eq!(merge_into(&updated, Some(v2), v2).1, Outcome::Unchanged);
+
+        // A hand edit elsewhere in the block survives the update
+        let edited = content.replace("Push it.", "Push it. Then tell the team.");
+        let (merged, outcome) = merge_into(&edited, Some(v1), v2);
+        assert_eq!(outcome, Outcome::Merged);
+        assert!(merged.contains("Log it now.\n\nPush it. Then tell the team.\n"));
+        assert_eq!(find(&merged).unwrap().hash, template_hash(v2));
+        assert_eq!(merge_into(&merged, Some(v2), v2).1, Outcome::Unchanged);
+
+        // Editing the line the template changed is a conflict
+        let clash = content.replace("Log it.", "Log everything.");
+        let (conflicted, outcome) = merge_into(&clash, Some(v1), v2);
+        assert_eq!(outcome, Outcome::Conflict);
+        assert!(conflicted.contains("<<<<<<< yours\nLog everything.\n=======\nLog it now.\n"));
+        assert!(conflicted.starts_with("# Mine\n\n"));
+    }
+
+    #[test]
+    fn test_merge_into_replaces_unmarked_section() {
+        let old = "# Mine\n\n## Decision Graph Workflow\n\nOld.\n\n## Other\n\nKept.";
+        let (content, outcome) = merge_into(old, None, "## Decision Graph Workflow\n\nNew.");
+        assert_eq!(outcome, Outcome::Replaced);
+        assert!(!content.contains("Old."));
+        assert!(content.contains(&format!("New.\n{}\n\n## Other\n\nKept.", END_MARKER)));
+    }
+}
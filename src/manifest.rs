@@ -0,0 +1,448 @@
+//! What `deciduous init` created, so `deciduous uninit` can take it out
+//!
+//! `init` snapshots the paths it touches before and after running and
+//! records the difference in `.deciduous/manifest.json`: files and
+//! directories it created (each file with the hash of what was written),
+//! workflow sections appended to an existing CLAUDE.md or AGENTS.md, and
+//! lines added to `.gitignore`. `update` refreshes the hashes of files it
+//! rewrites. `uninit` reverses exactly that, and leaves files that were
+//! edited since alone unless forced.
+
+use crate::init::workflow_section;
+use crate::remote_patch::sha256_hex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+/// Where the manifest lives, relative to the project root
+pub const MANIFEST_PATH: &str = ".deciduous/manifest.json";
+
+/// Top-level paths `init` and `update` can create or edit
+const TRACKED: &[&str] = &[
+    ".deciduous",
+    ".claude",
+    ".windsurf",
+    ".opencode",
+    ".codex",
+    ".zed",
+    ".gemini",
+    ".github",
+    "docs",
+    "CLAUDE.md",
+    "AGENTS.md",
+    "GEMINI.md",
+    ".rules",
+];
+
+/// Instruction files that get the workflow section
+const SECTION_FILES: &[&str] = &["CLAUDE.md", "AGENTS.md", "GEMINI.md", ".rules"];
+
+/// The tracked paths at one moment
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    /// Files relative to the root, with their SHA-256
+    files: BTreeMap<String, String>,
+    dirs: BTreeSet<String>,
+    /// Instruction files holding the workflow section
+    sections: BTreeSet<String>,
+    gitignore: Option<Vec<String>>,
+}
+
+impl Snapshot {
+    pub fn take(root: &Path) -> Self {
+        let mut snapshot = Self::default();
+        for top in TRACKED {
+            snapshot.walk(root, top);
+        }
+        for name in SECTION_FILES {
+            let content = fs::read_to_string(root.join(name)).unwrap_or_default();
+            if workflow_section(&content).is_some() {
+                snapshot.sections.insert(name.to_string());
+            }
+        }
+        snapshot.gitignore = fs::read_to_string(root.join(".gitignore"))
+            .ok()
+            .map(|c| c.lines().map(String::from).collect());
+        snapshot
+    }
+
+    fn walk(&mut self, root: &Path, rel: &str) {
+        let path = root.join(rel);
+        let Ok(meta) = fs::symlink_metadata(&path) else {
+            return;
+        };
+        if meta.is_dir() {
+            self.dirs.insert(rel.to_string());
+            let Ok(entries) = fs::read_dir(&path) else {
+                return;
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                self.walk(root, &format!("{}/{}", rel, name.to_string_lossy()));
+            }
+        } else if meta.is_file() && rel != MANIFEST_PATH {
+            if let Ok(bytes) = fs::read(&path) {
+                self.files.insert(rel.to_string(), sha256_hex(&bytes));
+            }
+        }
+    }
+}
+
+/// Everything `init` created in a project
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Manifest {
+    /// Files created, with the SHA-256 of what was last written
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+
+    /// Directories created
+    #[serde(default)]
+    pub dirs: BTreeSet<String>,
+
+    /// Existing instruction files the workflow section was appended to
+    #[serde(default)]
+    pub sections: BTreeSet<String>,
+
+    /// Lines appended to .gitignore
+    #[serde(default)]
+    pub gitignore_lines: Vec<String>,
+
+    /// Whether .gitignore was created rather than appended to
+    #[serde(default)]
+    pub gitignore_created: bool,
+}
+
+impl Manifest {
+    /// Fold what changed between two snapshots into the manifest
+    ///
+    /// New files and directories are added; files already in the manifest
+    /// that were rewritten get their new hash.
+    pub fn record(&mut self, before: &Snapshot, after: &Snapshot) {
+        for (path, hash) in &after.files {
+            match before.files.get(path) {
+                None => {
+                    self.files.insert(path.clone(), hash.clone());
+                }
+                Some(old) if old != hash => {
+                    if let Some(recorded) = self.files.get_mut(path) {
+                        *recorded = hash.clone();
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+        self.dirs
+            .extend(after.dirs.difference(&before.dirs).cloned());
+        for name in after.sections.difference(&before.sections) {
+            if !self.files.contains_key(name) {
+                self.sections.insert(name.clone());
+            }
+        }
+
+        if let Some(lines) = &after.gitignore {
+            let old = before.gitignore.as_deref().unwrap_or_default();
+            if before.gitignore.is_none() {
+                self.gitignore_created = true;
+            }
+            for line in lines {
+                if !line.trim().is_empty()
+                    && !old.contains(line)
+                    && !self.gitignore_lines.contains(line)
+                {
+                    self.gitignore_lines.push(line.clone());
+                }
+            }
+        }
+    }
+
+    /// The project's manifest, if `init` wrote one
+    pub fn load(root: &Path) -> Result<Option<Self>, String> {
+        let path = root.join(MANIFEST_PATH);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Could not read {}: {}", MANIFEST_PATH, e))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("Could not parse {}: {}", MANIFEST_PATH, e))
+    }
+
+    pub fn save(&self, root: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Could not serialize manifest: {}", e))?;
+        fs::write(root.join(MANIFEST_PATH), json + "\n")
+            .map_err(|e| format!("Could not write {}: {}", MANIFEST_PATH, e))
+    }
+}
+
+/// Record what changed since `before` in the project's manifest
+///
+/// With `create` unset, only an existing manifest is updated, so running
+/// `update` in a project initialized by an older version doesn't produce a
+/// manifest that only knows about part of it.
+pub fn record_since(root: &Path, before: &Snapshot, create: bool) -> Result<(), String> {
+    if !root.join(".deciduous").is_dir() {
+        return Ok(());
+    }
+    let mut manifest = match Manifest::load(root)? {
+        Some(manifest) => manifest,
+        None if create => Manifest::default(),
+        None => return Ok(()),
+    };
+    manifest.record(before, &Snapshot::take(root));
+    manifest.save(root)
+}
+
+/// One step of `deciduous uninit`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Removal {
+    /// A file init created, unchanged (or forced)
+    File(String),
+    /// A file init created that was edited since; left in place
+    Modified(String),
+    /// The workflow section in an instruction file
+    Section(String),
+    /// The lines init added to .gitignore
+    Gitignore(Vec<String>),
+    /// A directory init created, removed if nothing else is left in it
+    Dir(String),
+    /// `.deciduous/` with the database, config and patches
+    Deciduous,
+}
+
+impl Removal {
+    pub fn describe(&self) -> String {
+        match self {
+            Removal::File(path) => format!("Remove {}", path),
+            Removal::Modified(path) => {
+                format!("Keep {} (edited since init; --force removes it)", path)
+            }
+            Removal::Section(path) => {
+                format!("Remove the Decision Graph Workflow section from {}", path)
+            }
+            Removal::Gitignore(lines) => {
+                format!("Remove {} line(s) from .gitignore", lines.len())
+            }
+            Removal::Dir(path) => format!("Remove {}/ if empty", path),
+            Removal::Deciduous => "Remove .deciduous/ (database, config and patches)".to_string(),
+        }
+    }
+}
+
+fn under_deciduous(path: &str) -> bool {
+    path == ".deciduous" || path.starts_with(".deciduous/")
+}
+
+/// What `uninit` would do, in order
+///
+/// `.deciduous/` goes as a whole unless `keep_db` is set; everything in it is
+/// kept then.
+pub fn plan(root: &Path, manifest: &Manifest, keep_db: bool, force: bool) -> Vec<Removal> {
+    let mut steps = Vec::new();
+    for (path, hash) in &manifest.files {
+        if under_deciduous(path) {
+            continue;
+        }
+        let Ok(bytes) = fs::read(root.join(path)) else {
+            continue;
+        };
+        let has_section = SECTION_FILES.contains(&path.as_str())
+            && workflow_section(&String::from_utf8_lossy(&bytes)).is_some();
+        if force || sha256_hex(&bytes) == *hash {
+            steps.push(Removal::File(path.clone()));
+        } else if has_section {
+            steps.push(Removal::Section(path.clone()));
+        } else {
+            steps.push(Removal::Modified(path.clone()));
+        }
+    }
+    for path in &manifest.sections {
+        let content = fs::read_to_string(root.join(path)).unwrap_or_default();
+        if workflow_section(&content).is_some() {
+            steps.push(Removal::Section(path.clone()));
+        }
+    }
+    if !manifest.gitignore_lines.is_empty() && root.join(".gitignore").exists() {
+        steps.push(Removal::Gitignore(manifest.gitignore_lines.clone()));
+    }
+    // Deepest first, so a parent is only tried once its children are gone
+    let mut dirs: Vec<&String> = manifest
+        .dirs
+        .iter()
+        .filter(|d| !under_deciduous(d) && root.join(d).is_dir())
+        .collect();
+    dirs.sort_by_key(|d| std::cmp::Reverse(d.matches('/').count()));
+    steps.extend(dirs.into_iter().map(|d| Removal::Dir(d.clone())));
+    if !keep_db && manifest.dirs.contains(".deciduous") && root.join(".deciduous").is_dir() {
+        steps.push(Removal::Deciduous);
+    }
+    steps
+}
+
+/// Carry out a plan; returns directories left because they weren't empty
+pub fn apply(
+    root: &Path,
+    steps: &[Removal],
+    gitignore_created: bool,
+) -> Result<Vec<String>, String> {
+    let mut kept = Vec::new();
+    for step in steps {
+        match step {
+            Removal::File(path) => fs::remove_file(root.join(path))
+                .map_err(|e| format!("Could not remove {}: {}", path, e))?,
+            Removal::Modified(_) => {}
+            Removal::Section(path) => {
+                let file = root.join(path);
+                let content = fs::read_to_string(&file)
+                    .map_err(|e| format!("Could not read {}: {}", path, e))?;
+                if let Some((start, end)) = workflow_section(&content) {
+                    let rest = format!(
+                        "{}\n\n{}",
+                        content[..start].trim_end(),
+                        content[end..].trim_start()
+                    );
+                    fs::write(&file, format!("{}\n", rest.trim()))
+                        .map_err(|e| format!("Could not update {}: {}", path, e))?;
+                }
+            }
+            Removal::Gitignore(lines) => {
+                let file = root.join(".gitignore");
+                let content = fs::read_to_string(&file)
+                    .map_err(|e| format!("Could not read .gitignore: {}", e))?;
+                let rest = remove_lines(&content, lines);
+                let result = if gitignore_created && rest.trim().is_empty() {
+                    fs::remove_file(&file)
+                } else {
+                    fs::write(&file, rest)
+                };
+                result.map_err(|e| format!("Could not update .gitignore: {}", e))?;
+            }
+            Removal::Dir(path) => {
+                if fs::remove_dir(root.join(path)).is_err() {
+                    kept.push(path.clone());
+                }
+            }
+            Removal::Deciduous => fs::remove_dir_all(root.join(".deciduous"))
+                .map_err(|e| format!("Could not remove .deciduous/: {}", e))?,
+        }
+    }
+    Ok(kept)
+}
+
+/// `content` without `lines`, collapsing the blank lines they leave behind
+fn remove_lines(content: &str, lines: &[String]) -> String {
+    let mut rest: Vec<&str> = Vec::new();
+    for line in content.lines() {
+        if lines.iter().any(|l| l == line) {
+            continue;
+        }
+        let after_blank = rest.last().map(|l| l.trim().is_empty()).unwrap_or(true);
+        if line.trim().is_empty() && after_blank {
+            continue;
+        }
+        rest.push(line);
+    }
+    while rest.last().is_some_and(|l| l.trim().is_empty()) {
+        rest.pop();
+    }
+    if rest.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", rest.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_lines() {
+        let lines = vec![
+            "# Deciduous database (local)".to_string(),
+            ".deciduous/".to_string(),
+        ];
+        assert_eq!(
+            remove_lines(
+                "target/\n\n# Deciduous database (local)\n.deciduous/\n",
+                &lines
+            ),
+            "target/\n"
+        );
+        assert_eq!(
+            remove_lines("# Deciduous database (local)\n.deciduous/\n", &lines),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_init_then_uninit() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/notes.md"), "mine").unwrap();
+        fs::write(root.join("AGENTS.md"), "# Agents\n\nBe nice.\n").unwrap();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+
+        // What init does
+        let before = Snapshot::take(root);
+        fs::create_dir_all(root.join(".deciduous")).unwrap();
+        fs::write(root.join(".deciduous/config.toml"), "").unwrap();
+        fs::create_dir_all(root.join(".claude/commands")).unwrap();
+        fs::write(root.join(".claude/commands/decision.md"), "decide").unwrap();
+        fs::write(root.join(".claude/commands/recover.md"), "recover").unwrap();
+        fs::write(root.join("docs/index.html"), "<html>").unwrap();
+        fs::write(
+            root.join("AGENTS.md"),
+            "# Agents\n\nBe nice.\n\n## Decision Graph Workflow\n\nLog it.\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join(".gitignore"),
+            "target/\n\n# Deciduous database (local)\n.deciduous/\n",
+        )
+        .unwrap();
+        record_since(root, &before, true).unwrap();
+
+        let manifest = Manifest::load(root).unwrap().unwrap();
+        assert!(manifest.files.contains_key("docs/index.html"));
+        assert!(!manifest.files.contains_key("docs/notes.md"));
+        assert!(manifest.dirs.contains(".claude/commands"));
+        assert!(!manifest.dirs.contains("docs"));
+        assert!(manifest.sections.contains("AGENTS.md"));
+        assert!(!manifest.gitignore_created);
+
+        // Edited after init
+        fs::write(root.join(".claude/commands/recover.md"), "my version").unwrap();
+        fs::write(root.join(".deciduous/deciduous.db"), "data").unwrap();
+
+        let steps = plan(root, &manifest, true, false);
+        assert!(steps.contains(&Removal::Modified(
+            ".claude/commands/recover.md".to_string()
+        )));
+        assert!(!steps.contains(&Removal::Deciduous));
+        let kept = apply(root, &steps, manifest.gitignore_created).unwrap();
+        assert_eq!(kept, vec![".claude/commands", ".claude"]);
+        assert!(!root.join(".claude/commands/decision.md").exists());
+        assert!(root.join(".claude/commands/recover.md").exists());
+        assert!(!root.join("docs/index.html").exists());
+        assert!(root.join("docs/notes.md").exists());
+        assert!(root.join(".deciduous/deciduous.db").exists());
+        assert_eq!(
+            fs::read_to_string(root.join("AGENTS.md")).unwrap(),
+            "# Agents\n\nBe nice.\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join(".gitignore")).unwrap(),
+            "target/\n"
+        );
+
+        let steps = plan(root, &manifest, false, true);
+        apply(root, &steps, manifest.gitignore_created).unwrap();
+        assert!(!root.join(".claude").exists());
+        assert!(!root.join(".deciduous").exists());
+        assert!(root.join("docs").exists());
+    }
+}
@@ -0,0 +1,479 @@
+//! Model Context Protocol server over stdio
+//!
+//! `deciduous mcp` speaks JSON-RPC 2.0, one message per line, on stdin and
+//! stdout, so MCP clients like Claude Desktop and Cursor can read and write
+//! the graph without shelling out to the CLI. It offers five tools
+//! (`add_node`, `link`, `query`, `get_context`, `search`) and two resources:
+//! `deciduous://graph`, the whole graph, and `deciduous://node/<id>`, one node
+//! with its edges.
+//!
+//! Nodes and edges go through the same `[rules]` checks as `deciduous add` and
+//! `deciduous link`. Shell hooks are not run: their output would land in the
+//! protocol stream.
+
+use crate::config::Config;
+use crate::db::{DecisionEdge, DecisionNode};
+use crate::store::GraphStore;
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+/// MCP revision this server implements
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+pub(crate) const PARSE_ERROR: i64 = -32700;
+pub(crate) const METHOD_NOT_FOUND: i64 = -32601;
+pub(crate) const INVALID_PARAMS: i64 = -32602;
+
+/// Serve MCP requests from stdin until it closes
+pub fn serve(db: &dyn GraphStore) -> Result<(), String> {
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    for line in stdin.lock().lines() {
+        let line = line.map_err(|e| format!("Failed to read stdin: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle(db, &message),
+            Err(e) => Some(error(Value::Null, PARSE_ERROR, &e.to_string())),
+        };
+        if let Some(response) = response {
+            writeln!(stdout, "{}", response)
+                .and_then(|_| stdout.flush())
+                .map_err(|e| format!("Failed to write stdout: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// The response to one JSON-RPC message; None for notifications
+pub fn handle(db: &dyn GraphStore, message: &Value) -> Option<Value> {
+    let id = message.get("id")?.clone();
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+    let result = match message["method"].as_str().unwrap_or_default() {
+        "initialize" => Ok(json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {}, "resources": {} },
+            "serverInfo": { "name": "deciduous", "version": env!("CARGO_PKG_VERSION") },
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+        "tools/call" => call_tool(db, &params),
+        "resources/list" => Ok(json!({
+            "resources": [{
+                "uri": "deciduous://graph",
+                "name": "Decision graph",
+                "description": "Every node and edge",
+                "mimeType": "application/json",
+            }],
+            "resourceTemplates": [{
+                "uriTemplate": "deciduous://node/{id}",
+                "name": "Decision node",
+                "description": "One node with its incoming and outgoing edges",
+                "mimeType": "application/json",
+            }],
+        })),
+        "resources/read" => read_resource(db, &params),
+        method => Err((METHOD_NOT_FOUND, format!("Unknown method: {}", method))),
+    };
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error(id, code, &message),
+    })
+}
+
+pub(crate) fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "add_node",
+            "description": "Add a node to the decision graph. Returns the new node.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "type": {
+                        "type": "string",
+                        "enum": ["goal", "decision", "option", "action", "outcome", "observation"],
+                    },
+                    "title": { "type": "string" },
+                    "description": { "type": "string" },
+                    "confidence": { "type": "integer", "minimum": 0, "maximum": 100 },
+                    "prompt": { "type": "string", "description": "The user's request, verbatim" },
+                    "files": { "type": "string", "description": "Comma-separated paths" },
+                    "commit": { "type": "string", "description": "Commit hash, or HEAD" },
+                    "branch": { "type": "string", "description": "Defaults to the current git branch" },
+                },
+                "required": ["type", "title"],
+            },
+        },
+        {
+            "name": "link",
+            "description": "Connect two nodes with an edge. Returns the new edge.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "from": { "type": "integer" },
+                    "to": { "type": "integer" },
+                    "type": {
+                        "type": "string",
+                        "enum": ["leads_to", "requires", "chosen", "rejected", "blocks", "enables"],
+                        "default": "leads_to",
+                    },
+                    "rationale": { "type": "string" },
+                },
+                "required": ["from", "to"],
+            },
+        },
+        {
+            "name": "query",
+            "description": "List nodes, newest first, filtered by type, status or branch.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "type": { "type": "string" },
+                    "status": { "type": "string" },
+                    "branch": { "type": "string" },
+                    "limit": { "type": "integer", "default": 50 },
+                },
+            },
+        },
+        {
+            "name": "get_context",
+            "description": "A node with its parents, children and the edges between them; \
+                            without node_id, the most recent nodes and their edges.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "node_id": { "type": "integer" },
+                    "limit": { "type": "integer", "default": 20 },
+                },
+            },
+        },
+        {
+            "name": "search",
+            "description": "Find nodes whose title, description or prompt contains the text \
+                            (case-insensitive).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string" },
+                    "limit": { "type": "integer", "default": 20 },
+                },
+                "required": ["text"],
+            },
+        },
+    ])
+}
+
+/// Run a tool; failures come back as an `isError` result so the model sees them
+fn call_tool(db: &dyn GraphStore, params: &Value) -> Result<Value, (i64, String)> {
+    let name = params["name"]
+        .as_str()
+        .ok_or((INVALID_PARAMS, "tools/call needs a tool name".to_string()))?;
+    let args = &params["arguments"];
+    let result = match name {
+        "add_node" => add_node(db, args),
+        "link" => link(db, args),
+        "query" => query(db, args),
+        "get_context" => get_context(db, args),
+        "search" => search(db, args),
+        _ => return Err((INVALID_PARAMS, format!("Unknown tool: {}", name))),
+    };
+    let (text, is_error) = match result {
+        Ok(value) => (
+            serde_json::to_string_pretty(&value).unwrap_or_default(),
+            false,
+        ),
+        Err(e) => (e, true),
+    };
+    Ok(json!({ "content": [{ "type": "text", "text": text }], "isError": is_error }))
+}
+
+fn read_resource(db: &dyn GraphStore, params: &Value) -> Result<Value, (i64, String)> {
+    let uri = params["uri"]
+        .as_str()
+        .ok_or((INVALID_PARAMS, "resources/read needs a uri".to_string()))?;
+    let value = if uri == "deciduous://graph" {
+        db.get_graph()
+            .map(|graph| json!(graph))
+            .map_err(|e| e.to_string())
+    } else if let Some(id) = uri.strip_prefix("deciduous://node/") {
+        let id = id
+            .parse()
+            .map_err(|_| (INVALID_PARAMS, format!("Bad node ID in {}", uri)))?;
+        node_context(db, id)
+    } else {
+        return Err((INVALID_PARAMS, format!("Unknown resource: {}", uri)));
+    };
+    let value = value.map_err(|e| (INVALID_PARAMS, e))?;
+    Ok(json!({
+        "contents": [{
+            "uri": uri,
+            "mimeType": "application/json",
+            "text": serde_json::to_string_pretty(&value).unwrap_or_default(),
+        }],
+    }))
+}
+
+pub(crate) fn str_arg<'a>(args: &'a Value, key: &str) -> Option<&'a str> {
+    args[key].as_str().filter(|s| !s.is_empty())
+}
+
+pub(crate) fn id_arg(args: &Value, key: &str) -> Result<i32, String> {
+    args[key]
+        .as_i64()
+        .and_then(|id| i32::try_from(id).ok())
+        .ok_or_else(|| format!("`{}` must be a node ID", key))
+}
+
+pub(crate) fn limit_arg(args: &Value, default: usize) -> usize {
+    args["limit"]
+        .as_u64()
+        .map(|n| n as usize)
+        .unwrap_or(default)
+}
+
+pub(crate) fn add_node(db: &dyn GraphStore, args: &Value) -> Result<Value, String> {
+    let node_type = str_arg(args, "type").ok_or("`type` is required")?;
+    let title = str_arg(args, "title").ok_or("`title` is required")?;
+    let description = str_arg(args, "description");
+    let prompt = str_arg(args, "prompt");
+    let files = str_arg(args, "files");
+    let commit = str_arg(args, "commit").and_then(|c| {
+        if c.eq_ignore_ascii_case("HEAD") {
+            crate::db::get_current_git_commit()
+        } else {
+            Some(c.to_string())
+        }
+    });
+    let branch = match str_arg(args, "branch") {
+        Some(branch) => Some(branch.to_string()),
+        None => crate::db::get_current_git_branch(),
+    };
+    let confidence = match args["confidence"].as_u64() {
+        Some(c) if c > 100 => return Err("`confidence` must be 0-100".to_string()),
+        Some(c) => Some(c as u8),
+        None => None,
+    };
+
+    // Same conventions as `deciduous add`: [rules], the working directory's
+    // sub-project, and a link from the focused goal
+    let config = Config::load();
+    let mut node = db.node(node_type, title).rules(&config.rules).link_focus();
+    if let Some(description) = description {
+        node = node.description(description);
+    }
+    if let Some(confidence) = confidence {
+        node = node.confidence(confidence);
+    }
+    if let Some(commit) = commit {
+        node = node.commit(commit);
+    }
+    if let Some(prompt) = prompt {
+        node = node.prompt(prompt);
+    }
+    if let Some(files) = files {
+        node = node.files([files]);
+    }
+    if let Some(branch) = branch {
+        node = node.branch(branch);
+    }
+    if let Some(project) = crate::project::current(&config.projects) {
+        node = node.project(project);
+    }
+    let id = node.insert().map_err(|e| e.to_string())?;
+    let node = db.get_node_by_id(id).map_err(|e| e.to_string())?;
+    Ok(json!(node))
+}
+
+pub(crate) fn link(db: &dyn GraphStore, args: &Value) -> Result<Value, String> {
+    let from = id_arg(args, "from")?;
+    let to = id_arg(args, "to")?;
+    let edge_type = str_arg(args, "type").unwrap_or("leads_to");
+    let rationale = str_arg(args, "rationale");
+    let mut edge = db
+        .edge(from, to)
+        .edge_type(edge_type)
+        .rules(&Config::load().rules);
+    if let Some(rationale) = rationale {
+        edge = edge.rationale(rationale);
+    }
+    let id = edge.insert().map_err(|e| e.to_string())?;
+    Ok(json!({ "id": id, "from": from, "to": to, "type": edge_type, "rationale": rationale }))
+}
+
+pub(crate) fn node_branch(node: &DecisionNode) -> Option<String> {
+    let meta: Value = serde_json::from_str(node.metadata_json.as_deref()?).ok()?;
+    meta["branch"].as_str().map(String::from)
+}
+
+/// Nodes newest first
+fn newest_first(db: &dyn GraphStore) -> Result<Vec<DecisionNode>, String> {
+    let mut nodes = db.get_all_nodes().map_err(|e| e.to_string())?;
+    nodes.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+    Ok(nodes)
+}
+
+/// The `query` tool: nodes newest first, filtered by `type`, `status` and
+/// `branch`, at most `limit` (50)
+pub fn query(db: &dyn GraphStore, args: &Value) -> Result<Value, String> {
+    let node_type = str_arg(args, "type");
+    let status = str_arg(args, "status");
+    let branch = str_arg(args, "branch");
+    let nodes: Vec<DecisionNode> = newest_first(db)?
+        .into_iter()
+        .filter(|n| node_type.map(|t| n.node_type == t).unwrap_or(true))
+        .filter(|n| status.map(|s| n.status == s).unwrap_or(true))
+        .filter(|n| {
+            branch
+                .map(|b| node_branch(n).as_deref() == Some(b))
+                .unwrap_or(true)
+        })
+        .take(limit_arg(args, 50))
+        .collect();
+    Ok(json!(nodes))
+}
+
+/// The `search` tool: nodes whose title, description or prompt contains
+/// `text`, newest first, at most `limit` (20)
+pub fn search(db: &dyn GraphStore, args: &Value) -> Result<Value, String> {
+    let text = str_arg(args, "text").ok_or("`text` is required")?;
+    Ok(json!(search_nodes(db, text, limit_arg(args, 20))?))
+}
+
+/// Nodes whose title, description or prompt contain `text` (ignoring case),
+/// newest first
+pub fn search_nodes(
+    db: &dyn GraphStore,
+    text: &str,
+    limit: usize,
+) -> Result<Vec<DecisionNode>, String> {
+    let text = text.to_lowercase();
+    let matches = |field: Option<&str>| field.is_some_and(|f| f.to_lowercase().contains(&text));
+    let nodes: Vec<DecisionNode> = newest_first(db)?
+        .into_iter()
+        .filter(|n| {
+            let prompt = n
+                .metadata_json
+                .as_deref()
+                .and_then(|m| serde_json::from_str::<Value>(m).ok())
+                .and_then(|m| m["prompt"].as_str().map(String::from));
+            matches(Some(n.title.as_str()))
+                || matches(n.description.as_deref())
+                || matches(prompt.as_deref())
+        })
+        .take(limit)
+        .collect();
+    Ok(nodes)
+}
+
+/// A node, the nodes on either side of it, and the edges joining them
+pub(crate) fn node_context(db: &dyn GraphStore, id: i32) -> Result<Value, String> {
+    let node = db
+        .get_node_by_id(id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Node {} does not exist", id))?;
+    let edges: Vec<DecisionEdge> = db
+        .get_all_edges()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|e| e.from_node_id == id || e.to_node_id == id)
+        .collect();
+    let parents = db.get_node_parents(id).map_err(|e| e.to_string())?;
+    let children = db.get_node_children(id).map_err(|e| e.to_string())?;
+    Ok(json!({ "node": node, "parents": parents, "children": children, "edges": edges }))
+}
+
+/// The `get_context` tool: `node_id` with its neighbours, or the `limit` (20)
+/// newest nodes and the edges between them
+pub fn get_context(db: &dyn GraphStore, args: &Value) -> Result<Value, String> {
+    if !args["node_id"].is_null() {
+        return node_context(db, id_arg(args, "node_id")?);
+    }
+    let nodes: Vec<DecisionNode> = newest_first(db)?
+        .into_iter()
+        .take(limit_arg(args, 20))
+        .collect();
+    let ids: std::collections::HashSet<i32> = nodes.iter().map(|n| n.id).collect();
+    let edges: Vec<DecisionEdge> = db
+        .get_all_edges()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|e| ids.contains(&e.from_node_id) && ids.contains(&e.to_node_id))
+        .collect();
+    Ok(json!({ "nodes": nodes, "edges": edges }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    fn call(db: &dyn GraphStore, method: &str, params: Value) -> Value {
+        let message = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        handle(db, &message).unwrap()
+    }
+
+    fn tool(db: &dyn GraphStore, name: &str, arguments: Value) -> Value {
+        let response = call(
+            db,
+            "tools/call",
+            json!({ "name": name, "arguments": arguments }),
+        );
+        let result = &response["result"];
+        assert_eq!(result["isError"], false, "{}", result);
+        serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_mcp_tools() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+
+        let init = call(&db, "initialize", json!({}));
+        assert_eq!(init["result"]["protocolVersion"], PROTOCOL_VERSION);
+        assert!(handle(&db, &json!({ "method": "notifications/initialized" })).is_none());
+        let tools = call(&db, "tools/list", json!({}));
+        assert_eq!(tools["result"]["tools"].as_array().unwrap().len(), 5);
+
+        let goal = tool(
+            &db,
+            "add_node",
+            json!({ "type": "goal", "title": "Add caching", "branch": "main" }),
+        );
+        let action = tool(
+            &db,
+            "add_node",
+            json!({ "type": "action", "title": "Write LRU", "description": "Redis later" }),
+        );
+        let (goal_id, action_id) = (goal["id"].as_i64().unwrap(), action["id"].as_i64().unwrap());
+        tool(&db, "link", json!({ "from": goal_id, "to": action_id }));
+
+        let goals = tool(&db, "query", json!({ "type": "goal" }));
+        assert_eq!(goals.as_array().unwrap().len(), 1);
+        let found = tool(&db, "search", json!({ "text": "redis" }));
+        assert_eq!(found[0]["id"], action_id);
+        let context = tool(&db, "get_context", json!({ "node_id": goal_id }));
+        assert_eq!(context["children"][0]["title"], "Write LRU");
+        assert_eq!(context["edges"].as_array().unwrap().len(), 1);
+
+        let bad = call(
+            &db,
+            "tools/call",
+            json!({ "name": "link", "arguments": { "from": goal_id, "to": 999 } }),
+        );
+        assert_eq!(bad["result"]["isError"], true);
+        let graph = call(&db, "resources/read", json!({ "uri": "deciduous://graph" }));
+        assert!(graph["result"]["contents"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("Add caching"));
+        assert_eq!(
+            call(&db, "nope", json!({}))["error"]["code"],
+            METHOD_NOT_FOUND
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! MCP tool invocations in trace content
+//!
+//! MCP tools reach the model two ways: tools a client like Claude Code
+//! exposes from its own MCP servers, named `mcp__<server>__<tool>`, and
+//! servers the API calls itself through the MCP connector
+//! (`mcp_tool_use`/`mcp_tool_result` blocks). Either way each call is stored
+//! as `mcp_call` content (server, tool and arguments as JSON) and each result
+//! as `mcp_result` or `mcp_error`, with `server/tool` as the tool name.
+
+use crate::db::Database;
+use crate::redact::{store_content, Redactor};
+use serde_json::{json, Value};
+
+/// Content type of an MCP call: `{"server", "tool", "arguments"}`
+pub const MCP_CALL: &str = "mcp_call";
+/// Content type of a successful MCP result
+pub const MCP_RESULT: &str = "mcp_result";
+/// Content type of a failed MCP result
+pub const MCP_ERROR: &str = "mcp_error";
+
+/// One MCP invocation seen in API traffic
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct McpCapture {
+    /// tool_use ID linking the call to its result
+    pub id: Option<String>,
+    pub server: String,
+    pub tool: String,
+    /// Arguments as JSON text; None when only the result was seen
+    pub input: Option<String>,
+    pub result: Option<String>,
+    pub is_error: bool,
+    /// Content block index, while a streamed call's arguments arrive
+    pub index: Option<u64>,
+}
+
+/// Server and tool from a client-side MCP tool name (`mcp__server__tool`)
+pub fn split_tool_name(name: &str) -> Option<(&str, &str)> {
+    let (server, tool) = name.strip_prefix("mcp__")?.split_once("__")?;
+    (!server.is_empty() && !tool.is_empty()).then_some((server, tool))
+}
+
+/// Text of a tool result's content (a string or a list of blocks)
+pub fn result_text(content: &Value) -> String {
+    match content {
+        Value::String(s) => s.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .map(|b| match b["text"].as_str() {
+                Some(text) => text.to_string(),
+                None => b.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn call_content(server: &str, tool: &str, arguments: &str) -> String {
+    let arguments = if arguments.trim().is_empty() {
+        json!({})
+    } else {
+        serde_json::from_str(arguments).unwrap_or_else(|_| json!(arguments))
+    };
+    json!({"server": server, "tool": tool, "arguments": arguments}).to_string()
+}
+
+/// Store a call and, if seen, its result against a span
+pub fn store(
+    db: &Database,
+    redactor: &Redactor,
+    span_id: i32,
+    mcp: &McpCapture,
+) -> crate::db::Result<()> {
+    let name = format!("{}/{}", mcp.server, mcp.tool);
+    if let Some(input) = &mcp.input {
+        store_content(
+            db,
+            redactor,
+            span_id,
+            MCP_CALL,
+            &call_content(&mcp.server, &mcp.tool, input),
+            Some(&name),
+            mcp.id.as_deref(),
+        )?;
+    }
+    if let Some(result) = &mcp.result {
+        let content_type = if mcp.is_error { MCP_ERROR } else { MCP_RESULT };
+        store_content(
+            db,
+            redactor,
+            span_id,
+            content_type,
+            result,
+            Some(&name),
+            mcp.id.as_deref(),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_tool_name() {
+        assert_eq!(
+            split_tool_name("mcp__github__create_issue"),
+            Some(("github", "create_issue"))
+        );
+        assert_eq!(
+            split_tool_name("mcp__my_server__do__thing"),
+            Some(("my_server", "do__thing"))
+        );
+        assert_eq!(split_tool_name("Bash"), None);
+        assert_eq!(split_tool_name("mcp__nope"), None);
+    }
+
+    #[test]
+    fn test_call_content() {
+        let content: Value =
+            serde_json::from_str(&call_content("github", "search", r#"{"q":"bug"}"#)).unwrap();
+        assert_eq!(content["server"], "github");
+        assert_eq!(content["arguments"]["q"], "bug");
+        let content: Value = serde_json::from_str(&call_content("s", "t", "")).unwrap();
+        assert_eq!(content["arguments"], json!({}));
+    }
+
+    #[test]
+    fn test_result_text() {
+        let blocks = json!([{"type": "text", "text": "one"}, {"type": "text", "text": "two"}]);
+        assert_eq!(result_text(&blocks), "one\ntwo");
+        assert_eq!(result_text(&json!("plain")), "plain");
+    }
+}
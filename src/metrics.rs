@@ -0,0 +1,589 @@
+//! Structural measures of the decision graph
+//!
+//! `deciduous metrics` reports, per node, its depth below the root goals,
+//! fan-in and fan-out, and betweenness (how many shortest paths between
+//! other nodes run through it), and for the whole graph the longest chain,
+//! the decisions most paths depend on, and options nobody followed up on.
+//! Cycle time is the time from an action to the first outcome it leads to,
+//! summarized per root goal and per branch: how long decisions take to land.
+
+use crate::db::{DecisionEdge, DecisionNode};
+use serde::Serialize;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+/// Measures for one node
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeMetrics {
+    pub id: i32,
+    pub node_type: String,
+    pub status: String,
+    pub title: String,
+    /// Edges from the nearest root goal; None if no root goal reaches it
+    pub depth: Option<usize>,
+    pub fan_in: usize,
+    pub fan_out: usize,
+    /// Shortest paths between other nodes that pass through this one
+    pub betweenness: f64,
+    /// For actions: hours until the first outcome they lead to
+    pub cycle_time_hours: Option<f64>,
+}
+
+/// Time from an action to the first outcome it has an edge to
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CycleTime {
+    pub action: i32,
+    pub outcome: i32,
+    pub hours: f64,
+    /// The action's git branch
+    pub branch: Option<String>,
+    /// Goals the action descends from
+    pub goals: Vec<i32>,
+}
+
+/// Cycle times of a group of actions
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CycleTimeSummary {
+    /// Goal ID or branch name; "all" for every action
+    pub key: String,
+    pub actions: usize,
+    pub mean_hours: f64,
+    pub median_hours: f64,
+    pub max_hours: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CycleTimes {
+    pub overall: Option<CycleTimeSummary>,
+    pub by_goal: Vec<CycleTimeSummary>,
+    pub by_branch: Vec<CycleTimeSummary>,
+    pub actions: Vec<CycleTime>,
+}
+
+/// Measures for the whole graph
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GraphMetrics {
+    pub nodes: usize,
+    pub edges: usize,
+    /// Goals with no incoming edges
+    pub root_goals: Vec<i32>,
+    pub max_depth: Option<usize>,
+    pub mean_fan_out: f64,
+    /// Node IDs along the longest path, root first
+    pub longest_chain: Vec<i32>,
+    /// Decisions by betweenness, highest first (at most 10, only nonzero)
+    pub central_decisions: Vec<i32>,
+    /// Options with no outgoing edges that weren't rejected
+    pub dead_end_options: Vec<i32>,
+    /// Nodes no root goal reaches
+    pub unreachable: Vec<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Metrics {
+    pub graph: GraphMetrics,
+    pub cycle_times: CycleTimes,
+    pub nodes: Vec<NodeMetrics>,
+}
+
+/// Formats `deciduous metrics` writes
+pub const FORMATS: &[&str] = &["json", "csv", "text"];
+
+struct Adjacency {
+    children: HashMap<i32, Vec<i32>>,
+    parents: HashMap<i32, Vec<i32>>,
+}
+
+impl Adjacency {
+    fn new(nodes: &[DecisionNode], edges: &[DecisionEdge]) -> Self {
+        let ids: HashSet<i32> = nodes.iter().map(|n| n.id).collect();
+        let mut children: HashMap<i32, Vec<i32>> = HashMap::new();
+        let mut parents: HashMap<i32, Vec<i32>> = HashMap::new();
+        for e in edges {
+            if ids.contains(&e.from_node_id) && ids.contains(&e.to_node_id) {
+                children
+                    .entry(e.from_node_id)
+                    .or_default()
+                    .push(e.to_node_id);
+                parents
+                    .entry(e.to_node_id)
+                    .or_default()
+                    .push(e.from_node_id);
+            }
+        }
+        for list in children.values_mut().chain(parents.values_mut()) {
+            list.sort_unstable();
+            list.dedup();
+        }
+        Self { children, parents }
+    }
+
+    fn children(&self, id: i32) -> &[i32] {
+        self.children
+            .get(&id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn parents(&self, id: i32) -> &[i32] {
+        self.parents.get(&id).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+/// Edges from the nearest of `roots` to each node it reaches
+fn depths(adj: &Adjacency, roots: &[i32]) -> HashMap<i32, usize> {
+    let mut depth: HashMap<i32, usize> = roots.iter().map(|r| (*r, 0)).collect();
+    let mut queue: VecDeque<i32> = roots.iter().copied().collect();
+    while let Some(id) = queue.pop_front() {
+        let next = depth[&id] + 1;
+        for &child in adj.children(id) {
+            if let Entry::Vacant(slot) = depth.entry(child) {
+                slot.insert(next);
+                queue.push_back(child);
+            }
+        }
+    }
+    depth
+}
+
+/// Brandes' betweenness centrality over the directed, unweighted graph
+fn betweenness(adj: &Adjacency, ids: &[i32]) -> HashMap<i32, f64> {
+    let mut centrality: HashMap<i32, f64> = ids.iter().map(|id| (*id, 0.0)).collect();
+    for &source in ids {
+        let mut order = Vec::new();
+        let mut preds: HashMap<i32, Vec<i32>> = HashMap::new();
+        let mut paths: HashMap<i32, f64> = HashMap::from([(source, 1.0)]);
+        let mut dist: HashMap<i32, usize> = HashMap::from([(source, 0)]);
+        let mut queue = VecDeque::from([source]);
+        while let Some(v) = queue.pop_front() {
+            order.push(v);
+            for &w in adj.children(v) {
+                let next = dist[&v] + 1;
+                if let Entry::Vacant(slot) = dist.entry(w) {
+                    slot.insert(next);
+                    queue.push_back(w);
+                }
+                if dist[&w] == next {
+                    *paths.entry(w).or_default() += paths[&v];
+                    preds.entry(w).or_default().push(v);
+                }
+            }
+        }
+        let mut dependency: HashMap<i32, f64> = HashMap::new();
+        for &w in order.iter().rev() {
+            let through_w = 1.0 + dependency.get(&w).copied().unwrap_or(0.0);
+            for &v in preds.get(&w).map(Vec::as_slice).unwrap_or_default() {
+                *dependency.entry(v).or_default() += paths[&v] / paths[&w] * through_w;
+            }
+            if w != source {
+                *centrality.entry(w).or_default() += dependency.get(&w).copied().unwrap_or(0.0);
+            }
+        }
+    }
+    centrality
+}
+
+/// The longest path starting at `id`, ignoring edges back onto the path
+fn longest_from(
+    adj: &Adjacency,
+    id: i32,
+    memo: &mut HashMap<i32, Vec<i32>>,
+    on_path: &mut HashSet<i32>,
+) -> Vec<i32> {
+    if let Some(chain) = memo.get(&id) {
+        return chain.clone();
+    }
+    on_path.insert(id);
+    let mut best: Vec<i32> = Vec::new();
+    for &child in adj.children(id) {
+        if on_path.contains(&child) {
+            continue;
+        }
+        let chain = longest_from(adj, child, memo, on_path);
+        if chain.len() > best.len() {
+            best = chain;
+        }
+    }
+    on_path.remove(&id);
+    best.insert(0, id);
+    memo.insert(id, best.clone());
+    best
+}
+
+fn summarize(key: String, hours: &[f64]) -> CycleTimeSummary {
+    let mut sorted = hours.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    CycleTimeSummary {
+        key,
+        actions: sorted.len(),
+        mean_hours: sorted.iter().sum::<f64>() / sorted.len().max(1) as f64,
+        median_hours: match sorted.len() {
+            0 => 0.0,
+            n if n % 2 == 0 => (sorted[mid - 1] + sorted[mid]) / 2.0,
+            _ => sorted[mid],
+        },
+        max_hours: sorted.last().copied().unwrap_or(0.0),
+    }
+}
+
+/// Cycle time of every action that leads to an outcome, with summaries
+fn cycle_times(nodes: &[DecisionNode], adj: &Adjacency) -> CycleTimes {
+    let by_id: HashMap<i32, &DecisionNode> = nodes.iter().map(|n| (n.id, n)).collect();
+    let created = |n: &DecisionNode| chrono::DateTime::parse_from_rfc3339(&n.created_at).ok();
+
+    let mut actions: Vec<CycleTime> = Vec::new();
+    for action in nodes.iter().filter(|n| n.node_type == "action") {
+        let Some(start) = created(action) else {
+            continue;
+        };
+        let first_outcome = adj
+            .children(action.id)
+            .iter()
+            .filter_map(|id| by_id.get(id))
+            .filter(|n| n.node_type == "outcome")
+            .filter_map(|n| created(n).map(|t| (t, n.id)))
+            .min();
+        let Some((end, outcome)) = first_outcome else {
+            continue;
+        };
+
+        let mut goals = Vec::new();
+        let mut seen = HashSet::from([action.id]);
+        let mut queue: VecDeque<i32> = adj.parents(action.id).iter().copied().collect();
+        while let Some(id) = queue.pop_front() {
+            if !seen.insert(id) {
+                continue;
+            }
+            if by_id.get(&id).is_some_and(|n| n.node_type == "goal") {
+                goals.push(id);
+            }
+            queue.extend(adj.parents(id));
+        }
+        goals.sort_unstable();
+
+        let branch = action
+            .metadata_json
+            .as_deref()
+            .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+            .and_then(|m| m["branch"].as_str().map(String::from));
+        actions.push(CycleTime {
+            action: action.id,
+            outcome,
+            hours: ((end - start).num_seconds().max(0) as f64) / 3600.0,
+            branch,
+            goals,
+        });
+    }
+    actions.sort_by_key(|c| c.action);
+
+    let mut per_goal: BTreeMap<i32, Vec<f64>> = BTreeMap::new();
+    let mut per_branch: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for c in &actions {
+        for goal in &c.goals {
+            per_goal.entry(*goal).or_default().push(c.hours);
+        }
+        if let Some(branch) = &c.branch {
+            per_branch.entry(branch.clone()).or_default().push(c.hours);
+        }
+    }
+    let all: Vec<f64> = actions.iter().map(|c| c.hours).collect();
+    CycleTimes {
+        overall: (!all.is_empty()).then(|| summarize("all".to_string(), &all)),
+        by_goal: per_goal
+            .into_iter()
+            .map(|(goal, hours)| summarize(goal.to_string(), &hours))
+            .collect(),
+        by_branch: per_branch
+            .into_iter()
+            .map(|(branch, hours)| summarize(branch, &hours))
+            .collect(),
+        actions,
+    }
+}
+
+/// Compute every measure for a graph
+pub fn compute(nodes: &[DecisionNode], edges: &[DecisionEdge]) -> Metrics {
+    let adj = Adjacency::new(nodes, edges);
+    let mut ids: Vec<i32> = nodes.iter().map(|n| n.id).collect();
+    ids.sort_unstable();
+
+    let root_goals: Vec<i32> = nodes
+        .iter()
+        .filter(|n| n.node_type == "goal" && adj.parents(n.id).is_empty())
+        .map(|n| n.id)
+        .collect();
+    let depth = depths(&adj, &root_goals);
+    let centrality = betweenness(&adj, &ids);
+    let cycle_times = cycle_times(nodes, &adj);
+
+    let mut per_node: Vec<NodeMetrics> = nodes
+        .iter()
+        .map(|n| NodeMetrics {
+            id: n.id,
+            node_type: n.node_type.to_string(),
+            status: n.status.to_string(),
+            title: n.title.clone(),
+            depth: depth.get(&n.id).copied(),
+            fan_in: adj.parents(n.id).len(),
+            fan_out: adj.children(n.id).len(),
+            betweenness: centrality.get(&n.id).copied().unwrap_or(0.0),
+            cycle_time_hours: cycle_times
+                .actions
+                .iter()
+                .find(|c| c.action == n.id)
+                .map(|c| c.hours),
+        })
+        .collect();
+    per_node.sort_by_key(|m| m.id);
+
+    let mut memo = HashMap::new();
+    let mut longest_chain: Vec<i32> = Vec::new();
+    for &id in &ids {
+        if adj.parents(id).is_empty() {
+            let chain = longest_from(&adj, id, &mut memo, &mut HashSet::new());
+            if chain.len() > longest_chain.len() {
+                longest_chain = chain;
+            }
+        }
+    }
+
+    let rejected: HashSet<i32> = edges
+        .iter()
+        .filter(|e| e.edge_type == "rejected")
+        .map(|e| e.to_node_id)
+        .collect();
+    let dead_end_options = nodes
+        .iter()
+        .filter(|n| n.node_type == "option" && adj.children(n.id).is_empty())
+        .filter(|n| n.status != "rejected" && !rejected.contains(&n.id))
+        .map(|n| n.id)
+        .collect();
+
+    let mut decisions: Vec<&NodeMetrics> = per_node
+        .iter()
+        .filter(|m| m.node_type == "decision" && m.betweenness > 0.0)
+        .collect();
+    decisions.sort_by(|a, b| b.betweenness.total_cmp(&a.betweenness));
+
+    let fan_out_total: usize = per_node.iter().map(|m| m.fan_out).sum();
+    Metrics {
+        graph: GraphMetrics {
+            nodes: per_node.len(),
+            edges: edges.len(),
+            max_depth: depth.values().max().copied(),
+            mean_fan_out: if per_node.is_empty() {
+                0.0
+            } else {
+                fan_out_total as f64 / per_node.len() as f64
+            },
+            longest_chain,
+            central_decisions: decisions.iter().take(10).map(|m| m.id).collect(),
+            dead_end_options,
+            unreachable: per_node
+                .iter()
+                .filter(|m| m.depth.is_none())
+                .map(|m| m.id)
+                .collect(),
+            root_goals,
+        },
+        cycle_times,
+        nodes: per_node,
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One row per node, with a header
+pub fn to_csv(metrics: &Metrics) -> String {
+    let mut csv = String::from(
+        "id,node_type,status,title,depth,fan_in,fan_out,betweenness,cycle_time_hours\n",
+    );
+    for m in &metrics.nodes {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{:.3},{}\n",
+            m.id,
+            csv_field(&m.node_type),
+            csv_field(&m.status),
+            csv_field(&m.title),
+            m.depth.map(|d| d.to_string()).unwrap_or_default(),
+            m.fan_in,
+            m.fan_out,
+            m.betweenness,
+            m.cycle_time_hours
+                .map(|h| format!("{:.2}", h))
+                .unwrap_or_default()
+        ));
+    }
+    csv
+}
+
+/// A readable summary of the graph measures and cycle times
+pub fn to_text(metrics: &Metrics) -> String {
+    let g = &metrics.graph;
+    let ids = |ids: &[i32]| {
+        if ids.is_empty() {
+            "none".to_string()
+        } else {
+            ids.iter()
+                .map(|id| format!("#{}", id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    };
+    let mut text = format!(
+        "{} nodes, {} edges, mean fan-out {:.2}\n",
+        g.nodes, g.edges, g.mean_fan_out
+    );
+    text.push_str(&format!("Root goals: {}\n", ids(&g.root_goals)));
+    if let Some(depth) = g.max_depth {
+        text.push_str(&format!("Max depth: {}\n", depth));
+    }
+    text.push_str(&format!(
+        "Longest chain ({}): {}\n",
+        g.longest_chain.len(),
+        ids(&g.longest_chain)
+    ));
+    text.push_str(&format!(
+        "Central decisions: {}\n",
+        ids(&g.central_decisions)
+    ));
+    text.push_str(&format!("Dead-end options: {}\n", ids(&g.dead_end_options)));
+    text.push_str(&format!(
+        "Unreachable from goals: {}\n",
+        ids(&g.unreachable)
+    ));
+
+    let cycle = &metrics.cycle_times;
+    let Some(overall) = &cycle.overall else {
+        text.push_str("\nCycle time: no actions lead to an outcome yet\n");
+        return text;
+    };
+    let row = |label: &str, s: &CycleTimeSummary| {
+        format!(
+            "  {:<24} {:>3} actions  median {:>7.1}h  mean {:>7.1}h  max {:>7.1}h\n",
+            label, s.actions, s.median_hours, s.mean_hours, s.max_hours
+        )
+    };
+    text.push_str("\nCycle time (action to first outcome):\n");
+    text.push_str(&row("all", overall));
+    for s in &cycle.by_goal {
+        text.push_str(&row(&format!("goal #{}", s.key), s));
+    }
+    for s in &cycle.by_branch {
+        text.push_str(&row(&format!("branch {}", s.key), s));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn test_metrics() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let goal = db.node("goal", "Ship search").insert().unwrap();
+        let decision = db.node("decision", "Which index?").insert().unwrap();
+        let tantivy = db.node("option", "Tantivy").insert().unwrap();
+        let fts = db
+            .node("option", "SQLite FTS, \"built in\"")
+            .insert()
+            .unwrap();
+        let lucene = db.node("option", "Lucene").insert().unwrap();
+        let action = db.node("action", "Add FTS table").insert().unwrap();
+        let stray = db.node("observation", "Unlinked note").insert().unwrap();
+        db.edge(goal, decision).insert().unwrap();
+        for option in [tantivy, fts, lucene] {
+            db.edge(decision, option).insert().unwrap();
+        }
+        db.edge(decision, lucene)
+            .edge_type("rejected")
+            .insert()
+            .unwrap();
+        db.edge(fts, action).insert().unwrap();
+
+        let metrics = compute(&db.get_all_nodes().unwrap(), &db.get_all_edges().unwrap());
+        let graph = &metrics.graph;
+        assert_eq!(graph.root_goals, vec![goal]);
+        assert_eq!(graph.max_depth, Some(3));
+        assert_eq!(graph.longest_chain, vec![goal, decision, fts, action]);
+        assert_eq!(graph.dead_end_options, vec![tantivy]);
+        assert_eq!(graph.central_decisions, vec![decision]);
+        assert_eq!(graph.unreachable, vec![stray]);
+
+        let node = |id: i32| metrics.nodes.iter().find(|m| m.id == id).unwrap();
+        assert_eq!((node(decision).fan_in, node(decision).fan_out), (1, 3));
+        // Paths from the goal to the options and the action run through the decision
+        assert_eq!(node(decision).betweenness, 4.0);
+        assert_eq!(node(fts).betweenness, 2.0);
+
+        let csv = to_csv(&metrics);
+        assert!(csv.starts_with("id,node_type,"));
+        assert!(csv.contains(&format!(
+            "{},option,pending,\"SQLite FTS, \"\"built in\"\"\",2,1,1,2.000,\n",
+            fts
+        )));
+        assert!(csv.contains(&format!(
+            "{},observation,pending,Unlinked note,,0,0,",
+            stray
+        )));
+    }
+
+    #[test]
+    fn test_cycle_times() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let goal = db.node("goal", "Faster builds").insert().unwrap();
+        let cache = db
+            .node("action", "Cache deps")
+            .branch("ci-cache")
+            .insert()
+            .unwrap();
+        let split = db.node("action", "Split test job").insert().unwrap();
+        let pending = db.node("action", "Try sccache").insert().unwrap();
+        let done = db.node("outcome", "Deps cached").insert().unwrap();
+        let faster = db.node("outcome", "Tests in parallel").insert().unwrap();
+        for action in [cache, split, pending] {
+            db.edge(goal, action).insert().unwrap();
+        }
+        db.edge(cache, done).insert().unwrap();
+        db.edge(split, faster).insert().unwrap();
+
+        let mut nodes = db.get_all_nodes().unwrap();
+        for node in &mut nodes {
+            node.created_at = match node.id {
+                id if id == done => "2025-06-01T12:00:00+00:00",
+                id if id == faster => "2025-06-01T10:00:00+00:00",
+                _ => "2025-06-01T09:00:00+00:00",
+            }
+            .to_string();
+        }
+        let metrics = compute(&nodes, &db.get_all_edges().unwrap());
+        let cycle = &metrics.cycle_times;
+        assert_eq!(cycle.actions.len(), 2);
+        assert_eq!(cycle.actions[0].hours, 3.0);
+        assert_eq!(cycle.actions[0].goals, vec![goal]);
+        let overall = cycle.overall.as_ref().unwrap();
+        assert_eq!(
+            (overall.actions, overall.median_hours, overall.max_hours),
+            (2, 2.0, 3.0)
+        );
+        assert_eq!(cycle.by_goal[0].key, goal.to_string());
+        assert_eq!(cycle.by_branch.len(), 1);
+        assert_eq!(cycle.by_branch[0].key, "ci-cache");
+
+        let node = |id: i32| metrics.nodes.iter().find(|m| m.id == id).unwrap();
+        assert_eq!(node(split).cycle_time_hours, Some(1.0));
+        assert_eq!(node(pending).cycle_time_hours, None);
+        assert!(to_text(&metrics).contains("  branch ci-cache"));
+    }
+}
@@ -0,0 +1,252 @@
+//! Node types, statuses and edge types
+//!
+//! The database stores these as text and the CLI accepts any value, so each
+//! enum has a variant per built-in value plus `Custom` for everything else.
+//! They serialize as the bare string, compare equal to `&str`, and deref to
+//! `str`, so code that treated them as strings keeps working.
+
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::sql_types::Text;
+use diesel::sqlite::{Sqlite, SqliteValue};
+use std::fmt;
+use std::ops::Deref;
+#[cfg(feature = "ts-rs")]
+use ts_rs::TS;
+
+macro_rules! string_enum {
+    (
+        $(#[$meta:meta])*
+        $name:ident { $($(#[$vmeta:meta])* $variant:ident => $value:literal,)+ }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, FromSqlRow)]
+        #[cfg_attr(feature = "ts-rs", derive(TS))]
+        #[cfg_attr(feature = "ts-rs", ts(export, rename_all = "snake_case"))]
+        pub enum $name {
+            $($(#[$vmeta])* $variant,)+
+            /// Any other value, kept as written
+            #[cfg_attr(feature = "ts-rs", ts(untagged))]
+            Custom(String),
+        }
+
+        impl $name {
+            /// The built-in values, in the order the CLI lists them
+            pub const ALL: &'static [$name] = &[$($name::$variant),+];
+
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $($name::$variant => $value,)+
+                    $name::Custom(value) => value,
+                }
+            }
+
+            /// Whether this is a value outside the built-in set
+            pub fn is_custom(&self) -> bool {
+                matches!(self, $name::Custom(_))
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                match value {
+                    $($value => $name::$variant,)+
+                    other => $name::Custom(other.to_string()),
+                }
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                match $name::from(value.as_str()) {
+                    $name::Custom(_) => $name::Custom(value),
+                    known => known,
+                }
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                match value {
+                    $name::Custom(value) => value,
+                    known => known.as_str().to_string(),
+                }
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name::ALL[0].clone()
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        // Equality, hashing and ordering all go through `as_str`, so a
+        // `Custom` holding a built-in value is the same as that variant.
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.as_str() == other.as_str()
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl std::hash::Hash for $name {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.as_str().hash(state)
+            }
+        }
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                self.as_str().cmp(other.as_str())
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.as_str() == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.as_str() == *other
+            }
+        }
+
+        impl PartialEq<String> for $name {
+            fn eq(&self, other: &String) -> bool {
+                self.as_str() == other
+            }
+        }
+
+        impl PartialEq<$name> for str {
+            fn eq(&self, other: &$name) -> bool {
+                self == other.as_str()
+            }
+        }
+
+        impl PartialEq<$name> for &str {
+            fn eq(&self, other: &$name) -> bool {
+                *self == other.as_str()
+            }
+        }
+
+        impl PartialEq<$name> for String {
+            fn eq(&self, other: &$name) -> bool {
+                self == other.as_str()
+            }
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                String::deserialize(deserializer).map($name::from)
+            }
+        }
+
+        impl FromSql<Text, Sqlite> for $name {
+            fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+                <String as FromSql<Text, Sqlite>>::from_sql(value).map($name::from)
+            }
+        }
+    };
+}
+
+string_enum! {
+    /// What a node records
+    NodeType {
+        /// High-level objective
+        Goal => "goal",
+        /// Choice point with options
+        Decision => "decision",
+        /// Approach considered
+        Option => "option",
+        /// What was implemented
+        Action => "action",
+        /// What happened
+        Outcome => "outcome",
+        /// Technical insight
+        Observation => "observation",
+    }
+}
+
+string_enum! {
+    /// Where a node stands
+    NodeStatus {
+        Pending => "pending",
+        Active => "active",
+        Completed => "completed",
+        Rejected => "rejected",
+    }
+}
+
+string_enum! {
+    /// How two nodes are related
+    EdgeType {
+        LeadsTo => "leads_to",
+        Requires => "requires",
+        Chosen => "chosen",
+        Rejected => "rejected",
+        Blocks => "blocks",
+        Enables => "enables",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_and_custom_values() {
+        assert_eq!(NodeType::from("goal"), NodeType::Goal);
+        assert_eq!(EdgeType::from("leads_to"), EdgeType::LeadsTo);
+        let custom = NodeType::from("hypothesis");
+        assert!(custom.is_custom());
+        assert_eq!(custom, "hypothesis");
+        assert_eq!(String::from(custom.clone()), "hypothesis");
+        assert_eq!(NodeStatus::default(), NodeStatus::Pending);
+        assert_eq!(EdgeType::default(), "leads_to");
+
+        let json = serde_json::to_string(&[NodeType::Decision, custom]).unwrap();
+        assert_eq!(json, r#"["decision","hypothesis"]"#);
+        let back: Vec<NodeType> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, [NodeType::Decision, NodeType::from("hypothesis")]);
+
+        let spelled_out = NodeType::Custom("goal".to_string());
+        assert_eq!(spelled_out, NodeType::Goal);
+        assert_eq!(spelled_out.cmp(&NodeType::Goal), std::cmp::Ordering::Equal);
+        let set: std::collections::HashSet<_> = [spelled_out, NodeType::Goal].into();
+        assert_eq!(set.len(), 1);
+    }
+}
@@ -0,0 +1,298 @@
+//! Mirror nodes into a Notion database
+//!
+//! `deciduous sync --notion` keeps one page per goal, decision and outcome
+//! (the `[notion] node_types`) in the database named by `[notion]
+//! database_id`. Pages are matched to nodes by change_id, stored in the
+//! `Deciduous ID` text property, so the mirror works from any clone. Which
+//! database property each node field goes to is set under
+//! `[notion.properties]`. Pages whose node is gone are archived. Requests go
+//! through curl with the token from `NOTION_TOKEN` (or `[notion] token_env`).
+
+use crate::config::{NotionConfig, NotionProperties};
+use crate::db::DecisionNode;
+use crate::remote_patch::{curl_header, curl_request};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+
+const API_BASE: &str = "https://api.notion.com/v1";
+const NOTION_VERSION: &str = "2022-06-28";
+
+/// Notion's limit on one rich text item
+const MAX_TEXT: usize = 2000;
+
+fn text(content: &str) -> Value {
+    let content: String = content.chars().take(MAX_TEXT).collect();
+    json!([{ "type": "text", "text": { "content": content } }])
+}
+
+/// The page properties for `node`
+pub fn properties(node: &DecisionNode, names: &NotionProperties) -> Map<String, Value> {
+    let mut props = Map::new();
+    let mut set = |name: &str, value: Value| {
+        if !name.is_empty() {
+            props.insert(name.to_string(), value);
+        }
+    };
+    let select = |value: &str| json!({ "select": { "name": value } });
+    let meta: Value = node
+        .metadata_json
+        .as_deref()
+        .and_then(|m| serde_json::from_str(m).ok())
+        .unwrap_or(Value::Null);
+
+    set(&names.title, json!({ "title": text(&node.title) }));
+    set(
+        &names.change_id,
+        json!({ "rich_text": text(&node.change_id) }),
+    );
+    set(&names.node_type, select(node.node_type.as_str()));
+    set(&names.status, select(node.status.as_str()));
+    set(&names.confidence, json!({ "number": meta["confidence"] }));
+    set(
+        &names.branch,
+        json!({ "rich_text": text(meta["branch"].as_str().unwrap_or_default()) }),
+    );
+    set(
+        &names.description,
+        json!({ "rich_text": text(node.description.as_deref().unwrap_or_default()) }),
+    );
+    props
+}
+
+/// A property's value as plain JSON, for comparing what a page has with
+/// what `properties` would send
+fn plain(property: &Value) -> Value {
+    let texts = |items: &Value| {
+        let joined: String = items
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|t| t["plain_text"].as_str().or(t["text"]["content"].as_str()))
+            .collect();
+        json!(joined)
+    };
+    if property.get("title").is_some() {
+        texts(&property["title"])
+    } else if property.get("rich_text").is_some() {
+        texts(&property["rich_text"])
+    } else if property.get("select").is_some() {
+        property["select"]["name"].clone()
+    } else if property.get("number").is_some() {
+        property["number"]
+            .as_f64()
+            .map(|n| json!(n))
+            .unwrap_or(Value::Null)
+    } else {
+        Value::Null
+    }
+}
+
+/// What mirroring will do
+#[derive(Debug, Default)]
+pub struct NotionPlan {
+    /// Nodes without a page
+    pub create: Vec<DecisionNode>,
+    /// (page ID, node) for pages that differ from their node
+    pub update: Vec<(String, DecisionNode)>,
+    /// Pages whose node is gone or no longer mirrored
+    pub archive: Vec<String>,
+    pub unchanged: usize,
+}
+
+/// Match `nodes` against the database's `pages`
+pub fn plan(nodes: &[DecisionNode], pages: &[Value], config: &NotionConfig) -> NotionPlan {
+    let names = &config.properties;
+    let mut by_change_id: HashMap<String, &Value> = HashMap::new();
+    for page in pages {
+        let change_id = plain(&page["properties"][&names.change_id]);
+        if let Some(change_id) = change_id.as_str().filter(|c| !c.is_empty()) {
+            by_change_id.insert(change_id.to_string(), page);
+        }
+    }
+
+    let mut plan = NotionPlan::default();
+    let mut mirrored = HashSet::new();
+    for node in nodes
+        .iter()
+        .filter(|n| config.node_types.iter().any(|t| t == n.node_type.as_str()))
+    {
+        mirrored.insert(node.change_id.as_str());
+        let Some(page) = by_change_id.get(&node.change_id) else {
+            plan.create.push(node.clone());
+            continue;
+        };
+        let same = properties(node, names)
+            .iter()
+            .all(|(name, value)| plain(value) == plain(&page["properties"][name]));
+        if same {
+            plan.unchanged += 1;
+        } else if let Some(id) = page["id"].as_str() {
+            plan.update.push((id.to_string(), node.clone()));
+        }
+    }
+    for (change_id, page) in &by_change_id {
+        if !mirrored.contains(change_id.as_str()) {
+            if let Some(id) = page["id"].as_str() {
+                plan.archive.push(id.to_string());
+            }
+        }
+    }
+    plan.archive.sort();
+    plan
+}
+
+/// The Notion API, called through curl
+pub struct NotionClient {
+    token: String,
+    database_id: String,
+}
+
+impl NotionClient {
+    /// A client for the configured database, if the token is set
+    pub fn from_config(config: &NotionConfig) -> Result<Self, String> {
+        let database_id = config
+            .database_id
+            .clone()
+            .filter(|d| !d.is_empty())
+            .ok_or("Set [notion] database_id in .deciduous/config.toml")?;
+        let token = std::env::var(&config.token_env)
+            .ok()
+            .filter(|t| !t.is_empty())
+            .ok_or_else(|| format!("Set {} to a Notion integration token", config.token_env))?;
+        Ok(Self { token, database_id })
+    }
+
+    fn request(&self, method: &str, path: &str, body: &Value) -> Result<Value, String> {
+        let url = format!("{}/{}", API_BASE, path);
+        let config = curl_header("Content-Type: application/json")
+            + &curl_header(&format!("Notion-Version: {}", NOTION_VERSION))
+            + &curl_header(&format!("Authorization: Bearer {}", self.token));
+        let (status, response) =
+            curl_request(method, &url, &config, Some(body.to_string().as_bytes()))
+                .map_err(|e| e.to_string())?;
+        let response: Value = serde_json::from_str(&response).unwrap_or(Value::Null);
+        match status {
+            200..=299 => Ok(response),
+            0 => Err(format!("no response from {}", url)),
+            code => Err(format!(
+                "HTTP {} from {} {}: {}",
+                code,
+                method,
+                path,
+                response["message"].as_str().unwrap_or("no message")
+            )),
+        }
+    }
+
+    /// Every page in the database, following `next_cursor`
+    pub fn pages(&self) -> Result<Vec<Value>, String> {
+        let mut pages = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut body = json!({ "page_size": 100 });
+            if let Some(cursor) = &cursor {
+                body["start_cursor"] = json!(cursor);
+            }
+            let page = self.request(
+                "POST",
+                &format!("databases/{}/query", self.database_id),
+                &body,
+            )?;
+            pages.extend(page["results"].as_array().into_iter().flatten().cloned());
+            cursor = page["next_cursor"].as_str().map(String::from);
+            if page["has_more"].as_bool() != Some(true) || cursor.is_none() {
+                return Ok(pages);
+            }
+        }
+    }
+
+    pub fn create_page(&self, properties: Map<String, Value>) -> Result<(), String> {
+        let body = json!({
+            "parent": { "database_id": self.database_id },
+            "properties": properties,
+        });
+        self.request("POST", "pages", &body).map(|_| ())
+    }
+
+    pub fn update_page(&self, page_id: &str, properties: Map<String, Value>) -> Result<(), String> {
+        let body = json!({ "properties": properties });
+        self.request("PATCH", &format!("pages/{}", page_id), &body)
+            .map(|_| ())
+    }
+
+    pub fn archive_page(&self, page_id: &str) -> Result<(), String> {
+        let body = json!({ "archived": true });
+        self.request("PATCH", &format!("pages/{}", page_id), &body)
+            .map(|_| ())
+    }
+}
+
+/// Bring the database in line with `nodes`; returns what was done
+pub fn mirror(
+    client: &NotionClient,
+    nodes: &[DecisionNode],
+    config: &NotionConfig,
+) -> Result<NotionPlan, String> {
+    let plan = plan(nodes, &client.pages()?, config);
+    for node in &plan.create {
+        client.create_page(properties(node, &config.properties))?;
+    }
+    for (page_id, node) in &plan.update {
+        client.update_page(page_id, properties(node, &config.properties))?;
+    }
+    for page_id in &plan.archive {
+        client.archive_page(page_id)?;
+    }
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn test_notion_plan() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        db.node("goal", "Paged graph API")
+            .confidence(90)
+            .branch("feature/paging")
+            .insert()
+            .unwrap();
+        let decision = db.node("decision", "Cursor format").insert().unwrap();
+        db.node("decision", "Page size").insert().unwrap();
+        db.node("action", "Add cursor").insert().unwrap();
+        let nodes = db.get_all_nodes().unwrap();
+
+        let config = NotionConfig::default();
+        let page = |id: &str, node: &DecisionNode| json!({ "id": id, "properties": properties(node, &config.properties) });
+        let mut stale = nodes[1].clone();
+        stale.title = "Cursor shape".to_string();
+        let mut gone = nodes[2].clone();
+        gone.change_id = "deleted".to_string();
+        // Notion returns text as plain_text and numbers as floats
+        let mut fetched = page("p-goal", &nodes[0]);
+        fetched["properties"]["Name"] = json!({ "title": [{ "plain_text": "Paged graph API" }] });
+        fetched["properties"]["Confidence"] = json!({ "number": 90.0 });
+        let pages = vec![fetched, page("p-cursor", &stale), page("p-gone", &gone)];
+
+        let plan = plan(&nodes, &pages, &config);
+        assert_eq!(plan.unchanged, 1);
+        assert_eq!(
+            plan.update
+                .iter()
+                .map(|(p, n)| (p.as_str(), n.id))
+                .collect::<Vec<_>>(),
+            vec![("p-cursor", decision)]
+        );
+        assert_eq!(plan.create.len(), 1);
+        assert_eq!(plan.create[0].title, "Page size");
+        assert_eq!(plan.archive, vec!["p-gone"]);
+
+        let props = properties(&nodes[0], &config.properties);
+        assert_eq!(props["Type"], json!({ "select": { "name": "goal" } }));
+        assert_eq!(plain(&props["Branch"]), json!("feature/paging"));
+        assert!(!props.contains_key(""));
+    }
+}
@@ -0,0 +1,345 @@
+//! OpenTelemetry export for trace sessions
+//!
+//! A trace session becomes one OTel trace: a root span for the session with
+//! a child span per API call, carrying token counts and cost as attributes
+//! (following the `gen_ai.*` semantic conventions where they exist). The
+//! payload is OTLP/HTTP JSON, sent with `curl` to a collector such as Jaeger
+//! or Tempo.
+
+use crate::db::{TraceSession, TraceSpan};
+use crate::pricing::PriceTable;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// First `bytes` bytes of a SHA-256, hex encoded
+fn hashed_id(seed: &str, bytes: usize) -> String {
+    Sha256::digest(seed.as_bytes())[..bytes]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 32-hex-digit trace id; a UUID session id is used as-is
+pub fn trace_id(session_id: &str) -> String {
+    let hex: String = session_id.chars().filter(|c| *c != '-').collect();
+    if hex.len() == 32 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        hex.to_ascii_lowercase()
+    } else {
+        hashed_id(session_id, 16)
+    }
+}
+
+/// 16-hex-digit span id derived from a stable identifier
+pub fn span_id(seed: &str) -> String {
+    hashed_id(seed, 8)
+}
+
+/// RFC 3339 timestamp to OTLP's nanoseconds-since-epoch string
+fn unix_nanos(timestamp: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()?
+        .timestamp_nanos_opt()
+}
+
+fn string_attr(key: &str, value: &str) -> Value {
+    json!({"key": key, "value": {"stringValue": value}})
+}
+
+fn int_attr(key: &str, value: i64) -> Value {
+    json!({"key": key, "value": {"intValue": value.to_string()}})
+}
+
+/// The `gen_ai.system` value for a model name
+fn model_system(model: &str) -> &'static str {
+    if model.starts_with("claude") {
+        "anthropic"
+    } else if model.starts_with("gpt")
+        || (model.starts_with('o') && model[1..].starts_with(|c: char| c.is_ascii_digit()))
+    {
+        "openai"
+    } else {
+        "unknown"
+    }
+}
+
+fn span_json(session: &TraceSession, span: &TraceSpan, prices: &PriceTable) -> Option<Value> {
+    let start = unix_nanos(&span.started_at)?;
+    let end = span
+        .completed_at
+        .as_deref()
+        .and_then(unix_nanos)
+        .or_else(|| span.duration_ms.map(|ms| start + i64::from(ms) * 1_000_000))
+        .unwrap_or(start);
+    let model = span.model.as_deref().unwrap_or("unknown");
+
+    let mut attributes = vec![
+        string_attr("gen_ai.operation.name", "chat"),
+        string_attr("gen_ai.system", model_system(model)),
+        string_attr("gen_ai.request.model", model),
+        int_attr("deciduous.sequence_num", i64::from(span.sequence_num)),
+    ];
+    let ints = [
+        ("gen_ai.usage.input_tokens", span.input_tokens),
+        ("gen_ai.usage.output_tokens", span.output_tokens),
+        ("deciduous.cache_read_tokens", span.cache_read),
+        ("deciduous.cache_write_tokens", span.cache_write),
+        ("deciduous.linked_node_id", span.linked_node_id),
+    ];
+    for (key, value) in ints {
+        if let Some(v) = value {
+            attributes.push(int_attr(key, i64::from(v)));
+        }
+    }
+    if let Some(id) = &span.request_id {
+        attributes.push(string_attr("gen_ai.response.id", id));
+    }
+    if let Some(reason) = &span.stop_reason {
+        attributes.push(json!({
+            "key": "gen_ai.response.finish_reasons",
+            "value": {"arrayValue": {"values": [{"stringValue": reason}]}},
+        }));
+    }
+    if let Some(tools) = &span.tool_names {
+        attributes.push(string_attr("deciduous.tool_names", tools));
+    }
+    if let Some(cost) = prices.span_cost(span) {
+        attributes.push(json!({"key": "deciduous.cost_usd", "value": {"doubleValue": cost}}));
+    }
+
+    Some(json!({
+        "traceId": trace_id(&session.session_id),
+        "spanId": span_id(&span.change_id),
+        "parentSpanId": span_id(&session.session_id),
+        "name": format!("chat {}", model),
+        "kind": 3, // SPAN_KIND_CLIENT
+        "startTimeUnixNano": start.to_string(),
+        "endTimeUnixNano": end.to_string(),
+        "attributes": attributes,
+    }))
+}
+
+/// OTLP/HTTP JSON payload for a session and its spans
+///
+/// Spans with unparseable timestamps are skipped.
+pub fn session_to_otlp(session: &TraceSession, spans: &[TraceSpan], prices: &PriceTable) -> Value {
+    let children: Vec<Value> = spans
+        .iter()
+        .filter_map(|s| span_json(session, s, prices))
+        .collect();
+
+    let start = unix_nanos(&session.started_at).unwrap_or(0);
+    let last_child_end = children
+        .iter()
+        .filter_map(|c| c["endTimeUnixNano"].as_str()?.parse::<i64>().ok())
+        .max();
+    let end = session
+        .ended_at
+        .as_deref()
+        .and_then(unix_nanos)
+        .or(last_child_end)
+        .unwrap_or(start);
+
+    let mut attributes = vec![
+        string_attr("deciduous.session_id", &session.session_id),
+        int_attr(
+            "gen_ai.usage.input_tokens",
+            i64::from(session.total_input_tokens),
+        ),
+        int_attr(
+            "gen_ai.usage.output_tokens",
+            i64::from(session.total_output_tokens),
+        ),
+        json!({"key": "deciduous.cost_usd", "value": {"doubleValue": prices.total_cost(spans)}}),
+    ];
+    if let Some(command) = &session.command {
+        attributes.push(string_attr("process.command_line", command));
+    }
+    if let Some(branch) = &session.git_branch {
+        attributes.push(string_attr("vcs.ref.head.name", branch));
+    }
+    if let Some(node) = session.linked_node_id {
+        attributes.push(int_attr("deciduous.linked_node_id", i64::from(node)));
+    }
+
+    let root = json!({
+        "traceId": trace_id(&session.session_id),
+        "spanId": span_id(&session.session_id),
+        "name": format!("session {}", session.command.as_deref().unwrap_or("trace")),
+        "kind": 1, // SPAN_KIND_INTERNAL
+        "startTimeUnixNano": start.to_string(),
+        "endTimeUnixNano": end.to_string(),
+        "attributes": attributes,
+    });
+
+    let mut all_spans = vec![root];
+    all_spans.extend(children);
+
+    let mut resource = vec![string_attr("service.name", "deciduous")];
+    if let Some(dir) = &session.working_dir {
+        resource.push(string_attr("process.working_directory", dir));
+    }
+
+    json!({
+        "resourceSpans": [{
+            "resource": {"attributes": resource},
+            "scopeSpans": [{
+                "scope": {"name": "deciduous", "version": env!("CARGO_PKG_VERSION")},
+                "spans": all_spans,
+            }],
+        }],
+    })
+}
+
+/// The traces URL for a collector endpoint (`/v1/traces` is appended if missing)
+pub fn traces_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    if endpoint.ends_with("/v1/traces") {
+        endpoint.to_string()
+    } else {
+        format!("{}/v1/traces", endpoint)
+    }
+}
+
+/// POST a payload to an OTLP/HTTP collector
+///
+/// `headers` are extra `Name: value` lines, e.g. for collector auth.
+pub fn send(endpoint: &str, payload: &Value, headers: &[String]) -> Result<(), String> {
+    let url = traces_url(endpoint);
+    let mut cmd = Command::new("curl");
+    cmd.args(["-sS", "-X", "POST", "-w", "\n%{http_code}"])
+        .args(["-H", "Content-Type: application/json"]);
+    for header in headers {
+        cmd.arg("-H").arg(header);
+    }
+    cmd.args(["--data-binary", "@-"])
+        .arg(&url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = cmd.spawn().map_err(|e| format!("running curl: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(payload.to_string().as_bytes())
+            .map_err(|e| format!("sending payload: {}", e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("running curl: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (body, status) = stdout.rsplit_once('\n').unwrap_or(("", &*stdout));
+    match status.trim().parse::<u16>() {
+        Ok(200..=299) => Ok(()),
+        Ok(code) => Err(format!("{} returned HTTP {}: {}", url, code, body.trim())),
+        Err(_) => Err(format!("no response from {}", url)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> TraceSession {
+        TraceSession {
+            id: 1,
+            session_id: "0b6f6f4e-5c39-4c1b-9a43-3c1f0e8d2a11".to_string(),
+            started_at: "2025-06-01T10:00:00+00:00".to_string(),
+            ended_at: None,
+            working_dir: Some("/repo".to_string()),
+            git_branch: Some("main".to_string()),
+            command: Some("claude".to_string()),
+            summary: None,
+            total_input_tokens: 100,
+            total_output_tokens: 20,
+            total_cache_read: 0,
+            total_cache_write: 0,
+            linked_node_id: Some(7),
+            linked_change_id: None,
+        }
+    }
+
+    fn span() -> TraceSpan {
+        TraceSpan {
+            change_id: "span-change".to_string(),
+            session_id: "0b6f6f4e-5c39-4c1b-9a43-3c1f0e8d2a11".to_string(),
+            started_at: "2025-06-01T10:00:01+00:00".to_string(),
+            duration_ms: Some(1500),
+            model: Some("claude-sonnet-4-20250514".to_string()),
+            request_id: Some("msg_1".to_string()),
+            stop_reason: Some("end_turn".to_string()),
+            input_tokens: Some(100),
+            output_tokens: Some(20),
+            tool_names: Some("Bash,Read".to_string()),
+            ..TraceSpan::for_test(1)
+        }
+    }
+
+    #[test]
+    fn test_ids() {
+        assert_eq!(
+            trace_id("0b6f6f4e-5c39-4c1b-9a43-3c1f0e8d2a11"),
+            "0b6f6f4e5c394c1b9a433c1f0e8d2a11"
+        );
+        assert_eq!(trace_id("not-a-uuid").len(), 32);
+        assert_eq!(span_id("x").len(), 16);
+        assert_eq!(span_id("x"), span_id("x"));
+        assert_ne!(span_id("x"), span_id("y"));
+    }
+
+    #[test]
+    fn test_session_to_otlp() {
+        let payload = session_to_otlp(&session(), &[span()], &PriceTable::default());
+        let spans = payload["resourceSpans"][0]["scopeSpans"][0]["spans"]
+            .as_array()
+            .unwrap();
+        assert_eq!(spans.len(), 2);
+
+        let (root, child) = (&spans[0], &spans[1]);
+        assert_eq!(root["traceId"], child["traceId"]);
+        assert_eq!(child["parentSpanId"], root["spanId"]);
+        assert_eq!(child["name"], "chat claude-sonnet-4-20250514");
+        // Ends 1.5s after starting, and the open session ends with it
+        assert_eq!(child["endTimeUnixNano"], "1748772002500000000");
+        assert_eq!(root["endTimeUnixNano"], child["endTimeUnixNano"]);
+
+        let attr = |span: &Value, key: &str| {
+            span["attributes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|a| a["key"] == key)
+                .map(|a| a["value"].clone())
+        };
+        assert_eq!(
+            attr(child, "gen_ai.usage.input_tokens").unwrap()["intValue"],
+            "100"
+        );
+        assert_eq!(
+            attr(child, "gen_ai.system").unwrap()["stringValue"],
+            "anthropic"
+        );
+        assert!(attr(child, "deciduous.cost_usd").is_some());
+        assert_eq!(
+            attr(root, "deciduous.linked_node_id").unwrap()["intValue"],
+            "7"
+        );
+    }
+
+    #[test]
+    fn test_traces_url() {
+        assert_eq!(
+            traces_url("http://localhost:4318"),
+            "http://localhost:4318/v1/traces"
+        );
+        assert_eq!(
+            traces_url("http://tempo:4318/v1/traces/"),
+            "http://tempo:4318/v1/traces"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Find patches in git history that haven't been applied here
+//!
+//! `deciduous diff scan` walks `git log` for patch files added or modified
+//! under `.deciduous/patches/`, oldest commit first, and reads each version
+//! straight from its commit. Versions whose SHA-256 is already in the
+//! `applied_patches` table are skipped, so a teammate's patch is offered
+//! once per change however many branches it was merged through.
+//!
+//! `diff apply` records into the same ledger, which is what lets
+//! `diff status` tell applied files from new and edited ones.
+
+use crate::db::AppliedPatch;
+use crate::diff::GraphPatch;
+use crate::remote_patch::sha256_hex;
+use std::collections::HashSet;
+use std::path::{Component, Path};
+use std::process::Command;
+
+/// Where a patch file stands against the applied-patch ledger
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerState {
+    /// This exact content was applied
+    Applied,
+    /// The path was applied, but the file has changed since
+    Modified,
+    /// Neither the path nor the content has been applied
+    Unapplied,
+}
+
+impl LedgerState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerState::Applied => "applied",
+            LedgerState::Modified => "modified",
+            LedgerState::Unapplied => "unapplied",
+        }
+    }
+}
+
+/// A patch path as recorded in the ledger, without `./` components
+///
+/// `diff apply ./.deciduous/patches/a.json` and a `diff scan` of
+/// `.deciduous/patches/a.json` then record the same file.
+pub fn ledger_path(path: &Path) -> String {
+    path.components()
+        .filter(|c| *c != Component::CurDir)
+        .collect::<std::path::PathBuf>()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Classify a file by its ledger path and content hash
+///
+/// Matching content counts as applied wherever it was applied from, so a
+/// renamed or re-fetched patch isn't reported as new.
+pub fn ledger_state(applied: &[AppliedPatch], path: &str, sha256: &str) -> LedgerState {
+    if applied.iter().any(|a| a.content_hash == sha256) {
+        LedgerState::Applied
+    } else if applied.iter().any(|a| a.patch_path == path) {
+        LedgerState::Modified
+    } else {
+        LedgerState::Unapplied
+    }
+}
+
+/// A version of a patch file found in git history
+#[derive(Debug, Clone)]
+pub struct HistoryPatch {
+    /// Commit that added or modified the file
+    pub commit: String,
+    /// Path of the file relative to the repository root
+    pub path: String,
+    /// SHA-256 of the file's bytes at `commit`, hex
+    pub sha256: String,
+    pub patch: GraphPatch,
+}
+
+impl HistoryPatch {
+    /// `path@<short commit>`, for output
+    pub fn label(&self) -> String {
+        format!("{}@{}", self.path, &self.commit[..7.min(self.commit.len())])
+    }
+}
+
+fn git_output(repo: &Path, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// (commit, path) for every add or modify of a `.json` file under `dir`,
+/// oldest commit first
+pub fn patch_commits(repo: &Path, dir: &str) -> Result<Vec<(String, String)>, String> {
+    let log = git_output(
+        repo,
+        &[
+            "log",
+            "--reverse",
+            "--format=commit %H",
+            "--diff-filter=AM",
+            "--name-only",
+            "--",
+            dir,
+        ],
+    )?;
+    let mut found = Vec::new();
+    let mut commit = None;
+    for line in String::from_utf8_lossy(&log).lines() {
+        if let Some(hash) = line.strip_prefix("commit ") {
+            commit = Some(hash.to_string());
+        } else if line.ends_with(".json") {
+            if let Some(commit) = &commit {
+                found.push((commit.clone(), line.to_string()));
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// Patch versions under `dir` whose hash isn't in `applied`, oldest first
+///
+/// Identical content committed more than once is returned once, at its
+/// first commit. Files that don't parse are reported in the second list.
+pub fn unapplied_patches(
+    repo: &Path,
+    dir: &str,
+    applied: &HashSet<String>,
+) -> Result<(Vec<HistoryPatch>, Vec<String>), String> {
+    let mut seen = applied.clone();
+    let mut patches = Vec::new();
+    let mut errors = Vec::new();
+    for (commit, path) in patch_commits(repo, dir)? {
+        let bytes = git_output(repo, &["show", &format!("{}:{}", commit, path)])?;
+        let sha256 = sha256_hex(&bytes);
+        if !seen.insert(sha256.clone()) {
+            continue;
+        }
+        match GraphPatch::from_bytes(&bytes) {
+            Ok(patch) => patches.push(HistoryPatch {
+                commit,
+                path,
+                sha256,
+                patch,
+            }),
+            Err(e) => errors.push(format!("{}@{}: {}", path, &commit[..7], e)),
+        }
+    }
+    Ok((patches, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ledger_state() {
+        let applied = vec![AppliedPatch {
+            id: 1,
+            patch_path: ".deciduous/patches/alice.json".to_string(),
+            content_hash: "aaa".to_string(),
+            commit_sha: None,
+            applied_at: "2025-01-01T00:00:00Z".to_string(),
+            author: None,
+        }];
+        let path = ledger_path(Path::new("./.deciduous/patches/alice.json"));
+        assert_eq!(path, ".deciduous/patches/alice.json");
+        assert_eq!(ledger_state(&applied, &path, "aaa"), LedgerState::Applied);
+        assert_eq!(ledger_state(&applied, &path, "bbb"), LedgerState::Modified);
+        assert_eq!(
+            ledger_state(&applied, "renamed.json", "aaa"),
+            LedgerState::Applied
+        );
+        assert_eq!(
+            ledger_state(&applied, "bob.json", "ccc"),
+            LedgerState::Unapplied
+        );
+    }
+
+    fn git(repo: &Path, args: &[&str]) {
+        git_output(repo, args).unwrap();
+    }
+
+    fn commit_patch(repo: &Path, name: &str, author: &str) {
+        let patch = GraphPatch::new(Some(author.to_string()), None, None);
+        patch
+            .save(&repo.join(".deciduous/patches").join(name))
+            .unwrap();
+        git(repo, &["add", "."]);
+        git(repo, &["commit", "-q", "-m", name]);
+    }
+
+    #[test]
+    fn test_unapplied_patches() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path();
+        git(repo, &["init", "-q"]);
+        git(repo, &["config", "user.name", "Test"]);
+        git(repo, &["config", "user.email", "test@example.com"]);
+        std::fs::create_dir_all(repo.join(".deciduous/patches")).unwrap();
+
+        commit_patch(repo, "alice.json", "alice");
+        commit_patch(repo, "bob.json", "bob");
+        commit_patch(repo, "alice.json", "alice2");
+        std::fs::write(repo.join("README.md"), "unrelated").unwrap();
+        git(repo, &["add", "."]);
+        git(repo, &["commit", "-q", "-m", "readme"]);
+
+        let commits = patch_commits(repo, ".deciduous/patches").unwrap();
+        let paths: Vec<&str> = commits.iter().map(|(_, p)| p.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                ".deciduous/patches/alice.json",
+                ".deciduous/patches/bob.json",
+                ".deciduous/patches/alice.json"
+            ]
+        );
+
+        let (found, errors) =
+            unapplied_patches(repo, ".deciduous/patches", &HashSet::new()).unwrap();
+        assert!(errors.is_empty());
+        let authors: Vec<&str> = found
+            .iter()
+            .map(|p| p.patch.author.as_deref().unwrap())
+            .collect();
+        assert_eq!(authors, vec!["alice", "bob", "alice2"]);
+
+        let applied: HashSet<String> = found[..2].iter().map(|p| p.sha256.clone()).collect();
+        let (rest, _) = unapplied_patches(repo, ".deciduous/patches", &applied).unwrap();
+        assert_eq!(rest.len(), 1);
+        assert!(rest[0]
+            .label()
+            .starts_with(".deciduous/patches/alice.json@"));
+    }
+}
@@ -0,0 +1,204 @@
+//! Outcomes for merged pull requests
+//!
+//! `deciduous pr merged --pr 123`, run from CI when a PR merges, adds an
+//! outcome node summarizing it. The outcome hangs off the goals logged on
+//! the PR's branch and the actions behind its commits, and the branch's
+//! open actions are marked completed. Nodes belong to the PR through their
+//! `branch` or `pr` metadata. Running it twice for the same PR does nothing.
+
+use crate::db::{node_pr_number, Database, DecisionNode};
+use crate::forge::PullRequest;
+
+fn metadata_str(node: &DecisionNode, key: &str) -> Option<String> {
+    node.metadata_json
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        .and_then(|m| m[key].as_str().map(String::from))
+        .filter(|v| !v.is_empty())
+}
+
+/// What recording a merged PR will do
+#[derive(Debug, Clone, Default)]
+pub struct MergePlan {
+    /// Goals logged on the PR's branch, to link to the outcome
+    pub goals: Vec<DecisionNode>,
+    /// Actions with a commit, to link to the outcome
+    pub committed: Vec<DecisionNode>,
+    /// Pending or active actions, to mark completed
+    pub to_complete: Vec<DecisionNode>,
+    /// Commits of the PR's actions and outcomes, oldest node first
+    pub commits: Vec<String>,
+    /// An outcome already recorded for this PR
+    pub existing: Option<i32>,
+}
+
+/// Match `nodes` against the PR numbered `pr` from `branch`
+pub fn plan(nodes: &[DecisionNode], pr: i32, branch: &str) -> MergePlan {
+    let mut plan = MergePlan::default();
+    let in_pr = |n: &&DecisionNode| {
+        node_pr_number(n) == Some(pr) || metadata_str(n, "branch").as_deref() == Some(branch)
+    };
+    for node in nodes.iter().filter(in_pr) {
+        let commit = metadata_str(node, "commit");
+        match node.node_type.as_str() {
+            "goal" => plan.goals.push(node.clone()),
+            "outcome" if is_merge_outcome(node, pr) => {
+                plan.existing = Some(node.id);
+            }
+            "action" => {
+                if commit.is_some() {
+                    plan.committed.push(node.clone());
+                }
+                if node.status == "pending" || node.status == "active" {
+                    plan.to_complete.push(node.clone());
+                }
+            }
+            _ => {}
+        }
+        if let Some(commit) = commit {
+            if !plan.commits.contains(&commit) {
+                plan.commits.push(commit);
+            }
+        }
+    }
+    plan
+}
+
+/// Whether `node` is the outcome `record` made for PR `pr`
+fn is_merge_outcome(node: &DecisionNode, pr: i32) -> bool {
+    node.metadata_json
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        .and_then(|m| m["merged_pr"].as_i64())
+        == Some(pr as i64)
+}
+
+/// The outcome's description: the PR, its commits and what it completed
+pub fn summary(pr: &PullRequest, plan: &MergePlan) -> String {
+    let mut out = format!("Merged PR #{}: {}\n", pr.number, pr.title);
+    if !pr.html_url.is_empty() {
+        out.push_str(&format!("{}\n", pr.html_url));
+    }
+    out.push_str(&format!("\nBranch: {}\n", pr.head_branch));
+    if !plan.commits.is_empty() {
+        out.push_str("\nCommits:\n");
+        for commit in &plan.commits {
+            out.push_str(&format!(
+                "- {}\n",
+                commit.chars().take(7).collect::<String>()
+            ));
+        }
+    }
+    if !plan.to_complete.is_empty() {
+        out.push_str("\nCompleted actions:\n");
+        for action in &plan.to_complete {
+            out.push_str(&format!("- #{} {}\n", action.id, action.title));
+        }
+    }
+    out
+}
+
+/// Add the outcome, link it and complete the actions; returns its ID
+pub fn record(db: &Database, pr: &PullRequest, plan: &MergePlan) -> Result<i32, String> {
+    let err = |e: crate::db::DbError| e.to_string();
+    let outcome = db
+        .node(
+            "outcome",
+            &format!("Merged PR #{}: {}", pr.number, pr.title),
+        )
+        .description(summary(pr, plan))
+        .branch(pr.head_branch.clone())
+        .insert()
+        .map_err(err)?;
+    db.update_node_metadata_field(outcome, "pr", serde_json::json!(pr.number))
+        .map_err(err)?;
+    db.update_node_metadata_field(outcome, "merged_pr", serde_json::json!(pr.number))
+        .map_err(err)?;
+    if let Some(last) = plan.commits.last() {
+        db.update_node_commit(outcome, last).map_err(err)?;
+    }
+    for goal in &plan.goals {
+        db.edge(goal.id, outcome)
+            .rationale("PR merged")
+            .insert()
+            .map_err(err)?;
+    }
+    for action in &plan.committed {
+        db.edge(action.id, outcome)
+            .rationale("Merged in the PR")
+            .insert()
+            .map_err(err)?;
+    }
+    for action in &plan.to_complete {
+        db.update_node_status(action.id, "completed").map_err(err)?;
+    }
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pr_merged() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let goal = db
+            .node("goal", "Paged graph API")
+            .branch("feature/paging")
+            .insert()
+            .unwrap();
+        let done = db
+            .node("action", "Add cursor")
+            .branch("feature/paging")
+            .commit("aaa1111")
+            .insert()
+            .unwrap();
+        let open = db
+            .node("action", "Page edges")
+            .commit("bbb2222")
+            .insert()
+            .unwrap();
+        db.update_node_metadata_field(open, "pr", serde_json::json!(12))
+            .unwrap();
+        db.update_node_status(done, "completed").unwrap();
+        db.node("action", "Unrelated")
+            .branch("main")
+            .insert()
+            .unwrap();
+
+        let pr = PullRequest {
+            number: 12,
+            title: "Page the graph API".to_string(),
+            state: "merged".to_string(),
+            head_branch: "feature/paging".to_string(),
+            html_url: String::new(),
+        };
+        let found = plan(&db.get_all_nodes().unwrap(), 12, &pr.head_branch);
+        let ids = |nodes: &[DecisionNode]| nodes.iter().map(|n| n.id).collect::<Vec<_>>();
+        assert_eq!(ids(&found.goals), vec![goal]);
+        assert_eq!(ids(&found.committed), vec![done, open]);
+        assert_eq!(ids(&found.to_complete), vec![open]);
+        assert_eq!(found.commits, vec!["aaa1111", "bbb2222"]);
+        assert!(
+            summary(&pr, &found).contains(&format!("Completed actions:\n- #{} Page edges\n", open))
+        );
+
+        let outcome = record(&db, &pr, &found).unwrap();
+        assert_eq!(
+            db.get_node_by_id(open).unwrap().unwrap().status,
+            "completed"
+        );
+        let parents: Vec<i32> = db
+            .get_all_edges()
+            .unwrap()
+            .iter()
+            .filter(|e| e.to_node_id == outcome)
+            .map(|e| e.from_node_id)
+            .collect();
+        assert_eq!(parents, vec![goal, done, open]);
+
+        let again = plan(&db.get_all_nodes().unwrap(), 12, &pr.head_branch);
+        assert_eq!(again.existing, Some(outcome));
+    }
+}
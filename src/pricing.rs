@@ -0,0 +1,303 @@
+//! Cost accounting for API trace spans
+//!
+//! Prices are USD per million tokens, looked up by model name: an exact
+//! entry wins, otherwise the longest entry that prefixes the model name
+//! (so "claude-sonnet-4-20250514" uses "claude-sonnet-4"). The built-in
+//! table can be overridden or extended under `[trace.pricing]` in
+//! `.deciduous/config.toml`.
+//!
+//! Budgets under `[trace.budget]` are checked against the same costs after
+//! each recorded span.
+
+use crate::config::{BudgetConfig, Config, ModelPrice};
+use crate::db::{Database, TraceSpan};
+use std::collections::BTreeMap;
+
+/// Built-in prices: (model prefix, input, output, cache read, cache write)
+const BUILTIN_PRICES: &[(&str, f64, f64, f64, f64)] = &[
+    ("claude-opus-4-5", 5.0, 25.0, 0.5, 6.25),
+    ("claude-opus-4", 15.0, 75.0, 1.5, 18.75),
+    ("claude-3-opus", 15.0, 75.0, 1.5, 18.75),
+    ("claude-sonnet-4", 3.0, 15.0, 0.3, 3.75),
+    ("claude-3-7-sonnet", 3.0, 15.0, 0.3, 3.75),
+    ("claude-3-5-sonnet", 3.0, 15.0, 0.3, 3.75),
+    ("claude-haiku-4-5", 1.0, 5.0, 0.1, 1.25),
+    ("claude-3-5-haiku", 0.8, 4.0, 0.08, 1.0),
+    ("claude-3-haiku", 0.25, 1.25, 0.03, 0.3),
+    ("gpt-4o", 2.5, 10.0, 1.25, 2.5),
+    ("gpt-4o-mini", 0.15, 0.6, 0.075, 0.15),
+    ("gpt-4.1", 2.0, 8.0, 0.5, 2.0),
+    ("gpt-4.1-mini", 0.4, 1.6, 0.1, 0.4),
+    ("gpt-4.1-nano", 0.1, 0.4, 0.025, 0.1),
+    ("o3", 2.0, 8.0, 0.5, 2.0),
+    ("o3-mini", 1.1, 4.4, 0.55, 1.1),
+    ("o4-mini", 1.1, 4.4, 0.275, 1.1),
+];
+
+/// Model price lookup table
+#[derive(Debug, Clone)]
+pub struct PriceTable {
+    prices: BTreeMap<String, ModelPrice>,
+}
+
+impl Default for PriceTable {
+    fn default() -> Self {
+        let prices = BUILTIN_PRICES
+            .iter()
+            .map(|&(model, input, output, cache_read, cache_write)| {
+                (
+                    model.to_string(),
+                    ModelPrice {
+                        input,
+                        output,
+                        cache_read: Some(cache_read),
+                        cache_write: Some(cache_write),
+                    },
+                )
+            })
+            .collect();
+        Self { prices }
+    }
+}
+
+impl PriceTable {
+    /// Built-in prices with the config's `[trace.pricing]` entries applied
+    pub fn from_config(config: &Config) -> Self {
+        let mut table = Self::default();
+        for (model, price) in &config.trace.pricing {
+            table.prices.insert(model.clone(), *price);
+        }
+        table
+    }
+
+    /// Price for a model, by exact name or longest matching prefix
+    pub fn price(&self, model: &str) -> Option<&ModelPrice> {
+        if let Some(price) = self.prices.get(model) {
+            return Some(price);
+        }
+        self.prices
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, price)| price)
+    }
+
+    /// Cost of a span in USD; `None` if its model has no price
+    ///
+    /// Input tokens are counted as uncached input, the way Anthropic reports
+    /// them alongside the separate cache read/write counts.
+    pub fn span_cost(&self, span: &TraceSpan) -> Option<f64> {
+        let price = self.price(span.model.as_deref()?)?;
+        let tokens = |n: Option<i32>| n.unwrap_or(0).max(0) as f64;
+        let cost = tokens(span.input_tokens) * price.input
+            + tokens(span.output_tokens) * price.output
+            + tokens(span.cache_read) * price.cache_read.unwrap_or(price.input)
+            + tokens(span.cache_write) * price.cache_write.unwrap_or(price.input);
+        Some(cost / 1_000_000.0)
+    }
+
+    /// Total cost of the spans that have a price
+    pub fn total_cost<'a>(&self, spans: impl IntoIterator<Item = &'a TraceSpan>) -> f64 {
+        spans.into_iter().filter_map(|s| self.span_cost(s)).sum()
+    }
+}
+
+/// Tokens (input + output) and cost of a set of spans
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Usage {
+    pub tokens: i64,
+    pub cost: f64,
+}
+
+impl Usage {
+    pub fn of<'a>(prices: &PriceTable, spans: impl IntoIterator<Item = &'a TraceSpan>) -> Self {
+        spans
+            .into_iter()
+            .fold(Usage::default(), |usage, span| Usage {
+                tokens: usage.tokens
+                    + i64::from(span.input_tokens.unwrap_or(0))
+                    + i64::from(span.output_tokens.unwrap_or(0)),
+                cost: usage.cost + prices.span_cost(span).unwrap_or(0.0),
+            })
+    }
+}
+
+/// A blown budget, as reported after a span is recorded
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetAlert {
+    pub message: String,
+    /// Whether the proxy should refuse further API calls
+    pub block: bool,
+}
+
+/// Describe each limit the session or day has gone over
+pub fn budget_overruns(budget: &BudgetConfig, session: Usage, today: Usage) -> Vec<String> {
+    let mut over = Vec::new();
+    if let Some(limit) = budget.session_tokens.filter(|l| session.tokens > *l) {
+        over.push(format!(
+            "session used {} tokens (budget {})",
+            session.tokens, limit
+        ));
+    }
+    if let Some(limit) = budget.session_cost.filter(|l| session.cost > *l) {
+        over.push(format!(
+            "session cost {} (budget {})",
+            format_cost(session.cost),
+            format_cost(limit)
+        ));
+    }
+    if let Some(limit) = budget.daily_tokens.filter(|l| today.tokens > *l) {
+        over.push(format!(
+            "today used {} tokens (budget {})",
+            today.tokens, limit
+        ));
+    }
+    if let Some(limit) = budget.daily_cost.filter(|l| today.cost > *l) {
+        over.push(format!(
+            "today cost {} (budget {})",
+            format_cost(today.cost),
+            format_cost(limit)
+        ));
+    }
+    over
+}
+
+/// Check a session and today's usage against the configured budget
+pub fn check_budget(
+    db: &Database,
+    config: &Config,
+    session_id: &str,
+) -> crate::db::Result<Option<BudgetAlert>> {
+    let budget = &config.trace.budget;
+    if *budget == BudgetConfig::default() {
+        return Ok(None);
+    }
+
+    let prices = PriceTable::from_config(config);
+    let session = Usage::of(&prices, &db.get_trace_spans(session_id)?);
+    let today = if budget.daily_tokens.is_some() || budget.daily_cost.is_some() {
+        let midnight = chrono::Local::now().format("%Y-%m-%d").to_string();
+        Usage::of(&prices, &db.get_trace_spans_since(&midnight)?)
+    } else {
+        Usage::default()
+    };
+
+    let over = budget_overruns(budget, session, today);
+    Ok((!over.is_empty()).then(|| BudgetAlert {
+        message: format!("Trace budget exceeded: {}", over.join("; ")),
+        block: budget.enforce,
+    }))
+}
+
+/// Format a USD amount, with more precision for small amounts
+pub fn format_cost(cost: f64) -> String {
+    if cost < 0.01 && cost > 0.0 {
+        format!("${:.4}", cost)
+    } else {
+        format!("${:.2}", cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(model: Option<&str>, input: i32, output: i32, cache_read: i32) -> TraceSpan {
+        TraceSpan {
+            model: model.map(str::to_string),
+            input_tokens: Some(input),
+            output_tokens: Some(output),
+            cache_read: Some(cache_read),
+            ..TraceSpan::for_test(1)
+        }
+    }
+
+    #[test]
+    fn test_price_prefix_match() {
+        let table = PriceTable::default();
+        assert_eq!(table.price("claude-sonnet-4-20250514").unwrap().input, 3.0);
+        assert_eq!(table.price("claude-opus-4-5-20251101").unwrap().input, 5.0);
+        assert_eq!(table.price("claude-opus-4-1-20250805").unwrap().input, 15.0);
+        assert_eq!(table.price("gpt-4o-mini-2024-07-18").unwrap().output, 0.6);
+        assert!(table.price("llama-3").is_none());
+    }
+
+    #[test]
+    fn test_span_cost() {
+        let table = PriceTable::default();
+        let cost = table
+            .span_cost(&span(
+                Some("claude-sonnet-4"),
+                1_000_000,
+                100_000,
+                2_000_000,
+            ))
+            .unwrap();
+        // 3.00 input + 1.50 output + 0.60 cache read
+        assert!((cost - 5.1).abs() < 1e-9);
+        assert!(table.span_cost(&span(None, 10, 10, 0)).is_none());
+
+        let spans = vec![
+            span(Some("claude-haiku-4-5"), 1_000_000, 0, 0),
+            span(Some("mystery"), 1_000_000, 0, 0),
+        ];
+        assert!((table.total_cost(&spans) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_config_overrides() {
+        let config: Config =
+            toml::from_str("[trace.pricing.claude-sonnet-4]\ninput = 1.0\noutput = 2.0\n").unwrap();
+        let table = PriceTable::from_config(&config);
+        let price = table.price("claude-sonnet-4-5").unwrap();
+        assert_eq!(price.input, 1.0);
+        assert_eq!(price.cache_read, None);
+
+        let cost = table
+            .span_cost(&span(Some("claude-sonnet-4"), 0, 0, 1_000_000))
+            .unwrap();
+        assert!((cost - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_budget_overruns() {
+        let budget = BudgetConfig {
+            session_tokens: Some(1000),
+            daily_cost: Some(2.0),
+            ..Default::default()
+        };
+        let within = Usage {
+            tokens: 900,
+            cost: 1.0,
+        };
+        assert!(budget_overruns(&budget, within, within).is_empty());
+
+        let session = Usage {
+            tokens: 1500,
+            cost: 0.5,
+        };
+        let today = Usage {
+            tokens: 1500,
+            cost: 2.5,
+        };
+        assert_eq!(
+            budget_overruns(&budget, session, today),
+            vec![
+                "session used 1500 tokens (budget 1000)".to_string(),
+                "today cost $2.50 (budget $2.00)".to_string(),
+            ]
+        );
+
+        let spans = vec![span(Some("claude-haiku-4-5"), 1_000_000, 200_000, 0)];
+        let usage = Usage::of(&PriceTable::default(), &spans);
+        assert_eq!(usage.tokens, 1_200_000);
+        assert!((usage.cost - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_format_cost() {
+        assert_eq!(format_cost(0.0), "$0.00");
+        assert_eq!(format_cost(0.0042), "$0.0042");
+        assert_eq!(format_cost(12.346), "$12.35");
+    }
+}
@@ -0,0 +1,183 @@
+//! Monorepo sub-projects
+//!
+//! One database can hold several sub-projects' decisions. `deciduous add`
+//! records a `project` in node metadata, found from the directory it runs in
+//! through `[projects]` in .deciduous/config.toml. `--scope <project>` limits
+//! any command to that project's nodes, including lookups and edits by node
+//! ID (see `Database::with_project_scope`), and `sync --per-project` writes
+//! one export per project.
+
+use crate::config::ProjectsConfig;
+use crate::db::{DecisionGraph, DecisionNode};
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Component, Path};
+
+/// The project for work in `cwd`, in the repository at `root`
+///
+/// The longest `[projects.paths]` directory holding `cwd` names it; with
+/// `auto_detect`, any other directory below the root is named after its
+/// top-level directory. Nothing is detected at the root itself.
+pub fn detect(config: &ProjectsConfig, root: &Path, cwd: &Path) -> Option<String> {
+    let relative = cwd.strip_prefix(root).ok()?;
+    let mapped = config
+        .paths
+        .iter()
+        .filter(|(dir, _)| relative.starts_with(dir.trim_matches('/')))
+        .max_by_key(|(dir, _)| Path::new(dir.trim_matches('/')).components().count());
+    if let Some((_, project)) = mapped {
+        return Some(project.clone());
+    }
+    if !config.auto_detect {
+        return None;
+    }
+    match relative.components().next()? {
+        Component::Normal(dir) => Some(dir.to_string_lossy().to_string()),
+        _ => None,
+    }
+}
+
+/// The project for work in the current directory of the repository the
+/// database was found in
+pub fn current(config: &ProjectsConfig) -> Option<String> {
+    let db_path = crate::db::Database::db_path();
+    let root = db_path.parent()?.parent()?.canonicalize().ok()?;
+    let cwd = std::env::current_dir().ok()?.canonicalize().ok()?;
+    detect(config, &root, &cwd)
+}
+
+/// A node's `project` metadata
+pub fn project_of(node: &DecisionNode) -> Option<String> {
+    node.metadata_json
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        .and_then(|m| m["project"].as_str().map(String::from))
+        .filter(|p| !p.is_empty())
+}
+
+/// Every project named on a node, sorted
+pub fn projects(nodes: &[DecisionNode]) -> Vec<String> {
+    nodes
+        .iter()
+        .filter_map(project_of)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// `project`'s nodes and the edges between them
+pub fn filter_graph(graph: &DecisionGraph, project: &str) -> DecisionGraph {
+    let nodes: Vec<DecisionNode> = graph
+        .nodes
+        .iter()
+        .filter(|n| project_of(n).as_deref() == Some(project))
+        .cloned()
+        .collect();
+    let ids: HashSet<i32> = nodes.iter().map(|n| n.id).collect();
+    DecisionGraph {
+        nodes,
+        edges: graph
+            .edges
+            .iter()
+            .filter(|e| ids.contains(&e.from_node_id) && ids.contains(&e.to_node_id))
+            .cloned()
+            .collect(),
+        config: graph.config.clone(),
+        pull_requests: graph.pull_requests.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_detect_and_scope() {
+        let root = Path::new("/repo");
+        let config = ProjectsConfig {
+            paths: BTreeMap::from([
+                ("services".to_string(), "backend".to_string()),
+                ("services/billing/".to_string(), "billing".to_string()),
+            ]),
+            auto_detect: false,
+        };
+        let at = |dir: &str| detect(&config, root, &root.join(dir));
+        assert_eq!(at("services/billing/src").as_deref(), Some("billing"));
+        assert_eq!(at("services/auth").as_deref(), Some("backend"));
+        assert_eq!(at("services-old"), None);
+        assert_eq!(at("web/app"), None);
+        assert_eq!(detect(&config, root, root), None);
+        assert_eq!(detect(&config, root, Path::new("/elsewhere")), None);
+        let auto = ProjectsConfig {
+            auto_detect: true,
+            ..config
+        };
+        assert_eq!(
+            detect(&auto, root, &root.join("web/app")).as_deref(),
+            Some("web")
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        let db = Database::new(path.to_str().unwrap()).unwrap();
+        let goal = db.node("goal", "Invoices").insert().unwrap();
+        let action = db.node("action", "Invoice API").insert().unwrap();
+        let other = db.node("action", "New landing page").insert().unwrap();
+        for id in [goal, action] {
+            db.update_node_metadata_field(id, "project", serde_json::json!("billing"))
+                .unwrap();
+        }
+        db.update_node_metadata_field(other, "project", serde_json::json!("web"))
+            .unwrap();
+        db.node("observation", "Not in any project")
+            .insert()
+            .unwrap();
+        db.edge(goal, action).insert().unwrap();
+        db.edge(goal, other).insert().unwrap();
+
+        let graph = db.get_graph().unwrap();
+        assert_eq!(projects(&graph.nodes), vec!["billing", "web"]);
+        let billing = filter_graph(&graph, "billing");
+        assert_eq!(billing.nodes.len(), 2);
+        assert_eq!(billing.edges.len(), 1);
+
+        let scoped = Database::open_at(&path)
+            .unwrap()
+            .with_project_scope(Some("billing".to_string()));
+        let nodes = scoped.get_all_nodes().unwrap();
+        assert_eq!(
+            nodes.iter().map(|n| n.id).collect::<Vec<_>>(),
+            vec![goal, action]
+        );
+        let edges = scoped.get_all_edges().unwrap();
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].to_node_id, action);
+        assert_eq!(scoped.iter_nodes(1).count(), 2);
+        let page = scoped.get_graph_page(None, 10).unwrap();
+        assert_eq!((page.nodes.len(), page.edges.len()), (2, 1));
+
+        // Ids from other projects can't be looked up or written through it
+        assert!(scoped.get_node_by_id(goal).unwrap().is_some());
+        assert!(scoped.get_node_by_id(other).unwrap().is_none());
+        assert_eq!(scoped.get_node_children(goal).unwrap().len(), 1);
+        let err = scoped.update_node_status(other, "completed").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("Node {} is not in project 'billing' (--scope)", other)
+        );
+        assert!(scoped.edge(action, other).insert().is_err());
+        assert!(scoped.delete_node(other).is_err());
+        assert!(scoped.merge_nodes(action, other).is_err());
+        assert!(db.get_node_by_id(other).unwrap().is_some());
+        scoped.update_node_status(action, "completed").unwrap();
+
+        // New nodes join the scope's project
+        let added = scoped.node("action", "Refunds").insert().unwrap();
+        assert_eq!(
+            project_of(&scoped.get_node_by_id(added).unwrap().unwrap()).as_deref(),
+            Some("billing")
+        );
+        scoped.edge(goal, added).insert().unwrap();
+    }
+}
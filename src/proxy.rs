@@ -0,0 +1,1503 @@
+//! Standalone HTTPS trace proxy
+//!
+//! `deciduous proxy --listen 8787` runs a forward proxy that any client can
+//! use through `HTTPS_PROXY`, not just Node-based ones. CONNECT tunnels to the
+//! Anthropic and OpenAI APIs are terminated with certificates signed by a
+//! local CA (generated once under `~/.deciduous/proxy-ca/`), and each model
+//! call is recorded as a trace span. Other hosts are tunneled untouched.
+
+use crate::capture::CaptureFilter;
+use crate::config::Config;
+use crate::db::Database;
+use crate::mcp_trace::{self, McpCapture};
+use crate::pricing::check_budget;
+use crate::redact::{store_content, Redactor};
+use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, KeyPair, KeyUsagePurpose};
+use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer, ServerName};
+use rustls::{
+    ClientConfig, ClientConnection, RootCertStore, ServerConfig, ServerConnection, StreamOwned,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Longest a model response may stall before the upstream read gives up
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// An API whose traffic the proxy records
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Api {
+    Anthropic,
+    OpenAi,
+}
+
+impl Api {
+    /// The API a CONNECT target host belongs to, if it's one we record
+    pub fn from_host(host: &str) -> Option<Self> {
+        match host {
+            "api.anthropic.com" => Some(Api::Anthropic),
+            "api.openai.com" => Some(Api::OpenAi),
+            _ => None,
+        }
+    }
+
+    /// Whether a request path is a model call worth a span
+    pub fn records_path(&self, path: &str) -> bool {
+        let path = path.split('?').next().unwrap_or(path);
+        match self {
+            Api::Anthropic => path == "/v1/messages",
+            Api::OpenAi => path == "/v1/chat/completions",
+        }
+    }
+}
+
+fn other<E: std::fmt::Display>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e.to_string())
+}
+
+// ============================================================================
+// Local certificate authority
+// ============================================================================
+
+/// Default location of the proxy CA: `~/.deciduous/proxy-ca/`
+pub fn default_ca_dir() -> io::Result<PathBuf> {
+    let home = std::env::var("HOME").map_err(|_| {
+        io::Error::new(io::ErrorKind::NotFound, "HOME environment variable not set")
+    })?;
+    Ok(PathBuf::from(home).join(".deciduous").join("proxy-ca"))
+}
+
+/// Certificate authority that signs a certificate for each intercepted host
+pub struct LocalCa {
+    cert: rcgen::Certificate,
+    key: KeyPair,
+    cert_path: PathBuf,
+    leaves: Mutex<HashMap<String, Arc<ServerConfig>>>,
+}
+
+impl LocalCa {
+    /// Load the CA from `dir`, generating it on first use
+    ///
+    /// Clients must trust `ca.pem` (e.g. `NODE_EXTRA_CA_CERTS`, `SSL_CERT_FILE`)
+    /// for intercepted connections to succeed.
+    pub fn load_or_create(dir: &Path) -> io::Result<Self> {
+        let cert_path = dir.join("ca.pem");
+        let key_path = dir.join("ca-key.pem");
+
+        let (params, key) = if cert_path.exists() && key_path.exists() {
+            let key = KeyPair::from_pem(&std::fs::read_to_string(&key_path)?).map_err(other)?;
+            let params = CertificateParams::from_ca_cert_pem(&std::fs::read_to_string(&cert_path)?)
+                .map_err(other)?;
+            (params, key)
+        } else {
+            let key = KeyPair::generate().map_err(other)?;
+            let mut params = CertificateParams::default();
+            params
+                .distinguished_name
+                .push(DnType::CommonName, "deciduous trace proxy CA");
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            params.key_usages = vec![
+                KeyUsagePurpose::KeyCertSign,
+                KeyUsagePurpose::CrlSign,
+                KeyUsagePurpose::DigitalSignature,
+            ];
+            let cert = params.clone().self_signed(&key).map_err(other)?;
+
+            std::fs::create_dir_all(dir)?;
+            std::fs::write(&cert_path, cert.pem())?;
+            std::fs::write(&key_path, key.serialize_pem())?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(&key_path, std::fs::Permissions::from_mode(0o600))?;
+            }
+            (params, key)
+        };
+
+        // Re-signing the same subject and key gives an issuer that matches ca.pem
+        let cert = params.self_signed(&key).map_err(other)?;
+        Ok(LocalCa {
+            cert,
+            key,
+            cert_path,
+            leaves: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The CA certificate clients need to trust
+    pub fn cert_path(&self) -> &Path {
+        &self.cert_path
+    }
+
+    /// TLS config presenting a certificate for `host`, signed by this CA
+    fn server_config(&self, host: &str) -> io::Result<Arc<ServerConfig>> {
+        let mut leaves = self.leaves.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(config) = leaves.get(host) {
+            return Ok(Arc::clone(config));
+        }
+
+        let mut params = CertificateParams::new(vec![host.to_string()]).map_err(other)?;
+        params.distinguished_name.push(DnType::CommonName, host);
+        let key = KeyPair::generate().map_err(other)?;
+        let cert = params
+            .signed_by(&key, &self.cert, &self.key)
+            .map_err(other)?;
+
+        let mut config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![cert.der().clone(), self.cert.der().clone()],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.serialize_der())),
+            )
+            .map_err(other)?;
+        // Responses are relayed as HTTP/1.1, so don't let clients negotiate h2
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+        let config = Arc::new(config);
+        leaves.insert(host.to_string(), Arc::clone(&config));
+        Ok(config)
+    }
+}
+
+fn upstream_config() -> ClientConfig {
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let mut config = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    config
+}
+
+// ============================================================================
+// HTTP/1.1 framing
+// ============================================================================
+
+/// Start line and headers of a request or response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Head {
+    pub start: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl Head {
+    /// First header with this name, case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn is_chunked(&self) -> bool {
+        self.header("transfer-encoding")
+            .is_some_and(|v| v.to_ascii_lowercase().contains("chunked"))
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = format!("{}\r\n", self.start);
+        for (k, v) in &self.headers {
+            out.push_str(&format!("{}: {}\r\n", k, v));
+        }
+        out.push_str("\r\n");
+        out.into_bytes()
+    }
+}
+
+/// Read a request/response head; `None` if the peer closed before sending one
+pub fn read_head<R: BufRead>(reader: &mut R) -> io::Result<Option<Head>> {
+    let mut start = String::new();
+    if reader.read_line(&mut start)? == 0 {
+        return Ok(None);
+    }
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "connection closed mid-headers",
+            ));
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    Ok(Some(Head {
+        start: start.trim_end().to_string(),
+        headers,
+    }))
+}
+
+/// Read a body framed by `Content-Length` or chunked encoding
+pub fn read_body<R: BufRead>(reader: &mut R, head: &Head) -> io::Result<Vec<u8>> {
+    if head.is_chunked() {
+        return read_chunked(reader);
+    }
+    let len = head
+        .header("content-length")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body)?;
+    Ok(body)
+}
+
+/// Decode a chunked body, stopping at the terminating zero-size chunk
+pub fn read_chunked<R: BufRead>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let mut size_line = String::new();
+        if reader.read_line(&mut size_line)? == 0 {
+            break;
+        }
+        let size_hex = size_line.trim().split(';').next().unwrap_or("");
+        let size = usize::from_str_radix(size_hex, 16)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "bad chunk size"))?;
+        if size == 0 {
+            // Skip trailers up to the blank line
+            let mut trailer = String::new();
+            while reader.read_line(&mut trailer)? > 0 && !trailer.trim().is_empty() {
+                trailer.clear();
+            }
+            break;
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        let mut crlf = String::new();
+        reader.read_line(&mut crlf)?;
+    }
+    Ok(body)
+}
+
+/// The request as sent upstream: one exchange per connection, uncompressed
+/// so the response can be parsed, with the body re-framed by length
+pub fn upstream_request(head: &Head, body_len: usize) -> Head {
+    const DROP: [&str; 7] = [
+        "connection",
+        "proxy-connection",
+        "keep-alive",
+        "accept-encoding",
+        "transfer-encoding",
+        "content-length",
+        "expect",
+    ];
+    let mut headers: Vec<(String, String)> = head
+        .headers
+        .iter()
+        .filter(|(k, _)| !DROP.iter().any(|d| k.eq_ignore_ascii_case(d)))
+        .cloned()
+        .collect();
+    headers.push(("Accept-Encoding".to_string(), "identity".to_string()));
+    headers.push(("Connection".to_string(), "close".to_string()));
+    if body_len > 0 {
+        headers.push(("Content-Length".to_string(), body_len.to_string()));
+    }
+    Head {
+        start: head.start.clone(),
+        headers,
+    }
+}
+
+// ============================================================================
+// Span capture
+// ============================================================================
+
+/// A tool call made by the model
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ToolCallCapture {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    /// Input arguments as JSON text
+    pub input: String,
+}
+
+/// What the proxy saw of one model call
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SpanCapture {
+    pub model: Option<String>,
+    pub user_preview: Option<String>,
+    pub system_prompt: Option<String>,
+    pub request_id: Option<String>,
+    pub stop_reason: Option<String>,
+    pub input_tokens: Option<i32>,
+    pub output_tokens: Option<i32>,
+    pub cache_read: Option<i32>,
+    pub cache_write: Option<i32>,
+    pub thinking: String,
+    pub response: String,
+    pub tool_calls: Vec<ToolCallCapture>,
+    /// MCP connector calls, and results of client MCP tools called earlier
+    pub mcp_calls: Vec<McpCapture>,
+    /// Set for streamed responses
+    pub stream: Option<StreamStats>,
+}
+
+/// How a streamed response arrived
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamStats {
+    /// SSE events received
+    pub chunks: i32,
+    /// Time from sending the request to the first output event
+    pub first_token_ms: Option<i32>,
+    /// Whether the stream reached its end event, rather than being cut off
+    pub complete: bool,
+}
+
+fn int(value: &Value) -> Option<i32> {
+    value.as_i64().map(|v| v as i32)
+}
+
+fn text_of(content: &Value) -> Option<String> {
+    match content {
+        Value::String(s) => Some(s.clone()),
+        Value::Array(blocks) => {
+            let texts: Vec<&str> = blocks
+                .iter()
+                .filter(|b| b["type"] == "text")
+                .filter_map(|b| b["text"].as_str())
+                .collect();
+            (!texts.is_empty()).then(|| texts.join("\n"))
+        }
+        _ => None,
+    }
+}
+
+/// Client-injected context rather than something the user typed
+pub(crate) fn is_injected(text: &str) -> bool {
+    let text = text.trim();
+    text.starts_with("<system-reminder>") || text.starts_with("<system>")
+}
+
+/// Model, latest user message, and system prompt from a request body
+///
+/// Both APIs share the `model`/`messages` shape; Anthropic carries the system
+/// prompt separately, OpenAI as a `system` (or `developer`) message.
+pub fn parse_request(body: &[u8]) -> SpanCapture {
+    let mut capture = SpanCapture::default();
+    let Ok(request) = serde_json::from_slice::<Value>(body) else {
+        return capture;
+    };
+
+    capture.model = request["model"].as_str().map(str::to_string);
+    let messages = request["messages"].as_array().cloned().unwrap_or_default();
+
+    capture.user_preview = messages
+        .iter()
+        .rev()
+        .filter(|m| m["role"] == "user")
+        .filter_map(|m| text_of(&m["content"]))
+        .map(|t| t.trim().to_string())
+        .find(|t| !t.is_empty() && !is_injected(t))
+        .map(|t| t.chars().take(500).collect());
+
+    // Results of client MCP tools called in the previous turn
+    if let Some(last) = messages.last().filter(|m| m["role"] == "user") {
+        for block in last["content"].as_array().into_iter().flatten() {
+            if block["type"] != "tool_result" {
+                continue;
+            }
+            let id = block["tool_use_id"].as_str();
+            let name = messages
+                .iter()
+                .filter(|m| m["role"] == "assistant")
+                .flat_map(|m| m["content"].as_array().into_iter().flatten())
+                .find(|b| b["type"] == "tool_use" && b["id"].as_str() == id)
+                .and_then(|b| b["name"].as_str());
+            if let Some((server, tool)) = name.and_then(mcp_trace::split_tool_name) {
+                capture.mcp_calls.push(McpCapture {
+                    id: id.map(str::to_string),
+                    server: server.to_string(),
+                    tool: tool.to_string(),
+                    input: None,
+                    result: Some(mcp_trace::result_text(&block["content"])),
+                    is_error: block["is_error"].as_bool().unwrap_or(false),
+                    index: None,
+                });
+            }
+        }
+    }
+
+    capture.system_prompt = text_of(&request["system"]).or_else(|| {
+        messages
+            .iter()
+            .find(|m| m["role"] == "system" || m["role"] == "developer")
+            .and_then(|m| text_of(&m["content"]))
+    });
+
+    capture
+}
+
+/// Fill in the response side of a capture from the (decoded) response body
+pub fn parse_response(api: Api, body: &str, capture: &mut SpanCapture) {
+    let trimmed = body.trim_start();
+    if trimmed.starts_with('{') {
+        if let Ok(value) = serde_json::from_str::<Value>(trimmed) {
+            match api {
+                Api::Anthropic => anthropic_message(&value, capture),
+                Api::OpenAi => openai_completion(&value, capture),
+            }
+        }
+        return;
+    }
+
+    for data in body.lines().filter_map(sse_data) {
+        stream_event(api, data, capture);
+    }
+}
+
+/// Payload of an SSE `data:` line
+fn sse_data(line: &str) -> Option<&str> {
+    let data = line.strip_prefix("data:")?.trim();
+    (!data.is_empty()).then_some(data)
+}
+
+/// What a streamed event meant for the response as a whole
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EventKind {
+    /// Carried generated text, thinking or tool input
+    Output,
+    /// Marked the end of the stream
+    End,
+    Other,
+}
+
+/// Apply one streamed event to a capture; None if it wasn't a whole event
+fn stream_event(api: Api, data: &str, capture: &mut SpanCapture) -> Option<EventKind> {
+    if data == "[DONE]" {
+        return Some(EventKind::End);
+    }
+    let event = serde_json::from_str::<Value>(data).ok()?;
+    let kind = match api {
+        Api::Anthropic => {
+            anthropic_event(&event, capture);
+            match event["type"].as_str() {
+                Some("content_block_delta") => EventKind::Output,
+                Some("message_stop") => EventKind::End,
+                _ => EventKind::Other,
+            }
+        }
+        Api::OpenAi => {
+            openai_chunk(&event, capture);
+            let delta = &event["choices"][0]["delta"];
+            let has_text = delta["content"].as_str().is_some_and(|c| !c.is_empty());
+            if has_text || delta["tool_calls"].is_array() {
+                EventKind::Output
+            } else {
+                EventKind::Other
+            }
+        }
+    };
+    Some(kind)
+}
+
+/// Framing of the response body still to come
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Transfer {
+    Chunked,
+    Identity,
+    /// The last chunk arrived; anything after it is trailers
+    Done,
+}
+
+/// Reassembles a response while it's relayed
+///
+/// Transfer chunks and SSE events are decoded as bytes arrive, so a stream
+/// cut off by either side still yields everything received up to that point,
+/// along with when output started and how many events there were. Non-streamed
+/// (JSON) responses are parsed whole at the end.
+pub struct StreamAssembler {
+    api: Api,
+    started: Instant,
+    capture: SpanCapture,
+    stats: StreamStats,
+    transfer: Transfer,
+    /// Bytes not yet decoded from their transfer chunk
+    raw: Vec<u8>,
+    body: Vec<u8>,
+    /// Body bytes already handled as SSE lines
+    parsed: usize,
+}
+
+impl StreamAssembler {
+    /// `capture` holds the request side; `started` is when it was sent
+    pub fn new(api: Api, chunked: bool, started: Instant, capture: SpanCapture) -> Self {
+        Self {
+            api,
+            started,
+            capture,
+            stats: StreamStats::default(),
+            transfer: if chunked {
+                Transfer::Chunked
+            } else {
+                Transfer::Identity
+            },
+            raw: Vec::new(),
+            body: Vec::new(),
+            parsed: 0,
+        }
+    }
+
+    /// Add bytes as read from upstream
+    pub fn feed(&mut self, bytes: &[u8]) {
+        match self.transfer {
+            Transfer::Chunked => {
+                self.raw.extend_from_slice(bytes);
+                self.decode_chunks();
+            }
+            Transfer::Identity => self.body.extend_from_slice(bytes),
+            Transfer::Done => return,
+        }
+        if self.is_stream() {
+            self.parse_lines();
+        }
+    }
+
+    fn decode_chunks(&mut self) {
+        while let Some(eol) = self.raw.windows(2).position(|w| w == b"\r\n") {
+            let size_line = String::from_utf8_lossy(&self.raw[..eol]);
+            let size_hex = size_line.trim().split(';').next().unwrap_or("");
+            let Ok(size) = usize::from_str_radix(size_hex, 16) else {
+                // Not chunked after all: take the bytes as they are
+                self.transfer = Transfer::Identity;
+                self.body.append(&mut self.raw);
+                return;
+            };
+            if size == 0 {
+                self.transfer = Transfer::Done;
+                self.raw.clear();
+                return;
+            }
+            let end = eol + 2 + size;
+            if self.raw.len() < end + 2 {
+                return;
+            }
+            self.body.extend_from_slice(&self.raw[eol + 2..end]);
+            self.raw.drain(..end + 2);
+        }
+    }
+
+    fn is_stream(&self) -> bool {
+        self.body
+            .iter()
+            .find(|b| !b.is_ascii_whitespace())
+            .is_some_and(|&b| b != b'{')
+    }
+
+    fn parse_lines(&mut self) {
+        while let Some(len) = self.body[self.parsed..].iter().position(|&b| b == b'\n') {
+            let line = String::from_utf8_lossy(&self.body[self.parsed..self.parsed + len]);
+            let line = line.into_owned();
+            self.parsed += len + 1;
+            self.parse_line(&line);
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) {
+        let Some(data) = sse_data(line) else {
+            return;
+        };
+        let Some(kind) = stream_event(self.api, data, &mut self.capture) else {
+            return;
+        };
+        self.stats.chunks += 1;
+        match kind {
+            EventKind::Output if self.stats.first_token_ms.is_none() => {
+                self.stats.first_token_ms = Some(self.started.elapsed().as_millis() as i32);
+            }
+            EventKind::End => self.stats.complete = true,
+            _ => {}
+        }
+    }
+
+    /// The capture, once upstream has finished or the relay was cut off
+    pub fn finish(mut self) -> SpanCapture {
+        // A cut-off transfer chunk still holds whatever arrived of it
+        if self.transfer == Transfer::Chunked {
+            if let Some(eol) = self.raw.windows(2).position(|w| w == b"\r\n") {
+                self.body.extend_from_slice(&self.raw[eol + 2..]);
+            }
+        }
+
+        if self.is_stream() {
+            self.parse_lines();
+            let rest = String::from_utf8_lossy(&self.body[self.parsed..]).into_owned();
+            self.parse_line(&rest);
+            self.capture.stream = Some(self.stats);
+        } else {
+            parse_response(
+                self.api,
+                &String::from_utf8_lossy(&self.body),
+                &mut self.capture,
+            );
+        }
+        self.capture
+    }
+}
+
+fn anthropic_usage(usage: &Value, capture: &mut SpanCapture) {
+    if let Some(v) = int(&usage["input_tokens"]) {
+        capture.input_tokens = Some(v);
+    }
+    if let Some(v) = int(&usage["output_tokens"]) {
+        capture.output_tokens = Some(v);
+    }
+    if let Some(v) = int(&usage["cache_read_input_tokens"]) {
+        capture.cache_read = Some(v);
+    }
+    if let Some(v) = int(&usage["cache_creation_input_tokens"]) {
+        capture.cache_write = Some(v);
+    }
+}
+
+fn anthropic_message(message: &Value, capture: &mut SpanCapture) {
+    capture.request_id = message["id"].as_str().map(str::to_string);
+    if let Some(model) = message["model"].as_str() {
+        capture.model = Some(model.to_string());
+    }
+    capture.stop_reason = message["stop_reason"].as_str().map(str::to_string);
+    anthropic_usage(&message["usage"], capture);
+
+    for block in message["content"].as_array().into_iter().flatten() {
+        match block["type"].as_str() {
+            Some("text") => capture
+                .response
+                .push_str(block["text"].as_str().unwrap_or("")),
+            Some("thinking") => capture
+                .thinking
+                .push_str(block["thinking"].as_str().unwrap_or("")),
+            Some("tool_use") => capture.tool_calls.push(ToolCallCapture {
+                id: block["id"].as_str().map(str::to_string),
+                name: block["name"].as_str().map(str::to_string),
+                input: block["input"].to_string(),
+            }),
+            Some("mcp_tool_use") => capture.mcp_calls.push(mcp_tool_use(block, None)),
+            Some("mcp_tool_result") => mcp_tool_result(block, capture),
+            _ => {}
+        }
+    }
+}
+
+/// An MCP connector call; streamed calls' arguments arrive as deltas
+fn mcp_tool_use(block: &Value, index: Option<u64>) -> McpCapture {
+    let input = match index {
+        Some(_) => String::new(),
+        None => block["input"].to_string(),
+    };
+    McpCapture {
+        id: block["id"].as_str().map(str::to_string),
+        server: block["server_name"].as_str().unwrap_or("").to_string(),
+        tool: block["name"].as_str().unwrap_or("").to_string(),
+        input: Some(input),
+        result: None,
+        is_error: false,
+        index,
+    }
+}
+
+fn mcp_tool_result(block: &Value, capture: &mut SpanCapture) {
+    let id = block["tool_use_id"].as_str();
+    if let Some(call) = capture
+        .mcp_calls
+        .iter_mut()
+        .find(|c| c.id.is_some() && c.id.as_deref() == id)
+    {
+        call.result = Some(mcp_trace::result_text(&block["content"]));
+        call.is_error = block["is_error"].as_bool().unwrap_or(false);
+    }
+}
+
+fn anthropic_event(event: &Value, capture: &mut SpanCapture) {
+    match event["type"].as_str() {
+        Some("message_start") => {
+            let message = &event["message"];
+            capture.request_id = message["id"].as_str().map(str::to_string);
+            if let Some(model) = message["model"].as_str() {
+                capture.model = Some(model.to_string());
+            }
+            anthropic_usage(&message["usage"], capture);
+        }
+        Some("content_block_start") => {
+            let block = &event["content_block"];
+            match block["type"].as_str() {
+                Some("tool_use") => capture.tool_calls.push(ToolCallCapture {
+                    id: block["id"].as_str().map(str::to_string),
+                    name: block["name"].as_str().map(str::to_string),
+                    input: String::new(),
+                }),
+                Some("mcp_tool_use") => capture
+                    .mcp_calls
+                    .push(mcp_tool_use(block, event["index"].as_u64())),
+                Some("mcp_tool_result") => mcp_tool_result(block, capture),
+                _ => {}
+            }
+        }
+        Some("content_block_delta") => {
+            let delta = &event["delta"];
+            match delta["type"].as_str() {
+                Some("text_delta") => capture
+                    .response
+                    .push_str(delta["text"].as_str().unwrap_or("")),
+                Some("thinking_delta") => capture
+                    .thinking
+                    .push_str(delta["thinking"].as_str().unwrap_or("")),
+                Some("input_json_delta") => {
+                    let json = delta["partial_json"].as_str().unwrap_or("");
+                    let index = event["index"].as_u64();
+                    let mcp = capture
+                        .mcp_calls
+                        .iter_mut()
+                        .find(|c| index.is_some() && c.index == index);
+                    if let Some(input) = mcp.and_then(|c| c.input.as_mut()) {
+                        input.push_str(json);
+                    } else if let Some(tool) = capture.tool_calls.last_mut() {
+                        tool.input.push_str(json);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Some("message_delta") => {
+            if let Some(reason) = event["delta"]["stop_reason"].as_str() {
+                capture.stop_reason = Some(reason.to_string());
+            }
+            anthropic_usage(&event["usage"], capture);
+        }
+        _ => {}
+    }
+}
+
+fn openai_usage(usage: &Value, capture: &mut SpanCapture) {
+    if let Some(v) = int(&usage["prompt_tokens"]) {
+        capture.input_tokens = Some(v);
+    }
+    if let Some(v) = int(&usage["completion_tokens"]) {
+        capture.output_tokens = Some(v);
+    }
+    if let Some(v) = int(&usage["prompt_tokens_details"]["cached_tokens"]) {
+        capture.cache_read = Some(v);
+    }
+}
+
+fn openai_completion(completion: &Value, capture: &mut SpanCapture) {
+    capture.request_id = completion["id"].as_str().map(str::to_string);
+    if let Some(model) = completion["model"].as_str() {
+        capture.model = Some(model.to_string());
+    }
+    openai_usage(&completion["usage"], capture);
+
+    let choice = &completion["choices"][0];
+    capture.stop_reason = choice["finish_reason"].as_str().map(str::to_string);
+    let message = &choice["message"];
+    if let Some(content) = message["content"].as_str() {
+        capture.response.push_str(content);
+    }
+    for call in message["tool_calls"].as_array().into_iter().flatten() {
+        capture.tool_calls.push(ToolCallCapture {
+            id: call["id"].as_str().map(str::to_string),
+            name: call["function"]["name"].as_str().map(str::to_string),
+            input: call["function"]["arguments"]
+                .as_str()
+                .unwrap_or("")
+                .to_string(),
+        });
+    }
+}
+
+fn openai_chunk(chunk: &Value, capture: &mut SpanCapture) {
+    if capture.request_id.is_none() {
+        capture.request_id = chunk["id"].as_str().map(str::to_string);
+    }
+    if let Some(model) = chunk["model"].as_str() {
+        capture.model = Some(model.to_string());
+    }
+    openai_usage(&chunk["usage"], capture);
+
+    let choice = &chunk["choices"][0];
+    if let Some(reason) = choice["finish_reason"].as_str() {
+        capture.stop_reason = Some(reason.to_string());
+    }
+    let delta = &choice["delta"];
+    if let Some(content) = delta["content"].as_str() {
+        capture.response.push_str(content);
+    }
+    for call in delta["tool_calls"].as_array().into_iter().flatten() {
+        let index = call["index"].as_u64().unwrap_or(0) as usize;
+        if capture.tool_calls.len() <= index {
+            capture
+                .tool_calls
+                .resize(index + 1, ToolCallCapture::default());
+        }
+        let tool = &mut capture.tool_calls[index];
+        if let Some(id) = call["id"].as_str() {
+            tool.id = Some(id.to_string());
+        }
+        if let Some(name) = call["function"]["name"].as_str() {
+            tool.name = Some(name.to_string());
+        }
+        tool.input
+            .push_str(call["function"]["arguments"].as_str().unwrap_or(""));
+    }
+}
+
+fn preview(text: &str) -> Option<String> {
+    (!text.is_empty()).then(|| text.chars().take(500).collect())
+}
+
+/// Store a capture as a completed span in the session, redacting secrets
+pub fn record_span(
+    db: &Database,
+    redactor: &Redactor,
+    session_id: &str,
+    capture: &SpanCapture,
+    duration_ms: i32,
+) -> crate::db::Result<i32> {
+    let user_preview = redactor.redact_opt(capture.user_preview.as_deref());
+    let span_id = db.create_trace_span(
+        session_id,
+        capture.model.as_deref(),
+        user_preview.as_deref(),
+    )?;
+
+    let tool_names: Vec<&str> = capture
+        .tool_calls
+        .iter()
+        .filter_map(|t| t.name.as_deref())
+        .collect();
+    let tool_names = (!tool_names.is_empty()).then(|| tool_names.join(","));
+
+    db.complete_trace_span(
+        span_id,
+        duration_ms,
+        capture.request_id.as_deref(),
+        capture.stop_reason.as_deref(),
+        capture.input_tokens,
+        capture.output_tokens,
+        capture.cache_read,
+        capture.cache_write,
+        preview(&redactor.redact(&capture.thinking).text).as_deref(),
+        preview(&redactor.redact(&capture.response).text).as_deref(),
+        tool_names.as_deref(),
+        user_preview.as_deref(),
+    )?;
+
+    if !capture.thinking.is_empty() {
+        store_content(
+            db,
+            redactor,
+            span_id,
+            "thinking",
+            &capture.thinking,
+            None,
+            None,
+        )?;
+    }
+    if !capture.response.is_empty() {
+        store_content(
+            db,
+            redactor,
+            span_id,
+            "response",
+            &capture.response,
+            None,
+            None,
+        )?;
+    }
+    for tool in &capture.tool_calls {
+        store_content(
+            db,
+            redactor,
+            span_id,
+            "tool_input",
+            &tool.input,
+            tool.name.as_deref(),
+            tool.id.as_deref(),
+        )?;
+        if let Some((server, name)) = tool.name.as_deref().and_then(mcp_trace::split_tool_name) {
+            let call = McpCapture {
+                id: tool.id.clone(),
+                server: server.to_string(),
+                tool: name.to_string(),
+                input: Some(tool.input.clone()),
+                ..Default::default()
+            };
+            mcp_trace::store(db, redactor, span_id, &call)?;
+        }
+    }
+    for call in &capture.mcp_calls {
+        mcp_trace::store(db, redactor, span_id, call)?;
+    }
+    if let Some(system) = &capture.system_prompt {
+        store_content(db, redactor, span_id, "system", system, None, None)?;
+    }
+    if let Some(stream) = capture.stream {
+        db.set_span_stream(
+            span_id,
+            stream.chunks,
+            stream.first_token_ms,
+            stream.complete,
+        )?;
+    }
+
+    Ok(span_id)
+}
+
+// ============================================================================
+// Connection handling
+// ============================================================================
+
+/// State shared by all proxy connections
+struct Proxy {
+    ca: LocalCa,
+    upstream: Arc<ClientConfig>,
+    db: Database,
+    session_id: String,
+    config: Config,
+    redactor: Redactor,
+    /// Set once an enforced budget is blown; later model calls are refused
+    blocked: Mutex<Option<String>>,
+    debug: bool,
+}
+
+/// Serve proxy connections on `listen` until the process exits, recording
+/// model calls into `session_id` and checking them against the config's
+/// trace budget
+pub fn run(
+    listen: &str,
+    ca: LocalCa,
+    db: Database,
+    session_id: &str,
+    config: Config,
+) -> io::Result<()> {
+    let redactor = Redactor::from_config(&config.trace.redact)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+        .with_capture(CaptureFilter::from_config(&config.trace.capture));
+    let listener = TcpListener::bind(listen)?;
+    let proxy = Arc::new(Proxy {
+        ca,
+        upstream: Arc::new(upstream_config()),
+        db,
+        session_id: session_id.to_string(),
+        config,
+        redactor,
+        blocked: Mutex::new(None),
+        debug: std::env::var("DECIDUOUS_TRACE_DEBUG").is_ok_and(|v| v == "1" || v == "true"),
+    });
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let proxy = Arc::clone(&proxy);
+        std::thread::spawn(move || {
+            if let Err(e) = handle_client(&proxy, stream) {
+                if proxy.debug {
+                    eprintln!("proxy: {}", e);
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle_client(proxy: &Proxy, stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let Some(head) = read_head(&mut reader)? else {
+        return Ok(());
+    };
+
+    let mut parts = head.start.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let target = parts.next().unwrap_or("").to_string();
+    let mut client = stream;
+
+    if !method.eq_ignore_ascii_case("CONNECT") {
+        client.write_all(
+            b"HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        )?;
+        return Ok(());
+    }
+
+    let (host, port) = match target.rsplit_once(':') {
+        Some((h, p)) => (h.to_string(), p.parse::<u16>().unwrap_or(443)),
+        None => (target.clone(), 443),
+    };
+    client.write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")?;
+
+    match Api::from_host(&host) {
+        Some(api) if port == 443 => intercept(proxy, api, &host, client),
+        _ => tunnel(&target, client, reader.buffer().to_vec()),
+    }
+}
+
+/// Pass a CONNECT tunnel through without looking at it
+fn tunnel(target: &str, client: TcpStream, buffered: Vec<u8>) -> io::Result<()> {
+    let mut upstream = TcpStream::connect(target)?;
+    upstream.write_all(&buffered)?;
+
+    let mut client_read = client.try_clone()?;
+    let mut upstream_write = upstream.try_clone()?;
+    let outbound = std::thread::spawn(move || {
+        let _ = io::copy(&mut client_read, &mut upstream_write);
+        let _ = upstream_write.shutdown(std::net::Shutdown::Write);
+    });
+
+    let mut client_write = client;
+    let _ = io::copy(&mut upstream, &mut client_write);
+    let _ = client_write.shutdown(std::net::Shutdown::Write);
+    let _ = outbound.join();
+    Ok(())
+}
+
+/// Terminate TLS for an API host, relay one request, and record it
+fn intercept(proxy: &Proxy, api: Api, host: &str, client: TcpStream) -> io::Result<()> {
+    let tls = ServerConnection::new(proxy.ca.server_config(host)?).map_err(other)?;
+    let mut client = BufReader::new(StreamOwned::new(tls, client));
+
+    let Some(head) = read_head(&mut client)? else {
+        return Ok(());
+    };
+    if head
+        .header("expect")
+        .is_some_and(|v| v.eq_ignore_ascii_case("100-continue"))
+    {
+        client
+            .get_mut()
+            .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+        client.get_mut().flush()?;
+    }
+    let body = read_body(&mut client, &head)?;
+    let path = head.start.split_whitespace().nth(1).unwrap_or("");
+    let record = api.records_path(path);
+
+    let blocked = proxy
+        .blocked
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if let (true, Some(message)) = (record, blocked) {
+        let body = budget_error(api, &message);
+        client.get_mut().write_all(
+            format!(
+                "HTTP/1.1 403 Forbidden\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .as_bytes(),
+        )?;
+        client.get_mut().flush()?;
+        return Ok(());
+    }
+    let started = Instant::now();
+
+    let server_name = ServerName::try_from(host.to_string()).map_err(other)?;
+    let tcp = TcpStream::connect((host, 443))?;
+    tcp.set_read_timeout(Some(UPSTREAM_TIMEOUT))?;
+    let tls = ClientConnection::new(Arc::clone(&proxy.upstream), server_name).map_err(other)?;
+    let mut upstream = BufReader::new(StreamOwned::new(tls, tcp));
+
+    upstream
+        .get_mut()
+        .write_all(&upstream_request(&head, body.len()).to_bytes())?;
+    upstream.get_mut().write_all(&body)?;
+    upstream.get_mut().flush()?;
+
+    let Some(response_head) = read_head(&mut upstream)? else {
+        return Err(other(format!("{} closed without responding", host)));
+    };
+    client.get_mut().write_all(&response_head.to_bytes())?;
+    client.get_mut().flush()?;
+
+    // Relay the body as it streams in; upstream closes when it's done. If
+    // either side drops mid-stream, what arrived is still recorded.
+    let mut assembler = StreamAssembler::new(
+        api,
+        response_head.is_chunked(),
+        started,
+        parse_request(&body),
+    );
+    let mut relay_error = None;
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = match upstream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            // Servers often close without a TLS close_notify
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => {
+                relay_error = Some(e);
+                break;
+            }
+        };
+        if record {
+            assembler.feed(&buf[..n]);
+        }
+        let sent = client.get_mut().write_all(&buf[..n]);
+        if let Err(e) = sent.and_then(|()| client.get_mut().flush()) {
+            relay_error = Some(e);
+            break;
+        }
+    }
+    if relay_error.is_none() {
+        client.get_mut().conn.send_close_notify();
+        let _ = client.get_mut().flush();
+    }
+
+    if record {
+        let duration_ms = started.elapsed().as_millis() as i32;
+        let capture = assembler.finish();
+
+        match record_span(
+            &proxy.db,
+            &proxy.redactor,
+            &proxy.session_id,
+            &capture,
+            duration_ms,
+        ) {
+            Ok(span_id) => println!(
+                "  span #{} {} {}↓ {}↑ {:.1}s{}",
+                span_id,
+                capture.model.as_deref().unwrap_or("?"),
+                capture.input_tokens.unwrap_or(0),
+                capture.output_tokens.unwrap_or(0),
+                duration_ms as f64 / 1000.0,
+                if capture.stream.is_some_and(|s| !s.complete) {
+                    " (cut off)"
+                } else {
+                    ""
+                }
+            ),
+            Err(e) => eprintln!("proxy: recording span: {}", e),
+        }
+
+        match check_budget(&proxy.db, &proxy.config, &proxy.session_id) {
+            Ok(Some(alert)) => {
+                println!("  {}", alert.message);
+                if alert.block {
+                    println!("  Refusing further API calls until the proxy is restarted");
+                    *proxy.blocked.lock().unwrap_or_else(|e| e.into_inner()) = Some(alert.message);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("proxy: checking budget: {}", e),
+        }
+    }
+
+    relay_error.map_or(Ok(()), Err)
+}
+
+/// Error body in the API's own format, so clients show the message
+pub fn budget_error(api: Api, message: &str) -> String {
+    match api {
+        Api::Anthropic => serde_json::json!({
+            "type": "error",
+            "error": {"type": "permission_error", "message": message},
+        }),
+        Api::OpenAi => serde_json::json!({
+            "error": {"type": "budget_exceeded", "code": "budget_exceeded", "message": message},
+        }),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_from_host() {
+        assert_eq!(Api::from_host("api.anthropic.com"), Some(Api::Anthropic));
+        assert_eq!(Api::from_host("api.openai.com"), Some(Api::OpenAi));
+        assert_eq!(Api::from_host("github.com"), None);
+        assert!(Api::Anthropic.records_path("/v1/messages?beta=true"));
+        assert!(!Api::Anthropic.records_path("/v1/messages/count_tokens"));
+        assert!(Api::OpenAi.records_path("/v1/chat/completions"));
+    }
+
+    #[test]
+    fn test_read_head_and_chunked_body() {
+        let raw = b"POST /v1/messages HTTP/1.1\r\nHost: api.anthropic.com\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6;ext=1\r\n world\r\n0\r\n\r\n";
+        let mut reader = &raw[..];
+        let head = read_head(&mut reader).unwrap().unwrap();
+        assert_eq!(head.start, "POST /v1/messages HTTP/1.1");
+        assert_eq!(head.header("host"), Some("api.anthropic.com"));
+        assert_eq!(read_body(&mut reader, &head).unwrap(), b"hello world");
+
+        assert_eq!(read_head(&mut &b""[..]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_upstream_request_headers() {
+        let head = Head {
+            start: "POST /v1/messages HTTP/1.1".to_string(),
+            headers: vec![
+                ("Host".to_string(), "api.anthropic.com".to_string()),
+                ("Accept-Encoding".to_string(), "gzip, br".to_string()),
+                ("Transfer-Encoding".to_string(), "chunked".to_string()),
+                ("Connection".to_string(), "keep-alive".to_string()),
+                ("x-api-key".to_string(), "sk-test".to_string()),
+            ],
+        };
+        let upstream = upstream_request(&head, 11);
+        assert_eq!(upstream.header("accept-encoding"), Some("identity"));
+        assert_eq!(upstream.header("connection"), Some("close"));
+        assert_eq!(upstream.header("content-length"), Some("11"));
+        assert_eq!(upstream.header("transfer-encoding"), None);
+        assert_eq!(upstream.header("x-api-key"), Some("sk-test"));
+    }
+
+    #[test]
+    fn test_parse_request() {
+        let body = br#"{
+            "model": "claude-sonnet-4",
+            "system": [{"type": "text", "text": "Be brief."}],
+            "messages": [
+                {"role": "user", "content": "Fix the parser"},
+                {"role": "assistant", "content": "On it"},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "t1", "content": "ok"},
+                    {"type": "text", "text": "<system-reminder>ignore</system-reminder>"}
+                ]}
+            ]
+        }"#;
+        let capture = parse_request(body);
+        assert_eq!(capture.model.as_deref(), Some("claude-sonnet-4"));
+        assert_eq!(capture.user_preview.as_deref(), Some("Fix the parser"));
+        assert_eq!(capture.system_prompt.as_deref(), Some("Be brief."));
+
+        let openai = br#"{"model": "gpt-4o", "messages": [
+            {"role": "system", "content": "You are terse."},
+            {"role": "user", "content": "Hi"}
+        ]}"#;
+        let capture = parse_request(openai);
+        assert_eq!(capture.system_prompt.as_deref(), Some("You are terse."));
+        assert_eq!(capture.user_preview.as_deref(), Some("Hi"));
+    }
+
+    #[test]
+    fn test_parse_anthropic_stream() {
+        let body = r#"event: message_start
+data: {"type":"message_start","message":{"id":"msg_1","model":"claude-sonnet-4","usage":{"input_tokens":12,"cache_read_input_tokens":100,"cache_creation_input_tokens":5}}}
+
+data: {"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"Let me"}}
+
+data: {"type":"content_block_delta","index":1,"delta":{"type":"text_delta","text":"Done."}}
+
+data: {"type":"content_block_start","index":2,"content_block":{"type":"tool_use","id":"tu_1","name":"Bash"}}
+
+data: {"type":"content_block_delta","index":2,"delta":{"type":"input_json_delta","partial_json":"{\"cmd\":"}}
+
+data: {"type":"content_block_delta","index":2,"delta":{"type":"input_json_delta","partial_json":"\"ls\"}"}}
+
+data: {"type":"message_delta","delta":{"stop_reason":"tool_use"},"usage":{"output_tokens":34}}
+"#;
+        let mut capture = SpanCapture::default();
+        parse_response(Api::Anthropic, body, &mut capture);
+        assert_eq!(capture.request_id.as_deref(), Some("msg_1"));
+        assert_eq!(capture.input_tokens, Some(12));
+        assert_eq!(capture.output_tokens, Some(34));
+        assert_eq!(capture.cache_read, Some(100));
+        assert_eq!(capture.cache_write, Some(5));
+        assert_eq!(capture.thinking, "Let me");
+        assert_eq!(capture.response, "Done.");
+        assert_eq!(capture.stop_reason.as_deref(), Some("tool_use"));
+        assert_eq!(capture.tool_calls.len(), 1);
+        assert_eq!(capture.tool_calls[0].name.as_deref(), Some("Bash"));
+        assert_eq!(capture.tool_calls[0].input, r#"{"cmd":"ls"}"#);
+    }
+
+    #[test]
+    fn test_parse_openai_responses() {
+        let body = r#"{"id":"chatcmpl-1","model":"gpt-4o","choices":[{"finish_reason":"stop","message":{"content":"Hello"}}],"usage":{"prompt_tokens":9,"completion_tokens":2}}"#;
+        let mut capture = SpanCapture::default();
+        parse_response(Api::OpenAi, body, &mut capture);
+        assert_eq!(capture.response, "Hello");
+        assert_eq!(capture.input_tokens, Some(9));
+        assert_eq!(capture.stop_reason.as_deref(), Some("stop"));
+
+        let stream = r#"data: {"id":"chatcmpl-2","model":"gpt-4o","choices":[{"delta":{"tool_calls":[{"index":0,"id":"call_1","function":{"name":"search","arguments":"{\"q\":"}}]}}]}
+
+data: {"id":"chatcmpl-2","choices":[{"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\"rust\"}"}}]},"finish_reason":"tool_calls"}]}
+
+data: {"id":"chatcmpl-2","choices":[],"usage":{"prompt_tokens":20,"completion_tokens":7,"prompt_tokens_details":{"cached_tokens":4}}}
+
+data: [DONE]
+"#;
+        let mut capture = SpanCapture::default();
+        parse_response(Api::OpenAi, stream, &mut capture);
+        assert_eq!(capture.request_id.as_deref(), Some("chatcmpl-2"));
+        assert_eq!(capture.tool_calls.len(), 1);
+        assert_eq!(capture.tool_calls[0].id.as_deref(), Some("call_1"));
+        assert_eq!(capture.tool_calls[0].input, r#"{"q":"rust"}"#);
+        assert_eq!(capture.stop_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(capture.output_tokens, Some(7));
+        assert_eq!(capture.cache_read, Some(4));
+    }
+
+    #[test]
+    fn test_stream_assembler() {
+        let events = concat!(
+            "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"Hel\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"lo\"}}\n\n",
+            "data: {\"type\":\"message_stop\"}\n\n",
+        );
+        let mut framed = Vec::new();
+        for piece in events.as_bytes().chunks(50) {
+            framed.extend_from_slice(format!("{:x}\r\n", piece.len()).as_bytes());
+            framed.extend_from_slice(piece);
+            framed.extend_from_slice(b"\r\n");
+        }
+        framed.extend_from_slice(b"0\r\n\r\n");
+
+        // Reads split lines, chunk headers and chunk data at odd places
+        let mut assembler =
+            StreamAssembler::new(Api::Anthropic, true, Instant::now(), SpanCapture::default());
+        for read in framed.chunks(7) {
+            assembler.feed(read);
+        }
+        let capture = assembler.finish();
+        assert_eq!(capture.request_id.as_deref(), Some("msg_1"));
+        assert_eq!(capture.response, "Hello");
+        let stats = capture.stream.unwrap();
+        assert_eq!(stats.chunks, 4);
+        assert!(stats.first_token_ms.is_some());
+        assert!(stats.complete);
+
+        // Cut off partway through the second delta's transfer chunk
+        let mut assembler =
+            StreamAssembler::new(Api::Anthropic, true, Instant::now(), SpanCapture::default());
+        let cut = framed.len() - 120;
+        assembler.feed(&framed[..cut]);
+        let capture = assembler.finish();
+        assert_eq!(capture.response, "Hel");
+        let stats = capture.stream.unwrap();
+        assert!(!stats.complete);
+        assert_eq!(stats.chunks, 2);
+
+        // Plain JSON bodies aren't streams
+        let mut assembler =
+            StreamAssembler::new(Api::OpenAi, false, Instant::now(), SpanCapture::default());
+        assembler.feed(br#"{"id":"chatcmpl-1","choices":[{"message":{"content":"Hi"}}]}"#);
+        let capture = assembler.finish();
+        assert_eq!(capture.response, "Hi");
+        assert!(capture.stream.is_none());
+    }
+
+    #[test]
+    fn test_mcp_capture() {
+        // Result of a client MCP tool called in the previous turn
+        let body = br#"{"model": "claude-sonnet-4", "messages": [
+            {"role": "user", "content": "File the bug"},
+            {"role": "assistant", "content": [
+                {"type": "tool_use", "id": "t1", "name": "mcp__github__create_issue", "input": {}},
+                {"type": "tool_use", "id": "t2", "name": "Bash", "input": {}}
+            ]},
+            {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "t1", "content": [{"type": "text", "text": "Created #12"}]},
+                {"type": "tool_result", "tool_use_id": "t2", "content": "ok"}
+            ]}
+        ]}"#;
+        let mut capture = parse_request(body);
+        assert_eq!(capture.mcp_calls.len(), 1);
+        assert_eq!(capture.mcp_calls[0].server, "github");
+        assert_eq!(capture.mcp_calls[0].tool, "create_issue");
+        assert_eq!(capture.mcp_calls[0].result.as_deref(), Some("Created #12"));
+        assert_eq!(capture.mcp_calls[0].input, None);
+
+        // MCP connector call and result streamed in the response
+        let stream = r#"data: {"type":"content_block_start","index":0,"content_block":{"type":"mcp_tool_use","id":"m1","name":"search","server_name":"docs","input":{}}}
+
+data: {"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"{\"q\":\"auth\"}"}}
+
+data: {"type":"content_block_start","index":1,"content_block":{"type":"mcp_tool_result","tool_use_id":"m1","is_error":true,"content":[{"type":"text","text":"timeout"}]}}
+"#;
+        parse_response(Api::Anthropic, stream, &mut capture);
+        let call = &capture.mcp_calls[1];
+        assert_eq!(call.server, "docs");
+        assert_eq!(call.input.as_deref(), Some(r#"{"q":"auth"}"#));
+        assert_eq!(call.result.as_deref(), Some("timeout"));
+        assert!(call.is_error);
+        assert!(capture.tool_calls.is_empty());
+    }
+
+    #[test]
+    fn test_budget_error() {
+        let body: Value = serde_json::from_str(&budget_error(Api::Anthropic, "over")).unwrap();
+        assert_eq!(body["error"]["message"], "over");
+        let body: Value = serde_json::from_str(&budget_error(Api::OpenAi, "over")).unwrap();
+        assert_eq!(body["error"]["code"], "budget_exceeded");
+    }
+
+    #[test]
+    fn test_record_span() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        db.start_trace_session("s1", None, None, Some("proxy"))
+            .unwrap();
+
+        let capture = SpanCapture {
+            model: Some("claude-sonnet-4".to_string()),
+            response: "Done, key is sk-ant-REDACTED.".to_string(),
+            input_tokens: Some(10),
+            output_tokens: Some(3),
+            tool_calls: vec![ToolCallCapture {
+                id: Some("tu_1".to_string()),
+                name: Some("Bash".to_string()),
+                input: "{}".to_string(),
+            }],
+            mcp_calls: vec![McpCapture {
+                id: Some("t0".to_string()),
+                server: "github".to_string(),
+                tool: "create_issue".to_string(),
+                result: Some("Created #12".to_string()),
+                ..Default::default()
+            }],
+            stream: Some(StreamStats {
+                chunks: 6,
+                first_token_ms: Some(250),
+                complete: false,
+            }),
+            ..Default::default()
+        };
+        record_span(&db, &Redactor::default(), "s1", &capture, 1200).unwrap();
+
+        let spans = db.get_trace_spans("s1").unwrap();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].output_tokens, Some(3));
+        assert_eq!(spans[0].tool_names.as_deref(), Some("Bash"));
+        assert_eq!(
+            spans[0].response_preview.as_deref(),
+            Some("Done, key is [REDACTED:anthropic_key].")
+        );
+
+        let content = db.get_trace_content(spans[0].id).unwrap();
+        assert!(content.iter().all(|c| !c.content.contains("sk-ant-")));
+        let mcp = content
+            .iter()
+            .find(|c| c.content_type == mcp_trace::MCP_RESULT)
+            .unwrap();
+        assert_eq!(mcp.tool_name.as_deref(), Some("github/create_issue"));
+        let redactions = db.get_trace_redactions(spans[0].id).unwrap();
+        assert_eq!(redactions.len(), 1);
+        assert_eq!(redactions[0].rule, "anthropic_key");
+        assert_eq!(redactions[0].content_type, "response");
+
+        let stream = db.get_span_stream(spans[0].id).unwrap().unwrap();
+        assert_eq!(stream.chunk_count, 6);
+        assert_eq!(stream.first_token_ms, Some(250));
+        assert!(!stream.complete);
+    }
+}
@@ -0,0 +1,389 @@
+//! Fetch patches from URLs for `diff apply`
+//!
+//! `diff apply https://...` downloads the patch with curl, over HTTPS only,
+//! instead of making people save PR artifacts by hand. A gist page URL is
+//! expanded to every `.json` file in the gist. The expected SHA-256 can be
+//! given with `--sha256` or as a `#sha256=<hex>` fragment on the URL; a
+//! download that doesn't match is refused before it is parsed.
+
+use crate::diff::GraphPatch;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A patch downloaded from a URL
+#[derive(Debug, Clone)]
+pub struct FetchedPatch {
+    /// URL of the file itself (a gist's raw file URL, for gists)
+    pub source: String,
+    /// SHA-256 of the downloaded bytes, hex
+    pub sha256: String,
+    pub patch: GraphPatch,
+}
+
+/// Whether a `diff apply` argument is a URL rather than a file path
+pub fn is_url(arg: &str) -> bool {
+    arg.starts_with("https://") || arg.starts_with("http://")
+}
+
+/// Split a `#sha256=<hex>` fragment off a URL
+pub fn split_checksum(url: &str) -> (&str, Option<&str>) {
+    match url.split_once('#') {
+        Some((base, fragment)) => (base, fragment.strip_prefix("sha256=")),
+        None => (url, None),
+    }
+}
+
+/// The gist ID of a `gist.github.com` page URL
+///
+/// Raw file URLs (`gist.githubusercontent.com`) are fetched as they are.
+pub fn gist_id(url: &str) -> Option<&str> {
+    let path = url.strip_prefix("https://gist.github.com/")?;
+    let id = path.trim_end_matches('/').rsplit('/').next()?;
+    (!id.is_empty() && id.chars().all(|c| c.is_ascii_hexdigit())).then_some(id)
+}
+
+/// SHA-256 of `bytes`, hex
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Quote a value for a curl config file
+pub(crate) fn curl_config_value(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A `header = ...` line for a curl config file
+pub(crate) fn curl_header(value: &str) -> String {
+    format!("header = {}\n", curl_config_value(value))
+}
+
+/// Why `curl_request` got no HTTP response
+#[derive(Debug)]
+pub(crate) enum CurlError {
+    /// curl couldn't be run, or the body couldn't be written
+    Io(std::io::Error),
+    /// curl ran but the transfer failed; its stderr
+    Failed(String),
+}
+
+impl std::fmt::Display for CurlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CurlError::Io(e) => write!(f, "running curl: {}", e),
+            CurlError::Failed(stderr) => write!(f, "{}", stderr.trim()),
+        }
+    }
+}
+
+impl From<std::io::Error> for CurlError {
+    fn from(e: std::io::Error) -> Self {
+        CurlError::Io(e)
+    }
+}
+
+/// A request body in a temporary file, removed on drop
+struct BodyFile(std::path::PathBuf);
+
+impl BodyFile {
+    fn new(body: &[u8]) -> std::io::Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "deciduous-curl-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?
+            .write_all(body)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for BodyFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Make an HTTP request with curl; returns the status and response body
+///
+/// `config` (curl config lines: credentials, headers) goes in on stdin, so
+/// tokens never appear in `ps` or /proc. The body is sent from a temporary
+/// file.
+pub(crate) fn curl_request(
+    method: &str,
+    url: &str,
+    config: &str,
+    body: Option<&[u8]>,
+) -> Result<(u16, String), CurlError> {
+    let body_file = body.map(BodyFile::new).transpose()?;
+    let mut cmd = Command::new("curl");
+    cmd.args(["-sS", "-K", "-", "-X", method, "-w", "\n%{http_code}"]);
+    if let Some(file) = &body_file {
+        cmd.arg("--data-binary")
+            .arg(format!("@{}", file.0.display()));
+    }
+    cmd.arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = cmd.spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(config.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(CurlError::Failed(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (response, status) = stdout.rsplit_once('\n').unwrap_or(("", &*stdout));
+    Ok((status.trim().parse().unwrap_or(0), response.to_string()))
+}
+
+/// curl config lines with credentials from the environment
+///
+/// A bearer token in `token_var` wins; otherwise basic auth from `user_var`
+/// and `secret_var`. None when neither is set.
+#[cfg(feature = "forge")]
+pub(crate) fn curl_auth_from_env(
+    token_var: &str,
+    user_var: &str,
+    secret_var: &str,
+) -> Option<String> {
+    if let Ok(token) = std::env::var(token_var) {
+        if !token.is_empty() {
+            return Some(curl_header(&format!("Authorization: Bearer {}", token)));
+        }
+    }
+    let user = std::env::var(user_var).ok()?;
+    let secret = std::env::var(secret_var).ok()?;
+    Some(format!(
+        "user = {}\n",
+        curl_config_value(&format!("{}:{}", user, secret))
+    ))
+}
+
+/// Send a JSON API request with curl for a forge backend without a CLI
+///
+/// `auth` is curl config lines (see `curl_auth_from_env`). Returns the parsed
+/// body, Null when it's empty. 401/403 and 429 map to `NotAuthenticated` and
+/// `RateLimited`; other failures carry `error_message` of the body, or the
+/// raw body when that finds nothing.
+#[cfg(feature = "forge")]
+pub(crate) fn curl_json(
+    method: &str,
+    url: &str,
+    auth: &str,
+    body: Option<&Value>,
+    error_message: fn(&Value) -> Option<String>,
+) -> crate::github::Result<Value> {
+    use crate::github::GitHubError;
+
+    let mut config = curl_header("Accept: application/json") + auth;
+    let payload = body.map(|b| b.to_string());
+    if payload.is_some() {
+        config += &curl_header("Content-Type: application/json");
+    }
+    let command = format!("curl -X {} {}", method, url);
+    let (status, response) =
+        match curl_request(method, url, &config, payload.as_deref().map(str::as_bytes)) {
+            Ok(result) => result,
+            Err(CurlError::Io(e)) => return Err(GitHubError::IoError(e)),
+            Err(CurlError::Failed(stderr)) => {
+                return Err(GitHubError::CommandFailed { command, stderr })
+            }
+        };
+
+    match status {
+        200..=299 => {
+            if response.trim().is_empty() {
+                Ok(Value::Null)
+            } else {
+                serde_json::from_str(&response).map_err(|e| GitHubError::ParseError {
+                    message: format!("JSON parse error: {}", e),
+                })
+            }
+        }
+        401 | 403 => Err(GitHubError::NotAuthenticated),
+        429 => Err(GitHubError::RateLimited),
+        _ => {
+            let message = serde_json::from_str::<Value>(&response)
+                .ok()
+                .and_then(|v| error_message(&v))
+                .filter(|m| !m.is_empty())
+                .unwrap_or_else(|| response.trim().to_string());
+            Err(GitHubError::CommandFailed {
+                command,
+                stderr: format!("HTTP {}: {}", status, message),
+            })
+        }
+    }
+}
+
+/// Check downloaded bytes against an expected checksum; returns the actual one
+pub fn verify_checksum(bytes: &[u8], expected: Option<&str>) -> Result<String, String> {
+    let actual = sha256_hex(bytes);
+    match expected {
+        Some(expected) if !expected.trim().eq_ignore_ascii_case(&actual) => Err(format!(
+            "checksum mismatch: expected sha256 {}, got {}",
+            expected.trim(),
+            actual
+        )),
+        _ => Ok(actual),
+    }
+}
+
+fn download(url: &str) -> Result<Vec<u8>, String> {
+    if !url.starts_with("https://") {
+        return Err(format!("Refusing {}: only https:// URLs are fetched", url));
+    }
+    let output = Command::new("curl")
+        .args(["-sSfL", "--proto", "=https", "--proto-redir", "=https"])
+        .args(["-H", "User-Agent: deciduous"])
+        .arg(url)
+        .output()
+        .map_err(|e| format!("running curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Fetching {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Raw URLs of the `.json` files in a gist
+fn gist_files(id: &str) -> Result<Vec<String>, String> {
+    let body = download(&format!("https://api.github.com/gists/{}", id))?;
+    let gist: Value =
+        serde_json::from_slice(&body).map_err(|e| format!("Unexpected gist response: {}", e))?;
+    let mut files: Vec<String> = gist["files"]
+        .as_object()
+        .map(|files| {
+            files
+                .iter()
+                .filter(|(name, _)| name.ends_with(".json"))
+                .filter_map(|(_, file)| file["raw_url"].as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    if files.is_empty() {
+        return Err(format!("Gist {} has no .json files", id));
+    }
+    Ok(files)
+}
+
+/// Download the patch(es) at a URL, checking the checksum if one is given
+///
+/// `expected` overrides a `#sha256=` fragment. A gist with several patch
+/// files can't be checked against one checksum; link the file instead.
+pub fn fetch_patches(url: &str, expected: Option<&str>) -> Result<Vec<FetchedPatch>, String> {
+    let (url, fragment) = split_checksum(url);
+    let expected = expected.or(fragment);
+    let sources = match gist_id(url) {
+        Some(id) => gist_files(id)?,
+        None => vec![url.to_string()],
+    };
+    if expected.is_some() && sources.len() > 1 {
+        return Err(format!(
+            "{} holds {} patch files; give the URL of one to check its checksum",
+            url,
+            sources.len()
+        ));
+    }
+
+    sources
+        .into_iter()
+        .map(|source| {
+            let bytes = download(&source)?;
+            let sha256 = verify_checksum(&bytes, expected)
+                .map_err(|e| format!("Refusing {}: {}", source, e))?;
+            let patch = GraphPatch::from_bytes(&bytes).map_err(|e| format!("{}: {}", source, e))?;
+            Ok(FetchedPatch {
+                source,
+                sha256,
+                patch,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_checksum() {
+        assert_eq!(
+            split_checksum("https://example.com/p.json#sha256=abc"),
+            ("https://example.com/p.json", Some("abc"))
+        );
+        assert_eq!(
+            split_checksum("https://example.com/p.json#top"),
+            ("https://example.com/p.json", None)
+        );
+        assert_eq!(
+            split_checksum("https://x/p.json"),
+            ("https://x/p.json", None)
+        );
+    }
+
+    #[test]
+    fn test_gist_id() {
+        assert_eq!(
+            gist_id("https://gist.github.com/alice/0a1b2c3d4e5f"),
+            Some("0a1b2c3d4e5f")
+        );
+        assert_eq!(
+            gist_id("https://gist.github.com/0a1b2c3d/"),
+            Some("0a1b2c3d")
+        );
+        assert_eq!(
+            gist_id("https://gist.githubusercontent.com/alice/0a1b/raw/p.json"),
+            None
+        );
+        assert_eq!(gist_id("https://gist.github.com/alice"), None);
+        assert!(is_url("https://example.com/p.json"));
+        assert!(!is_url(".deciduous/patches/p.json"));
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let sum = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert_eq!(verify_checksum(b"hello", None).unwrap(), sum);
+        assert!(verify_checksum(b"hello", Some(&sum.to_uppercase())).is_ok());
+        assert!(verify_checksum(b"hello!", Some(sum)).is_err());
+    }
+
+    #[test]
+    fn test_curl_config_lines() {
+        assert_eq!(
+            curl_header(r#"Authorization: Bearer a"b\c"#),
+            "header = \"Authorization: Bearer a\\\"b\\\\c\"\n"
+        );
+        let file = BodyFile::new(b"{}").unwrap();
+        let path = file.0.clone();
+        assert_eq!(std::fs::read(&path).unwrap(), b"{}");
+        drop(file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_refuses_plain_http() {
+        assert!(download("http://example.com/p.json").is_err());
+    }
+}
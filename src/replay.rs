@@ -0,0 +1,257 @@
+//! Replay a recorded trace session against a model
+//!
+//! Rebuilds each span's Messages API request from what was captured (system
+//! prompt, tool definitions, user message, the original assistant turns and
+//! the tool results that followed them) and sends it again, optionally to a
+//! different model. Replies are recorded as a new session so the two can be
+//! compared side by side.
+//!
+//! The conversation history is always the *original* one: tools can't be
+//! re-run, so a replayed reply that calls different tools doesn't change what
+//! the next request contains. Captured user messages and tool results are the
+//! interceptor's truncated copies, and thinking blocks aren't replayed.
+
+use crate::db::{TraceContent, TraceSpan};
+use crate::remote_patch::{curl_header, curl_request};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Stand-in for a tool result that wasn't captured
+const MISSING_RESULT: &str = "[tool result not recorded]";
+
+/// One rebuilt request, ready to send
+#[derive(Debug, Clone)]
+pub struct ReplayRequest {
+    /// Span the request was rebuilt from
+    pub span_id: i32,
+    pub user_preview: Option<String>,
+    pub body: Value,
+}
+
+fn of_type<'a>(
+    items: &'a [TraceContent],
+    kind: &'a str,
+) -> impl Iterator<Item = &'a TraceContent> + 'a {
+    items.iter().filter(move |c| c.content_type == kind)
+}
+
+/// Rebuild the session's requests in order
+///
+/// `model` overrides each span's recorded model. Spans with nothing new to
+/// send (no user message and no pending tool results) are skipped.
+pub fn build_requests(
+    spans: &[TraceSpan],
+    content: &HashMap<i32, Vec<TraceContent>>,
+    model: Option<&str>,
+    max_tokens: u32,
+) -> Vec<ReplayRequest> {
+    let mut requests = Vec::new();
+    let mut history: Vec<Value> = Vec::new();
+    let mut last_user: Option<&str> = None;
+    let mut pending_tools: Vec<String> = Vec::new();
+    let mut system: Option<&str> = None;
+    let mut tools: Option<Value> = None;
+
+    for span in spans {
+        let items = content.get(&span.id).map(Vec::as_slice).unwrap_or(&[]);
+        if let Some(s) = of_type(items, "system").next() {
+            system = Some(s.content.as_str());
+        }
+        if let Some(defs) = of_type(items, "tool_definitions").next() {
+            tools = serde_json::from_str(&defs.content).ok();
+        }
+
+        // Every tool_use in the previous assistant turn needs a result
+        let mut blocks: Vec<Value> = pending_tools
+            .drain(..)
+            .map(|id| {
+                let result = items.iter().find(|c| {
+                    (c.content_type == "tool_output" || c.content_type == "tool_error")
+                        && c.tool_use_id.as_deref() == Some(id.as_str())
+                });
+                json!({
+                    "type": "tool_result",
+                    "tool_use_id": id,
+                    "content": result.map_or(MISSING_RESULT, |r| r.content.as_str()),
+                    "is_error": result.map(|r| r.content_type == "tool_error").unwrap_or(true),
+                })
+            })
+            .collect();
+
+        let user = span.user_preview.as_deref().filter(|u| !u.is_empty());
+        if user.is_some() && user != last_user {
+            blocks.push(json!({"type": "text", "text": user}));
+            last_user = user;
+        }
+        if blocks.is_empty() {
+            continue;
+        }
+        history.push(json!({"role": "user", "content": blocks}));
+
+        let Some(model) = model.or(span.model.as_deref()) else {
+            continue;
+        };
+        let mut body = json!({
+            "model": model,
+            "max_tokens": max_tokens,
+            "messages": history,
+        });
+        if let Some(system) = system {
+            body["system"] = json!(system);
+        }
+        if let Some(tools) = &tools {
+            body["tools"] = tools.clone();
+        }
+        requests.push(ReplayRequest {
+            span_id: span.id,
+            user_preview: user.map(str::to_string),
+            body,
+        });
+
+        // Continue from what the original model said, not the replay
+        let mut reply: Vec<Value> = Vec::new();
+        let response = of_type(items, "response")
+            .map(|c| c.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let response = if response.is_empty() {
+            span.response_preview.clone().unwrap_or_default()
+        } else {
+            response
+        };
+        if !response.trim().is_empty() {
+            reply.push(json!({"type": "text", "text": response}));
+        }
+        for call in of_type(items, "tool_input") {
+            let Some(id) = &call.tool_use_id else {
+                continue;
+            };
+            reply.push(json!({
+                "type": "tool_use",
+                "id": id,
+                "name": call.tool_name.as_deref().unwrap_or("tool"),
+                "input": serde_json::from_str::<Value>(&call.content)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({})),
+            }));
+            pending_tools.push(id.clone());
+        }
+        if reply.is_empty() {
+            reply.push(json!({"type": "text", "text": "(no response recorded)"}));
+        }
+        history.push(json!({"role": "assistant", "content": reply}));
+    }
+    requests
+}
+
+/// Send one request to the Messages API, returning the raw response body
+///
+/// Uses `ANTHROPIC_API_KEY`, and `ANTHROPIC_BASE_URL` if set.
+pub fn send(body: &Value) -> Result<String, String> {
+    let key = std::env::var("ANTHROPIC_API_KEY")
+        .ok()
+        .filter(|k| !k.is_empty())
+        .ok_or("ANTHROPIC_API_KEY is not set")?;
+    let base = std::env::var("ANTHROPIC_BASE_URL")
+        .unwrap_or_else(|_| "https://api.anthropic.com".to_string());
+    let url = format!("{}/v1/messages", base.trim_end_matches('/'));
+
+    let config = curl_header("Content-Type: application/json")
+        + &curl_header("anthropic-version: 2023-06-01")
+        + &curl_header(&format!("x-api-key: {}", key));
+    let (status, response) = curl_request("POST", &url, &config, Some(body.to_string().as_bytes()))
+        .map_err(|e| e.to_string())?;
+    match status {
+        200..=299 => Ok(response),
+        0 => Err(format!("no response from {}", url)),
+        code => Err(format!("HTTP {}: {}", code, response.trim())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(id: i32, user: Option<&str>) -> TraceSpan {
+        TraceSpan {
+            model: Some("claude-sonnet-4".to_string()),
+            user_preview: user.map(str::to_string),
+            ..TraceSpan::for_test(id)
+        }
+    }
+
+    #[test]
+    fn test_build_requests() {
+        let spans = vec![
+            span(1, Some("List the files")),
+            span(2, Some("List the files")),
+            span(3, Some("Thanks")),
+        ];
+        let mut content = HashMap::new();
+        content.insert(
+            1,
+            vec![
+                TraceContent::for_test(1, "system", "You are helpful.", None),
+                TraceContent::for_test(1, "response", "Running ls.", None),
+                TraceContent::for_test(
+                    1,
+                    "tool_input",
+                    r#"{"command":"ls"}"#,
+                    Some(("Bash", "tu_1")),
+                ),
+            ],
+        );
+        content.insert(
+            2,
+            vec![
+                TraceContent::for_test(2, "tool_output", "README.md", Some(("Bash", "tu_1"))),
+                TraceContent::for_test(2, "response", "One file.", None),
+            ],
+        );
+
+        let requests = build_requests(&spans, &content, Some("claude-haiku-4-5"), 1024);
+        assert_eq!(requests.len(), 3);
+
+        let first = &requests[0].body;
+        assert_eq!(first["model"], "claude-haiku-4-5");
+        assert_eq!(first["system"], "You are helpful.");
+        assert_eq!(first["messages"].as_array().unwrap().len(), 1);
+
+        // Second request carries the original tool call and its result
+        let messages = requests[1].body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1]["content"][1]["type"], "tool_use");
+        assert_eq!(messages[1]["content"][1]["input"]["command"], "ls");
+        assert_eq!(messages[2]["content"][0]["tool_use_id"], "tu_1");
+        assert_eq!(messages[2]["content"][0]["content"], "README.md");
+        assert_eq!(messages[2]["content"].as_array().unwrap().len(), 1);
+
+        // A new user message starts a new turn
+        let messages = requests[2].body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 5);
+        assert_eq!(messages[4]["content"][0]["text"], "Thanks");
+        assert_eq!(requests[2].body["system"], "You are helpful.");
+    }
+
+    #[test]
+    fn test_missing_tool_result() {
+        let spans = vec![span(1, Some("Go")), span(2, Some("Stop"))];
+        let mut content = HashMap::new();
+        content.insert(
+            1,
+            vec![TraceContent::for_test(
+                1,
+                "tool_input",
+                "{}",
+                Some(("Bash", "tu_9")),
+            )],
+        );
+        let requests = build_requests(&spans, &content, None, 1024);
+        let messages = requests[1].body["messages"].as_array().unwrap();
+        assert_eq!(messages[2]["content"][0]["content"], MISSING_RESULT);
+        assert_eq!(messages[2]["content"][0]["is_error"], true);
+        assert_eq!(messages[2]["content"][1]["text"], "Stop");
+        assert_eq!(requests[1].body["model"], "claude-sonnet-4");
+    }
+}
@@ -4,6 +4,8 @@
 //! handles metadata comments for sync, and provides utilities
 //! for bidirectional synchronization with GitHub Issues.
 
+use crate::db::RoadmapItem;
+use crate::hook_script::HookBlock;
 use regex::Regex;
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
@@ -34,6 +36,18 @@ pub struct RoadmapCheckItem {
     pub checked: bool,
     pub outcome_change_id: Option<String>,
     pub line_number: usize,
+    /// Nesting level: 0 for top-level checkboxes, 1 for their sub-items, ...
+    #[serde(default)]
+    pub depth: usize,
+    /// change_id of the enclosing checkbox, for indented sub-items
+    #[serde(default)]
+    pub parent_change_id: Option<String>,
+    /// Priority from a `(P1)` annotation; lower is more urgent
+    #[serde(default)]
+    pub priority: Option<u8>,
+    /// Target date from a `(due: 2025-06)` annotation
+    #[serde(default)]
+    pub due: Option<String>,
 }
 
 /// Metadata embedded in HTML comments
@@ -202,6 +216,41 @@ pub fn generate_item_metadata(change_id: &str, outcome_change_id: Option<&str>)
     )
 }
 
+/// Checkbox line: indentation, mark, text
+const CHECKBOX_PATTERN: &str = r"^(\s*)-\s+\[([ xX])\]\s+(.+)$";
+
+/// Width of leading whitespace, counting a tab as four spaces
+fn indent_width(indent: &str) -> usize {
+    indent.chars().map(|c| if c == '\t' { 4 } else { 1 }).sum()
+}
+
+const PRIORITY_PATTERN: &str = r"(?i)\(p([0-9])\)";
+const DUE_PATTERN: &str = r"(?i)\(due:\s*([0-9]{4}-[0-9]{2}(?:-[0-9]{2})?)\)";
+
+/// Extract `(P1)` priority and `(due: 2025-06)` target date annotations
+///
+/// The annotations stay in the item text so it still matches the issue
+/// checklist; these are just the structured values.
+pub fn parse_item_annotations(text: &str) -> (Option<u8>, Option<String>) {
+    let priority_re = Regex::new(PRIORITY_PATTERN).unwrap();
+    let due_re = Regex::new(DUE_PATTERN).unwrap();
+
+    let priority = priority_re
+        .captures(text)
+        .and_then(|caps| caps[1].parse().ok());
+    let due = due_re.captures(text).map(|caps| caps[1].to_string());
+    (priority, due)
+}
+
+/// Item text with the priority/due annotations removed, for display
+pub fn strip_item_annotations(text: &str) -> String {
+    let priority_re = Regex::new(PRIORITY_PATTERN).unwrap();
+    let due_re = Regex::new(DUE_PATTERN).unwrap();
+    let stripped = priority_re.replace_all(text, "");
+    let stripped = due_re.replace_all(&stripped, "");
+    stripped.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 /// Parse ROADMAP.md file into structured sections
 pub fn parse_roadmap<P: AsRef<Path>>(path: P) -> Result<ParsedRoadmap> {
     let content = fs::read_to_string(path.as_ref())?;
@@ -213,7 +262,7 @@ pub fn parse_roadmap<P: AsRef<Path>>(path: P) -> Result<ParsedRoadmap> {
 
     // Regex patterns
     let header_re = Regex::new(r"^(#{2,3})\s+(.+)$")?;
-    let checkbox_re = Regex::new(r"^-\s+\[([ xX])\]\s+(.+)$")?;
+    let checkbox_re = Regex::new(CHECKBOX_PATTERN)?;
     let section_meta_re = Regex::new(r"<!--\s*roadmap:section\s+(.+?)\s*-->")?;
     let item_meta_re = Regex::new(r"<!--\s*roadmap:item\s+(.+?)\s*-->")?;
 
@@ -248,6 +297,8 @@ pub fn parse_roadmap<P: AsRef<Path>>(path: P) -> Result<ParsedRoadmap> {
             // Collect description lines until next header or checkbox
             let mut description_lines: Vec<&str> = Vec::new();
             let mut items: Vec<RoadmapCheckItem> = Vec::new();
+            // Enclosing checkboxes as (indent width, change_id), innermost last
+            let mut parents: Vec<(usize, String)> = Vec::new();
             let mut j = description_start;
 
             while j < lines.len() {
@@ -260,8 +311,14 @@ pub fn parse_roadmap<P: AsRef<Path>>(path: P) -> Result<ParsedRoadmap> {
 
                 // Check for checkbox item
                 if let Some(check_caps) = checkbox_re.captures(next_line) {
-                    let checked = check_caps.get(1).unwrap().as_str().to_lowercase() == "x";
-                    let text = check_caps.get(2).unwrap().as_str().trim().to_string();
+                    let indent = indent_width(check_caps.get(1).unwrap().as_str());
+                    let checked = check_caps.get(2).unwrap().as_str().to_lowercase() == "x";
+                    let text = check_caps.get(3).unwrap().as_str().trim().to_string();
+                    let line_number = j + 1; // 1-indexed, before skipping metadata
+
+                    while parents.last().is_some_and(|(width, _)| *width >= indent) {
+                        parents.pop();
+                    }
 
                     // Look for item metadata on next line
                     let mut item_meta = ItemMetadata::default();
@@ -274,14 +331,20 @@ pub fn parse_roadmap<P: AsRef<Path>>(path: P) -> Result<ParsedRoadmap> {
                     }
 
                     let item_change_id = item_meta.id.unwrap_or_else(|| Uuid::new_v4().to_string());
+                    let (priority, due) = parse_item_annotations(&text);
 
                     items.push(RoadmapCheckItem {
-                        change_id: item_change_id,
+                        change_id: item_change_id.clone(),
                         text,
                         checked,
                         outcome_change_id: item_meta.outcome_change_id,
-                        line_number: j + 1, // 1-indexed
+                        line_number,
+                        depth: parents.len(),
+                        parent_change_id: parents.last().map(|(_, id)| id.clone()),
+                        priority,
+                        due,
                     });
+                    parents.push((indent, item_change_id));
                 } else if !next_line.trim().is_empty()
                     && !item_meta_re.is_match(next_line)
                     && !section_meta_re.is_match(next_line)
@@ -342,7 +405,7 @@ pub fn write_roadmap_with_metadata<P: AsRef<Path>>(
     let mut output_lines: Vec<String> = Vec::new();
 
     let header_re = Regex::new(r"^(#{2,3})\s+(.+)$")?;
-    let checkbox_re = Regex::new(r"^-\s+\[([ xX])\]\s+(.+)$")?;
+    let checkbox_re = Regex::new(CHECKBOX_PATTERN)?;
     let section_meta_re = Regex::new(r"<!--\s*roadmap:section\s+(.+?)\s*-->")?;
     let item_meta_re = Regex::new(r"<!--\s*roadmap:item\s+(.+?)\s*-->")?;
 
@@ -383,20 +446,17 @@ pub fn write_roadmap_with_metadata<P: AsRef<Path>>(
 
         // Check for checkbox item
         if let Some(check_caps) = checkbox_re.captures(line) {
-            let text = check_caps.get(2).unwrap().as_str().trim();
+            let indent = check_caps.get(1).unwrap().as_str();
+            let text = check_caps.get(3).unwrap().as_str().trim();
 
             output_lines.push(line.to_string());
 
-            // Find matching item in sections
-            let mut found_item: Option<&RoadmapCheckItem> = None;
-            for section in sections {
-                for item in &section.items {
-                    if item.text.contains(text) || text.contains(&item.text) {
-                        found_item = Some(item);
-                        break;
-                    }
-                }
-            }
+            // Find matching item in sections, preferring an exact text match
+            let all_items = || sections.iter().flat_map(|s| s.items.iter());
+            let found_item: Option<&RoadmapCheckItem> =
+                all_items().find(|item| item.text == text).or_else(|| {
+                    all_items().find(|item| item.text.contains(text) || text.contains(&item.text))
+                });
 
             // Skip existing item metadata if present
             if i + 1 < lines.len() && item_meta_re.is_match(lines[i + 1]) {
@@ -407,7 +467,7 @@ pub fn write_roadmap_with_metadata<P: AsRef<Path>>(
             if let Some(item) = found_item {
                 let meta_comment =
                     generate_item_metadata(&item.change_id, item.outcome_change_id.as_deref());
-                output_lines.push(format!("  {}", meta_comment));
+                output_lines.push(format!("{}  {}", indent, meta_comment));
             }
 
             i += 1;
@@ -447,10 +507,27 @@ pub fn generate_issue_body(section: &RoadmapSection) -> String {
         body.push_str("## Tasks\n\n");
         for item in &section.items {
             let checkbox = if item.checked { "[x]" } else { "[ ]" };
-            body.push_str(&format!("- {} {}\n", checkbox, item.text));
+            body.push_str(&format!(
+                "{}- {} {}\n",
+                "  ".repeat(item.depth),
+                checkbox,
+                item.text
+            ));
         }
     }
 
+    // Most urgent priority and earliest target date across the tasks
+    let priority = section.items.iter().filter_map(|i| i.priority).min();
+    let due = section.items.iter().filter_map(|i| i.due.as_deref()).min();
+    let schedule: Vec<String> = priority
+        .map(|p| format!("**Priority:** P{}", p))
+        .into_iter()
+        .chain(due.map(|d| format!("**Due:** {}", d)))
+        .collect();
+    if !schedule.is_empty() {
+        body.push_str(&format!("\n{}\n", schedule.join(" · ")));
+    }
+
     // Add metadata footer
     body.push_str("\n---\n");
     body.push_str(&format!(
@@ -477,6 +554,457 @@ pub fn parse_issue_body_checkboxes(body: &str) -> Vec<(String, bool)> {
     items
 }
 
+/// How a section's checkboxes on the issue differ from ROADMAP.md
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteChange {
+    /// Toggled on the issue only; pull it into ROADMAP.md
+    Checkbox { text: String, checked: bool },
+    /// Local and remote disagree and there's no baseline saying which side moved
+    Conflict {
+        text: String,
+        local: bool,
+        remote: bool,
+    },
+    /// Checkbox added on the issue that ROADMAP.md has never had
+    RemoteOnly { text: String, checked: bool },
+}
+
+/// Three-way compare of a section's checkboxes against its issue body
+///
+/// `baseline` is the checkbox state both sides agreed on at the last sync
+/// (item text -> checked). An item that differs is pulled when only the issue
+/// moved away from the baseline, left for the push when only ROADMAP.md moved,
+/// and reported as a conflict when there is no baseline to decide.
+pub fn diff_remote_checkboxes(
+    section: &RoadmapSection,
+    remote_body: &str,
+    baseline: &HashMap<String, bool>,
+) -> Vec<RemoteChange> {
+    let remote = parse_issue_body_checkboxes(remote_body);
+    let mut changes = Vec::new();
+
+    for item in &section.items {
+        let Some((_, remote_checked)) = remote.iter().find(|(text, _)| *text == item.text) else {
+            continue; // Added locally; the push will add it to the issue
+        };
+        if *remote_checked == item.checked {
+            continue;
+        }
+        match baseline.get(&item.text) {
+            Some(base) if *base == item.checked => changes.push(RemoteChange::Checkbox {
+                text: item.text.clone(),
+                checked: *remote_checked,
+            }),
+            Some(_) => {} // Changed locally; the push wins
+            None => changes.push(RemoteChange::Conflict {
+                text: item.text.clone(),
+                local: item.checked,
+                remote: *remote_checked,
+            }),
+        }
+    }
+
+    for (text, checked) in remote {
+        let known_locally = section.items.iter().any(|i| i.text == text);
+        // Items in the baseline but not ROADMAP.md were deleted locally
+        if !known_locally && !baseline.contains_key(&text) {
+            changes.push(RemoteChange::RemoteOnly { text, checked });
+        }
+    }
+
+    changes
+}
+
+/// Set the checkbox on the given 1-indexed lines, leaving everything else intact
+pub fn set_checkbox_lines(content: &str, changes: &[(usize, bool)]) -> String {
+    let checkbox_re = Regex::new(r"^(\s*-\s+\[)[ xX](\])").unwrap();
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+
+    for (line_number, checked) in changes {
+        let Some(line) = line_number
+            .checked_sub(1)
+            .and_then(|idx| lines.get_mut(idx))
+        else {
+            continue;
+        };
+        let mark = if *checked { "x" } else { " " };
+        *line = checkbox_re
+            .replace(line, format!("${{1}}{}${{2}}", mark))
+            .to_string();
+    }
+
+    let mut output = lines.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+    output
+}
+
+/// Tick the checkboxes with the given texts in an issue body, leaving the rest as-is
+pub fn check_items_in_body(body: &str, texts: &[String]) -> String {
+    let checkbox_re = Regex::new(CHECKBOX_PATTERN).unwrap();
+    let changes: Vec<(usize, bool)> = body
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            checkbox_re
+                .captures(line)
+                .is_some_and(|caps| texts.iter().any(|t| t == caps[3].trim()))
+        })
+        .map(|(idx, _)| (idx + 1, true))
+        .collect();
+    set_checkbox_lines(body, &changes)
+}
+
+/// ROADMAP.md split into what stays and what moves to the archive
+#[derive(Debug, Clone)]
+pub struct RoadmapArchive {
+    /// ROADMAP.md without the archived sections
+    pub remaining: String,
+    /// Archived section blocks, metadata comments included
+    pub archived: String,
+    /// The sections that were moved
+    pub sections: Vec<RoadmapSection>,
+}
+
+fn all_checked(sections: &[RoadmapSection]) -> bool {
+    sections.iter().any(|s| !s.items.is_empty())
+        && sections.iter().all(|s| s.items.iter().all(|i| i.checked))
+}
+
+/// Pull fully-complete sections out of ROADMAP.md content
+///
+/// A `###` section moves when it has checkboxes and all are ticked. A `##`
+/// section moves together with its `###` children once everything under it
+/// is ticked; otherwise its finished children move on their own, under a
+/// plain copy of the `##` header in the archive. `sections` must come from
+/// parsing `content`.
+pub fn split_completed_sections(content: &str, sections: &[RoadmapSection]) -> RoadmapArchive {
+    let lines: Vec<&str> = content.lines().collect();
+    // 0-indexed [start, end) line ranges to move
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let mut archived = String::new();
+    let mut moved: Vec<RoadmapSection> = Vec::new();
+
+    let mut take = |group: &[RoadmapSection], archived: &mut String| {
+        let start = group[0].line_start - 1;
+        let end = group[group.len() - 1].line_end;
+        ranges.push((start, end));
+        let block = lines[start..end].join("\n");
+        archived.push_str(block.trim_end());
+        archived.push_str("\n\n");
+        moved.extend(group.iter().cloned());
+    };
+
+    let mut idx = 0;
+    while idx < sections.len() {
+        let section = &sections[idx];
+        if section.level != 2 {
+            if all_checked(std::slice::from_ref(section)) {
+                take(std::slice::from_ref(section), &mut archived);
+            }
+            idx += 1;
+            continue;
+        }
+
+        let children = sections[idx + 1..]
+            .iter()
+            .take_while(|s| s.level == 3)
+            .count();
+        let group = &sections[idx..=idx + children];
+        if all_checked(group) {
+            take(group, &mut archived);
+        } else {
+            let mut header_written = false;
+            for child in &group[1..] {
+                if all_checked(std::slice::from_ref(child)) {
+                    if !header_written {
+                        archived.push_str(&format!("## {}\n\n", section.title));
+                        header_written = true;
+                    }
+                    take(std::slice::from_ref(child), &mut archived);
+                }
+            }
+        }
+        idx += children + 1;
+    }
+
+    let mut remaining = lines
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !ranges.iter().any(|(start, end)| i >= start && i < end))
+        .map(|(_, line)| *line)
+        .collect::<Vec<_>>()
+        .join("\n");
+    if content.ends_with('\n') && !remaining.is_empty() {
+        remaining.push('\n');
+    }
+
+    RoadmapArchive {
+        remaining,
+        archived,
+        sections: moved,
+    }
+}
+
+/// Prompt recorded on the goal node `roadmap start` creates for an item
+///
+/// The item text plus where it sits in the roadmap, so the goal can be picked
+/// up later without reopening ROADMAP.md.
+pub fn goal_prompt(
+    item_text: &str,
+    section: Option<&str>,
+    section_description: Option<&str>,
+    siblings: &[String],
+) -> String {
+    let mut prompt = format!("Roadmap item: {}\n", item_text);
+    if let Some(section) = section {
+        prompt.push_str(&format!("Section: {}\n", section));
+    }
+    if let Some(desc) = section_description.filter(|d| !d.trim().is_empty()) {
+        prompt.push_str(&format!("\n{}\n", desc.trim()));
+    }
+    if !siblings.is_empty() {
+        prompt.push_str("\nOther items in this section:\n");
+        for sibling in siblings {
+            prompt.push_str(&format!("- {}\n", sibling));
+        }
+    }
+    prompt
+}
+
+/// Feature branch name for a roadmap item, e.g. `roadmap/add-login-flow`
+pub fn item_branch_name(item_text: &str) -> String {
+    let mut slug = String::new();
+    for c in strip_item_annotations(item_text).chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.truncate(50);
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "roadmap/item".to_string()
+    } else {
+        format!("roadmap/{}", slug)
+    }
+}
+
+/// Project board column for a roadmap section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoardStatus {
+    Todo,
+    InProgress,
+    Done,
+}
+
+impl BoardStatus {
+    /// Status option names to look for on the board, in order of preference
+    pub fn option_names(&self) -> &'static [&'static str] {
+        match self {
+            BoardStatus::Todo => &["Todo", "To Do", "Backlog"],
+            BoardStatus::InProgress => &["In Progress", "In progress", "Doing"],
+            BoardStatus::Done => &["Done", "Complete", "Completed"],
+        }
+    }
+}
+
+impl std::fmt::Display for BoardStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.option_names()[0])
+    }
+}
+
+/// Decide which board column a section belongs in
+///
+/// Done when the issue is closed or every checkbox is ticked; In Progress
+/// once any item is checked or linked to an outcome; Todo otherwise.
+pub fn section_board_status(section: &RoadmapSection) -> BoardStatus {
+    let issue_closed = section.github_issue_state.as_deref() == Some("closed");
+    let all_checked = !section.items.is_empty() && section.items.iter().all(|i| i.checked);
+    let any_progress = section
+        .items
+        .iter()
+        .any(|i| i.checked || i.outcome_change_id.is_some());
+
+    if issue_closed || all_checked {
+        BoardStatus::Done
+    } else if any_progress {
+        BoardStatus::InProgress
+    } else {
+        BoardStatus::Todo
+    }
+}
+
+/// A level-2 section (milestone) and the level-3 sections under it
+pub type MilestoneGroup<'a> = (&'a RoadmapSection, Vec<&'a RoadmapSection>);
+
+/// Group level-3 sections under their level-2 parent (the milestone)
+///
+/// Level-3 sections before any level-2 header have no milestone and are skipped.
+pub fn milestone_groups(sections: &[RoadmapSection]) -> Vec<MilestoneGroup<'_>> {
+    let mut groups: Vec<MilestoneGroup<'_>> = Vec::new();
+
+    for section in sections {
+        match section.level {
+            2 => groups.push((section, Vec::new())),
+            3 => {
+                if let Some((_, children)) = groups.last_mut() {
+                    children.push(section);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    groups.retain(|(_, children)| !children.is_empty());
+    groups
+}
+
+/// A difference between ROADMAP.md and the roadmap rows in the database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoadmapDrift {
+    SectionAdded(String),
+    SectionRemoved(String),
+    ItemAdded {
+        section: String,
+        text: String,
+    },
+    ItemRemoved {
+        section: String,
+        text: String,
+    },
+    Checkbox {
+        section: String,
+        text: String,
+        checked: bool,
+    },
+}
+
+impl std::fmt::Display for RoadmapDrift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RoadmapDrift::SectionAdded(title) => write!(f, "+ section {}", title),
+            RoadmapDrift::SectionRemoved(title) => write!(f, "- section {}", title),
+            RoadmapDrift::ItemAdded { section, text } => write!(f, "+ {} / {}", section, text),
+            RoadmapDrift::ItemRemoved { section, text } => write!(f, "- {} / {}", section, text),
+            RoadmapDrift::Checkbox {
+                section,
+                text,
+                checked,
+            } => write!(
+                f,
+                "~ {} / [{}] {}",
+                section,
+                if *checked { "x" } else { " " },
+                text
+            ),
+        }
+    }
+}
+
+/// Compare parsed ROADMAP.md sections against the stored roadmap items
+///
+/// Archived rows are ignored, since their sections have left ROADMAP.md on
+/// purpose.
+pub fn roadmap_drift(sections: &[RoadmapSection], items: &[RoadmapItem]) -> Vec<RoadmapDrift> {
+    let active: Vec<&RoadmapItem> = items.iter().filter(|i| i.archived_at.is_none()).collect();
+    let stored_sections: Vec<&str> = active
+        .iter()
+        .filter(|i| !i.is_task())
+        .map(|i| i.title.as_str())
+        .collect();
+    let stored_tasks: HashMap<(&str, &str), bool> = active
+        .iter()
+        .filter(|i| i.is_task())
+        .map(|i| {
+            (
+                (i.section.as_deref().unwrap_or(""), i.title.as_str()),
+                i.is_checked(),
+            )
+        })
+        .collect();
+
+    let mut drift = Vec::new();
+    for section in sections {
+        if !stored_sections.contains(&section.title.as_str()) {
+            drift.push(RoadmapDrift::SectionAdded(section.title.clone()));
+        }
+        for item in &section.items {
+            match stored_tasks.get(&(section.title.as_str(), item.text.as_str())) {
+                None => drift.push(RoadmapDrift::ItemAdded {
+                    section: section.title.clone(),
+                    text: item.text.clone(),
+                }),
+                Some(checked) if *checked != item.checked => drift.push(RoadmapDrift::Checkbox {
+                    section: section.title.clone(),
+                    text: item.text.clone(),
+                    checked: item.checked,
+                }),
+                Some(_) => {}
+            }
+        }
+    }
+
+    for title in &stored_sections {
+        if !sections.iter().any(|s| s.title == *title) {
+            drift.push(RoadmapDrift::SectionRemoved(title.to_string()));
+        }
+    }
+    for item in active.iter().filter(|i| i.is_task()) {
+        let section = item.section.as_deref().unwrap_or("");
+        let in_file = sections
+            .iter()
+            .filter(|s| s.title == section)
+            .any(|s| s.items.iter().any(|i| i.text == item.title));
+        if !in_file {
+            drift.push(RoadmapDrift::ItemRemoved {
+                section: section.to_string(),
+                text: item.title.clone(),
+            });
+        }
+    }
+
+    drift
+}
+
+const ROADMAP_HOOK: HookBlock = HookBlock {
+    hook: "pre-commit",
+    what: "the roadmap check",
+    start: "# >>> deciduous roadmap hook >>>",
+    end: "# <<< deciduous roadmap hook <<<",
+};
+
+/// The pre-commit snippet that checks ROADMAP.md when it is staged
+fn hook_snippet(strict: bool) -> String {
+    format!(
+        "if git diff --cached --name-only | grep -qx 'ROADMAP.md'; then\n    deciduous roadmap hook check{} || exit 1\nfi",
+        if strict { " --strict" } else { "" },
+    )
+}
+
+/// Add the roadmap check to a pre-commit hook's content
+///
+/// Returns `None` when the check is already installed. An existing hook
+/// that isn't ours is only extended when `append` is set.
+pub fn add_hook_block(
+    existing: Option<&str>,
+    strict: bool,
+    append: bool,
+) -> std::result::Result<Option<String>, String> {
+    ROADMAP_HOOK.add(existing, &hook_snippet(strict), append)
+}
+
+/// Remove the roadmap check from a pre-commit hook's content
+///
+/// Returns `None` when the check isn't installed, and `Some("")` when
+/// nothing but the shebang would be left.
+pub fn remove_hook_block(content: &str) -> Option<String> {
+    ROADMAP_HOOK.remove(content)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -645,6 +1173,10 @@ Description here.
                     checked: false,
                     outcome_change_id: None,
                     line_number: 1,
+                    depth: 0,
+                    parent_change_id: None,
+                    priority: None,
+                    due: None,
                 },
                 RoadmapCheckItem {
                     change_id: "item-2".to_string(),
@@ -652,6 +1184,10 @@ Description here.
                     checked: true,
                     outcome_change_id: Some("outcome-123".to_string()),
                     line_number: 2,
+                    depth: 0,
+                    parent_change_id: None,
+                    priority: None,
+                    due: None,
                 },
             ],
             github_issue_number: None,
@@ -669,6 +1205,360 @@ Description here.
         assert!(body.contains("test-uuid"));
     }
 
+    fn sync_section() -> RoadmapSection {
+        let item = |text: &str, checked: bool, line_number: usize| RoadmapCheckItem {
+            change_id: format!("id-{}", line_number),
+            text: text.to_string(),
+            checked,
+            outcome_change_id: None,
+            line_number,
+            depth: 0,
+            parent_change_id: None,
+            priority: None,
+            due: None,
+        };
+        RoadmapSection {
+            change_id: "sec".to_string(),
+            title: "Sync".to_string(),
+            level: 3,
+            description: None,
+            items: vec![
+                item("Pulled", false, 3),
+                item("Pushed", true, 4),
+                item("Unknown", false, 5),
+                item("Same", true, 6),
+            ],
+            github_issue_number: Some(1),
+            github_issue_state: Some("open".to_string()),
+            line_start: 1,
+            line_end: 6,
+            content_hash: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_diff_remote_checkboxes() {
+        let body = "## Tasks\n\n- [x] Pulled\n- [ ] Pushed\n- [x] Unknown\n- [x] Same\n- [ ] Extra\n- [ ] Deleted\n";
+        let baseline: HashMap<String, bool> = [
+            ("Pulled".to_string(), false),
+            ("Pushed".to_string(), false),
+            ("Same".to_string(), true),
+            ("Deleted".to_string(), false),
+        ]
+        .into_iter()
+        .collect();
+
+        let changes = diff_remote_checkboxes(&sync_section(), body, &baseline);
+        assert_eq!(
+            changes,
+            vec![
+                RemoteChange::Checkbox {
+                    text: "Pulled".to_string(),
+                    checked: true
+                },
+                RemoteChange::Conflict {
+                    text: "Unknown".to_string(),
+                    local: false,
+                    remote: true
+                },
+                RemoteChange::RemoteOnly {
+                    text: "Extra".to_string(),
+                    checked: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set_checkbox_lines() {
+        let content = "## Phase\n- [ ] One\n  <!-- roadmap:item id=\"a\" -->\n- [x] Two\n";
+        let updated = set_checkbox_lines(content, &[(2, true), (4, false), (99, true)]);
+        assert_eq!(
+            updated,
+            "## Phase\n- [x] One\n  <!-- roadmap:item id=\"a\" -->\n- [ ] Two\n"
+        );
+    }
+
+    #[test]
+    fn test_split_completed_sections() {
+        let content = r#"# Roadmap
+
+## Done Milestone
+<!-- roadmap:section id="m1" -->
+
+### Shipped
+<!-- roadmap:section id="s1" issue="4" status="open" -->
+- [x] First
+  <!-- roadmap:item id="i1" outcome_change_id="" -->
+
+## Active Milestone
+
+### Finished Part
+- [x] Second
+
+### Open Part
+- [ ] Third
+"#;
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        let parsed = parse_roadmap(file.path()).unwrap();
+
+        let split = split_completed_sections(content, &parsed.sections);
+        let titles: Vec<&str> = split.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["Done Milestone", "Shipped", "Finished Part"]);
+
+        assert_eq!(
+            split.remaining,
+            "# Roadmap\n\n## Active Milestone\n\n### Open Part\n- [ ] Third\n"
+        );
+        assert!(split
+            .archived
+            .contains(r#"<!-- roadmap:section id="s1" issue="4" status="open" -->"#));
+        assert!(split
+            .archived
+            .contains("## Active Milestone\n\n### Finished Part\n- [x] Second\n"));
+        assert!(split.archived.contains(r#"<!-- roadmap:item id="i1""#));
+    }
+
+    #[test]
+    fn test_split_completed_sections_keeps_unfinished() {
+        let content = "## Work\n\n### Partly\n- [x] One\n- [ ] Two\n\n### Empty\nNotes only\n";
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        let parsed = parse_roadmap(file.path()).unwrap();
+
+        let split = split_completed_sections(content, &parsed.sections);
+        assert!(split.sections.is_empty());
+        assert!(split.archived.is_empty());
+        assert_eq!(split.remaining, content);
+    }
+
+    fn stored(title: &str, section: &str, state: &str) -> RoadmapItem {
+        RoadmapItem {
+            id: 0,
+            change_id: String::new(),
+            title: title.to_string(),
+            description: None,
+            section: Some(section.to_string()),
+            parent_id: None,
+            checkbox_state: state.to_string(),
+            github_issue_number: None,
+            github_issue_state: None,
+            outcome_node_id: None,
+            outcome_change_id: None,
+            markdown_line_start: None,
+            markdown_line_end: None,
+            content_hash: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+            last_synced_at: None,
+            priority: None,
+            due_date: None,
+            goal_node_id: None,
+            goal_change_id: None,
+            archived_at: None,
+        }
+    }
+
+    #[test]
+    fn test_roadmap_drift() {
+        let content = "### Auth\n- [x] Login\n- [ ] Signup\n\n### Search\n- [ ] Index\n";
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        let parsed = parse_roadmap(file.path()).unwrap();
+
+        let mut archived = stored("Old", "", "none");
+        archived.archived_at = Some("2025-01-01T00:00:00Z".to_string());
+        let items = vec![
+            stored("Auth", "", "none"),
+            stored("Login", "Auth", "unchecked"),
+            stored("Logout", "Auth", "unchecked"),
+            stored("Billing", "", "none"),
+            archived,
+        ];
+
+        let drift = roadmap_drift(&parsed.sections, &items);
+        assert_eq!(
+            drift,
+            vec![
+                RoadmapDrift::Checkbox {
+                    section: "Auth".to_string(),
+                    text: "Login".to_string(),
+                    checked: true
+                },
+                RoadmapDrift::ItemAdded {
+                    section: "Auth".to_string(),
+                    text: "Signup".to_string()
+                },
+                RoadmapDrift::SectionAdded("Search".to_string()),
+                RoadmapDrift::ItemAdded {
+                    section: "Search".to_string(),
+                    text: "Index".to_string()
+                },
+                RoadmapDrift::SectionRemoved("Billing".to_string()),
+                RoadmapDrift::ItemRemoved {
+                    section: "Auth".to_string(),
+                    text: "Logout".to_string()
+                },
+            ]
+        );
+        assert_eq!(drift[0].to_string(), "~ Auth / [x] Login");
+    }
+
+    #[test]
+    fn test_hook_block_roundtrip() {
+        let fresh = add_hook_block(None, false, false).unwrap().unwrap();
+        assert!(fresh.starts_with("#!/bin/sh\n"));
+        assert!(fresh.contains("deciduous roadmap hook check || exit 1"));
+        assert_eq!(add_hook_block(Some(&fresh), false, false).unwrap(), None);
+        assert_eq!(remove_hook_block(&fresh).unwrap(), "");
+
+        let theirs = "#!/bin/sh\ncargo fmt --check\n";
+        assert!(add_hook_block(Some(theirs), true, false).is_err());
+        let combined = add_hook_block(Some(theirs), true, true).unwrap().unwrap();
+        assert!(combined.starts_with(theirs));
+        assert!(combined.contains("hook check --strict"));
+        assert_eq!(remove_hook_block(&combined).unwrap(), theirs);
+        assert_eq!(remove_hook_block(theirs), None);
+    }
+
+    #[test]
+    fn test_goal_prompt() {
+        let prompt = goal_prompt(
+            "Add login flow",
+            Some("Auth"),
+            Some("User accounts"),
+            &["Password reset".to_string()],
+        );
+        assert_eq!(
+            prompt,
+            "Roadmap item: Add login flow\nSection: Auth\n\nUser accounts\n\nOther items in this section:\n- Password reset\n"
+        );
+        assert_eq!(goal_prompt("Solo", None, None, &[]), "Roadmap item: Solo\n");
+    }
+
+    #[test]
+    fn test_item_branch_name() {
+        assert_eq!(
+            item_branch_name("Add OAuth login (P1) (due: 2025-06)"),
+            "roadmap/add-oauth-login"
+        );
+        assert_eq!(item_branch_name("`--sort` flag!"), "roadmap/sort-flag");
+        assert_eq!(item_branch_name("???"), "roadmap/item");
+        assert!(item_branch_name(&"x ".repeat(60)).len() <= "roadmap/".len() + 50);
+    }
+
+    #[test]
+    fn test_parse_item_annotations() {
+        assert_eq!(
+            parse_item_annotations("Ship login (P1) (due: 2025-06)"),
+            (Some(1), Some("2025-06".to_string()))
+        );
+        assert_eq!(
+            parse_item_annotations("Docs (p3) (Due: 2025-06-30)"),
+            (Some(3), Some("2025-06-30".to_string()))
+        );
+        assert_eq!(parse_item_annotations("Plain item"), (None, None));
+        assert_eq!(
+            strip_item_annotations("Ship (P1) login (due: 2025-06)"),
+            "Ship login"
+        );
+        assert_eq!(
+            parse_item_annotations("Fix HTTP2 (P) (due: soon)"),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn test_issue_body_schedule() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "### Auth\n- [ ] Login (P2) (due: 2025-07)\n- [ ] Logout (P1)\n- [ ] Signup (due: 2025-06)"
+        )
+        .unwrap();
+        let parsed = parse_roadmap(file.path()).unwrap();
+        let items = &parsed.sections[0].items;
+        assert_eq!(items[0].priority, Some(2));
+        assert_eq!(items[0].due.as_deref(), Some("2025-07"));
+        assert_eq!(items[0].text, "Login (P2) (due: 2025-07)");
+
+        let body = generate_issue_body(&parsed.sections[0]);
+        assert!(body.contains("**Priority:** P1 · **Due:** 2025-06"));
+    }
+
+    #[test]
+    fn test_check_items_in_body() {
+        let body = "## Tasks\n\n- [ ] Login\n  - [ ] Logout\n- [ ] Signup\n";
+        let updated = check_items_in_body(body, &["Login".to_string(), "Logout".to_string()]);
+        assert_eq!(
+            updated,
+            "## Tasks\n\n- [x] Login\n  - [x] Logout\n- [ ] Signup\n"
+        );
+    }
+
+    #[test]
+    fn test_item_line_number_skips_metadata() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "### Phase\n- [ ] One\n  <!-- roadmap:item id=\"a\" outcome_change_id=\"\" -->\n- [ ] Two"
+        )
+        .unwrap();
+        let parsed = parse_roadmap(file.path()).unwrap();
+        let lines: Vec<usize> = parsed.sections[0]
+            .items
+            .iter()
+            .map(|i| i.line_number)
+            .collect();
+        assert_eq!(lines, vec![2, 4]);
+    }
+
+    #[test]
+    fn test_parse_roadmap_nested_items() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "### Phase\n- [ ] Parent\n  - [x] Child\n    - [ ] Grandchild\n  - [ ] Second child\n- [ ] Sibling"
+        )
+        .unwrap();
+        let parsed = parse_roadmap(file.path()).unwrap();
+        let items = &parsed.sections[0].items;
+
+        let depths: Vec<usize> = items.iter().map(|i| i.depth).collect();
+        assert_eq!(depths, vec![0, 1, 2, 1, 0]);
+        assert!(items[1].checked);
+        assert_eq!(items[1].parent_change_id, Some(items[0].change_id.clone()));
+        assert_eq!(items[2].parent_change_id, Some(items[1].change_id.clone()));
+        assert_eq!(items[3].parent_change_id, Some(items[0].change_id.clone()));
+        assert_eq!(items[4].parent_change_id, None);
+
+        let body = generate_issue_body(&parsed.sections[0]);
+        assert!(body.contains("- [ ] Parent\n  - [x] Child\n    - [ ] Grandchild\n"));
+    }
+
+    #[test]
+    fn test_write_metadata_keeps_nested_indent() {
+        let mut file = NamedTempFile::new().unwrap();
+        let content = "### Phase\n- [ ] Parent\n  - [ ] Child";
+        write!(file, "{}", content).unwrap();
+        let parsed = parse_roadmap(file.path()).unwrap();
+
+        let written = write_roadmap_with_metadata(file.path(), &parsed.sections, content).unwrap();
+        let child_meta = written
+            .lines()
+            .skip_while(|l| !l.contains("Child"))
+            .nth(1)
+            .unwrap();
+        assert!(child_meta.starts_with("    <!-- roadmap:item"));
+
+        let reparsed = parse_roadmap(file.path()).unwrap();
+        let items = &reparsed.sections[0].items;
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].parent_change_id, Some(items[0].change_id.clone()));
+        assert_eq!(items[1].change_id, parsed.sections[0].items[1].change_id);
+    }
+
     #[test]
     fn test_parse_issue_body_checkboxes() {
         let body = r#"Some description.
@@ -689,4 +1579,78 @@ More text.
         assert_eq!(items[1], ("Checked task".to_string(), true));
         assert_eq!(items[2], ("Also checked (uppercase)".to_string(), true));
     }
+
+    fn board_section(items: &[(bool, bool)], issue_state: Option<&str>) -> RoadmapSection {
+        RoadmapSection {
+            change_id: "s".to_string(),
+            title: "Section".to_string(),
+            level: 3,
+            description: None,
+            items: items
+                .iter()
+                .enumerate()
+                .map(|(i, (checked, linked))| RoadmapCheckItem {
+                    change_id: format!("i{}", i),
+                    text: format!("Item {}", i),
+                    checked: *checked,
+                    outcome_change_id: linked.then(|| "outcome".to_string()),
+                    line_number: i,
+                    depth: 0,
+                    parent_change_id: None,
+                    priority: None,
+                    due: None,
+                })
+                .collect(),
+            github_issue_number: Some(1),
+            github_issue_state: issue_state.map(|s| s.to_string()),
+            line_start: 0,
+            line_end: 0,
+            content_hash: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_section_board_status() {
+        let todo = board_section(&[(false, false), (false, false)], Some("open"));
+        assert_eq!(section_board_status(&todo), BoardStatus::Todo);
+
+        let linked = board_section(&[(false, true), (false, false)], Some("open"));
+        assert_eq!(section_board_status(&linked), BoardStatus::InProgress);
+
+        let partial = board_section(&[(true, false), (false, false)], Some("open"));
+        assert_eq!(section_board_status(&partial), BoardStatus::InProgress);
+
+        let checked = board_section(&[(true, false), (true, false)], Some("open"));
+        assert_eq!(section_board_status(&checked), BoardStatus::Done);
+
+        let closed = board_section(&[(false, false)], Some("closed"));
+        assert_eq!(section_board_status(&closed), BoardStatus::Done);
+
+        let empty = board_section(&[], None);
+        assert_eq!(section_board_status(&empty), BoardStatus::Todo);
+    }
+
+    #[test]
+    fn test_milestone_groups() {
+        let mut orphan = board_section(&[], None);
+        orphan.title = "Orphan".to_string();
+        let mut v1 = board_section(&[], None);
+        v1.level = 2;
+        v1.title = "v1.0".to_string();
+        let mut a = board_section(&[], None);
+        a.title = "A".to_string();
+        let mut b = board_section(&[], None);
+        b.title = "B".to_string();
+        let mut empty = board_section(&[], None);
+        empty.level = 2;
+        empty.title = "Empty".to_string();
+
+        let sections = vec![orphan, v1, a, b, empty];
+        let groups = milestone_groups(&sections);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].0.title, "v1.0");
+        let titles: Vec<&str> = groups[0].1.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["A", "B"]);
+    }
 }
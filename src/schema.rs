@@ -38,6 +38,52 @@ diesel::table! {
     }
 }
 
+// Deleted nodes, so patches don't bring them back
+diesel::table! {
+    node_tombstones (change_id) {
+        change_id -> Text,
+        deleted_at -> Text,
+    }
+}
+
+// Fields where an applied patch disagreed with the local node
+diesel::table! {
+    node_conflicts (id) {
+        id -> Integer,
+        change_id -> Text,
+        field -> Text,
+        local_value -> Nullable<Text>,
+        remote_value -> Nullable<Text>,
+        remote_updated_at -> Text,
+        patch_author -> Nullable<Text>,
+        resolution -> Nullable<Text>,
+        detected_at -> Text,
+        resolved_at -> Nullable<Text>,
+    }
+}
+
+// Nodes written to patch files, so `diff export --new` can skip them
+diesel::table! {
+    node_exports (id) {
+        id -> Integer,
+        change_id -> Text,
+        patch_path -> Text,
+        branch -> Nullable<Text>,
+        exported_at -> Text,
+    }
+}
+
+diesel::table! {
+    applied_patches (id) {
+        id -> Integer,
+        patch_path -> Text,
+        content_hash -> Text,
+        commit_sha -> Nullable<Text>,
+        applied_at -> Text,
+        author -> Nullable<Text>,
+    }
+}
+
 diesel::table! {
     decision_context (id) {
         id -> Integer,
@@ -83,6 +129,19 @@ diesel::table! {
     }
 }
 
+// Nodes and edges each logged command created, changed or deleted
+diesel::table! {
+    command_changes (id) {
+        id -> Integer,
+        command_id -> Integer,           // FK to command_log.id
+        kind -> Text,                    // "node" or "edge"
+        target_id -> Integer,            // Node or edge ID
+        action -> Text,                  // "created", "updated" or "deleted"
+        detail -> Text,                  // Title, edge endpoints or changed fields
+        created_at -> Text,
+    }
+}
+
 // ============================================================================
 // Roadmap Board Tables
 // ============================================================================
@@ -106,6 +165,20 @@ diesel::table! {
         created_at -> Text,
         updated_at -> Text,
         last_synced_at -> Nullable<Text>,
+        priority -> Nullable<Integer>,
+        due_date -> Nullable<Text>,
+        goal_node_id -> Nullable<Integer>,
+        goal_change_id -> Nullable<Text>,
+        archived_at -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    roadmap_item_outcomes (roadmap_item_id, outcome_node_id) {
+        roadmap_item_id -> Integer,      // FK to roadmap_items.id
+        outcome_node_id -> Integer,      // FK to decision_nodes.id
+        outcome_change_id -> Text,
+        created_at -> Text,
     }
 }
 
@@ -153,6 +226,23 @@ diesel::table! {
     }
 }
 
+// ============================================================================
+// GitHub PR Cache - Pull request state for nodes linked with `--pr`
+// ============================================================================
+
+diesel::table! {
+    github_pr_cache (id) {
+        id -> Integer,
+        pr_number -> Integer,
+        repo -> Text,
+        title -> Text,
+        state -> Text,                   // 'open', 'closed', 'merged'
+        head_branch -> Text,
+        html_url -> Text,
+        cached_at -> Text,
+    }
+}
+
 // ============================================================================
 // Claude Trace Tables - API traffic capture for decision graph correlation
 // ============================================================================
@@ -216,6 +306,106 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    trace_redactions (id) {
+        id -> Integer,
+        span_id -> Integer,              // FK to trace_spans.id
+        content_type -> Text,            // Content type (or preview field) the match was in
+        rule -> Text,                    // Redaction rule name, e.g. 'anthropic_key', 'env:GH_TOKEN'
+        count -> Integer,
+    }
+}
+
+// Subagent hierarchy: spans made by a subagent, pointing at the span that
+// started it
+diesel::table! {
+    span_parents (span_id) {
+        span_id -> Integer,              // FK to trace_spans.id (the subagent's span)
+        parent_span_id -> Integer,       // FK to trace_spans.id (the span that called Task)
+        tool_use_id -> Nullable<Text>,   // The Task call that started the subagent
+    }
+}
+
+// How a span's response streamed in
+diesel::table! {
+    span_streams (span_id) {
+        span_id -> Integer,              // FK to trace_spans.id
+        chunk_count -> Integer,          // SSE events received
+        first_token_ms -> Nullable<Integer>, // Request sent → first output event
+        complete -> Bool,                // Whether the stream reached its end event
+    }
+}
+
+// Reviewer notes on spans
+diesel::table! {
+    span_annotations (id) {
+        id -> Integer,
+        span_id -> Integer,              // FK to trace_spans.id
+        note -> Text,
+        created_at -> Text,
+    }
+}
+
+// Commits made by a span's tool calls
+diesel::table! {
+    span_commits (span_id, commit_hash) {
+        span_id -> Integer,              // FK to trace_spans.id
+        commit_hash -> Text,
+        committed_at -> Text,            // Committer date
+        created_at -> Text,              // When the link was recorded
+    }
+}
+
+// Node text embeddings for semantic search, one per node
+diesel::table! {
+    node_embeddings (node_id) {
+        node_id -> Integer,              // FK to decision_nodes.id
+        model -> Text,                   // Embedding model that produced the vector
+        content_hash -> Text,            // SHA-256 of the embedded text
+        vector -> Binary,                // Little-endian f32s
+        created_at -> Text,
+    }
+}
+
+// Other projects' databases queried alongside this one
+diesel::table! {
+    federated_repos (name) {
+        name -> Text,                    // Namespace for the repo's change_ids
+        db_path -> Text,                 // Absolute path to its deciduous.db
+        added_at -> Text,
+    }
+}
+
+// Labeled copies of the graph, for `deciduous snapshot diff`
+diesel::table! {
+    snapshots (id) {
+        id -> Integer,
+        label -> Text,
+        created_at -> Text,
+        patch_json -> Text,              // The graph as a GraphPatch (nodes and edges by change_id)
+    }
+}
+
+// Which nodes touched which files, for `deciduous why <file>`
+diesel::table! {
+    node_files (node_id, path) {
+        node_id -> Integer,              // FK to decision_nodes.id
+        path -> Text,                    // Repo-relative file path
+        source -> Text,                  // "files", "commit" or "files, commit"
+        node_updated_at -> Text,         // The node's updated_at when indexed
+    }
+}
+
+// The goal `deciduous focus` links new work to (at most one row, id 1)
+diesel::table! {
+    focus (id) {
+        id -> Integer,
+        goal_id -> Integer,              // FK to decision_nodes.id
+        started_at -> Text,
+        expires_at -> Nullable<Text>,    // End of the time box, if any
+    }
+}
+
 // ============================================================================
 // Span-Node Linking - Tracks which decision nodes were created during which spans
 // ============================================================================
@@ -1,8 +1,15 @@
 //! HTTP server for decision graph viewer
 //!
 //! `deciduous serve` → starts server, opens browser, shows graph
-
-use crate::db::{Database, DecisionGraph, RoadmapItem};
+//! `deciduous serve --sync` → also acts as a patch hub (see `hub`)
+//! `deciduous serve --federated` → includes registered repos (see `federation`)
+
+use crate::config::Config;
+use crate::db::{Database, DecisionGraph, GraphPage, RoadmapItem};
+use crate::forge;
+use crate::hub;
+use crate::mcp_trace;
+use crate::webhook;
 use serde::Serialize;
 use tiny_http::{Header, Method, Request, Response, Server};
 
@@ -27,9 +34,29 @@ impl<T: Serialize> ApiResponse<T> {
 // To rebuild: cd web && ./build-embed.sh
 const GRAPH_VIEWER_HTML: &str = include_str!("viewer.html");
 
+/// Most nodes `/api/graph?limit=` returns in one page
+const MAX_PAGE: usize = 5000;
+
+/// Which graph `/api/graph` serves
+#[derive(Debug, Clone, Default)]
+pub struct GraphScope {
+    /// Append the graphs of repos added with `deciduous federate`
+    pub federated: bool,
+    /// Only this sub-project's nodes (see `project`)
+    pub project: Option<String>,
+}
+
 /// Start the decision graph viewer server
-pub fn start_graph_server(port: u16) -> std::io::Result<()> {
-    let addr = format!("127.0.0.1:{}", port);
+///
+/// With `sync`, the patch hub endpoints are served too. `scope` picks the
+/// graph the viewer shows.
+pub fn start_graph_server(
+    host: &str,
+    port: u16,
+    sync: bool,
+    scope: GraphScope,
+) -> std::io::Result<()> {
+    let addr = format!("{}:{}", host, port);
     let server = Server::http(&addr)
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
 
@@ -37,11 +64,26 @@ pub fn start_graph_server(port: u16) -> std::io::Result<()> {
 
     eprintln!("\n\x1b[1;32m🌳 Deciduous\x1b[0m");
     eprintln!("   Graph viewer: {}", url);
+    if sync {
+        eprintln!("   Patch hub: {}{}", url, hub::PULL_PATH);
+        if hub::sync_token(&Config::load()).is_none() {
+            eprintln!(
+                "   No sync token set: anyone who can reach the hub can push and pull (set {})",
+                hub::SYNC_TOKEN_ENV
+            );
+        }
+    }
+    if scope.federated {
+        eprintln!("   Federated: including repos from `deciduous federate list`");
+    }
+    if let Some(project) = &scope.project {
+        eprintln!("   Project: {}", project);
+    }
     eprintln!("   Press Ctrl+C to stop\n");
 
     // Handle requests
     for request in server.incoming_requests() {
-        if let Err(e) = handle_request(request) {
+        if let Err(e) = handle_request(request, sync, &scope) {
             eprintln!("Error: {}", e);
         }
     }
@@ -49,7 +91,7 @@ pub fn start_graph_server(port: u16) -> std::io::Result<()> {
     Ok(())
 }
 
-fn handle_request(request: Request) -> std::io::Result<()> {
+fn handle_request(request: Request, sync: bool, scope: &GraphScope) -> std::io::Result<()> {
     let url = request.url().to_string();
     let path = url.split('?').next().unwrap_or("/");
     let method = request.method().clone();
@@ -62,10 +104,22 @@ fn handle_request(request: Request) -> std::io::Result<()> {
             request.respond(response)
         }
 
-        // API: Get decision graph
+        // API: Get decision graph, or one page of it with ?limit=N[&after=ID]
         (&Method::Get, "/api/graph") => {
-            let graph = get_decision_graph();
-            let json = serde_json::to_string(&ApiResponse::success(graph))?;
+            crate::cache::refresh_expired_in_background(&Config::load());
+            let json = match query_param(&url, "limit").and_then(|l| l.parse().ok()) {
+                Some(limit) => {
+                    let after = query_param(&url, "after").and_then(|a| a.parse().ok());
+                    match get_graph_page(after, limit, scope) {
+                        Ok(page) => serde_json::to_string(&ApiResponse::success(page))?,
+                        Err(e) => return respond_json_error(request, 500, e),
+                    }
+                }
+                None => match get_decision_graph(scope) {
+                    Ok(graph) => serde_json::to_string(&ApiResponse::success(graph))?,
+                    Err(e) => return respond_json_error(request, 500, e),
+                },
+            };
 
             let response = Response::from_string(json).with_header(
                 Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap(),
@@ -86,6 +140,7 @@ fn handle_request(request: Request) -> std::io::Result<()> {
 
         // API: Get roadmap items
         (&Method::Get, "/api/roadmap") => {
+            crate::cache::refresh_expired_in_background(&Config::load());
             let items = get_roadmap_items();
             let json = serde_json::to_string(&ApiResponse::success(items))?;
 
@@ -98,6 +153,13 @@ fn handle_request(request: Request) -> std::io::Result<()> {
         // API: Toggle roadmap item checkbox (POST /api/roadmap/checkbox)
         (&Method::Post, "/api/roadmap/checkbox") => handle_toggle_checkbox(request),
 
+        // Webhook: GitHub issue events (POST /api/webhooks/github)
+        (&Method::Post, "/api/webhooks/github") => handle_github_webhook(request),
+
+        // Patch hub (serve --sync)
+        (&Method::Post, hub::PUSH_PATH) if sync => handle_sync_push(request),
+        (&Method::Get, hub::PULL_PATH) if sync => handle_sync_pull(request),
+
         // API: Get traces linked to a node
         (&Method::Get, p) if p.starts_with("/api/nodes/") && p.ends_with("/traces") => {
             // Parse /api/nodes/{node_id}/traces
@@ -190,28 +252,87 @@ fn handle_request(request: Request) -> std::io::Result<()> {
     }
 }
 
-fn get_decision_graph() -> DecisionGraph {
+/// The graph in `scope`; only a broken federated repo is an error
+fn get_decision_graph(scope: &GraphScope) -> Result<DecisionGraph, String> {
     // Load config for external repo support
     let config = crate::config::Config::load();
     let include_config = config.github.commit_repo.is_some();
     let config_opt = if include_config { Some(config) } else { None };
 
-    match Database::open() {
-        Ok(db) => db
-            .get_graph_with_config(config_opt.clone())
-            .unwrap_or_else(|_| DecisionGraph {
+    let (db, graph) = match Database::open() {
+        Ok(db) => {
+            let db = db.with_project_scope(scope.project.clone());
+            let graph = db
+                .get_graph_with_config(config_opt.clone())
+                .unwrap_or_else(|_| DecisionGraph {
+                    nodes: vec![],
+                    edges: vec![],
+                    config: config_opt.clone(),
+                    pull_requests: vec![],
+                });
+            (db, graph)
+        }
+        Err(_) => {
+            return Ok(DecisionGraph {
                 nodes: vec![],
                 edges: vec![],
-                config: config_opt.clone(),
-            }),
-        Err(_) => DecisionGraph {
-            nodes: vec![],
-            edges: vec![],
-            config: config_opt,
-        },
+                config: config_opt,
+                pull_requests: vec![],
+            })
+        }
+    };
+    if scope.federated {
+        crate::federation::federate(&db, graph)
+    } else {
+        Ok(graph)
     }
 }
 
+/// One page of the graph; a federated graph is always sent as one page
+fn get_graph_page(
+    after: Option<i32>,
+    limit: usize,
+    scope: &GraphScope,
+) -> Result<GraphPage, String> {
+    if scope.federated {
+        let graph = match after {
+            None => get_decision_graph(scope)?,
+            Some(_) => DecisionGraph {
+                nodes: vec![],
+                edges: vec![],
+                config: None,
+                pull_requests: vec![],
+            },
+        };
+        return Ok(GraphPage {
+            nodes: graph.nodes,
+            edges: graph.edges,
+            config: graph.config,
+            pull_requests: graph.pull_requests,
+            next: None,
+        });
+    }
+    let db = Database::open()
+        .map_err(|e| format!("Database error: {}", e))?
+        .with_project_scope(scope.project.clone());
+    let mut page = db
+        .get_graph_page(after, limit.min(MAX_PAGE))
+        .map_err(|e| e.to_string())?;
+    if after.is_none() {
+        let config = Config::load();
+        page.config = config.github.commit_repo.is_some().then_some(config);
+    }
+    Ok(page)
+}
+
+/// The value of `key` in the URL's query string
+fn query_param<'a>(url: &'a str, key: &str) -> Option<&'a str> {
+    url.split_once('?')?
+        .1
+        .split('&')
+        .find_map(|pair| pair.strip_prefix(key)?.strip_prefix('='))
+}
+
 fn get_command_log() -> Vec<crate::db::CommandLog> {
     match Database::open() {
         Ok(db) => db.get_recent_commands(100).unwrap_or_default(),
@@ -295,6 +416,7 @@ struct SpanWithNodeCount {
     #[serde(flatten)]
     span: crate::db::TraceSpan,
     node_count: i64,
+    annotations: Vec<crate::db::SpanAnnotation>,
 }
 
 fn get_trace_spans(session_id: &str) -> Vec<SpanWithNodeCount> {
@@ -303,14 +425,16 @@ fn get_trace_spans(session_id: &str) -> Vec<SpanWithNodeCount> {
             let spans = db.get_trace_spans(session_id).unwrap_or_default();
             let span_ids: Vec<i32> = spans.iter().map(|s| s.id).collect();
             let node_counts = db.get_node_counts_for_spans(&span_ids).unwrap_or_default();
+            let mut annotations = db.get_annotations_for_spans(&span_ids).unwrap_or_default();
 
             spans
                 .into_iter()
                 .map(|span| {
                     let count = node_counts.get(&span.id).copied().unwrap_or(0);
                     SpanWithNodeCount {
-                        span,
                         node_count: count,
+                        annotations: annotations.remove(&span.id).unwrap_or_default(),
+                        span,
                     }
                 })
                 .collect()
@@ -337,6 +461,8 @@ fn get_span_nodes(span_id: i32) -> Vec<crate::db::DecisionNode> {
 #[derive(serde::Serialize)]
 struct NodeTraceInfo {
     spans: Vec<SpanWithSession>,
+    /// MCP tools (`server/tool`) consulted in those spans
+    mcp_tools: Vec<String>,
 }
 
 #[derive(serde::Serialize)]
@@ -358,6 +484,14 @@ fn get_node_trace_info(node_id: i32) -> NodeTraceInfo {
     match Database::open() {
         Ok(db) => {
             let spans = db.get_spans_for_node(node_id).unwrap_or_default();
+            let mut mcp_tools: Vec<String> = spans
+                .iter()
+                .flat_map(|s| db.get_trace_content_by_type(s.id, mcp_trace::MCP_CALL))
+                .flatten()
+                .filter_map(|c| c.tool_name)
+                .collect();
+            mcp_tools.sort();
+            mcp_tools.dedup();
             let spans_with_session: Vec<SpanWithSession> = spans
                 .into_iter()
                 .map(|s| SpanWithSession {
@@ -375,9 +509,13 @@ fn get_node_trace_info(node_id: i32) -> NodeTraceInfo {
                 .collect();
             NodeTraceInfo {
                 spans: spans_with_session,
+                mcp_tools,
             }
         }
-        Err(_) => NodeTraceInfo { spans: vec![] },
+        Err(_) => NodeTraceInfo {
+            spans: vec![],
+            mcp_tools: vec![],
+        },
     }
 }
 
@@ -446,10 +584,165 @@ fn handle_toggle_checkbox(mut request: Request) -> std::io::Result<()> {
     request.respond(response)
 }
 
+fn header_value(request: &Request, name: &str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.as_str().as_str().eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_str().to_string())
+}
+
+fn respond_json_error(request: Request, status: u16, message: String) -> std::io::Result<()> {
+    let json = serde_json::to_string(&ApiResponse::<()> {
+        ok: false,
+        data: None,
+        error: Some(message),
+    })?;
+    let response = Response::from_string(json)
+        .with_status_code(status)
+        .with_header(Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap());
+    request.respond(response)
+}
+
+/// The repository webhooks are accepted from: the one `roadmap sync` last
+/// used, else the `origin` remote
+fn webhook_repo(db: &Database) -> Option<String> {
+    db.get_roadmap_sync_state("ROADMAP.md")
+        .ok()
+        .flatten()
+        .and_then(|state| state.github_repo)
+        .or_else(|| forge::git_remote_url().and_then(|url| forge::parse_repo_from_remote(&url)))
+}
+
+fn handle_github_webhook(mut request: Request) -> std::io::Result<()> {
+    let Some(secret) = webhook::webhook_secret(&Config::load()) else {
+        return respond_json_error(
+            request,
+            403,
+            format!(
+                "Webhook secret not configured (set {} or github.webhook_secret)",
+                webhook::WEBHOOK_SECRET_ENV
+            ),
+        );
+    };
+
+    let mut body = Vec::new();
+    if let Err(e) = request.as_reader().read_to_end(&mut body) {
+        return respond_json_error(request, 400, format!("Failed to read body: {}", e));
+    }
+
+    let signature = header_value(&request, "X-Hub-Signature-256").unwrap_or_default();
+    if !webhook::verify_signature(&secret, &body, &signature) {
+        return respond_json_error(request, 401, "Invalid webhook signature".to_string());
+    }
+
+    let db = match Database::open() {
+        Ok(db) => db,
+        Err(e) => return respond_json_error(request, 500, format!("Database error: {}", e)),
+    };
+    let Some(repo) = webhook_repo(&db) else {
+        return respond_json_error(
+            request,
+            403,
+            "Repository unknown (run roadmap sync or add an origin remote)".to_string(),
+        );
+    };
+
+    let event = header_value(&request, "X-GitHub-Event").unwrap_or_default();
+    let result = webhook::apply_event(&db, &repo, &event, &body);
+
+    match result {
+        Ok(outcome) => {
+            if let Some(number) = outcome.issue {
+                eprintln!(
+                    "   webhook: issue #{} {} ({} roadmap item(s) updated)",
+                    number,
+                    outcome.action.as_deref().unwrap_or("updated"),
+                    outcome.roadmap_items_updated
+                );
+            }
+            let json = serde_json::to_string(&ApiResponse::success(outcome))?;
+            let response = Response::from_string(json).with_header(
+                Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap(),
+            );
+            request.respond(response)
+        }
+        Err(e) => respond_json_error(request, 400, e),
+    }
+}
+
+/// Check the hub token, answering 401 when it's missing or wrong
+fn sync_authorized(request: Request) -> std::io::Result<Option<Request>> {
+    let token = hub::sync_token(&Config::load());
+    let header = header_value(&request, "Authorization");
+    if hub::authorized(token.as_deref(), header.as_deref()) {
+        Ok(Some(request))
+    } else {
+        respond_json_error(request, 401, "Invalid or missing sync token".to_string())?;
+        Ok(None)
+    }
+}
+
+fn handle_sync_push(request: Request) -> std::io::Result<()> {
+    let Some(mut request) = sync_authorized(request)? else {
+        return Ok(());
+    };
+    let mut body = Vec::new();
+    if let Err(e) = request.as_reader().read_to_end(&mut body) {
+        return respond_json_error(request, 400, format!("Failed to read body: {}", e));
+    }
+
+    let result = Database::open()
+        .map_err(|e| (500, format!("Database error: {}", e)))
+        .and_then(|db| hub::receive_patch(&db, &body, &Config::load().sync_with_team()));
+    match result {
+        Ok(applied) => {
+            eprintln!(
+                "   sync: {} node(s), {} edge(s) added, {} updated",
+                applied.nodes_added, applied.edges_added, applied.nodes_updated
+            );
+            let json = serde_json::to_string(&ApiResponse::success(applied))?;
+            let response = Response::from_string(json).with_header(
+                Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap(),
+            );
+            request.respond(response)
+        }
+        Err((status, e)) => respond_json_error(request, status, e),
+    }
+}
+
+fn handle_sync_pull(request: Request) -> std::io::Result<()> {
+    let Some(request) = sync_authorized(request)? else {
+        return Ok(());
+    };
+    let result = Database::open()
+        .map_err(|e| format!("Database error: {}", e))
+        .and_then(|db| hub::hub_patch(&db));
+    match result {
+        Ok(patch) => {
+            let json = serde_json::to_string(&ApiResponse::success(patch))?;
+            let response = Response::from_string(json).with_header(
+                Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap(),
+            );
+            request.respond(response)
+        }
+        Err(e) => respond_json_error(request, 500, e),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_query_param() {
+        let url = "/api/graph?after=12&limit=500";
+        assert_eq!(query_param(url, "limit"), Some("500"));
+        assert_eq!(query_param(url, "after"), Some("12"));
+        assert_eq!(query_param("/api/graph?afterward=1", "after"), None);
+        assert_eq!(query_param("/api/graph", "limit"), None);
+    }
+
     // === ApiResponse Tests ===
 
     #[test]
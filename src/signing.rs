@@ -0,0 +1,316 @@
+//! Ed25519 signatures for patch files
+//!
+//! `diff export --sign` signs a patch with the user's key, kept outside the
+//! repository in `~/.deciduous/signing-key.pk8`. The signature covers the
+//! patch's JSON with the `signature` field left out and carries the signer's
+//! public key, which receivers look up in `[sync] trusted_keys`.
+//!
+//! Keys and the signature check itself need the `signing` feature (in
+//! `cli`). Without it a signed patch is reported as `Unchecked` and treated
+//! like an unsigned one.
+
+use crate::config::{Config, SyncConfig};
+use crate::diff::GraphPatch;
+#[cfg(feature = "signing")]
+use ring::rand::SystemRandom;
+#[cfg(feature = "signing")]
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "signing")]
+use std::path::Path;
+use std::path::PathBuf;
+
+/// The only algorithm patches are signed with
+pub const SIGNATURE_ALGORITHM: &str = "ed25519";
+
+/// A patch's signature
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatchSignature {
+    pub algorithm: String,
+    /// Signer's public key, hex
+    pub public_key: String,
+    /// Signature over the unsigned patch JSON, hex
+    pub signature: String,
+}
+
+/// Where a patch came from, according to its signature
+#[derive(Debug, Clone, PartialEq)]
+pub enum Provenance {
+    Unsigned,
+    /// Signed by a trusted key; holds the name it's listed under
+    Trusted(String),
+    /// Validly signed by a key that isn't trusted; holds the key
+    Untrusted(String),
+    /// The signature doesn't match the patch; holds the reason
+    Invalid(String),
+    /// Signed, but built without the `signing` feature to check it
+    Unchecked,
+}
+
+impl Provenance {
+    /// Whether the patch may be applied
+    ///
+    /// Invalid signatures are always refused; unsigned and untrusted patches
+    /// only when `require_signatures` is set.
+    pub fn acceptable(&self, config: &SyncConfig) -> bool {
+        match self {
+            Provenance::Trusted(_) => true,
+            Provenance::Unsigned | Provenance::Untrusted(_) | Provenance::Unchecked => {
+                !config.require_signatures
+            }
+            Provenance::Invalid(_) => false,
+        }
+    }
+
+    /// One-line description for apply and validate output
+    pub fn describe(&self) -> String {
+        match self {
+            Provenance::Unsigned => "unsigned".to_string(),
+            Provenance::Trusted(name) => format!("signed by {}", name),
+            Provenance::Untrusted(key) => format!("signed by untrusted key {}", key),
+            Provenance::Invalid(reason) => format!("invalid signature ({})", reason),
+            Provenance::Unchecked => "signed (not checked in this build)".to_string(),
+        }
+    }
+}
+
+/// Default location of the signing key: `~/.deciduous/signing-key.pk8`
+pub fn default_key_path() -> Result<PathBuf, String> {
+    let home = std::env::var("HOME").map_err(|_| "HOME environment variable not set")?;
+    Ok(PathBuf::from(home)
+        .join(".deciduous")
+        .join("signing-key.pk8"))
+}
+
+/// The signing key to use: `[identity] key` (`~/` expanded), else the default
+pub fn key_path(config: &Config) -> Result<PathBuf, String> {
+    let configured = config
+        .identity
+        .key
+        .as_deref()
+        .map(str::trim)
+        .filter(|k| !k.is_empty());
+    match configured {
+        Some(path) => match path.strip_prefix("~/") {
+            Some(rest) => {
+                let home =
+                    std::env::var("HOME").map_err(|_| "HOME environment variable not set")?;
+                Ok(PathBuf::from(home).join(rest))
+            }
+            None => Ok(PathBuf::from(path)),
+        },
+        None => default_key_path(),
+    }
+}
+
+/// A user's patch signing key
+#[cfg(feature = "signing")]
+pub struct SigningKey {
+    pair: Ed25519KeyPair,
+}
+
+#[cfg(feature = "signing")]
+impl SigningKey {
+    /// Load the key from `path` (PKCS#8), generating it on first use
+    pub fn load_or_create(path: &Path) -> Result<Self, String> {
+        if path.exists() {
+            let bytes =
+                std::fs::read(path).map_err(|e| format!("Failed to read signing key: {}", e))?;
+            let pair = Ed25519KeyPair::from_pkcs8(&bytes)
+                .map_err(|e| format!("Invalid signing key {}: {}", path.display(), e))?;
+            return Ok(Self { pair });
+        }
+
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map_err(|_| "Failed to generate signing key")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+        std::fs::write(path, pkcs8.as_ref())
+            .map_err(|e| format!("Failed to write signing key: {}", e))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+                .map_err(|e| format!("Failed to protect signing key: {}", e))?;
+        }
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref())
+            .map_err(|e| format!("Invalid signing key: {}", e))?;
+        Ok(Self { pair })
+    }
+
+    /// Public key to share with teammates for `trusted_keys`
+    pub fn public_key_hex(&self) -> String {
+        to_hex(self.pair.public_key().as_ref())
+    }
+
+    /// Sign a patch, replacing any existing signature
+    pub fn sign(&self, patch: &mut GraphPatch) -> Result<(), String> {
+        patch.signature = None;
+        let signature = self.pair.sign(&signing_payload(patch)?);
+        patch.signature = Some(PatchSignature {
+            algorithm: SIGNATURE_ALGORITHM.to_string(),
+            public_key: self.public_key_hex(),
+            signature: to_hex(signature.as_ref()),
+        });
+        Ok(())
+    }
+}
+
+/// The bytes a signature covers: the patch's JSON without its signature
+#[cfg(feature = "signing")]
+fn signing_payload(patch: &GraphPatch) -> Result<Vec<u8>, String> {
+    let mut unsigned = patch.clone();
+    unsigned.signature = None;
+    serde_json::to_vec(&unsigned).map_err(|e| format!("Failed to serialize patch: {}", e))
+}
+
+/// Check a patch's signature against the trusted keys
+pub fn verify(patch: &GraphPatch, config: &SyncConfig) -> Provenance {
+    let Some(sig) = &patch.signature else {
+        return Provenance::Unsigned;
+    };
+    if sig.algorithm != SIGNATURE_ALGORITHM {
+        return Provenance::Invalid(format!("unsupported algorithm '{}'", sig.algorithm));
+    }
+    #[cfg(not(feature = "signing"))]
+    {
+        let _ = config;
+        Provenance::Unchecked
+    }
+    #[cfg(feature = "signing")]
+    {
+        if let Err(reason) = check(patch, sig) {
+            return Provenance::Invalid(reason);
+        }
+        let trusted = config
+            .trusted_keys
+            .iter()
+            .find(|(_, k)| k.trim().eq_ignore_ascii_case(&sig.public_key));
+        match trusted {
+            Some((name, _)) => Provenance::Trusted(name.clone()),
+            None => Provenance::Untrusted(sig.public_key.clone()),
+        }
+    }
+}
+
+/// Whether `sig` is a valid signature of `patch`; Err holds why not
+#[cfg(feature = "signing")]
+fn check(patch: &GraphPatch, sig: &PatchSignature) -> Result<(), String> {
+    let (Some(key), Some(signature)) = (from_hex(&sig.public_key), from_hex(&sig.signature)) else {
+        return Err("malformed hex".to_string());
+    };
+    UnparsedPublicKey::new(&ED25519, &key)
+        .verify(&signing_payload(patch)?, &signature)
+        .map_err(|_| "content doesn't match".to_string())
+}
+
+#[cfg(feature = "signing")]
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(feature = "signing")]
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::PatchNode;
+
+    fn patch() -> GraphPatch {
+        let mut p = GraphPatch::new(Some("alice".to_string()), None, None);
+        p.nodes.push(PatchNode {
+            change_id: "cid-1".to_string(),
+            node_type: "goal".to_string(),
+            title: "Ship it".to_string(),
+            description: None,
+            status: "pending".to_string(),
+            metadata_json: None,
+            created_at: "2025-01-01T00:00:00Z".to_string(),
+        });
+        p
+    }
+
+    #[test]
+    #[cfg(feature = "signing")]
+    fn test_sign_and_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("keys").join("signing-key.pk8");
+        let key = SigningKey::load_or_create(&key_path).unwrap();
+        let reloaded = SigningKey::load_or_create(&key_path).unwrap();
+        assert_eq!(key.public_key_hex(), reloaded.public_key_hex());
+
+        let mut p = patch();
+        key.sign(&mut p).unwrap();
+
+        // Survives a save/load round trip
+        let path = dir.path().join("patch.json");
+        p.save(&path).unwrap();
+        let loaded = GraphPatch::load(&path).unwrap();
+
+        let mut config = SyncConfig::default();
+        assert_eq!(
+            verify(&loaded, &config),
+            Provenance::Untrusted(key.public_key_hex())
+        );
+        config
+            .trusted_keys
+            .insert("alice".to_string(), key.public_key_hex().to_uppercase());
+        assert_eq!(
+            verify(&loaded, &config),
+            Provenance::Trusted("alice".to_string())
+        );
+
+        // Tampering breaks the signature
+        let mut tampered = loaded.clone();
+        tampered.nodes[0].status = "completed".to_string();
+        assert!(matches!(verify(&tampered, &config), Provenance::Invalid(_)));
+    }
+
+    #[test]
+    fn test_acceptable() {
+        let mut config = SyncConfig::default();
+        assert!(Provenance::Unsigned.acceptable(&config));
+        assert!(Provenance::Untrusted("ab".to_string()).acceptable(&config));
+        assert!(!Provenance::Invalid("x".to_string()).acceptable(&config));
+        assert!(Provenance::Unchecked.acceptable(&config));
+
+        config.require_signatures = true;
+        assert!(!Provenance::Unsigned.acceptable(&config));
+        assert!(!Provenance::Unchecked.acceptable(&config));
+        assert!(!Provenance::Untrusted("ab".to_string()).acceptable(&config));
+        assert!(Provenance::Trusted("bob".to_string()).acceptable(&config));
+        assert_eq!(verify(&patch(), &config), Provenance::Unsigned);
+    }
+
+    #[test]
+    #[cfg(not(feature = "signing"))]
+    fn test_unchecked_without_signing() {
+        let mut p = patch();
+        p.signature = Some(PatchSignature {
+            algorithm: SIGNATURE_ALGORITHM.to_string(),
+            public_key: "ab".to_string(),
+            signature: "cd".to_string(),
+        });
+        assert_eq!(verify(&p, &SyncConfig::default()), Provenance::Unchecked);
+    }
+
+    #[test]
+    #[cfg(feature = "signing")]
+    fn test_hex() {
+        assert_eq!(to_hex(&[0x00, 0xab, 0x10]), "00ab10");
+        assert_eq!(from_hex("00AB10"), Some(vec![0x00, 0xab, 0x10]));
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex("zz"), None);
+    }
+}
@@ -0,0 +1,309 @@
+//! Labeled point-in-time copies of the graph
+//!
+//! `deciduous snapshot create "before refactor"` stores every node and edge
+//! as a `GraphPatch` under that label. `deciduous snapshot diff <a> [b]`
+//! compares two snapshots (or one against the graph as it is now) by
+//! change_id: nodes and edges added or removed, and nodes whose fields
+//! changed.
+
+use crate::db::Database;
+use crate::diff::{GraphPatch, PatchEdge, PatchNode};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+
+/// What `snapshot diff` compares against when no second snapshot is given
+pub const CURRENT: &str = "current";
+
+/// The graph as it is now: every node and edge, without updates or tombstones
+pub fn capture(db: &Database) -> Result<GraphPatch, String> {
+    let mut patch = GraphPatch::new(None, crate::db::get_current_git_branch(), None);
+    for node in db.get_all_nodes().map_err(|e| e.to_string())? {
+        patch.add_node(&node);
+    }
+    for edge in db.get_all_edges().map_err(|e| e.to_string())? {
+        patch.add_edge(&edge);
+    }
+    Ok(patch)
+}
+
+/// Snapshot the graph as `label`; returns the snapshot's ID and its copy
+pub fn create(db: &Database, label: &str) -> Result<(i32, GraphPatch), String> {
+    if label.trim().is_empty() || label == CURRENT {
+        return Err(format!("'{}' can't label a snapshot", label));
+    }
+    let patch = capture(db)?;
+    let json = serde_json::to_string(&patch).map_err(|e| e.to_string())?;
+    let id = db
+        .create_snapshot(label, &json)
+        .map_err(|e| e.to_string())?;
+    Ok((id, patch))
+}
+
+/// The graph stored as snapshot `label` (or ID), or the live graph for "current"
+pub fn load(db: &Database, label: &str) -> Result<GraphPatch, String> {
+    if label == CURRENT {
+        return capture(db);
+    }
+    let snapshot = db
+        .get_snapshot(label)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No snapshot '{}' (see deciduous snapshot list)", label))?;
+    serde_json::from_str(&snapshot.patch_json)
+        .map_err(|e| format!("Snapshot '{}' is unreadable: {}", label, e))
+}
+
+/// A node present in both graphs whose fields differ
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct NodeChange {
+    pub change_id: String,
+    /// Title in the later graph
+    pub title: String,
+    /// Changed fields; metadata keys as `metadata.<key>`
+    pub fields: Vec<String>,
+}
+
+/// How the graph moved from `from` to `to`
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct SnapshotDiff {
+    pub from: String,
+    pub to: String,
+    pub nodes_added: Vec<PatchNode>,
+    pub nodes_removed: Vec<PatchNode>,
+    pub nodes_changed: Vec<NodeChange>,
+    pub edges_added: Vec<PatchEdge>,
+    pub edges_removed: Vec<PatchEdge>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.nodes_added.is_empty()
+            && self.nodes_removed.is_empty()
+            && self.nodes_changed.is_empty()
+            && self.edges_added.is_empty()
+            && self.edges_removed.is_empty()
+    }
+}
+
+fn metadata(node: &PatchNode) -> serde_json::Map<String, Value> {
+    node.metadata_json
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<Value>(m).ok())
+        .and_then(|m| m.as_object().cloned())
+        .unwrap_or_default()
+}
+
+/// Fields that differ between two versions of a node
+fn changed_fields(before: &PatchNode, after: &PatchNode) -> Vec<String> {
+    let mut fields: Vec<String> = [
+        ("node_type", before.node_type != after.node_type),
+        ("title", before.title != after.title),
+        ("description", before.description != after.description),
+        ("status", before.status != after.status),
+    ]
+    .into_iter()
+    .filter(|(_, changed)| *changed)
+    .map(|(field, _)| field.to_string())
+    .collect();
+    let (old, new) = (metadata(before), metadata(after));
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    fields.extend(
+        keys.into_iter()
+            .filter(|k| old.get(*k) != new.get(*k))
+            .map(|k| format!("metadata.{}", k)),
+    );
+    fields
+}
+
+fn edge_key(edge: &PatchEdge) -> (&str, &str, &str) {
+    (&edge.from_change_id, &edge.to_change_id, &edge.edge_type)
+}
+
+/// Compare two copies of the graph by change_id
+pub fn diff(from: &str, before: &GraphPatch, to: &str, after: &GraphPatch) -> SnapshotDiff {
+    let old: HashMap<&str, &PatchNode> = before
+        .nodes
+        .iter()
+        .map(|n| (n.change_id.as_str(), n))
+        .collect();
+    let new: HashMap<&str, &PatchNode> = after
+        .nodes
+        .iter()
+        .map(|n| (n.change_id.as_str(), n))
+        .collect();
+    let old_edges: BTreeSet<_> = before.edges.iter().map(edge_key).collect();
+    let new_edges: BTreeSet<_> = after.edges.iter().map(edge_key).collect();
+
+    SnapshotDiff {
+        from: from.to_string(),
+        to: to.to_string(),
+        nodes_added: after
+            .nodes
+            .iter()
+            .filter(|n| !old.contains_key(n.change_id.as_str()))
+            .cloned()
+            .collect(),
+        nodes_removed: before
+            .nodes
+            .iter()
+            .filter(|n| !new.contains_key(n.change_id.as_str()))
+            .cloned()
+            .collect(),
+        nodes_changed: after
+            .nodes
+            .iter()
+            .filter_map(|n| {
+                let fields = changed_fields(old.get(n.change_id.as_str())?, n);
+                (!fields.is_empty()).then(|| NodeChange {
+                    change_id: n.change_id.clone(),
+                    title: n.title.clone(),
+                    fields,
+                })
+            })
+            .collect(),
+        edges_added: after
+            .edges
+            .iter()
+            .filter(|e| !old_edges.contains(&edge_key(e)))
+            .cloned()
+            .collect(),
+        edges_removed: before
+            .edges
+            .iter()
+            .filter(|e| !new_edges.contains(&edge_key(e)))
+            .cloned()
+            .collect(),
+    }
+}
+
+/// The diff as text, naming edge endpoints by title where either graph has them
+pub fn render(diff: &SnapshotDiff, before: &GraphPatch, after: &GraphPatch) -> String {
+    let titles: HashMap<&str, &str> = before
+        .nodes
+        .iter()
+        .chain(&after.nodes)
+        .map(|n| (n.change_id.as_str(), n.title.as_str()))
+        .collect();
+    let name = |change_id: &str| {
+        titles
+            .get(change_id)
+            .map(|t| t.to_string())
+            .unwrap_or_else(|| change_id.chars().take(8).collect())
+    };
+    let edge = |e: &PatchEdge| {
+        format!(
+            "{} -> {} ({})",
+            name(&e.from_change_id),
+            name(&e.to_change_id),
+            e.edge_type
+        )
+    };
+
+    let mut out = format!("Snapshot diff: {} -> {}\n", diff.from, diff.to);
+    if diff.is_empty() {
+        out.push_str("No changes.\n");
+        return out;
+    }
+    let mut section = |heading: &str, lines: Vec<String>| {
+        if !lines.is_empty() {
+            out.push_str(&format!("\n{} ({}):\n", heading, lines.len()));
+            for line in lines {
+                out.push_str(&format!("  {}\n", line));
+            }
+        }
+    };
+    section(
+        "Nodes added",
+        diff.nodes_added
+            .iter()
+            .map(|n| format!("+ [{}] {}", n.node_type, n.title))
+            .collect(),
+    );
+    section(
+        "Nodes removed",
+        diff.nodes_removed
+            .iter()
+            .map(|n| format!("- [{}] {}", n.node_type, n.title))
+            .collect(),
+    );
+    section(
+        "Nodes changed",
+        diff.nodes_changed
+            .iter()
+            .map(|c| format!("~ {} ({})", c.title, c.fields.join(", ")))
+            .collect(),
+    );
+    section(
+        "Edges added",
+        diff.edges_added
+            .iter()
+            .map(|e| format!("+ {}", edge(e)))
+            .collect(),
+    );
+    section(
+        "Edges removed",
+        diff.edges_removed
+            .iter()
+            .map(|e| format!("- {}", edge(e)))
+            .collect(),
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_diff() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let goal = db.node("goal", "Refactor storage").insert().unwrap();
+        let old = db.node("action", "Keep JSON files").insert().unwrap();
+        let kept = db
+            .node("decision", "Which store?")
+            .confidence(50)
+            .insert()
+            .unwrap();
+        db.edge(goal, old).insert().unwrap();
+
+        let (_, before) = create(&db, "before refactor").unwrap();
+        assert!(create(&db, "before refactor").is_err());
+        assert!(create(&db, CURRENT).is_err());
+
+        db.delete_node(old).unwrap();
+        let new = db.node("action", "Move to SQLite").insert().unwrap();
+        db.edge(goal, new).insert().unwrap();
+        db.update_node_status(kept, "completed").unwrap();
+        db.update_node_metadata_field(kept, "confidence", serde_json::json!(90))
+            .unwrap();
+        create(&db, "after").unwrap();
+
+        let after = load(&db, "after").unwrap();
+        assert_eq!(load(&db, "1").unwrap().nodes.len(), before.nodes.len());
+        assert!(load(&db, "missing").is_err());
+
+        let d = diff("before refactor", &before, "after", &after);
+        assert_eq!(d.nodes_added.len(), 1);
+        assert_eq!(d.nodes_added[0].title, "Move to SQLite");
+        assert_eq!(d.nodes_removed[0].title, "Keep JSON files");
+        assert_eq!(
+            d.nodes_changed,
+            vec![NodeChange {
+                change_id: d.nodes_changed[0].change_id.clone(),
+                title: "Which store?".to_string(),
+                fields: vec!["status".to_string(), "metadata.confidence".to_string()],
+            }]
+        );
+        assert_eq!((d.edges_added.len(), d.edges_removed.len()), (1, 1));
+
+        let text = render(&d, &before, &after);
+        assert!(text.contains("Nodes added (1):\n  + [action] Move to SQLite\n"));
+        assert!(text.contains("- Refactor storage -> Keep JSON files (leads_to)"));
+        assert!(text.contains("~ Which store? (status, metadata.confidence)"));
+
+        let now = load(&db, CURRENT).unwrap();
+        assert!(diff("after", &after, CURRENT, &now).is_empty());
+        assert!(db.delete_snapshot("after").unwrap());
+        assert_eq!(db.get_snapshots().unwrap().len(), 1);
+    }
+}
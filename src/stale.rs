@@ -0,0 +1,113 @@
+//! Decisions and actions that stopped moving
+//!
+//! `deciduous stale` (and `audit --stale`) flags decisions that still have no
+//! chosen option `[stale] decision_days` after they were logged, and actions
+//! still pending or active `action_days` after they were logged. With
+//! `--notify` the list goes to the `on_stale` hook, which can post it to chat.
+
+use crate::config::StaleConfig;
+use crate::db::{DecisionEdge, DecisionNode};
+use chrono::{DateTime, FixedOffset};
+
+/// A node that has waited longer than its threshold
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StaleNode {
+    pub id: i32,
+    pub node_type: String,
+    pub title: String,
+    pub status: String,
+    /// Whole days since the node was logged
+    pub age_days: i64,
+    pub reason: String,
+}
+
+/// Stale decisions and actions as of `now`, oldest first
+pub fn find_stale(
+    nodes: &[DecisionNode],
+    edges: &[DecisionEdge],
+    config: &StaleConfig,
+    now: DateTime<FixedOffset>,
+) -> Vec<StaleNode> {
+    let age = |node: &DecisionNode| {
+        DateTime::parse_from_rfc3339(&node.created_at)
+            .ok()
+            .map(|t| (now - t).num_days())
+    };
+    let chosen = |id: i32| {
+        edges
+            .iter()
+            .any(|e| e.from_node_id == id && e.edge_type == "chosen")
+    };
+
+    let mut stale: Vec<StaleNode> = nodes
+        .iter()
+        .filter(|n| n.status != "completed" && n.status != "rejected")
+        .filter_map(|n| {
+            let days = age(n)?;
+            let reason = match n.node_type.as_str() {
+                "decision" if days > config.decision_days && !chosen(n.id) => {
+                    format!("no chosen option after {} days", days)
+                }
+                "action"
+                    if days > config.action_days
+                        && (n.status == "pending" || n.status == "active") =>
+                {
+                    format!("{} for {} days", n.status, days)
+                }
+                _ => return None,
+            };
+            Some(StaleNode {
+                id: n.id,
+                node_type: n.node_type.to_string(),
+                title: n.title.clone(),
+                status: n.status.to_string(),
+                age_days: days,
+                reason,
+            })
+        })
+        .collect();
+    stale.sort_by(|a, b| b.age_days.cmp(&a.age_days).then(a.id.cmp(&b.id)));
+    stale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use chrono::{Duration, Local};
+
+    #[test]
+    fn test_find_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let open = db.node("decision", "Which queue?").insert().unwrap();
+        let decided = db.node("decision", "Which cache?").insert().unwrap();
+        let option = db.node("option", "Redis").insert().unwrap();
+        db.edge(decided, option)
+            .edge_type("chosen")
+            .insert()
+            .unwrap();
+        let action = db.node("action", "Wire up the queue").insert().unwrap();
+        let done = db.node("action", "Add metrics").insert().unwrap();
+        db.update_node_status(done, "completed").unwrap();
+
+        let nodes = db.get_all_nodes().unwrap();
+        let edges = db.get_all_edges().unwrap();
+        let config = StaleConfig::default();
+        let today = Local::now().fixed_offset();
+        assert!(find_stale(&nodes, &edges, &config, today).is_empty());
+
+        let in_5_days = today + Duration::days(5);
+        let found = find_stale(&nodes, &edges, &config, in_5_days);
+        assert_eq!(found.iter().map(|s| s.id).collect::<Vec<_>>(), vec![action]);
+        assert_eq!(found[0].reason, "pending for 5 days");
+
+        let in_10_days = today + Duration::days(10);
+        let found = find_stale(&nodes, &edges, &config, in_10_days);
+        assert_eq!(
+            found.iter().map(|s| s.id).collect::<Vec<_>>(),
+            vec![open, action]
+        );
+        assert_eq!(found[0].reason, "no chosen option after 10 days");
+    }
+}
@@ -0,0 +1,193 @@
+//! Storage backends for the decision graph
+//!
+//! [`GraphStore`] is the part of [`Database`] that graph-level tools (the MCP
+//! server, the C API) need: read nodes and edges, add them, change a status,
+//! delete a node. SQLite, through [`Database`], is the built-in backend. A
+//! shared backend such as Postgres implements the same trait so one team
+//! database can serve many clients; [`open_store`] picks the backend from the
+//! location's URL scheme.
+
+use crate::db::{
+    Database, DbError, DecisionEdge, DecisionGraph, DecisionNode, EdgeBuilder, NodeBuilder, Result,
+};
+
+/// Where a decision graph is stored
+///
+/// Method names and semantics match the [`Database`] methods of the same name.
+pub trait GraphStore: Send + Sync {
+    /// Short name of the backend, e.g. "sqlite"
+    fn backend(&self) -> &'static str;
+
+    /// All nodes, oldest first
+    fn get_all_nodes(&self) -> Result<Vec<DecisionNode>>;
+
+    /// All edges, oldest first
+    fn get_all_edges(&self) -> Result<Vec<DecisionEdge>>;
+
+    fn get_node_by_id(&self, node_id: i32) -> Result<Option<DecisionNode>>;
+
+    /// Nodes this node has an edge to
+    fn get_node_children(&self, node_id: i32) -> Result<Vec<DecisionNode>> {
+        let ids: Vec<i32> = self
+            .get_all_edges()?
+            .into_iter()
+            .filter(|e| e.from_node_id == node_id)
+            .map(|e| e.to_node_id)
+            .collect();
+        nodes_with_ids(self, &ids)
+    }
+
+    /// Nodes with an edge to this node
+    fn get_node_parents(&self, node_id: i32) -> Result<Vec<DecisionNode>> {
+        let ids: Vec<i32> = self
+            .get_all_edges()?
+            .into_iter()
+            .filter(|e| e.to_node_id == node_id)
+            .map(|e| e.from_node_id)
+            .collect();
+        nodes_with_ids(self, &ids)
+    }
+
+    /// Nodes and edges; backends without a pull request cache leave it empty
+    fn get_graph(&self) -> Result<DecisionGraph> {
+        Ok(DecisionGraph {
+            nodes: self.get_all_nodes()?,
+            edges: self.get_all_edges()?,
+            config: None,
+            pull_requests: Vec::new(),
+        })
+    }
+
+    /// Insert a pending node; returns its ID
+    ///
+    /// Called by [`NodeBuilder::insert`] after its `[rules]` checks; use
+    /// `store.node(...)` rather than calling this directly.
+    fn insert_node(&self, node: &NodeBuilder<'_>) -> Result<i32>;
+
+    /// Insert an edge; fails with a validation error if either node is missing
+    ///
+    /// Called by [`EdgeBuilder::insert`]; use `store.edge(...)`.
+    fn insert_edge(&self, edge: &EdgeBuilder<'_>) -> Result<i32>;
+
+    /// The goal `deciduous focus` points new work at; none for backends
+    /// without focus
+    fn focused_goal(&self) -> Result<Option<DecisionNode>> {
+        Ok(None)
+    }
+
+    fn update_node_status(&self, node_id: i32, status: &str) -> Result<()>;
+
+    /// Delete a node with its edges; false if there was no such node
+    fn delete_node(&self, node_id: i32) -> Result<bool>;
+}
+
+impl<'s> dyn GraphStore + 's {
+    /// Start building a node; see [`NodeBuilder`]
+    pub fn node(&self, node_type: &str, title: &str) -> NodeBuilder<'_> {
+        NodeBuilder::new(self, node_type, title)
+    }
+
+    /// Start building an edge from one node to another; see [`EdgeBuilder`]
+    pub fn edge(&self, from_id: i32, to_id: i32) -> EdgeBuilder<'_> {
+        EdgeBuilder::new(self, from_id, to_id)
+    }
+}
+
+fn nodes_with_ids<S: GraphStore + ?Sized>(store: &S, ids: &[i32]) -> Result<Vec<DecisionNode>> {
+    Ok(store
+        .get_all_nodes()?
+        .into_iter()
+        .filter(|n| ids.contains(&n.id))
+        .collect())
+}
+
+impl GraphStore for Database {
+    fn backend(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn get_all_nodes(&self) -> Result<Vec<DecisionNode>> {
+        Database::get_all_nodes(self)
+    }
+
+    fn get_all_edges(&self) -> Result<Vec<DecisionEdge>> {
+        Database::get_all_edges(self)
+    }
+
+    fn get_node_by_id(&self, node_id: i32) -> Result<Option<DecisionNode>> {
+        Database::get_node_by_id(self, node_id)
+    }
+
+    fn get_node_children(&self, node_id: i32) -> Result<Vec<DecisionNode>> {
+        Database::get_node_children(self, node_id)
+    }
+
+    fn get_node_parents(&self, node_id: i32) -> Result<Vec<DecisionNode>> {
+        Database::get_node_parents(self, node_id)
+    }
+
+    fn get_graph(&self) -> Result<DecisionGraph> {
+        Database::get_graph(self)
+    }
+
+    fn insert_node(&self, node: &NodeBuilder<'_>) -> Result<i32> {
+        Database::insert_node(self, node)
+    }
+
+    fn insert_edge(&self, edge: &EdgeBuilder<'_>) -> Result<i32> {
+        Database::insert_edge(self, edge)
+    }
+
+    fn focused_goal(&self) -> Result<Option<DecisionNode>> {
+        Ok(crate::focus::active(self, chrono::Local::now()))
+    }
+
+    fn update_node_status(&self, node_id: i32, status: &str) -> Result<()> {
+        Database::update_node_status(self, node_id, status)
+    }
+
+    fn delete_node(&self, node_id: i32) -> Result<bool> {
+        Database::delete_node(self, node_id)
+    }
+}
+
+/// Open the store at `location`
+///
+/// `sqlite://<path>`, or a bare path, opens a SQLite database. Other schemes
+/// (`postgres://...`) name backends this build doesn't include and fail with
+/// a connection error.
+pub fn open_store(location: &str) -> Result<Box<dyn GraphStore>> {
+    match location.split_once("://") {
+        None => Ok(Box::new(Database::open_at(location)?)),
+        Some(("sqlite", path)) => Ok(Box::new(Database::open_at(path)?)),
+        Some((scheme, _)) => Err(DbError::Connection(format!(
+            "No '{}' storage backend in this build (only sqlite)",
+            scheme
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.db");
+        let store = open_store(&format!("sqlite://{}", path.display())).unwrap();
+        assert_eq!(store.backend(), "sqlite");
+
+        let goal = store.node("goal", "Share one graph").insert().unwrap();
+        let option = store.node("option", "Postgres").insert().unwrap();
+        store.edge(goal, option).insert().unwrap();
+        store.update_node_status(option, "active").unwrap();
+        assert_eq!(store.get_node_children(goal).unwrap()[0].status, "active");
+        assert_eq!(store.get_graph().unwrap().edges.len(), 1);
+        assert!(store.delete_node(option).unwrap());
+        assert!(store.get_node_parents(option).unwrap().is_empty());
+
+        let err = open_store("postgres://team@db/deciduous").err().unwrap();
+        assert!(err.to_string().contains("postgres"));
+    }
+}
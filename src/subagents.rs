@@ -0,0 +1,246 @@
+//! Reconstruct subagent trees from trace spans
+//!
+//! Claude Code runs subagents through the `Task` tool. Their requests go
+//! through the same proxy, so they land in the parent's session, interleaved
+//! with everything else. A subagent's spans run while the `Task` call is
+//! outstanding: after the span that made the call completes and before the
+//! parent conversation resumes. Within that window, the subagent's latest
+//! user message is the task prompt, which tells parallel tasks apart.
+
+use crate::db::{Database, TraceContent, TraceSpan};
+use chrono::{DateTime, FixedOffset};
+use std::collections::HashMap;
+
+/// Tools that start a subagent
+const SUBAGENT_TOOLS: &[&str] = &["Task", "Agent"];
+
+/// Characters of the prompt compared against a span's user message
+const PROMPT_PREFIX: usize = 200;
+
+/// A subagent call and the window its spans ran in
+struct TaskCall<'a> {
+    parent: &'a TraceSpan,
+    tool_use_id: String,
+    prompt: Option<String>,
+    opened: DateTime<FixedOffset>,
+    closed: Option<DateTime<FixedOffset>>,
+}
+
+fn parse_time(s: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(s).ok()
+}
+
+fn prefix(text: &str) -> String {
+    text.trim().chars().take(PROMPT_PREFIX).collect()
+}
+
+fn task_calls<'a>(spans: &'a [TraceSpan], content: &[TraceContent]) -> Vec<TaskCall<'a>> {
+    let by_id: HashMap<i32, &TraceSpan> = spans.iter().map(|s| (s.id, s)).collect();
+    let mut calls = Vec::new();
+
+    for call in content.iter().filter(|c| {
+        c.content_type == "tool_input"
+            && c.tool_name
+                .as_deref()
+                .is_some_and(|n| SUBAGENT_TOOLS.contains(&n))
+    }) {
+        let (Some(parent), Some(tool_use_id)) =
+            (by_id.get(&call.span_id).copied(), &call.tool_use_id)
+        else {
+            continue;
+        };
+        let done = parent.completed_at.as_deref();
+        let Some(opened) = parse_time(done.unwrap_or(&parent.started_at)) else {
+            continue;
+        };
+        let prompt = serde_json::from_str::<serde_json::Value>(&call.content)
+            .ok()
+            .and_then(|input| input["prompt"].as_str().map(prefix));
+
+        // The call is over once its result comes back or the parent
+        // conversation carries on
+        let result_span = content
+            .iter()
+            .find(|c| {
+                (c.content_type == "tool_output" || c.content_type == "tool_error")
+                    && c.tool_use_id.as_deref() == Some(tool_use_id.as_str())
+            })
+            .and_then(|c| by_id.get(&c.span_id));
+        let resumed = spans.iter().find(|s| {
+            s.id != parent.id
+                && s.user_preview == parent.user_preview
+                && parse_time(&s.started_at).is_some_and(|t| t > opened)
+        });
+        let closed = result_span
+            .copied()
+            .or(resumed)
+            .and_then(|s| parse_time(&s.started_at));
+
+        calls.push(TaskCall {
+            parent,
+            tool_use_id: tool_use_id.clone(),
+            prompt,
+            opened,
+            closed,
+        });
+    }
+    calls
+}
+
+/// Find each subagent span's parent: (span ID, parent span ID, tool_use_id)
+pub fn detect_parents(spans: &[TraceSpan], content: &[TraceContent]) -> Vec<(i32, i32, String)> {
+    let calls = task_calls(spans, content);
+    let mut parents = Vec::new();
+
+    for span in spans {
+        let Some(started) = parse_time(&span.started_at) else {
+            continue;
+        };
+        let user = span.user_preview.as_deref().map(prefix);
+        let open: Vec<&TaskCall> = calls
+            .iter()
+            .filter(|c| {
+                c.parent.id != span.id
+                    && c.parent.user_preview != span.user_preview
+                    && c.opened < started
+                    && c.closed.map_or(true, |closed| started < closed)
+            })
+            .collect();
+
+        let matches_prompt = |c: &&&TaskCall| match (&c.prompt, &user) {
+            (Some(p), Some(u)) => p.starts_with(u.as_str()) || u.starts_with(p.as_str()),
+            _ => false,
+        };
+        let innermost = |c: &&&TaskCall| c.opened;
+        let call = open
+            .iter()
+            .filter(matches_prompt)
+            .max_by_key(innermost)
+            .or_else(|| open.iter().max_by_key(innermost));
+        if let Some(call) = call {
+            parents.push((span.id, call.parent.id, call.tool_use_id.clone()));
+        }
+    }
+    parents
+}
+
+/// Spans in tree order (each parent followed by its subagents), with depth
+///
+/// Spans whose parent isn't among `spans` are shown at the top level.
+pub fn tree_order<'a>(
+    spans: &'a [TraceSpan],
+    parents: &HashMap<i32, i32>,
+) -> Vec<(&'a TraceSpan, usize)> {
+    let ids: std::collections::HashSet<i32> = spans.iter().map(|s| s.id).collect();
+    let mut children: HashMap<i32, Vec<&TraceSpan>> = HashMap::new();
+    let mut roots = Vec::new();
+    for span in spans {
+        match parents.get(&span.id).filter(|p| ids.contains(*p)) {
+            Some(parent) => children.entry(*parent).or_default().push(span),
+            None => roots.push(span),
+        }
+    }
+
+    let mut ordered = Vec::with_capacity(spans.len());
+    let mut stack: Vec<(&TraceSpan, usize)> = roots.into_iter().rev().map(|s| (s, 0)).collect();
+    while let Some((span, depth)) = stack.pop() {
+        ordered.push((span, depth));
+        if let Some(kids) = children.get(&span.id) {
+            stack.extend(kids.iter().rev().map(|s| (*s, depth + 1)));
+        }
+    }
+    ordered
+}
+
+/// Detect and store a session's subagent spans; returns every span's parent
+pub fn link_session_subagents(
+    db: &Database,
+    session_id: &str,
+) -> crate::db::Result<HashMap<i32, i32>> {
+    let spans = db.get_trace_spans(session_id)?;
+    let mut content = Vec::new();
+    for span in &spans {
+        content.extend(db.get_trace_content(span.id)?);
+    }
+    for (span_id, parent_id, tool_use_id) in detect_parents(&spans, &content) {
+        db.set_span_parent(span_id, parent_id, Some(&tool_use_id))?;
+    }
+    db.get_span_parents(session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(id: i32, started: &str, completed: &str, user: &str) -> TraceSpan {
+        TraceSpan {
+            started_at: format!("2025-06-01T10:00:{}+00:00", started),
+            completed_at: Some(format!("2025-06-01T10:00:{}+00:00", completed)),
+            user_preview: Some(user.to_string()),
+            ..TraceSpan::for_test(id)
+        }
+    }
+
+    fn task(span_id: i32, id: &str, prompt: &str) -> TraceContent {
+        TraceContent {
+            id: 0,
+            span_id,
+            content_type: "tool_input".to_string(),
+            tool_name: Some("Task".to_string()),
+            tool_use_id: Some(id.to_string()),
+            content: serde_json::json!({ "prompt": prompt }).to_string(),
+            sequence_num: 0,
+        }
+    }
+
+    #[test]
+    fn test_parallel_subagents() {
+        let spans = vec![
+            span(1, "00", "05", "Audit the repo"),
+            span(2, "06", "08", "Check the tests"),
+            span(3, "06", "09", "Check the docs"),
+            span(4, "09", "10", "Check the tests"),
+            span(5, "10", "12", "Check the docs"),
+            span(6, "20", "25", "Audit the repo"),
+            span(7, "30", "31", "Something else"),
+        ];
+        let content = vec![
+            task(1, "t1", "Check the tests"),
+            task(1, "t2", "Check the docs"),
+        ];
+
+        let parents = detect_parents(&spans, &content);
+        let found: Vec<(i32, i32)> = parents.iter().map(|(s, p, _)| (*s, *p)).collect();
+        assert_eq!(found, vec![(2, 1), (3, 1), (4, 1), (5, 1)]);
+        assert_eq!(parents[1].2, "t2");
+        assert_eq!(parents[2].2, "t1");
+    }
+
+    #[test]
+    fn test_nested_subagent_and_tree_order() {
+        let spans = vec![
+            span(1, "00", "05", "Ship it"),
+            span(2, "06", "08", "Run the release"),
+            span(3, "09", "10", "Tag the version"),
+            span(4, "11", "12", "Run the release"),
+            span(5, "13", "14", "Ship it"),
+        ];
+        let content = vec![
+            task(1, "t1", "Run the release"),
+            task(2, "t2", "Tag the version"),
+        ];
+        let parents: HashMap<i32, i32> = detect_parents(&spans, &content)
+            .into_iter()
+            .map(|(s, p, _)| (s, p))
+            .collect();
+        assert_eq!(parents.get(&3), Some(&2));
+        assert_eq!(parents.get(&2), Some(&1));
+        assert_eq!(parents.get(&5), None);
+
+        let order: Vec<(i32, usize)> = tree_order(&spans, &parents)
+            .iter()
+            .map(|(s, d)| (s.id, *d))
+            .collect();
+        assert_eq!(order, vec![(1, 0), (2, 1), (3, 2), (4, 1), (5, 0)]);
+    }
+}
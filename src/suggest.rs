@@ -0,0 +1,345 @@
+//! Suggest decision nodes for trace activity nobody logged
+//!
+//! Spans that used tools but aren't linked to any node are grouped into
+//! turns (consecutive spans answering the same user message). Each turn gets
+//! a draft goal (the user's request), action (what the tools touched) and,
+//! when the turn ended with a reply, outcome (the reply's first sentence).
+//! Drafts can optionally be rewritten by a model.
+
+use crate::db::{TraceContent, TraceSpan};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// Longest draft title
+const MAX_TITLE: usize = 80;
+
+/// Tools whose `file_path` input means the file was changed
+const EDIT_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// Draft nodes for one turn of unlinked activity
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub session_id: String,
+    pub span_ids: Vec<i32>,
+    /// The user's message, kept as the goal's prompt
+    pub prompt: Option<String>,
+    pub goal: String,
+    pub action: String,
+    pub outcome: Option<String>,
+    /// Files the tools changed
+    pub files: Vec<String>,
+    /// Tool name → call count
+    pub tools: BTreeMap<String, usize>,
+}
+
+fn first_line(text: &str, max: usize) -> String {
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or("");
+    if line.chars().count() <= max {
+        return line.to_string();
+    }
+    let mut cut: String = line.chars().take(max - 3).collect();
+    if let Some(space) = cut.rfind(' ') {
+        cut.truncate(space);
+    }
+    format!("{}...", cut.trim_end())
+}
+
+fn first_sentence(text: &str) -> String {
+    let line = first_line(text, usize::MAX);
+    let end = line
+        .char_indices()
+        .find(|&(i, c)| {
+            matches!(c, '.' | '!' | '?')
+                && line[i + c.len_utf8()..]
+                    .chars()
+                    .next()
+                    .map_or(true, char::is_whitespace)
+        })
+        .map(|(i, c)| i + c.len_utf8());
+    first_line(&line[..end.unwrap_or(line.len())], MAX_TITLE)
+}
+
+/// Group unlinked spans that used tools into turns
+///
+/// `node_counts` is the number of nodes recorded against each span (see
+/// `Database::get_node_counts_for_spans`); spans with any, or with a linked
+/// node, count as logged.
+pub fn unlinked_turns<'a>(
+    spans: &'a [TraceSpan],
+    node_counts: &HashMap<i32, i64>,
+) -> Vec<Vec<&'a TraceSpan>> {
+    let logged = |s: &TraceSpan| {
+        s.linked_node_id.is_some() || node_counts.get(&s.id).copied().unwrap_or(0) > 0
+    };
+
+    let mut turns: Vec<Vec<&TraceSpan>> = Vec::new();
+    for span in spans {
+        if logged(span) {
+            turns.push(Vec::new());
+            continue;
+        }
+        let same_turn = turns.last().and_then(|t| t.last()).is_some_and(|prev| {
+            prev.session_id == span.session_id && prev.user_preview == span.user_preview
+        });
+        match turns.last_mut() {
+            Some(turn) if same_turn => turn.push(span),
+            _ => turns.push(vec![span]),
+        }
+    }
+
+    turns
+        .into_iter()
+        .filter(|turn| {
+            turn.iter()
+                .any(|s| s.tool_names.as_deref().is_some_and(|t| !t.is_empty()))
+        })
+        .collect()
+}
+
+/// Draft a suggestion from a turn's spans and their content
+pub fn draft(turn: &[&TraceSpan], content: &HashMap<i32, Vec<TraceContent>>) -> Suggestion {
+    let mut tools: BTreeMap<String, usize> = BTreeMap::new();
+    let mut files: Vec<String> = Vec::new();
+    let mut commands: Vec<String> = Vec::new();
+
+    for span in turn {
+        for name in span.tool_names.as_deref().unwrap_or("").split(',') {
+            let name = name.trim();
+            if !name.is_empty() {
+                *tools.entry(name.to_string()).or_insert(0) += 1;
+            }
+        }
+        for call in content
+            .get(&span.id)
+            .into_iter()
+            .flatten()
+            .filter(|c| c.content_type == "tool_input")
+        {
+            let input: Value = serde_json::from_str(&call.content).unwrap_or(Value::Null);
+            let name = call.tool_name.as_deref().unwrap_or("");
+            if EDIT_TOOLS.contains(&name) {
+                let path = input["file_path"]
+                    .as_str()
+                    .or_else(|| input["notebook_path"].as_str());
+                if let Some(path) = path {
+                    if !files.iter().any(|f| f == path) {
+                        files.push(path.to_string());
+                    }
+                }
+            } else if name == "Bash" {
+                if let Some(command) = input["command"].as_str() {
+                    commands.push(command.to_string());
+                }
+            }
+        }
+    }
+
+    let first = turn[0];
+    let last = turn[turn.len() - 1];
+    let prompt = first.user_preview.clone().filter(|p| !p.trim().is_empty());
+
+    let goal = prompt
+        .as_deref()
+        .map(|p| first_line(p, MAX_TITLE))
+        .unwrap_or_else(|| "Untracked agent work".to_string());
+
+    let short_path = |path: &str| path.rsplit('/').next().unwrap_or(path).to_string();
+    let action = if !files.is_empty() {
+        let names: Vec<String> = files.iter().take(3).map(|f| short_path(f)).collect();
+        let more = files.len().saturating_sub(3);
+        let more = if more > 0 {
+            format!(" and {} more", more)
+        } else {
+            String::new()
+        };
+        first_line(&format!("Edit {}{}", names.join(", "), more), MAX_TITLE)
+    } else if let Some(command) = commands.first() {
+        first_line(&format!("Run {}", command), MAX_TITLE)
+    } else {
+        let names: Vec<&str> = tools.keys().map(String::as_str).collect();
+        first_line(&format!("Use {}", names.join(", ")), MAX_TITLE)
+    };
+
+    let outcome = if last.stop_reason.as_deref() == Some("end_turn") {
+        last.response_preview
+            .as_deref()
+            .map(first_sentence)
+            .filter(|s| !s.is_empty())
+    } else {
+        None
+    };
+
+    Suggestion {
+        session_id: first.session_id.clone(),
+        span_ids: turn.iter().map(|s| s.id).collect(),
+        prompt,
+        goal,
+        action,
+        outcome,
+        files,
+        tools,
+    }
+}
+
+/// A Messages API request asking a model to improve a suggestion's titles
+pub fn llm_request(suggestion: &Suggestion, last_response: Option<&str>, model: &str) -> Value {
+    let tools: Vec<String> = suggestion
+        .tools
+        .iter()
+        .map(|(name, n)| format!("{} ×{}", name, n))
+        .collect();
+    let context = format!(
+        "User request:\n{}\n\nTools used: {}\nFiles changed: {}\n\nFinal reply:\n{}",
+        suggestion.prompt.as_deref().unwrap_or("(none)"),
+        tools.join(", "),
+        if suggestion.files.is_empty() {
+            "(none)".to_string()
+        } else {
+            suggestion.files.join(", ")
+        },
+        last_response.unwrap_or("(none)"),
+    );
+    json!({
+        "model": model,
+        "max_tokens": 300,
+        "system": "You label a coding agent's work for a decision graph. Reply with only a JSON \
+                   object: {\"goal\": ..., \"action\": ..., \"outcome\": ... or null}. Each is a \
+                   short imperative or past-tense title under 80 characters. Use null for \
+                   outcome if the work didn't finish.",
+        "messages": [{"role": "user", "content": context}],
+    })
+}
+
+/// Apply titles from a model's JSON reply; returns false if none were usable
+pub fn apply_llm_reply(suggestion: &mut Suggestion, reply_text: &str) -> bool {
+    let (Some(start), Some(end)) = (reply_text.find('{'), reply_text.rfind('}')) else {
+        return false;
+    };
+    let Ok(titles) = serde_json::from_str::<Value>(&reply_text[start..=end]) else {
+        return false;
+    };
+    let title = |key: &str| {
+        titles[key]
+            .as_str()
+            .map(|t| first_line(t, MAX_TITLE))
+            .filter(|t| !t.is_empty())
+    };
+
+    let mut applied = false;
+    if let Some(goal) = title("goal") {
+        suggestion.goal = goal;
+        applied = true;
+    }
+    if let Some(action) = title("action") {
+        suggestion.action = action;
+        applied = true;
+    }
+    if titles.get("outcome").is_some() {
+        suggestion.outcome = title("outcome");
+        applied = true;
+    }
+    applied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(id: i32, user: &str, tools: Option<&str>, linked: bool) -> TraceSpan {
+        TraceSpan {
+            stop_reason: Some("tool_use".to_string()),
+            user_preview: Some(user.to_string()),
+            tool_names: tools.map(str::to_string),
+            linked_node_id: linked.then_some(7),
+            ..TraceSpan::for_test(id)
+        }
+    }
+
+    fn tool_input(span_id: i32, tool: &str, input: &str) -> TraceContent {
+        TraceContent {
+            id: 0,
+            span_id,
+            content_type: "tool_input".to_string(),
+            tool_name: Some(tool.to_string()),
+            tool_use_id: None,
+            content: input.to_string(),
+            sequence_num: 0,
+        }
+    }
+
+    #[test]
+    fn test_unlinked_turns() {
+        let spans = vec![
+            span(1, "fix the login bug", Some("Read"), false),
+            span(2, "fix the login bug", Some("Edit"), false),
+            span(3, "fix the login bug", None, false),
+            span(4, "thanks", None, false),
+            span(5, "add docs", Some("Write"), true),
+            span(6, "add tests", Some("Bash"), false),
+        ];
+        let mut counts = HashMap::new();
+        counts.insert(6, 1);
+        let turns = unlinked_turns(&spans, &counts);
+        assert_eq!(turns.len(), 1);
+        let ids: Vec<i32> = turns[0].iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_draft() {
+        let mut last = span(3, "", None, false);
+        last.user_preview = Some("Fix the login redirect loop\nIt happens after logout".into());
+        last.stop_reason = Some("end_turn".to_string());
+        last.response_preview = Some("Fixed the redirect loop. Tests pass now.".to_string());
+        let first = span(
+            2,
+            "Fix the login redirect loop\nIt happens after logout",
+            Some("Edit,Bash"),
+            false,
+        );
+
+        let mut content = HashMap::new();
+        content.insert(
+            2,
+            vec![
+                tool_input(2, "Edit", r#"{"file_path":"/repo/src/auth.rs"}"#),
+                tool_input(2, "Bash", r#"{"command":"cargo test"}"#),
+            ],
+        );
+
+        let s = draft(&[&first, &last], &content);
+        assert_eq!(s.goal, "Fix the login redirect loop");
+        assert_eq!(s.action, "Edit auth.rs");
+        assert_eq!(s.outcome.as_deref(), Some("Fixed the redirect loop."));
+        assert_eq!(s.files, vec!["/repo/src/auth.rs"]);
+        assert_eq!(s.tools["Bash"], 1);
+        assert_eq!(s.span_ids, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_apply_llm_reply() {
+        let turn = span(1, "do it", Some("Bash"), false);
+        let mut s = draft(&[&turn], &HashMap::new());
+        assert_eq!(s.action, "Use Bash");
+        assert!(apply_llm_reply(
+            &mut s,
+            "Sure:\n{\"goal\": \"Speed up CI\", \"action\": \"Cache cargo registry\", \"outcome\": null}"
+        ));
+        assert_eq!(s.goal, "Speed up CI");
+        assert_eq!(s.action, "Cache cargo registry");
+        assert_eq!(s.outcome, None);
+        assert!(!apply_llm_reply(&mut s, "no json here"));
+    }
+
+    #[test]
+    fn test_first_line_truncates_at_word() {
+        let long = "word ".repeat(30);
+        let line = first_line(&long, 20);
+        assert!(line.len() <= 20);
+        assert!(line.ends_with("word..."));
+    }
+}
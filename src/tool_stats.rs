@@ -0,0 +1,171 @@
+//! Tool usage analytics across trace spans
+//!
+//! Call counts come from each span's `tool_names`. Results are matched back
+//! to their tool through `tool_use_id`, since results arrive in the *next*
+//! request and are stored without a tool name. A result stored as
+//! `tool_error` counts as a failure.
+
+use crate::db::{TraceContent, TraceSpan};
+use std::collections::{BTreeMap, HashMap};
+
+/// Usage of one tool
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolStats {
+    pub calls: usize,
+    /// Results seen (some calls' results aren't captured)
+    pub results: usize,
+    pub errors: usize,
+    /// Total size of the results, in bytes
+    pub output_bytes: usize,
+}
+
+impl ToolStats {
+    /// Share of captured results that were errors
+    pub fn failure_rate(&self) -> Option<f64> {
+        if self.results == 0 {
+            return None;
+        }
+        Some(self.errors as f64 / self.results as f64)
+    }
+
+    /// Average result size in bytes
+    pub fn avg_output(&self) -> Option<usize> {
+        self.output_bytes.checked_div(self.results)
+    }
+}
+
+/// Tool usage overall and broken down by the type of node the work was for
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolReport {
+    pub by_tool: BTreeMap<String, ToolStats>,
+    /// Node type → tool → calls
+    pub by_node_type: BTreeMap<String, BTreeMap<String, usize>>,
+}
+
+impl ToolReport {
+    /// Tools, most called first
+    pub fn ranked(&self) -> Vec<(&str, &ToolStats)> {
+        let mut tools: Vec<(&str, &ToolStats)> = self
+            .by_tool
+            .iter()
+            .map(|(name, stats)| (name.as_str(), stats))
+            .collect();
+        tools.sort_by(|a, b| b.1.calls.cmp(&a.1.calls).then(a.0.cmp(b.0)));
+        tools
+    }
+}
+
+/// Aggregate tool usage
+///
+/// `content` holds the spans' tool content (`tool_input`, `tool_output`,
+/// `tool_error`); `node_types` maps a span ID to the type of node it's linked
+/// to, directly or through its session. Unlinked spans are grouped under
+/// "(unlinked)".
+pub fn tool_report(
+    spans: &[TraceSpan],
+    content: &[TraceContent],
+    node_types: &HashMap<i32, String>,
+) -> ToolReport {
+    let mut report = ToolReport::default();
+
+    for span in spans {
+        let node_type = node_types
+            .get(&span.id)
+            .cloned()
+            .unwrap_or_else(|| "(unlinked)".to_string());
+        for name in span
+            .tool_names
+            .as_deref()
+            .unwrap_or("")
+            .split(',')
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+        {
+            report.by_tool.entry(name.to_string()).or_default().calls += 1;
+            *report
+                .by_node_type
+                .entry(node_type.clone())
+                .or_default()
+                .entry(name.to_string())
+                .or_insert(0) += 1;
+        }
+    }
+
+    let names: HashMap<&str, &str> = content
+        .iter()
+        .filter(|c| c.content_type == "tool_input")
+        .filter_map(|c| Some((c.tool_use_id.as_deref()?, c.tool_name.as_deref()?)))
+        .collect();
+    for result in content
+        .iter()
+        .filter(|c| c.content_type == "tool_output" || c.content_type == "tool_error")
+    {
+        let name = result.tool_name.as_deref().or_else(|| {
+            result
+                .tool_use_id
+                .as_deref()
+                .and_then(|id| names.get(id).copied())
+        });
+        let stats = report
+            .by_tool
+            .entry(name.unwrap_or("(unknown)").to_string())
+            .or_default();
+        stats.results += 1;
+        stats.output_bytes += result.content.len();
+        if result.content_type == "tool_error" {
+            stats.errors += 1;
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(id: i32, tools: &str) -> TraceSpan {
+        TraceSpan {
+            tool_names: Some(tools.to_string()),
+            ..TraceSpan::for_test(id)
+        }
+    }
+
+    fn item(span_id: i32, kind: &str, tool: Option<&str>, id: &str, text: &str) -> TraceContent {
+        TraceContent {
+            tool_name: tool.map(str::to_string),
+            tool_use_id: Some(id.to_string()),
+            ..TraceContent::for_test(span_id, kind, text, None)
+        }
+    }
+
+    #[test]
+    fn test_tool_report() {
+        let spans = vec![span(1, "Bash,Read"), span(2, "Bash"), span(3, "")];
+        let content = vec![
+            item(1, "tool_input", Some("Bash"), "t1", "{}"),
+            item(1, "tool_input", Some("Read"), "t2", "{}"),
+            item(2, "tool_output", None, "t1", "0123456789"),
+            item(2, "tool_error", None, "t2", "no such file"),
+            item(2, "tool_input", Some("Bash"), "t3", "{}"),
+            item(3, "tool_error", None, "t3", "exit 1"),
+        ];
+        let mut node_types = HashMap::new();
+        node_types.insert(2, "action".to_string());
+
+        let report = tool_report(&spans, &content, &node_types);
+        let bash = &report.by_tool["Bash"];
+        assert_eq!(bash.calls, 2);
+        assert_eq!(bash.results, 2);
+        assert_eq!(bash.errors, 1);
+        assert_eq!(bash.failure_rate(), Some(0.5));
+        assert_eq!(bash.avg_output(), Some(8));
+        assert_eq!(report.by_tool["Read"].failure_rate(), Some(1.0));
+
+        assert_eq!(report.by_node_type["action"]["Bash"], 1);
+        assert_eq!(report.by_node_type["(unlinked)"]["Read"], 1);
+
+        let ranked: Vec<&str> = report.ranked().iter().map(|(n, _)| *n).collect();
+        assert_eq!(ranked, vec!["Bash", "Read"]);
+    }
+}
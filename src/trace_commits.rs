@@ -0,0 +1,225 @@
+//! Attribute git commits to the API spans that produced them
+//!
+//! A commit is made by a tool call, which runs after the span that asked for
+//! it completes and before the next span starts. So each commit goes to the
+//! latest span that started before it, preferring spans that called a shell
+//! tool, as long as that span finished no more than `MAX_GAP_MINUTES` earlier.
+
+use crate::db::{Database, TraceSpan};
+use chrono::{DateTime, Duration, FixedOffset};
+use std::process::Command;
+
+/// Longest a tool call can run between a span completing and its commit
+const MAX_GAP_MINUTES: i64 = 10;
+
+/// Tools that can run `git commit`
+const SHELL_TOOLS: &[&str] = &["Bash", "shell", "exec_command"];
+
+/// A commit from `git log`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitInfo {
+    pub hash: String,
+    pub time: DateTime<FixedOffset>,
+    pub subject: String,
+}
+
+/// Commits on any branch with a committer date at or after `since`
+pub fn commits_since(since: &DateTime<FixedOffset>) -> Vec<CommitInfo> {
+    let output = Command::new("git")
+        .args([
+            "log",
+            "--all",
+            &format!("--since={}", since.to_rfc3339()),
+            "--format=%H%x09%cI%x09%s",
+        ])
+        .output();
+    match output {
+        Ok(o) if o.status.success() => parse_log(&String::from_utf8_lossy(&o.stdout)),
+        _ => Vec::new(),
+    }
+}
+
+/// Subject line of a commit, if it's in this repository
+pub fn commit_subject(hash: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["show", "-s", "--format=%s", hash])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn parse_log(log: &str) -> Vec<CommitInfo> {
+    log.lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let hash = parts.next()?.to_string();
+            let time = DateTime::parse_from_rfc3339(parts.next()?).ok()?;
+            let subject = parts.next().unwrap_or("").to_string();
+            Some(CommitInfo {
+                hash,
+                time,
+                subject,
+            })
+        })
+        .collect()
+}
+
+fn parse_time(s: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(s).ok()
+}
+
+fn used_shell(span: &TraceSpan) -> bool {
+    span.tool_names
+        .as_deref()
+        .unwrap_or("")
+        .split(',')
+        .any(|t| SHELL_TOOLS.contains(&t.trim()))
+}
+
+/// The span a commit made at `time` most likely came from
+pub fn span_for_commit<'a>(
+    spans: &'a [TraceSpan],
+    time: &DateTime<FixedOffset>,
+) -> Option<&'a TraceSpan> {
+    let max_gap = Duration::minutes(MAX_GAP_MINUTES);
+    let candidates: Vec<(&TraceSpan, DateTime<FixedOffset>)> = spans
+        .iter()
+        .filter_map(|s| Some((s, parse_time(&s.started_at)?)))
+        .filter(|(s, started)| {
+            let done = s.completed_at.as_deref().and_then(parse_time);
+            *started <= *time && done.map_or(true, |d| *time - d <= max_gap)
+        })
+        .collect();
+
+    let latest = |shell_only: bool| {
+        candidates
+            .iter()
+            .filter(|(s, _)| !shell_only || used_shell(s))
+            .max_by_key(|(_, started)| *started)
+            .map(|(s, _)| *s)
+    };
+    latest(true).or_else(|| latest(false))
+}
+
+/// Pair commits with the spans that made them
+pub fn match_commits<'a, 'b>(
+    spans: &'a [TraceSpan],
+    commits: &'b [CommitInfo],
+) -> Vec<(&'a TraceSpan, &'b CommitInfo)> {
+    commits
+        .iter()
+        .filter_map(|c| Some((span_for_commit(spans, &c.time)?, c)))
+        .collect()
+}
+
+/// Record links for commits made during a session; returns the new links
+///
+/// With `update_nodes`, a matched span's linked node also gets the commit if
+/// it doesn't have one yet.
+pub fn link_session_commits(
+    db: &Database,
+    session_id: &str,
+    update_nodes: bool,
+) -> crate::db::Result<Vec<(i32, CommitInfo)>> {
+    let spans = db.get_trace_spans(session_id)?;
+    let Some(start) = spans.iter().filter_map(|s| parse_time(&s.started_at)).min() else {
+        return Ok(Vec::new());
+    };
+    let commits = commits_since(&start);
+
+    let mut linked = Vec::new();
+    for (span, commit) in match_commits(&spans, &commits) {
+        if !db.link_span_to_commit(span.id, &commit.hash, &commit.time.to_rfc3339())? {
+            continue;
+        }
+        if update_nodes {
+            if let Some(node_id) = span.linked_node_id {
+                let has_commit = db.get_node_by_id(node_id)?.is_some_and(|node| {
+                    node.metadata_json
+                        .as_deref()
+                        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+                        .is_some_and(|m| m.get("commit").is_some())
+                });
+                if !has_commit {
+                    db.update_node_commit(node_id, &commit.hash)?;
+                }
+            }
+        }
+        linked.push((span.id, commit.clone()));
+    }
+    Ok(linked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(id: i32, started: &str, completed: &str, tools: Option<&str>) -> TraceSpan {
+        TraceSpan {
+            started_at: started.to_string(),
+            completed_at: Some(completed.to_string()),
+            tool_names: tools.map(str::to_string),
+            ..TraceSpan::for_test(id)
+        }
+    }
+
+    fn at(time: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(time).unwrap()
+    }
+
+    #[test]
+    fn test_parse_log() {
+        let commits =
+            parse_log("abc123\t2025-06-01T10:05:00+00:00\tFix login\tredirect\nbad line\n");
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].hash, "abc123");
+        assert_eq!(commits[0].subject, "Fix login\tredirect");
+        assert_eq!(commits[0].time, at("2025-06-01T10:05:00+00:00"));
+    }
+
+    #[test]
+    fn test_span_for_commit() {
+        let spans = vec![
+            span(
+                1,
+                "2025-06-01T10:00:00+00:00",
+                "2025-06-01T10:00:20+00:00",
+                Some("Bash"),
+            ),
+            span(
+                2,
+                "2025-06-01T10:00:30+00:00",
+                "2025-06-01T10:00:40+00:00",
+                Some("Read"),
+            ),
+            span(
+                3,
+                "2025-06-01T10:30:00+00:00",
+                "2025-06-01T10:30:10+00:00",
+                Some("Bash"),
+            ),
+        ];
+
+        // Shell span preferred over a later non-shell one
+        let found = span_for_commit(&spans, &at("2025-06-01T10:00:45+00:00"));
+        assert_eq!(found.map(|s| s.id), Some(1));
+
+        // Too long after every span finished
+        assert!(span_for_commit(&spans, &at("2025-06-01T10:20:00+00:00")).is_none());
+
+        // Before any span
+        assert!(span_for_commit(&spans, &at("2025-06-01T09:00:00+00:00")).is_none());
+
+        let commits = vec![CommitInfo {
+            hash: "def456".to_string(),
+            time: at("2025-06-01T10:31:00+00:00"),
+            subject: "Add tests".to_string(),
+        }];
+        let matched = match_commits(&spans, &commits);
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].0.id, 3);
+    }
+}
@@ -0,0 +1,248 @@
+//! Compare two trace sessions span by span
+//!
+//! Spans are grouped into turns (consecutive spans with the same user
+//! message), turns are aligned by user message with a longest common
+//! subsequence, and spans within matched turns are paired in order. Spans
+//! that don't line up are reported as only present on one side.
+
+use crate::db::{TraceContent, TraceSpan};
+use std::collections::{BTreeMap, HashMap};
+
+/// Aligned spans from the two sessions; at least one side is set
+#[derive(Debug, Clone, Copy)]
+pub struct SpanPair<'a> {
+    pub a: Option<&'a TraceSpan>,
+    pub b: Option<&'a TraceSpan>,
+}
+
+impl<'a> SpanPair<'a> {
+    /// User message of the turn the pair belongs to
+    pub fn user(&self) -> Option<&'a str> {
+        self.a.or(self.b).and_then(|s| s.user_preview.as_deref())
+    }
+}
+
+/// Consecutive spans answering the same user message
+pub(crate) fn turns(spans: &[TraceSpan]) -> Vec<&[TraceSpan]> {
+    let mut turns = Vec::new();
+    let mut start = 0;
+    for i in 1..=spans.len() {
+        if i == spans.len() || spans[i].user_preview != spans[start].user_preview {
+            turns.push(&spans[start..i]);
+            start = i;
+        }
+    }
+    turns
+}
+
+fn key(turn: &[TraceSpan]) -> Option<&str> {
+    turn[0].user_preview.as_deref()
+}
+
+/// Pair up the spans of two sessions
+pub fn align<'a>(a: &'a [TraceSpan], b: &'a [TraceSpan]) -> Vec<SpanPair<'a>> {
+    let (ta, tb) = (turns(a), turns(b));
+
+    // lcs[i][j]: matched turns between ta[i..] and tb[j..]
+    let mut lcs = vec![vec![0usize; tb.len() + 1]; ta.len() + 1];
+    for i in (0..ta.len()).rev() {
+        for j in (0..tb.len()).rev() {
+            lcs[i][j] = if key(ta[i]) == key(tb[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let only_a = |turn: &'a [TraceSpan], pairs: &mut Vec<SpanPair<'a>>| {
+        pairs.extend(turn.iter().map(|s| SpanPair {
+            a: Some(s),
+            b: None,
+        }));
+    };
+    let only_b = |turn: &'a [TraceSpan], pairs: &mut Vec<SpanPair<'a>>| {
+        pairs.extend(turn.iter().map(|s| SpanPair {
+            a: None,
+            b: Some(s),
+        }));
+    };
+
+    let (mut i, mut j) = (0, 0);
+    while i < ta.len() && j < tb.len() {
+        if key(ta[i]) == key(tb[j]) {
+            let len = ta[i].len().max(tb[j].len());
+            for k in 0..len {
+                pairs.push(SpanPair {
+                    a: ta[i].get(k),
+                    b: tb[j].get(k),
+                });
+            }
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            only_a(ta[i], &mut pairs);
+            i += 1;
+        } else {
+            only_b(tb[j], &mut pairs);
+            j += 1;
+        }
+    }
+    for &turn in &ta[i..] {
+        only_a(turn, &mut pairs);
+    }
+    for &turn in &tb[j..] {
+        only_b(turn, &mut pairs);
+    }
+    pairs
+}
+
+fn tool_counts(span: &TraceSpan) -> BTreeMap<&str, usize> {
+    let mut counts = BTreeMap::new();
+    for name in span
+        .tool_names
+        .as_deref()
+        .unwrap_or("")
+        .split(',')
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+    {
+        *counts.entry(name).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Tool calls made in `b` but not `a`, and in `a` but not `b`
+pub fn tool_changes(a: &TraceSpan, b: &TraceSpan) -> (Vec<String>, Vec<String>) {
+    let (ca, cb) = (tool_counts(a), tool_counts(b));
+    let extra = |x: &BTreeMap<&str, usize>, y: &BTreeMap<&str, usize>| -> Vec<String> {
+        x.iter()
+            .flat_map(|(name, n)| {
+                let missing = n.saturating_sub(y.get(name).copied().unwrap_or(0));
+                std::iter::repeat(name.to_string()).take(missing)
+            })
+            .collect()
+    };
+    (extra(&cb, &ca), extra(&ca, &cb))
+}
+
+/// A span's full response text, falling back to the stored preview
+pub fn response_text(span: &TraceSpan, content: &HashMap<i32, Vec<TraceContent>>) -> String {
+    let parts: Vec<&str> = content
+        .get(&span.id)
+        .into_iter()
+        .flatten()
+        .filter(|c| c.content_type == "response")
+        .map(|c| c.content.as_str())
+        .collect();
+    if parts.is_empty() {
+        span.response_preview.clone().unwrap_or_default()
+    } else {
+        parts.join("\n\n")
+    }
+}
+
+/// Word overlap between two texts, from 0.0 (nothing shared) to 1.0
+///
+/// Dice coefficient over the bags of whitespace-separated words.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    fn bag(text: &str) -> HashMap<&str, usize> {
+        let mut words = HashMap::new();
+        for word in text.split_whitespace() {
+            *words.entry(word).or_insert(0) += 1;
+        }
+        words
+    }
+    let (wa, wb) = (bag(a), bag(b));
+    let total: usize = wa.values().sum::<usize>() + wb.values().sum::<usize>();
+    if total == 0 {
+        return 1.0;
+    }
+    let shared: usize = wa
+        .iter()
+        .map(|(word, n)| (*n).min(wb.get(word).copied().unwrap_or(0)))
+        .sum();
+    2.0 * shared as f64 / total as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(id: i32, user: &str, tools: Option<&str>) -> TraceSpan {
+        TraceSpan {
+            user_preview: Some(user.to_string()),
+            tool_names: tools.map(str::to_string),
+            ..TraceSpan::for_test(id)
+        }
+    }
+
+    fn ids(pairs: &[SpanPair]) -> Vec<(Option<i32>, Option<i32>)> {
+        pairs
+            .iter()
+            .map(|p| (p.a.map(|s| s.id), p.b.map(|s| s.id)))
+            .collect()
+    }
+
+    #[test]
+    fn test_align_by_turn() {
+        let a = vec![
+            span(1, "fix bug", None),
+            span(2, "fix bug", None),
+            span(3, "add test", None),
+            span(4, "ship it", None),
+        ];
+        let b = vec![
+            span(11, "fix bug", None),
+            span(12, "explain", None),
+            span(13, "ship it", None),
+            span(14, "ship it", None),
+        ];
+        assert_eq!(
+            ids(&align(&a, &b)),
+            vec![
+                (Some(1), Some(11)),
+                (Some(2), None),
+                (Some(3), None),
+                (None, Some(12)),
+                (Some(4), Some(13)),
+                (None, Some(14)),
+            ]
+        );
+
+        // The turn's user message outlives the pair it came from
+        let mut turns = Vec::new();
+        for pair in align(&a, &b) {
+            if turns.last() != Some(&pair.user()) {
+                turns.push(pair.user());
+            }
+        }
+        assert_eq!(
+            turns,
+            vec![
+                Some("fix bug"),
+                Some("add test"),
+                Some("explain"),
+                Some("ship it")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tool_changes() {
+        let a = span(1, "x", Some("Bash,Read,Bash"));
+        let b = span(2, "x", Some("Bash,Edit"));
+        let (added, removed) = tool_changes(&a, &b);
+        assert_eq!(added, vec!["Edit"]);
+        assert_eq!(removed, vec!["Bash", "Read"]);
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("a b c", "a b c"), 1.0);
+        assert_eq!(similarity("a b", "c d"), 0.0);
+        assert!((similarity("a b c d", "a b x y") - 0.5).abs() < 1e-9);
+        assert_eq!(similarity("", ""), 1.0);
+    }
+}
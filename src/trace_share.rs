@@ -0,0 +1,214 @@
+//! Anonymized trace bundles for bug reports
+//!
+//! A bundle keeps the shape of a session — span order, subagent nesting,
+//! timings, token counts, models, stop reasons and tool names — and drops
+//! everything that could leak code or prompts: message text, thinking, tool
+//! inputs and outputs, paths, branch names and ids. Times are offsets from the
+//! session start rather than wall-clock timestamps.
+
+use crate::db::{TraceContent, TraceSession, TraceSpan};
+use chrono::DateTime;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Bumped when the bundle layout changes
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// A self-contained, anonymized session
+#[derive(Debug, Clone, Serialize)]
+pub struct ShareBundle {
+    pub version: u32,
+    pub generator: String,
+    pub session: SharedSession,
+    pub spans: Vec<SharedSpan>,
+}
+
+/// Session totals
+#[derive(Debug, Clone, Serialize)]
+pub struct SharedSession {
+    pub duration_ms: Option<i64>,
+    pub span_count: usize,
+    pub models: Vec<String>,
+    pub total_input_tokens: i32,
+    pub total_output_tokens: i32,
+    pub total_cache_read: i32,
+    pub total_cache_write: i32,
+}
+
+/// One span, numbered from 1 in session order
+#[derive(Debug, Clone, Serialize)]
+pub struct SharedSpan {
+    pub index: usize,
+    /// Index of the span whose `Task` call started this subagent span
+    pub parent: Option<usize>,
+    pub start_offset_ms: Option<i64>,
+    pub duration_ms: Option<i32>,
+    pub model: Option<String>,
+    pub stop_reason: Option<String>,
+    pub input_tokens: Option<i32>,
+    pub output_tokens: Option<i32>,
+    pub cache_read: Option<i32>,
+    pub cache_write: Option<i32>,
+    pub tools: Vec<String>,
+    /// Tool results in this span's request that were errors
+    pub tool_errors: usize,
+}
+
+fn millis_since(start: &str, time: &str) -> Option<i64> {
+    let start = DateTime::parse_from_rfc3339(start).ok()?;
+    let time = DateTime::parse_from_rfc3339(time).ok()?;
+    Some((time - start).num_milliseconds())
+}
+
+/// Build a bundle; `parents` maps subagent span ids to their parent span ids
+pub fn build_bundle(
+    session: &TraceSession,
+    spans: &[TraceSpan],
+    parents: &HashMap<i32, i32>,
+    content: &HashMap<i32, Vec<TraceContent>>,
+) -> ShareBundle {
+    let index_of: HashMap<i32, usize> = spans
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.id, i + 1))
+        .collect();
+
+    let mut models: Vec<String> = Vec::new();
+    for model in spans.iter().filter_map(|s| s.model.as_ref()) {
+        if !models.contains(model) {
+            models.push(model.clone());
+        }
+    }
+
+    let shared = spans
+        .iter()
+        .enumerate()
+        .map(|(i, span)| SharedSpan {
+            index: i + 1,
+            parent: parents.get(&span.id).and_then(|p| index_of.get(p)).copied(),
+            start_offset_ms: millis_since(&session.started_at, &span.started_at),
+            duration_ms: span.duration_ms,
+            model: span.model.clone(),
+            stop_reason: span.stop_reason.clone(),
+            input_tokens: span.input_tokens,
+            output_tokens: span.output_tokens,
+            cache_read: span.cache_read,
+            cache_write: span.cache_write,
+            tools: span
+                .tool_names
+                .as_deref()
+                .unwrap_or("")
+                .split(',')
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+                .collect(),
+            tool_errors: content
+                .get(&span.id)
+                .into_iter()
+                .flatten()
+                .filter(|c| c.content_type == "tool_error")
+                .count(),
+        })
+        .collect();
+
+    ShareBundle {
+        version: BUNDLE_VERSION,
+        generator: format!("deciduous {}", env!("CARGO_PKG_VERSION")),
+        session: SharedSession {
+            duration_ms: session
+                .ended_at
+                .as_deref()
+                .and_then(|end| millis_since(&session.started_at, end)),
+            span_count: spans.len(),
+            models,
+            total_input_tokens: session.total_input_tokens,
+            total_output_tokens: session.total_output_tokens,
+            total_cache_read: session.total_cache_read,
+            total_cache_write: session.total_cache_write,
+        },
+        spans: shared,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(id: i32, started: &str, tools: Option<&str>) -> TraceSpan {
+        TraceSpan {
+            session_id: "secret-session".to_string(),
+            started_at: started.to_string(),
+            duration_ms: Some(1500),
+            model: Some("claude-sonnet-4".to_string()),
+            request_id: Some("req_secret".to_string()),
+            stop_reason: Some("tool_use".to_string()),
+            input_tokens: Some(100),
+            output_tokens: Some(20),
+            user_preview: Some("fix /home/alice/secret-project".to_string()),
+            thinking_preview: Some("the API key is in .env".to_string()),
+            response_preview: Some("Reading the config".to_string()),
+            tool_names: tools.map(str::to_string),
+            linked_node_id: Some(3),
+            ..TraceSpan::for_test(id)
+        }
+    }
+
+    #[test]
+    fn test_build_bundle() {
+        let session = TraceSession {
+            id: 1,
+            session_id: "secret-session".to_string(),
+            started_at: "2025-06-01T10:00:00+00:00".to_string(),
+            ended_at: Some("2025-06-01T10:01:00+00:00".to_string()),
+            working_dir: Some("/home/alice/secret-project".to_string()),
+            git_branch: Some("alice/secret-feature".to_string()),
+            command: Some("claude".to_string()),
+            summary: Some("Secret work".to_string()),
+            total_input_tokens: 200,
+            total_output_tokens: 40,
+            total_cache_read: 0,
+            total_cache_write: 0,
+            linked_node_id: None,
+            linked_change_id: None,
+        };
+        let spans = vec![
+            span(7, "2025-06-01T10:00:00+00:00", Some("Task")),
+            span(8, "2025-06-01T10:00:02.500+00:00", Some("Bash, Read")),
+        ];
+        let parents = HashMap::from([(8, 7)]);
+        let content = HashMap::from([(
+            8,
+            vec![TraceContent {
+                id: 1,
+                span_id: 8,
+                content_type: "tool_error".to_string(),
+                tool_name: Some("Bash".to_string()),
+                tool_use_id: Some("tu_1".to_string()),
+                content: "permission denied: /home/alice".to_string(),
+                sequence_num: 0,
+            }],
+        )]);
+
+        let bundle = build_bundle(&session, &spans, &parents, &content);
+        assert_eq!(bundle.session.duration_ms, Some(60_000));
+        assert_eq!(bundle.session.models, vec!["claude-sonnet-4"]);
+        assert_eq!(bundle.spans[1].index, 2);
+        assert_eq!(bundle.spans[1].parent, Some(1));
+        assert_eq!(bundle.spans[1].start_offset_ms, Some(2500));
+        assert_eq!(bundle.spans[1].tools, vec!["Bash", "Read"]);
+        assert_eq!(bundle.spans[1].tool_errors, 1);
+
+        let json = serde_json::to_string(&bundle).unwrap();
+        for leak in [
+            "secret",
+            "alice",
+            "req_",
+            "API key",
+            "Reading",
+            "2025-06-01",
+        ] {
+            assert!(!json.contains(leak), "bundle contains {:?}", leak);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! `Deciduous: #42` commit trailers
+//!
+//! A commit message line like `Deciduous: #42, #57` names the nodes the
+//! commit implements. `deciduous audit --from-trailers` reads them from git
+//! log and sets each referenced node's commit, with no keyword guessing.
+
+use crate::db::DecisionNode;
+use std::collections::{HashMap, HashSet};
+use std::process::Command;
+
+/// The trailer key, matched case-insensitively
+pub const TRAILER: &str = "Deciduous";
+
+/// Node IDs named by `Deciduous:` trailers in a commit message, in order
+pub fn parse_refs(message: &str) -> Vec<i32> {
+    let mut ids = Vec::new();
+    for line in message.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        if !key.trim().eq_ignore_ascii_case(TRAILER) {
+            continue;
+        }
+        for id in value
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter_map(|r| r.trim_start_matches('#').parse::<i32>().ok())
+        {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    ids
+}
+
+/// A commit whose message has `Deciduous:` trailers
+#[derive(Debug, Clone)]
+pub struct TrailerCommit {
+    pub hash: String,
+    pub subject: String,
+    pub node_ids: Vec<i32>,
+}
+
+/// Commits with trailers, newest first
+pub fn commits_with_trailers() -> Vec<TrailerCommit> {
+    let output = Command::new("git")
+        .args([
+            "log",
+            "-i",
+            &format!("--grep=^{}:", TRAILER),
+            "--format=%H%x00%s%x00%B%x1e",
+        ])
+        .output()
+        .ok()
+        .filter(|o| o.status.success());
+    let Some(output) = output else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut parts = record.trim_start_matches('\n').splitn(3, '\0');
+            let hash = parts.next()?.to_string();
+            let subject = parts.next()?.to_string();
+            let node_ids = parse_refs(parts.next()?);
+            (!node_ids.is_empty()).then_some(TrailerCommit {
+                hash,
+                subject,
+                node_ids,
+            })
+        })
+        .collect()
+}
+
+/// What the trailers say to do
+#[derive(Debug, Default)]
+pub struct TrailerPlan {
+    /// (node ID, commit hash) for nodes without a commit, from the newest
+    /// commit naming them
+    pub links: Vec<(i32, String)>,
+    /// (node ID, commit hash) already linked to that commit or another
+    pub already_linked: Vec<(i32, String)>,
+    /// (node ID, commit hash) naming a node that doesn't exist
+    pub unknown: Vec<(i32, String)>,
+}
+
+/// Match `commits` (newest first) against `nodes`
+pub fn plan(nodes: &[DecisionNode], commits: &[TrailerCommit]) -> TrailerPlan {
+    let existing: HashMap<i32, Option<String>> = nodes
+        .iter()
+        .map(|n| {
+            let commit = n
+                .metadata_json
+                .as_deref()
+                .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+                .and_then(|m| m["commit"].as_str().map(String::from))
+                .filter(|c| !c.is_empty());
+            (n.id, commit)
+        })
+        .collect();
+    let mut plan = TrailerPlan::default();
+    let mut seen = HashSet::new();
+    for commit in commits {
+        for &id in &commit.node_ids {
+            if !seen.insert(id) {
+                continue;
+            }
+            let entry = (id, commit.hash.clone());
+            match existing.get(&id) {
+                None => plan.unknown.push(entry),
+                Some(Some(_)) => plan.already_linked.push(entry),
+                Some(None) => plan.links.push(entry),
+            }
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn test_trailers() {
+        assert_eq!(
+            parse_refs("Add paging\n\nBody text: #9\n\nDeciduous: #42, #57\ndeciduous: 42 #60"),
+            vec![42, 57, 60]
+        );
+        assert!(parse_refs("Fix #42").is_empty());
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let action = db.node("action", "Add paging").insert().unwrap();
+        let linked = db
+            .node("action", "Stream export")
+            .commit("old")
+            .insert()
+            .unwrap();
+        let commit = |hash: &str, node_ids: Vec<i32>| TrailerCommit {
+            hash: hash.to_string(),
+            subject: String::new(),
+            node_ids,
+        };
+        let commits = vec![
+            commit("newest", vec![action, 999]),
+            commit("older", vec![action, linked]),
+        ];
+        let plan = plan(&db.get_all_nodes().unwrap(), &commits);
+        assert_eq!(plan.links, vec![(action, "newest".to_string())]);
+        assert_eq!(plan.already_linked, vec![(linked, "older".to_string())]);
+        assert_eq!(plan.unknown, vec![(999, "newest".to_string())]);
+    }
+}
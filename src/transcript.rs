@@ -0,0 +1,462 @@
+//! Shareable transcripts of trace sessions
+//!
+//! Renders a session's spans in order — user message, thinking (optional),
+//! response, and tool calls with their results — as Markdown or a
+//! self-contained HTML page, for reviewing what an agent actually did.
+
+use crate::db::{TraceContent, TraceSession, TraceSpan};
+use crate::pricing::{format_cost, PriceTable};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+macro_rules! wln {
+    ($dst:expr) => {
+        let _ = writeln!($dst);
+    };
+    ($dst:expr, $($arg:tt)*) => {
+        let _ = writeln!($dst, $($arg)*);
+    };
+}
+
+/// Output format for a transcript
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptFormat {
+    Markdown,
+    Html,
+}
+
+impl TranscriptFormat {
+    /// HTML for `.html`/`.htm` output paths, Markdown otherwise
+    pub fn from_path(path: &std::path::Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("html") | Some("htm") => TranscriptFormat::Html,
+            _ => TranscriptFormat::Markdown,
+        }
+    }
+}
+
+/// One piece of a transcript, in reading order
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Span {
+        title: String,
+    },
+    User(String),
+    Thinking(String),
+    Response(String),
+    Tool {
+        name: String,
+        input: String,
+        output: Option<(String, bool)>,
+    },
+}
+
+fn blocks(
+    spans: &[TraceSpan],
+    content: &HashMap<i32, Vec<TraceContent>>,
+    thinking: bool,
+    prices: &PriceTable,
+) -> Vec<Block> {
+    // Tool results arrive with the request after the call, so index them by id
+    let results: HashMap<&str, (&str, bool)> = content
+        .values()
+        .flatten()
+        .filter(|c| c.content_type == "tool_output" || c.content_type == "tool_error")
+        .filter_map(|c| {
+            let id = c.tool_use_id.as_deref()?;
+            Some((id, (c.content.as_str(), c.content_type == "tool_error")))
+        })
+        .collect();
+
+    let mut out = Vec::new();
+    for span in spans {
+        let mut title = format!(
+            "{}. {}",
+            span.sequence_num,
+            span.model.as_deref().unwrap_or("unknown model")
+        );
+        if let Some(ms) = span.duration_ms {
+            let _ = write!(title, " · {:.1}s", f64::from(ms) / 1000.0);
+        }
+        if let (Some(i), Some(o)) = (span.input_tokens, span.output_tokens) {
+            let _ = write!(title, " · {}↓ {}↑", i, o);
+        }
+        if let Some(cost) = prices.span_cost(span) {
+            let _ = write!(title, " · {}", format_cost(cost));
+        }
+        out.push(Block::Span { title });
+
+        if let Some(user) = &span.user_preview {
+            out.push(Block::User(user.clone()));
+        }
+
+        let items = content.get(&span.id).map(Vec::as_slice).unwrap_or(&[]);
+        let text_of = |kind: &str| -> Option<String> {
+            let parts: Vec<&str> = items
+                .iter()
+                .filter(|c| c.content_type == kind)
+                .map(|c| c.content.as_str())
+                .collect();
+            (!parts.is_empty()).then(|| parts.join("\n\n"))
+        };
+
+        if thinking {
+            if let Some(text) = text_of("thinking").or_else(|| span.thinking_preview.clone()) {
+                out.push(Block::Thinking(text));
+            }
+        }
+        if let Some(text) = text_of("response").or_else(|| span.response_preview.clone()) {
+            out.push(Block::Response(text));
+        }
+        for call in items.iter().filter(|c| c.content_type == "tool_input") {
+            out.push(Block::Tool {
+                name: call.tool_name.clone().unwrap_or_else(|| "tool".to_string()),
+                input: call.content.clone(),
+                output: call
+                    .tool_use_id
+                    .as_deref()
+                    .and_then(|id| results.get(id))
+                    .map(|(text, is_error)| (text.to_string(), *is_error)),
+            });
+        }
+    }
+    out
+}
+
+/// Session facts shown at the top of the transcript
+fn header(
+    session: &TraceSession,
+    spans: &[TraceSpan],
+    prices: &PriceTable,
+) -> Vec<(String, String)> {
+    let mut facts = vec![("Started".to_string(), session.started_at.clone())];
+    if let Some(command) = &session.command {
+        facts.push(("Command".to_string(), command.clone()));
+    }
+    if let Some(branch) = &session.git_branch {
+        facts.push(("Branch".to_string(), branch.clone()));
+    }
+    facts.push((
+        "Usage".to_string(),
+        format!(
+            "{} spans · {}↓ {}↑ · {}",
+            spans.len(),
+            session.total_input_tokens,
+            session.total_output_tokens,
+            format_cost(prices.total_cost(spans))
+        ),
+    ));
+    if let Some(node) = session.linked_node_id {
+        facts.push(("Linked node".to_string(), format!("#{}", node)));
+    }
+    if let Some(summary) = &session.summary {
+        facts.push(("Summary".to_string(), summary.clone()));
+    }
+    facts
+}
+
+/// A code fence longer than any backtick run in `text`
+fn fence(text: &str) -> String {
+    let longest = text
+        .split(|c: char| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_markdown(title: &str, facts: &[(String, String)], blocks: &[Block]) -> String {
+    let mut md = String::new();
+    wln!(md, "# {}", title);
+    wln!(md);
+    for (label, value) in facts {
+        wln!(md, "- **{}:** {}", label, value);
+    }
+
+    for block in blocks {
+        wln!(md);
+        match block {
+            Block::Span { title } => {
+                wln!(md, "---");
+                wln!(md);
+                wln!(md, "## {}", title);
+            }
+            Block::User(text) => {
+                wln!(md, "**User**");
+                wln!(md);
+                for line in text.lines() {
+                    wln!(md, "> {}", line);
+                }
+            }
+            Block::Thinking(text) => {
+                wln!(md, "<details><summary>Thinking</summary>");
+                wln!(md);
+                wln!(md, "{}", text.trim_end());
+                wln!(md);
+                wln!(md, "</details>");
+            }
+            Block::Response(text) => {
+                wln!(md, "**Response**");
+                wln!(md);
+                wln!(md, "{}", text.trim_end());
+            }
+            Block::Tool {
+                name,
+                input,
+                output,
+            } => {
+                let f = fence(input);
+                wln!(md, "**Tool: {}**", name);
+                wln!(md);
+                wln!(md, "{}json", f);
+                wln!(md, "{}", input.trim_end());
+                wln!(md, "{}", f);
+                if let Some((text, is_error)) = output {
+                    let f = fence(text);
+                    wln!(md);
+                    wln!(
+                        md,
+                        "<details><summary>{}</summary>",
+                        if *is_error { "Error" } else { "Output" }
+                    );
+                    wln!(md);
+                    wln!(md, "{}", f);
+                    wln!(md, "{}", text.trim_end());
+                    wln!(md, "{}", f);
+                    wln!(md);
+                    wln!(md, "</details>");
+                }
+            }
+        }
+    }
+    md
+}
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,BlinkMacSystemFont,sans-serif;max-width:860px;margin:2em auto;padding:0 1em;color:#24292f;line-height:1.5}\
+h2{border-top:1px solid #d0d7de;padding-top:1em;font-size:1.1em}\
+.user{background:#eff6ff;border-left:3px solid #3b82f6;padding:.5em 1em;white-space:pre-wrap}\
+.thinking{background:#fef3c7;padding:.5em 1em;white-space:pre-wrap}\
+.response{white-space:pre-wrap}\
+pre{background:#f6f8fa;padding:.75em;overflow-x:auto;white-space:pre-wrap}\
+.tool{color:#7c3aed;font-weight:600}\
+.error{color:#dc2626}";
+
+fn render_html(title: &str, facts: &[(String, String)], blocks: &[Block]) -> String {
+    let mut html = String::new();
+    wln!(html, "<!DOCTYPE html>");
+    wln!(html, "<html><head><meta charset=\"utf-8\">");
+    wln!(html, "<title>{}</title>", escape_html(title));
+    wln!(html, "<style>{}</style>", HTML_STYLE);
+    wln!(html, "</head><body>");
+    wln!(html, "<h1>{}</h1>", escape_html(title));
+    wln!(html, "<ul>");
+    for (label, value) in facts {
+        wln!(
+            html,
+            "<li><strong>{}:</strong> {}</li>",
+            escape_html(label),
+            escape_html(value)
+        );
+    }
+    wln!(html, "</ul>");
+
+    for block in blocks {
+        match block {
+            Block::Span { title } => {
+                wln!(html, "<h2>{}</h2>", escape_html(title));
+            }
+            Block::User(text) => {
+                wln!(html, "<div class=\"user\">{}</div>", escape_html(text));
+            }
+            Block::Thinking(text) => {
+                wln!(
+                    html,
+                    "<details><summary>Thinking</summary><div class=\"thinking\">{}</div></details>",
+                    escape_html(text)
+                );
+            }
+            Block::Response(text) => {
+                wln!(
+                    html,
+                    "<div class=\"response\">{}</div>",
+                    escape_html(text.trim_end())
+                );
+            }
+            Block::Tool {
+                name,
+                input,
+                output,
+            } => {
+                wln!(html, "<p class=\"tool\">Tool: {}</p>", escape_html(name));
+                wln!(html, "<pre>{}</pre>", escape_html(input));
+                if let Some((text, is_error)) = output {
+                    wln!(
+                        html,
+                        "<details><summary{}>{}</summary><pre>{}</pre></details>",
+                        if *is_error { " class=\"error\"" } else { "" },
+                        if *is_error { "Error" } else { "Output" },
+                        escape_html(text)
+                    );
+                }
+            }
+        }
+    }
+    wln!(html, "</body></html>");
+    html
+}
+
+/// Render a session transcript
+///
+/// `content` holds each span's stored content, keyed by span id.
+pub fn session_transcript(
+    session: &TraceSession,
+    spans: &[TraceSpan],
+    content: &HashMap<i32, Vec<TraceContent>>,
+    format: TranscriptFormat,
+    thinking: bool,
+    prices: &PriceTable,
+) -> String {
+    let title = format!(
+        "Session {}",
+        &session.session_id[..8.min(session.session_id.len())]
+    );
+    let facts = header(session, spans, prices);
+    let blocks = blocks(spans, content, thinking, prices);
+    match format {
+        TranscriptFormat::Markdown => render_markdown(&title, &facts, &blocks),
+        TranscriptFormat::Html => render_html(&title, &facts, &blocks),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> TraceSession {
+        TraceSession {
+            id: 1,
+            session_id: "abcdef12-0000".to_string(),
+            started_at: "2025-06-01T10:00:00+00:00".to_string(),
+            ended_at: None,
+            working_dir: None,
+            git_branch: Some("main".to_string()),
+            command: Some("claude".to_string()),
+            summary: None,
+            total_input_tokens: 30,
+            total_output_tokens: 12,
+            total_cache_read: 0,
+            total_cache_write: 0,
+            linked_node_id: None,
+            linked_change_id: None,
+        }
+    }
+
+    fn span(id: i32, user: Option<&str>) -> TraceSpan {
+        TraceSpan {
+            session_id: "abcdef12-0000".to_string(),
+            duration_ms: Some(1200),
+            model: Some("claude-sonnet-4".to_string()),
+            input_tokens: Some(15),
+            output_tokens: Some(6),
+            user_preview: user.map(str::to_string),
+            ..TraceSpan::for_test(id)
+        }
+    }
+
+    fn fixture() -> (Vec<TraceSpan>, HashMap<i32, Vec<TraceContent>>) {
+        let spans = vec![span(1, Some("List the files")), span(2, None)];
+        let mut content = HashMap::new();
+        content.insert(
+            1,
+            vec![
+                TraceContent::for_test(1, "thinking", "I should run ls", None),
+                TraceContent::for_test(1, "response", "Listing now.", None),
+                TraceContent::for_test(
+                    1,
+                    "tool_input",
+                    r#"{"command":"ls"}"#,
+                    Some(("Bash", "tu_1")),
+                ),
+            ],
+        );
+        content.insert(
+            2,
+            vec![
+                TraceContent::for_test(2, "tool_output", "README.md\nsrc", Some(("Bash", "tu_1"))),
+                TraceContent::for_test(2, "response", "Two entries <done>.", None),
+            ],
+        );
+        (spans, content)
+    }
+
+    #[test]
+    fn test_markdown_transcript() {
+        let (spans, content) = fixture();
+        let prices = PriceTable::default();
+        let md = session_transcript(
+            &session(),
+            &spans,
+            &content,
+            TranscriptFormat::Markdown,
+            false,
+            &prices,
+        );
+        assert!(md.starts_with("# Session abcdef12\n"));
+        assert!(md.contains("- **Command:** claude"));
+        assert!(md.contains("## 1. claude-sonnet-4 · 1.2s · 15↓ 6↑"));
+        assert!(md.contains("> List the files"));
+        assert!(!md.contains("I should run ls"));
+        // The result recorded with span 2 is shown under the call from span 1
+        let call = md.find("**Tool: Bash**").unwrap();
+        let output = md.find("README.md\nsrc").unwrap();
+        let second = md.find("## 2.").unwrap();
+        assert!(call < output && output < second);
+
+        let with_thinking = session_transcript(
+            &session(),
+            &spans,
+            &content,
+            TranscriptFormat::Markdown,
+            true,
+            &prices,
+        );
+        assert!(with_thinking.contains("I should run ls"));
+    }
+
+    #[test]
+    fn test_html_transcript_escapes() {
+        let (spans, content) = fixture();
+        let html = session_transcript(
+            &session(),
+            &spans,
+            &content,
+            TranscriptFormat::Html,
+            false,
+            &PriceTable::default(),
+        );
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("Two entries &lt;done&gt;."));
+        assert!(html.contains("{&quot;command&quot;:&quot;ls&quot;}"));
+    }
+
+    #[test]
+    fn test_fence_and_format() {
+        assert_eq!(fence("plain"), "```");
+        assert_eq!(fence("has ```` inside"), "`````");
+        assert_eq!(
+            TranscriptFormat::from_path(std::path::Path::new("s.html")),
+            TranscriptFormat::Html
+        );
+        assert_eq!(
+            TranscriptFormat::from_path(std::path::Path::new("s.md")),
+            TranscriptFormat::Markdown
+        );
+    }
+}
@@ -1,5 +1,6 @@
 //! Application state for the TUI
 
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::time::Instant;
 
@@ -12,6 +13,7 @@ use syntect::parsing::SyntaxSet;
 use super::types;
 use super::views::roadmap::RoadmapState;
 use super::views::trace::TraceState;
+use crate::pricing::PriceTable;
 use crate::{Database, DecisionEdge, DecisionGraph, DecisionNode};
 
 // Lazy static syntax highlighting resources
@@ -474,6 +476,8 @@ impl App {
 
     /// Load roadmap items from database
     pub fn load_roadmap_items(&mut self) {
+        // Stale issue state is refreshed in the background; the DB watcher reloads us
+        crate::cache::refresh_expired_in_background(&crate::config::Config::load());
         match self.db.get_all_roadmap_items() {
             Ok(items) => {
                 self.roadmap_state.set_items(items);
@@ -492,6 +496,18 @@ impl App {
         match self.db.get_trace_sessions(100) {
             Ok(sessions) => {
                 self.trace_state.set_sessions(sessions);
+
+                // Costs depend on each span's model, so sum them per session
+                if let Ok(spans) = self.db.get_all_trace_spans() {
+                    let prices = PriceTable::from_config(&crate::config::Config::load());
+                    let mut costs: HashMap<String, f64> = HashMap::new();
+                    for span in &spans {
+                        if let Some(cost) = prices.span_cost(span) {
+                            *costs.entry(span.session_id.clone()).or_insert(0.0) += cost;
+                        }
+                    }
+                    self.trace_state.set_session_costs(costs);
+                }
             }
             Err(e) => {
                 self.set_status(format!("Failed to load traces: {}", e));
@@ -503,14 +519,36 @@ impl App {
     pub fn load_trace_spans(&mut self, session_id: &str) {
         match self.db.get_trace_spans(session_id) {
             Ok(spans) => {
+                // Subagent spans go right after the span that started them
+                let parents = crate::subagents::link_session_subagents(&self.db, session_id)
+                    .unwrap_or_default();
+                let ordered = crate::subagents::tree_order(&spans, &parents);
+                let depths = ordered.iter().map(|(s, d)| (s.id, *d)).collect();
+                let spans: Vec<crate::db::TraceSpan> =
+                    ordered.into_iter().map(|(s, _)| s.clone()).collect();
+                self.trace_state.set_span_depths(depths);
+
                 // Get span IDs for node count lookup
                 let span_ids: Vec<i32> = spans.iter().map(|s| s.id).collect();
+                let prices = PriceTable::from_config(&crate::config::Config::load());
+                let costs = spans
+                    .iter()
+                    .filter_map(|s| prices.span_cost(s).map(|c| (s.id, c)))
+                    .collect();
                 self.trace_state.set_spans(spans);
+                self.trace_state.set_span_costs(costs);
 
                 // Load node counts for all spans
                 if let Ok(counts) = self.db.get_node_counts_for_spans(&span_ids) {
                     self.trace_state.set_node_counts(counts);
                 }
+                if let Ok(annotations) = self.db.get_annotations_for_spans(&span_ids) {
+                    let notes = annotations
+                        .into_iter()
+                        .map(|(id, a)| (id, a.into_iter().map(|a| a.note).collect()))
+                        .collect();
+                    self.trace_state.set_span_notes(notes);
+                }
             }
             Err(e) => {
                 self.set_status(format!("Failed to load spans: {}", e));
@@ -384,10 +384,10 @@ mod tests {
         DecisionNode {
             id,
             change_id: format!("change-{}", id),
-            node_type: node_type.to_string(),
+            node_type: node_type.into(),
             title: title.to_string(),
             description: None,
-            status: "pending".to_string(),
+            status: "pending".into(),
             created_at: format!("2024-12-10T12:00:0{}Z", id),
             updated_at: format!("2024-12-10T12:00:0{}Z", id),
             metadata_json: metadata.map(|s| s.to_string()),
@@ -401,7 +401,7 @@ mod tests {
             to_node_id: to,
             from_change_id: None,
             to_change_id: None,
-            edge_type: "leads_to".to_string(),
+            edge_type: "leads_to".into(),
             weight: Some(1.0),
             rationale: None,
             created_at: "2024-12-10T12:00:00Z".to_string(),
@@ -573,10 +573,10 @@ mod tests {
         DecisionNode {
             id,
             change_id: format!("change-{}", id),
-            node_type: node_type.to_string(),
+            node_type: node_type.into(),
             title: title.to_string(),
             description: None,
-            status: "pending".to_string(),
+            status: "pending".into(),
             created_at: "2024-12-10T12:00:00Z".to_string(),
             updated_at: updated_at.to_string(),
             metadata_json: None,
@@ -823,10 +823,10 @@ mod proptests {
             let nodes: Vec<DecisionNode> = (0..node_count)
                 .map(|i| DecisionNode {
                     id: i as i32,
-                    node_type: if i % 3 == 0 { "goal" } else { "action" }.to_string(),
+                    node_type: if i % 3 == 0 { "goal" } else { "action" }.into(),
                     title: format!("Node {}", i),
                     description: None,
-                    status: "pending".to_string(),
+                    status: "pending".into(),
                     created_at: "2024-01-01".to_string(),
                     updated_at: "2024-01-01".to_string(),
                     metadata_json: None,
@@ -850,10 +850,10 @@ mod proptests {
             let nodes: Vec<DecisionNode> = (0..node_count)
                 .map(|i| DecisionNode {
                     id: i as i32,
-                    node_type: "action".to_string(),
+                    node_type: "action".into(),
                     title: format!("Node {}", i),
                     description: None,
-                    status: "pending".to_string(),
+                    status: "pending".into(),
                     created_at: "2024-01-01".to_string(),
                     updated_at: "2024-01-01".to_string(),
                     metadata_json: None,
@@ -870,10 +870,10 @@ mod proptests {
             let nodes: Vec<DecisionNode> = (0..node_count)
                 .map(|i| DecisionNode {
                     id: i as i32,
-                    node_type: "action".to_string(),
+                    node_type: "action".into(),
                     title: format!("Node {}", i),
                     description: None,
-                    status: "pending".to_string(),
+                    status: "pending".into(),
                     created_at: format!("2024-01-{:02}", (i % 28) + 1),
                     updated_at: "2024-01-01".to_string(),
                     metadata_json: None,
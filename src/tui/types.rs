@@ -49,6 +49,8 @@ pub struct NodeMetadata {
     pub files: Vec<String>,
     /// Git branch this node was created on
     pub branch: Option<String>,
+    /// Linked pull request number
+    pub pr: Option<i32>,
 }
 
 impl NodeMetadata {
@@ -82,6 +84,7 @@ impl NodeMetadata {
                     .get("branch")
                     .and_then(|b| b.as_str())
                     .map(|s| s.to_string()),
+                pr: v.get("pr").and_then(|p| p.as_i64()).map(|p| p as i32),
             })
             .unwrap_or_default()
     }
@@ -111,6 +114,11 @@ pub fn get_branch(node: &DecisionNode) -> Option<String> {
     NodeMetadata::from_option(node.metadata_json.as_ref()).branch
 }
 
+/// Extract linked PR number from a node (mirrors getPr in TypeScript)
+pub fn get_pr(node: &DecisionNode) -> Option<i32> {
+    NodeMetadata::from_option(node.metadata_json.as_ref()).pr
+}
+
 /// Extract files from a node (mirrors getFiles in TypeScript)
 pub fn get_files(node: &DecisionNode) -> Vec<String> {
     NodeMetadata::from_option(node.metadata_json.as_ref()).files
@@ -196,10 +204,10 @@ mod tests {
         DecisionNode {
             id,
             change_id: format!("test-change-{}", id),
-            node_type: node_type.to_string(),
+            node_type: node_type.into(),
             title: title.to_string(),
             description: None,
-            status: "pending".to_string(),
+            status: "pending".into(),
             created_at: "2024-12-10T12:00:00Z".to_string(),
             updated_at: "2024-12-10T12:00:00Z".to_string(),
             metadata_json: metadata_json.map(|s| s.to_string()),
@@ -213,7 +221,7 @@ mod tests {
             to_node_id: to_id,
             from_change_id: None,
             to_change_id: None,
-            edge_type: edge_type.to_string(),
+            edge_type: edge_type.into(),
             weight: Some(1.0),
             rationale: None,
             created_at: "2024-12-10T12:00:00Z".to_string(),
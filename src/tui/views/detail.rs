@@ -6,6 +6,7 @@ use ratatui::{
 };
 
 use crate::tui::app::App;
+use crate::tui::types;
 use crate::tui::ui::{node_type_color, node_type_style};
 
 /// Draw the detail panel for the selected node
@@ -298,10 +299,16 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
 
         // Run git log to get commit info
         if let Ok(output) = std::process::Command::new("git")
-            .args(["log", "-1", "--format=%h %s%n%n%b", hash])
+            .args(["log", "-1", "--format=%an%x00%ae%x00%h %s%n%n%b", hash])
             .output()
         {
-            let commit_info = String::from_utf8_lossy(&output.stdout);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let mut parts = stdout.splitn(3, '\0');
+            let (name, email, commit_info) = (
+                parts.next().unwrap_or(""),
+                parts.next().unwrap_or(""),
+                parts.next().unwrap_or(""),
+            );
             for line in commit_info.lines() {
                 if !line.is_empty() {
                     lines.push(Line::from(Span::styled(
@@ -310,6 +317,14 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
                     )));
                 }
             }
+            if !name.is_empty() {
+                // Teammates show under their roster name
+                let who = crate::config::Config::load().commit_author(name, email);
+                lines.push(Line::from(vec![
+                    Span::styled("By: ", Style::default().fg(Color::DarkGray)),
+                    Span::styled(who, Style::default().fg(Color::Cyan)),
+                ]));
+            }
         } else {
             lines.push(Line::from(vec![
                 Span::styled("Hash: ", Style::default().fg(Color::DarkGray)),
@@ -327,6 +342,28 @@ pub fn draw(frame: &mut Frame, app: &App, area: Rect) {
         ]));
     }
 
+    // Linked pull request (state from the local PR cache)
+    if let Some(number) = types::get_pr(node) {
+        let mut spans = vec![
+            Span::styled("PR: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(format!("#{}", number), Style::default().fg(Color::Cyan)),
+        ];
+        if let Some(pr) = app.graph.pull_request_for(node) {
+            let state_color = match pr.state.as_str() {
+                "merged" => Color::Magenta,
+                "closed" => Color::Red,
+                _ => Color::Green,
+            };
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled(
+                format!("[{}]", pr.state),
+                Style::default().fg(state_color).bold(),
+            ));
+            spans.push(Span::raw(format!(" {}", pr.title)));
+        }
+        lines.push(Line::from(spans));
+    }
+
     lines.push(Line::from(""));
 
     // Action hints
@@ -54,13 +54,7 @@ pub struct RoadmapState {
 /// - Checkbox is checked, OR
 /// - It's in a "Completed" section (case-insensitive)
 pub fn is_item_complete(item: &RoadmapItem) -> bool {
-    let checkbox_checked = item.checkbox_state == "checked";
-    let in_completed_section = item
-        .section
-        .as_deref()
-        .map(|s| s.to_lowercase().contains("completed"))
-        .unwrap_or(false);
-    checkbox_checked || in_completed_section
+    item.is_complete()
 }
 
 /// Check if an item is fully synced (all three criteria met).
@@ -112,6 +106,24 @@ pub fn filter_by_mode(items: &[RoadmapItem], mode: RoadmapViewMode) -> Vec<Roadm
     }
 }
 
+/// Nesting depth of an item: how many parent checkbox items enclose it
+///
+/// Section headers don't count, so top-level checkboxes are at depth 0.
+pub fn item_depth(item: &RoadmapItem, all_items: &[RoadmapItem]) -> usize {
+    let mut depth = 0;
+    let mut parent_id = item.parent_id;
+
+    while let Some(parent) = parent_id.and_then(|id| all_items.iter().find(|i| i.id == id)) {
+        if is_section_header(parent) || depth > all_items.len() {
+            break;
+        }
+        depth += 1;
+        parent_id = parent.parent_id;
+    }
+
+    depth
+}
+
 /// Group items by section
 pub fn group_by_section(items: &[RoadmapItem]) -> Vec<(String, Vec<&RoadmapItem>)> {
     use std::collections::BTreeMap;
@@ -395,6 +407,7 @@ fn draw_list(frame: &mut Frame, state: &RoadmapState, area: Rect) {
                 let is_selected = idx == state.selected_index;
                 list_items.push(render_item_grouped(
                     item,
+                    item_depth(item, &state.all_items),
                     display_idx,
                     is_selected,
                     inner_area.width,
@@ -496,12 +509,14 @@ fn render_section_header(section: &str, _width: u16) -> ListItem<'static> {
 /// Render a roadmap item (grouped under section, no section name shown)
 fn render_item_grouped(
     item: &RoadmapItem,
+    depth: usize,
     index: usize,
     is_selected: bool,
     width: u16,
 ) -> ListItem<'static> {
     // Line 1: indent, number, checkbox, title
     let mut line1_spans = vec![];
+    let nesting = "  ".repeat(depth);
 
     // Indent (2 spaces)
     line1_spans.push(Span::raw("  "));
@@ -514,6 +529,9 @@ fn render_item_grouped(
     };
     line1_spans.push(Span::styled(format!("{:>2} ", index + 1), num_style));
 
+    // Sub-items sit under their parent item
+    line1_spans.push(Span::raw(nesting.clone()));
+
     // Checkbox
     let checkbox = match item.checkbox_state.as_str() {
         "checked" => Span::styled("[x]", Style::default().fg(Color::Green).bold()),
@@ -524,7 +542,7 @@ fn render_item_grouped(
     line1_spans.push(Span::raw(" "));
 
     // Title
-    let max_title_len = (width as usize).saturating_sub(12);
+    let max_title_len = (width as usize).saturating_sub(12 + nesting.len());
     let title = truncate_str(&item.title, max_title_len);
     let title_style = if is_selected {
         Style::default().fg(Color::White).bold()
@@ -539,7 +557,7 @@ fn render_item_grouped(
 
     // Line 2: status indicators (indented)
     let mut line2_spans = vec![];
-    line2_spans.push(Span::raw("       ")); // Indent to align under title
+    line2_spans.push(Span::raw(format!("       {}", nesting))); // Indent to align under title
 
     // Issue link
     if let Some(issue_num) = item.github_issue_number {
@@ -893,6 +911,11 @@ mod tests {
             created_at: "2024-01-01".to_string(),
             updated_at: "2024-01-01".to_string(),
             last_synced_at: None,
+            priority: None,
+            due_date: None,
+            goal_node_id: None,
+            goal_change_id: None,
+            archived_at: None,
         }
     }
 
@@ -1051,6 +1074,22 @@ mod tests {
         assert_eq!(truncate_str("hi", 2), "hi");
     }
 
+    #[test]
+    fn test_item_depth() {
+        let header = make_item(1, "Phase", "none", None, None);
+        let mut parent = make_item(2, "Parent", "unchecked", None, None);
+        parent.parent_id = Some(1);
+        let mut child = make_item(3, "Child", "unchecked", None, None);
+        child.parent_id = Some(2);
+        let mut grandchild = make_item(4, "Grandchild", "checked", None, None);
+        grandchild.parent_id = Some(3);
+        let items = vec![header, parent, child, grandchild];
+
+        assert_eq!(item_depth(&items[1], &items), 0);
+        assert_eq!(item_depth(&items[2], &items), 1);
+        assert_eq!(item_depth(&items[3], &items), 2);
+    }
+
     #[test]
     fn test_roadmap_state_toggle_mode() {
         let mut state = RoadmapState::new();
@@ -17,6 +17,7 @@ use ratatui::{
 };
 
 use crate::db::{DecisionNode, TraceContent, TraceSession, TraceSpan};
+use crate::pricing::format_cost;
 use std::collections::HashMap;
 
 // =============================================================================
@@ -73,6 +74,14 @@ pub struct TraceState {
     pub node_counts: HashMap<i32, i64>,
     /// Nodes for the detail view (Nodes tab)
     pub detail_nodes: Vec<DecisionNode>,
+    /// Cost per session in USD (session_id -> cost)
+    pub session_costs: HashMap<String, f64>,
+    /// Cost per span in USD, for spans whose model has a price
+    pub span_costs: HashMap<i32, f64>,
+    /// Subagent nesting depth per span (0 for the main conversation)
+    pub span_depths: HashMap<i32, usize>,
+    /// Reviewer notes per span, oldest first
+    pub span_notes: HashMap<i32, Vec<String>>,
 }
 
 // =============================================================================
@@ -216,6 +225,26 @@ impl TraceState {
         self.detail_nodes = nodes;
     }
 
+    /// Set per-session costs
+    pub fn set_session_costs(&mut self, costs: HashMap<String, f64>) {
+        self.session_costs = costs;
+    }
+
+    /// Set per-span costs for the expanded session
+    pub fn set_span_costs(&mut self, costs: HashMap<i32, f64>) {
+        self.span_costs = costs;
+    }
+
+    /// Set subagent depths for the expanded session's spans
+    pub fn set_span_depths(&mut self, depths: HashMap<i32, usize>) {
+        self.span_depths = depths;
+    }
+
+    /// Set reviewer notes for the expanded session's spans
+    pub fn set_span_notes(&mut self, notes: HashMap<i32, Vec<String>>) {
+        self.span_notes = notes;
+    }
+
     /// Clear all state (for refresh)
     pub fn clear(&mut self) {
         self.sessions.clear();
@@ -529,6 +558,13 @@ fn draw_sessions(frame: &mut Frame, state: &TraceState, area: Rect) {
             let duration = format_duration(&session.started_at, session.ended_at.as_deref());
             let tokens_in = format_tokens(session.total_input_tokens);
             let tokens_out = format_tokens(session.total_output_tokens);
+            let cost = format_cost(
+                state
+                    .session_costs
+                    .get(&session.session_id)
+                    .copied()
+                    .unwrap_or(0.0),
+            );
             let linked = if session.linked_node_id.is_some() {
                 format!(" → #{}", session.linked_node_id.unwrap())
             } else {
@@ -536,8 +572,8 @@ fn draw_sessions(frame: &mut Frame, state: &TraceState, area: Rect) {
             };
 
             let line = format!(
-                " {} │ {:>6} │ {:>6} │ {}↓ {}↑{}",
-                id_short, time_ago, duration, tokens_in, tokens_out, linked
+                " {} │ {:>6} │ {:>6} │ {}↓ {}↑ │ {:>7}{}",
+                id_short, time_ago, duration, tokens_in, tokens_out, cost, linked
             );
 
             let style = if is_selected {
@@ -631,6 +667,11 @@ fn draw_spans_list(frame: &mut Frame, state: &TraceState, area: Rect, title: &st
             let tokens_out = span.output_tokens.map(format_tokens).unwrap_or("-".into());
             let tools = span.tool_names.as_deref().unwrap_or("-");
             let tools_short = truncate_str(tools, 15);
+            let cost = state
+                .span_costs
+                .get(&span.id)
+                .map(|c| format_cost(*c))
+                .unwrap_or_else(|| "-".into());
 
             // Get node count for this span
             let node_count = state.node_counts.get(&span.id).copied().unwrap_or(0);
@@ -639,10 +680,31 @@ fn draw_spans_list(frame: &mut Frame, state: &TraceState, area: Rect, title: &st
             } else {
                 String::new()
             };
+            let notes_str = match state.span_notes.get(&span.id).map_or(0, Vec::len) {
+                0 => String::new(),
+                n => format!(" ✎{}", n),
+            };
+
+            // Subagent spans are indented under the span that started them
+            let depth = state.span_depths.get(&span.id).copied().unwrap_or(0);
+            let tree = if depth > 0 {
+                format!("{}└", "  ".repeat(depth - 1))
+            } else {
+                String::new()
+            };
 
             let line = format!(
-                " #{:<2} │ {:>6} │ {:>6} │ {}↓ {}↑ │ {}{}",
-                span.sequence_num, model, duration, tokens_in, tokens_out, tools_short, nodes_str
+                " {}#{:<2} │ {:>6} │ {:>6} │ {}↓ {}↑ │ {:>7} │ {}{}{}",
+                tree,
+                span.sequence_num,
+                model,
+                duration,
+                tokens_in,
+                tokens_out,
+                cost,
+                tools_short,
+                nodes_str,
+                notes_str
             );
 
             let style = if is_selected {
@@ -676,6 +738,17 @@ fn draw_span_preview(frame: &mut Frame, state: &TraceState, area: Rect) {
     if let Some(span) = state.selected_span() {
         let mut lines = vec![];
 
+        // Reviewer notes
+        for note in state.span_notes.get(&span.id).into_iter().flatten() {
+            lines.push(Line::from(vec![
+                Span::styled("Note: ", Style::default().fg(Color::Magenta)),
+                Span::raw(note.as_str()),
+            ]));
+        }
+        if state.span_notes.contains_key(&span.id) {
+            lines.push(Line::from(""));
+        }
+
         // User preview
         if let Some(ref user) = span.user_preview {
             lines.push(Line::from(vec![
@@ -0,0 +1,283 @@
+//! GitHub webhook handling for `deciduous serve`
+//!
+//! GitHub POSTs issue events to `/api/webhooks/github`. Payloads are verified
+//! against the `X-Hub-Signature-256` header using the shared webhook secret,
+//! then applied to the local issue cache and roadmap items so the local view
+//! stays in step with upstream between `roadmap sync` runs.
+
+use crate::config::Config;
+use crate::db::Database;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Environment variable that overrides `github.webhook_secret` in config.toml
+pub const WEBHOOK_SECRET_ENV: &str = "DECIDUOUS_WEBHOOK_SECRET";
+
+const HMAC_BLOCK_SIZE: usize = 64;
+
+/// Resolve the webhook secret: environment first, then config.toml
+pub fn webhook_secret(config: &Config) -> Option<String> {
+    std::env::var(WEBHOOK_SECRET_ENV)
+        .ok()
+        .or_else(|| config.github.webhook_secret.clone())
+        .filter(|s| !s.is_empty())
+}
+
+/// Compute HMAC-SHA256 of `message` keyed by `key`
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; HMAC_BLOCK_SIZE];
+    if key.len() > HMAC_BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(block.map(|b| b ^ 0x36));
+    inner.update(message);
+    let inner_hash = inner.finalize();
+
+    let mut outer = Sha256::new();
+    outer.update(block.map(|b| b ^ 0x5c));
+    outer.update(inner_hash);
+    outer.finalize().into()
+}
+
+/// Verify a `sha256=<hex>` signature header against the payload
+pub fn verify_signature(secret: &str, payload: &[u8], signature: &str) -> bool {
+    let Some(hex) = signature.trim().strip_prefix("sha256=") else {
+        return false;
+    };
+    let expected: String = hmac_sha256(secret.as_bytes(), payload)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    constant_time_eq(hex.to_ascii_lowercase().as_bytes(), expected.as_bytes())
+}
+
+/// Compare secrets in constant time, so they can't be guessed byte by byte
+pub fn constant_time_eq(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len()
+        && given
+            .iter()
+            .zip(expected)
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+#[derive(Debug, Deserialize)]
+struct IssuesEvent {
+    action: String,
+    issue: WebhookIssue,
+    repository: WebhookRepository,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookIssue {
+    number: i32,
+    title: String,
+    body: Option<String>,
+    state: String,
+    html_url: String,
+    created_at: String,
+    updated_at: String,
+    /// Present when the "issue" is actually a pull request
+    #[serde(default)]
+    pull_request: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookRepository {
+    full_name: String,
+}
+
+/// What a webhook delivery changed locally
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct WebhookOutcome {
+    pub event: String,
+    pub action: Option<String>,
+    pub issue: Option<i32>,
+    pub roadmap_items_updated: usize,
+    pub ignored: bool,
+}
+
+impl WebhookOutcome {
+    fn ignored(event: &str, action: Option<String>) -> Self {
+        Self {
+            event: event.to_string(),
+            action,
+            issue: None,
+            roadmap_items_updated: 0,
+            ignored: true,
+        }
+    }
+}
+
+/// Apply a verified webhook delivery to the database
+///
+/// Only `issues` events are acted on; `ping` and anything else is acknowledged
+/// and ignored so GitHub doesn't mark the hook as failing. Issue events from a
+/// repository other than `repo` are rejected, since roadmap items are matched
+/// by issue number alone.
+pub fn apply_event(
+    db: &Database,
+    repo: &str,
+    event: &str,
+    payload: &[u8],
+) -> Result<WebhookOutcome, String> {
+    if event != "issues" {
+        return Ok(WebhookOutcome::ignored(event, None));
+    }
+
+    let parsed: IssuesEvent =
+        serde_json::from_slice(payload).map_err(|e| format!("Invalid issues payload: {}", e))?;
+    if !parsed.repository.full_name.eq_ignore_ascii_case(repo) {
+        return Err(format!(
+            "Event is for {}, not {}",
+            parsed.repository.full_name, repo
+        ));
+    }
+    if parsed.issue.pull_request.is_some() {
+        return Ok(WebhookOutcome::ignored(event, Some(parsed.action)));
+    }
+
+    let issue = &parsed.issue;
+    db.cache_github_issue(
+        issue.number,
+        &parsed.repository.full_name,
+        &issue.title,
+        issue.body.as_deref(),
+        &issue.state,
+        &issue.html_url,
+        &issue.created_at,
+        &issue.updated_at,
+    )
+    .map_err(|e| format!("Failed to cache issue: {}", e))?;
+
+    let updated = db
+        .update_roadmap_issue_state(issue.number, &issue.state)
+        .map_err(|e| format!("Failed to update roadmap: {}", e))?;
+
+    Ok(WebhookOutcome {
+        event: event.to_string(),
+        action: Some(parsed.action),
+        issue: Some(issue.number),
+        roadmap_items_updated: updated,
+        ignored: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const SECRET: &str = "It's a Secret to Everybody";
+    const SIGNATURE: &str =
+        "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+
+    #[test]
+    fn test_verify_signature_matches_github_example() {
+        assert!(verify_signature(SECRET, b"Hello, World!", SIGNATURE));
+        assert!(verify_signature(
+            SECRET,
+            b"Hello, World!",
+            &SIGNATURE.to_uppercase().replace("SHA256=", "sha256=")
+        ));
+    }
+
+    #[test]
+    fn test_verify_signature_rejects_tampering() {
+        assert!(!verify_signature(SECRET, b"Hello, World?", SIGNATURE));
+        assert!(!verify_signature("wrong", b"Hello, World!", SIGNATURE));
+        assert!(!verify_signature(SECRET, b"Hello, World!", "sha1=abc"));
+        assert!(!verify_signature(SECRET, b"Hello, World!", "sha256=abc"));
+    }
+
+    #[test]
+    fn test_hmac_long_key_is_hashed() {
+        let key = [0xaau8; 131];
+        let mac = hmac_sha256(
+            &key,
+            b"Test Using Larger Than Block-Size Key - Hash Key First",
+        );
+        let hex: String = mac.iter().map(|b| format!("{:02x}", b)).collect();
+        // RFC 4231 test case 6
+        assert_eq!(
+            hex,
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn test_apply_issues_event_updates_cache_and_roadmap() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let item = db
+            .create_roadmap_item("Phase 1", None, None, None, "none")
+            .unwrap();
+        db.update_roadmap_item_github(item, Some(12), Some("open"))
+            .unwrap();
+
+        let payload = serde_json::json!({
+            "action": "closed",
+            "issue": {
+                "number": 12,
+                "title": "Phase 1",
+                "body": "Tasks",
+                "state": "closed",
+                "html_url": "https://github.com/o/r/issues/12",
+                "created_at": "2025-01-01T00:00:00Z",
+                "updated_at": "2025-01-02T00:00:00Z"
+            },
+            "repository": { "full_name": "o/r" }
+        });
+        let outcome = apply_event(&db, "o/r", "issues", payload.to_string().as_bytes()).unwrap();
+        assert!(!outcome.ignored);
+        assert_eq!(outcome.roadmap_items_updated, 1);
+
+        let cached = db.get_cached_issue("o/r", 12).unwrap().unwrap();
+        assert_eq!(cached.state, "closed");
+        let items = db.get_all_roadmap_items().unwrap();
+        assert_eq!(items[0].github_issue_state.as_deref(), Some("closed"));
+    }
+
+    #[test]
+    fn test_apply_ignores_other_events() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let outcome = apply_event(&db, "o/r", "ping", b"{\"zen\":\"hi\"}").unwrap();
+        assert!(outcome.ignored);
+    }
+
+    #[test]
+    fn test_apply_rejects_other_repositories() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(dir.path().join("test.db").to_str().unwrap()).unwrap();
+        let item = db
+            .create_roadmap_item("Phase 1", None, None, None, "none")
+            .unwrap();
+        db.update_roadmap_item_github(item, Some(12), Some("open"))
+            .unwrap();
+
+        let payload = serde_json::json!({
+            "action": "closed",
+            "issue": {
+                "number": 12,
+                "title": "Unrelated",
+                "state": "closed",
+                "html_url": "https://github.com/other/repo/issues/12",
+                "created_at": "2025-01-01T00:00:00Z",
+                "updated_at": "2025-01-02T00:00:00Z"
+            },
+            "repository": { "full_name": "other/repo" }
+        });
+        let err = apply_event(&db, "o/r", "issues", payload.to_string().as_bytes()).unwrap_err();
+        assert!(err.contains("other/repo"));
+
+        let items = db.get_all_roadmap_items().unwrap();
+        assert_eq!(items[0].github_issue_state.as_deref(), Some("open"));
+        assert!(db.get_cached_issue("other/repo", 12).unwrap().is_none());
+    }
+}
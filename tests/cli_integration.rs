@@ -502,7 +502,7 @@ fn test_diff_export_import() {
 
     assert!(patch.get("nodes").is_some());
     assert!(patch.get("edges").is_some());
-    assert_eq!(patch["version"], "1.0");
+    assert_eq!(patch["version"], "2.0");
 }
 
 #[test]
@@ -534,3 +534,57 @@ fn test_diff_dry_run() {
     // Dry run should report what would be added
     assert!(out.contains("added") || out.contains("would"));
 }
+
+// =============================================================================
+// CI Validation Tests
+// =============================================================================
+
+#[test]
+fn test_ci_validates_exported_branch() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let db_path = temp_dir.path().join("test.db");
+    let patches_dir = temp_dir.path().join("patches");
+    std::fs::create_dir_all(&patches_dir).unwrap();
+    let patch_path = patches_dir.join("feature-ci.json");
+
+    run_deciduous(&["add", "goal", "CI Goal", "-b", "feature-ci"], &db_path);
+    run_deciduous(
+        &[
+            "diff",
+            "export",
+            "--branch",
+            "feature-ci",
+            "-o",
+            patch_path.to_str().unwrap(),
+        ],
+        &db_path,
+    );
+
+    let output = run_deciduous(
+        &[
+            "ci",
+            "--patches",
+            patches_dir.to_str().unwrap(),
+            "--branch",
+            "feature-ci",
+        ],
+        &db_path,
+    );
+    assert!(output.status.success(), "ci failed: {}", stdout(&output));
+    assert!(stdout(&output).contains("Passed"));
+
+    // A branch with no exported patch fails the build
+    let output = run_deciduous(
+        &[
+            "ci",
+            "--patches",
+            patches_dir.to_str().unwrap(),
+            "--branch",
+            "unexported-branch",
+            "--annotations",
+        ],
+        &db_path,
+    );
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("::error::No decision graph patch"));
+}
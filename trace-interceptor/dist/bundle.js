@@ -39,6 +39,7 @@ var debugLog = (msg) => {
 var DeciduousClient = class {
   sessionId = null;
   deciduousBin;
+  budgetBlock = null;
   constructor() {
     this.deciduousBin = process.env.DECIDUOUS_BIN || "deciduous";
     this.sessionId = process.env.DECIDUOUS_TRACE_SESSION || null;
@@ -117,6 +118,12 @@ var DeciduousClient = class {
       );
       const parsed = JSON.parse(result.trim());
       debugLog(`Recorded span #${parsed.span_id}`);
+      if (parsed.budget) {
+        debugLog(parsed.budget.message);
+        if (parsed.budget.block) {
+          this.budgetBlock = parsed.budget.message;
+        }
+      }
       return parsed.span_id;
     } catch (error) {
       debugLog(`Failed to record span: ${error}`);
@@ -182,14 +189,25 @@ var ResponseAccumulator = class {
   stopReason;
   requestId;
   model;
+  chunkCount = 0;
+  firstTokenMs;
+  complete = false;
+  pending = "";
+  startTime;
+  /**
+   * @param startTime When the request was sent, for first-token latency
+   */
+  constructor(startTime = Date.now()) {
+    this.startTime = startTime;
+  }
   /**
    * Process an SSE event line
    */
   processLine(line) {
-    if (!line.startsWith("data: ")) {
+    if (!line.startsWith("data:")) {
       return;
     }
-    const dataStr = line.slice(6).trim();
+    const dataStr = line.slice(5).trim();
     if (dataStr === "[DONE]") {
       return;
     }
@@ -203,6 +221,7 @@ var ResponseAccumulator = class {
    * Process a parsed SSE event
    */
   processEvent(event) {
+    this.chunkCount++;
     switch (event.type) {
       case "message_start":
         if (event.message) {
@@ -227,6 +246,9 @@ var ResponseAccumulator = class {
         }
         break;
       case "content_block_delta":
+        if (this.firstTokenMs === void 0) {
+          this.firstTokenMs = Date.now() - this.startTime;
+        }
         if (event.delta) {
           if (event.delta.type === "thinking_delta" && event.delta.thinking) {
             this.thinking += event.delta.thinking;
@@ -256,18 +278,32 @@ var ResponseAccumulator = class {
         }
         break;
       case "message_stop":
+        this.complete = true;
         break;
     }
   }
   /**
    * Process a chunk of SSE data
+   *
+   * Network chunks don't line up with events, so a trailing partial line is
+   * held until the rest of it arrives.
    */
   processChunk(chunk) {
-    const lines = chunk.split("\n");
+    const lines = (this.pending + chunk).split("\n");
+    this.pending = lines.pop() ?? "";
     for (const line of lines) {
       this.processLine(line);
     }
   }
+  /**
+   * Process whatever came after the last newline
+   */
+  flush() {
+    if (this.pending) {
+      this.processLine(this.pending);
+      this.pending = "";
+    }
+  }
   /**
    * Get the accumulated span data
    */
@@ -291,18 +327,39 @@ var ResponseAccumulator = class {
       tool_names: this.toolCalls.map((t) => t.name).filter(Boolean).join(",") || void 0,
       thinking: this.thinking || void 0,
       response: this.response || void 0,
-      tool_calls: this.toolCalls.length > 0 ? this.toolCalls : void 0
+      tool_calls: this.toolCalls.length > 0 ? this.toolCalls : void 0,
+      chunk_count: this.chunkCount,
+      first_token_ms: this.firstTokenMs,
+      stream_complete: this.complete
     };
   }
 };
 function createAccumulatingStream(originalBody, accumulator, onComplete) {
   const reader = originalBody.getReader();
   const decoder = new TextDecoder();
+  let finished = false;
+  const finish = () => {
+    if (finished) {
+      return;
+    }
+    finished = true;
+    accumulator.processChunk(decoder.decode());
+    accumulator.flush();
+    onComplete();
+  };
   return new ReadableStream({
     async pull(controller) {
-      const { done, value } = await reader.read();
+      let result;
+      try {
+        result = await reader.read();
+      } catch (error) {
+        finish();
+        controller.error(error);
+        return;
+      }
+      const { done, value } = result;
       if (done) {
-        onComplete();
+        finish();
         controller.close();
         return;
       }
@@ -311,6 +368,7 @@ function createAccumulatingStream(originalBody, accumulator, onComplete) {
       controller.enqueue(value);
     },
     cancel() {
+      finish();
       reader.cancel();
     }
   });
@@ -519,6 +577,12 @@ function parseNonStreamingResponse(data) {
     tool_calls: toolCalls.length > 0 ? toolCalls : void 0
   };
 }
+function budgetExceededResponse(message) {
+  return new Response(
+    JSON.stringify({ type: "error", error: { type: "permission_error", message } }),
+    { status: 403, headers: { "content-type": "application/json" } }
+  );
+}
 async function ensureSession() {
   if (!client) {
     client = new DeciduousClient();
@@ -535,6 +599,9 @@ async function interceptedFetch(input, init) {
     return originalFetch(input, init);
   }
   const deciduous = await ensureSession();
+  if (deciduous.budgetBlock) {
+    return budgetExceededResponse(deciduous.budgetBlock);
+  }
   const startTime = Date.now();
   let requestBody;
   if (init?.body) {
@@ -590,7 +657,7 @@ async function interceptedFetch(input, init) {
   }
   const response = await originalFetch(input, init);
   if (isStreamingResponse(response)) {
-    const accumulator = new ResponseAccumulator();
+    const accumulator = new ResponseAccumulator(startTime);
     const onComplete = async () => {
       const duration2 = Date.now() - startTime;
       const spanData2 = {
@@ -5,6 +5,8 @@ import type { SpanData } from './types';
 export declare class DeciduousClient {
     private sessionId;
     private deciduousBin;
+    /** Set once an enforced trace budget is blown; API calls are refused after that */
+    budgetBlock: string | null;
     constructor();
     /**
      * Start or resume a trace session
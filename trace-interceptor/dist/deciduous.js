@@ -57,6 +57,8 @@ const debugLog = (msg) => {
 class DeciduousClient {
     sessionId = null;
     deciduousBin;
+    /** Set once an enforced trace budget is blown; API calls are refused after that */
+    budgetBlock = null;
     constructor() {
         // Use DECIDUOUS_BIN env var or default to 'deciduous'
         this.deciduousBin = process.env.DECIDUOUS_BIN || 'deciduous';
@@ -132,6 +134,12 @@ class DeciduousClient {
             });
             const parsed = JSON.parse(result.trim());
             debugLog(`Recorded span #${parsed.span_id}`);
+            if (parsed.budget) {
+                debugLog(parsed.budget.message);
+                if (parsed.budget.block) {
+                    this.budgetBlock = parsed.budget.message;
+                }
+            }
             return parsed.span_id;
         }
         catch (error) {
@@ -332,6 +332,12 @@ function parseNonStreamingResponse(data) {
         tool_calls: toolCalls.length > 0 ? toolCalls : undefined,
     };
 }
+/**
+ * Error returned in place of the API call once an enforced budget is blown
+ */
+function budgetExceededResponse(message) {
+    return new Response(JSON.stringify({ type: 'error', error: { type: 'permission_error', message } }), { status: 403, headers: { 'content-type': 'application/json' } });
+}
 /**
  * Ensure client is initialized and session started
  */
@@ -357,6 +363,10 @@ async function interceptedFetch(input, init) {
     }
     // Initialize session
     const deciduous = await ensureSession();
+    // Refuse the call once an enforced trace budget is blown
+    if (deciduous.budgetBlock) {
+        return budgetExceededResponse(deciduous.budgetBlock);
+    }
     const startTime = Date.now();
     // Parse request body for preview
     let requestBody;
@@ -425,7 +435,7 @@ async function interceptedFetch(input, init) {
     const response = await originalFetch(input, init);
     // Handle streaming response
     if (isStreamingResponse(response)) {
-        const accumulator = new stream_parser_1.ResponseAccumulator();
+        const accumulator = new stream_parser_1.ResponseAccumulator(startTime);
         const onComplete = async () => {
             const duration = Date.now() - startTime;
             const spanData = {
@@ -14,6 +14,15 @@ export declare class ResponseAccumulator {
     private stopReason;
     private requestId;
     private model;
+    private chunkCount;
+    private firstTokenMs;
+    private complete;
+    private pending;
+    private readonly startTime;
+    /**
+     * @param startTime When the request was sent, for first-token latency
+     */
+    constructor(startTime?: number);
     /**
      * Process an SSE event line
      */
@@ -24,8 +33,15 @@ export declare class ResponseAccumulator {
     private processEvent;
     /**
      * Process a chunk of SSE data
+     *
+     * Network chunks don't line up with events, so a trailing partial line is
+     * held until the rest of it arrives.
      */
     processChunk(chunk: string): void;
+    /**
+     * Process whatever came after the last newline
+     */
+    flush(): void;
     /**
      * Get the accumulated span data
      */
@@ -62,14 +62,25 @@ class ResponseAccumulator {
     stopReason;
     requestId;
     model;
+    chunkCount = 0;
+    firstTokenMs;
+    complete = false;
+    pending = '';
+    startTime;
+    /**
+     * @param startTime When the request was sent, for first-token latency
+     */
+    constructor(startTime = Date.now()) {
+        this.startTime = startTime;
+    }
     /**
      * Process an SSE event line
      */
     processLine(line) {
-        if (!line.startsWith('data: ')) {
+        if (!line.startsWith('data:')) {
             return;
         }
-        const dataStr = line.slice(6).trim();
+        const dataStr = line.slice(5).trim();
         if (dataStr === '[DONE]') {
             return;
         }
@@ -85,6 +96,7 @@ class ResponseAccumulator {
      * Process a parsed SSE event
      */
     processEvent(event) {
+        this.chunkCount++;
         switch (event.type) {
             case 'message_start':
                 if (event.message) {
@@ -109,6 +121,9 @@ class ResponseAccumulator {
                 }
                 break;
             case 'content_block_delta':
+                if (this.firstTokenMs === undefined) {
+                    this.firstTokenMs = Date.now() - this.startTime;
+                }
                 if (event.delta) {
                     if (event.delta.type === 'thinking_delta' && event.delta.thinking) {
                         this.thinking += event.delta.thinking;
@@ -142,19 +157,33 @@ class ResponseAccumulator {
                 }
                 break;
             case 'message_stop':
-                // Final message
+                // Final message; anything short of this was cut off
+                this.complete = true;
                 break;
         }
     }
     /**
      * Process a chunk of SSE data
+     *
+     * Network chunks don't line up with events, so a trailing partial line is
+     * held until the rest of it arrives.
      */
     processChunk(chunk) {
-        const lines = chunk.split('\n');
+        const lines = (this.pending + chunk).split('\n');
+        this.pending = lines.pop() ?? '';
         for (const line of lines) {
             this.processLine(line);
         }
     }
+    /**
+     * Process whatever came after the last newline
+     */
+    flush() {
+        if (this.pending) {
+            this.processLine(this.pending);
+            this.pending = '';
+        }
+    }
     /**
      * Get the accumulated span data
      */
@@ -179,6 +208,9 @@ class ResponseAccumulator {
             thinking: this.thinking || undefined,
             response: this.response || undefined,
             tool_calls: this.toolCalls.length > 0 ? this.toolCalls : undefined,
+            chunk_count: this.chunkCount,
+            first_token_ms: this.firstTokenMs,
+            stream_complete: this.complete,
         };
     }
 }
@@ -189,11 +221,31 @@ exports.ResponseAccumulator = ResponseAccumulator;
 function createAccumulatingStream(originalBody, accumulator, onComplete) {
     const reader = originalBody.getReader();
     const decoder = new TextDecoder();
+    let finished = false;
+    // Record the span once, however the stream ends
+    const finish = () => {
+        if (finished) {
+            return;
+        }
+        finished = true;
+        accumulator.processChunk(decoder.decode());
+        accumulator.flush();
+        onComplete();
+    };
     return new ReadableStream({
         async pull(controller) {
-            const { done, value } = await reader.read();
+            let result;
+            try {
+                result = await reader.read();
+            }
+            catch (error) {
+                finish();
+                controller.error(error);
+                return;
+            }
+            const { done, value } = result;
             if (done) {
-                onComplete();
+                finish();
                 controller.close();
                 return;
             }
@@ -204,6 +256,7 @@ function createAccumulatingStream(originalBody, accumulator, onComplete) {
             controller.enqueue(value);
         },
         cancel() {
+            finish();
             reader.cancel();
         },
     });
@@ -21,6 +21,9 @@ export interface SpanData {
     system_prompt?: string;
     tool_definitions?: ToolDefinition[];
     message_count?: number;
+    chunk_count?: number;
+    first_token_ms?: number;
+    stream_complete?: boolean;
 }
 export interface ToolResult {
     tool_use_id: string;
@@ -43,6 +46,11 @@ export interface StartSessionResponse {
 }
 export interface RecordSpanResponse {
     span_id: number;
+    /** Present when the trace budget in config has been exceeded */
+    budget?: {
+        message: string;
+        block: boolean;
+    };
 }
 export interface ContentBlock {
     type: string;
@@ -25,6 +25,8 @@ const debugLog = (msg: string) => {
 export class DeciduousClient {
   private sessionId: string | null = null;
   private deciduousBin: string;
+  /** Set once an enforced trace budget is blown; API calls are refused after that */
+  budgetBlock: string | null = null;
 
   constructor() {
     // Use DECIDUOUS_BIN env var or default to 'deciduous'
@@ -124,6 +126,13 @@ export class DeciduousClient {
 
       debugLog(`Recorded span #${parsed.span_id}`);
 
+      if (parsed.budget) {
+        debugLog(parsed.budget.message);
+        if (parsed.budget.block) {
+          this.budgetBlock = parsed.budget.message;
+        }
+      }
+
       return parsed.span_id;
     } catch (error) {
       debugLog(`Failed to record span: ${error}`);
@@ -304,6 +304,16 @@ function parseNonStreamingResponse(data: AnthropicResponse): Omit<SpanData, 'dur
   };
 }
 
+/**
+ * Error returned in place of the API call once an enforced budget is blown
+ */
+function budgetExceededResponse(message: string): Response {
+  return new Response(
+    JSON.stringify({ type: 'error', error: { type: 'permission_error', message } }),
+    { status: 403, headers: { 'content-type': 'application/json' } }
+  );
+}
+
 /**
  * Ensure client is initialized and session started
  */
@@ -335,6 +345,12 @@ async function interceptedFetch(
 
   // Initialize session
   const deciduous = await ensureSession();
+
+  // Refuse the call once an enforced trace budget is blown
+  if (deciduous.budgetBlock) {
+    return budgetExceededResponse(deciduous.budgetBlock);
+  }
+
   const startTime = Date.now();
 
   // Parse request body for preview
@@ -404,7 +420,7 @@ async function interceptedFetch(
 
   // Handle streaming response
   if (isStreamingResponse(response)) {
-    const accumulator = new ResponseAccumulator();
+    const accumulator = new ResponseAccumulator(startTime);
 
     const onComplete = async () => {
       const duration = Date.now() - startTime;
@@ -29,16 +29,28 @@ export class ResponseAccumulator {
   private stopReason: string | undefined;
   private requestId: string | undefined;
   private model: string | undefined;
+  private chunkCount = 0;
+  private firstTokenMs: number | undefined;
+  private complete = false;
+  private pending = '';
+  private readonly startTime: number;
+
+  /**
+   * @param startTime When the request was sent, for first-token latency
+   */
+  constructor(startTime: number = Date.now()) {
+    this.startTime = startTime;
+  }
 
   /**
    * Process an SSE event line
    */
   processLine(line: string): void {
-    if (!line.startsWith('data: ')) {
+    if (!line.startsWith('data:')) {
       return;
     }
 
-    const dataStr = line.slice(6).trim();
+    const dataStr = line.slice(5).trim();
     if (dataStr === '[DONE]') {
       return;
     }
@@ -55,6 +67,7 @@ export class ResponseAccumulator {
    * Process a parsed SSE event
    */
   private processEvent(event: SSEEvent): void {
+    this.chunkCount++;
     switch (event.type) {
       case 'message_start':
         if (event.message) {
@@ -81,6 +94,9 @@ export class ResponseAccumulator {
         break;
 
       case 'content_block_delta':
+        if (this.firstTokenMs === undefined) {
+          this.firstTokenMs = Date.now() - this.startTime;
+        }
         if (event.delta) {
           if (event.delta.type === 'thinking_delta' && event.delta.thinking) {
             this.thinking += event.delta.thinking;
@@ -115,21 +131,36 @@ export class ResponseAccumulator {
         break;
 
       case 'message_stop':
-        // Final message
+        // Final message; anything short of this was cut off
+        this.complete = true;
         break;
     }
   }
 
   /**
    * Process a chunk of SSE data
+   *
+   * Network chunks don't line up with events, so a trailing partial line is
+   * held until the rest of it arrives.
    */
   processChunk(chunk: string): void {
-    const lines = chunk.split('\n');
+    const lines = (this.pending + chunk).split('\n');
+    this.pending = lines.pop() ?? '';
     for (const line of lines) {
       this.processLine(line);
     }
   }
 
+  /**
+   * Process whatever came after the last newline
+   */
+  flush(): void {
+    if (this.pending) {
+      this.processLine(this.pending);
+      this.pending = '';
+    }
+  }
+
   /**
    * Get the accumulated span data
    */
@@ -154,6 +185,9 @@ export class ResponseAccumulator {
       thinking: this.thinking || undefined,
       response: this.response || undefined,
       tool_calls: this.toolCalls.length > 0 ? this.toolCalls : undefined,
+      chunk_count: this.chunkCount,
+      first_token_ms: this.firstTokenMs,
+      stream_complete: this.complete,
     };
   }
 }
@@ -168,13 +202,33 @@ export function createAccumulatingStream(
 ): ReadableStream<Uint8Array> {
   const reader = originalBody.getReader();
   const decoder = new TextDecoder();
+  let finished = false;
+
+  // Record the span once, however the stream ends
+  const finish = () => {
+    if (finished) {
+      return;
+    }
+    finished = true;
+    accumulator.processChunk(decoder.decode());
+    accumulator.flush();
+    onComplete();
+  };
 
   return new ReadableStream({
     async pull(controller) {
-      const { done, value } = await reader.read();
+      let result: ReadableStreamReadResult<Uint8Array>;
+      try {
+        result = await reader.read();
+      } catch (error) {
+        finish();
+        controller.error(error);
+        return;
+      }
+      const { done, value } = result;
 
       if (done) {
-        onComplete();
+        finish();
         controller.close();
         return;
       }
@@ -188,6 +242,7 @@ export function createAccumulatingStream(
     },
 
     cancel() {
+      finish();
       reader.cancel();
     },
   });
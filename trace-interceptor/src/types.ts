@@ -23,6 +23,9 @@ export interface SpanData {
   system_prompt?: string;           // The hidden system instructions
   tool_definitions?: ToolDefinition[]; // Available tools with schemas
   message_count?: number;           // Number of messages in conversation
+  chunk_count?: number;             // SSE events received
+  first_token_ms?: number;          // Request start to first content delta
+  stream_complete?: boolean;        // False if the stream ended before message_stop
 }
 
 export interface ToolResult {
@@ -50,6 +53,8 @@ export interface StartSessionResponse {
 
 export interface RecordSpanResponse {
   span_id: number;
+  /** Present when the trace budget in config has been exceeded */
+  budget?: { message: string; block: boolean };
 }
 
 export interface ContentBlock {
@@ -8,7 +8,7 @@
 import React, { useState, useEffect } from 'react';
 import { useNavigate } from 'react-router-dom';
 import type { DecisionNode, GraphData, GitCommit } from '../types/graph';
-import { getPrompt, getFiles, getBranch, getCommit, shortCommit, githubCommitUrl, getCommitRepo } from '../types/graph';
+import { getPrompt, getFiles, getBranch, getCommit, shortCommit, githubCommitUrl, getCommitRepo, getPr, getPullRequest, githubPrUrl } from '../types/graph';
 import { NodeBadges, EdgeBadge, StatusBadge } from './NodeBadge';
 import { formatDuration, getModelShortName } from '../types/trace';
 
@@ -29,6 +29,8 @@ interface SpanWithSession {
 
 interface NodeTraceInfo {
   spans: SpanWithSession[];
+  /** MCP tools (server/tool) consulted in those spans */
+  mcp_tools?: string[];
 }
 
 interface DetailPanelProps {
@@ -46,6 +48,15 @@ function getCommitInfo(hash: string | null, gitHistory: GitCommit[]): GitCommit
   return gitHistory.find(c => c.hash === hash || c.short_hash === hash) ?? null;
 }
 
+// Badge colors match GitHub's PR state labels
+function prStateStyle(state: string): React.CSSProperties {
+  switch (state) {
+    case 'merged': return { backgroundColor: '#8250df' };
+    case 'closed': return { backgroundColor: '#cf222e' };
+    default: return { backgroundColor: '#1a7f37' };
+  }
+}
+
 export const DetailPanel: React.FC<DetailPanelProps> = ({
   node,
   graphData,
@@ -109,6 +120,8 @@ export const DetailPanel: React.FC<DetailPanelProps> = ({
   const branch = getBranch(node);
   const commitHash = getCommit(node);
   const commitInfo = getCommitInfo(commitHash, gitHistory);
+  const prNumber = getPr(node);
+  const pullRequest = getPullRequest(node, graphData);
 
   const getNodeTitle = (id: number): string => {
     const n = graphData.nodes.find(n => n.id === id);
@@ -191,9 +204,36 @@ export const DetailPanel: React.FC<DetailPanelProps> = ({
         </div>
       )}
 
+      {prNumber !== null && (
+        <div style={styles.section}>
+          <h3 style={styles.sectionTitle}>Linked PR</h3>
+          <div style={styles.commitSection}>
+            <a
+              href={pullRequest?.html_url ?? githubPrUrl(prNumber, effectiveRepo)}
+              target="_blank"
+              rel="noopener noreferrer"
+              style={styles.commitHash}
+            >
+              #{prNumber}
+            </a>
+            {pullRequest && (
+              <>
+                <span style={{ ...styles.prState, ...prStateStyle(pullRequest.state) }}>
+                  {pullRequest.state}
+                </span>
+                <div style={styles.commitMessage}>{pullRequest.title}</div>
+              </>
+            )}
+          </div>
+        </div>
+      )}
+
       {traceInfo && traceInfo.spans.length > 0 && (
         <div style={styles.section}>
           <h3 style={styles.sectionTitle}>Created During Trace</h3>
+          {traceInfo.mcp_tools && traceInfo.mcp_tools.length > 0 && (
+            <div style={styles.traceSession}>MCP tools: {traceInfo.mcp_tools.join(', ')}</div>
+          )}
           {traceInfo.spans.map((span) => {
             const isExpanded = expandedSpan === span.span_id;
             const hasContent = span.thinking_preview || span.response_preview || span.tool_names;
@@ -469,6 +509,14 @@ const styles: Record<string, React.CSSProperties> = {
     padding: '3px 8px',
     borderRadius: '4px',
   },
+  prState: {
+    marginLeft: '8px',
+    padding: '3px 8px',
+    borderRadius: '10px',
+    fontSize: '12px',
+    fontWeight: 600,
+    color: '#ffffff',
+  },
   commitMessage: {
     fontSize: '14px',
     color: '#24292f',
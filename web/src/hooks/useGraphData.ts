@@ -65,16 +65,9 @@ export function useGraphData(options: UseGraphDataOptions = {}): UseGraphDataRes
    */
   const fetchGraph = useCallback(async () => {
     try {
-      const response = await fetch(graphUrl);
-      if (!response.ok) {
-        throw new Error(`Failed to fetch graph: ${response.status} ${response.statusText}`);
-      }
-      const json = await response.json();
-      // Handle both API response {ok, data, error} and direct GraphData format
-      const data: GraphData = json.data ?? json;
-      if (json.ok === false && json.error) {
-        throw new Error(json.error);
-      }
+      const data = isGraphApi(graphUrl)
+        ? await fetchGraphPages(graphUrl)
+        : await fetchGraphJson(graphUrl);
       setGraphData(data);
       setLastUpdated(new Date());
       setError(null);
@@ -217,6 +210,55 @@ export function useGraphData(options: UseGraphDataOptions = {}): UseGraphDataRes
   };
 }
 
+/** Nodes per request when paging through `deciduous serve`'s /api/graph */
+const GRAPH_PAGE_SIZE = 1000;
+
+interface GraphPage extends GraphData {
+  /** `after` for the next page; null on the last */
+  next: number | null;
+}
+
+function isGraphApi(url: string): boolean {
+  return url.split('?')[0].endsWith('/api/graph');
+}
+
+/**
+ * Fetch one graph document, either an API response {ok, data, error} or GraphData
+ */
+async function fetchGraphJson<T = GraphData>(url: string): Promise<T> {
+  const response = await fetch(url);
+  if (!response.ok) {
+    throw new Error(`Failed to fetch graph: ${response.status} ${response.statusText}`);
+  }
+  const json = await response.json();
+  if (json.ok === false && json.error) {
+    throw new Error(json.error);
+  }
+  return json.data ?? json;
+}
+
+/**
+ * Fetch the graph from /api/graph a page at a time, so the server never
+ * loads the whole graph for one response
+ */
+async function fetchGraphPages(url: string): Promise<GraphData> {
+  const separator = url.includes('?') ? '&' : '?';
+  const first = await fetchGraphJson<GraphPage>(`${url}${separator}limit=${GRAPH_PAGE_SIZE}`);
+  const { next: firstNext, ...graph } = first;
+  const nodes = [...first.nodes];
+  const edges = [...first.edges];
+  let next = firstNext;
+  while (next !== null && next !== undefined) {
+    const page = await fetchGraphJson<GraphPage>(
+      `${url}${separator}limit=${GRAPH_PAGE_SIZE}&after=${next}`
+    );
+    nodes.push(...page.nodes);
+    edges.push(...page.edges);
+    next = page.next;
+  }
+  return { ...graph, nodes, edges };
+}
+
 /**
  * Detect the appropriate graph URL based on environment
  */
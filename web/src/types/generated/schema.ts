@@ -1,9 +1,15 @@
 // This file is auto-generated by bin/gen_types.rs
 // Do not edit manually.
 
-export type DecisionNode = { id: number, change_id: string, node_type: string, title: string, description: string | null, status: string, created_at: string, updated_at: string, metadata_json: string | null, };
+export type NodeType = "goal" | "decision" | "option" | "action" | "outcome" | "observation" | string;
 
-export type DecisionEdge = { id: number, from_node_id: number, to_node_id: number, from_change_id: string | null, to_change_id: string | null, edge_type: string, weight: number | null, rationale: string | null, created_at: string, };
+export type NodeStatus = "pending" | "active" | "completed" | "rejected" | string;
+
+export type EdgeType = "leads_to" | "requires" | "chosen" | "rejected" | "blocks" | "enables" | string;
+
+export type DecisionNode = { id: number, change_id: string, node_type: NodeType, title: string, description: string | null, status: NodeStatus, created_at: string, updated_at: string, metadata_json: string | null, };
+
+export type DecisionEdge = { id: number, from_node_id: number, to_node_id: number, from_change_id: string | null, to_change_id: string | null, edge_type: EdgeType, weight: number | null, rationale: string | null, created_at: string, };
 
 export type DecisionContext = { id: number, node_id: number, context_type: string, content_json: string, captured_at: string, };
 
@@ -11,7 +17,23 @@ export type DecisionSession = { id: number, name: string | null, started_at: str
 
 export type CommandLog = { id: number, command: string, description: string | null, working_dir: string | null, exit_code: number | null, stdout: string | null, stderr: string | null, started_at: string, completed_at: string | null, duration_ms: number | null, decision_node_id: number | null, };
 
-export type RoadmapItem = { id: number, change_id: string, title: string, description: string | null, section: string | null, parent_id: number | null, checkbox_state: string, github_issue_number: number | null, github_issue_state: string | null, outcome_node_id: number | null, outcome_change_id: string | null, markdown_line_start: number | null, markdown_line_end: number | null, content_hash: string | null, created_at: string, updated_at: string, last_synced_at: string | null, };
+export type RoadmapItem = { id: number, change_id: string, title: string, description: string | null, section: string | null, parent_id: number | null, checkbox_state: string, github_issue_number: number | null, github_issue_state: string | null, outcome_node_id: number | null, outcome_change_id: string | null, markdown_line_start: number | null, markdown_line_end: number | null, content_hash: string | null, created_at: string, updated_at: string, last_synced_at: string | null, 
+/**
+ * From a `(P1)` annotation; lower is more urgent
+ */
+priority: number | null, 
+/**
+ * From a `(due: 2025-06)` annotation
+ */
+due_date: string | null, 
+/**
+ * Goal node created for this item by `roadmap start`
+ */
+goal_node_id: number | null, goal_change_id: string | null, 
+/**
+ * When `roadmap archive` moved the item to ROADMAP_ARCHIVE.md
+ */
+archived_at: string | null, };
 
 export type RoadmapSyncState = { id: number, roadmap_path: string, roadmap_content_hash: string | null, github_repo: string | null, last_github_sync: string | null, last_markdown_parse: string | null, conflict_count: number, };
 
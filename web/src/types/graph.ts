@@ -36,6 +36,7 @@ export interface NodeMetadata {
   prompt?: string;      // User prompt that triggered this decision
   files?: string[];     // Associated files
   branch?: string;      // Git branch this node was created on
+  pr?: number;          // Linked pull request number
   [key: string]: unknown;  // Allow extension
 }
 
@@ -84,6 +85,20 @@ export interface DeciduousConfig {
   branch?: BranchConfig;
 }
 
+/**
+ * Cached pull request state (github_pr_cache table)
+ */
+export interface PullRequestCache {
+  id: number;
+  pr_number: number;
+  repo: string;
+  title: string;
+  state: 'open' | 'closed' | 'merged' | string;
+  head_branch: string;
+  html_url: string;
+  cached_at: string;
+}
+
 /**
  * Full graph data structure as exported by `deciduous sync`
  * This is the JSON format written to graph-data.json
@@ -92,6 +107,7 @@ export interface GraphData {
   nodes: DecisionNode[];
   edges: DecisionEdge[];
   config?: DeciduousConfig;  // Optional config for external repo links
+  pull_requests?: PullRequestCache[];  // Cached state of PRs linked via `--pr`
 }
 
 // =============================================================================
@@ -212,6 +228,23 @@ export function getFiles(node: DecisionNode): string[] | null {
   return meta?.files ?? null;
 }
 
+/**
+ * Extract linked PR number from a node
+ */
+export function getPr(node: DecisionNode): number | null {
+  const meta = parseMetadata(node.metadata_json);
+  return meta?.pr ?? null;
+}
+
+/**
+ * Find the cached pull request a node is linked to
+ */
+export function getPullRequest(node: DecisionNode, graphData: GraphData | null): PullRequestCache | null {
+  const pr = getPr(node);
+  if (pr === null) return null;
+  return graphData?.pull_requests?.find(p => p.pr_number === pr) ?? null;
+}
+
 /**
  * Get all unique branches from a list of nodes
  */
@@ -56,6 +56,15 @@ export interface TraceSpan {
   linked_change_id: string | null;
   // Added: count of nodes created during this span
   node_count?: number;
+  // Added: reviewer notes from `deciduous trace annotate`
+  annotations?: SpanAnnotation[];
+}
+
+export interface SpanAnnotation {
+  id: number;
+  span_id: number;
+  note: string;
+  created_at: string;
 }
 
 // =============================================================================
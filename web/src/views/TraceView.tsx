@@ -344,6 +344,9 @@ const TraceView: React.FC = () => {
                               {span.node_count && span.node_count > 0 && (
                                 <span style={styles.nodeCount}>+{span.node_count} nodes</span>
                               )}
+                              {span.annotations && span.annotations.length > 0 && (
+                                <span style={styles.noteBadge}>📝 {span.annotations.length}</span>
+                              )}
                               {/* Span summary - show prompt with appropriate indicator */}
                               <span style={styles.spanSummary}>
                                 {hasUserPrompt && (
@@ -367,6 +370,16 @@ const TraceView: React.FC = () => {
                                   )}
                                 </div>
 
+                                {/* Reviewer notes */}
+                                {span.annotations && span.annotations.length > 0 && (
+                                  <div style={styles.contentSection}>
+                                    <div style={styles.contentLabel}>📝 NOTES</div>
+                                    {span.annotations.map((a) => (
+                                      <div key={a.id} style={styles.noteBox}>{a.note}</div>
+                                    ))}
+                                  </div>
+                                )}
+
                                 {/* User/Subagent Message */}
                                 {span.user_preview && (
                                   <div style={styles.contentSection}>
@@ -663,6 +676,14 @@ const styles: Record<string, React.CSSProperties> = {
     fontSize: '10px',
     flexShrink: 0,
   },
+  noteBadge: {
+    backgroundColor: '#fce7f3',
+    color: '#be185d',
+    padding: '2px 6px',
+    borderRadius: '4px',
+    fontSize: '10px',
+    flexShrink: 0,
+  },
   spanSummary: {
     color: '#6b7280',
     fontSize: '12px',
@@ -737,6 +758,17 @@ const styles: Record<string, React.CSSProperties> = {
     wordBreak: 'break-word',
     color: '#92400e',
   },
+  noteBox: {
+    backgroundColor: '#fdf2f8',
+    border: '1px solid #f9a8d4',
+    borderRadius: '6px',
+    padding: '8px 10px',
+    marginBottom: '4px',
+    fontSize: '12px',
+    whiteSpace: 'pre-wrap',
+    wordBreak: 'break-word',
+    color: '#9d174d',
+  },
   responseBox: {
     backgroundColor: '#f0fdf4',
     border: '1px solid #86efac',